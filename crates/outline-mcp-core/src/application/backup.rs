@@ -0,0 +1,164 @@
+//! 無損失バックアップ: 保存形式そのままの `TemplateBook` を、形式名とスキーマ版で包んだ JSON。
+//!
+//! `checklist` の JSON 形式と違い、Book ID・ノード ID・作成日時・完了状態・依存関係など
+//! `JsonBookRepository` が書くものをすべて運び、読み戻すと元と同じ Book になる。
+
+use serde::{Deserialize, Serialize};
+
+use crate::domain::model::book::TemplateBook;
+
+/// `format` field that marks a file as a backup.
+pub const BACKUP_FORMAT: &str = "outline-mcp-backup";
+
+/// Backup schema version written by this build; newer backups are refused.
+pub const BACKUP_SCHEMA_VERSION: u32 = 1;
+
+/// バックアップ読み込みの失敗。
+#[derive(Debug, thiserror::Error)]
+pub enum BackupError {
+    /// The JSON is not a backup (no `"format": "outline-mcp-backup"`).
+    #[error("not an outline-mcp backup (expected \"format\": \"{BACKUP_FORMAT}\")")]
+    NotABackup,
+    /// Written by a newer build with a schema this one does not know.
+    #[error("backup schema_version {found} is newer than this build supports ({supported}); upgrade outline-mcp to restore it")]
+    TooNew {
+        /// Version in the file.
+        found: u32,
+        /// [`BACKUP_SCHEMA_VERSION`].
+        supported: u32,
+    },
+    /// The JSON could not be parsed as a backup of this schema.
+    #[error("invalid backup: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+/// バックアップファイルの中身。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BookBackup {
+    /// Always [`BACKUP_FORMAT`].
+    pub format: String,
+    /// [`BACKUP_SCHEMA_VERSION`] at the time of writing.
+    pub schema_version: u32,
+    /// The book exactly as it is persisted.
+    pub book: TemplateBook,
+}
+
+/// 読み込み前の判定用。`format` と `schema_version` だけを見る。
+#[derive(Deserialize)]
+struct BackupHeader {
+    format: Option<String>,
+    schema_version: Option<u32>,
+}
+
+/// `book` をバックアップ JSON にする。
+pub fn render_backup(book: &TemplateBook) -> Result<String, serde_json::Error> {
+    serde_json::to_string_pretty(&BookBackup {
+        format: BACKUP_FORMAT.to_string(),
+        schema_version: BACKUP_SCHEMA_VERSION,
+        book: book.clone(),
+    })
+}
+
+/// `content` がバックアップ（`"format": "outline-mcp-backup"` を持つ JSON オブジェクト）なら `true`。
+pub fn is_backup(content: &str) -> bool {
+    serde_json::from_str::<BackupHeader>(content)
+        .is_ok_and(|h| h.format.as_deref() == Some(BACKUP_FORMAT))
+}
+
+/// バックアップ JSON から Book を取り出す。スキーマ版が新しすぎるものは中身を読む前に拒否する。
+pub fn parse_backup(content: &str) -> Result<TemplateBook, BackupError> {
+    let header: BackupHeader = serde_json::from_str(content)?;
+    if header.format.as_deref() != Some(BACKUP_FORMAT) {
+        return Err(BackupError::NotABackup);
+    }
+    match header.schema_version {
+        Some(found) if found > BACKUP_SCHEMA_VERSION => Err(BackupError::TooNew {
+            found,
+            supported: BACKUP_SCHEMA_VERSION,
+        }),
+        _ => Ok(serde_json::from_str::<BookBackup>(content)?.book),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::domain::model::book::{AddNodeRequest, Position, UpdateNodeRequest};
+    use crate::domain::model::node::NodeType;
+
+    fn sample() -> TemplateBook {
+        let mut book = TemplateBook::new("Runbook", 4);
+        let ops = book
+            .add_node(AddNodeRequest {
+                parent: None,
+                title: "Ops".into(),
+                node_type: NodeType::Section,
+                body: Some("Runs weekly".into()),
+                placeholder: None,
+                position: Position::Last,
+                properties: HashMap::from([("inject".into(), "true".into())]),
+            })
+            .unwrap();
+        let restart = book
+            .add_node(AddNodeRequest {
+                parent: Some(ops),
+                title: "Restart".into(),
+                node_type: NodeType::Content,
+                body: None,
+                placeholder: Some("host".into()),
+                position: Position::Last,
+                properties: HashMap::new(),
+            })
+            .unwrap();
+        book.update_node(
+            restart,
+            UpdateNodeRequest {
+                notes: Some(Some("private".into())),
+                estimate_minutes: Some(Some(15)),
+                owner: Some(Some("ops-team".into())),
+                done: Some(true),
+                protected: Some(true),
+                command: Some(Some("systemctl restart app".into())),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        book.add_link(restart, Some("Dashboard".into()), "https://example.com/d")
+            .unwrap();
+        book.set_tags(vec!["ops".into()]);
+        book
+    }
+
+    #[test]
+    fn round_trip_restores_the_persisted_book_exactly() {
+        let book = sample();
+        let json = render_backup(&book).unwrap();
+        assert!(is_backup(&json));
+
+        let restored = parse_backup(&json).unwrap();
+        assert_eq!(restored.id(), book.id());
+        assert_eq!(
+            serde_json::to_value(&restored).unwrap(),
+            serde_json::to_value(&book).unwrap()
+        );
+    }
+
+    #[test]
+    fn rejects_newer_schemas_and_other_json() {
+        let mut value: serde_json::Value =
+            serde_json::from_str(&render_backup(&sample()).unwrap()).unwrap();
+        value["schema_version"] = (BACKUP_SCHEMA_VERSION + 1).into();
+        let err = parse_backup(&value.to_string()).unwrap_err();
+        assert!(matches!(
+            err,
+            BackupError::TooNew { found, supported } if found == BACKUP_SCHEMA_VERSION + 1 && supported == BACKUP_SCHEMA_VERSION
+        ));
+
+        let export = r#"{"title": "Runbook", "max_depth": 4, "nodes": []}"#;
+        assert!(!is_backup(export));
+        assert!(matches!(parse_backup(export), Err(BackupError::NotABackup)));
+        assert!(!is_backup("[1, 2]"));
+    }
+}
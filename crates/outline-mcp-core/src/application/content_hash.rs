@@ -0,0 +1,113 @@
+//! JSON エクスポートに載せる、ノード内容の変更検出用ハッシュ。
+//!
+//! どちらも SHA-256 の小文字16進（64文字）で、入力のバイト列だけから決まるので
+//! プラットフォームや実行ごとに変わらない。
+//!
+//! - `content_hash`: タグ `"outline-mcp/content/v1\0"` に続けて、`node_type`・
+//!   `title`・`body`・`placeholder` の順に各項目を符号化したもの。値がある項目は
+//!   `0x01` + UTF-8 バイト長（u64 ビッグエンディアン）+ UTF-8 バイト列、ない項目は
+//!   `0x00` の1バイト。`None` と空文字列は区別される。ID・notes・owner・
+//!   properties などは含まない。
+//! - `children_hash`: タグ `"outline-mcp/children/v1\0"` に続けて、子ごとに順番に
+//!   その `content_hash`（16進64文字）と、子自身の `children_hash` があれば `0x01` +
+//!   その16進64文字、なければ `0x00`。子孫全体と並び順を覆うので、1回の比較で
+//!   部分木の下のどこかが変わったか分かる。
+
+use sha2::{Digest, Sha256};
+
+const CONTENT_TAG: &[u8] = b"outline-mcp/content/v1\0";
+const CHILDREN_TAG: &[u8] = b"outline-mcp/children/v1\0";
+
+/// ノード1件の内容ハッシュ（`node_type` は `"section"` / `"content"`）。
+pub fn content_hash(
+    node_type: &str,
+    title: &str,
+    body: Option<&str>,
+    placeholder: Option<&str>,
+) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(CONTENT_TAG);
+    for field in [Some(node_type), Some(title), body, placeholder] {
+        match field {
+            Some(value) => {
+                hasher.update([1]);
+                hasher.update((value.len() as u64).to_be_bytes());
+                hasher.update(value.as_bytes());
+            }
+            None => hasher.update([0]),
+        }
+    }
+    hex(&hasher.finalize())
+}
+
+/// 子の `(content_hash, children_hash)` を順に並べた列から求める Section のハッシュ。
+pub fn children_hash<'a>(children: impl IntoIterator<Item = (&'a str, Option<&'a str>)>) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(CHILDREN_TAG);
+    for (content, grandchildren) in children {
+        hasher.update(content.as_bytes());
+        match grandchildren {
+            Some(hash) => {
+                hasher.update([1]);
+                hasher.update(hash.as_bytes());
+            }
+            None => hasher.update([0]),
+        }
+    }
+    hex(&hasher.finalize())
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 期待値は仕様どおりのバイト列を別実装（Python hashlib）で計算して固定したもの
+    #[test]
+    fn content_hash_is_pinned_for_fixed_inputs() {
+        assert_eq!(
+            content_hash("content", "Run tests", Some("cargo test"), None),
+            "ccae788d0e7b5af8d91cac88418c975ac5e443e69d8570998414ccf7c2fd3a1a"
+        );
+        assert_eq!(
+            content_hash("section", "Deploy", None, None),
+            "4d97af3aa2dd6c91336612c8a5e90983a8005f2198520b4a15d482fbc7151409"
+        );
+    }
+
+    #[test]
+    fn missing_and_empty_fields_hash_differently() {
+        let none = content_hash("content", "Step", None, None);
+        let empty = content_hash("content", "Step", Some(""), None);
+        let moved = content_hash("content", "Step", None, Some(""));
+        assert_ne!(none, empty);
+        assert_ne!(empty, moved);
+        // 項目の境界がずれても衝突しない
+        assert_ne!(
+            content_hash("content", "ab", Some("c"), None),
+            content_hash("content", "a", Some("bc"), None)
+        );
+    }
+
+    #[test]
+    fn children_hash_is_pinned_and_order_sensitive() {
+        let a = content_hash("content", "Run tests", Some("cargo test"), None);
+        let b = content_hash("section", "Deploy", None, None);
+        let nested = children_hash([(a.as_str(), None)]);
+        assert_eq!(
+            children_hash([]),
+            "b5c3ecd51ff02bc415b366d3a4481da06e90d20fc6020dd5461252c1d9a1e05f"
+        );
+        assert_eq!(
+            children_hash([(a.as_str(), None), (b.as_str(), Some(nested.as_str()))]),
+            "7133b6fed3a17f236eb0d674e4196c2dda33ee33ddca1f621f81773e8b26119a"
+        );
+        assert_ne!(
+            children_hash([(a.as_str(), None), (b.as_str(), None)]),
+            children_hash([(b.as_str(), None), (a.as_str(), None)])
+        );
+    }
+}
@@ -0,0 +1,309 @@
+use crate::domain::error::DomainError;
+use crate::domain::model::book::{
+    AddNodeRequest, Placement, Position, TemplateBook, UpdateNodeRequest,
+};
+use crate::domain::model::id::NodeId;
+
+use super::duplicates::normalize_title;
+
+/// 追加するノードが同じ親の下の既存ノードとタイトルで重なったときの扱い。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DedupeMode {
+    /// Always create (today's behavior).
+    #[default]
+    Off,
+    /// Reuse the matching sibling as is; nothing new is created for it.
+    Skip,
+    /// Reuse the matching sibling, overwriting its body / placeholder with
+    /// the incoming values (fields the incoming node leaves empty are kept).
+    Update,
+}
+
+/// 取り込む兄弟1件ごとの判断（[`plan_siblings`] の結果）。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DedupeDecision {
+    /// No matching sibling: create the node.
+    Create,
+    /// Matches this existing sibling; keep its fields (`Skip`).
+    Skip(NodeId),
+    /// Matches this existing sibling; overwrite its body / placeholder (`Update`).
+    Update(NodeId),
+    /// Repeats the incoming sibling at this earlier index of the same batch.
+    /// It is not created twice; its children go under the earlier one.
+    Repeat(usize),
+}
+
+/// 重複判定の集計。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DedupeCounts {
+    /// Nodes created.
+    pub created: usize,
+    /// Incoming nodes that matched an existing (or earlier incoming) sibling
+    /// and were not created.
+    pub skipped: usize,
+    /// Existing siblings overwritten from a matching incoming node.
+    pub updated: usize,
+}
+
+/// 同じ親の下に入る `incoming` タイトルを、既存の子 `existing`（ID とタイトル）と
+/// 突き合わせる。比較は [`normalize_title`]（大文字小文字・空白の違いを無視）で行い、
+/// 正規化して空になるタイトルは何にも一致しない。既存の子に同じタイトルが複数あれば
+/// 最初のものに一致する。`DedupeMode::Off` ではすべて `Create`。
+pub fn plan_siblings(
+    existing: &[(NodeId, &str)],
+    incoming: &[&str],
+    mode: DedupeMode,
+) -> Vec<DedupeDecision> {
+    let mut seen: Vec<String> = Vec::with_capacity(incoming.len());
+    incoming
+        .iter()
+        .map(|title| {
+            let key = normalize_title(title);
+            seen.push(key.clone());
+            if mode == DedupeMode::Off || key.is_empty() {
+                return DedupeDecision::Create;
+            }
+            if let Some((id, _)) = existing.iter().find(|(_, t)| normalize_title(t) == key) {
+                return match mode {
+                    DedupeMode::Update => DedupeDecision::Update(*id),
+                    _ => DedupeDecision::Skip(*id),
+                };
+            }
+            match seen[..seen.len() - 1].iter().position(|k| *k == key) {
+                Some(earlier) => DedupeDecision::Repeat(earlier),
+                None => DedupeDecision::Create,
+            }
+        })
+        .collect()
+}
+
+/// `parent` の子（`None` ならトップレベル）の ID とタイトル。
+pub fn sibling_titles(book: &TemplateBook, parent: Option<NodeId>) -> Vec<(NodeId, &str)> {
+    let ids = match parent {
+        Some(p) => book.get_node(p).map(|n| n.children()).unwrap_or(&[]),
+        None => book.root_nodes(),
+    };
+    ids.iter()
+        .filter_map(|id| book.get_node(*id).map(|n| (*id, n.title())))
+        .collect()
+}
+
+/// 既存ノードの本文・placeholder を取り込む側の値で上書きする（`None` の項目は残す）。
+pub fn overwrite_fields(
+    book: &mut TemplateBook,
+    id: NodeId,
+    body: Option<String>,
+    placeholder: Option<String>,
+) -> Result<(), DomainError> {
+    book.update_node(
+        id,
+        UpdateNodeRequest {
+            body: body.map(Some),
+            placeholder: placeholder.map(Some),
+            ..Default::default()
+        },
+    )
+}
+
+/// [`add_deduped`] の結果。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DedupedAdd {
+    /// The created node, or the existing sibling it matched.
+    pub node_id: NodeId,
+    /// Where the node was inserted; `None` when it matched an existing sibling.
+    pub placed: Option<Placement>,
+    /// Children created, in order.
+    pub created_children: Vec<NodeId>,
+    /// Existing nodes overwritten (`DedupeMode::Update`), in order.
+    pub updated: Vec<NodeId>,
+    /// What happened to the node and its children.
+    pub counts: DedupeCounts,
+}
+
+/// `req` と子 `children`（1階層）を `mode` に従って追加する。
+///
+/// `req` が既存の兄弟に一致したら新規作成せずそのノードを使い、子はその既存の子と
+/// 突き合わせる。一致しなければ `req` を作成し、子は同じバッチ内の重複だけを除いて
+/// まとめて追加する（深さ・上限の検証は [`TemplateBook::add_node_with_children`] と同じ）。
+pub fn add_deduped(
+    book: &mut TemplateBook,
+    req: AddNodeRequest,
+    children: Vec<AddNodeRequest>,
+    mode: DedupeMode,
+) -> Result<DedupedAdd, DomainError> {
+    let mut counts = DedupeCounts::default();
+    let mut updated = Vec::new();
+    let decision = plan_siblings(&sibling_titles(book, req.parent), &[&req.title], mode)[0];
+    let (node_id, placed) = match decision {
+        DedupeDecision::Skip(id) => {
+            counts.skipped += 1;
+            (id, None)
+        }
+        DedupeDecision::Update(id) => {
+            overwrite_fields(book, id, req.body, req.placeholder)?;
+            counts.updated += 1;
+            updated.push(id);
+            (id, None)
+        }
+        DedupeDecision::Create | DedupeDecision::Repeat(_) => {
+            let titles: Vec<&str> = children.iter().map(|c| c.title.as_str()).collect();
+            let plan = plan_siblings(&[], &titles, mode);
+            let kept: Vec<AddNodeRequest> = children
+                .into_iter()
+                .zip(&plan)
+                .filter(|(_, d)| **d == DedupeDecision::Create)
+                .map(|(c, _)| c)
+                .collect();
+            counts.skipped += plan.len() - kept.len();
+            let (placed, created_children) = if kept.is_empty() {
+                (book.add_node_placed(req)?, Vec::new())
+            } else {
+                book.add_node_with_children(req, kept)?
+            };
+            counts.created += 1 + created_children.len();
+            return Ok(DedupedAdd {
+                node_id: placed.node_id,
+                placed: Some(placed),
+                created_children,
+                updated,
+                counts,
+            });
+        }
+    };
+
+    // 既存ノードに一致した: 子をその既存の子と突き合わせる
+    let titles: Vec<&str> = children.iter().map(|c| c.title.as_str()).collect();
+    let plan = plan_siblings(&sibling_titles(book, Some(node_id)), &titles, mode);
+    let mut created_children = Vec::new();
+    for (child, decision) in children.into_iter().zip(plan) {
+        match decision {
+            DedupeDecision::Create => {
+                created_children.push(book.add_node(AddNodeRequest {
+                    parent: Some(node_id),
+                    position: Position::Last,
+                    ..child
+                })?);
+                counts.created += 1;
+            }
+            DedupeDecision::Update(id) => {
+                overwrite_fields(book, id, child.body, child.placeholder)?;
+                counts.updated += 1;
+                updated.push(id);
+            }
+            DedupeDecision::Skip(_) | DedupeDecision::Repeat(_) => counts.skipped += 1,
+        }
+    }
+    Ok(DedupedAdd {
+        node_id,
+        placed,
+        created_children,
+        updated,
+        counts,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::domain::model::node::NodeType;
+
+    fn req(parent: Option<NodeId>, title: &str) -> AddNodeRequest {
+        AddNodeRequest {
+            parent,
+            title: title.into(),
+            node_type: NodeType::Content,
+            body: Some(format!("{title} body")),
+            placeholder: None,
+            position: Position::Last,
+            properties: HashMap::new(),
+        }
+    }
+
+    #[test]
+    fn plan_matches_case_and_whitespace_insensitively() {
+        let a = NodeId::new();
+        let b = NodeId::new();
+        let existing = [(a, "Run  Tests"), (b, "deploy")];
+        let incoming = ["run tests", " DEPLOY ", "Deploy to prod", "", "  "];
+        assert_eq!(
+            plan_siblings(&existing, &incoming, DedupeMode::Skip),
+            vec![
+                DedupeDecision::Skip(a),
+                DedupeDecision::Skip(b),
+                DedupeDecision::Create,
+                DedupeDecision::Create,
+                DedupeDecision::Create,
+            ]
+        );
+        assert_eq!(
+            plan_siblings(&existing, &incoming[..2], DedupeMode::Update),
+            vec![DedupeDecision::Update(a), DedupeDecision::Update(b)]
+        );
+        assert!(plan_siblings(&existing, &incoming, DedupeMode::Off)
+            .iter()
+            .all(|d| *d == DedupeDecision::Create));
+    }
+
+    #[test]
+    fn plan_collapses_repeats_within_the_batch_and_prefers_first_existing() {
+        let first = NodeId::new();
+        let second = NodeId::new();
+        let existing = [(first, "Review"), (second, "REVIEW")];
+        let incoming = ["Lint", "review", "lint ", "Build", "LINT"];
+        assert_eq!(
+            plan_siblings(&existing, &incoming, DedupeMode::Skip),
+            vec![
+                DedupeDecision::Create,
+                DedupeDecision::Skip(first),
+                DedupeDecision::Repeat(0),
+                DedupeDecision::Create,
+                DedupeDecision::Repeat(0),
+            ]
+        );
+    }
+
+    #[test]
+    fn add_deduped_merges_children_into_matching_sibling() {
+        let mut book = TemplateBook::new("Runbook", 4);
+        let deploy = book
+            .add_node(AddNodeRequest {
+                node_type: NodeType::Section,
+                ..req(None, "Deploy")
+            })
+            .unwrap();
+        let build = book.add_node(req(Some(deploy), "Build")).unwrap();
+
+        let children = vec![
+            req(None, "build"),
+            req(None, "Smoke test"),
+            req(None, "smoke  test"),
+        ];
+        let added = add_deduped(
+            &mut book,
+            AddNodeRequest {
+                body: None,
+                ..req(None, " deploy")
+            },
+            children,
+            DedupeMode::Update,
+        )
+        .unwrap();
+
+        assert_eq!(added.node_id, deploy);
+        assert_eq!(added.placed, None);
+        assert_eq!(
+            added.counts,
+            DedupeCounts {
+                created: 1,
+                skipped: 1,
+                updated: 2,
+            }
+        );
+        assert_eq!(book.root_nodes(), [deploy]);
+        assert_eq!(book.get_node(deploy).unwrap().body(), Some("Deploy body"));
+        assert_eq!(book.get_node(build).unwrap().body(), Some("build body"));
+        assert_eq!(book.get_node(deploy).unwrap().children().len(), 2);
+    }
+}
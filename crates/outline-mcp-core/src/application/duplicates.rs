@@ -0,0 +1,192 @@
+use std::collections::HashMap;
+
+use crate::domain::model::book::TemplateBook;
+use crate::domain::model::id::NodeId;
+use crate::domain::model::node::NodeType;
+
+/// 重複とみなされた Content ノードのグループ。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DuplicateGroup {
+    /// グループを代表する正規化タイトル（最初に出現したメンバーのもの）。
+    pub key: String,
+    /// メンバーのノードID（DFS順、2件以上）。
+    pub members: Vec<NodeId>,
+}
+
+/// タイトルを比較用に正規化する（小文字化 + 連続空白の圧縮 + 前後空白除去）。
+pub fn normalize_title(title: &str) -> String {
+    title
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+/// Content ノードを正規化タイトルでグループ化し、2件以上のグループを返す。
+///
+/// `max_distance > 0` のとき、正規化タイトル同士の編集距離が
+/// `max_distance` 以下のものも同じグループにまとめる（近似重複）。
+/// グループは最初のメンバーのDFS順に並ぶ。
+pub fn find_duplicates(book: &TemplateBook, max_distance: usize) -> Vec<DuplicateGroup> {
+    // 正規化タイトルごとにメンバーを集める（出現順を保持）
+    let mut keys: Vec<String> = Vec::new();
+    let mut by_key: HashMap<String, Vec<NodeId>> = HashMap::new();
+    for node in book.all_nodes_dfs() {
+        if node.node_type() != &NodeType::Content {
+            continue;
+        }
+        let key = normalize_title(node.title());
+        if key.is_empty() {
+            continue;
+        }
+        by_key
+            .entry(key.clone())
+            .or_insert_with(|| {
+                keys.push(key);
+                Vec::new()
+            })
+            .push(node.id());
+    }
+
+    // 近似重複: 編集距離でキー同士を union-find で束ねる
+    let mut parent: Vec<usize> = (0..keys.len()).collect();
+    if max_distance > 0 {
+        for i in 0..keys.len() {
+            for j in (i + 1)..keys.len() {
+                if edit_distance(&keys[i], &keys[j]) <= max_distance {
+                    let (ri, rj) = (find_root(&mut parent, i), find_root(&mut parent, j));
+                    if ri != rj {
+                        parent[rj.max(ri)] = ri.min(rj);
+                    }
+                }
+            }
+        }
+    }
+
+    let mut clusters: Vec<(usize, Vec<NodeId>)> = Vec::new();
+    let mut cluster_of_root: HashMap<usize, usize> = HashMap::new();
+    for (i, key) in keys.iter().enumerate() {
+        let root = find_root(&mut parent, i);
+        let idx = *cluster_of_root.entry(root).or_insert_with(|| {
+            clusters.push((root, Vec::new()));
+            clusters.len() - 1
+        });
+        clusters[idx].1.extend(by_key[key].iter().copied());
+    }
+
+    let order: HashMap<NodeId, usize> = book
+        .all_nodes_dfs()
+        .iter()
+        .enumerate()
+        .map(|(i, n)| (n.id(), i))
+        .collect();
+
+    let mut groups: Vec<DuplicateGroup> = clusters
+        .into_iter()
+        .filter(|(_, members)| members.len() > 1)
+        .map(|(root, mut members)| {
+            members.sort_by_key(|id| order[id]);
+            DuplicateGroup {
+                key: keys[root].clone(),
+                members,
+            }
+        })
+        .collect();
+    groups.sort_by_key(|g| order[&g.members[0]]);
+    groups
+}
+
+fn find_root(parent: &mut [usize], i: usize) -> usize {
+    let mut r = i;
+    while parent[r] != r {
+        r = parent[r];
+    }
+    parent[i] = r;
+    r
+}
+
+/// 文字単位の Levenshtein 距離。
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    let mut curr = vec![0; b.len() + 1];
+    for (i, ca) in a.iter().enumerate() {
+        curr[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let cost = usize::from(ca != cb);
+            curr[j + 1] = (prev[j] + cost).min(prev[j + 1] + 1).min(curr[j] + 1);
+        }
+        std::mem::swap(&mut prev, &mut curr);
+    }
+    prev[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::model::book::{AddNodeRequest, Position};
+
+    fn add(book: &mut TemplateBook, parent: Option<NodeId>, title: &str, t: NodeType) -> NodeId {
+        book.add_node(AddNodeRequest {
+            parent,
+            title: title.into(),
+            node_type: t,
+            body: None,
+            placeholder: None,
+            position: Position::Last,
+            properties: HashMap::new(),
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn normalize_collapses_case_and_whitespace() {
+        assert_eq!(normalize_title("  Run   the  Tests "), "run the tests");
+    }
+
+    #[test]
+    fn edit_distance_basics() {
+        assert_eq!(edit_distance("", "abc"), 3);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("same", "same"), 0);
+    }
+
+    #[test]
+    fn exact_duplicates_are_grouped_across_sections() {
+        let mut book = TemplateBook::new("Dup", 4);
+        let a = add(&mut book, None, "Build", NodeType::Section);
+        let b = add(&mut book, None, "Release", NodeType::Section);
+        let x = add(&mut book, Some(a), "Run tests", NodeType::Content);
+        add(&mut book, Some(a), "Lint", NodeType::Content);
+        let y = add(&mut book, Some(b), "run  TESTS", NodeType::Content);
+
+        let groups = find_duplicates(&book, 0);
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].key, "run tests");
+        assert_eq!(groups[0].members, vec![x, y]);
+    }
+
+    #[test]
+    fn near_duplicates_only_with_similarity() {
+        let mut book = TemplateBook::new("Dup", 4);
+        let x = add(&mut book, None, "Run unit tests", NodeType::Content);
+        let y = add(&mut book, None, "Run unit test", NodeType::Content);
+        add(&mut book, None, "Deploy", NodeType::Content);
+
+        assert!(find_duplicates(&book, 0).is_empty());
+        let groups = find_duplicates(&book, 2);
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].members, vec![x, y]);
+    }
+
+    #[test]
+    fn sections_are_ignored_and_empty_book_has_no_groups() {
+        let mut book = TemplateBook::new("Dup", 4);
+        assert!(find_duplicates(&book, 2).is_empty());
+
+        add(&mut book, None, "Setup", NodeType::Section);
+        add(&mut book, None, "Setup", NodeType::Section);
+        assert!(find_duplicates(&book, 0).is_empty());
+    }
+}
@@ -1,12 +1,22 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 use serde::{Deserialize, Serialize};
 
-use crate::domain::model::book::{AddNodeRequest, TemplateBook};
+use crate::domain::model::book::{AddNodeRequest, Position, TemplateBook, UpdateNodeRequest};
 use crate::domain::model::id::NodeId;
 use crate::domain::model::node::{NodeType, TemplateNode};
+use crate::domain::model::timestamp::Timestamp;
 
+use crate::domain::error::DomainError;
+
+use super::content_hash as hash;
+use super::dedupe::{
+    overwrite_fields, plan_siblings, sibling_titles, DedupeCounts, DedupeDecision, DedupeMode,
+};
 use super::error::AppError;
+use super::lint::needs_detail;
+use super::markdown_import::{needs_body_marker, BODY_LINE_MARKER, NEEDS_DETAIL_TAG};
+use super::wrap::wrap_markdown;
 
 /// Eject出力フォーマット
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -15,6 +25,19 @@ pub enum EjectFormat {
     Markdown,
     /// Tree-structured JSON (see `EjectTree`).
     Json,
+    /// Self-contained HTML fill-in form (checkboxes and text fields).
+    Html,
+}
+
+/// Markdown 出力での Content ノードの描き方
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MarkdownStyle {
+    /// Task list: each content node is a `- [ ]` checkbox item.
+    #[default]
+    Checkbox,
+    /// Data-capture form: each content node is a bold label followed by a
+    /// `placeholder: _______` fill line, without checkboxes.
+    Form,
 }
 
 /// Eject設定
@@ -29,8 +52,83 @@ pub struct EjectConfig {
     pub format: EjectFormat,
     /// 部分木のルート（Noneなら全体）
     pub subtree_root: Option<NodeId>,
+    /// 指定時はこの時刻以降に作成されたノード（とその祖先）のみ出力する
+    pub created_after: Option<Timestamp>,
+    /// Markdown 出力にノードの notes を含めるか（JSON は常に含む）
+    pub include_notes: bool,
+    /// Markdown 出力の各項目に見積もり（`(~30m)`）を付けるか
+    pub include_estimates: bool,
+    /// 指定時はこの担当者のノード（とその祖先）のみ出力する
+    pub owner: Option<String>,
+    /// 部分木出力時に祖先のタイトルを文脈として含めるか
+    /// （Markdown は `Context:` 行、JSON は `ancestors`）
+    pub include_ancestors: bool,
+    /// Markdown 出力の Content ノードの描き方（JSON では無視）
+    pub style: MarkdownStyle,
+    /// 指定時は Markdown 出力の本文の行をこの幅で折り返す（タイトル・見出しは折り返さない）
+    pub wrap_width: Option<usize>,
+    /// JSON の部分木出力に `source_slug` として記録する Book の slug
+    pub source_slug: Option<String>,
+    /// Markdown のチェックボックス項目に付ける状態語（JSON / HTML では無視）
+    pub labels: CheckboxLabels,
+    /// Book 全体の Markdown 出力でトップレベルのノードの間に `---` を入れるか
+    pub section_separators: bool,
+    /// Markdown のチェックボックス項目のうち、placeholder だけで本文のないものに
+    /// `⚠ needs detail` を付けるか（[`needs_detail`]）
+    pub mark_incomplete: bool,
+    /// Markdown の本文のうち構造の構文と紛れる行に [`BODY_LINE_MARKER`] を付けるか
+    /// （付けると Markdown の読み込みでその行が本文に戻る）
+    pub mark_body_lines: bool,
+    /// 出力の末尾に元の Book（タイトル・ID・ノード数・生成時刻）を記したフッターを付けるか。
+    /// Markdown / HTML はコメント、JSON はツリーの外の `_comment` キー（[`EjectService::footer`]）
+    pub footer: bool,
+}
+
+/// チェックボックス項目の先頭に付ける状態語（`- [ ] TODO Deploy`）。
+/// 既定ではどちらも付けず `[ ]` / `[x]` だけにする。
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CheckboxLabels {
+    /// Word after an open checkbox (e.g. `TODO`, `未着手`).
+    pub todo_word: Option<String>,
+    /// Word after a checked-off checkbox (e.g. `DONE`, `完了`).
+    pub done_word: Option<String>,
+}
+
+/// Markdown 描画オプション
+#[derive(Debug, Clone, Default)]
+pub struct MarkdownOptions {
+    /// Whether to include placeholder hints as fill-in fields.
+    pub include_placeholders: bool,
+    /// Whether to render each node's private notes as an indented italic block.
+    pub include_notes: bool,
+    /// Whether to append each item's effort estimate (e.g. `(~30m)`).
+    pub include_estimates: bool,
+    /// Whether a subtree export starts with a `Context: A → B → C` breadcrumb.
+    pub include_ancestors: bool,
+    /// How content nodes are drawn (checkbox items or form fields).
+    pub style: MarkdownStyle,
+    /// Soft-wrap body lines at this many characters (see [`wrap_markdown`]).
+    pub wrap_width: Option<usize>,
+    /// Status words placed after checkbox markers.
+    pub labels: CheckboxLabels,
+    /// Push every heading this many levels down (book title `#` → `##` for 1),
+    /// for embedding the book in a larger document. Levels stop at `######`.
+    pub heading_offset: usize,
+    /// Put a `---` rule between consecutive top-level nodes of a full-book render.
+    pub section_separators: bool,
+    /// Append [`NEEDS_DETAIL_MARKER`] (followed by the invisible [`NEEDS_DETAIL_TAG`]
+    /// so import can tell it apart from title text) to checkbox items that have a
+    /// placeholder but no body (see [`needs_detail`]).
+    pub mark_incomplete: bool,
+    /// End body lines that look like checklist syntax (list items, quotes,
+    /// `_notes_`) with [`BODY_LINE_MARKER`] so Markdown import keeps them in
+    /// the body instead of reading them as nodes.
+    pub mark_body_lines: bool,
 }
 
+/// `mark_incomplete` で本文のない記入欄つき項目に付ける印。
+pub const NEEDS_DETAIL_MARKER: &str = "⚠ needs detail";
+
 /// JSON Eject用のツリー構造DTO
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EjectTreeNode {
@@ -46,12 +144,47 @@ pub struct EjectTreeNode {
     /// Optional placeholder hint.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub placeholder: Option<String>,
+    /// Optional private notes (absent in older exports).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notes: Option<String>,
+    /// Optional public heads-up note (absent in older exports).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+    /// Optional runnable shell command (absent in older exports).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub command: Option<String>,
+    /// External links as `[label, url]` pairs (absent in older exports).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub links: Vec<(String, String)>,
+    /// Optional effort estimate in minutes (absent in older exports).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub estimate_minutes: Option<u32>,
+    /// Optional owner / assignee (absent in older exports).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub owner: Option<String>,
+    /// `false` for a section whose children are unordered (absent otherwise and in older exports).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ordered: Option<bool>,
+    /// Optional manual sort key among siblings (absent in older exports).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub order: Option<f64>,
+    /// Optional Markdown heading level override (absent in older exports).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub heading_level: Option<usize>,
     /// Child nodes, in order.
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub children: Vec<EjectTreeNode>,
     /// Key-value properties.
     #[serde(default, skip_serializing_if = "HashMap::is_empty")]
     pub properties: HashMap<String, String>,
+    /// Hash of `node_type` / `title` / `body` / `placeholder` (see
+    /// [`content_hash`](super::content_hash)). Computed on export, ignored on import.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_hash: Option<String>,
+    /// Sections only: hash over every descendant's content and order. Computed
+    /// on export, ignored on import.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub children_hash: Option<String>,
 }
 
 /// JSON Eject 用のツリー全体 DTO (書籍全体 or 部分木)。
@@ -63,6 +196,237 @@ pub struct EjectTree {
     pub max_depth: u8,
     /// Root-level nodes of the exported tree.
     pub nodes: Vec<EjectTreeNode>,
+    /// Book-level default placeholder hint (absent in older exports).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_placeholder: Option<String>,
+    /// Titles of the subtree root's ancestors, root first (only set when
+    /// exported with `include_ancestors`; absent in older exports).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub ancestors: Vec<String>,
+    /// Id of the book a subtree export came from (subtree exports only).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub book_id: Option<String>,
+    /// Shelf slug of that book, when the exporter knew it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_slug: Option<String>,
+    /// UUID of the exported subtree root (subtree exports only).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub subtree_uuid: Option<String>,
+    /// Hierarchical ID of the subtree root at export time (e.g. `2-1`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub subtree_path: Option<String>,
+}
+
+impl EjectTree {
+    /// JSON 文字列から読み込む。`strict` なら未知のキー（`"titel"` などの打ち間違い）を
+    /// エラーにする。既定の寛容モードは新しい版の出力との前方互換のため未知キーを無視する。
+    pub fn from_json(json: &str, strict: bool) -> Result<Self, serde_json::Error> {
+        if strict {
+            serde_json::from_str::<StrictEjectTree>(json).map(Into::into)
+        } else {
+            serde_json::from_str(json)
+        }
+    }
+
+    /// 部分木の出力を元の場所へ戻せるなら、その部分木のルート。`book_id` が
+    /// `book` と一致し、`subtree_uuid` のノードがまだ `book` にあるときだけ返す。
+    pub fn in_place_target(&self, book: &TemplateBook) -> Option<NodeId> {
+        let book_id = book.id().to_string();
+        if self.book_id.as_deref() != Some(book_id.as_str()) {
+            return None;
+        }
+        let uuid = self.subtree_uuid.as_deref()?;
+        book.all_nodes_dfs()
+            .into_iter()
+            .map(|n| n.id())
+            .find(|id| id.to_string() == uuid)
+    }
+
+    /// Find a top-level node by 1-based position (`"2"`) or by title
+    /// (case-insensitive exact match, first wins).
+    pub fn find_top_level(&self, selector: &str) -> Option<&EjectTreeNode> {
+        let selector = selector.trim();
+        if let Ok(index) = selector.parse::<usize>() {
+            return index.checked_sub(1).and_then(|i| self.nodes.get(i));
+        }
+        self.nodes
+            .iter()
+            .find(|n| n.title.eq_ignore_ascii_case(selector))
+    }
+}
+
+/// 取り込むツリーが取り込み先の max_depth を超えるときの扱い。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DepthStrategy {
+    /// Refuse the import, naming the deepest offending path; nothing is changed.
+    #[default]
+    Error,
+    /// Fold nodes beyond the limit into their deepest allowed ancestor's
+    /// body as bullet lines (their notes and properties are dropped).
+    Flatten,
+    /// Raise the destination's max_depth to fit, up to
+    /// [`TemplateBook::MAX_DEPTH_LIMIT`].
+    Raise,
+}
+
+/// マージ取り込みで、取り込むノードの `id` が Book の既存ノードと同じだったときの扱い。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum IdConflict {
+    /// Ignore incoming IDs; every node is created with a fresh ID (today's behavior).
+    #[default]
+    Off,
+    /// Refuse the import, listing every colliding ID; nothing is changed.
+    Error,
+    /// Keep the existing node as is and merge the incoming children under it.
+    Skip,
+    /// Overwrite the existing node's fields with the incoming ones (it stays
+    /// where it is) and merge the incoming children under it.
+    Replace,
+}
+
+/// 深さ調整の結果（[`EjectService::import_tree_fitted`] など）。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DepthFit {
+    /// max_depth of the destination after the import.
+    pub max_depth: u8,
+    /// Nodes folded into a parent body (`Flatten` only).
+    pub folded: usize,
+}
+
+/// フッター付きの JSON 出力。`_comment` はツリーの後ろに置く。
+#[derive(Serialize)]
+struct FootedEjectTree<'a> {
+    #[serde(flatten)]
+    tree: &'a EjectTree,
+    #[serde(rename = "_comment")]
+    comment: String,
+}
+
+/// `EjectTree` の strict 読み込み用ミラー（未知フィールドを拒否）。
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct StrictEjectTree {
+    title: String,
+    max_depth: u8,
+    nodes: Vec<StrictEjectTreeNode>,
+    #[serde(default)]
+    default_placeholder: Option<String>,
+    #[serde(default)]
+    ancestors: Vec<String>,
+    #[serde(default)]
+    book_id: Option<String>,
+    #[serde(default)]
+    source_slug: Option<String>,
+    #[serde(default)]
+    subtree_uuid: Option<String>,
+    #[serde(default)]
+    subtree_path: Option<String>,
+    /// `footer` の `_comment`（読み込みでは使わない）。
+    #[serde(default)]
+    _comment: Option<String>,
+}
+
+/// `EjectTreeNode` の strict 読み込み用ミラー（未知フィールドを拒否）。
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct StrictEjectTreeNode {
+    id: String,
+    title: String,
+    node_type: String,
+    #[serde(default)]
+    body: Option<String>,
+    #[serde(default)]
+    placeholder: Option<String>,
+    #[serde(default)]
+    notes: Option<String>,
+    #[serde(default)]
+    note: Option<String>,
+    #[serde(default)]
+    command: Option<String>,
+    #[serde(default)]
+    links: Vec<(String, String)>,
+    #[serde(default)]
+    estimate_minutes: Option<u32>,
+    #[serde(default)]
+    owner: Option<String>,
+    #[serde(default)]
+    ordered: Option<bool>,
+    #[serde(default)]
+    order: Option<f64>,
+    #[serde(default)]
+    heading_level: Option<usize>,
+    #[serde(default)]
+    children: Vec<StrictEjectTreeNode>,
+    #[serde(default)]
+    properties: HashMap<String, String>,
+    #[serde(default)]
+    content_hash: Option<String>,
+    #[serde(default)]
+    children_hash: Option<String>,
+}
+
+// 構造体リテラルで全フィールドを列挙するので、DTO にフィールドが増えたらここで
+// コンパイルエラーになりミラーの更新漏れに気付ける。
+impl From<StrictEjectTree> for EjectTree {
+    fn from(tree: StrictEjectTree) -> Self {
+        Self {
+            title: tree.title,
+            max_depth: tree.max_depth,
+            nodes: tree.nodes.into_iter().map(Into::into).collect(),
+            default_placeholder: tree.default_placeholder,
+            ancestors: tree.ancestors,
+            book_id: tree.book_id,
+            source_slug: tree.source_slug,
+            subtree_uuid: tree.subtree_uuid,
+            subtree_path: tree.subtree_path,
+        }
+    }
+}
+
+impl From<StrictEjectTreeNode> for EjectTreeNode {
+    fn from(node: StrictEjectTreeNode) -> Self {
+        Self {
+            id: node.id,
+            title: node.title,
+            node_type: node.node_type,
+            body: node.body,
+            placeholder: node.placeholder,
+            notes: node.notes,
+            note: node.note,
+            command: node.command,
+            links: node.links,
+            estimate_minutes: node.estimate_minutes,
+            owner: node.owner,
+            ordered: node.ordered,
+            order: node.order,
+            heading_level: node.heading_level,
+            children: node.children.into_iter().map(Into::into).collect(),
+            properties: node.properties,
+            content_hash: node.content_hash,
+            children_hash: node.children_hash,
+        }
+    }
+}
+
+/// [`EjectService::import_subtree_fitted`] の結果。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SubtreeImport {
+    /// The imported subtree root (or the existing sibling it was merged into).
+    pub root: NodeId,
+    /// How the depth was fitted.
+    pub fit: DepthFit,
+    /// Nodes created / skipped / updated by sibling de-duplication.
+    pub dedupe: DedupeCounts,
+    /// Incoming nodes matched to an existing node by ID (`Skip` / `Replace`).
+    pub id_matches: usize,
+}
+
+/// [`EjectService::import_merged`] の突き合わせ規則。
+struct MergeRules {
+    dedupe: DedupeMode,
+    on_id_conflict: IdConflict,
+    /// 取り込むノードの ID 文字列 → 同じ ID の既存ノード（事前に求めておく）。
+    existing: HashMap<String, NodeId>,
 }
 
 /// Template Book → 作業用ファイルへの変換
@@ -74,26 +438,64 @@ impl EjectService {
         book: &TemplateBook,
         include_placeholders: bool,
         subtree_root: Option<NodeId>,
+    ) -> String {
+        let options = MarkdownOptions {
+            include_placeholders,
+            ..MarkdownOptions::default()
+        };
+        Self::render_markdown_with(book, &options, subtree_root)
+    }
+
+    /// `MarkdownOptions` を指定してBookの内容をMarkdown文字列に変換する。
+    pub fn render_markdown_with(
+        book: &TemplateBook,
+        options: &MarkdownOptions,
+        subtree_root: Option<NodeId>,
     ) -> String {
         let mut buf = String::new();
 
         match subtree_root {
             Some(root_id) => {
                 if let Some(node) = book.get_node(root_id) {
-                    buf.push_str(&format!("# {}\n\n", node.title()));
+                    if options.include_ancestors {
+                        let ancestors = Self::ancestor_titles(book, root_id);
+                        if !ancestors.is_empty() {
+                            buf.push_str(&format!(
+                                "Context: {} → {}\n\n",
+                                ancestors.join(" → "),
+                                node.title()
+                            ));
+                        }
+                    }
+                    buf.push_str(&format!(
+                        "{} {}\n\n",
+                        Self::hashes(1, options),
+                        node.title()
+                    ));
                     for &child_id in node.children() {
                         if let Some(child) = book.get_node(child_id) {
-                            Self::render_node(book, child, 0, include_placeholders, &mut buf);
+                            Self::render_node(book, child, 0, None, options, &mut buf);
                         }
                     }
                 }
             }
             None => {
-                buf.push_str(&format!("# {}\n\n", book.title()));
-                for &root_id in book.root_nodes() {
-                    if let Some(node) = book.get_node(root_id) {
-                        Self::render_node(book, node, 0, include_placeholders, &mut buf);
+                buf.push_str(&format!(
+                    "{} {}\n\n",
+                    Self::hashes(1, options),
+                    book.title()
+                ));
+                let roots = book.root_nodes().iter().filter_map(|&id| book.get_node(id));
+                for (i, node) in roots.enumerate() {
+                    // 最初のノードの前（Book タイトルの直後）には入れない
+                    if options.section_separators && i > 0 {
+                        // 直前の行が段落やリストの続きと読まれないよう空行を挟む
+                        if !buf.ends_with("\n\n") {
+                            buf.push('\n');
+                        }
+                        buf.push_str("---\n\n");
                     }
+                    Self::render_node(book, node, 0, None, options, &mut buf);
                 }
             }
         }
@@ -110,6 +512,37 @@ impl EjectService {
         serde_json::to_string_pretty(&tree).map_err(|e| AppError::Storage(Box::new(e)))
     }
 
+    /// 複数Bookを1つのMarkdown文書に結合する。各Bookは H1 見出しとその配下のツリーになり、
+    /// Book間は水平線で区切る。
+    pub fn render_combined_markdown(books: &[TemplateBook], include_placeholders: bool) -> String {
+        let options = MarkdownOptions {
+            include_placeholders,
+            ..MarkdownOptions::default()
+        };
+        Self::render_combined_markdown_with(books, &options)
+    }
+
+    /// `MarkdownOptions` を指定して [`Self::render_combined_markdown`] と同じ結合をする
+    /// （`heading_offset` で各 Book の見出しを下げれば、より大きな文書に埋め込める）。
+    pub fn render_combined_markdown_with(
+        books: &[TemplateBook],
+        options: &MarkdownOptions,
+    ) -> String {
+        books
+            .iter()
+            .map(|book| Self::render_markdown_with(book, options, None))
+            .map(|md| md.trim_end().to_string())
+            .collect::<Vec<_>>()
+            .join("\n\n---\n\n")
+            + "\n"
+    }
+
+    /// 複数Bookを `EjectTree` の配列としてJSON文字列に変換する。
+    pub fn render_combined_json(books: &[TemplateBook]) -> Result<String, AppError> {
+        let trees: Vec<EjectTree> = books.iter().map(|b| Self::build_tree(b, None)).collect();
+        serde_json::to_string_pretty(&trees).map_err(|e| AppError::Storage(Box::new(e)))
+    }
+
     /// ツリー構造DTOを構築する。
     pub fn build_tree(book: &TemplateBook, subtree_root: Option<NodeId>) -> EjectTree {
         let root_ids: Vec<NodeId> = match subtree_root {
@@ -133,16 +566,32 @@ impl EjectService {
             .filter_map(|id| Self::build_tree_node(book, *id))
             .collect();
 
+        // 部分木の出力には元の Book と位置を残し、後で同じ場所へ戻せるようにする
+        let subtree = subtree_root.filter(|id| book.get_node(*id).is_some());
         EjectTree {
             title,
             max_depth: book.max_depth(),
             nodes,
+            default_placeholder: book.default_placeholder().map(str::to_string),
+            ancestors: Vec::new(),
+            book_id: subtree.map(|_| book.id().to_string()),
+            source_slug: None,
+            subtree_uuid: subtree.map(|id| id.to_string()),
+            subtree_path: subtree.and_then(|id| book.outline_number(id)),
         }
     }
 
+    /// `id` の祖先タイトル（ルート側から）。
+    fn ancestor_titles(book: &TemplateBook, id: NodeId) -> Vec<String> {
+        book.ancestors(id)
+            .iter()
+            .map(|n| n.title().to_string())
+            .collect()
+    }
+
     fn build_tree_node(book: &TemplateBook, id: NodeId) -> Option<EjectTreeNode> {
         let node = book.get_node(id)?;
-        let children = node
+        let children: Vec<EjectTreeNode> = node
             .children()
             .iter()
             .filter_map(|cid| Self::build_tree_node(book, *cid))
@@ -152,6 +601,14 @@ impl EjectService {
             NodeType::Section => "section",
             NodeType::Content => "content",
         };
+        let children_hash = (*node.node_type() == NodeType::Section).then(|| {
+            hash::children_hash(children.iter().map(|c| {
+                (
+                    c.content_hash.as_deref().unwrap_or_default(),
+                    c.children_hash.as_deref(),
+                )
+            }))
+        });
 
         Some(EjectTreeNode {
             id: id.to_string(),
@@ -159,8 +616,24 @@ impl EjectService {
             node_type: node_type.to_string(),
             body: node.body().map(|s| s.to_string()),
             placeholder: node.placeholder().map(|s| s.to_string()),
+            notes: node.notes().map(|s| s.to_string()),
+            note: node.note().map(|s| s.to_string()),
+            command: node.command().map(|s| s.to_string()),
+            links: node.links().to_vec(),
+            estimate_minutes: node.estimate_minutes(),
+            owner: node.owner().map(|s| s.to_string()),
+            ordered: (!node.is_ordered()).then_some(false),
+            order: node.order(),
+            heading_level: node.heading_level(),
             children,
             properties: node.properties().clone(),
+            content_hash: Some(hash::content_hash(
+                node_type,
+                node.title(),
+                node.body(),
+                node.placeholder(),
+            )),
+            children_hash,
         })
     }
 
@@ -170,32 +643,387 @@ impl EjectService {
 
     /// Convert a JSON `EjectTree` back into a `TemplateBook`.
     pub fn import_tree(tree: &EjectTree) -> Result<TemplateBook, AppError> {
-        let mut book = TemplateBook::new(&tree.title, tree.max_depth);
+        let mut book = TemplateBook::try_new(&tree.title, tree.max_depth)?;
+        book.set_default_placeholder(tree.default_placeholder.clone());
         for node in &tree.nodes {
             Self::import_tree_node(&mut book, None, node, 0)?;
         }
         Ok(book)
     }
 
+    /// Import one exported node (with its descendants) into an existing book
+    /// under `parent` (`None` = top level), appended after existing siblings.
+    /// Nodes get fresh IDs. Returns the ID of the imported subtree root.
+    pub fn import_subtree(
+        book: &mut TemplateBook,
+        parent: Option<NodeId>,
+        tree_node: &EjectTreeNode,
+    ) -> Result<NodeId, AppError> {
+        Self::import_tree_node(book, parent, tree_node, 0)
+    }
+
+    /// [`Self::import_tree`] after fitting the tree into `tree.max_depth`
+    /// with `strategy`.
+    pub fn import_tree_fitted(
+        tree: &EjectTree,
+        strategy: DepthStrategy,
+    ) -> Result<(TemplateBook, DepthFit), AppError> {
+        TemplateBook::validate_max_depth(tree.max_depth)?;
+        let (nodes, fit) = Self::fit_depth(&tree.nodes, 0, tree.max_depth, strategy)?;
+        let book = Self::import_tree(&EjectTree {
+            max_depth: fit.max_depth,
+            nodes,
+            ..tree.clone()
+        })?;
+        Ok((book, fit))
+    }
+
+    /// [`Self::import_subtree`] after fitting `tree_node` under `parent` with
+    /// `strategy`, matching incoming nodes against existing siblings with
+    /// `dedupe` (see [`DedupeMode`]). Depth problems are detected before
+    /// `book` is touched.
+    pub fn import_subtree_fitted(
+        book: &mut TemplateBook,
+        parent: Option<NodeId>,
+        tree_node: &EjectTreeNode,
+        strategy: DepthStrategy,
+        dedupe: DedupeMode,
+    ) -> Result<SubtreeImport, AppError> {
+        Self::import_subtree_merged(book, parent, tree_node, strategy, dedupe, IdConflict::Off)
+    }
+
+    /// [`Self::import_subtree_fitted`] that also matches incoming nodes to
+    /// existing ones by their exported `id` (see [`IdConflict`]). Collisions
+    /// are resolved in a pre-pass, so `Error` leaves `book` untouched.
+    pub fn import_subtree_merged(
+        book: &mut TemplateBook,
+        parent: Option<NodeId>,
+        tree_node: &EjectTreeNode,
+        strategy: DepthStrategy,
+        dedupe: DedupeMode,
+        on_id_conflict: IdConflict,
+    ) -> Result<SubtreeImport, AppError> {
+        let base = match parent {
+            Some(p) if book.get_node(p).is_none() => {
+                return Err(DomainError::NodeNotFound(p).into())
+            }
+            Some(p) => book.depth_of(p),
+            None => 0,
+        };
+        let (nodes, fit) = Self::fit_depth(
+            std::slice::from_ref(tree_node),
+            base,
+            book.max_depth(),
+            strategy,
+        )?;
+        let existing = match on_id_conflict {
+            IdConflict::Off => HashMap::new(),
+            _ => Self::id_collisions(book, &nodes),
+        };
+        if on_id_conflict == IdConflict::Error && !existing.is_empty() {
+            let mut ids: Vec<String> = existing
+                .values()
+                .map(|id| {
+                    let title = book.get_node(*id).map(|n| n.title()).unwrap_or_default();
+                    format!("{} ({title})", id.short())
+                })
+                .collect();
+            ids.sort();
+            return Err(AppError::ImportIdConflict(ids.join(", ")));
+        }
+        book.raise_max_depth(fit.max_depth)?;
+        let rules = MergeRules {
+            dedupe,
+            on_id_conflict,
+            existing,
+        };
+        let mut counts = DedupeCounts::default();
+        let mut id_matches = 0;
+        let root = Self::import_merged(
+            book,
+            parent,
+            &nodes,
+            &rules,
+            0,
+            &mut counts,
+            &mut id_matches,
+        )?[0];
+        Ok(SubtreeImport {
+            root,
+            fit,
+            dedupe: counts,
+            id_matches,
+        })
+    }
+
+    /// 部分木の出力 `tree` で `root` の子を丸ごと置き換える（`root` 自体は残し、
+    /// タイトルだけ出力時のものに戻す）。守られた子孫があれば削除の時点で失敗する。
+    /// 深さの問題は `book` に触れる前に検出する。
+    pub fn replace_subtree(
+        book: &mut TemplateBook,
+        root: NodeId,
+        tree: &EjectTree,
+        strategy: DepthStrategy,
+    ) -> Result<DepthFit, AppError> {
+        let node = book.get_node(root).ok_or(DomainError::NodeNotFound(root))?;
+        let children = node.children().to_vec();
+        let retitle = (node.title() != tree.title).then(|| tree.title.clone());
+        let (nodes, fit) =
+            Self::fit_depth(&tree.nodes, book.depth_of(root), book.max_depth(), strategy)?;
+        book.raise_max_depth(fit.max_depth)?;
+        for child in children {
+            book.remove_node(child)?;
+        }
+        if retitle.is_some() {
+            book.update_node(
+                root,
+                UpdateNodeRequest {
+                    title: retitle,
+                    ..Default::default()
+                },
+            )?;
+        }
+        for node in &nodes {
+            Self::import_tree_node(book, Some(root), node, 0)?;
+        }
+        Ok(fit)
+    }
+
+    /// `nodes` を深さ `base` の親の下に置いたとき max_depth に収まるよう調整する。
+    fn fit_depth(
+        nodes: &[EjectTreeNode],
+        base: u8,
+        max_depth: u8,
+        strategy: DepthStrategy,
+    ) -> Result<(Vec<EjectTreeNode>, DepthFit), AppError> {
+        let path = Self::deepest_path(nodes);
+        let deepest = base.saturating_add(u8::try_from(path.len()).unwrap_or(u8::MAX));
+        let too_deep = |max| AppError::ImportTooDeep {
+            path: path.join(" > "),
+            depth: deepest,
+            max,
+        };
+        let mut fit = DepthFit {
+            max_depth,
+            folded: 0,
+        };
+        if deepest <= max_depth {
+            return Ok((nodes.to_vec(), fit));
+        }
+        match strategy {
+            DepthStrategy::Error => Err(too_deep(max_depth)),
+            DepthStrategy::Raise if deepest > TemplateBook::MAX_DEPTH_LIMIT => {
+                Err(too_deep(TemplateBook::MAX_DEPTH_LIMIT))
+            }
+            DepthStrategy::Raise => {
+                fit.max_depth = deepest;
+                Ok((nodes.to_vec(), fit))
+            }
+            // 親がすでに上限の深さなら、畳み込む先のノードすら置けない
+            DepthStrategy::Flatten if base >= max_depth => Err(too_deep(max_depth)),
+            DepthStrategy::Flatten => {
+                let mut nodes = nodes.to_vec();
+                for node in &mut nodes {
+                    fit.folded += Self::fold_below(node, max_depth - base);
+                }
+                Ok((nodes, fit))
+            }
+        }
+    }
+
+    /// 最も深い枝のタイトル列（ルート側から）。
+    fn deepest_path(nodes: &[EjectTreeNode]) -> Vec<&str> {
+        let mut deepest = Vec::new();
+        for node in nodes {
+            let rest = Self::deepest_path(&node.children);
+            if rest.len() + 1 > deepest.len() {
+                deepest = std::iter::once(node.title.as_str()).chain(rest).collect();
+            }
+        }
+        deepest
+    }
+
+    /// `levels` 段目（node 自身 = 1）より深い子孫を本文の箇条書きに畳み込む。
+    /// 畳み込んだノード数を返す。
+    fn fold_below(node: &mut EjectTreeNode, levels: u8) -> usize {
+        if levels > 1 {
+            return node
+                .children
+                .iter_mut()
+                .map(|child| Self::fold_below(child, levels - 1))
+                .sum();
+        }
+        if node.children.is_empty() {
+            return 0;
+        }
+        let mut lines = Vec::new();
+        let folded = Self::bullet_lines(&node.children, 0, &mut lines);
+        node.children.clear();
+        let bullets = lines.join("\n");
+        node.body = Some(match node.body.take() {
+            Some(body) if !body.trim().is_empty() => format!("{}\n\n{bullets}", body.trim_end()),
+            _ => bullets,
+        });
+        folded
+    }
+
+    fn bullet_lines(nodes: &[EjectTreeNode], level: usize, out: &mut Vec<String>) -> usize {
+        let indent = "  ".repeat(level);
+        let mut count = 0;
+        for node in nodes {
+            out.push(format!("{indent}- {}", node.title));
+            for line in node.body.iter().flat_map(|b| b.lines()) {
+                if !line.trim().is_empty() {
+                    out.push(format!("{indent}  {line}"));
+                }
+            }
+            count += 1 + Self::bullet_lines(&node.children, level + 1, out);
+        }
+        count
+    }
+
     fn import_tree_node(
         book: &mut TemplateBook,
         parent: Option<NodeId>,
         tree_node: &EjectTreeNode,
         depth: u8,
+    ) -> Result<NodeId, AppError> {
+        let id = Self::import_single_node(book, parent, tree_node, depth)?;
+        for child in &tree_node.children {
+            Self::import_tree_node(book, Some(id), child, depth + 1)?;
+        }
+        Ok(id)
+    }
+
+    /// `nodes` 以下で `id` が `book` の既存ノードと同じものを集める（ID 文字列 → 既存ノード）。
+    fn id_collisions(book: &TemplateBook, nodes: &[EjectTreeNode]) -> HashMap<String, NodeId> {
+        let by_id: HashMap<String, NodeId> = book
+            .all_nodes_dfs()
+            .into_iter()
+            .map(|n| (n.id().to_string(), n.id()))
+            .collect();
+        let mut found = HashMap::new();
+        let mut stack: Vec<&EjectTreeNode> = nodes.iter().collect();
+        while let Some(node) = stack.pop() {
+            if let Some(id) = by_id.get(node.id.trim()) {
+                found.insert(node.id.trim().to_string(), *id);
+            }
+            stack.extend(&node.children);
+        }
+        found
+    }
+
+    /// `nodes` を `parent` の下へ取り込む。ID が既存ノードと同じものは
+    /// `rules.on_id_conflict` で、それ以外は既存の兄弟と `rules.dedupe` で突き合わせ、
+    /// 一致したノードは作らずに既存ノードの下へ子を同じ規則で取り込む。
+    /// 戻り値は `nodes` と同じ並びの、作成または一致したノードの ID。
+    fn import_merged(
+        book: &mut TemplateBook,
+        parent: Option<NodeId>,
+        nodes: &[EjectTreeNode],
+        rules: &MergeRules,
+        depth: u8,
+        counts: &mut DedupeCounts,
+        id_matches: &mut usize,
+    ) -> Result<Vec<NodeId>, AppError> {
+        let titles: Vec<&str> = nodes.iter().map(|n| n.title.as_str()).collect();
+        let plan = plan_siblings(&sibling_titles(book, parent), &titles, rules.dedupe);
+        let mut ids: Vec<NodeId> = Vec::with_capacity(nodes.len());
+        for (node, decision) in nodes.iter().zip(plan) {
+            let matched = rules.existing.get(node.id.trim()).copied();
+            let id = match (matched, decision) {
+                (Some(id), _) => {
+                    if rules.on_id_conflict == IdConflict::Replace {
+                        Self::replace_fields(book, id, node)?;
+                    }
+                    *id_matches += 1;
+                    id
+                }
+                (None, DedupeDecision::Create) => {
+                    counts.created += 1;
+                    Self::import_single_node(book, parent, node, depth)?
+                }
+                (None, DedupeDecision::Skip(id)) => {
+                    counts.skipped += 1;
+                    id
+                }
+                (None, DedupeDecision::Update(id)) => {
+                    overwrite_fields(book, id, node.body.clone(), node.placeholder.clone())?;
+                    counts.updated += 1;
+                    id
+                }
+                (None, DedupeDecision::Repeat(earlier)) => {
+                    counts.skipped += 1;
+                    ids[earlier]
+                }
+            };
+            ids.push(id);
+            Self::import_merged(
+                book,
+                Some(id),
+                &node.children,
+                rules,
+                depth + 1,
+                counts,
+                id_matches,
+            )?;
+        }
+        Ok(ids)
+    }
+
+    /// 既存ノード `id` の項目（子と状態以外）を `tree_node` のもので置き換える。
+    fn replace_fields(
+        book: &mut TemplateBook,
+        id: NodeId,
+        tree_node: &EjectTreeNode,
     ) -> Result<(), AppError> {
+        book.update_node(
+            id,
+            UpdateNodeRequest {
+                title: Some(tree_node.title.clone()),
+                body: Some(tree_node.body.clone()),
+                node_type: Some(Self::parse_node_type(&tree_node.node_type)?),
+                placeholder: Some(tree_node.placeholder.clone()),
+                properties: Some(tree_node.properties.clone()),
+                notes: Some(tree_node.notes.clone()),
+                note: Some(tree_node.note.clone()),
+                estimate_minutes: Some(tree_node.estimate_minutes),
+                owner: Some(tree_node.owner.clone()),
+                ordered: Some(tree_node.ordered != Some(false)),
+                order: Some(tree_node.order),
+                heading_level: Some(tree_node.heading_level),
+                command: Some(tree_node.command.clone()),
+                ..Default::default()
+            },
+        )?;
+        book.set_links(id, tree_node.links.clone())?;
+        Ok(())
+    }
+
+    fn parse_node_type(node_type: &str) -> Result<NodeType, AppError> {
+        match node_type {
+            "section" => Ok(NodeType::Section),
+            "content" => Ok(NodeType::Content),
+            // 旧フォーマット互換: checklist/reference/runnable → Content
+            "checklist" | "reference" | "runnable" => Ok(NodeType::Content),
+            other => Err(AppError::ImportInvalidType(other.to_string())),
+        }
+    }
+
+    /// 子を除いた1ノード分を取り込む。
+    fn import_single_node(
+        book: &mut TemplateBook,
+        parent: Option<NodeId>,
+        tree_node: &EjectTreeNode,
+        depth: u8,
+    ) -> Result<NodeId, AppError> {
         if depth >= Self::IMPORT_MAX_RECURSION {
             return Err(AppError::ImportInvalidType(
                 "maximum import nesting depth exceeded".to_string(),
             ));
         }
 
-        let node_type = match tree_node.node_type.as_str() {
-            "section" => NodeType::Section,
-            "content" => NodeType::Content,
-            // 旧フォーマット互換: checklist/reference/runnable → Content
-            "checklist" | "reference" | "runnable" => NodeType::Content,
-            other => return Err(AppError::ImportInvalidType(other.to_string())),
-        };
+        let node_type = Self::parse_node_type(&tree_node.node_type)?;
 
         let id = book.add_node(AddNodeRequest {
             parent,
@@ -203,15 +1031,131 @@ impl EjectService {
             node_type,
             body: tree_node.body.clone(),
             placeholder: tree_node.placeholder.clone(),
-            position: usize::MAX,
+            position: Position::Last,
             properties: tree_node.properties.clone(),
         })?;
+        if tree_node.notes.is_some()
+            || tree_node.note.is_some()
+            || tree_node.estimate_minutes.is_some()
+            || tree_node.owner.is_some()
+            || tree_node.ordered.is_some()
+            || tree_node.order.is_some()
+            || tree_node.heading_level.is_some()
+            || tree_node.command.is_some()
+        {
+            book.update_node(
+                id,
+                UpdateNodeRequest {
+                    notes: Some(tree_node.notes.clone()),
+                    note: Some(tree_node.note.clone()),
+                    estimate_minutes: Some(tree_node.estimate_minutes),
+                    owner: Some(tree_node.owner.clone()),
+                    ordered: tree_node.ordered,
+                    order: tree_node.order.map(Some),
+                    heading_level: tree_node.heading_level.map(Some),
+                    command: Some(tree_node.command.clone()),
+                    ..Default::default()
+                },
+            )?;
+        }
+        if !tree_node.links.is_empty() {
+            book.set_links(id, tree_node.links.clone())?;
+        }
+        Ok(id)
+    }
 
-        for child in &tree_node.children {
-            Self::import_tree_node(book, Some(id), child, depth + 1)?;
+    /// 設定に従って出力内容を文字列として描画する（ファイルには書かない）。
+    pub fn render(book: &TemplateBook, config: &EjectConfig) -> Result<String, AppError> {
+        let pruned;
+        let book = if config.created_after.is_some() || config.owner.is_some() {
+            pruned = Self::prune_to_matching(book, |node| {
+                config
+                    .created_after
+                    .is_none_or(|since| node.created_at().is_some_and(|c| c >= since))
+                    && config
+                        .owner
+                        .as_deref()
+                        .is_none_or(|owner| node.owner() == Some(owner))
+            });
+            &pruned
+        } else {
+            book
+        };
+        let footer = config
+            .footer
+            .then(|| Self::footer(book, config.subtree_root, Timestamp::now()));
+        match config.format {
+            EjectFormat::Markdown => {
+                let options = MarkdownOptions {
+                    include_placeholders: config.include_placeholders,
+                    include_notes: config.include_notes,
+                    include_estimates: config.include_estimates,
+                    include_ancestors: config.include_ancestors,
+                    style: config.style,
+                    wrap_width: config.wrap_width,
+                    labels: config.labels.clone(),
+                    heading_offset: 0,
+                    section_separators: config.section_separators,
+                    mark_incomplete: config.mark_incomplete,
+                    mark_body_lines: config.mark_body_lines,
+                };
+                let md = Self::render_markdown_with(book, &options, config.subtree_root);
+                Ok(Self::append_comment_footer(md, footer))
+            }
+            EjectFormat::Html => {
+                let options = MarkdownOptions {
+                    include_placeholders: config.include_placeholders,
+                    include_notes: config.include_notes,
+                    include_estimates: config.include_estimates,
+                    include_ancestors: config.include_ancestors,
+                    ..MarkdownOptions::default()
+                };
+                let html = Self::render_html(book, &options, config.subtree_root);
+                Ok(Self::append_comment_footer(html, footer))
+            }
+            EjectFormat::Json => {
+                let mut tree = Self::build_tree(book, config.subtree_root);
+                if tree.subtree_uuid.is_some() {
+                    tree.source_slug = config.source_slug.clone();
+                }
+                if config.include_ancestors {
+                    if let Some(root_id) = config.subtree_root {
+                        tree.ancestors = Self::ancestor_titles(book, root_id);
+                    }
+                }
+                // フッターは `nodes` の外のキーにして、ツリーの形は変えない
+                let json = match footer {
+                    Some(comment) => serde_json::to_string_pretty(&FootedEjectTree {
+                        tree: &tree,
+                        comment,
+                    }),
+                    None => serde_json::to_string_pretty(&tree),
+                };
+                json.map_err(|e| AppError::Storage(Box::new(e)))
+            }
         }
+    }
 
-        Ok(())
+    /// 出力元の Book を示すフッター文（`subtree_root` なら書き出したその子以下の数を数える）。
+    pub fn footer(book: &TemplateBook, subtree_root: Option<NodeId>, at: Timestamp) -> String {
+        let count = match subtree_root {
+            Some(root) => book.descendant_count(root),
+            None => book.node_count(),
+        };
+        format!(
+            "Exported from \"{}\" (book {}): {count} node(s), generated {at}",
+            one_line(book.title()),
+            book.id()
+        )
+    }
+
+    /// Markdown / HTML の末尾にフッターを HTML コメントとして足す。
+    fn append_comment_footer(mut content: String, footer: Option<String>) -> String {
+        if let Some(text) = footer {
+            // タイトル中の `--` でコメントが閉じないようにする
+            content.push_str(&format!("\n<!-- {} -->\n", text.replace("--", "- -")));
+        }
+        content
     }
 
     /// ファイルに書き出す。
@@ -219,13 +1163,64 @@ impl EjectService {
         book: &TemplateBook,
         config: &EjectConfig,
     ) -> Result<std::path::PathBuf, AppError> {
-        let content = match config.format {
-            EjectFormat::Markdown => {
-                Self::render_markdown(book, config.include_placeholders, config.subtree_root)
+        let content = Self::render(book, config)?;
+        Self::write_export(config, content)
+    }
+
+    /// [`Self::render_commands`] の結果を `config` の出力先に書き出す
+    /// （`subtree_root` 以外の描画設定は使わない）。
+    pub fn eject_commands(
+        book: &TemplateBook,
+        config: &EjectConfig,
+    ) -> Result<std::path::PathBuf, AppError> {
+        let script = Self::render_commands(book, config.subtree_root)?;
+        Self::write_export(config, script)
+    }
+
+    /// 部分木（省略時は Book 全体）の command を出現順に並べた、コピーして使える
+    /// シェルスクリプト。各コマンドの前に `# 2-3 Title` のコメントを置く。実行はしない。
+    pub fn render_commands(
+        book: &TemplateBook,
+        subtree_root: Option<NodeId>,
+    ) -> Result<String, AppError> {
+        let mut script = String::from("#!/bin/sh\n");
+        match subtree_root {
+            Some(root) => {
+                let node = book.get_node(root).ok_or(DomainError::NodeNotFound(root))?;
+                let number = book.outline_number(root).unwrap_or_default();
+                script.push_str(&format!(
+                    "# {} — {number} {}\n",
+                    one_line(book.title()),
+                    one_line(node.title())
+                ));
+            }
+            None => script.push_str(&format!("# {}\n", one_line(book.title()))),
+        }
+        let in_scope = |node: &TemplateNode| match subtree_root {
+            Some(root) => {
+                node.id() == root || book.ancestors(node.id()).iter().any(|a| a.id() == root)
             }
-            EjectFormat::Json => Self::render_json(book, config.subtree_root)?,
+            None => true,
         };
+        let mut found = 0;
+        for node in book.all_nodes_dfs().into_iter().filter(|n| in_scope(n)) {
+            let Some(command) = node.command().map(str::trim_end).filter(|c| !c.is_empty()) else {
+                continue;
+            };
+            let number = book.outline_number(node.id()).unwrap_or_default();
+            script.push_str(&format!(
+                "\n# {number} {}\n{command}\n",
+                one_line(node.title())
+            ));
+            found += 1;
+        }
+        if found == 0 {
+            script.push_str("\n# (no commands)\n");
+        }
+        Ok(script)
+    }
 
+    fn write_export(config: &EjectConfig, content: String) -> Result<std::path::PathBuf, AppError> {
         let path = config.output_dir.join(&config.filename);
 
         if let Some(parent) = path.parent() {
@@ -236,6 +1231,54 @@ impl EjectService {
         Ok(path)
     }
 
+    /// `matches` を満たすノードとその祖先だけを残した複製を返す。
+    ///
+    /// 残らないノードの子孫も残らない（残るノードの祖先は必ず残る）ため、
+    /// 上位から部分木ごと削除してよい。
+    fn prune_to_matching(
+        book: &TemplateBook,
+        matches: impl Fn(&TemplateNode) -> bool,
+    ) -> TemplateBook {
+        let mut keep: HashSet<NodeId> = HashSet::new();
+        for node in book.all_nodes_dfs() {
+            if matches(node) {
+                keep.insert(node.id());
+                keep.extend(book.ancestors(node.id()).iter().map(|a| a.id()));
+            }
+        }
+
+        let mut pruned = book.clone();
+        for node in book.all_nodes_dfs() {
+            if !keep.contains(&node.id()) && pruned.get_node(node.id()).is_some() {
+                // 直前のチェックで存在を確認済み
+                let _ = pruned.remove_node_overriding_protection(node.id());
+            }
+        }
+        pruned
+    }
+
+    /// 本文の1行をノードのインデント + 2 の下に置く。行頭の空白は本文内の
+    /// 相対的な深さとしてその後ろに残し（タブは2スペースに展開）、
+    /// 入れ子のリストが親の項目の下に入れ子のまま出力されるようにする。
+    /// `mark` なら構造の構文と紛れる行に [`BODY_LINE_MARKER`] を付ける。
+    fn indent_body_line(indent: &str, line: &str, mark: bool) -> String {
+        let trimmed = line.trim_start();
+        if trimmed.is_empty() {
+            return "\n".to_string();
+        }
+        let leading: String = line[..line.len() - trimmed.len()]
+            .chars()
+            .map(|c| if c == '\t' { "  " } else { " " })
+            .collect();
+        let converted = Self::list_to_checkbox(trimmed);
+        // 構造の構文と紛れる行は印を付け、Markdown の読み込みで本文に戻せるようにする
+        if mark && needs_body_marker(&converted) {
+            format!("{indent}  {leading}{converted} {BODY_LINE_MARKER}\n")
+        } else {
+            format!("{indent}  {leading}{converted}\n")
+        }
+    }
+
     /// リスト行 (`- `, `* `) をチェックボックス形式に変換する。
     fn list_to_checkbox(line: &str) -> String {
         let trimmed = line.trim_start();
@@ -250,56 +1293,271 @@ impl EjectService {
         }
     }
 
+    /// 未解決の依存があれば ` (blocked by 2-1, 3)` を返す。
+    pub(super) fn blocked_suffix(book: &TemplateBook, node: &TemplateNode) -> String {
+        let numbers: Vec<String> = book
+            .blocked_by(node.id())
+            .into_iter()
+            .filter_map(|id| book.outline_number(id))
+            .collect();
+        if numbers.is_empty() {
+            String::new()
+        } else {
+            format!(" (blocked by {})", numbers.join(", "))
+        }
+    }
+
+    /// Placeholder hint shown for `node`: its own, or for a Content node
+    /// without one, the book's default.
+    pub fn effective_placeholder<'a>(
+        book: &'a TemplateBook,
+        node: &'a TemplateNode,
+    ) -> Option<&'a str> {
+        match node.node_type() {
+            NodeType::Content => node.placeholder().or(book.default_placeholder()),
+            NodeType::Section => node.placeholder(),
+        }
+    }
+
+    /// Render one node as an editable fill-in stub: the title as a heading,
+    /// the body verbatim, and the placeholder as an empty `hint: ____` line.
+    pub fn render_stub(book: &TemplateBook, node: &TemplateNode) -> String {
+        let mut buf = format!("# {}\n", node.title());
+        if let Some(body) = node.body() {
+            buf.push('\n');
+            buf.push_str(body.trim_end());
+            buf.push('\n');
+        }
+        if let Some(ph) = Self::effective_placeholder(book, node) {
+            buf.push_str(&format!("\n{ph}: ____\n"));
+        }
+        buf
+    }
+
+    /// `parent_heading` は祖先の `heading_level` から決まった親の見出しレベル
+    /// （上書きのない枝では `None`）。
     fn render_node(
         book: &TemplateBook,
         node: &TemplateNode,
         indent_level: usize,
-        include_placeholders: bool,
+        parent_heading: Option<usize>,
+        options: &MarkdownOptions,
         buf: &mut String,
     ) {
+        // 上書きがあればそのレベル、上書きされた祖先の下なら親の1段下
+        let heading = node
+            .heading_level()
+            .filter(|_| *node.node_type() == NodeType::Section)
+            .or(parent_heading.map(|l| l + 1));
+        if options.style == MarkdownStyle::Form && *node.node_type() == NodeType::Content {
+            Self::render_form_field(book, node, options, buf);
+            for &child_id in node.children() {
+                if let Some(child) = book.get_node(child_id) {
+                    Self::render_node(book, child, indent_level + 1, heading, options, buf);
+                }
+            }
+            return;
+        }
         let indent = "  ".repeat(indent_level);
+        let mut suffix = Self::blocked_suffix(book, node);
+        if options.include_estimates {
+            if let Some(m) = node.estimate_minutes() {
+                suffix.push_str(&format!(" (~{m}m)"));
+            }
+        }
 
         match node.node_type() {
             NodeType::Section => {
-                let heading_level = (indent_level + 2).min(4);
-                let hashes = "#".repeat(heading_level);
-                buf.push_str(&format!("{} {}\n\n", hashes, node.title()));
+                // 深い Section は H4 で頭打ちにし、そのうえで heading_offset だけ下げる
+                let level = heading.unwrap_or((indent_level + 2).min(4));
+                let hashes = Self::hashes(level, options);
+                buf.push_str(&format!("{} {}{}\n\n", hashes, node.title(), suffix));
             }
             NodeType::Content => {
-                buf.push_str(&format!("{}- [ ] {}\n", indent, node.title()));
+                // 順不同の Section の子は手順ではないのでチェックボックスを付けない
+                let unordered = node
+                    .parent()
+                    .and_then(|p| book.get_node(p))
+                    .is_some_and(|p| !p.is_ordered());
+                let marker = match (unordered, node.is_done()) {
+                    (true, _) => "- ".to_string(),
+                    (false, true) => Self::checkbox_marker("- [x] ", &options.labels.done_word),
+                    (false, false) => Self::checkbox_marker("- [ ] ", &options.labels.todo_word),
+                };
+                if options.mark_incomplete && needs_detail(node) {
+                    suffix.push_str(&format!(" {NEEDS_DETAIL_MARKER}{NEEDS_DETAIL_TAG}"));
+                }
+                buf.push_str(&format!("{indent}{marker}{}{suffix}\n", node.title()));
             }
         }
 
         if let Some(body) = node.body() {
-            for line in body.lines() {
-                let converted = Self::list_to_checkbox(line);
-                buf.push_str(&format!("{indent}  {converted}\n"));
-            }
+            let rendered: String = body
+                .lines()
+                .map(|line| Self::indent_body_line(&indent, line, options.mark_body_lines))
+                .collect();
+            buf.push_str(&Self::wrap_body(rendered, options));
         }
 
-        if include_placeholders {
-            if let Some(ph) = node.placeholder() {
+        if let Some(command) = node.command() {
+            buf.push_str(&Self::command_block(&format!("{indent}  "), command));
+        }
+
+        // 見出しの下のリンクは入れ子にしない（項目の下ならその子として字下げする）
+        let link_prefix = match node.node_type() {
+            NodeType::Section => String::new(),
+            NodeType::Content => format!("{indent}  "),
+        };
+        buf.push_str(&Self::links_block(&link_prefix, node.links()));
+
+        if let Some(note) = node.note() {
+            buf.push_str(&Self::note_block(&format!("{indent}  "), note));
+        }
+
+        if options.include_placeholders {
+            if let Some(ph) = Self::effective_placeholder(book, node) {
                 buf.push_str(&format!("{indent}  > {ph}: ___\n"));
             }
         }
 
-        if !node.is_leaf() {
+        if options.include_notes {
+            if let Some(notes) = node.notes() {
+                for line in notes.lines().filter(|l| !l.trim().is_empty()) {
+                    buf.push_str(&format!("{indent}  _{}_\n", line.trim()));
+                }
+            }
+        }
+
+        // フォームの項目はそれぞれ空行で終わるので、区切りの空行は足さない
+        if !node.is_leaf() && options.style != MarkdownStyle::Form {
             buf.push('\n');
         }
 
         for &child_id in node.children() {
             if let Some(child) = book.get_node(child_id) {
-                Self::render_node(book, child, indent_level + 1, include_placeholders, buf);
+                Self::render_node(book, child, indent_level + 1, heading, options, buf);
             }
         }
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::domain::model::book::AddNodeRequest;
-    use crate::domain::model::node::NodeType;
+    /// 見出しレベル `level` に `heading_offset` を足した `#` の列（最大 6 個）。
+    fn hashes(level: usize, options: &MarkdownOptions) -> String {
+        "#".repeat((level + options.heading_offset).min(6))
+    }
+
+    /// チェックボックスに状態語があれば続ける（`- [ ] TODO `）。
+    fn checkbox_marker(checkbox: &str, word: &Option<String>) -> String {
+        match word.as_deref().map(str::trim).filter(|w| !w.is_empty()) {
+            Some(word) => format!("{checkbox}{word} "),
+            None => checkbox.to_string(),
+        }
+    }
+
+    /// note を引用ブロックとして描画する（本文と違いチェックボックスには変換しない）。
+    fn note_block(prefix: &str, note: &str) -> String {
+        note.trim_end()
+            .lines()
+            .map(|line| match line.trim_end() {
+                "" => format!("{prefix}>\n"),
+                line => format!("{prefix}> {line}\n"),
+            })
+            .collect()
+    }
+
+    /// command を ```` ```sh ```` ブロックとして描画する。中にバッククォートの並びが
+    /// あれば、それより長いフェンスを使う。
+    fn command_block(prefix: &str, command: &str) -> String {
+        let longest = command.split(|c| c != '`').map(str::len).max().unwrap_or(0);
+        let fence = "`".repeat((longest + 1).max(3));
+        let mut out = format!("{prefix}{fence}sh\n");
+        for line in command.trim_end().lines() {
+            match line.trim_end() {
+                "" => out.push('\n'),
+                line => out.push_str(&format!("{prefix}{line}\n")),
+            }
+        }
+        out.push_str(&format!("{prefix}{fence}\n"));
+        out
+    }
+
+    /// 外部リンクを `- [label](url)` のリストとして描画する。ラベル中の `]` は
+    /// リンクを壊すのでエスケープする。
+    fn links_block(prefix: &str, links: &[(String, String)]) -> String {
+        links
+            .iter()
+            .map(|(label, url)| {
+                let label = label.replace('[', "\\[").replace(']', "\\]");
+                format!("{prefix}- [{label}]({url})\n")
+            })
+            .collect()
+    }
+
+    /// `wrap_width` が指定されていれば描画済みの本文を折り返す。
+    fn wrap_body(rendered: String, options: &MarkdownOptions) -> String {
+        match options.wrap_width {
+            Some(width) => wrap_markdown(&rendered, width),
+            None => rendered,
+        }
+    }
+
+    /// フォーム形式の1項目: 太字のラベル、本文、記入欄（`placeholder: _______`）。
+    /// 入れ子にしない（定義リストのように左端に揃える）。記入欄は placeholder が
+    /// なくても `_______` だけを出す。
+    fn render_form_field(
+        book: &TemplateBook,
+        node: &TemplateNode,
+        options: &MarkdownOptions,
+        buf: &mut String,
+    ) {
+        let mut suffix = Self::blocked_suffix(book, node);
+        if options.include_estimates {
+            if let Some(m) = node.estimate_minutes() {
+                suffix.push_str(&format!(" (~{m}m)"));
+            }
+        }
+        buf.push_str(&format!("**{}**{suffix}\n\n", node.title()));
+        if let Some(body) = node.body().map(str::trim_end).filter(|b| !b.is_empty()) {
+            buf.push_str(&Self::wrap_body(body.to_string(), options));
+            buf.push_str("\n\n");
+        }
+        if let Some(command) = node.command() {
+            buf.push_str(&Self::command_block("", command));
+            buf.push('\n');
+        }
+        if !node.links().is_empty() {
+            buf.push_str(&Self::links_block("", node.links()));
+            buf.push('\n');
+        }
+        if let Some(note) = node.note() {
+            buf.push_str(&Self::note_block("", note));
+            buf.push('\n');
+        }
+        match Self::effective_placeholder(book, node).filter(|_| options.include_placeholders) {
+            Some(ph) => buf.push_str(&format!("{ph}: _______\n\n")),
+            None => buf.push_str("_______\n\n"),
+        }
+        if options.include_notes {
+            if let Some(notes) = node.notes() {
+                for line in notes.lines().filter(|l| !l.trim().is_empty()) {
+                    buf.push_str(&format!("_{}_\n", line.trim()));
+                }
+                buf.push('\n');
+            }
+        }
+    }
+}
+
+/// 複数行のタイトルをコメント用に1行へまとめる。
+fn one_line(title: &str) -> String {
+    title.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::application::content_hash;
+    use crate::domain::model::book::{AddNodeRequest, Position};
+    use crate::domain::model::node::NodeType;
 
     fn make_test_book() -> (TemplateBook, NodeId, NodeId) {
         let mut book = TemplateBook::new("Dev Runbook", 3);
@@ -311,7 +1569,7 @@ mod tests {
                 node_type: NodeType::Section,
                 body: None,
                 placeholder: None,
-                position: usize::MAX,
+                position: Position::Last,
                 properties: HashMap::new(),
             })
             .unwrap();
@@ -323,7 +1581,7 @@ mod tests {
                 node_type: NodeType::Content,
                 body: None,
                 placeholder: Some("requirements list".into()),
-                position: usize::MAX,
+                position: Position::Last,
                 properties: HashMap::new(),
             })
             .unwrap();
@@ -334,7 +1592,7 @@ mod tests {
             node_type: NodeType::Content,
             body: Some("REST endpoints".into()),
             placeholder: None,
-            position: usize::MAX,
+            position: Position::Last,
             properties: HashMap::new(),
         })
         .unwrap();
@@ -342,6 +1600,54 @@ mod tests {
         (book, design, req_id)
     }
 
+    #[test]
+    fn export_hashes_track_content_and_subtree_changes() {
+        let (mut book, _, req_id) = make_test_book();
+        let before = EjectService::build_tree(&book, None);
+        let section = &before.nodes[0];
+        assert!(section.children_hash.is_some());
+        assert!(section.children[0].children_hash.is_none());
+        assert_eq!(
+            section.children[0].content_hash.as_deref(),
+            Some(
+                content_hash::content_hash(
+                    "content",
+                    "Define requirements",
+                    None,
+                    Some("requirements list")
+                )
+                .as_str()
+            )
+        );
+
+        // notes はハッシュに含まれない
+        let mut notes_only = book.clone();
+        set_notes(&mut notes_only, req_id, "ask the PM");
+        let same = EjectService::build_tree(&notes_only, None);
+        assert_eq!(same.nodes[0].children_hash, section.children_hash);
+
+        book.update_node(
+            req_id,
+            UpdateNodeRequest {
+                body: Some(Some("latency budget".into())),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        let after = EjectService::build_tree(&book, None);
+        let changed = &after.nodes[0];
+        assert_eq!(changed.content_hash, section.content_hash);
+        assert_ne!(changed.children_hash, section.children_hash);
+        assert_ne!(
+            changed.children[0].content_hash,
+            section.children[0].content_hash
+        );
+        assert_eq!(
+            changed.children[1].content_hash,
+            section.children[1].content_hash
+        );
+    }
+
     #[test]
     fn render_markdown_full() {
         let (book, _, _) = make_test_book();
@@ -362,6 +1668,173 @@ mod tests {
         assert!(!md.contains("> requirements list"));
     }
 
+    #[test]
+    fn render_markdown_form_style_uses_labels_and_fill_lines() {
+        let (book, _, _) = make_test_book();
+        let options = MarkdownOptions {
+            include_placeholders: true,
+            style: MarkdownStyle::Form,
+            ..MarkdownOptions::default()
+        };
+        let md = EjectService::render_markdown_with(&book, &options, None);
+        assert_eq!(
+            md,
+            "# Dev Runbook\n\n## Design\n\n\
+             **Define requirements**\n\nrequirements list: _______\n\n\
+             **API design**\n\nREST endpoints\n\n_______\n\n"
+        );
+        assert!(!md.contains("- ["));
+    }
+
+    #[test]
+    fn checkbox_labels_follow_the_checkbox() {
+        let (mut book, _, req_id) = make_test_book();
+        book.update_node(
+            req_id,
+            UpdateNodeRequest {
+                done: Some(true),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        let plain = EjectService::render_markdown(&book, false, None);
+        assert!(plain.contains("- [x] Define requirements\n"));
+        assert!(plain.contains("- [ ] API design\n"));
+
+        let options = MarkdownOptions {
+            labels: CheckboxLabels {
+                todo_word: Some("未着手".into()),
+                done_word: Some("DONE".into()),
+            },
+            ..MarkdownOptions::default()
+        };
+        let md = EjectService::render_markdown_with(&book, &options, None);
+        assert!(md.contains("- [x] DONE Define requirements\n"), "{md}");
+        assert!(md.contains("- [ ] 未着手 API design\n"), "{md}");
+    }
+
+    #[test]
+    fn wrap_width_wraps_bodies_but_not_titles() {
+        let mut book = TemplateBook::new("Runbook", 3);
+        book.add_node(AddNodeRequest {
+            parent: None,
+            title: "A title much longer than the wrap width".into(),
+            node_type: NodeType::Content,
+            body: Some("Check the logs on every node before restarting\n- then restart the workers one by one".into()),
+            placeholder: None,
+            position: Position::Last,
+            properties: HashMap::new(),
+        })
+        .unwrap();
+        let options = MarkdownOptions {
+            wrap_width: Some(24),
+            ..MarkdownOptions::default()
+        };
+        let md = EjectService::render_markdown_with(&book, &options, None);
+        assert_eq!(
+            md,
+            "# Runbook\n\n- [ ] A title much longer than the wrap width\n\
+             \x20 Check the logs on\n\
+             \x20 every node before\n\
+             \x20 restarting\n\
+             \x20 - [ ] then restart the\n\
+             \x20       workers one by\n\
+             \x20       one\n"
+        );
+    }
+
+    #[test]
+    fn section_separators_rule_off_top_level_nodes() {
+        let (mut book, _, _) = make_test_book();
+        for title in ["Deploy", "Wrap-up"] {
+            book.add_node(AddNodeRequest {
+                parent: None,
+                title: title.into(),
+                node_type: NodeType::Content,
+                body: None,
+                placeholder: None,
+                position: Position::Last,
+                properties: HashMap::new(),
+            })
+            .unwrap();
+        }
+        let options = MarkdownOptions {
+            section_separators: true,
+            ..MarkdownOptions::default()
+        };
+        let md = EjectService::render_markdown_with(&book, &options, None);
+        assert!(md.starts_with("# Dev Runbook\n\n## Design\n\n"), "{md}");
+        assert!(
+            md.ends_with("\n\n---\n\n- [ ] Deploy\n\n---\n\n- [ ] Wrap-up\n"),
+            "{md}"
+        );
+        assert_eq!(md.matches("---").count(), 2);
+
+        // 既定では入れない。部分木の出力にも入れない
+        let plain = EjectService::render_markdown_with(&book, &MarkdownOptions::default(), None);
+        assert!(!plain.contains("---"));
+        let design = book.root_nodes()[0];
+        let subtree = EjectService::render_markdown_with(&book, &options, Some(design));
+        assert!(!subtree.contains("---"));
+    }
+
+    #[test]
+    fn mark_incomplete_flags_placeholder_only_items() {
+        let mut book = TemplateBook::new("Runbook", 4);
+        for (title, body, placeholder) in [
+            ("Pick host", None, Some("host")),
+            ("Blank body", Some("   "), Some("host")),
+            ("Restart", Some("systemctl restart app"), Some("host")),
+            ("Wrap-up", None, None),
+            ("Audit ⚠ needs detail", Some("Read the log"), None),
+        ] {
+            book.add_node(AddNodeRequest {
+                parent: None,
+                title: title.into(),
+                node_type: NodeType::Content,
+                body: body.map(str::to_string),
+                placeholder: placeholder.map(str::to_string),
+                position: Position::Last,
+                properties: HashMap::new(),
+            })
+            .unwrap();
+        }
+        let options = MarkdownOptions {
+            include_placeholders: true,
+            mark_incomplete: true,
+            ..MarkdownOptions::default()
+        };
+        let md = EjectService::render_markdown_with(&book, &options, None);
+        assert!(
+            md.contains("- [ ] Pick host ⚠ needs detail<!--needs-detail-->\n"),
+            "{md}"
+        );
+        assert!(
+            md.contains("- [ ] Blank body ⚠ needs detail<!--needs-detail-->\n"),
+            "{md}"
+        );
+        assert!(md.contains("- [ ] Restart\n"), "{md}");
+        assert!(md.contains("- [ ] Wrap-up\n"), "{md}");
+        assert_eq!(md.matches(NEEDS_DETAIL_TAG).count(), 2);
+
+        // 読み戻すとタイトルに印は残らない
+        let tree = EjectTree::from_markdown(&md).unwrap();
+        assert_eq!(tree.nodes[0].title, "Pick host");
+        // 目印のない同じ文言は本物のタイトルなので残す
+        assert_eq!(tree.nodes[4].title, "Audit ⚠ needs detail");
+        assert_eq!(tree.nodes[0].placeholder.as_deref(), Some("host"));
+
+        let plain = EjectService::render_markdown_with(
+            &book,
+            &MarkdownOptions {
+                mark_incomplete: false,
+                ..options
+            },
+            None,
+        );
+        assert!(!plain.contains(NEEDS_DETAIL_TAG));
+    }
+
     #[test]
     fn render_markdown_subtree() {
         let (book, design, _) = make_test_book();
@@ -435,6 +1908,77 @@ mod tests {
         assert_eq!(child1.body(), Some("REST endpoints"));
     }
 
+    #[test]
+    fn strict_json_rejects_unknown_fields_lenient_ignores_them() {
+        let json = r#"{"title": "Ops", "max_depth": 3, "nodes": [
+            {"id": "a", "title": "Deploy", "node_type": "section", "children": [
+                {"id": "b", "title": "Rollback", "node_type": "content", "placholder": "steps"}
+            ]}
+        ]}"#;
+        let lenient = EjectTree::from_json(json, false).unwrap();
+        assert_eq!(lenient.nodes[0].children[0].placeholder, None);
+
+        let err = EjectTree::from_json(json, true).unwrap_err().to_string();
+        assert!(err.contains("unknown field `placholder`"), "{err}");
+
+        // 正しい出力は strict でも同じ内容で読める
+        let (book, _, _) = make_test_book();
+        let exported = serde_json::to_string(&EjectService::build_tree(&book, None)).unwrap();
+        let strict = EjectTree::from_json(&exported, true).unwrap();
+        assert_eq!(serde_json::to_string(&strict).unwrap(), exported);
+    }
+
+    #[test]
+    fn find_top_level_by_index_or_title() {
+        let (mut book, _, _) = make_test_book();
+        book.add_node(AddNodeRequest {
+            parent: None,
+            title: "Release".into(),
+            node_type: NodeType::Section,
+            body: None,
+            placeholder: None,
+            position: Position::Last,
+            properties: HashMap::new(),
+        })
+        .unwrap();
+        let tree = EjectService::build_tree(&book, None);
+        assert_eq!(tree.find_top_level("2").unwrap().title, "Release");
+        assert_eq!(tree.find_top_level("design").unwrap().title, "Design");
+        assert!(tree.find_top_level("0").is_none());
+        assert!(tree.find_top_level("3").is_none());
+        assert!(tree.find_top_level("Ops").is_none());
+    }
+
+    #[test]
+    fn import_subtree_appends_under_parent_with_fresh_ids() {
+        let (source, design, _) = make_test_book();
+        let tree = EjectService::build_tree(&source, None);
+        let exported = tree.find_top_level("Design").unwrap();
+
+        let mut target = TemplateBook::new("Target", 4);
+        let host = target
+            .add_node(AddNodeRequest {
+                parent: None,
+                title: "Imported".into(),
+                node_type: NodeType::Section,
+                body: None,
+                placeholder: None,
+                position: Position::Last,
+                properties: HashMap::new(),
+            })
+            .unwrap();
+        let root = EjectService::import_subtree(&mut target, Some(host), exported).unwrap();
+
+        assert_ne!(root, design);
+        assert_eq!(target.get_node(root).unwrap().parent(), Some(host));
+        assert_eq!(target.node_count(), 4);
+        let child = target.get_node(root).unwrap().children()[0];
+        assert_eq!(
+            target.get_node(child).unwrap().placeholder(),
+            Some("requirements list")
+        );
+    }
+
     #[test]
     fn import_tree_invalid_type() {
         let tree = EjectTree {
@@ -446,15 +1990,383 @@ mod tests {
                 node_type: "unknown_type".into(),
                 body: None,
                 placeholder: None,
+                notes: None,
+                note: None,
+                command: None,
+                links: Vec::new(),
+                estimate_minutes: None,
+                owner: None,
+                ordered: None,
+                order: None,
+                heading_level: None,
                 children: vec![],
                 properties: HashMap::new(),
+                content_hash: None,
+                children_hash: None,
             }],
+            default_placeholder: None,
+            ancestors: Vec::new(),
+            book_id: None,
+            source_slug: None,
+            subtree_uuid: None,
+            subtree_path: None,
         };
 
         let result = EjectService::import_tree(&tree);
         assert!(result.is_err());
     }
 
+    #[test]
+    fn import_tree_rejects_out_of_range_max_depth() {
+        let tree = EjectTree {
+            title: "Flat".into(),
+            max_depth: 0,
+            nodes: vec![],
+            default_placeholder: None,
+            ancestors: Vec::new(),
+            book_id: None,
+            source_slug: None,
+            subtree_uuid: None,
+            subtree_path: None,
+        };
+        assert!(matches!(
+            EjectService::import_tree(&tree),
+            Err(AppError::Domain(DomainError::InvalidMaxDepth { .. }))
+        ));
+    }
+
+    /// `titles` を親→子の一本道にした部分木（末端は content、他は section）。
+    fn chain(titles: &[&str]) -> EjectTreeNode {
+        let (last, rest) = titles.split_last().unwrap();
+        let mut node = EjectTreeNode {
+            id: "dummy".into(),
+            title: last.to_string(),
+            node_type: "content".into(),
+            body: Some("leaf body".into()),
+            placeholder: None,
+            notes: None,
+            note: None,
+            command: None,
+            links: Vec::new(),
+            estimate_minutes: None,
+            owner: None,
+            ordered: None,
+            order: None,
+            heading_level: None,
+            children: vec![],
+            properties: HashMap::new(),
+            content_hash: None,
+            children_hash: None,
+        };
+        for title in rest.iter().rev() {
+            let template = node.clone();
+            node = EjectTreeNode {
+                title: title.to_string(),
+                node_type: "section".into(),
+                body: None,
+                children: vec![node],
+                ..template
+            };
+        }
+        node
+    }
+
+    #[test]
+    fn depth_error_names_deepest_path_without_touching_book() {
+        let (mut book, design, _) = make_test_book();
+        let before = serde_json::to_string(&EjectService::build_tree(&book, None)).unwrap();
+        let deep = chain(&["Ops", "Deploy", "Rollback"]);
+
+        let err = EjectService::import_subtree_fitted(
+            &mut book,
+            Some(design),
+            &deep,
+            DepthStrategy::Error,
+            DedupeMode::Off,
+        )
+        .unwrap_err();
+        match err {
+            AppError::ImportTooDeep { path, depth, max } => {
+                assert_eq!(path, "Ops > Deploy > Rollback");
+                assert_eq!((depth, max), (4, 3));
+            }
+            other => panic!("unexpected error: {other}"),
+        }
+        let after = serde_json::to_string(&EjectService::build_tree(&book, None)).unwrap();
+        assert_eq!(before, after);
+        assert_eq!(book.max_depth(), 3);
+    }
+
+    #[test]
+    fn depth_flatten_folds_excess_levels_into_parent_body() {
+        let tree = EjectTree {
+            title: "Deep".into(),
+            max_depth: 2,
+            nodes: vec![chain(&["Ops", "Deploy", "Rollback", "Verify"])],
+            default_placeholder: None,
+            ancestors: Vec::new(),
+            book_id: None,
+            source_slug: None,
+            subtree_uuid: None,
+            subtree_path: None,
+        };
+        let (book, fit) = EjectService::import_tree_fitted(&tree, DepthStrategy::Flatten).unwrap();
+
+        assert_eq!(
+            fit,
+            DepthFit {
+                max_depth: 2,
+                folded: 2
+            }
+        );
+        assert_eq!(book.node_count(), 2);
+        assert_eq!(book.actual_depth(), 2);
+        let ops = book.root_nodes()[0];
+        let deploy = book
+            .get_node(book.get_node(ops).unwrap().children()[0])
+            .unwrap();
+        assert_eq!(deploy.body(), Some("- Rollback\n  - Verify\n    leaf body"));
+    }
+
+    #[test]
+    fn depth_raise_lifts_max_depth_up_to_the_ceiling() {
+        let (mut book, design, _) = make_test_book();
+        let deep = chain(&["Ops", "Deploy", "Rollback"]);
+        let SubtreeImport { root, fit, .. } = EjectService::import_subtree_fitted(
+            &mut book,
+            Some(design),
+            &deep,
+            DepthStrategy::Raise,
+            DedupeMode::Off,
+        )
+        .unwrap();
+        assert_eq!(
+            fit,
+            DepthFit {
+                max_depth: 4,
+                folded: 0
+            }
+        );
+        assert_eq!(book.max_depth(), 4);
+        assert_eq!(book.get_node(root).unwrap().parent(), Some(design));
+        assert_eq!(book.actual_depth(), 4);
+
+        let titles: Vec<String> = (0..=TemplateBook::MAX_DEPTH_LIMIT)
+            .map(|i| format!("L{i}"))
+            .collect();
+        let refs: Vec<&str> = titles.iter().map(String::as_str).collect();
+        let tree = EjectTree {
+            title: "Too deep".into(),
+            max_depth: 4,
+            nodes: vec![chain(&refs)],
+            default_placeholder: None,
+            ancestors: Vec::new(),
+            book_id: None,
+            source_slug: None,
+            subtree_uuid: None,
+            subtree_path: None,
+        };
+        assert!(matches!(
+            EjectService::import_tree_fitted(&tree, DepthStrategy::Raise),
+            Err(AppError::ImportTooDeep {
+                depth: 17,
+                max: 16,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn subtree_export_records_its_origin_and_goes_back_in_place() {
+        let (mut book, design, _) = make_test_book();
+        assert!(EjectService::build_tree(&book, None).book_id.is_none());
+
+        let mut tree = EjectService::build_tree(&book, Some(design));
+        assert_eq!(tree.book_id, Some(book.id().to_string()));
+        assert_eq!(tree.subtree_uuid, Some(design.to_string()));
+        assert_eq!(tree.subtree_path.as_deref(), Some("1"));
+        assert_eq!(tree.in_place_target(&book), Some(design));
+        assert_eq!(tree.in_place_target(&TemplateBook::new("Other", 3)), None);
+
+        tree.nodes.remove(0);
+        tree.title = "Design v2".into();
+        EjectService::replace_subtree(&mut book, design, &tree, DepthStrategy::Error).unwrap();
+        let node = book.get_node(design).unwrap();
+        assert_eq!(node.title(), "Design v2");
+        let children: Vec<&str> = node
+            .children()
+            .iter()
+            .map(|id| book.get_node(*id).unwrap().title())
+            .collect();
+        assert_eq!(children, ["API design"]);
+
+        book.remove_node(design).unwrap();
+        assert_eq!(tree.in_place_target(&book), None);
+    }
+
+    #[test]
+    fn merge_import_dedupe_skips_or_updates_matching_siblings() {
+        let (source, _, _) = make_test_book();
+        let mut exported = EjectService::build_tree(&source, None).nodes.remove(0);
+        // "Design" > ["Define requirements", "API design"] に新しい手順を足す
+        let mut extra = exported.children[1].clone();
+        extra.title = "Threat model".into();
+        extra.body = Some("STRIDE".into());
+        exported.children.push(extra);
+        exported.children[1].body = Some("gRPC services".into());
+        exported.title = "  design ".into();
+
+        for (mode, counts, api_body) in [
+            (
+                DedupeMode::Skip,
+                DedupeCounts {
+                    created: 1,
+                    skipped: 3,
+                    updated: 0,
+                },
+                "REST endpoints",
+            ),
+            (
+                DedupeMode::Update,
+                DedupeCounts {
+                    created: 1,
+                    skipped: 0,
+                    updated: 3,
+                },
+                "gRPC services",
+            ),
+        ] {
+            let (mut book, design, _) = make_test_book();
+            let import = EjectService::import_subtree_fitted(
+                &mut book,
+                None,
+                &exported,
+                DepthStrategy::Error,
+                mode,
+            )
+            .unwrap();
+            assert_eq!(import.root, design);
+            assert_eq!(import.dedupe, counts);
+            assert_eq!(book.root_nodes(), [design]);
+            let titles: Vec<(&str, Option<&str>)> = book
+                .all_nodes_dfs()
+                .into_iter()
+                .skip(1)
+                .map(|n| (n.title(), n.body()))
+                .collect();
+            assert_eq!(
+                titles,
+                vec![
+                    ("Define requirements", None),
+                    ("API design", Some(api_body)),
+                    ("Threat model", Some("STRIDE")),
+                ]
+            );
+        }
+
+        // off は従来どおり丸ごと追加する
+        let (mut book, _, _) = make_test_book();
+        let import = EjectService::import_subtree_fitted(
+            &mut book,
+            None,
+            &exported,
+            DepthStrategy::Error,
+            DedupeMode::Off,
+        )
+        .unwrap();
+        assert_eq!(import.dedupe.created, 4);
+        assert_eq!(book.root_nodes().len(), 2);
+    }
+
+    #[test]
+    fn merge_import_resolves_id_conflicts_by_policy() {
+        let (source, _, _) = make_test_book();
+        let mut exported = EjectService::build_tree(&source, None).nodes.remove(0);
+        exported.title = "Design v2".into();
+        exported.children[1].body = Some("gRPC services".into());
+        let mut extra = exported.children[1].clone();
+        extra.id = "not-in-this-book".into();
+        extra.title = "Threat model".into();
+        exported.children.push(extra);
+
+        let merge = |book: &mut TemplateBook, policy| {
+            EjectService::import_subtree_merged(
+                book,
+                None,
+                &exported,
+                DepthStrategy::Error,
+                DedupeMode::Off,
+                policy,
+            )
+        };
+        let outline = |book: &TemplateBook| -> Vec<(String, Option<String>)> {
+            book.all_nodes_dfs()
+                .into_iter()
+                .map(|n| (n.title().to_string(), n.body().map(str::to_string)))
+                .collect()
+        };
+
+        // error: 衝突した ID を列挙し、何も変えない
+        let mut book = source.clone();
+        let before = serde_json::to_string(&EjectService::build_tree(&book, None)).unwrap();
+        match merge(&mut book, IdConflict::Error) {
+            Err(AppError::ImportIdConflict(ids)) => {
+                assert_eq!(ids.matches(", ").count(), 2, "{ids}");
+                assert!(ids.contains("(API design)"), "{ids}");
+            }
+            other => panic!("unexpected result: {other:?}"),
+        }
+        let after = serde_json::to_string(&EjectService::build_tree(&book, None)).unwrap();
+        assert_eq!(before, after);
+
+        // skip: 既存ノードはそのまま、新しい子だけ足す
+        let mut book = source.clone();
+        let import = merge(&mut book, IdConflict::Skip).unwrap();
+        assert_eq!(import.root, source.root_nodes()[0]);
+        assert_eq!(import.id_matches, 3);
+        assert_eq!(
+            outline(&book),
+            [
+                ("Design".to_string(), None),
+                ("Define requirements".to_string(), None),
+                ("API design".to_string(), Some("REST endpoints".to_string())),
+                (
+                    "Threat model".to_string(),
+                    Some("gRPC services".to_string())
+                ),
+            ]
+        );
+
+        // replace: 既存ノードの項目を上書きし、新しい子を足す
+        let mut book = source.clone();
+        let import = merge(&mut book, IdConflict::Replace).unwrap();
+        assert_eq!(import.id_matches, 3);
+        assert_eq!(
+            outline(&book),
+            [
+                ("Design v2".to_string(), None),
+                ("Define requirements".to_string(), None),
+                ("API design".to_string(), Some("gRPC services".to_string())),
+                (
+                    "Threat model".to_string(),
+                    Some("gRPC services".to_string())
+                ),
+            ]
+        );
+        let requirements = book.root_nodes()[0];
+        let requirements = book.get_node(requirements).unwrap().children()[0];
+        assert_eq!(
+            book.get_node(requirements).unwrap().placeholder(),
+            Some("requirements list")
+        );
+
+        // off: ID を見ずにすべて新規作成する
+        let mut book = source.clone();
+        let import = merge(&mut book, IdConflict::Off).unwrap();
+        assert_eq!((import.id_matches, import.dedupe.created), (0, 4));
+        assert_eq!(book.root_nodes().len(), 2);
+    }
+
     #[test]
     fn list_to_checkbox_dash() {
         assert_eq!(
@@ -479,8 +2391,637 @@ mod tests {
         );
     }
 
+    #[test]
+    fn indent_body_line_keeps_relative_nesting() {
+        assert_eq!(
+            EjectService::indent_body_line("    ", "  - sub item", false),
+            "        - [ ] sub item\n"
+        );
+        assert_eq!(
+            EjectService::indent_body_line("  ", "\t- tabbed", false),
+            "      - [ ] tabbed\n"
+        );
+        assert_eq!(EjectService::indent_body_line("    ", "   ", false), "\n");
+        // mark_body_lines のときだけ構造と紛れる行に印を付ける
+        assert_eq!(
+            EjectService::indent_body_line("  ", "\t- tabbed", true),
+            "      - [ ] tabbed <!--body-->\n"
+        );
+        assert_eq!(
+            EjectService::indent_body_line("  ", "plain text", true),
+            "    plain text\n"
+        );
+    }
+
     #[test]
     fn list_to_checkbox_non_list() {
         assert_eq!(EjectService::list_to_checkbox("plain text"), "plain text");
     }
+
+    #[test]
+    fn render_created_after_keeps_new_nodes_and_their_ancestors() {
+        let (mut book, design, req_id) = make_test_book();
+        let api = book.get_node(design).unwrap().children()[1];
+        book.set_node_created_at(design, Some(Timestamp::from_millis(1_000)));
+        book.set_node_created_at(req_id, Some(Timestamp::from_millis(1_000)));
+        book.set_node_created_at(api, Some(Timestamp::from_millis(5_000)));
+
+        let config = EjectConfig {
+            output_dir: std::path::PathBuf::from("."),
+            filename: "unused.md".into(),
+            include_placeholders: true,
+            format: EjectFormat::Markdown,
+            subtree_root: None,
+            created_after: Some(Timestamp::from_millis(2_000)),
+            include_notes: false,
+            include_estimates: false,
+            owner: None,
+            include_ancestors: false,
+            style: MarkdownStyle::Checkbox,
+            wrap_width: None,
+            source_slug: None,
+            labels: CheckboxLabels::default(),
+            section_separators: false,
+            mark_incomplete: false,
+            mark_body_lines: false,
+            footer: false,
+        };
+        let md = EjectService::render(&book, &config).unwrap();
+
+        assert!(md.contains("## Design"));
+        assert!(md.contains("- [ ] API design"));
+        assert!(!md.contains("Define requirements"));
+        // 元の Book は変更されない
+        assert_eq!(book.node_count(), 3);
+    }
+
+    #[test]
+    fn render_combined_markdown_joins_books_with_separators() {
+        let (first, _, _) = make_test_book();
+        let second = TemplateBook::new("Ops Handbook", 3);
+
+        let md = EjectService::render_combined_markdown(&[first, second], true);
+        assert!(md.starts_with("# Dev Runbook\n"));
+        assert!(md.contains("- [ ] API design"));
+        assert!(md.contains("\n\n---\n\n# Ops Handbook"));
+        assert_eq!(md.matches("\n---\n").count(), 1);
+    }
+
+    #[test]
+    fn render_combined_json_is_array_of_trees() {
+        let (first, _, _) = make_test_book();
+        let second = TemplateBook::new("Ops Handbook", 3);
+
+        let json = EjectService::render_combined_json(&[first, second]).unwrap();
+        let trees: Vec<EjectTree> = serde_json::from_str(&json).unwrap();
+        assert_eq!(trees.len(), 2);
+        assert_eq!(trees[0].title, "Dev Runbook");
+        assert_eq!(trees[1].title, "Ops Handbook");
+    }
+
+    #[test]
+    fn default_placeholder_fills_content_without_own_hint() {
+        let (mut book, _, _) = make_test_book();
+        book.set_default_placeholder(Some("notes/observations".into()));
+
+        let md = EjectService::render_markdown(&book, true, None);
+        // 自前の placeholder はそのまま
+        assert!(md.contains("> requirements list: ___"));
+        // placeholder なしの Content は Book 既定
+        assert!(md.contains("> notes/observations: ___"));
+        // Section には適用しない
+        assert_eq!(md.matches("notes/observations").count(), 1);
+
+        let md = EjectService::render_markdown(&book, false, None);
+        assert!(!md.contains("notes/observations"));
+    }
+
+    #[test]
+    fn render_stub_fills_placeholder_and_keeps_body() {
+        let (mut book, design, req_id) = make_test_book();
+        let req = book.get_node(req_id).unwrap();
+        assert_eq!(
+            EjectService::render_stub(&book, req),
+            "# Define requirements\n\nrequirements list: ____\n"
+        );
+
+        let api_id = book.get_node(design).unwrap().children()[1];
+        let api = book.get_node(api_id).unwrap();
+        assert_eq!(
+            EjectService::render_stub(&book, api),
+            "# API design\n\nREST endpoints\n"
+        );
+
+        // Book 既定の placeholder も使う
+        book.set_default_placeholder(Some("notes".into()));
+        let api = book.get_node(api_id).unwrap();
+        assert_eq!(
+            EjectService::render_stub(&book, api),
+            "# API design\n\nREST endpoints\n\nnotes: ____\n"
+        );
+    }
+
+    #[test]
+    fn default_placeholder_roundtrips_through_json() {
+        let (mut book, _, _) = make_test_book();
+        book.set_default_placeholder(Some("notes".into()));
+        let json = EjectService::render_json(&book, None).unwrap();
+        let tree: EjectTree = serde_json::from_str(&json).unwrap();
+        let imported = EjectService::import_tree(&tree).unwrap();
+        assert_eq!(imported.default_placeholder(), Some("notes"));
+
+        // 旧エクスポート（フィールドなし）も読める
+        let legacy: EjectTree =
+            serde_json::from_str(r#"{"title": "Old", "max_depth": 3, "nodes": []}"#).unwrap();
+        assert!(legacy.default_placeholder.is_none());
+        assert!(legacy.ancestors.is_empty());
+    }
+
+    fn set_notes(book: &mut TemplateBook, id: NodeId, notes: &str) {
+        book.update_node(
+            id,
+            UpdateNodeRequest {
+                notes: Some(Some(notes.into())),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn notes_hidden_from_markdown_by_default() {
+        let (mut book, _, req_id) = make_test_book();
+        set_notes(&mut book, req_id, "see INC-42");
+
+        let md = EjectService::render_markdown(&book, true, None);
+        assert!(!md.contains("INC-42"));
+    }
+
+    #[test]
+    fn notes_rendered_as_indented_italics_when_opted_in() {
+        let (mut book, _, req_id) = make_test_book();
+        set_notes(&mut book, req_id, "why: audit finding\nsee INC-42");
+
+        let options = MarkdownOptions {
+            include_notes: true,
+            ..MarkdownOptions::default()
+        };
+        let md = EjectService::render_markdown_with(&book, &options, None);
+        assert!(md
+            .contains("  - [ ] Define requirements\n    _why: audit finding_\n    _see INC-42_\n"));
+    }
+
+    fn set_command(book: &mut TemplateBook, id: NodeId, command: &str) {
+        book.update_node(
+            id,
+            UpdateNodeRequest {
+                command: Some(Some(command.into())),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn command_rendered_as_sh_fence_and_extracted_in_outline_order() {
+        let (mut book, design, req_id) = make_test_book();
+        let api = book.get_node(design).unwrap().children()[1];
+        set_command(&mut book, api, "curl -s localhost/health");
+        set_command(&mut book, req_id, "make reqs\nmake lint");
+
+        let md = EjectService::render_markdown(&book, false, None);
+        assert!(
+            md.contains("- [ ] API design\n    REST endpoints\n    ```sh\n    curl -s localhost/health\n    ```\n"),
+            "{md}"
+        );
+
+        let script = EjectService::render_commands(&book, None).unwrap();
+        assert_eq!(
+            script,
+            "#!/bin/sh\n# Dev Runbook\n\n# 1-1 Define requirements\nmake reqs\nmake lint\n\n# 1-2 API design\ncurl -s localhost/health\n"
+        );
+        let script = EjectService::render_commands(&book, Some(api)).unwrap();
+        assert_eq!(
+            script,
+            "#!/bin/sh\n# Dev Runbook — 1-2 API design\n\n# 1-2 API design\ncurl -s localhost/health\n"
+        );
+
+        let tree = EjectService::build_tree(&book, None);
+        assert_eq!(
+            tree.nodes[0].children[1].command.as_deref(),
+            Some("curl -s localhost/health")
+        );
+        let imported = EjectService::import_tree(&tree).unwrap();
+        let root = imported.get_node(imported.root_nodes()[0]).unwrap();
+        let api = imported.get_node(root.children()[1]).unwrap();
+        assert_eq!(api.command(), Some("curl -s localhost/health"));
+    }
+
+    #[test]
+    fn links_listed_under_the_item_and_kept_by_json_export() {
+        let (mut book, design, _) = make_test_book();
+        let api = book.get_node(design).unwrap().children()[1];
+        book.add_link(api, Some("Spec [v2]".into()), "https://wiki.example/api")
+            .unwrap();
+
+        let md = EjectService::render_markdown(&book, false, None);
+        assert!(
+            md.contains("- [ ] API design\n    REST endpoints\n    - [Spec \\[v2\\]](https://wiki.example/api)\n"),
+            "{md}"
+        );
+
+        let tree = EjectService::build_tree(&book, None);
+        let imported = EjectService::import_tree(&tree).unwrap();
+        let root = imported.get_node(imported.root_nodes()[0]).unwrap();
+        let api = imported.get_node(root.children()[1]).unwrap();
+        assert_eq!(
+            api.links(),
+            &[(
+                "Spec [v2]".to_string(),
+                "https://wiki.example/api".to_string()
+            )]
+        );
+    }
+
+    #[test]
+    fn command_fence_outgrows_backticks_and_empty_script_says_so() {
+        assert_eq!(
+            EjectService::command_block("", "echo ```"),
+            "````sh\necho ```\n````\n"
+        );
+        let (book, _, _) = make_test_book();
+        assert!(EjectService::render_commands(&book, None)
+            .unwrap()
+            .ends_with("# (no commands)\n"));
+    }
+
+    #[test]
+    fn note_rendered_as_blockquote_and_never_as_checkboxes() {
+        let (mut book, _, req_id) = make_test_book();
+        book.update_node(
+            req_id,
+            UpdateNodeRequest {
+                note: Some(Some("- staging only\n\nask #ops first".into())),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        let md = EjectService::render_markdown(&book, false, None);
+        assert!(md.contains(
+            "  - [ ] Define requirements\n    > - staging only\n    >\n    > ask #ops first\n"
+        ));
+
+        let tree = EjectTree::from_markdown(&md).unwrap();
+        assert_eq!(
+            tree.nodes[0].children[0].note.as_deref(),
+            Some("- staging only\n\nask #ops first")
+        );
+    }
+
+    #[test]
+    fn notes_roundtrip_through_json() {
+        let (mut book, _, req_id) = make_test_book();
+        set_notes(&mut book, req_id, "see INC-42");
+
+        let json = EjectService::render_json(&book, None).unwrap();
+        let tree: EjectTree = serde_json::from_str(&json).unwrap();
+        assert_eq!(
+            tree.nodes[0].children[0].notes.as_deref(),
+            Some("see INC-42")
+        );
+
+        let imported = EjectService::import_tree(&tree).unwrap();
+        let node = imported
+            .all_nodes_dfs()
+            .into_iter()
+            .find(|n| n.title() == "Define requirements")
+            .unwrap();
+        assert_eq!(node.notes(), Some("see INC-42"));
+        assert!(imported
+            .all_nodes_dfs()
+            .iter()
+            .filter(|n| n.id() != node.id())
+            .all(|n| n.notes().is_none()));
+    }
+
+    #[test]
+    fn blocked_items_name_their_dependencies() {
+        let (mut book, design, req_id) = make_test_book();
+        let api = book.get_node(design).unwrap().children()[1];
+        book.add_dependency(api, req_id).unwrap();
+
+        let md = EjectService::render_markdown(&book, false, None);
+        assert!(md.contains("- [ ] API design (blocked by 1-1)\n"));
+        assert!(md.contains("- [ ] Define requirements\n"));
+    }
+
+    #[test]
+    fn estimates_appended_only_when_requested() {
+        let (mut book, _, req_id) = make_test_book();
+        book.update_node(
+            req_id,
+            UpdateNodeRequest {
+                estimate_minutes: Some(Some(30)),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        let md = EjectService::render_markdown(&book, false, None);
+        assert!(!md.contains("(~30m)"));
+
+        let options = MarkdownOptions {
+            include_estimates: true,
+            ..MarkdownOptions::default()
+        };
+        let md = EjectService::render_markdown_with(&book, &options, None);
+        assert!(md.contains("- [ ] Define requirements (~30m)\n"));
+    }
+
+    #[test]
+    fn done_items_render_checked() {
+        let (mut book, _, req_id) = make_test_book();
+        book.update_node(
+            req_id,
+            UpdateNodeRequest {
+                done: Some(true),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        let md = EjectService::render_markdown(&book, false, None);
+        assert!(md.contains("- [x] Define requirements\n"));
+        assert!(md.contains("- [ ] API design"));
+    }
+
+    #[test]
+    fn unordered_flag_survives_json_roundtrip() {
+        let (mut book, design_id, _) = make_test_book();
+        book.update_node(
+            design_id,
+            UpdateNodeRequest {
+                ordered: Some(false),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        let tree = EjectService::build_tree(&book, None);
+        assert_eq!(tree.nodes[0].ordered, Some(false));
+        assert_eq!(tree.nodes[0].children[0].ordered, None);
+        let imported = EjectService::import_tree(&tree).unwrap();
+        let design = imported.get_node(imported.root_nodes()[0]).unwrap();
+        assert!(!design.is_ordered());
+    }
+
+    #[test]
+    fn heading_level_overrides_depth_and_shifts_descendant_sections() {
+        let (mut book, design, _) = make_test_book();
+        let section = |parent, title: &str| AddNodeRequest {
+            parent,
+            title: title.into(),
+            node_type: NodeType::Section,
+            body: None,
+            placeholder: None,
+            position: Position::Last,
+            properties: HashMap::new(),
+        };
+        let apis = book.add_node(section(Some(design), "APIs")).unwrap();
+        book.add_node(section(Some(apis), "Auth")).unwrap();
+        book.add_node(section(None, "Ops")).unwrap();
+        book.update_node(
+            design,
+            UpdateNodeRequest {
+                heading_level: Some(Some(1)),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        let md = EjectService::render_markdown(&book, false, None);
+        let headings: Vec<&str> = md.lines().filter(|l| l.starts_with('#')).collect();
+        assert_eq!(
+            headings,
+            ["# Dev Runbook", "# Design", "## APIs", "### Auth", "## Ops"]
+        );
+
+        let tree = EjectService::build_tree(&book, None);
+        assert_eq!(tree.nodes[0].heading_level, Some(1));
+        assert_eq!(tree.nodes[0].children[2].heading_level, None);
+        let imported = EjectService::import_tree(&tree).unwrap();
+        let design = imported.get_node(imported.root_nodes()[0]).unwrap();
+        assert_eq!(design.heading_level(), Some(1));
+    }
+
+    #[test]
+    fn order_keys_survive_json_roundtrip() {
+        let (mut book, design_id, _) = make_test_book();
+        let keyed = |order| UpdateNodeRequest {
+            order: Some(Some(order)),
+            ..Default::default()
+        };
+        let last = book
+            .add_node(AddNodeRequest {
+                parent: None,
+                title: "Appendix".into(),
+                node_type: NodeType::Section,
+                body: None,
+                placeholder: None,
+                position: Position::Last,
+                properties: HashMap::new(),
+            })
+            .unwrap();
+        book.update_node(last, keyed(5.0)).unwrap();
+        book.update_node(design_id, keyed(7.5)).unwrap();
+
+        let tree = EjectService::build_tree(&book, None);
+        assert_eq!(tree.nodes[0].order, Some(5.0));
+        let imported = EjectService::import_tree(&tree).unwrap();
+        let orders: Vec<_> = imported
+            .root_nodes()
+            .iter()
+            .map(|id| imported.get_node(*id).unwrap().order())
+            .collect();
+        assert_eq!(orders, [Some(5.0), Some(7.5)]);
+    }
+
+    #[test]
+    fn include_ancestors_adds_breadcrumb_for_nested_subtree() {
+        let (mut book, design, _) = make_test_book();
+        let deploy = book
+            .add_node(AddNodeRequest {
+                parent: Some(design),
+                title: "Deployment".into(),
+                node_type: NodeType::Section,
+                body: None,
+                placeholder: None,
+                position: Position::Last,
+                properties: HashMap::new(),
+            })
+            .unwrap();
+        let mut config = EjectConfig {
+            output_dir: std::path::PathBuf::from("."),
+            filename: "out.md".into(),
+            include_placeholders: false,
+            format: EjectFormat::Markdown,
+            subtree_root: Some(deploy),
+            created_after: None,
+            include_notes: false,
+            include_estimates: false,
+            owner: None,
+            include_ancestors: true,
+            style: MarkdownStyle::Checkbox,
+            wrap_width: None,
+            source_slug: None,
+            labels: CheckboxLabels::default(),
+            section_separators: false,
+            mark_incomplete: false,
+            mark_body_lines: false,
+            footer: false,
+        };
+        let md = EjectService::render(&book, &config).unwrap();
+        assert!(md.starts_with("Context: Design → Deployment\n\n# Deployment\n"));
+
+        // ルート直下の部分木には付けない
+        config.subtree_root = Some(design);
+        let md = EjectService::render(&book, &config).unwrap();
+        assert!(md.starts_with("# Design\n"));
+
+        config.subtree_root = Some(deploy);
+        config.format = EjectFormat::Json;
+        let tree: EjectTree =
+            serde_json::from_str(&EjectService::render(&book, &config).unwrap()).unwrap();
+        assert_eq!(tree.ancestors, vec!["Design".to_string()]);
+
+        config.include_ancestors = false;
+        let json = EjectService::render(&book, &config).unwrap();
+        assert!(!json.contains("\"ancestors\""));
+    }
+
+    #[test]
+    fn footer_records_source_book_outside_the_tree() {
+        let (book, design, _) = make_test_book();
+        let footer = EjectService::footer(&book, None, Timestamp::from_millis(0));
+        assert_eq!(
+            footer,
+            format!(
+                "Exported from \"Dev Runbook\" (book {}): {} node(s), generated 1970-01-01T00:00:00.000Z",
+                book.id(),
+                book.node_count()
+            )
+        );
+        let sub = EjectService::footer(&book, Some(design), Timestamp::from_millis(0));
+        assert!(
+            sub.contains(&format!(": {} node(s)", book.descendant_count(design))),
+            "{sub}"
+        );
+
+        let mut config = EjectConfig {
+            output_dir: std::path::PathBuf::from("."),
+            filename: "out.md".into(),
+            include_placeholders: false,
+            format: EjectFormat::Markdown,
+            subtree_root: None,
+            created_after: None,
+            include_notes: false,
+            include_estimates: false,
+            owner: None,
+            include_ancestors: false,
+            style: MarkdownStyle::Checkbox,
+            wrap_width: None,
+            source_slug: None,
+            labels: CheckboxLabels::default(),
+            section_separators: false,
+            mark_incomplete: false,
+            mark_body_lines: false,
+            footer: true,
+        };
+        let md = EjectService::render(&book, &config).unwrap();
+        let last = md.trim_end().lines().last().unwrap();
+        assert!(
+            last.starts_with("<!-- Exported from \"Dev Runbook\" (book "),
+            "{md}"
+        );
+        assert!(last.ends_with(" -->"), "{last}");
+        // 読み戻してもフッターはノードにならない
+        let reimported = EjectTree::from_markdown(&md).unwrap();
+        assert_eq!(reimported.nodes.len(), book.root_nodes().len());
+
+        config.format = EjectFormat::Json;
+        let json = EjectService::render(&book, &config).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert!(value["_comment"]
+            .as_str()
+            .unwrap()
+            .contains(&book.id().to_string()));
+        let tree = EjectTree::from_json(&json, true).unwrap();
+        assert_eq!(tree.nodes.len(), book.root_nodes().len());
+
+        config.footer = false;
+        assert!(!EjectService::render(&book, &config)
+            .unwrap()
+            .contains("_comment"));
+
+        // タイトル中の `-->` でコメントが閉じない
+        let tricky = TemplateBook::new("Ops --> Dev", 3);
+        config.format = EjectFormat::Markdown;
+        config.footer = true;
+        let md = EjectService::render(&tricky, &config).unwrap();
+        let last = md.trim_end().lines().last().unwrap();
+        assert!(
+            last.starts_with("<!-- Exported from \"Ops - -> Dev\""),
+            "{md}"
+        );
+        assert_eq!(last.matches("-->").count(), 1, "{last}");
+    }
+
+    #[test]
+    fn owner_filter_keeps_owned_items_and_ancestors() {
+        let (mut book, _, req_id) = make_test_book();
+        book.update_node(
+            req_id,
+            UpdateNodeRequest {
+                owner: Some(Some("alice".into())),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        let config = EjectConfig {
+            output_dir: std::path::PathBuf::from("."),
+            filename: "out.md".into(),
+            include_placeholders: false,
+            format: EjectFormat::Markdown,
+            subtree_root: None,
+            created_after: None,
+            include_notes: false,
+            include_estimates: false,
+            owner: Some("alice".into()),
+            include_ancestors: false,
+            style: MarkdownStyle::Checkbox,
+            wrap_width: None,
+            source_slug: None,
+            labels: CheckboxLabels::default(),
+            section_separators: false,
+            mark_incomplete: false,
+            mark_body_lines: false,
+            footer: false,
+        };
+        let md = EjectService::render(&book, &config).unwrap();
+        assert!(md.contains("## Design"));
+        assert!(md.contains("- [ ] Define requirements"));
+        assert!(!md.contains("API design"));
+
+        let json = EjectService::render_json(&book, None).unwrap();
+        let tree: EjectTree = serde_json::from_str(&json).unwrap();
+        let imported = EjectService::import_tree(&tree).unwrap();
+        let owners: Vec<_> = imported
+            .all_nodes_dfs()
+            .iter()
+            .filter_map(|n| n.owner().map(str::to_string))
+            .collect();
+        assert_eq!(owners, vec!["alice".to_string()]);
+    }
 }
@@ -11,6 +11,10 @@ pub enum AppError {
     #[error("book not found: initialize first")]
     BookNotFound,
 
+    /// `create_book` found a book already stored under this name.
+    #[error("book already exists")]
+    BookAlreadyExists,
+
     /// The underlying `BookRepository` failed to load or save.
     #[error("storage error: {0}")]
     Storage(#[source] Box<dyn std::error::Error + Send + Sync>),
@@ -23,6 +27,30 @@ pub enum AppError {
     #[error("import: invalid node type: {0}")]
     ImportInvalidType(String),
 
+    /// An imported Markdown checklist could not be read.
+    #[error("import: Markdown line {line}: {reason}")]
+    ImportMarkdown {
+        /// 1-based line number.
+        line: usize,
+        /// What was wrong there.
+        reason: String,
+    },
+
+    /// An imported tree is deeper than the destination allows.
+    #[error("import: '{path}' would reach depth {depth} but max_depth is {max} (depth_strategy 'flatten' folds deeper nodes into their parent's body, 'raise' lifts max_depth up to 16)")]
+    ImportTooDeep {
+        /// Titles along the deepest offending branch, joined with " > ".
+        path: String,
+        /// Depth that branch would reach in the destination.
+        depth: u8,
+        /// The destination's max depth (or the absolute ceiling for `raise`).
+        max: u8,
+    },
+
+    /// Merged nodes reuse IDs already in the book (`IdConflict::Error`).
+    #[error("import: incoming nodes reuse IDs already in the book: {0} (on_id_conflict 'skip' keeps those nodes, 'replace' overwrites them)")]
+    ImportIdConflict(String),
+
     /// A snapshot operation failed (not found / I/O / serde).
     #[error("snapshot error: {0}")]
     Snapshot(String),
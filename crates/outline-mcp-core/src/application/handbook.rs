@@ -0,0 +1,123 @@
+//! 棚の全 Book を1つの Markdown 文書（ハンドブック）にまとめる。
+//!
+//! 本体は [`EjectService::render_combined_markdown_with`] の結合で、各 Book が
+//! 文書タイトル（`#`）の下の `##` になるよう見出しを1段ずつ下げ、先頭に目次を置く。
+//! 目次のリンク先は GitHub と同じ規則で作る見出しのアンカー（同じ見出しが続けば
+//! `-1`, `-2` を付ける）。
+
+use std::collections::HashMap;
+
+use crate::domain::model::book::TemplateBook;
+
+use super::eject::{EjectService, MarkdownOptions};
+
+impl EjectService {
+    /// `books` を目次付きの1つの Markdown 文書にする。目次には Book と
+    /// そのトップレベルの Section を載せる。
+    pub fn render_handbook(
+        books: &[TemplateBook],
+        title: &str,
+        include_placeholders: bool,
+    ) -> String {
+        let options = MarkdownOptions {
+            include_placeholders,
+            heading_offset: 1,
+            ..MarkdownOptions::default()
+        };
+        let mut out = format!("# {title}\n\n## Contents\n\n");
+        if books.is_empty() {
+            return out;
+        }
+        let body = Self::render_combined_markdown_with(books, &options);
+
+        let mut anchors = Anchors::default();
+        anchors.next(title);
+        anchors.next("Contents");
+        for (level, text) in headings(&body) {
+            let anchor = anchors.next(text);
+            let indent = match level {
+                2 => "",
+                3 => "  ",
+                _ => continue,
+            };
+            out.push_str(&format!("{indent}- [{text}](#{anchor})\n"));
+        }
+        out.push_str("\n---\n\n");
+        out.push_str(&body);
+        out
+    }
+}
+
+/// コードフェンスの外にある ATX 見出しの (レベル, テキスト)。
+fn headings(md: &str) -> Vec<(usize, &str)> {
+    let mut in_fence = false;
+    let mut found = Vec::new();
+    for line in md.lines() {
+        if line.trim_start().starts_with("```") {
+            in_fence = !in_fence;
+            continue;
+        }
+        if in_fence {
+            continue;
+        }
+        let level = line.chars().take_while(|c| *c == '#').count();
+        if (1..=6).contains(&level) && line[level..].starts_with(' ') {
+            found.push((level, line[level..].trim()));
+        }
+    }
+    found
+}
+
+/// 文書内で一意な見出しアンカーを順に払い出す。
+#[derive(Default)]
+struct Anchors {
+    seen: HashMap<String, usize>,
+}
+
+impl Anchors {
+    fn next(&mut self, heading: &str) -> String {
+        let base = slug(heading);
+        let count = self.seen.entry(base.clone()).or_default();
+        let anchor = match *count {
+            0 => base,
+            n => format!("{base}-{n}"),
+        };
+        *count += 1;
+        anchor
+    }
+}
+
+/// GitHub の見出しアンカー: 小文字にし、英数字・`-`・`_` 以外を落とし、空白を `-` にする。
+fn slug(heading: &str) -> String {
+    heading
+        .trim()
+        .to_lowercase()
+        .chars()
+        .filter_map(|c| match c {
+            ' ' => Some('-'),
+            c if c.is_alphanumeric() || c == '-' || c == '_' => Some(c),
+            _ => None,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn slugs_follow_github_rules_and_repeat_with_suffixes() {
+        assert_eq!(slug("Deploy & Rollback"), "deploy--rollback");
+        assert_eq!(slug("手順 2"), "手順-2");
+        let mut anchors = Anchors::default();
+        assert_eq!(anchors.next("Setup"), "setup");
+        assert_eq!(anchors.next("Setup"), "setup-1");
+        assert_eq!(anchors.next("setup"), "setup-2");
+    }
+
+    #[test]
+    fn headings_skip_code_fences() {
+        let md = "## Book\n\n```\n# not a heading\n```\n### Section\n#tag\n";
+        assert_eq!(headings(md), [(2, "Book"), (3, "Section")]);
+    }
+}
@@ -0,0 +1,323 @@
+//! 記入用フォームとしての HTML 出力（[`EjectFormat::Html`]）。
+//!
+//! 外部のファイルを参照しない1枚の HTML を出す。Content はチェックボックス、
+//! placeholder は記入欄（長い記述を求める hint なら `<textarea>`）になり、
+//! 埋め込みのスクリプトで記入した値ごと保存・印刷できる。フィールド名は
+//! アウトライン番号（`item-2-1` / `field-2-1`）から作るので、出力は実行ごとに変わらない。
+//!
+//! [`EjectFormat::Html`]: super::eject::EjectFormat::Html
+
+use crate::domain::model::book::TemplateBook;
+use crate::domain::model::id::NodeId;
+use crate::domain::model::node::{NodeType, TemplateNode};
+
+use super::eject::{EjectService, MarkdownOptions};
+
+const STYLE: &str = "body{font-family:system-ui,sans-serif;max-width:48rem;margin:2rem auto;padding:0 1rem;line-height:1.5}\
+ul{list-style:none;padding-left:1.25rem}\
+.body{white-space:pre-wrap;margin:.25rem 0 .25rem 1.5rem}\
+.command{margin:.25rem 0 .25rem 1.5rem;padding:.5rem;background:#f4f4f4;overflow-x:auto}\
+.links{list-style:disc;margin:.25rem 0 .25rem 1.5rem}\
+.note{margin:.25rem 0 .25rem 1.5rem;padding-left:.75rem;border-left:3px solid #999;color:#444}\
+.notes{margin:.25rem 0 .25rem 1.5rem;font-style:italic;color:#666}\
+.field{display:block;margin:.25rem 0 .5rem 1.5rem}\
+.field input,.field textarea{display:block;width:100%;box-sizing:border-box}\
+.context{color:#666}\
+@media print{.no-print{display:none}}";
+
+// 保存前に入力中の値を属性へ書き戻し、`outerHTML` に値が残るようにする
+const SCRIPT: &str = "function outlineSync(){document.querySelectorAll('input,textarea').forEach(function(el){\
+if(el.type==='checkbox'){if(el.checked){el.setAttribute('checked','')}else{el.removeAttribute('checked')}}\
+else if(el.tagName==='TEXTAREA'){el.textContent=el.value}else{el.setAttribute('value',el.value)}})}\
+function outlineSave(){outlineSync();\
+var blob=new Blob(['<!DOCTYPE html>\\n'+document.documentElement.outerHTML],{type:'text/html'});\
+var a=document.createElement('a');a.href=URL.createObjectURL(blob);\
+a.download=document.title.replace(/[^A-Za-z0-9._-]+/g,'_')+'.html';a.click();URL.revokeObjectURL(a.href)}\
+window.addEventListener('beforeprint',outlineSync);";
+
+/// 長い記述を求める hint とみなす語（小文字で比較）。
+const LONG_ANSWER_HINTS: [&str; 8] = [
+    "notes",
+    "description",
+    "details",
+    "summary",
+    "explain",
+    "comments",
+    "list",
+    "steps",
+];
+
+impl EjectService {
+    /// Bookの内容を記入用の HTML フォームに変換する。
+    ///
+    /// `options` のうち `include_placeholders` / `include_notes` /
+    /// `include_estimates` / `include_ancestors` に従う（`style` と
+    /// `wrap_width` は Markdown 専用）。
+    pub fn render_html(
+        book: &TemplateBook,
+        options: &MarkdownOptions,
+        subtree_root: Option<NodeId>,
+    ) -> String {
+        let (title, children, context) = match subtree_root.and_then(|id| book.get_node(id)) {
+            Some(root) => {
+                let ancestors: Vec<&str> = book
+                    .ancestors(root.id())
+                    .iter()
+                    .map(|n| n.title())
+                    .collect();
+                let context = (options.include_ancestors && !ancestors.is_empty())
+                    .then(|| format!("{} → {}", ancestors.join(" → "), root.title()));
+                (root.title(), root.children(), context)
+            }
+            None => (book.title(), book.root_nodes(), None),
+        };
+        let mut body = String::new();
+        if let Some(context) = context {
+            body.push_str(&format!(
+                "<p class=\"context\">Context: {}</p>\n",
+                escape(&context)
+            ));
+        }
+        Self::render_html_children(book, children, 0, options, &mut body);
+        Self::html_document(title, &body)
+    }
+
+    /// 複数Bookを1つの HTML フォームにまとめる（Bookごとに `<h1>` と区切り線）。
+    pub fn render_combined_html(books: &[TemplateBook], include_placeholders: bool) -> String {
+        let options = MarkdownOptions {
+            include_placeholders,
+            ..MarkdownOptions::default()
+        };
+        let mut body = String::new();
+        for (i, book) in books.iter().enumerate() {
+            if i > 0 {
+                body.push_str("<hr>\n");
+            }
+            body.push_str(&format!("<h1>{}</h1>\n", escape(book.title())));
+            Self::render_html_children(book, book.root_nodes(), 0, &options, &mut body);
+        }
+        Self::html_document("Shelf", &body)
+    }
+
+    fn html_document(title: &str, body: &str) -> String {
+        format!(
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{title}</title>\n\
+             <style>{STYLE}</style>\n</head>\n<body>\n\
+             <nav class=\"no-print\"><button type=\"button\" onclick=\"outlineSave()\">Save filled copy</button> \
+             <button type=\"button\" onclick=\"window.print()\">Print</button></nav>\n\
+             <form>\n<h1>{title}</h1>\n{body}</form>\n<script>{SCRIPT}</script>\n</body>\n</html>\n",
+            title = escape(title),
+        )
+    }
+
+    /// 兄弟を順に描く。続く Content は1つの `<ul>` にまとめ、Section の前で閉じる。
+    fn render_html_children(
+        book: &TemplateBook,
+        children: &[NodeId],
+        depth: usize,
+        options: &MarkdownOptions,
+        buf: &mut String,
+    ) {
+        let mut list_open = false;
+        for node in children.iter().filter_map(|id| book.get_node(*id)) {
+            match node.node_type() {
+                NodeType::Section => {
+                    if list_open {
+                        buf.push_str("</ul>\n");
+                        list_open = false;
+                    }
+                    let level = (depth + 2).min(6);
+                    buf.push_str(&format!(
+                        "<section>\n<h{level}>{}</h{level}>\n",
+                        escape(&Self::html_title(book, node, options))
+                    ));
+                    Self::render_html_details(book, node, options, buf);
+                    Self::render_html_children(book, node.children(), depth + 1, options, buf);
+                    buf.push_str("</section>\n");
+                }
+                NodeType::Content => {
+                    if !list_open {
+                        buf.push_str("<ul>\n");
+                        list_open = true;
+                    }
+                    Self::render_html_item(book, node, depth, options, buf);
+                }
+            }
+        }
+        if list_open {
+            buf.push_str("</ul>\n");
+        }
+    }
+
+    fn render_html_item(
+        book: &TemplateBook,
+        node: &TemplateNode,
+        depth: usize,
+        options: &MarkdownOptions,
+        buf: &mut String,
+    ) {
+        let title = escape(&Self::html_title(book, node, options));
+        // 順不同の Section の子は手順ではないのでチェックボックスを付けない（Markdown と同じ）
+        let unordered = node
+            .parent()
+            .and_then(|p| book.get_node(p))
+            .is_some_and(|p| !p.is_ordered());
+        if unordered {
+            buf.push_str(&format!("<li>{title}\n"));
+        } else {
+            let checked = if node.is_done() { " checked" } else { "" };
+            buf.push_str(&format!(
+                "<li><label><input type=\"checkbox\" name=\"item-{}\"{checked}> {title}</label>\n",
+                field_key(book, node)
+            ));
+        }
+        Self::render_html_details(book, node, options, buf);
+        if !node.is_leaf() {
+            Self::render_html_children(book, node.children(), depth + 1, options, buf);
+        }
+        buf.push_str("</li>\n");
+    }
+
+    /// 見出し・項目の下に付く本文・note・記入欄・notes。
+    fn render_html_details(
+        book: &TemplateBook,
+        node: &TemplateNode,
+        options: &MarkdownOptions,
+        buf: &mut String,
+    ) {
+        if let Some(body) = node.body().map(str::trim_end).filter(|b| !b.is_empty()) {
+            buf.push_str(&format!("<div class=\"body\">{}</div>\n", escape(body)));
+        }
+        if let Some(command) = node.command().map(str::trim_end).filter(|c| !c.is_empty()) {
+            buf.push_str(&format!(
+                "<pre class=\"command\"><code>{}</code></pre>\n",
+                escape(command)
+            ));
+        }
+        if !node.links().is_empty() {
+            buf.push_str("<ul class=\"links\">\n");
+            for (label, url) in node.links() {
+                buf.push_str(&format!(
+                    "<li><a href=\"{}\">{}</a></li>\n",
+                    escape(url),
+                    escape(label)
+                ));
+            }
+            buf.push_str("</ul>\n");
+        }
+        if let Some(note) = node.note().map(str::trim_end).filter(|n| !n.is_empty()) {
+            let lines: Vec<String> = note.lines().map(escape).collect();
+            buf.push_str(&format!(
+                "<blockquote class=\"note\">{}</blockquote>\n",
+                lines.join("<br>")
+            ));
+        }
+        if options.include_placeholders {
+            if let Some(hint) = Self::effective_placeholder(book, node) {
+                let name = format!("field-{}", field_key(book, node));
+                let input = if wants_textarea(hint) {
+                    format!("<textarea name=\"{name}\" rows=\"4\"></textarea>")
+                } else {
+                    format!("<input type=\"text\" name=\"{name}\">")
+                };
+                buf.push_str(&format!(
+                    "<label class=\"field\">{}: {input}</label>\n",
+                    escape(hint)
+                ));
+            }
+        }
+        if options.include_notes {
+            if let Some(notes) = node.notes() {
+                let lines: Vec<String> = notes
+                    .lines()
+                    .filter(|l| !l.trim().is_empty())
+                    .map(|l| escape(l.trim()))
+                    .collect();
+                buf.push_str(&format!("<p class=\"notes\">{}</p>\n", lines.join("<br>")));
+            }
+        }
+    }
+
+    /// タイトルに `(blocked by …)` と（指定時は）見積もりを付けたもの。
+    fn html_title(book: &TemplateBook, node: &TemplateNode, options: &MarkdownOptions) -> String {
+        let mut title = format!("{}{}", node.title(), Self::blocked_suffix(book, node));
+        if options.include_estimates {
+            if let Some(m) = node.estimate_minutes() {
+                title.push_str(&format!(" (~{m}m)"));
+            }
+        }
+        title
+    }
+}
+
+/// フィールド名に使うアウトライン番号（`2-1`）。
+fn field_key(book: &TemplateBook, node: &TemplateNode) -> String {
+    book.outline_number(node.id())
+        .unwrap_or_else(|| node.id().short())
+}
+
+/// hint が長い記述を求めているか（`<textarea>` にするか）。
+fn wants_textarea(hint: &str) -> bool {
+    let lower = hint.to_lowercase();
+    hint.chars().count() > 40 || LONG_ANSWER_HINTS.iter().any(|w| lower.contains(w))
+}
+
+/// HTML のテキスト・属性値として安全な形にする。
+fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::domain::model::book::{AddNodeRequest, Position};
+
+    fn add(book: &mut TemplateBook, parent: Option<NodeId>, title: &str, ty: NodeType) -> NodeId {
+        book.add_node(AddNodeRequest {
+            parent,
+            title: title.into(),
+            node_type: ty,
+            body: None,
+            placeholder: None,
+            position: Position::Last,
+            properties: HashMap::new(),
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn textarea_only_for_long_answer_hints() {
+        assert!(!wants_textarea("version"));
+        assert!(wants_textarea("Incident summary"));
+        assert!(wants_textarea("requirements list"));
+        assert!(wants_textarea(&"x".repeat(41)));
+    }
+
+    #[test]
+    fn titles_and_bodies_are_escaped() {
+        let mut book = TemplateBook::new("A & B", 4);
+        add(
+            &mut book,
+            None,
+            "<script>alert(1)</script>",
+            NodeType::Content,
+        );
+        let html = EjectService::render_html(&book, &MarkdownOptions::default(), None);
+        assert!(html.contains("<title>A &amp; B</title>"));
+        assert!(html.contains("&lt;script&gt;alert(1)&lt;/script&gt;"));
+        assert_eq!(html.matches("<script>").count(), 1);
+    }
+}
@@ -0,0 +1,237 @@
+use crate::domain::model::book::TemplateBook;
+use crate::domain::model::id::NodeId;
+use crate::domain::model::node::{NodeType, TemplateNode};
+
+/// 既定の「深すぎる枝」の閾値（この深さを超える葉を報告する）。
+pub const DEFAULT_DEEP_BRANCH_THRESHOLD: u8 = 5;
+
+/// 構造上の問題の種類。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LintKind {
+    /// 子を持たない Section。
+    EmptySection,
+    /// 子を持つ Content。
+    ContentWithChildren,
+    /// タイトルが空（空白のみを含む）。
+    EmptyTitle,
+    /// 閾値を超える深さの葉。
+    DeepBranch {
+        /// 葉の深さ（ルート = 1）。
+        depth: u8,
+    },
+    /// 記入欄（placeholder）だけで本文のない Content（[`needs_detail`]）。
+    NeedsDetail,
+}
+
+impl LintKind {
+    /// 問題の短い説明。
+    pub fn describe(&self) -> String {
+        match self {
+            LintKind::EmptySection => "section has no children".to_string(),
+            LintKind::ContentWithChildren => "content node has children".to_string(),
+            LintKind::EmptyTitle => "title is empty".to_string(),
+            LintKind::DeepBranch { depth } => format!("branch is {depth} levels deep"),
+            LintKind::NeedsDetail => "content has a placeholder but no body".to_string(),
+        }
+    }
+
+    /// 推奨される修正。
+    pub fn suggestion(&self) -> &'static str {
+        match self {
+            LintKind::EmptySection => {
+                "add content under it, or change its type to content with `node_update`"
+            }
+            LintKind::ContentWithChildren => "change its type to section with `node_update`",
+            LintKind::EmptyTitle => "give it a title with `node_update`",
+            LintKind::DeepBranch { .. } => "flatten the branch by moving nodes up with `node_move`",
+            LintKind::NeedsDetail => "write its body with `node_update`, or clear the placeholder",
+        }
+    }
+}
+
+/// 1件の指摘。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LintFinding {
+    /// 対象ノード。
+    pub node_id: NodeId,
+    /// 問題の種類。
+    pub kind: LintKind,
+}
+
+/// 子も本文もなく placeholder だけを持つ Content か（空白だけの本文は空とみなす）。
+///
+/// チェックリストでは記入欄つきの空のチェックボックスになり、手順が定義済みに見えてしまう。
+/// `lint` の [`LintKind::NeedsDetail`] と Markdown 出力の `mark_incomplete` が共有する。
+pub fn needs_detail(node: &TemplateNode) -> bool {
+    *node.node_type() == NodeType::Content
+        && node.is_leaf()
+        && node.placeholder().is_some()
+        && node.body().is_none_or(|b| b.trim().is_empty())
+}
+
+/// 単一ノードに対する構造チェック（`deep_threshold` は葉の深さの上限）。
+pub fn lint_node(book: &TemplateBook, node: &TemplateNode, deep_threshold: u8) -> Vec<LintKind> {
+    let mut kinds = Vec::new();
+    if node.title().trim().is_empty() {
+        kinds.push(LintKind::EmptyTitle);
+    }
+    match node.node_type() {
+        NodeType::Section if node.is_leaf() => kinds.push(LintKind::EmptySection),
+        NodeType::Content if !node.is_leaf() => kinds.push(LintKind::ContentWithChildren),
+        _ => {}
+    }
+    if needs_detail(node) {
+        kinds.push(LintKind::NeedsDetail);
+    }
+    if node.is_leaf() {
+        let depth = book.depth_of(node.id());
+        if depth > deep_threshold {
+            kinds.push(LintKind::DeepBranch { depth });
+        }
+    }
+    kinds
+}
+
+/// Book 全体の構造上の問題を DFS 順に返す。
+pub fn lint(book: &TemplateBook, deep_threshold: u8) -> Vec<LintFinding> {
+    book.all_nodes_dfs()
+        .into_iter()
+        .flat_map(|node| {
+            lint_node(book, node, deep_threshold)
+                .into_iter()
+                .map(move |kind| LintFinding {
+                    node_id: node.id(),
+                    kind,
+                })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::model::book::{AddNodeRequest, Position, UpdateNodeRequest};
+    use std::collections::HashMap;
+
+    fn add(book: &mut TemplateBook, parent: Option<NodeId>, title: &str, t: NodeType) -> NodeId {
+        book.add_node(AddNodeRequest {
+            parent,
+            title: title.into(),
+            node_type: t,
+            body: None,
+            placeholder: None,
+            position: Position::Last,
+            properties: HashMap::new(),
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn clean_book_has_no_findings() {
+        let mut book = TemplateBook::new("Clean", 4);
+        let sec = add(&mut book, None, "Section", NodeType::Section);
+        add(&mut book, Some(sec), "Item", NodeType::Content);
+        assert!(lint(&book, DEFAULT_DEEP_BRANCH_THRESHOLD).is_empty());
+    }
+
+    #[test]
+    fn reports_each_smell() {
+        let mut book = TemplateBook::new("Smelly", 8);
+        let empty = add(&mut book, None, "Empty", NodeType::Section);
+        let parent = add(&mut book, None, "Parent", NodeType::Content);
+        let blank = add(&mut book, Some(parent), "  ", NodeType::Content);
+
+        let findings = lint(&book, DEFAULT_DEEP_BRANCH_THRESHOLD);
+        assert_eq!(
+            findings,
+            vec![
+                LintFinding {
+                    node_id: empty,
+                    kind: LintKind::EmptySection
+                },
+                LintFinding {
+                    node_id: parent,
+                    kind: LintKind::ContentWithChildren
+                },
+                LintFinding {
+                    node_id: blank,
+                    kind: LintKind::EmptyTitle
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_leaves_deeper_than_threshold() {
+        let mut book = TemplateBook::new("Deep", 8);
+        let mut parent = None;
+        for i in 0..4 {
+            parent = Some(add(&mut book, parent, &format!("L{i}"), NodeType::Section));
+        }
+        let leaf = add(&mut book, parent, "Leaf", NodeType::Content);
+
+        let findings = lint(&book, 4);
+        assert_eq!(
+            findings,
+            vec![LintFinding {
+                node_id: leaf,
+                kind: LintKind::DeepBranch { depth: 5 }
+            }]
+        );
+        assert!(lint(&book, 5).is_empty());
+    }
+
+    fn set_fields(
+        book: &mut TemplateBook,
+        id: NodeId,
+        body: Option<&str>,
+        placeholder: Option<&str>,
+    ) {
+        book.update_node(
+            id,
+            UpdateNodeRequest {
+                body: Some(body.map(str::to_string)),
+                placeholder: Some(placeholder.map(str::to_string)),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn needs_detail_only_for_placeholder_without_body() {
+        let cases = [
+            (None, None, false),
+            (None, Some("host"), true),
+            (Some(""), Some("host"), true),
+            (Some(" \n\t"), Some("host"), true),
+            (Some("Restart it"), Some("host"), false),
+            (Some("Restart it"), None, false),
+            (Some("  "), None, false),
+        ];
+        for (body, placeholder, expected) in cases {
+            let mut book = TemplateBook::new("Runbook", 4);
+            let id = add(&mut book, None, "Restart", NodeType::Content);
+            set_fields(&mut book, id, body, placeholder);
+            let node = book.get_node(id).unwrap();
+            assert_eq!(needs_detail(node), expected, "{body:?} / {placeholder:?}");
+            assert_eq!(
+                lint_node(&book, node, DEFAULT_DEEP_BRANCH_THRESHOLD)
+                    .contains(&LintKind::NeedsDetail),
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn needs_detail_ignores_sections_and_nodes_with_children() {
+        let mut book = TemplateBook::new("Runbook", 4);
+        let sec = add(&mut book, None, "Ops", NodeType::Section);
+        let parent = add(&mut book, Some(sec), "Deploy", NodeType::Content);
+        add(&mut book, Some(parent), "Build", NodeType::Content);
+        set_fields(&mut book, sec, None, Some("team"));
+        set_fields(&mut book, parent, None, Some("version"));
+        assert!(!needs_detail(book.get_node(sec).unwrap()));
+        assert!(!needs_detail(book.get_node(parent).unwrap()));
+    }
+}
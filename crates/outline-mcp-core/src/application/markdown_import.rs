@@ -0,0 +1,385 @@
+//! Markdown チェックリスト（[`EjectService::render_markdown`] の出力）の読み込み。
+//!
+//! 読み取る構文は書き出しと対になっている:
+//!
+//! - 行頭の `# Title` は Book のタイトル、`## ` 以降の見出しは Section
+//!   （`##` がトップレベル、`###` がその下、`####` がさらに下）。
+//! - 2スペース単位でインデントした `- [ ] Title` / `- [x] Title` / `- Title` は
+//!   Content（インデントの段数が深さ）。`- Title` が並ぶ親は順不同（`ordered: false`）。
+//! - `> hint: ___` は直前のノードの placeholder、それ以外の `> text` / `>` は note、
+//!   `_text_` は notes。
+//! - それ以外のインデントされた行は直前のノードの本文。
+//!
+//! 本文の行が構造の構文と紛れる場合（リスト項目・引用・notes の形）は、
+//! 書き出しの `mark_body_lines` で行末へ [`BODY_LINE_MARKER`] を付けておけば、
+//! ここで取り除いて本文に戻す。印のない書き出しでは、そうした行はノードとして読まれる。
+//! 書き出しがリスト項目に付けた `[ ] ` もここで外す。
+//!
+//! [`EjectService::render_markdown`]: super::eject::EjectService::render_markdown
+
+use std::collections::HashMap;
+
+use super::eject::{EjectTree, EjectTreeNode, NEEDS_DETAIL_MARKER};
+use super::error::AppError;
+
+/// 構造の構文と紛れる本文の行の末尾に付ける印（Markdown の表示には現れない）。
+pub const BODY_LINE_MARKER: &str = "<!--body-->";
+
+/// `mark_incomplete` の印（[`NEEDS_DETAIL_MARKER`]）の直後に付ける目印（表示には現れない）。
+/// 読み込み時はこれが続く印だけを外すので、同じ文言で終わる本物のタイトルは残る。
+pub const NEEDS_DETAIL_TAG: &str = "<!--needs-detail-->";
+
+/// 描画済みの本文の行（インデントを除いた部分）に [`BODY_LINE_MARKER`] が必要か。
+pub(crate) fn needs_body_marker(line: &str) -> bool {
+    let trimmed = line.trim();
+    trimmed.starts_with("- ")
+        || is_quote_line(trimmed)
+        || is_notes_line(trimmed)
+        || line.ends_with(BODY_LINE_MARKER)
+}
+
+fn is_placeholder_line(trimmed: &str) -> bool {
+    trimmed.starts_with("> ") && trimmed.ends_with(": ___")
+}
+
+/// 引用の行（placeholder か note）。
+fn is_quote_line(trimmed: &str) -> bool {
+    trimmed == ">" || trimmed.starts_with("> ")
+}
+
+fn is_notes_line(trimmed: &str) -> bool {
+    trimmed.len() >= 2 && trimmed.starts_with('_') && trimmed.ends_with('_')
+}
+
+/// 読み込み中のノードと、その深さ・本文の途中の空行数。
+struct Open {
+    depth: usize,
+    node: EjectTreeNode,
+    pending_blanks: usize,
+}
+
+impl EjectTree {
+    /// `checklist` の Markdown 出力（checkbox 形式）を読み込む。
+    ///
+    /// 書き出しとの往復で木の形・タイトル・種類・本文・note・placeholder・notes が保たれる
+    /// （本文にリスト項目などを含む場合は `mark_body_lines` で書き出したとき）。
+    /// 保たれないもの: `(blocked by …)` / `(~30m)` の接尾辞はタイトルの一部として残り、
+    /// 本文の `* ` のリストは `- ` に、タブは2スペースになる。`wrap_width` で折り返した
+    /// 出力は行が分かれたまま読み込まれる。見出しは `####` で頭打ちになるため、
+    /// 3段より深い Section は3段目として読み込まれる。note の行が `: ___` で終わると
+    /// placeholder として読まれる。ID は読み込み時に振り直される。
+    pub fn from_markdown(markdown: &str) -> Result<Self, AppError> {
+        let mut title: Option<String> = None;
+        let mut roots: Vec<EjectTreeNode> = Vec::new();
+        let mut open: Vec<Open> = Vec::new();
+        let mut max_levels = 0;
+
+        for (index, raw) in markdown.lines().enumerate() {
+            let line_no = index + 1;
+            let trimmed = raw.trim();
+            if trimmed.is_empty() {
+                if let Some(top) = open.last_mut() {
+                    if top.node.body.is_some() {
+                        top.pending_blanks += 1;
+                    }
+                }
+                continue;
+            }
+
+            // 見出しは行頭のものだけ（本文の行は必ずインデントされている）
+            if let Some(heading) = parse_heading(raw) {
+                let (level, text) = heading;
+                if level == 1 {
+                    if title.is_some() || !open.is_empty() || !roots.is_empty() {
+                        return Err(markdown_error(
+                            line_no,
+                            "'# ' heading after the book title or outline (one book per file)",
+                        ));
+                    }
+                    title = Some(text.to_string());
+                    continue;
+                }
+                let depth = level - 2;
+                push_node(&mut open, &mut roots, depth, new_node(text, "section"));
+                max_levels = max_levels.max(open.len());
+                continue;
+            }
+            if title.is_none() {
+                // 書き出しの `Context: A → B` やタイトル前の前置きは読み飛ばす
+                continue;
+            }
+            if raw.trim_end() == "---" {
+                // `section_separators` の区切り線（本文の行ならインデントされている）
+                continue;
+            }
+
+            let indent = raw.len() - raw.trim_start().len();
+            if let Some(body) = raw.strip_suffix(&format!(" {BODY_LINE_MARKER}")) {
+                if let Some(top) = open.last_mut() {
+                    append_body(top, unconvert_list_item(strip_indent(body, top.depth)));
+                }
+                continue;
+            }
+            if indent % 2 == 0 {
+                if let Some((item, unordered)) = parse_item(trimmed) {
+                    let depth = indent / 2;
+                    if unordered {
+                        mark_parent_unordered(&mut open, depth);
+                    }
+                    push_node(&mut open, &mut roots, depth, new_node(item, "content"));
+                    max_levels = max_levels.max(open.len());
+                    continue;
+                }
+            }
+            let Some(top) = open.last_mut() else {
+                // 最初のノードより前の本文は受け皿がない
+                continue;
+            };
+            if is_placeholder_line(trimmed) {
+                let hint = &trimmed["> ".len()..trimmed.len() - ": ___".len()];
+                top.node.placeholder = Some(hint.to_string());
+            } else if is_quote_line(trimmed) {
+                let line = trimmed.strip_prefix('>').unwrap_or_default().trim_start();
+                top.node.note = Some(match top.node.note.take() {
+                    Some(note) => format!("{note}\n{line}"),
+                    None => line.to_string(),
+                });
+            } else if is_notes_line(trimmed) {
+                let note = &trimmed[1..trimmed.len() - 1];
+                top.node.notes = Some(match top.node.notes.take() {
+                    Some(notes) => format!("{notes}\n{note}"),
+                    None => note.to_string(),
+                });
+            } else {
+                let depth = top.depth;
+                append_body(top, strip_indent(raw, depth).to_string());
+            }
+        }
+
+        while let Some(done) = open.pop() {
+            attach(&mut open, &mut roots, done.node);
+        }
+        let title = title.ok_or_else(|| markdown_error(1, "no '# Title' heading"))?;
+        Ok(EjectTree {
+            title,
+            // 既定の 4 段に収まらない木はそのまま受け入れられる段数にする
+            max_depth: max_levels.clamp(4, 16) as u8,
+            nodes: roots,
+            default_placeholder: None,
+            ancestors: Vec::new(),
+            book_id: None,
+            source_slug: None,
+            subtree_uuid: None,
+            subtree_path: None,
+        })
+    }
+}
+
+fn markdown_error(line: usize, reason: &str) -> AppError {
+    AppError::ImportMarkdown {
+        line,
+        reason: reason.to_string(),
+    }
+}
+
+/// 行頭の `#` 見出し（レベルと本文）。
+fn parse_heading(line: &str) -> Option<(usize, &str)> {
+    let level = line.bytes().take_while(|b| *b == b'#').count();
+    if level == 0 || level > 6 {
+        return None;
+    }
+    line[level..]
+        .strip_prefix(' ')
+        .map(|text| (level, text.trim()))
+}
+
+/// `- [ ] Title` / `- [x] Title` / `- Title` のタイトルと、チェックボックスがないか。
+fn parse_item(trimmed: &str) -> Option<(&str, bool)> {
+    let rest = trimmed.strip_prefix("- ")?;
+    // 書き出しの `mark_incomplete` が付けた印（目印つき）だけをタイトルから外す
+    let rest = rest
+        .strip_suffix(&format!(" {NEEDS_DETAIL_MARKER}{NEEDS_DETAIL_TAG}"))
+        .unwrap_or(rest);
+    for checkbox in ["[ ] ", "[x] ", "[X] "] {
+        if let Some(title) = rest.strip_prefix(checkbox) {
+            return Some((title, false));
+        }
+    }
+    Some((rest, true))
+}
+
+fn new_node(title: &str, node_type: &str) -> EjectTreeNode {
+    EjectTreeNode {
+        id: String::new(),
+        title: title.to_string(),
+        node_type: node_type.to_string(),
+        body: None,
+        placeholder: None,
+        notes: None,
+        note: None,
+        command: None,
+        links: Vec::new(),
+        estimate_minutes: None,
+        owner: None,
+        ordered: None,
+        order: None,
+        heading_level: None,
+        children: Vec::new(),
+        properties: HashMap::new(),
+        content_hash: None,
+        children_hash: None,
+    }
+}
+
+/// `depth` のノードを開く。同じか深いノードは閉じて親に付ける。
+fn push_node(
+    open: &mut Vec<Open>,
+    roots: &mut Vec<EjectTreeNode>,
+    depth: usize,
+    node: EjectTreeNode,
+) {
+    while open.last().is_some_and(|o| o.depth >= depth) {
+        let done = open.pop().expect("checked above");
+        attach(open, roots, done.node);
+    }
+    open.push(Open {
+        depth,
+        node,
+        pending_blanks: 0,
+    });
+}
+
+fn attach(open: &mut [Open], roots: &mut Vec<EjectTreeNode>, node: EjectTreeNode) {
+    match open.last_mut() {
+        Some(parent) => parent.node.children.push(node),
+        None => roots.push(node),
+    }
+}
+
+/// `depth` に置く項目の親（開いているうち `depth` より浅い最後のもの）を順不同にする。
+fn mark_parent_unordered(open: &mut [Open], depth: usize) {
+    if let Some(parent) = open.iter_mut().rev().find(|o| o.depth < depth) {
+        if parent.node.node_type == "section" {
+            parent.node.ordered = Some(false);
+        }
+    }
+}
+
+/// 本文の行から、ノードの深さ分のインデント（`depth + 1` 段）を外す。
+fn strip_indent(line: &str, depth: usize) -> &str {
+    let width = (depth + 1) * 2;
+    let spaces = line.bytes().take(width).take_while(|b| *b == b' ').count();
+    &line[spaces..]
+}
+
+/// 書き出しが `- ` / `* ` のリスト項目に付けた `[ ] ` を外す。
+fn unconvert_list_item(line: &str) -> String {
+    let trimmed = line.trim_start();
+    let leading = &line[..line.len() - trimmed.len()];
+    match trimmed.strip_prefix("- [ ] ") {
+        Some(rest) => format!("{leading}- {rest}"),
+        None => line.to_string(),
+    }
+}
+
+/// 本文に1行足す。間にあった空行は本文の途中の空行として戻す（末尾の空行は捨てる）。
+fn append_body(top: &mut Open, line: String) {
+    match top.node.body.as_mut() {
+        Some(body) => {
+            body.push('\n');
+            body.push_str(&"\n".repeat(top.pending_blanks));
+            body.push_str(&line);
+        }
+        None => top.node.body = Some(line),
+    }
+    top.pending_blanks = 0;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_sections_items_placeholders_and_notes() {
+        let md = "Context: Ops → Deploy\n\n# Deploy\n\n## Prepare\n\n  Freeze merges first.\n\n\
+                  \x20 - [ ] Tag release\n    > version: ___\n    _ask release manager_\n\
+                  \x20 - [x] Notify\n\n## Links\n\n  - Dashboard\n  - Runbook\n";
+        let tree = EjectTree::from_markdown(md).unwrap();
+        assert_eq!(tree.title, "Deploy");
+        assert_eq!(tree.max_depth, 4);
+        let prepare = &tree.nodes[0];
+        assert_eq!(prepare.node_type, "section");
+        assert_eq!(prepare.body.as_deref(), Some("Freeze merges first."));
+        assert_eq!(prepare.ordered, None);
+        let tag = &prepare.children[0];
+        assert_eq!(
+            (tag.title.as_str(), tag.node_type.as_str()),
+            ("Tag release", "content")
+        );
+        assert_eq!(tag.placeholder.as_deref(), Some("version"));
+        assert_eq!(tag.notes.as_deref(), Some("ask release manager"));
+        assert_eq!(prepare.children[1].title, "Notify");
+        let links = &tree.nodes[1];
+        assert_eq!(links.ordered, Some(false));
+        assert_eq!(links.children.len(), 2);
+    }
+
+    #[test]
+    fn marked_body_lines_stay_in_the_body() {
+        let md = "# Book\n\n- [ ] Step\n  Run:\n  - [ ] first <!--body-->\n\n    - [ ] nested <!--body-->\n\
+                  \x20 > not: ___ <!--body-->\n  kept <!--body--> <!--body-->\n";
+        let tree = EjectTree::from_markdown(md).unwrap();
+        assert_eq!(tree.nodes.len(), 1);
+        assert_eq!(
+            tree.nodes[0].body.as_deref(),
+            Some("Run:\n- first\n\n  - nested\n> not: ___\nkept <!--body-->")
+        );
+        assert!(tree.nodes[0].children.is_empty());
+    }
+
+    #[test]
+    fn needs_marker_only_for_colliding_lines() {
+        assert!(needs_body_marker("- [ ] step"));
+        assert!(needs_body_marker("  > hint: ___"));
+        assert!(needs_body_marker("_aside_"));
+        assert!(needs_body_marker("already <!--body-->"));
+        assert!(!needs_body_marker("plain text"));
+        // 引用は note と紛れる
+        assert!(needs_body_marker("> a quote"));
+        assert!(needs_body_marker(">"));
+        assert!(!needs_body_marker(">not a quote"));
+        assert!(!needs_body_marker("1. numbered"));
+    }
+
+    #[test]
+    fn quote_lines_are_read_as_the_note() {
+        let md = "# Book\n\n- [ ] Step\n  Run it.\n  > Flaky on CI.\n  >\n  > Retry once.\n  > env: ___\n";
+        let tree = EjectTree::from_markdown(md).unwrap();
+        let step = &tree.nodes[0];
+        assert_eq!(step.body.as_deref(), Some("Run it."));
+        assert_eq!(step.note.as_deref(), Some("Flaky on CI.\n\nRetry once."));
+        assert_eq!(step.placeholder.as_deref(), Some("env"));
+    }
+
+    #[test]
+    fn section_separator_rules_are_skipped() {
+        let md = "# Book\n\n## One\n\n  Intro.\n\n---\n\n## Two\n\n  ---\n";
+        let tree = EjectTree::from_markdown(md).unwrap();
+        let titles: Vec<&str> = tree.nodes.iter().map(|n| n.title.as_str()).collect();
+        assert_eq!(titles, ["One", "Two"]);
+        assert_eq!(tree.nodes[0].body.as_deref(), Some("Intro."));
+        assert_eq!(tree.nodes[1].body.as_deref(), Some("---"));
+    }
+
+    #[test]
+    fn rejects_missing_or_repeated_book_title() {
+        assert!(matches!(
+            EjectTree::from_markdown("## Only a section\n"),
+            Err(AppError::ImportMarkdown { line: 1, .. })
+        ));
+        assert!(matches!(
+            EjectTree::from_markdown("# One\n\n- [ ] a\n\n# Two\n"),
+            Err(AppError::ImportMarkdown { line: 5, .. })
+        ));
+    }
+}
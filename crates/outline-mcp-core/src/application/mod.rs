@@ -1,6 +1,36 @@
+/// 保存形式そのままの無損失バックアップ（`format: backup`）。
+pub mod backup;
+/// エクスポートするノードの変更検出用ハッシュ（`content_hash` / `children_hash`）。
+pub mod content_hash;
+/// 追加・取り込み時の兄弟との重複の扱い（skip / update）。
+pub mod dedupe;
+/// 重複・近似重複タイトルの検出。
+pub mod duplicates;
 /// `TemplateBook` → 作業用ファイル (Markdown / JSON) 変換サービス。
 pub mod eject;
 /// Application-layer error type (`AppError`).
 pub mod error;
+/// 棚全体を1冊にまとめた Markdown ハンドブック（`EjectService::render_handbook`）。
+pub mod handbook;
+/// 記入用フォームとしての HTML 出力（`EjectService::render_html`）。
+pub mod html_export;
+/// 構造上の問題（空の Section など）の検出。
+pub mod lint;
+/// `checklist` の Markdown 出力の読み込み（`EjectTree::from_markdown`）。
+pub mod markdown_import;
+/// 自然順（大文字小文字を区別せず、数字は数値として比べる）の比較。
+pub mod natural_sort;
+/// 書き出し前に尋ねる値（`{{variables}}` と記入欄）の収集。
+pub mod prompts;
+/// JSON エクスポート → 取り込みの往復で失われる情報の検出。
+pub mod roundtrip;
+/// タイトル・本文などのテキスト検索。
+pub mod search;
 /// `TemplateBook` に対するユースケース (`BookService`)。
 pub mod service;
+/// 2つの Book 間のノード単位の差分（追加・削除・変更・移動）。
+pub mod tree_diff;
+/// UI 描画用の入れ子 JSON（階層番号・深さ付き）。
+pub mod tree_view;
+/// エクスポートする Markdown 本文の折り返し。
+pub mod wrap;
@@ -0,0 +1,88 @@
+//! 一覧の並べ替えに使う、数字を数値として比べる自然順の文字列比較。
+
+use std::cmp::Ordering;
+
+/// 自然順の比較。大文字小文字を区別せず、数字の並びは数値として比べるので
+/// `step 2` < `step 10`、`Book` と `book` は隣り合う。
+///
+/// それ以外が等しい場合（大文字小文字・先頭ゼロの違いだけ）はバイト順で決め、
+/// 異なる文字列が `Equal` にならないようにする（並びが実行ごとに変わらない）。
+pub fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let mut left = a.chars().peekable();
+    let mut right = b.chars().peekable();
+    loop {
+        match (left.peek().copied(), right.peek().copied()) {
+            (None, None) => return a.cmp(b),
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(l), Some(r)) if l.is_ascii_digit() && r.is_ascii_digit() => {
+                let l = take_digits(&mut left);
+                let r = take_digits(&mut right);
+                let ord = compare_digit_runs(&l, &r);
+                if ord != Ordering::Equal {
+                    return ord;
+                }
+            }
+            (Some(l), Some(r)) => {
+                left.next();
+                right.next();
+                let ord = l.to_lowercase().cmp(r.to_lowercase());
+                if ord != Ordering::Equal {
+                    return ord;
+                }
+            }
+        }
+    }
+}
+
+fn take_digits(chars: &mut std::iter::Peekable<std::str::Chars<'_>>) -> String {
+    let mut digits = String::new();
+    while let Some(c) = chars.next_if(char::is_ascii_digit) {
+        digits.push(c);
+    }
+    digits
+}
+
+/// 数字の並びを数値として比べる（桁数に上限はない）。先頭のゼロは無視する。
+fn compare_digit_runs(a: &str, b: &str) -> Ordering {
+    let a = a.trim_start_matches('0');
+    let b = b.trim_start_matches('0');
+    a.len().cmp(&b.len()).then_with(|| a.cmp(b))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sorted(items: &[&str]) -> Vec<String> {
+        let mut items: Vec<String> = items.iter().map(|s| s.to_string()).collect();
+        items.sort_by(|a, b| natural_cmp(a, b));
+        items
+    }
+
+    #[test]
+    fn digit_runs_compare_numerically() {
+        assert_eq!(
+            sorted(&["step 10", "step 2", "step 1", "step 02a"]),
+            ["step 1", "step 2", "step 02a", "step 10"]
+        );
+        assert_eq!(
+            sorted(&["book10", "book2", "book1", "book"]),
+            ["book", "book1", "book2", "book10"]
+        );
+        assert_eq!(
+            natural_cmp("v99999999999999999999999", "v100000000000000000000000"),
+            Ordering::Less
+        );
+    }
+
+    #[test]
+    fn case_is_ignored_but_ties_stay_deterministic() {
+        assert_eq!(
+            sorted(&["beta", "Alpha", "alpha", "Gamma"]),
+            ["Alpha", "alpha", "beta", "Gamma"]
+        );
+        assert_eq!(natural_cmp("a1", "a01"), "a1".cmp("a01"));
+        assert_eq!(natural_cmp("same", "same"), Ordering::Equal);
+    }
+}
@@ -0,0 +1,190 @@
+use crate::domain::model::book::TemplateBook;
+use crate::domain::model::id::NodeId;
+
+use super::eject::EjectService;
+
+/// 書き出し前に利用者へ尋ねる値の一覧。
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EjectPrompts {
+    /// `{{name}}` 変数（初出の DFS 順、名前ごとに1件）。
+    pub variables: Vec<PromptVariable>,
+    /// 記入欄のあるノード（DFS 順）。
+    pub placeholders: Vec<PromptPlaceholder>,
+}
+
+/// 1つの `{{name}}` 変数と、それを使うノード。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PromptVariable {
+    /// Variable name as written between the braces, trimmed.
+    pub name: String,
+    /// Nodes whose text uses the variable, in DFS order, each once.
+    pub nodes: Vec<NodeId>,
+}
+
+/// 記入欄のある1ノード。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PromptPlaceholder {
+    /// The node asking for input.
+    pub node_id: NodeId,
+    /// Its effective placeholder hint (own, or the book default for content).
+    pub hint: String,
+}
+
+impl EjectPrompts {
+    /// 尋ねる値が1つもなければ `true`。
+    pub fn is_empty(&self) -> bool {
+        self.variables.is_empty() && self.placeholders.is_empty()
+    }
+}
+
+/// `root` の部分木（`None` なら Book 全体）から `{{variables}}` と記入欄を集める。
+///
+/// 変数はタイトル・本文・placeholder・note・command から拾い、同じ名前は
+/// 1件にまとめる。記入欄は `checklist` が描画するもの（[`EjectService::effective_placeholder`]）。
+pub fn collect_prompts(book: &TemplateBook, root: Option<NodeId>) -> EjectPrompts {
+    let nodes = match root {
+        Some(id) => book.subtree_nodes(id),
+        None => book.all_nodes_dfs(),
+    };
+    let mut prompts = EjectPrompts::default();
+    for node in nodes {
+        let texts = [
+            Some(node.title()),
+            node.body(),
+            node.placeholder(),
+            node.note(),
+            node.command(),
+        ];
+        for name in texts.into_iter().flatten().flat_map(variable_names) {
+            match prompts.variables.iter_mut().find(|v| v.name == name) {
+                Some(var) if var.nodes.contains(&node.id()) => {}
+                Some(var) => var.nodes.push(node.id()),
+                None => prompts.variables.push(PromptVariable {
+                    name: name.to_string(),
+                    nodes: vec![node.id()],
+                }),
+            }
+        }
+        if let Some(hint) = EjectService::effective_placeholder(book, node) {
+            prompts.placeholders.push(PromptPlaceholder {
+                node_id: node.id(),
+                hint: hint.to_string(),
+            });
+        }
+    }
+    prompts
+}
+
+/// `text` 中の `{{name}}` の名前（前後の空白は除く）。名前は英数字と `_` `-` `.` のみで、
+/// それ以外を含む `{{...}}` は変数とみなさない。
+fn variable_names(text: &str) -> Vec<&str> {
+    let mut names = Vec::new();
+    let mut rest = text;
+    while let Some(start) = rest.find("{{") {
+        let after = &rest[start + 2..];
+        let Some(end) = after.find("}}") else {
+            break;
+        };
+        let name = after[..end].trim();
+        let valid = !name.is_empty()
+            && name
+                .chars()
+                .all(|c| c.is_alphanumeric() || matches!(c, '_' | '-' | '.'));
+        if valid {
+            names.push(name);
+            rest = &after[end + 2..];
+        } else {
+            rest = after;
+        }
+    }
+    names
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::domain::model::book::{AddNodeRequest, Position};
+    use crate::domain::model::node::NodeType;
+
+    fn add(
+        book: &mut TemplateBook,
+        parent: Option<NodeId>,
+        title: &str,
+        body: Option<&str>,
+        placeholder: Option<&str>,
+    ) -> NodeId {
+        book.add_node(AddNodeRequest {
+            parent,
+            title: title.into(),
+            node_type: if parent.is_some() {
+                NodeType::Content
+            } else {
+                NodeType::Section
+            },
+            body: body.map(Into::into),
+            placeholder: placeholder.map(Into::into),
+            position: Position::Last,
+            properties: HashMap::new(),
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn variable_names_skip_blank_and_non_identifier_braces() {
+        assert_eq!(
+            variable_names("ssh {{ host }} -p {{port}} {{}} {{a b}} {{env.region}} {{tail"),
+            vec!["host", "port", "env.region"]
+        );
+        assert!(variable_names("no braces { here }").is_empty());
+    }
+
+    #[test]
+    fn collects_deduplicated_variables_and_placeholders_in_outline_order() {
+        let mut book = TemplateBook::new("Deploy", 3);
+        let deploy = add(&mut book, None, "Deploy {{service}}", None, None);
+        let push = add(
+            &mut book,
+            Some(deploy),
+            "Push",
+            Some("push {{service}} to {{env}}\nthen tag {{service}}"),
+            None,
+        );
+        let verify = add(&mut book, Some(deploy), "Verify", None, Some("URL"));
+        let other = add(&mut book, None, "Cleanup", None, None);
+        let drop = add(&mut book, Some(other), "Drop {{env}} cache", None, None);
+
+        let prompts = collect_prompts(&book, None);
+        assert_eq!(
+            prompts.variables,
+            vec![
+                PromptVariable {
+                    name: "service".into(),
+                    nodes: vec![deploy, push],
+                },
+                PromptVariable {
+                    name: "env".into(),
+                    nodes: vec![push, drop],
+                },
+            ]
+        );
+        assert_eq!(
+            prompts.placeholders,
+            vec![PromptPlaceholder {
+                node_id: verify,
+                hint: "URL".into(),
+            }]
+        );
+
+        let sub = collect_prompts(&book, Some(other));
+        assert_eq!(sub.variables.len(), 1);
+        assert_eq!(sub.variables[0].nodes, vec![drop]);
+        assert!(sub.placeholders.is_empty());
+
+        book.set_default_placeholder(Some("notes".into()));
+        let with_default = collect_prompts(&book, Some(other));
+        assert_eq!(with_default.placeholders[0].node_id, drop);
+        assert!(!with_default.is_empty());
+    }
+}
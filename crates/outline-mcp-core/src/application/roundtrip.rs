@@ -0,0 +1,206 @@
+//! JSON エクスポートの往復（`build_tree` → JSON → `import_tree`）で失われる情報の検出。
+//!
+//! 元の Book と往復後の Book を兄弟の並び順どおりに突き合わせ、エクスポートが
+//! 運ぶはずの項目の違いと、増減したノードを報告する。完了・状態・保護・依存関係は
+//! エクスポートに含まれないので比較しない。
+
+use crate::domain::model::book::TemplateBook;
+use crate::domain::model::id::NodeId;
+use crate::domain::model::node::TemplateNode;
+
+use super::eject::{EjectService, EjectTree};
+use super::error::AppError;
+
+/// 往復で変わった1か所。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RoundtripDifference {
+    /// The original node; for a node only the copy has, its parent in the
+    /// original (`None` at the top level).
+    pub node_id: Option<NodeId>,
+    /// What differs (e.g. `fields differ: body, order`).
+    pub detail: String,
+}
+
+impl EjectService {
+    /// `book` を JSON に書き出して読み戻し、元と違う箇所を返す。空なら往復は無損失。
+    pub fn verify_roundtrip(book: &TemplateBook) -> Result<Vec<RoundtripDifference>, AppError> {
+        let json = Self::render_json(book, None)?;
+        let tree = EjectTree::from_json(&json, true).map_err(|e| AppError::Storage(Box::new(e)))?;
+        let copy = Self::import_tree(&tree)?;
+
+        let mut differences = Vec::new();
+        if copy.title() != book.title() {
+            differences.push(RoundtripDifference {
+                node_id: None,
+                detail: "book title differs".to_string(),
+            });
+        }
+        if copy.max_depth() != book.max_depth() {
+            differences.push(RoundtripDifference {
+                node_id: None,
+                detail: "max_depth differs".to_string(),
+            });
+        }
+        compare_siblings(
+            book,
+            &copy,
+            None,
+            book.root_nodes(),
+            copy.root_nodes(),
+            &mut differences,
+        );
+        Ok(differences)
+    }
+}
+
+fn compare_siblings(
+    original: &TemplateBook,
+    copy: &TemplateBook,
+    parent: Option<NodeId>,
+    original_ids: &[NodeId],
+    copy_ids: &[NodeId],
+    out: &mut Vec<RoundtripDifference>,
+) {
+    let original_nodes: Vec<&TemplateNode> = original_ids
+        .iter()
+        .filter_map(|id| original.get_node(*id))
+        .collect();
+    let copy_nodes: Vec<&TemplateNode> = copy_ids
+        .iter()
+        .filter_map(|id| copy.get_node(*id))
+        .collect();
+    for (old, new) in original_nodes.iter().zip(&copy_nodes) {
+        let fields = differing_fields(old, new);
+        if !fields.is_empty() {
+            out.push(RoundtripDifference {
+                node_id: Some(old.id()),
+                detail: format!("fields differ: {}", fields.join(", ")),
+            });
+        }
+        compare_siblings(
+            original,
+            copy,
+            Some(old.id()),
+            old.children(),
+            new.children(),
+            out,
+        );
+    }
+    for missing in original_nodes.iter().skip(copy_nodes.len()) {
+        out.push(RoundtripDifference {
+            node_id: Some(missing.id()),
+            detail: "missing after the round trip".to_string(),
+        });
+    }
+    for extra in copy_nodes.iter().skip(original_nodes.len()) {
+        out.push(RoundtripDifference {
+            node_id: parent,
+            detail: format!("extra child '{}' after the round trip", extra.title()),
+        });
+    }
+}
+
+/// エクスポートが運ぶ項目のうち、値の違うもの。
+fn differing_fields(old: &TemplateNode, new: &TemplateNode) -> Vec<&'static str> {
+    let checks = [
+        ("title", old.title() == new.title()),
+        ("type", old.node_type() == new.node_type()),
+        ("body", old.body() == new.body()),
+        ("placeholder", old.placeholder() == new.placeholder()),
+        ("note", old.note() == new.note()),
+        ("notes", old.notes() == new.notes()),
+        ("estimate", old.estimate_minutes() == new.estimate_minutes()),
+        ("owner", old.owner() == new.owner()),
+        ("ordered", old.is_ordered() == new.is_ordered()),
+        ("order", old.order() == new.order()),
+        ("heading_level", old.heading_level() == new.heading_level()),
+        ("command", old.command() == new.command()),
+        ("links", old.links() == new.links()),
+        ("properties", old.properties() == new.properties()),
+    ];
+    checks
+        .into_iter()
+        .filter(|(_, same)| !same)
+        .map(|(name, _)| name)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::domain::model::book::{AddNodeRequest, Position, UpdateNodeRequest};
+    use crate::domain::model::node::NodeType;
+
+    fn add(book: &mut TemplateBook, parent: Option<NodeId>, title: &str, ty: NodeType) -> NodeId {
+        book.add_node(AddNodeRequest {
+            parent,
+            title: title.into(),
+            node_type: ty,
+            body: Some(format!("About {title}\n- [ ] sub-step")),
+            placeholder: Some("value".into()),
+            position: Position::Last,
+            properties: HashMap::from([("k".to_string(), title.to_string())]),
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn every_exported_field_survives_the_round_trip() {
+        let mut book = TemplateBook::new("Runbook", 4);
+        let ops = add(&mut book, None, "Ops", NodeType::Section);
+        let links = add(&mut book, Some(ops), "Links", NodeType::Section);
+        add(&mut book, Some(links), "Dashboard", NodeType::Content);
+        let step = add(&mut book, Some(ops), "Restart", NodeType::Content);
+        add(&mut book, Some(step), "Drain", NodeType::Content);
+        add(&mut book, None, "Same title", NodeType::Content);
+        add(&mut book, None, "Same title", NodeType::Content);
+        book.update_node(
+            links,
+            UpdateNodeRequest {
+                notes: Some(Some("why".into())),
+                note: Some(Some("heads-up".into())),
+                estimate_minutes: Some(Some(15)),
+                owner: Some(Some("sam".into())),
+                ordered: Some(false),
+                order: Some(Some(5.0)),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        assert_eq!(EjectService::verify_roundtrip(&book).unwrap(), []);
+    }
+
+    #[test]
+    fn siblings_are_compared_in_order() {
+        let mut a = TemplateBook::new("Runbook", 4);
+        let ops = add(&mut a, None, "Ops", NodeType::Section);
+        let first = add(&mut a, Some(ops), "First", NodeType::Content);
+        let mut b = a.clone();
+        add(&mut b, Some(ops), "Second", NodeType::Content);
+        b.remove_node(first).unwrap();
+
+        let mut out = Vec::new();
+        compare_siblings(&a, &b, None, a.root_nodes(), b.root_nodes(), &mut out);
+        assert_eq!(
+            out,
+            [RoundtripDifference {
+                node_id: Some(first),
+                detail: "fields differ: title, body, properties".to_string(),
+            }]
+        );
+
+        add(&mut b, Some(ops), "Third", NodeType::Content);
+        let mut out = Vec::new();
+        compare_siblings(&a, &b, None, a.root_nodes(), b.root_nodes(), &mut out);
+        assert_eq!(
+            out[1],
+            RoundtripDifference {
+                node_id: Some(ops),
+                detail: "extra child 'Third' after the round trip".to_string(),
+            }
+        );
+    }
+}
@@ -0,0 +1,133 @@
+use crate::domain::model::book::TemplateBook;
+use crate::domain::model::id::NodeId;
+
+/// スニペットとして一致箇所の前後に残す文字数。
+const SNIPPET_CONTEXT: usize = 30;
+
+/// テキスト検索で見つかった1ノード。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextMatch {
+    /// The matching node.
+    pub node_id: NodeId,
+    /// Field the query was found in: `"title"`, `"body"`, `"placeholder"`, or `"notes"`.
+    pub field: &'static str,
+    /// One-line excerpt around the first occurrence (`…` marks cut text).
+    pub snippet: String,
+}
+
+/// `query` を大文字小文字を区別せずにタイトル・本文・プレースホルダ・メモから探す。
+///
+/// 1ノードにつき最初に一致したフィールドの1件を DFS 順に返し、`limit` 件で打ち切る。
+/// 空白だけの `query` は何にも一致しない。
+pub fn search_text(book: &TemplateBook, query: &str, limit: usize) -> Vec<TextMatch> {
+    let needle = query.trim().to_lowercase();
+    if needle.is_empty() {
+        return Vec::new();
+    }
+    let mut matches = Vec::new();
+    for node in book.all_nodes_dfs() {
+        if matches.len() >= limit {
+            break;
+        }
+        let fields = [
+            ("title", Some(node.title())),
+            ("body", node.body()),
+            ("placeholder", node.placeholder()),
+            ("notes", node.notes()),
+        ];
+        let found = fields.into_iter().find_map(|(field, text)| {
+            let text = text?;
+            snippet(text, &needle).map(|snippet| (field, snippet))
+        });
+        if let Some((field, snippet)) = found {
+            matches.push(TextMatch {
+                node_id: node.id(),
+                field,
+                snippet,
+            });
+        }
+    }
+    matches
+}
+
+/// `needle`（小文字化済み）が `text` にあれば、その前後を1行に切り出す。
+fn snippet(text: &str, needle: &str) -> Option<String> {
+    let lower = text.to_lowercase();
+    let at = lower.find(needle)?;
+    let chars: Vec<char> = text.chars().collect();
+    // 小文字化でバイト長が変わる文字を含む場合は位置がずれるので先頭から切り出す
+    let (start, len) = if lower.len() == text.len() {
+        (
+            text[..at].chars().count(),
+            text[at..at + needle.len()].chars().count(),
+        )
+    } else {
+        (0, 0)
+    };
+    let from = start.saturating_sub(SNIPPET_CONTEXT);
+    let to = (start + len + SNIPPET_CONTEXT).min(chars.len());
+    let mut out = String::new();
+    if from > 0 {
+        out.push('…');
+    }
+    let excerpt: String = chars[from..to].iter().collect();
+    out.push_str(&excerpt.split_whitespace().collect::<Vec<_>>().join(" "));
+    if to < chars.len() {
+        out.push('…');
+    }
+    Some(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::domain::model::book::{AddNodeRequest, Position};
+    use crate::domain::model::node::NodeType;
+
+    fn add(book: &mut TemplateBook, title: &str, body: Option<&str>) -> NodeId {
+        book.add_node(AddNodeRequest {
+            parent: None,
+            title: title.into(),
+            node_type: NodeType::Content,
+            body: body.map(String::from),
+            placeholder: None,
+            position: Position::Last,
+            properties: HashMap::new(),
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn finds_title_before_body_and_stops_at_limit() {
+        let mut book = TemplateBook::new("Ops", 4);
+        let deploy = add(&mut book, "Deploy", Some("Run the deploy script"));
+        let rollback = add(&mut book, "Rollback", Some("Undo a bad DEPLOY quickly"));
+        add(&mut book, "Monitoring", None);
+
+        let found = search_text(&book, "deploy", 10);
+        assert_eq!(
+            found
+                .iter()
+                .map(|m| (m.node_id, m.field))
+                .collect::<Vec<_>>(),
+            vec![(deploy, "title"), (rollback, "body")]
+        );
+        assert_eq!(found[1].snippet, "Undo a bad DEPLOY quickly");
+        assert_eq!(search_text(&book, "deploy", 1).len(), 1);
+        assert!(search_text(&book, "  ", 10).is_empty());
+    }
+
+    #[test]
+    fn long_bodies_are_cut_around_the_match() {
+        let mut book = TemplateBook::new("Ops", 4);
+        let body = format!("{}needle\nin the{}", "x".repeat(50), "y".repeat(50));
+        add(&mut book, "Haystack", Some(&body));
+        let found = search_text(&book, "NEEDLE", 10);
+        assert_eq!(
+            found[0].snippet,
+            format!("…{}needle in the{}…", "x".repeat(30), "y".repeat(23))
+        );
+    }
+}
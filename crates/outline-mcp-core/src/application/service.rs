@@ -1,6 +1,10 @@
-use crate::domain::model::book::{AddNodeRequest, TemplateBook, UpdateNodeRequest};
+use crate::domain::error::DomainError;
+use crate::domain::model::book::{
+    AddNodeRequest, NodeCountMismatch, Placement, Position, TemplateBook, UpdateNodeRequest,
+};
 use crate::domain::model::changelog::{ChangeAction, ChangeEntry};
 use crate::domain::model::id::NodeId;
+use crate::domain::model::node::TemplateNode;
 use crate::domain::model::timestamp::Timestamp;
 use crate::domain::repository::{BookRepository, ChangeLogRepository};
 
@@ -28,25 +32,33 @@ impl<R: BookRepository> BookService<R> {
         self
     }
 
-    /// Bookを新規作成して永続化する。既存Bookがあれば上書き。
+    /// Bookを新規作成して永続化する。既存Bookがあれば `AppError::BookAlreadyExists`（上書きしない）。
+    ///
+    /// `max_depth` が範囲外なら `DomainError::InvalidMaxDepth`。
     pub async fn create_book(&self, title: &str, max_depth: u8) -> Result<TemplateBook, AppError> {
-        let book = TemplateBook::new(title, max_depth);
-        self.repo
-            .save(&book)
+        let book = TemplateBook::try_new(title, max_depth)?;
+        let created = self
+            .repo
+            .create(&book)
             .await
             .map_err(|e| AppError::Storage(Box::new(e)))?;
+        if !created {
+            return Err(AppError::BookAlreadyExists);
+        }
         Ok(book)
     }
 
     /// ノードを追加する。
     ///
-    /// 戻り値: `(NodeId, Option<String>)` — 第2要素は changelog 書き込み失敗時の警告メッセージ。
+    /// 戻り値: `(Placement, Option<String>)` — 第1要素は作成ノードと実際の挿入位置、
+    /// 第2要素は changelog 書き込み失敗時の警告メッセージ。
     pub async fn add_node(
         &self,
         req: AddNodeRequest,
-    ) -> Result<(NodeId, Option<String>), AppError> {
+    ) -> Result<(Placement, Option<String>), AppError> {
         let mut book = self.load_book().await?;
-        let id = book.add_node(req)?;
+        let placed = book.add_node_placed(req)?;
+        let id = placed.node_id;
         self.persist(&book).await?;
 
         let after_json = book
@@ -55,7 +67,39 @@ impl<R: BookRepository> BookService<R> {
         let entry = ChangeEntry::new(id, ChangeAction::Create, None, after_json, Timestamp::now());
         let warning = self.append_changelog(entry).await;
 
-        Ok((id, warning))
+        Ok((placed, warning))
+    }
+
+    /// ノードとその子ノード群を1回の保存で追加する（[`TemplateBook::add_node_with_children`]）。
+    ///
+    /// 戻り値: `((Placement, Vec<NodeId>), Vec<Option<String>>)` — 第1要素は親ノードの
+    /// 挿入位置と子ノードID、第2要素は作成ノードごとの changelog 書き込み警告。
+    pub async fn add_node_with_children(
+        &self,
+        req: AddNodeRequest,
+        children: Vec<AddNodeRequest>,
+    ) -> Result<((Placement, Vec<NodeId>), Vec<Option<String>>), AppError> {
+        let mut book = self.load_book().await?;
+        let (placed, child_ids) = book.add_node_with_children(req, children)?;
+        let id = placed.node_id;
+        self.persist(&book).await?;
+
+        let mut warnings = Vec::with_capacity(child_ids.len() + 1);
+        for created in std::iter::once(id).chain(child_ids.iter().copied()) {
+            let after_json = book
+                .get_node(created)
+                .and_then(|n| serde_json::to_string(n).ok());
+            let entry = ChangeEntry::new(
+                created,
+                ChangeAction::Create,
+                None,
+                after_json,
+                Timestamp::now(),
+            );
+            warnings.push(self.append_changelog(entry).await);
+        }
+
+        Ok(((placed, child_ids), warnings))
     }
 
     /// ノードを更新する。
@@ -88,20 +132,149 @@ impl<R: BookRepository> BookService<R> {
         Ok(((), warning))
     }
 
+    /// ノードを更新し、続けて同一トランザクション内で移動する。
+    ///
+    /// `relocate` は `(new_parent, position)`。移動の検証（循環・深さ）に失敗した場合は
+    /// 何も保存しない（更新も取り消される）。changelog には Update と Move を記録する。
+    /// 戻り値: `(Placement, Vec<Option<String>>)` — 移動先での実際の位置と、
+    /// changelog 書き込み失敗時の警告メッセージ。
+    pub async fn update_and_move(
+        &self,
+        id: NodeId,
+        req: UpdateNodeRequest,
+        relocate: (Option<NodeId>, Position),
+    ) -> Result<(Placement, Vec<Option<String>>), AppError> {
+        let mut book = self.load_book().await?;
+        let before_json = book
+            .get_node(id)
+            .and_then(|n| serde_json::to_string(n).ok());
+        book.update_node(id, req)?;
+        let updated_json = book
+            .get_node(id)
+            .and_then(|n| serde_json::to_string(n).ok());
+        let (new_parent, position) = relocate;
+        let placed = book.move_node(id, new_parent, position)?;
+        self.persist(&book).await?;
+
+        let after_json = book
+            .get_node(id)
+            .and_then(|n| serde_json::to_string(n).ok());
+        let update_entry = ChangeEntry::new(
+            id,
+            ChangeAction::Update,
+            before_json,
+            updated_json.clone(),
+            Timestamp::now(),
+        );
+        let move_entry = ChangeEntry::new(
+            id,
+            ChangeAction::Move,
+            updated_json,
+            after_json,
+            Timestamp::now(),
+        );
+        let warnings = vec![
+            self.append_changelog(update_entry).await,
+            self.append_changelog(move_entry).await,
+        ];
+
+        Ok((placed, warnings))
+    }
+
+    /// 依存（"blocked by"）を追加 (`depend = true`) または解除する。
+    ///
+    /// 戻り値: `(bool, Option<String>)` — 第1要素は変更の有無（既に同じ状態なら `false`）、
+    /// 第2要素は changelog 書き込み失敗時の警告メッセージ。
+    pub async fn set_dependency(
+        &self,
+        id: NodeId,
+        on: NodeId,
+        depend: bool,
+    ) -> Result<(bool, Option<String>), AppError> {
+        let mut book = self.load_book().await?;
+        let before_json = book
+            .get_node(id)
+            .and_then(|n| serde_json::to_string(n).ok());
+        let changed = if depend {
+            book.add_dependency(id, on)?
+        } else {
+            book.remove_dependency(id, on)?
+        };
+        if !changed {
+            return Ok((false, None));
+        }
+        self.persist(&book).await?;
+
+        let after_json = book
+            .get_node(id)
+            .and_then(|n| serde_json::to_string(n).ok());
+        let entry = ChangeEntry::new(
+            id,
+            ChangeAction::Update,
+            before_json,
+            after_json,
+            Timestamp::now(),
+        );
+        let warning = self.append_changelog(entry).await;
+
+        Ok((true, warning))
+    }
+
+    /// 外部リンクを追加 (`link = true`) または外す。外すときは `url` にラベルも渡せる。
+    ///
+    /// 戻り値: `(bool, Option<String>)` — 第1要素は変更の有無（既に同じ状態なら `false`）、
+    /// 第2要素は changelog 書き込み失敗時の警告メッセージ。
+    pub async fn set_link(
+        &self,
+        id: NodeId,
+        url: &str,
+        label: Option<String>,
+        link: bool,
+    ) -> Result<(bool, Option<String>), AppError> {
+        let mut book = self.load_book().await?;
+        let before_json = book
+            .get_node(id)
+            .and_then(|n| serde_json::to_string(n).ok());
+        let changed = if link {
+            book.add_link(id, label, url)?
+        } else {
+            book.remove_link(id, url)?
+        };
+        if !changed {
+            return Ok((false, None));
+        }
+        self.persist(&book).await?;
+
+        let after_json = book
+            .get_node(id)
+            .and_then(|n| serde_json::to_string(n).ok());
+        let entry = ChangeEntry::new(
+            id,
+            ChangeAction::Update,
+            before_json,
+            after_json,
+            Timestamp::now(),
+        );
+        let warning = self.append_changelog(entry).await;
+
+        Ok((true, warning))
+    }
+
     /// ノードを移動する。
     ///
-    /// 戻り値: `((), Option<String>)` — 第2要素は changelog 書き込み失敗時の警告メッセージ。
+    /// 戻り値: `(Placement, Option<String>)` — 第1要素は移動先での実際の位置、
+    /// 第2要素は changelog 書き込み失敗時の警告メッセージ。
     pub async fn move_node(
         &self,
         id: NodeId,
         new_parent: Option<NodeId>,
-        position: usize,
-    ) -> Result<((), Option<String>), AppError> {
+        position: Position,
+    ) -> Result<(Placement, Option<String>), AppError> {
         let mut book = self.load_book().await?;
         let before_json = book
             .get_node(id)
             .and_then(|n| serde_json::to_string(n).ok());
-        book.move_node(id, new_parent, position)?;
+        let placed = book.move_node(id, new_parent, position)?;
         self.persist(&book).await?;
 
         let after_json = book
@@ -116,7 +289,7 @@ impl<R: BookRepository> BookService<R> {
         );
         let warning = self.append_changelog(entry).await;
 
-        Ok(((), warning))
+        Ok((placed, warning))
     }
 
     /// ノードを削除する（子孫ごと）。
@@ -144,11 +317,11 @@ impl<R: BookRepository> BookService<R> {
 
     /// 複数ノードをアトミックに移動する（C案: 全成功 or 全保存なし）。
     ///
-    /// `moves` は `(NodeId, new_parent: Option<NodeId>, position: usize)` のリスト。
+    /// `moves` は `(NodeId, new_parent: Option<NodeId>, position: Position)` のリスト。
     /// 戻り値: `(成功件数, changelog警告リスト)` — エラー時はErrを返しsaveしない。
     pub async fn batch_move(
         &self,
-        moves: Vec<(NodeId, Option<NodeId>, usize)>,
+        moves: Vec<(NodeId, Option<NodeId>, Position)>,
     ) -> Result<(usize, Vec<Option<String>>), AppError> {
         let mut book = self.load_book().await?;
         let mut before_jsons: Vec<Option<String>> = Vec::with_capacity(moves.len());
@@ -213,6 +386,13 @@ impl<R: BookRepository> BookService<R> {
                     placeholder: req.placeholder.clone(),
                     properties: req.properties.clone(),
                     status: req.status,
+                    notes: req.notes.clone(),
+                    estimate_minutes: req.estimate_minutes,
+                    owner: req.owner.clone(),
+                    done: req.done,
+                    ordered: req.ordered,
+                    order: req.order,
+                    ..Default::default()
                 },
             )?;
         }
@@ -240,11 +420,111 @@ impl<R: BookRepository> BookService<R> {
         Ok((node_ids.len(), warnings))
     }
 
+    /// `root` の部分木（自身を含む、`None` なら Book 全体）のうち `matches` を満たす
+    /// 全ノードに `update` を適用する（`update` には対象ノードが渡される）。
+    /// 1回の保存で行い、該当なしなら保存しない。
+    ///
+    /// 戻り値: `(更新件数, changelog警告リスト)`
+    pub async fn update_matching(
+        &self,
+        root: Option<NodeId>,
+        matches: impl Fn(&TemplateNode) -> bool,
+        update: impl Fn(&TemplateNode) -> UpdateNodeRequest,
+    ) -> Result<(usize, Vec<Option<String>>), AppError> {
+        let book = self.load_book().await?;
+        let candidates = match root {
+            Some(id) => {
+                if book.get_node(id).is_none() {
+                    return Err(DomainError::NodeNotFound(id).into());
+                }
+                book.subtree_nodes(id)
+            }
+            None => book.all_nodes_dfs(),
+        };
+        let updates: Vec<_> = candidates
+            .into_iter()
+            .filter(|n| matches(n))
+            .map(|n| (n.id(), update(n)))
+            .collect();
+        if updates.is_empty() {
+            return Ok((0, Vec::new()));
+        }
+        self.batch_update(updates).await
+    }
+
+    /// Book を1回だけ読み込み、`f` で解決・変更・応答の組み立てをすべて同じインスタンスに
+    /// 対して行ってから1回だけ保存する。
+    ///
+    /// `f` は `(値, 変更記録)` を返す。変更記録 `(NodeId, ChangeAction)` ごとに、読み込み時と
+    /// 変更後のノードから changelog エントリを書く。変更記録が空なら保存しない。
+    /// `f` が `Err` を返した場合も何も保存せず、そのエラーを内側の `Err` として返す。
+    ///
+    /// 戻り値: 外側の `Err` は読み込み・保存の失敗。内側は `(値, changelog警告リスト)`。
+    #[allow(clippy::type_complexity)]
+    pub async fn with_book<T, E>(
+        &self,
+        f: impl FnOnce(&mut TemplateBook) -> Result<(T, Vec<(NodeId, ChangeAction)>), E>,
+    ) -> Result<Result<(T, Vec<Option<String>>), E>, AppError> {
+        let mut book = self.load_book().await?;
+        let before = book.clone();
+        let (value, changes) = match f(&mut book) {
+            Ok(done) => done,
+            Err(e) => return Ok(Err(e)),
+        };
+        if changes.is_empty() {
+            return Ok(Ok((value, Vec::new())));
+        }
+        self.persist(&book).await?;
+
+        let node_json = |book: &TemplateBook, id: NodeId| {
+            book.get_node(id)
+                .and_then(|n| serde_json::to_string(n).ok())
+        };
+        let mut warnings = Vec::with_capacity(changes.len());
+        for (id, action) in changes {
+            let entry = ChangeEntry::new(
+                id,
+                action,
+                node_json(&before, id),
+                node_json(&book, id),
+                Timestamp::now(),
+            );
+            warnings.push(self.append_changelog(entry).await);
+        }
+        Ok(Ok((value, warnings)))
+    }
+
     /// Tree全体または部分木を読み取る。
     pub async fn read_tree(&self) -> Result<TemplateBook, AppError> {
         self.load_book().await
     }
 
+    /// [`Self::read_tree`] と同じだが、孤立ノードによる件数の食い違い
+    /// （[`TemplateBook::check_node_count`]）も返す。食い違いがあっても読み込みは成功する。
+    pub async fn read_tree_checked(
+        &self,
+    ) -> Result<(TemplateBook, Option<NodeCountMismatch>), AppError> {
+        let book = self.load_book().await?;
+        let mismatch = book.check_node_count();
+        Ok((book, mismatch))
+    }
+
+    /// Bookが保存済みかを返す。
+    pub async fn exists(&self) -> Result<bool, AppError> {
+        self.repo
+            .exists()
+            .await
+            .map_err(|e| AppError::Storage(Box::new(e)))
+    }
+
+    /// 保存済みのBookを削除する。存在しなければ何もしない。
+    pub async fn delete_book(&self) -> Result<(), AppError> {
+        self.repo
+            .delete()
+            .await
+            .map_err(|e| AppError::Storage(Box::new(e)))
+    }
+
     /// インポートされたBookを保存する。
     pub async fn save_book(&self, book: &TemplateBook) -> Result<(), AppError> {
         self.persist(book).await
@@ -261,6 +541,20 @@ impl<R: BookRepository> BookService<R> {
     }
 
     async fn persist(&self, book: &TemplateBook) -> Result<(), AppError> {
+        // テスト（と integrity-checks 機能）では保存のたびに木構造の不変条件を検証する
+        #[cfg(any(test, feature = "integrity-checks"))]
+        {
+            let issues = book.check_integrity();
+            assert!(
+                issues.is_empty(),
+                "book integrity violated on save: {}",
+                issues
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join("; ")
+            );
+        }
         self.repo
             .save(book)
             .await
@@ -286,7 +580,7 @@ impl<R: BookRepository> BookService<R> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::domain::model::book::{AddNodeRequest, UpdateNodeRequest};
+    use crate::domain::model::book::{AddNodeRequest, Position, UpdateNodeRequest};
     use crate::domain::model::changelog::{ChangeAction, ChangeEntry, NodeStatus};
     use crate::domain::model::id::NodeId;
     use crate::domain::model::node::NodeType;
@@ -329,6 +623,13 @@ mod tests {
             *self.book.lock().unwrap() = Some(book.clone());
             Ok(())
         }
+        async fn exists(&self) -> Result<bool, RepoError> {
+            Ok(self.book.lock().unwrap().is_some())
+        }
+        async fn delete(&self) -> Result<(), RepoError> {
+            *self.book.lock().unwrap() = None;
+            Ok(())
+        }
     }
 
     // --- Recording ChangeLogRepository ---
@@ -406,7 +707,7 @@ mod tests {
             node_type: NodeType::Content,
             body: None,
             placeholder: None,
-            position: usize::MAX,
+            position: Position::Last,
             properties: Default::default(),
         }
     }
@@ -451,7 +752,8 @@ mod tests {
         }
 
         let svc = BookService::new(repo).with_changelog(Box::new(ArcChangeLog(cl_clone)));
-        let (id, warning) = svc.add_node(add_req("Node A")).await.expect("add_node");
+        let (placed, warning) = svc.add_node(add_req("Node A")).await.expect("add_node");
+        let id = placed.node_id;
 
         assert!(
             warning.is_none(),
@@ -465,6 +767,92 @@ mod tests {
         assert!(entries[0].after.is_some());
     }
 
+    #[tokio::test]
+    async fn read_tree_checked_loads_a_book_with_orphans_and_reports_them() {
+        let mut book = TemplateBook::new("Test", 4);
+        book.add_node(add_req("Kept")).unwrap();
+        let lost = book.add_node(add_req("Lost")).unwrap();
+        // ルート一覧から外れたノード（手編集や古いバグで生じる）
+        let mut json = serde_json::to_value(&book).unwrap();
+        json["root_nodes"]
+            .as_array_mut()
+            .unwrap()
+            .retain(|id| id.as_str() != Some(lost.to_string().as_str()));
+        let orphaned: TemplateBook = serde_json::from_value(json).unwrap();
+        let svc = BookService::new(InMemoryBookRepo::with_book(orphaned));
+
+        let (loaded, mismatch) = svc.read_tree_checked().await.expect("still loads");
+        assert_eq!(loaded.node_count(), 2);
+        let mismatch = mismatch.expect("orphan reported");
+        assert_eq!((mismatch.stored, mismatch.reachable), (2, 1));
+
+        let svc = BookService::new(InMemoryBookRepo::with_book(book));
+        assert!(svc.read_tree_checked().await.unwrap().1.is_none());
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "book integrity violated on save")]
+    async fn saving_a_corrupt_book_panics_under_integrity_checks() {
+        let mut book = TemplateBook::new("Test", 4);
+        let kept = book.add_node(add_req("Kept")).unwrap();
+        // 同じ子を2つの親に並べた Book（JSON の手編集で生じうる）
+        let child = book
+            .add_node(AddNodeRequest {
+                parent: Some(kept),
+                ..add_req("Child")
+            })
+            .unwrap();
+        let other = book.add_node(add_req("Other")).unwrap();
+        let mut json = serde_json::to_value(&book).unwrap();
+        json["nodes"][other.to_string()]["children"] = serde_json::json!([child.to_string()]);
+        let corrupt: TemplateBook = serde_json::from_value(json).unwrap();
+        let svc = BookService::new(InMemoryBookRepo::with_book(corrupt));
+
+        let _ = svc
+            .with_book(|book| {
+                let id = book.add_node(add_req("New"))?;
+                Ok::<_, AppError>(((), vec![(id, ChangeAction::Create)]))
+            })
+            .await;
+    }
+
+    #[tokio::test]
+    async fn with_book_saves_once_and_rejections_save_nothing() {
+        let repo = InMemoryBookRepo::with_book(TemplateBook::new("Test", 4));
+        let svc =
+            BookService::new(repo.clone()).with_changelog(Box::new(RecordingChangeLog::failing()));
+
+        let (id, warnings) = svc
+            .with_book(|book| {
+                let id = book.add_node(add_req("A"))?;
+                Ok::<_, AppError>((id, vec![(id, ChangeAction::Create)]))
+            })
+            .await
+            .expect("load/save")
+            .expect("closure");
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].is_some(), "changelog failure is surfaced");
+        assert!(repo
+            .book
+            .lock()
+            .unwrap()
+            .as_ref()
+            .unwrap()
+            .get_node(id)
+            .is_some());
+
+        // クロージャが拒否した場合は途中の変更も保存しない
+        let rejected = svc
+            .with_book(|book| {
+                book.add_node(add_req("B")).unwrap();
+                Err::<((), Vec<(NodeId, ChangeAction)>), _>("rejected")
+            })
+            .await
+            .expect("load/save");
+        assert_eq!(rejected.unwrap_err(), "rejected");
+        assert_eq!(repo.book.lock().unwrap().as_ref().unwrap().node_count(), 1);
+    }
+
     #[tokio::test]
     async fn test_add_node_changelog_failure_produces_warning() {
         let book = TemplateBook::new("Test", 4);
@@ -489,14 +877,11 @@ mod tests {
         let cl = RecordingChangeLog::new();
         let svc = BookService::new(repo).with_changelog(Box::new(cl));
 
-        let (id, _) = svc.add_node(add_req("original title")).await.expect("add");
+        let (placed, _) = svc.add_node(add_req("original title")).await.expect("add");
+        let id = placed.node_id;
         let update_req = UpdateNodeRequest {
             title: Some("updated title".to_string()),
-            body: None,
-            node_type: None,
-            placeholder: None,
-            properties: None,
-            status: None,
+            ..Default::default()
         };
         let ((), warning) = svc.update_node(id, update_req).await.expect("update");
         assert!(warning.is_none());
@@ -509,11 +894,159 @@ mod tests {
         let cl = RecordingChangeLog::new();
         let svc = BookService::new(repo).with_changelog(Box::new(cl));
 
-        let (id, _) = svc.add_node(add_req("to be removed")).await.expect("add");
+        let (placed, _) = svc.add_node(add_req("to be removed")).await.expect("add");
+        let id = placed.node_id;
         let ((), warning) = svc.remove_node(id).await.expect("remove");
         assert!(warning.is_none());
     }
 
+    #[tokio::test]
+    async fn test_set_dependency_reports_no_op() {
+        let book = TemplateBook::new("Test", 4);
+        let repo = InMemoryBookRepo::with_book(book);
+        let svc = BookService::new(repo);
+
+        let a = svc.add_node(add_req("A")).await.expect("add").0.node_id;
+        let b = svc.add_node(add_req("B")).await.expect("add").0.node_id;
+        let (changed, _) = svc.set_dependency(b, a, true).await.expect("depend");
+        assert!(changed);
+        let (changed, _) = svc.set_dependency(b, a, true).await.expect("depend");
+        assert!(!changed);
+
+        let book = svc.read_tree().await.unwrap();
+        assert_eq!(book.blocked_by(b), vec![a]);
+
+        let (changed, _) = svc.set_dependency(b, a, false).await.expect("undepend");
+        assert!(changed);
+        assert!(svc.read_tree().await.unwrap().blocked_by(b).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_add_node_with_children_is_all_or_nothing() {
+        let book = TemplateBook::new("Test", 2);
+        let repo = InMemoryBookRepo::with_book(book);
+        let svc = BookService::new(repo);
+
+        let (placed, _) = svc.add_node(add_req("section")).await.expect("add");
+        let section = placed.node_id;
+        let mut nested = add_req("nested");
+        nested.parent = Some(section);
+
+        // section 直下の nested は深さ2、その子は深さ3 → 何も作らない
+        let err = svc
+            .add_node_with_children(nested, vec![add_req("step")])
+            .await;
+        assert!(err.is_err());
+        assert_eq!(svc.read_tree().await.unwrap().node_count(), 1);
+
+        let ((placed, children), warnings) = svc
+            .add_node_with_children(add_req("rollback"), vec![add_req("a"), add_req("b")])
+            .await
+            .expect("add with children");
+        let id = placed.node_id;
+        assert_eq!(warnings.len(), 3);
+        let book = svc.read_tree().await.unwrap();
+        assert_eq!(book.get_node(id).unwrap().children(), children.as_slice());
+    }
+
+    #[tokio::test]
+    async fn test_update_and_move_rolls_back_on_invalid_move() {
+        let book = TemplateBook::new("Test", 4);
+        let repo = InMemoryBookRepo::with_book(book);
+        let svc = BookService::new(repo);
+
+        let (placed, _) = svc.add_node(add_req("parent")).await.expect("add");
+        let parent = placed.node_id;
+        let mut child_req = add_req("child");
+        child_req.parent = Some(parent);
+        let child = svc.add_node(child_req).await.expect("add").0.node_id;
+
+        let rename = |t: &str| UpdateNodeRequest {
+            title: Some(t.to_string()),
+            ..Default::default()
+        };
+
+        // 自身の子孫の下へは移動できない → 改名も保存されない
+        let err = svc
+            .update_and_move(parent, rename("renamed"), (Some(child), Position::Last))
+            .await;
+        assert!(err.is_err());
+        let book = svc.read_tree().await.unwrap();
+        assert_eq!(book.get_node(parent).unwrap().title(), "parent");
+
+        let (placed, warnings) = svc
+            .update_and_move(child, rename("moved child"), (None, Position::At(0)))
+            .await
+            .expect("update_and_move");
+        assert_eq!(warnings.len(), 2);
+        assert_eq!(placed.index, 0);
+        let book = svc.read_tree().await.unwrap();
+        let node = book.get_node(child).unwrap();
+        assert_eq!(node.title(), "moved child");
+        assert_eq!(node.parent(), None);
+        assert_eq!(book.root_nodes()[0], child);
+    }
+
+    #[tokio::test]
+    async fn test_update_matching_limits_to_subtree_and_saves_once() {
+        let book = TemplateBook::new("Test", 4);
+        let repo = InMemoryBookRepo::with_book(book);
+        let cl = RecordingChangeLog::new();
+        let svc = BookService::new(repo).with_changelog(Box::new(cl));
+
+        let (placed, _) = svc.add_node(add_req("run")).await.expect("add");
+        let run = placed.node_id;
+        let mut step = add_req("step");
+        step.parent = Some(run);
+        svc.add_node(step).await.expect("add");
+        svc.add_node(add_req("outside")).await.expect("add");
+
+        let done = |_: &TemplateNode| UpdateNodeRequest {
+            done: Some(true),
+            ..Default::default()
+        };
+        let (count, warnings) = svc
+            .update_matching(Some(run), |_| true, done)
+            .await
+            .expect("update_matching");
+        assert_eq!(count, 2);
+        assert_eq!(warnings.len(), 2);
+        let book = svc.read_tree().await.unwrap();
+        let checked: Vec<&str> = book
+            .all_nodes_dfs()
+            .into_iter()
+            .filter(|n| n.is_done())
+            .map(|n| n.title())
+            .collect();
+        assert_eq!(checked, vec!["run", "step"]);
+
+        // 該当なしは 0 件（エラーにしない）
+        let (count, _) = svc
+            .update_matching(None, |n| n.title() == "missing", done)
+            .await
+            .expect("no match");
+        assert_eq!(count, 0);
+        assert!(svc
+            .update_matching(Some(NodeId::new()), |_| true, done)
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_exists_and_delete_book() {
+        let svc = BookService::new(InMemoryBookRepo::empty());
+        assert!(!svc.exists().await.unwrap());
+
+        svc.create_book("Test", 4).await.unwrap();
+        assert!(svc.exists().await.unwrap());
+
+        svc.delete_book().await.unwrap();
+        assert!(!svc.exists().await.unwrap());
+        assert!(matches!(svc.read_tree().await, Err(AppError::BookNotFound)));
+        // 二重削除はエラーにしない
+        svc.delete_book().await.unwrap();
+    }
+
     #[tokio::test]
     async fn test_move_node_records_move() {
         let book = TemplateBook::new("Test", 4);
@@ -521,9 +1054,16 @@ mod tests {
         let cl = RecordingChangeLog::new();
         let svc = BookService::new(repo).with_changelog(Box::new(cl));
 
-        let (id, _) = svc.add_node(add_req("node to move")).await.expect("add");
-        let ((), warning) = svc.move_node(id, None, 0).await.expect("move");
+        let (placed, _) = svc.add_node(add_req("node to move")).await.expect("add");
+        let id = placed.node_id;
+        let (placed, warning) = svc
+            .move_node(id, None, Position::At(7))
+            .await
+            .expect("move");
         assert!(warning.is_none());
+        // ルートは1件だけ → 7 は 0 に丸められる
+        assert_eq!((placed.index, placed.requested), (0, Position::At(7)));
+        assert!(placed.was_clamped());
     }
 
     #[tokio::test]
@@ -540,7 +1080,7 @@ mod tests {
         let book = TemplateBook::new("Test", 4);
         let repo = InMemoryBookRepo::with_book(book);
         let svc = BookService::new(repo); // with_changelog なし
-        let (id, _) = svc.add_node(add_req("x")).await.expect("add");
+        let id = svc.add_node(add_req("x")).await.expect("add").0.node_id;
         let tree = svc.read_tree().await.expect("read_tree");
         assert!(tree.get_node(id).is_some());
     }
@@ -570,12 +1110,14 @@ mod tests {
         let repo = InMemoryBookRepo::with_book(book);
         let svc = BookService::new(repo);
 
-        let (id_a, _) = svc.add_node(add_req("Node A")).await.expect("add_node A");
-        let (id_b, _) = svc.add_node(add_req("Node B")).await.expect("add_node B");
+        let (placed, _) = svc.add_node(add_req("Node A")).await.expect("add_node A");
+        let id_a = placed.node_id;
+        let (placed, _) = svc.add_node(add_req("Node B")).await.expect("add_node B");
+        let id_b = placed.node_id;
 
         // Move A under B at position 0
         let (count, warnings) = svc
-            .batch_move(vec![(id_a, Some(id_b), 0)])
+            .batch_move(vec![(id_a, Some(id_b), Position::At(0))])
             .await
             .expect("batch_move");
         assert_eq!(count, 1);
@@ -588,7 +1130,8 @@ mod tests {
         let repo = InMemoryBookRepo::with_book(book);
         let svc = BookService::new(repo);
 
-        let (id_a, _) = svc.add_node(add_req("Node A")).await.expect("add_node A");
+        let (placed, _) = svc.add_node(add_req("Node A")).await.expect("add_node A");
+        let id_a = placed.node_id;
 
         // fake NodeId that doesn't exist
         let fake_id: NodeId = serde_json::from_value(serde_json::Value::String(
@@ -598,7 +1141,10 @@ mod tests {
 
         // Attempt to move A and then a nonexistent node — should fail
         let result = svc
-            .batch_move(vec![(id_a, None, 0), (fake_id, None, 0)])
+            .batch_move(vec![
+                (id_a, None, Position::At(0)),
+                (fake_id, None, Position::At(0)),
+            ])
             .await;
         assert!(result.is_err(), "batch_move with invalid node should fail");
     }
@@ -610,11 +1156,13 @@ mod tests {
         let cl = RecordingChangeLog::new();
         let svc = BookService::new(repo).with_changelog(Box::new(cl));
 
-        let (id_a, _) = svc.add_node(add_req("Node A")).await.expect("add_node A");
-        let (id_b, _) = svc.add_node(add_req("Node B")).await.expect("add_node B");
+        let (placed, _) = svc.add_node(add_req("Node A")).await.expect("add_node A");
+        let id_a = placed.node_id;
+        let (placed, _) = svc.add_node(add_req("Node B")).await.expect("add_node B");
+        let id_b = placed.node_id;
 
         let (count, _warnings) = svc
-            .batch_move(vec![(id_a, Some(id_b), 0)])
+            .batch_move(vec![(id_a, Some(id_b), Position::At(0))])
             .await
             .expect("batch_move");
         assert_eq!(count, 1);
@@ -639,15 +1187,12 @@ mod tests {
         let repo = InMemoryBookRepo::with_book(book);
         let svc = BookService::new(repo);
 
-        let (id, _) = svc.add_node(add_req("Original")).await.expect("add_node");
+        let (placed, _) = svc.add_node(add_req("Original")).await.expect("add_node");
+        let id = placed.node_id;
 
         let req = UpdateNodeRequest {
             title: Some("Updated".to_string()),
-            body: None,
-            node_type: None,
-            placeholder: None,
-            properties: None,
-            status: None,
+            ..Default::default()
         };
         let (count, warnings) = svc
             .batch_update(vec![(id, req)])
@@ -666,30 +1211,25 @@ mod tests {
         let repo = InMemoryBookRepo::with_book(book);
         let svc = BookService::new(repo);
 
-        let (id_a, _) = svc.add_node(add_req("Alpha")).await.expect("add_node A");
-        let (id_b, _) = svc.add_node(add_req("Beta")).await.expect("add_node B");
+        let (placed, _) = svc.add_node(add_req("Alpha")).await.expect("add_node A");
+        let id_a = placed.node_id;
+        let (placed, _) = svc.add_node(add_req("Beta")).await.expect("add_node B");
+        let id_b = placed.node_id;
 
         let updates = vec![
             (
                 id_a,
                 UpdateNodeRequest {
                     title: Some("Alpha Updated".to_string()),
-                    body: None,
-                    node_type: None,
-                    placeholder: None,
-                    properties: None,
-                    status: None,
+                    ..Default::default()
                 },
             ),
             (
                 id_b,
                 UpdateNodeRequest {
                     title: Some("Beta Updated".to_string()),
-                    body: None,
-                    node_type: None,
-                    placeholder: None,
-                    properties: None,
                     status: Some(NodeStatus::Draft),
+                    ..Default::default()
                 },
             ),
         ];
@@ -712,7 +1252,8 @@ mod tests {
         let repo = InMemoryBookRepo::with_book(book);
         let svc = BookService::new(repo);
 
-        let (id_a, _) = svc.add_node(add_req("Node A")).await.expect("add_node A");
+        let (placed, _) = svc.add_node(add_req("Node A")).await.expect("add_node A");
+        let id_a = placed.node_id;
 
         let fake_id: NodeId = serde_json::from_value(serde_json::Value::String(
             "ffffffff-ffff-ffff-ffff-ffffffffffff".to_string(),
@@ -725,22 +1266,14 @@ mod tests {
                     id_a,
                     UpdateNodeRequest {
                         title: Some("A Updated".to_string()),
-                        body: None,
-                        node_type: None,
-                        placeholder: None,
-                        properties: None,
-                        status: None,
+                        ..Default::default()
                     },
                 ),
                 (
                     fake_id,
                     UpdateNodeRequest {
                         title: Some("Fake".to_string()),
-                        body: None,
-                        node_type: None,
-                        placeholder: None,
-                        properties: None,
-                        status: None,
+                        ..Default::default()
                     },
                 ),
             ])
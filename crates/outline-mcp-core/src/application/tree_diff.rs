@@ -0,0 +1,326 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::domain::model::book::TemplateBook;
+use crate::domain::model::id::NodeId;
+use crate::domain::model::node::TemplateNode;
+
+/// ノード単位の変更の種類。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChangeKind {
+    /// `after` にのみ存在する。
+    Added,
+    /// `before` にのみ存在する。
+    Removed,
+    /// 両方に存在し、内容（タイトル・本文など）が変わった。
+    Modified,
+    /// 両方に存在し、親が変わった。
+    Moved,
+}
+
+/// 2つの Book 間の1ノード分の変更。
+///
+/// 内容変更と移動が同時に起きたノードは `Modified` と `Moved` の2件になる。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NodeChange {
+    /// The changed node.
+    pub node_id: NodeId,
+    /// What happened to it.
+    pub kind: ChangeKind,
+    /// Node title (from `after`, or from `before` for removed nodes).
+    pub title: String,
+    /// Title of the top-level node the change lives under (the node's own
+    /// title when it is top-level itself).
+    pub group: String,
+    /// Changed fields, for `Modified` (e.g. `["title", "body"]`).
+    pub fields: Vec<&'static str>,
+}
+
+/// `before` → `after` のノード単位の差分を返す。
+///
+/// 順序: `after` の DFS 順に追加・変更・移動、続いて `before` の DFS 順に削除。
+/// 兄弟内の並び替えだけの変化は移動として扱わない（挿入のたびに後続の兄弟が
+/// すべて「移動」扱いになるのを避けるため）。
+pub fn diff_books(before: &TemplateBook, after: &TemplateBook) -> Vec<NodeChange> {
+    let mut changes = Vec::new();
+    for node in after.all_nodes_dfs() {
+        let group = group_title(after, node);
+        let Some(old) = before.get_node(node.id()) else {
+            changes.push(change(node, ChangeKind::Added, group, Vec::new()));
+            continue;
+        };
+        let fields = changed_fields(old, node);
+        if !fields.is_empty() {
+            changes.push(change(node, ChangeKind::Modified, group.clone(), fields));
+        }
+        if old.parent() != node.parent() {
+            changes.push(change(node, ChangeKind::Moved, group, Vec::new()));
+        }
+    }
+    let remaining: HashSet<NodeId> = after.all_nodes_dfs().iter().map(|n| n.id()).collect();
+    for node in before.all_nodes_dfs() {
+        if !remaining.contains(&node.id()) {
+            let group = group_title(before, node);
+            changes.push(change(node, ChangeKind::Removed, group, Vec::new()));
+        }
+    }
+    changes
+}
+
+/// ルートからのタイトル列。同名の兄弟は何番目か（0 始まり）で区別する。
+type TitlePath = Vec<(String, usize)>;
+
+/// ID ではなくタイトルの道筋でノードを突き合わせる差分。
+///
+/// エクスポートを読み直した Book のように ID が振り直された相手との比較用。
+/// タイトルが変わったノードは削除と追加になり、`Moved` は出ない。依存関係は
+/// ID で持つため比較しない。順序は [`diff_books`] と同じ。
+pub fn diff_books_by_path(before: &TemplateBook, after: &TemplateBook) -> Vec<NodeChange> {
+    let before_paths = title_paths(before);
+    let by_path: HashMap<&TitlePath, NodeId> =
+        before_paths.iter().map(|(id, path)| (path, *id)).collect();
+    let after_paths = title_paths(after);
+    let mut matched = HashSet::new();
+    let mut changes = Vec::new();
+    for node in after.all_nodes_dfs() {
+        let group = group_title(after, node);
+        let old = after_paths
+            .get(&node.id())
+            .and_then(|path| by_path.get(path))
+            .and_then(|id| before.get_node(*id));
+        let Some(old) = old else {
+            changes.push(change(node, ChangeKind::Added, group, Vec::new()));
+            continue;
+        };
+        matched.insert(old.id());
+        let mut fields = changed_fields(old, node);
+        fields.retain(|f| *f != "dependencies");
+        if !fields.is_empty() {
+            changes.push(change(node, ChangeKind::Modified, group, fields));
+        }
+    }
+    for node in before.all_nodes_dfs() {
+        if !matched.contains(&node.id()) {
+            let group = group_title(before, node);
+            changes.push(change(node, ChangeKind::Removed, group, Vec::new()));
+        }
+    }
+    changes
+}
+
+fn title_paths(book: &TemplateBook) -> HashMap<NodeId, TitlePath> {
+    fn walk(
+        book: &TemplateBook,
+        ids: &[NodeId],
+        prefix: &TitlePath,
+        paths: &mut HashMap<NodeId, TitlePath>,
+    ) {
+        let mut seen: HashMap<&str, usize> = HashMap::new();
+        for node in ids.iter().filter_map(|id| book.get_node(*id)) {
+            let nth = seen.entry(node.title()).or_default();
+            let mut path = prefix.clone();
+            path.push((node.title().to_string(), *nth));
+            *nth += 1;
+            walk(book, node.children(), &path, paths);
+            paths.insert(node.id(), path);
+        }
+    }
+    let mut paths = HashMap::new();
+    walk(book, book.root_nodes(), &Vec::new(), &mut paths);
+    paths
+}
+
+fn change(
+    node: &TemplateNode,
+    kind: ChangeKind,
+    group: String,
+    fields: Vec<&'static str>,
+) -> NodeChange {
+    NodeChange {
+        node_id: node.id(),
+        kind,
+        title: node.title().to_string(),
+        group,
+        fields,
+    }
+}
+
+/// ノードが属するトップレベルノードのタイトル。
+fn group_title(book: &TemplateBook, node: &TemplateNode) -> String {
+    book.ancestors(node.id())
+        .first()
+        .map(|top| top.title())
+        .unwrap_or(node.title())
+        .to_string()
+}
+
+fn changed_fields(old: &TemplateNode, new: &TemplateNode) -> Vec<&'static str> {
+    let mut fields = Vec::new();
+    if old.title() != new.title() {
+        fields.push("title");
+    }
+    if old.body() != new.body() {
+        fields.push("body");
+    }
+    if old.node_type() != new.node_type() {
+        fields.push("type");
+    }
+    if old.placeholder() != new.placeholder() {
+        fields.push("placeholder");
+    }
+    if old.properties() != new.properties() {
+        fields.push("properties");
+    }
+    if old.status() != new.status() {
+        fields.push("status");
+    }
+    if old.notes() != new.notes() {
+        fields.push("notes");
+    }
+    if old.note() != new.note() {
+        fields.push("note");
+    }
+    if old.estimate_minutes() != new.estimate_minutes() {
+        fields.push("estimate");
+    }
+    if old.owner() != new.owner() {
+        fields.push("owner");
+    }
+    if old.is_done() != new.is_done() {
+        fields.push("done");
+    }
+    if old.is_ordered() != new.is_ordered() {
+        fields.push("ordered");
+    }
+    if old.order() != new.order() {
+        fields.push("order");
+    }
+    if old.command() != new.command() {
+        fields.push("command");
+    }
+    if old.links() != new.links() {
+        fields.push("links");
+    }
+    if old.heading_level() != new.heading_level() {
+        fields.push("heading_level");
+    }
+    if old.is_protected() != new.is_protected() {
+        fields.push("protected");
+    }
+    if old.depends_on() != new.depends_on() {
+        fields.push("dependencies");
+    }
+    fields
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::domain::model::book::{AddNodeRequest, Position, UpdateNodeRequest};
+    use crate::domain::model::node::NodeType;
+
+    fn add(book: &mut TemplateBook, parent: Option<NodeId>, title: &str, ty: NodeType) -> NodeId {
+        book.add_node(AddNodeRequest {
+            parent,
+            title: title.into(),
+            node_type: ty,
+            body: None,
+            placeholder: None,
+            position: Position::Last,
+            properties: HashMap::new(),
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn identical_books_have_no_changes() {
+        let mut book = TemplateBook::new("Same", 4);
+        let s = add(&mut book, None, "Design", NodeType::Section);
+        add(&mut book, Some(s), "API", NodeType::Content);
+        assert!(diff_books(&book, &book.clone()).is_empty());
+    }
+
+    #[test]
+    fn reports_added_removed_modified_and_moved_by_group() {
+        let mut before = TemplateBook::new("Runbook", 4);
+        let design = add(&mut before, None, "Design", NodeType::Section);
+        let ops = add(&mut before, None, "Ops", NodeType::Section);
+        let api = add(&mut before, Some(design), "API", NodeType::Content);
+        let gone = add(&mut before, Some(design), "Obsolete", NodeType::Content);
+        let wander = add(&mut before, Some(design), "Deploy", NodeType::Content);
+
+        let mut after = before.clone();
+        after.remove_node(gone).unwrap();
+        after
+            .update_node(
+                api,
+                UpdateNodeRequest {
+                    title: Some("API v2".into()),
+                    body: Some(Some("REST".into())),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        after.move_node(wander, Some(ops), Position::Last).unwrap();
+        let fresh = add(&mut after, Some(ops), "Rollback", NodeType::Content);
+
+        let changes = diff_books(&before, &after);
+        let summary: Vec<(NodeId, ChangeKind, &str)> = changes
+            .iter()
+            .map(|c| (c.node_id, c.kind, c.group.as_str()))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (api, ChangeKind::Modified, "Design"),
+                (wander, ChangeKind::Moved, "Ops"),
+                (fresh, ChangeKind::Added, "Ops"),
+                (gone, ChangeKind::Removed, "Design"),
+            ]
+        );
+        assert_eq!(changes[0].fields, vec!["title", "body"]);
+        assert_eq!(changes[0].title, "API v2");
+    }
+
+    #[test]
+    fn path_diff_matches_nodes_across_fresh_ids() {
+        use crate::application::eject::EjectService;
+
+        let mut before = TemplateBook::new("Runbook", 4);
+        let design = add(&mut before, None, "Design", NodeType::Section);
+        add(&mut before, Some(design), "Step", NodeType::Content);
+        add(&mut before, Some(design), "Step", NodeType::Content);
+        let gone = add(&mut before, None, "Obsolete", NodeType::Content);
+
+        let tree = EjectService::build_tree(&before, None);
+        let mut after = EjectService::import_tree(&tree).unwrap();
+        assert!(diff_books_by_path(&before, &after).is_empty());
+
+        let second_step = after.get_node(after.root_nodes()[0]).unwrap().children()[1];
+        after
+            .update_node(
+                second_step,
+                UpdateNodeRequest {
+                    body: Some(Some("twice".into())),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        after.remove_node(after.root_nodes()[1]).unwrap();
+        let fresh = add(&mut after, None, "Rollback", NodeType::Content);
+
+        let changes = diff_books_by_path(&before, &after);
+        let summary: Vec<(NodeId, ChangeKind)> =
+            changes.iter().map(|c| (c.node_id, c.kind)).collect();
+        assert_eq!(
+            summary,
+            vec![
+                (second_step, ChangeKind::Modified),
+                (fresh, ChangeKind::Added),
+                (gone, ChangeKind::Removed),
+            ]
+        );
+        assert_eq!(changes[0].fields, vec!["body"]);
+    }
+}
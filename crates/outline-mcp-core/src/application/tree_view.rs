@@ -0,0 +1,146 @@
+use serde::Serialize;
+
+use crate::domain::model::book::TemplateBook;
+use crate::domain::model::id::NodeId;
+
+use super::eject::{EjectService, EjectTreeNode};
+
+/// UI 描画用の Book 全体（または部分木）の読み取りモデル。
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TreeView {
+    /// Book title, or the subtree root's title.
+    pub title: String,
+    /// Configured maximum tree depth.
+    pub max_depth: u8,
+    /// Number of nodes in `nodes` and all their descendants.
+    pub node_count: usize,
+    /// Top-level nodes of the view, in order.
+    pub nodes: Vec<TreeViewNode>,
+}
+
+/// [`TreeView`] の1ノード。階層番号と深さは部分木でも Book 全体での値。
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TreeViewNode {
+    /// Node ID as a UUID string.
+    pub uuid: String,
+    /// Outline number as shown by `toc` (e.g. `"2-3"`).
+    pub hierarchical_id: String,
+    /// Node title.
+    pub title: String,
+    /// `"section"` or `"content"`.
+    pub node_type: String,
+    /// Markdown body, `null` when unset.
+    pub body: Option<String>,
+    /// Placeholder hint, `null` when unset.
+    pub placeholder: Option<String>,
+    /// Level in the book (top-level nodes are 1).
+    pub depth: u8,
+    /// Child nodes, in order.
+    pub children: Vec<TreeViewNode>,
+}
+
+/// [`EjectService::build_tree`] の出力に階層番号と深さを足して [`TreeView`] を作る。
+///
+/// `root` を渡すとその子以下を返す（`checklist` の部分木出力と同じ範囲）。
+pub fn build_tree_view(book: &TemplateBook, root: Option<NodeId>) -> TreeView {
+    let tree = EjectService::build_tree(book, root);
+    let (prefix, depth) = match root {
+        Some(id) => (book.outline_number(id), book.depth_of(id) + 1),
+        None => (None, 1),
+    };
+    let nodes: Vec<TreeViewNode> = tree
+        .nodes
+        .into_iter()
+        .enumerate()
+        .map(|(i, node)| view_node(node, prefix.as_deref(), i, depth))
+        .collect();
+    TreeView {
+        title: tree.title,
+        max_depth: tree.max_depth,
+        node_count: nodes.iter().map(count).sum(),
+        nodes,
+    }
+}
+
+fn view_node(node: EjectTreeNode, prefix: Option<&str>, index: usize, depth: u8) -> TreeViewNode {
+    let hierarchical_id = match prefix {
+        Some(p) => format!("{p}-{}", index + 1),
+        None => (index + 1).to_string(),
+    };
+    let children = node
+        .children
+        .into_iter()
+        .enumerate()
+        .map(|(i, child)| view_node(child, Some(&hierarchical_id), i, depth.saturating_add(1)))
+        .collect();
+    TreeViewNode {
+        uuid: node.id,
+        hierarchical_id,
+        title: node.title,
+        node_type: node.node_type,
+        body: node.body,
+        placeholder: node.placeholder,
+        depth,
+        children,
+    }
+}
+
+fn count(node: &TreeViewNode) -> usize {
+    1 + node.children.iter().map(count).sum::<usize>()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::domain::model::book::{AddNodeRequest, Position};
+    use crate::domain::model::node::NodeType;
+
+    fn add(book: &mut TemplateBook, parent: Option<NodeId>, title: &str, ty: NodeType) -> NodeId {
+        book.add_node(AddNodeRequest {
+            parent,
+            title: title.into(),
+            node_type: ty,
+            body: None,
+            placeholder: None,
+            position: Position::Last,
+            properties: HashMap::new(),
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn carries_outline_numbers_and_depths_matching_the_book() {
+        let mut book = TemplateBook::new("Spec", 4);
+        add(&mut book, None, "Intro", NodeType::Section);
+        let design = add(&mut book, None, "Design", NodeType::Section);
+        let api = add(&mut book, Some(design), "API", NodeType::Section);
+        let auth = add(&mut book, Some(api), "Auth", NodeType::Content);
+
+        let view = build_tree_view(&book, None);
+        assert_eq!(view.title, "Spec");
+        assert_eq!(view.node_count, 4);
+        let leaf = &view.nodes[1].children[0].children[0];
+        assert_eq!(leaf.uuid, auth.to_string());
+        assert_eq!(leaf.hierarchical_id, "2-1-1");
+        assert_eq!(leaf.node_type, "content");
+        assert_eq!(leaf.depth, 3);
+        assert_eq!(
+            Some(leaf.hierarchical_id.clone()),
+            book.outline_number(auth)
+        );
+
+        // 部分木でも Book 全体の番号と深さのまま
+        let sub = build_tree_view(&book, Some(design));
+        assert_eq!(sub.title, "Design");
+        assert_eq!(sub.node_count, 2);
+        assert_eq!(sub.nodes[0].hierarchical_id, "2-1");
+        assert_eq!(sub.nodes[0].depth, 2);
+        assert_eq!(sub.nodes[0].children[0].hierarchical_id, "2-1-1");
+
+        let json = serde_json::to_value(&view).unwrap();
+        assert_eq!(json["nodes"][0]["body"], serde_json::Value::Null);
+        assert_eq!(json["nodes"][1]["children"][0]["hierarchical_id"], "2-1");
+    }
+}
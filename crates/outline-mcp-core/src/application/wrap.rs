@@ -0,0 +1,199 @@
+/// Markdown テキストの行を `width` 文字で折り返す。
+///
+/// 各行を独立に扱うので、既存の改行（ハードブレークを含む）はそのまま残る。
+/// `width` 以下の行、見出し（`#`）、表の行（`|`）、フェンス（```` ``` ```` / `~~~`）
+/// 内の行は変更しない。折り返した続きの行は元の行頭の空白と揃え、リスト項目
+/// （`- `, `* `, `+ `, `1. `, `- [ ] `）はマーカーの後ろの本文の位置に揃える。
+/// バッククォートのコードスパンと空白を含まない語（URL など）は途中で切らず、
+/// 1語で `width` を超える場合ははみ出させる。`width` が 0 なら何もしない。
+pub fn wrap_markdown(text: &str, width: usize) -> String {
+    if width == 0 {
+        return text.to_string();
+    }
+    let mut out = String::with_capacity(text.len());
+    let mut fence: Option<&str> = None;
+    for (i, line) in text.split('\n').enumerate() {
+        if i > 0 {
+            out.push('\n');
+        }
+        let trimmed = line.trim_start();
+        if let Some(open) = fence {
+            if trimmed.starts_with(open) {
+                fence = None;
+            }
+            out.push_str(line);
+            continue;
+        }
+        if let Some(marker) = ["```", "~~~"].into_iter().find(|m| trimmed.starts_with(m)) {
+            fence = Some(marker);
+            out.push_str(line);
+            continue;
+        }
+        if line.chars().count() <= width || trimmed.starts_with('#') || trimmed.starts_with('|') {
+            out.push_str(line);
+            continue;
+        }
+        wrap_line(line, width, &mut out);
+    }
+    out
+}
+
+/// 1行を折り返して `out` に書く（末尾の改行は付けない）。
+fn wrap_line(line: &str, width: usize, out: &mut String) {
+    let (prefix, rest) = split_prefix(line);
+    let hanging = " ".repeat(prefix.chars().count());
+    // 行末の2つ以上の空白はハードブレークなので最後の行に残す
+    let hard_break = line.ends_with("  ");
+    let atoms = atoms(rest);
+
+    let mut current = prefix.to_string();
+    let mut current_len = current.chars().count();
+    let mut empty = true;
+    for atom in atoms {
+        let len = atom.chars().count();
+        if !empty && current_len + 1 + len > width {
+            out.push_str(&current);
+            out.push('\n');
+            current = hanging.clone();
+            current_len = current.chars().count();
+            empty = true;
+        }
+        if !empty {
+            current.push(' ');
+            current_len += 1;
+        }
+        current.push_str(atom);
+        current_len += len;
+        empty = false;
+    }
+    out.push_str(&current);
+    if hard_break {
+        out.push_str("  ");
+    }
+}
+
+/// 行頭の空白とリストマーカー（あれば）を本文から切り分ける。
+fn split_prefix(line: &str) -> (&str, &str) {
+    let body = line.trim_start();
+    let mut at = line.len() - body.len();
+    let bullet = ["- ", "* ", "+ "].into_iter().find(|m| body.starts_with(m));
+    let marker_len = match bullet {
+        Some(m) => m.len(),
+        None => {
+            let digits = body.bytes().take_while(u8::is_ascii_digit).count();
+            let after = &body[digits..];
+            if digits > 0 && (after.starts_with(". ") || after.starts_with(") ")) {
+                digits + 2
+            } else {
+                0
+            }
+        }
+    };
+    at += marker_len;
+    if marker_len > 0 {
+        let after = &line[at..];
+        if let Some(task) = ["[ ] ", "[x] ", "[X] "]
+            .into_iter()
+            .find(|t| after.starts_with(t))
+        {
+            at += task.len();
+        }
+    }
+    (&line[..at], &line[at..])
+}
+
+/// 空白で区切った語の列。バッククォートのコードスパンは中の空白ごと1語として扱う。
+fn atoms(text: &str) -> Vec<&str> {
+    let bytes = text.as_bytes();
+    let mut atoms = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b' ' {
+            i += 1;
+            continue;
+        }
+        let start = i;
+        while i < bytes.len() && bytes[i] != b' ' {
+            if bytes[i] == b'`' {
+                let ticks = bytes[i..].iter().take_while(|b| **b == b'`').count();
+                let delim = &text[i..i + ticks];
+                match find_closing(&text[i + ticks..], delim) {
+                    Some(end) => i += ticks + end + ticks,
+                    None => i += ticks,
+                }
+            } else {
+                i += 1;
+            }
+        }
+        atoms.push(&text[start..i]);
+    }
+    atoms
+}
+
+/// `delim` と同じ長さのバッククォート列の位置（より長い列には一致しない）。
+fn find_closing(text: &str, delim: &str) -> Option<usize> {
+    let bytes = text.as_bytes();
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'`' {
+            let run = bytes[i..].iter().take_while(|b| **b == b'`').count();
+            if run == delim.len() {
+                return Some(i);
+            }
+            i += run;
+        } else {
+            i += 1;
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wraps_paragraphs_and_keeps_short_lines_and_hard_breaks() {
+        let text = "one two three four five six\nshort\nalpha beta gamma delta  \nend";
+        assert_eq!(
+            wrap_markdown(text, 12),
+            "one two\nthree four\nfive six\nshort\nalpha beta\ngamma delta  \nend"
+        );
+        assert_eq!(wrap_markdown(text, 0), text);
+    }
+
+    #[test]
+    fn list_items_continue_under_their_text() {
+        let text = "  - [ ] check the deploy logs carefully\n3. restart every worker now";
+        assert_eq!(
+            wrap_markdown(text, 20),
+            "  - [ ] check the\n        deploy logs\n        carefully\n\
+             3. restart every\n   worker now"
+        );
+    }
+
+    #[test]
+    fn code_fences_headings_and_tables_are_left_alone() {
+        let text = "```sh\ncargo build --workspace --all-targets\n```\n\
+                    # A very long heading that stays\n| a long | table row |";
+        assert_eq!(wrap_markdown(text, 10), text);
+
+        let tilde = "~~~\n``` not a closer here at all\n~~~\nwrap me please";
+        assert_eq!(
+            wrap_markdown(tilde, 10),
+            "~~~\n``` not a closer here at all\n~~~\nwrap me\nplease"
+        );
+    }
+
+    #[test]
+    fn long_urls_and_code_spans_overflow_instead_of_breaking() {
+        let text =
+            "see https://example.com/a/very/long/path/to/a/page for `cargo test --all` details";
+        assert_eq!(
+            wrap_markdown(text, 16),
+            "see\nhttps://example.com/a/very/long/path/to/a/page\nfor\n`cargo test --all`\ndetails"
+        );
+        // 閉じていないバッククォートは普通の文字として扱う
+        assert_eq!(wrap_markdown("a `b c d", 3), "a\n`b\nc d");
+    }
+}
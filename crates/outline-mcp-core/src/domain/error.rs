@@ -16,7 +16,66 @@ pub enum DomainError {
         max: u8,
     },
 
-    /// A move would place a node under one of its own descendants.
-    #[error("cannot move node {0} under its own descendant")]
-    CyclicMove(NodeId),
+    /// Adding nodes would exceed the book's `max_nodes` limit.
+    #[error("max_nodes {max} reached: the book already has {count} nodes (remove nodes or raise max_nodes)")]
+    MaxNodesExceeded {
+        /// The book's configured node limit.
+        max: u32,
+        /// Nodes currently in the book.
+        count: usize,
+    },
+
+    /// A book's max depth is outside `1..=TemplateBook::MAX_DEPTH_LIMIT`.
+    #[error("max_depth must be between 1 and {limit}, got {max_depth}")]
+    InvalidMaxDepth {
+        /// The rejected value.
+        max_depth: u8,
+        /// The largest accepted value.
+        limit: u8,
+    },
+
+    /// A move would place a node under itself or one of its own descendants.
+    #[error("cannot move node {node_id} under {new_parent} (itself or its own descendant)")]
+    CyclicMove {
+        /// The node being moved.
+        node_id: NodeId,
+        /// The requested new parent.
+        new_parent: NodeId,
+    },
+
+    /// A move passed the depth pre-check but left a branch deeper than
+    /// `max_depth`, because the stored tree is inconsistent (e.g. a
+    /// hand-edited file whose parent links disagree with its child lists).
+    /// The move is undone before this is returned.
+    #[error("moving node {node_id} would leave a branch at depth {depth} (max {max}): the stored tree is inconsistent")]
+    DepthInvariantBroken {
+        /// The node that was being moved.
+        node_id: NodeId,
+        /// Deepest level actually reached under the moved node.
+        depth: u8,
+        /// The book's configured maximum depth.
+        max: u8,
+    },
+
+    /// Removing a node would delete a protected node (itself or a descendant).
+    #[error("cannot remove node {removing}: it is or contains protected node {node_id}")]
+    ProtectedNode {
+        /// The protected node that would be deleted.
+        node_id: NodeId,
+        /// The node whose removal was requested.
+        removing: NodeId,
+    },
+
+    /// A dependency would make a node (transitively) depend on itself.
+    #[error("dependency of {node_id} on {on} would create a cycle")]
+    CyclicDependency {
+        /// The node that would gain the dependency.
+        node_id: NodeId,
+        /// The node it would depend on.
+        on: NodeId,
+    },
+
+    /// A link URL has no scheme (e.g. `example.com` instead of `https://example.com`).
+    #[error("invalid link url '{0}': expected a scheme such as https://")]
+    InvalidLinkUrl(String),
 }
@@ -1,9 +1,11 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
 
 use serde::{Deserialize, Serialize};
 
 use super::id::{BookId, NodeId};
 use super::node::{NodeType, TemplateNode};
+use super::timestamp::Timestamp;
 use crate::domain::error::DomainError;
 
 /// ノード追加リクエスト
@@ -18,13 +20,14 @@ pub struct AddNodeRequest {
     pub body: Option<String>,
     /// Optional placeholder hint used by `checklist`/`eject` export.
     pub placeholder: Option<String>,
-    /// 兄弟内での挿入位置（末尾ならusize::MAX）
-    pub position: usize,
+    /// 兄弟内での挿入位置
+    pub position: Position,
     /// Optional key-value properties (e.g. `inject`, `scope`).
     pub properties: HashMap<String, String>,
 }
 
 /// ノード更新リクエスト（Noneのフィールドは変更しない）
+#[derive(Default)]
 pub struct UpdateNodeRequest {
     /// New title, or `None` to keep the current one.
     pub title: Option<String>,
@@ -38,6 +41,104 @@ pub struct UpdateNodeRequest {
     pub properties: Option<HashMap<String, String>>,
     /// New lifecycle status, or `None` to keep the current one.
     pub status: Option<super::changelog::NodeStatus>,
+    /// New private notes: `Some(None)` clears them, `None` keeps the current value.
+    pub notes: Option<Option<String>>,
+    /// New public note: `Some(None)` clears it, `None` keeps the current value.
+    pub note: Option<Option<String>>,
+    /// New effort estimate in minutes: `Some(None)` clears it, `None` keeps the current value.
+    pub estimate_minutes: Option<Option<u32>>,
+    /// New owner: `Some(None)` clears it, `None` keeps the current value.
+    pub owner: Option<Option<String>>,
+    /// Check the node off (`true`) or reopen it (`false`); `None` keeps the current value.
+    pub done: Option<bool>,
+    /// Mark a Section's children as a sequence (`true`) or an unordered collection
+    /// (`false`); `None` keeps the current value.
+    pub ordered: Option<bool>,
+    /// New manual sort key among siblings: `Some(None)` clears it, `None` keeps
+    /// the current value.
+    pub order: Option<Option<f64>>,
+    /// Protect the node from removal (`Some(true)`) or lift the protection.
+    pub protected: Option<bool>,
+    /// New Markdown heading level override (1-6): `Some(None)` clears it,
+    /// `None` keeps the current value.
+    pub heading_level: Option<Option<usize>>,
+    /// New runnable shell command: `Some(None)` clears it, `None` keeps the
+    /// current value.
+    pub command: Option<Option<String>>,
+}
+
+/// ノードを兄弟のどこに置くか（追加・移動の指定）。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Position {
+    /// Before every existing sibling.
+    First,
+    /// After every existing sibling (append).
+    #[default]
+    Last,
+    /// At this 0-based index; beyond the sibling count it is clamped to the end.
+    At(usize),
+}
+
+impl Position {
+    /// The index to insert at among `len` siblings.
+    pub fn index_in(self, len: usize) -> usize {
+        match self {
+            Self::First => 0,
+            Self::Last => len,
+            Self::At(index) => index.min(len),
+        }
+    }
+}
+
+impl fmt::Display for Position {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::First => f.write_str("first"),
+            Self::Last => f.write_str("last"),
+            Self::At(index) => write!(f, "{index}"),
+        }
+    }
+}
+
+/// ノード追加・移動の結果として、ノードが兄弟内のどこに置かれたか。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Placement {
+    /// The added or moved node.
+    pub node_id: NodeId,
+    /// Actual index among its new siblings.
+    pub index: usize,
+    /// Position the caller asked for.
+    pub requested: Position,
+}
+
+impl Placement {
+    /// Whether an explicit index beyond the sibling count was clamped to
+    /// the end. `First` / `Last` never count as clamping.
+    pub fn was_clamped(&self) -> bool {
+        matches!(self.requested, Position::At(index) if index != self.index)
+    }
+}
+
+/// `checklist` で毎回同じ値を渡さずに済むよう、Book に覚えさせる書き出しの既定値。
+/// リクエストで省略された項目だけに使う（`None` の項目は全体設定・組み込みの既定値へ）。
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EjectDefaults {
+    /// Directory exports are written into.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output_dir: Option<String>,
+    /// Export format (`"markdown"` or `"json"`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub format: Option<String>,
+    /// Whether placeholder hints are rendered as fill-in fields.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub include_placeholders: Option<bool>,
+}
+
+impl EjectDefaults {
+    /// Whether no field is set.
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
 }
 
 /// Template Book — 集約ルート。全ノード操作はここを経由する。
@@ -48,10 +149,172 @@ pub struct TemplateBook {
     max_depth: u8,
     nodes: HashMap<NodeId, TemplateNode>,
     root_nodes: Vec<NodeId>,
+    /// 自前の placeholder を持たない Content ノードに Eject 時に使う既定ヒント
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    default_placeholder: Option<String>,
+    /// ノード数の上限（None で無制限）。暴走したエージェントから共有サーバーを守る
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    max_nodes: Option<u32>,
+    /// Shelf で絞り込むための分類タグ（`ops`, `onboarding` 等）
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    tags: Vec<String>,
+    /// Shelf 上でまとめて一覧するための分類（`Operations`, `Onboarding` 等）
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    categories: Vec<String>,
+    /// `checklist` の書き出し既定値（リクエストで省略された項目に使う）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    eject_defaults: Option<EjectDefaults>,
+}
+
+/// [`TemplateBook::check_node_count`] が見つけた、格納数と到達可能数の食い違い。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NodeCountMismatch {
+    /// Nodes stored in the book (what [`TemplateBook::node_count`] reports).
+    pub stored: usize,
+    /// Nodes reachable from the root list.
+    pub reachable: usize,
+}
+
+impl NodeCountMismatch {
+    /// Stored nodes no root leads to (negative counts are reported as 0).
+    pub fn unreachable(&self) -> usize {
+        self.stored.saturating_sub(self.reachable)
+    }
+}
+
+impl std::fmt::Display for NodeCountMismatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} of {} stored nodes are unreachable from the top level (orphans); node counts include them",
+            self.unreachable(),
+            self.stored
+        )
+    }
+}
+
+/// [`TemplateBook::check_integrity`] が見つけた木構造の破損。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IntegrityIssue {
+    /// A node listed more than once across the root list and all children lists.
+    DuplicateReference {
+        /// The node listed repeatedly.
+        id: NodeId,
+        /// How many times it is listed.
+        count: usize,
+    },
+    /// A listed node whose parent pointer names a different container.
+    ParentMismatch {
+        /// The listed node.
+        id: NodeId,
+        /// Where it is listed (`None` = the root list).
+        listed_under: Option<NodeId>,
+        /// What its own parent pointer says (`None` = top level).
+        parent: Option<NodeId>,
+    },
+    /// A root or children list entry naming a node that is not stored.
+    DanglingReference {
+        /// The missing node.
+        id: NodeId,
+        /// Where it is listed (`None` = the root list).
+        listed_under: Option<NodeId>,
+    },
+    /// Stored nodes that no root leads to.
+    Unreachable(NodeCountMismatch),
+}
+
+impl std::fmt::Display for IntegrityIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let container = |c: &Option<NodeId>| match c {
+            Some(id) => format!("node {}", id.short()),
+            None => "the top level".to_string(),
+        };
+        match self {
+            Self::DuplicateReference { id, count } => write!(
+                f,
+                "node {} is listed {count} times as a child; its subtree is visited repeatedly",
+                id.short()
+            ),
+            Self::ParentMismatch {
+                id,
+                listed_under,
+                parent,
+            } => write!(
+                f,
+                "node {} is listed under {} but its parent is {}",
+                id.short(),
+                container(listed_under),
+                container(parent)
+            ),
+            Self::DanglingReference { id, listed_under } => write!(
+                f,
+                "{} lists node {}, which does not exist",
+                container(listed_under),
+                id.short()
+            ),
+            Self::Unreachable(mismatch) => mismatch.fmt(f),
+        }
+    }
+}
+
+/// 前後の空白を除き、空の値と大文字小文字違いの重複を捨てる（最初の表記を残す）。
+fn normalize_labels(labels: Vec<String>) -> Vec<String> {
+    let mut kept: Vec<String> = Vec::new();
+    for label in labels {
+        let label = label.trim();
+        if !label.is_empty() && !kept.iter().any(|k| k.eq_ignore_ascii_case(label)) {
+            kept.push(label.to_string());
+        }
+    }
+    kept
+}
+
+/// `scheme:rest` の形か（scheme は英字始まりの英数字と `+-.`）。到達性は確認しない。
+/// HTML 書き出しでリンクになるので `javascript:` 等のスクリプト用 scheme は拒否する。
+fn has_url_scheme(url: &str) -> bool {
+    let Some((scheme, rest)) = url.split_once(':') else {
+        return false;
+    };
+    let scripting = ["javascript", "vbscript", "data"]
+        .iter()
+        .any(|s| scheme.eq_ignore_ascii_case(s));
+    !scripting
+        && scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+        && scheme
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+        && !rest.trim_start_matches('/').is_empty()
+        && !url.contains(char::is_whitespace)
 }
 
 impl TemplateBook {
+    /// Largest accepted `max_depth`. Deeper trees are almost always a
+    /// mistake and make depth-first operations needlessly expensive.
+    pub const MAX_DEPTH_LIMIT: u8 = 16;
+
+    /// Check that `max_depth` is usable: 0 would reject every node, and
+    /// anything above [`Self::MAX_DEPTH_LIMIT`] is refused.
+    pub fn validate_max_depth(max_depth: u8) -> Result<(), DomainError> {
+        if max_depth == 0 || max_depth > Self::MAX_DEPTH_LIMIT {
+            return Err(DomainError::InvalidMaxDepth {
+                max_depth,
+                limit: Self::MAX_DEPTH_LIMIT,
+            });
+        }
+        Ok(())
+    }
+
+    /// Create a new, empty book, rejecting an out-of-range `max_depth`
+    /// (see [`Self::validate_max_depth`]).
+    pub fn try_new(title: impl Into<String>, max_depth: u8) -> Result<Self, DomainError> {
+        Self::validate_max_depth(max_depth)?;
+        Ok(Self::new(title, max_depth))
+    }
+
     /// Create a new, empty book with the given title and max depth.
+    ///
+    /// Does not validate `max_depth`; prefer [`Self::try_new`] for
+    /// user-supplied values.
     pub fn new(title: impl Into<String>, max_depth: u8) -> Self {
         Self {
             id: BookId::new(),
@@ -59,6 +322,11 @@ impl TemplateBook {
             max_depth,
             nodes: HashMap::new(),
             root_nodes: Vec::new(),
+            default_placeholder: None,
+            max_nodes: None,
+            tags: Vec::new(),
+            categories: Vec::new(),
+            eject_defaults: None,
         }
     }
 
@@ -77,6 +345,84 @@ impl TemplateBook {
         self.max_depth
     }
 
+    /// max_depth を `max_depth` まで引き上げる（現在値以下なら何もしない）。
+    /// [`Self::MAX_DEPTH_LIMIT`] を超える値は拒否する。
+    pub fn raise_max_depth(&mut self, max_depth: u8) -> Result<(), DomainError> {
+        Self::validate_max_depth(max_depth)?;
+        self.max_depth = self.max_depth.max(max_depth);
+        Ok(())
+    }
+
+    /// Return the book-level default placeholder hint, if any.
+    pub fn default_placeholder(&self) -> Option<&str> {
+        self.default_placeholder.as_deref()
+    }
+
+    /// Book 既定の placeholder ヒントを設定する（None で解除）。
+    pub fn set_default_placeholder(&mut self, placeholder: Option<String>) {
+        self.default_placeholder = placeholder;
+    }
+
+    /// Return the book's node-count limit, if any.
+    pub fn max_nodes(&self) -> Option<u32> {
+        self.max_nodes
+    }
+
+    /// ノード数の上限を設定する（None で解除）。既存ノードは削除しない。
+    pub fn set_max_nodes(&mut self, max_nodes: Option<u32>) {
+        self.max_nodes = max_nodes;
+    }
+
+    /// Return the book's tags, in the order they were set.
+    pub fn tags(&self) -> &[String] {
+        &self.tags
+    }
+
+    /// Return `true` if the book carries `tag` (case-insensitive).
+    pub fn has_tag(&self, tag: &str) -> bool {
+        let tag = tag.trim();
+        self.tags.iter().any(|t| t.eq_ignore_ascii_case(tag))
+    }
+
+    /// タグを置き換える。前後の空白を除き、空のタグと大文字小文字違いの重複は捨てる
+    /// （最初の表記を残す）。空の `Vec` で全解除。
+    pub fn set_tags(&mut self, tags: Vec<String>) {
+        self.tags = normalize_labels(tags);
+    }
+
+    /// Return the book's categories, in the order they were set.
+    pub fn categories(&self) -> &[String] {
+        &self.categories
+    }
+
+    /// 分類を置き換える。正規化は [`Self::set_tags`] と同じ。空の `Vec` で全解除。
+    pub fn set_categories(&mut self, categories: Vec<String>) {
+        self.categories = normalize_labels(categories);
+    }
+
+    /// Return the book's export defaults, if any are set.
+    pub fn eject_defaults(&self) -> Option<&EjectDefaults> {
+        self.eject_defaults.as_ref()
+    }
+
+    /// 書き出しの既定値を置き換える。すべて未設定なら解除する。
+    pub fn set_eject_defaults(&mut self, defaults: EjectDefaults) {
+        self.eject_defaults = (!defaults.is_empty()).then_some(defaults);
+    }
+
+    /// `additional` 個のノードを追加しても `max_nodes` を超えないか検証する。
+    fn check_capacity(&self, additional: usize) -> Result<(), DomainError> {
+        match self.max_nodes {
+            Some(max) if self.nodes.len() + additional > max as usize => {
+                Err(DomainError::MaxNodesExceeded {
+                    max,
+                    count: self.nodes.len(),
+                })
+            }
+            _ => Ok(()),
+        }
+    }
+
     /// Return the IDs of root-level nodes, in order.
     pub fn root_nodes(&self) -> &[NodeId] {
         &self.root_nodes
@@ -87,13 +433,110 @@ impl TemplateBook {
         self.nodes.get(&id)
     }
 
+    /// 実際に最も深い枝の深さ（ルート直下 = 1、空の Book は 0）。
+    pub fn actual_depth(&self) -> u8 {
+        self.nodes
+            .keys()
+            .map(|id| self.depth_of(*id))
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// 最も深い段に達しているノードごとに、ルートからそのノードまでの経路と段数を返す
+    /// （DFS 順）。空の Book では空。max_depth にどれだけ近いかの確認用。
+    pub fn deepest_paths(&self) -> Vec<(Vec<NodeId>, u8)> {
+        let nodes = self.all_nodes_dfs();
+        let depths: Vec<u8> = nodes.iter().map(|n| self.depth_of(n.id())).collect();
+        let Some(&deepest) = depths.iter().max() else {
+            return Vec::new();
+        };
+        nodes
+            .iter()
+            .zip(&depths)
+            .filter(|(_, depth)| **depth == deepest)
+            .map(|(node, _)| {
+                let mut path: Vec<NodeId> =
+                    self.ancestors(node.id()).iter().map(|a| a.id()).collect();
+                path.push(node.id());
+                (path, deepest)
+            })
+            .collect()
+    }
+
     /// Return the total number of nodes in the book.
     pub fn node_count(&self) -> usize {
         self.nodes.len()
     }
 
-    /// ノード追加。深さ制限を検証してから挿入する。
+    /// 格納ノード数とルートから辿れるノード数を比べる。食い違えば孤立ノードがあり、
+    /// [`Self::node_count`] は水増しされている。
+    pub fn check_node_count(&self) -> Option<NodeCountMismatch> {
+        let reachable = self.all_nodes_dfs().len();
+        (reachable != self.nodes.len()).then_some(NodeCountMismatch {
+            stored: self.nodes.len(),
+            reachable,
+        })
+    }
+
+    /// 木構造の不変条件を検証する。各ノードはルート一覧と全 children の中にちょうど1回現れ、
+    /// 親ポインタは並んでいる場所と一致し、格納ノードはすべてルートから辿れること。
+    pub fn check_integrity(&self) -> Vec<IntegrityIssue> {
+        let mut issues = Vec::new();
+        let mut counts: HashMap<NodeId, usize> = HashMap::new();
+        let lists = std::iter::once((None, self.root_nodes.as_slice()))
+            .chain(self.nodes.values().map(|n| (Some(n.id()), n.children())));
+        for (listed_under, ids) in lists {
+            for &id in ids {
+                *counts.entry(id).or_default() += 1;
+                match self.nodes.get(&id) {
+                    None => issues.push(IntegrityIssue::DanglingReference { id, listed_under }),
+                    Some(node) if node.parent() != listed_under => {
+                        issues.push(IntegrityIssue::ParentMismatch {
+                            id,
+                            listed_under,
+                            parent: node.parent(),
+                        })
+                    }
+                    Some(_) => {}
+                }
+            }
+        }
+        let mut duplicates: Vec<_> = counts.into_iter().filter(|(_, c)| *c > 1).collect();
+        duplicates.sort_by_key(|(id, _)| id.to_string());
+        issues.extend(
+            duplicates
+                .into_iter()
+                .map(|(id, count)| IntegrityIssue::DuplicateReference { id, count }),
+        );
+
+        // 重複参照や循環があっても止まるよう、訪問済みを記録しながら数える
+        let mut reached: HashSet<NodeId> = HashSet::new();
+        let mut stack: Vec<NodeId> = self.root_nodes.clone();
+        while let Some(id) = stack.pop() {
+            if let Some(node) = self.nodes.get(&id) {
+                if reached.insert(id) {
+                    stack.extend(node.children());
+                }
+            }
+        }
+        if reached.len() != self.nodes.len() {
+            issues.push(IntegrityIssue::Unreachable(NodeCountMismatch {
+                stored: self.nodes.len(),
+                reachable: reached.len(),
+            }));
+        }
+        issues
+    }
+
+    /// ノード追加。ノード数上限と深さ制限を検証してから挿入する。
     pub fn add_node(&mut self, req: AddNodeRequest) -> Result<NodeId, DomainError> {
+        self.add_node_placed(req).map(|placed| placed.node_id)
+    }
+
+    /// [`Self::add_node`] と同じだが、実際の挿入位置も返す。
+    pub fn add_node_placed(&mut self, req: AddNodeRequest) -> Result<Placement, DomainError> {
+        self.check_capacity(1)?;
+
         // 親の存在チェック
         if let Some(parent_id) = req.parent {
             if !self.nodes.contains_key(&parent_id) {
@@ -124,21 +567,55 @@ impl TemplateBook {
         self.nodes.insert(node_id, node);
 
         // 親の children or root_nodes に挿入
-        match req.parent {
-            Some(parent_id) => {
-                let parent = self
-                    .nodes
-                    .get_mut(&parent_id)
-                    .ok_or(DomainError::NodeNotFound(parent_id))?;
-                parent.add_child(node_id, req.position);
-            }
-            None => {
-                let pos = req.position.min(self.root_nodes.len());
-                self.root_nodes.insert(pos, node_id);
+        let index = self.attach_to_parent(node_id, req.parent, req.position)?;
+
+        Ok(Placement {
+            node_id,
+            index,
+            requested: req.position,
+        })
+    }
+
+    /// Add a node and, in order, children under it (each child's `parent`
+    /// is ignored). Depth and `max_nodes` are checked for the children
+    /// before anything is created, so a rejected call leaves the book
+    /// unchanged.
+    ///
+    /// Returns where the new node was placed and its children's IDs.
+    pub fn add_node_with_children(
+        &mut self,
+        req: AddNodeRequest,
+        children: Vec<AddNodeRequest>,
+    ) -> Result<(Placement, Vec<NodeId>), DomainError> {
+        if let Some(parent_id) = req.parent {
+            if !self.nodes.contains_key(&parent_id) {
+                return Err(DomainError::NodeNotFound(parent_id));
             }
         }
-
-        Ok(node_id)
+        self.check_capacity(1 + children.len())?;
+        let depth = req.parent.map_or(1, |pid| self.depth_of(pid) + 1);
+        let deepest = if children.is_empty() {
+            depth
+        } else {
+            depth + 1
+        };
+        if deepest > self.max_depth {
+            return Err(DomainError::MaxDepthExceeded {
+                node_id: NodeId::new(),
+                max: self.max_depth,
+            });
+        }
+        let placed = self.add_node_placed(req)?;
+        let id = placed.node_id;
+        let mut child_ids = Vec::with_capacity(children.len());
+        for child in children {
+            child_ids.push(self.add_node(AddNodeRequest {
+                parent: Some(id),
+                position: Position::Last,
+                ..child
+            })?);
+        }
+        Ok((placed, child_ids))
     }
 
     /// ノード更新。
@@ -166,28 +643,99 @@ impl TemplateBook {
         if let Some(status) = req.status {
             node.set_status(status);
         }
+        if let Some(notes) = req.notes {
+            node.set_notes(notes);
+        }
+        if let Some(note) = req.note {
+            node.set_note(note);
+        }
+        if let Some(minutes) = req.estimate_minutes {
+            node.set_estimate_minutes(minutes);
+        }
+        if let Some(owner) = req.owner {
+            node.set_owner(owner);
+        }
+        if let Some(done) = req.done {
+            node.set_done(done);
+        }
+        if let Some(ordered) = req.ordered {
+            node.set_ordered(ordered);
+        }
+        if let Some(protected) = req.protected {
+            node.set_protected(protected);
+        }
+        if let Some(level) = req.heading_level {
+            node.set_heading_level(level);
+        }
+        if let Some(command) = req.command {
+            node.set_command(command);
+        }
+        if let Some(order) = req.order {
+            node.set_order(order);
+            let parent = node.parent();
+            self.sort_siblings(parent);
+        }
 
         Ok(())
     }
 
-    /// ノード移動。循環参照と深さ超過を検証する。
+    /// ノード移動。循環参照と深さ超過を検証し、移動先での実際の位置を返す。
     pub fn move_node(
         &mut self,
         id: NodeId,
         new_parent: Option<NodeId>,
-        position: usize,
-    ) -> Result<(), DomainError> {
+        position: Position,
+    ) -> Result<Placement, DomainError> {
         self.validate_move(id, new_parent)?;
+        let old_parent = self.nodes.get(&id).and_then(|n| n.parent());
+        let old_index = self.sibling_index(id).map_or(Position::Last, Position::At);
         self.detach_from_parent(id)?;
-        self.attach_to_parent(id, new_parent, position)?;
-        Ok(())
+        let index = self.attach_to_parent(id, new_parent, position)?;
+        // 事前検証は親リンクで深さを数えるので、子リストと食い違う壊れた木では
+        // すり抜けうる。移動後に子リストを辿って測り直し、超えていれば元に戻す
+        let depth = self
+            .depth_of(id)
+            .saturating_add(self.subtree_height(id))
+            .saturating_sub(1);
+        if depth > self.max_depth {
+            self.detach_from_parent(id)?;
+            self.attach_to_parent(id, old_parent, old_index)?;
+            return Err(DomainError::DepthInvariantBroken {
+                node_id: id,
+                depth,
+                max: self.max_depth,
+            });
+        }
+        Ok(Placement {
+            node_id: id,
+            index,
+            requested: position,
+        })
     }
 
-    /// ノード削除（子孫ごと再帰的に削除）
+    /// ノード削除（子孫ごと再帰的に削除）。ノード自身か子孫に守られたノード
+    /// （`protected`）があれば `DomainError::ProtectedNode` で拒否する。
     pub fn remove_node(&mut self, id: NodeId) -> Result<(), DomainError> {
         if !self.nodes.contains_key(&id) {
             return Err(DomainError::NodeNotFound(id));
         }
+        let protected = std::iter::once(id)
+            .chain(self.collect_descendants(id))
+            .find(|n| self.nodes.get(n).is_some_and(|n| n.is_protected()));
+        if let Some(protected) = protected {
+            return Err(DomainError::ProtectedNode {
+                node_id: protected,
+                removing: id,
+            });
+        }
+        self.remove_node_overriding_protection(id)
+    }
+
+    /// [`Self::remove_node`] と同じだが、守られたノードも削除する。
+    pub fn remove_node_overriding_protection(&mut self, id: NodeId) -> Result<(), DomainError> {
+        if !self.nodes.contains_key(&id) {
+            return Err(DomainError::NodeNotFound(id));
+        }
 
         // 子孫IDを収集
         let descendants = self.collect_descendants(id);
@@ -213,13 +761,135 @@ impl TemplateBook {
 
         // 本体 + 子孫を削除
         self.nodes.remove(&id);
-        for desc_id in descendants {
-            self.nodes.remove(&desc_id);
+        for desc_id in &descendants {
+            self.nodes.remove(desc_id);
+        }
+
+        // 削除済みノードへの依存を残さない
+        for node in self.nodes.values_mut() {
+            node.remove_dependency(id);
+            for desc_id in &descendants {
+                node.remove_dependency(*desc_id);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// `id` が `on` の完了を待つ依存を追加する。既に登録済みなら `false`。
+    pub fn add_dependency(&mut self, id: NodeId, on: NodeId) -> Result<bool, DomainError> {
+        if !self.nodes.contains_key(&on) {
+            return Err(DomainError::NodeNotFound(on));
+        }
+        if id == on || self.depends_transitively(on, id) {
+            return Err(DomainError::CyclicDependency { node_id: id, on });
+        }
+        let node = self
+            .nodes
+            .get_mut(&id)
+            .ok_or(DomainError::NodeNotFound(id))?;
+        Ok(node.add_dependency(on))
+    }
+
+    /// 依存を解除する。登録されていなければ `false`。
+    pub fn remove_dependency(&mut self, id: NodeId, on: NodeId) -> Result<bool, DomainError> {
+        let node = self
+            .nodes
+            .get_mut(&id)
+            .ok_or(DomainError::NodeNotFound(id))?;
+        Ok(node.remove_dependency(on))
+    }
+
+    /// 外部リンクを追加する。`label` 省略時は URL をそのまま使う。
+    /// 同じ URL が既にあればラベルを差し替え、何も変わらなければ `false`。
+    pub fn add_link(
+        &mut self,
+        id: NodeId,
+        label: Option<String>,
+        url: &str,
+    ) -> Result<bool, DomainError> {
+        let url = url.trim();
+        if !has_url_scheme(url) {
+            return Err(DomainError::InvalidLinkUrl(url.to_string()));
         }
+        let label = label
+            .map(|l| l.trim().to_string())
+            .filter(|l| !l.is_empty())
+            .unwrap_or_else(|| url.to_string());
+        let node = self
+            .nodes
+            .get_mut(&id)
+            .ok_or(DomainError::NodeNotFound(id))?;
+        Ok(node.add_link(label, url.to_string()))
+    }
 
+    /// リンクを丸ごと置き換える（取り込み用）。URL はすべて [`Self::add_link`] と同じく検証する。
+    pub fn set_links(
+        &mut self,
+        id: NodeId,
+        links: Vec<(String, String)>,
+    ) -> Result<(), DomainError> {
+        if let Some((_, url)) = links.iter().find(|(_, url)| !has_url_scheme(url)) {
+            return Err(DomainError::InvalidLinkUrl(url.clone()));
+        }
+        let node = self
+            .nodes
+            .get_mut(&id)
+            .ok_or(DomainError::NodeNotFound(id))?;
+        node.set_links(links);
         Ok(())
     }
 
+    /// URL またはラベルが一致するリンクを外す。見つからなければ `false`。
+    pub fn remove_link(&mut self, id: NodeId, url_or_label: &str) -> Result<bool, DomainError> {
+        let node = self
+            .nodes
+            .get_mut(&id)
+            .ok_or(DomainError::NodeNotFound(id))?;
+        Ok(node.remove_link(url_or_label.trim()))
+    }
+
+    /// `id` の着手を妨げている依存先を返す（チェック済みの依存は除く）。
+    pub fn blocked_by(&self, id: NodeId) -> Vec<NodeId> {
+        self.nodes
+            .get(&id)
+            .map(|n| {
+                n.depends_on()
+                    .iter()
+                    .copied()
+                    .filter(|dep| !self.nodes.get(dep).is_some_and(|d| d.is_done()))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// 見積もり（分）の合計。`root` 指定時はその部分木（自身を含む）、`None` なら Book 全体。
+    ///
+    /// Section 自身の見積もりは葉の場合のみ数える（子の合計と二重計上しないため）。
+    /// 合計はオーバーフローせず `u32::MAX` で飽和する。
+    pub fn estimate_total(&self, root: Option<NodeId>) -> u32 {
+        let nodes = match root {
+            Some(id) => self.subtree_nodes(id),
+            None => self.all_nodes_dfs(),
+        };
+        nodes
+            .iter()
+            .filter(|n| *n.node_type() == NodeType::Content || n.is_leaf())
+            .filter_map(|n| n.estimate_minutes())
+            .fold(0u32, |acc, m| acc.saturating_add(m))
+    }
+
+    /// `toc` と同じ階層番号（`2-3` 形式）を返す。
+    pub fn outline_number(&self, id: NodeId) -> Option<String> {
+        let mut parts: Vec<String> = self
+            .ancestors(id)
+            .iter()
+            .map(|a| self.sibling_index(a.id()).map(|i| (i + 1).to_string()))
+            .collect::<Option<_>>()?;
+        parts.push((self.sibling_index(id)? + 1).to_string());
+        Some(parts.join("-"))
+    }
+
     /// 指定ノードを含むサブツリーのノード一覧（DFS順）
     pub fn subtree_nodes(&self, root: NodeId) -> Vec<&TemplateNode> {
         let mut result = Vec::new();
@@ -253,6 +923,59 @@ impl TemplateBook {
         result
     }
 
+    /// 兄弟内での現在位置（0始まり）を返す。ノードが存在しなければ None。
+    pub fn sibling_index(&self, id: NodeId) -> Option<usize> {
+        let node = self.nodes.get(&id)?;
+        let siblings = match node.parent() {
+            Some(p) => self.nodes.get(&p)?.children(),
+            None => &self.root_nodes,
+        };
+        siblings.iter().position(|sid| *sid == id)
+    }
+
+    /// 祖先ノードをルート側から順に返す（自身は含まない）。
+    pub fn ancestors(&self, id: NodeId) -> Vec<&TemplateNode> {
+        let mut result = Vec::new();
+        let mut current = self.nodes.get(&id).and_then(|n| n.parent());
+        while let Some(parent_id) = current {
+            let Some(parent) = self.nodes.get(&parent_id) else {
+                break;
+            };
+            // 破損データの循環を防御
+            if result.len() > self.nodes.len() {
+                break;
+            }
+            result.push(parent);
+            current = parent.parent();
+        }
+        result.reverse();
+        result
+    }
+
+    /// 作成日時の新しい順にノードを返す。
+    ///
+    /// `since` 指定時はそれ以降に作成されたノードのみ。作成日時を持たない
+    /// （導入前の）ノードは含まない。同時刻のノードはDFS順を保つ。
+    pub fn recently_created(&self, limit: usize, since: Option<Timestamp>) -> Vec<&TemplateNode> {
+        let mut nodes: Vec<&TemplateNode> = self
+            .all_nodes_dfs()
+            .into_iter()
+            .filter(|n| match (n.created_at(), since) {
+                (Some(created), Some(since)) => created >= since,
+                (Some(_), None) => true,
+                (None, _) => false,
+            })
+            .collect();
+        nodes.sort_by_key(|n| std::cmp::Reverse(n.created_at()));
+        nodes.truncate(limit);
+        nodes
+    }
+
+    /// `id` の子孫の数（`id` 自身は含まない）。
+    pub fn descendant_count(&self, id: NodeId) -> usize {
+        self.collect_descendants(id).len()
+    }
+
     /// ノードの深さを返す（ルート=1）。破損データの無限ループを防御する。
     pub fn depth_of(&self, id: NodeId) -> u8 {
         let mut depth = 1u8;
@@ -267,6 +990,14 @@ impl TemplateBook {
         depth
     }
 
+    /// テスト用: 作成日時を差し替える。
+    #[cfg(test)]
+    pub(crate) fn set_node_created_at(&mut self, id: NodeId, created_at: Option<Timestamp>) {
+        if let Some(node) = self.nodes.get_mut(&id) {
+            node.set_created_at(created_at);
+        }
+    }
+
     // --- Private helpers ---
 
     fn validate_move(&self, id: NodeId, new_parent: Option<NodeId>) -> Result<(), DomainError> {
@@ -277,8 +1008,12 @@ impl TemplateBook {
             if !self.nodes.contains_key(&np_id) {
                 return Err(DomainError::NodeNotFound(np_id));
             }
-            if self.is_descendant_of(np_id, id) {
-                return Err(DomainError::CyclicMove(id));
+            // 自身を親にすると detach 後に自分へ attach して木が壊れる
+            if np_id == id || self.is_descendant_of(np_id, id) {
+                return Err(DomainError::CyclicMove {
+                    node_id: id,
+                    new_parent: np_id,
+                });
             }
         }
         let subtree_max = self.subtree_max_depth(id);
@@ -322,27 +1057,66 @@ impl TemplateBook {
         &mut self,
         id: NodeId,
         new_parent: Option<NodeId>,
-        position: usize,
-    ) -> Result<(), DomainError> {
+        position: Position,
+    ) -> Result<usize, DomainError> {
         let node = self
             .nodes
             .get_mut(&id)
             .ok_or(DomainError::NodeNotFound(id))?;
         node.set_parent(new_parent);
-        match new_parent {
+        let pos = match new_parent {
             Some(np_id) => {
                 let np = self
                     .nodes
                     .get_mut(&np_id)
                     .ok_or(DomainError::NodeNotFound(np_id))?;
-                np.add_child(id, position);
+                np.add_child(id, position.index_in(np.children().len()))
             }
-            None => {
-                let pos = position.min(self.root_nodes.len());
-                self.root_nodes.insert(pos, id);
+            None => match self.root_nodes.iter().position(|rid| *rid == id) {
+                Some(existing) => existing,
+                None => {
+                    let pos = position.index_in(self.root_nodes.len());
+                    self.root_nodes.insert(pos, id);
+                    pos
+                }
+            },
+        };
+        // 並び順キーを持つ兄弟がいれば、指定位置よりキーを優先する
+        if self.sort_siblings(new_parent) {
+            return self.sibling_index(id).ok_or(DomainError::NodeNotFound(id));
+        }
+        Ok(pos)
+    }
+
+    /// `parent` の子（`None` ならトップレベル）を並び順キーで並べ直す。キーを持つ兄弟が
+    /// 昇順で先、持たない兄弟は今の順でその後。キーを持つ兄弟がいなければ何もせず `false`。
+    fn sort_siblings(&mut self, parent: Option<NodeId>) -> bool {
+        let mut siblings = match parent {
+            Some(p) => match self.nodes.get(&p) {
+                Some(node) => node.children().to_vec(),
+                None => return false,
+            },
+            None => self.root_nodes.clone(),
+        };
+        let key = |id: &NodeId| self.nodes.get(id).and_then(|n| n.order());
+        if !siblings.iter().any(|id| key(id).is_some()) {
+            return false;
+        }
+        siblings.sort_by(|a, b| match (key(a), key(b)) {
+            (Some(x), Some(y)) => x.total_cmp(&y),
+            (Some(_), None) => std::cmp::Ordering::Less,
+            (None, Some(_)) => std::cmp::Ordering::Greater,
+            (None, None) => std::cmp::Ordering::Equal,
+        });
+        match parent {
+            Some(p) => {
+                if let Some(node) = self.nodes.get_mut(&p) {
+                    *node.children_mut() = siblings;
+                }
             }
+            None => self.root_nodes = siblings,
         }
-        Ok(())
+        true
     }
 
     fn is_descendant_of(&self, node: NodeId, ancestor: NodeId) -> bool {
@@ -368,6 +1142,46 @@ impl TemplateBook {
         max
     }
 
+    /// `root` 自身を 1 として、子リストを辿った部分木の段数。
+    /// 壊れた木の循環で止まらないよう、一度見たノードは辿らない。
+    fn subtree_height(&self, root: NodeId) -> u8 {
+        let mut seen = HashSet::new();
+        let mut stack = vec![(root, 1u8)];
+        let mut height = 0;
+        while let Some((id, level)) = stack.pop() {
+            if !seen.insert(id) {
+                continue;
+            }
+            height = height.max(level);
+            if let Some(node) = self.nodes.get(&id) {
+                stack.extend(
+                    node.children()
+                        .iter()
+                        .map(|c| (*c, level.saturating_add(1))),
+                );
+            }
+        }
+        height
+    }
+
+    /// `from` から依存を辿って `target` に到達するか。
+    fn depends_transitively(&self, from: NodeId, target: NodeId) -> bool {
+        let mut stack = vec![from];
+        let mut seen = HashSet::new();
+        while let Some(current) = stack.pop() {
+            if current == target {
+                return true;
+            }
+            if !seen.insert(current) {
+                continue;
+            }
+            if let Some(node) = self.nodes.get(&current) {
+                stack.extend(node.depends_on().iter().copied());
+            }
+        }
+        false
+    }
+
     fn collect_descendants(&self, id: NodeId) -> Vec<NodeId> {
         let mut result = Vec::new();
         if let Some(node) = self.nodes.get(&id) {
@@ -397,6 +1211,27 @@ mod tests {
         TemplateBook::new("Test Book", 4)
     }
 
+    #[test]
+    fn try_new_rejects_zero_max_depth() {
+        let err = TemplateBook::try_new("Bad", 0).unwrap_err();
+        assert!(matches!(
+            err,
+            DomainError::InvalidMaxDepth {
+                max_depth: 0,
+                limit: TemplateBook::MAX_DEPTH_LIMIT
+            }
+        ));
+    }
+
+    #[test]
+    fn try_new_rejects_max_depth_above_limit() {
+        assert!(TemplateBook::try_new("Bad", TemplateBook::MAX_DEPTH_LIMIT + 1).is_err());
+        assert!(TemplateBook::try_new("Bad", u8::MAX).is_err());
+        assert!(TemplateBook::try_new("Ok", 1).is_ok());
+        let book = TemplateBook::try_new("Ok", TemplateBook::MAX_DEPTH_LIMIT).unwrap();
+        assert_eq!(book.max_depth(), TemplateBook::MAX_DEPTH_LIMIT);
+    }
+
     #[test]
     fn add_root_node() {
         let mut book = make_book();
@@ -407,7 +1242,7 @@ mod tests {
                 node_type: NodeType::Section,
                 body: None,
                 placeholder: None,
-                position: usize::MAX,
+                position: Position::Last,
                 properties: HashMap::new(),
             })
             .unwrap();
@@ -416,6 +1251,267 @@ mod tests {
         assert_eq!(book.get_node(id).unwrap().title(), "Design");
     }
 
+    #[test]
+    fn add_node_with_children_creates_in_order() {
+        let mut book = make_book();
+        let child = |title: &str| AddNodeRequest {
+            parent: None,
+            title: title.into(),
+            node_type: NodeType::Content,
+            body: None,
+            placeholder: None,
+            position: Position::At(0),
+            properties: HashMap::new(),
+        };
+        let (placed, children) = book
+            .add_node_with_children(
+                AddNodeRequest {
+                    node_type: NodeType::Section,
+                    ..child("Rollback")
+                },
+                vec![child("Stop traffic"), child("Restore"), child("Verify")],
+            )
+            .unwrap();
+        let id = placed.node_id;
+        let titles: Vec<&str> = book
+            .get_node(id)
+            .unwrap()
+            .children()
+            .iter()
+            .map(|c| book.get_node(*c).unwrap().title())
+            .collect();
+        assert_eq!(titles, vec!["Stop traffic", "Restore", "Verify"]);
+        assert_eq!(book.get_node(id).unwrap().children(), children.as_slice());
+    }
+
+    #[test]
+    fn add_node_with_children_rejects_depth_without_partial_state() {
+        let mut book = TemplateBook::new("Shallow", 1);
+        let req = |title: &str, node_type| AddNodeRequest {
+            parent: None,
+            title: title.into(),
+            node_type,
+            body: None,
+            placeholder: None,
+            position: Position::Last,
+            properties: HashMap::new(),
+        };
+        let err = book
+            .add_node_with_children(
+                req("Rollback", NodeType::Section),
+                vec![req("Step", NodeType::Content)],
+            )
+            .unwrap_err();
+        assert!(matches!(err, DomainError::MaxDepthExceeded { max: 1, .. }));
+        assert_eq!(book.node_count(), 0);
+    }
+
+    #[test]
+    fn add_node_placed_reports_exact_clamped_and_appended_positions() {
+        let mut book = make_book();
+        let parent = add_content(&mut book, None, "Parent");
+        for title in ["A", "B", "C"] {
+            add_content(&mut book, Some(parent), title);
+        }
+        let req = |position| AddNodeRequest {
+            parent: Some(parent),
+            title: "New".into(),
+            node_type: NodeType::Content,
+            body: None,
+            placeholder: None,
+            position,
+            properties: HashMap::new(),
+        };
+
+        let exact = book.add_node_placed(req(Position::At(1))).unwrap();
+        assert_eq!((exact.index, exact.requested), (1, Position::At(1)));
+        assert!(!exact.was_clamped());
+
+        // 兄弟は4つ → 7 は末尾 (4) に丸められる
+        let clamped = book.add_node_placed(req(Position::At(7))).unwrap();
+        assert_eq!((clamped.index, clamped.requested), (4, Position::At(7)));
+        assert!(clamped.was_clamped());
+        assert_eq!(book.sibling_index(clamped.node_id), Some(4));
+
+        let appended = book.add_node_placed(req(Position::Last)).unwrap();
+        assert_eq!(appended.index, 5);
+        assert!(!appended.was_clamped());
+    }
+
+    #[test]
+    fn move_node_reports_clamped_position() {
+        let mut book = make_book();
+        let a = add_content(&mut book, None, "A");
+        let b = add_content(&mut book, None, "B");
+        add_content(&mut book, Some(b), "B-1");
+
+        let placed = book.move_node(a, Some(b), Position::At(7)).unwrap();
+        assert_eq!(placed.node_id, a);
+        assert_eq!((placed.index, placed.requested), (1, Position::At(7)));
+        assert!(placed.was_clamped());
+
+        let placed = book.move_node(a, None, Position::At(0)).unwrap();
+        assert_eq!(placed.index, 0);
+        assert!(!placed.was_clamped());
+    }
+
+    #[test]
+    fn actual_depth_tracks_deepest_branch() {
+        let mut book = make_book();
+        assert_eq!(book.actual_depth(), 0);
+        let a = add_content(&mut book, None, "A");
+        add_content(&mut book, None, "B");
+        assert_eq!(book.actual_depth(), 1);
+        let a1 = add_content(&mut book, Some(a), "A-1");
+        add_content(&mut book, Some(a1), "A-1-1");
+        assert_eq!(book.actual_depth(), 3);
+    }
+
+    #[test]
+    fn deepest_paths_on_skewed_and_balanced_trees() {
+        let mut book = make_book();
+        assert!(book.deepest_paths().is_empty());
+
+        // 偏った木: A → A-1 → A-1-1 の一本だけが深い
+        let a = add_content(&mut book, None, "A");
+        let a1 = add_content(&mut book, Some(a), "A-1");
+        let a11 = add_content(&mut book, Some(a1), "A-1-1");
+        let b = add_content(&mut book, None, "B");
+        add_content(&mut book, Some(b), "B-1");
+        assert_eq!(book.deepest_paths(), vec![(vec![a, a1, a11], 3)]);
+
+        // 釣り合った木: どちらの枝も 3 段に達する（DFS 順）
+        let b1 = book.get_node(b).unwrap().children()[0];
+        let b11 = add_content(&mut book, Some(b1), "B-1-1");
+        let a12 = add_content(&mut book, Some(a1), "A-1-2");
+        assert_eq!(
+            book.deepest_paths(),
+            vec![
+                (vec![a, a1, a11], 3),
+                (vec![a, a1, a12], 3),
+                (vec![b, b1, b11], 3),
+            ]
+        );
+        assert_eq!(book.deepest_paths()[0].1, book.actual_depth());
+    }
+
+    #[test]
+    fn set_tags_trims_and_dedupes_case_insensitively() {
+        let mut book = make_book();
+        book.set_tags(vec![
+            " Ops ".into(),
+            "".into(),
+            "ops".into(),
+            "Onboarding".into(),
+        ]);
+        assert_eq!(book.tags(), ["Ops", "Onboarding"]);
+        assert!(book.has_tag("OPS"));
+        assert!(!book.has_tag("security"));
+
+        let json = serde_json::to_string(&book).unwrap();
+        let back: TemplateBook = serde_json::from_str(&json).unwrap();
+        assert_eq!(back.tags(), book.tags());
+        book.set_tags(Vec::new());
+        assert!(!serde_json::to_string(&book).unwrap().contains("tags"));
+    }
+
+    #[test]
+    fn order_keys_sort_siblings_ahead_of_insertion_order() {
+        let mut book = make_book();
+        let set_order = |book: &mut TemplateBook, id, order| {
+            book.update_node(
+                id,
+                UpdateNodeRequest {
+                    order: Some(order),
+                    ..Default::default()
+                },
+            )
+            .unwrap()
+        };
+        let a = add_content(&mut book, None, "A");
+        let b = add_content(&mut book, None, "B");
+        let c = add_content(&mut book, None, "C");
+        set_order(&mut book, c, Some(10.0));
+        set_order(&mut book, a, Some(30.0));
+        // キーなしの B はキー付きの後ろ
+        assert_eq!(book.root_nodes(), [c, a, b]);
+
+        // 既存のキーを振り直さずに間へ入れる
+        set_order(&mut book, b, Some(20.0));
+        assert_eq!(book.root_nodes(), [c, b, a]);
+
+        // 新規ノードは位置指定よりキー付き兄弟の後ろが優先される
+        let placed = book
+            .add_node_placed(AddNodeRequest {
+                parent: None,
+                title: "D".into(),
+                node_type: NodeType::Content,
+                body: None,
+                placeholder: None,
+                position: Position::At(0),
+                properties: HashMap::new(),
+            })
+            .unwrap();
+        assert_eq!(placed.index, 3);
+
+        set_order(&mut book, a, None);
+        assert_eq!(book.root_nodes(), [c, b, a, placed.node_id]);
+    }
+
+    #[test]
+    fn add_node_stops_at_max_nodes() {
+        let mut book = make_book();
+        book.set_max_nodes(Some(3));
+        for title in ["A", "B", "C"] {
+            add_content(&mut book, None, title);
+        }
+        let err = book
+            .add_node(AddNodeRequest {
+                parent: None,
+                title: "D".into(),
+                node_type: NodeType::Content,
+                body: None,
+                placeholder: None,
+                position: Position::Last,
+                properties: HashMap::new(),
+            })
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            DomainError::MaxNodesExceeded { max: 3, count: 3 }
+        ));
+        assert!(err.to_string().contains("3 nodes"));
+        assert_eq!(book.node_count(), 3);
+
+        // 上限解除で再び追加できる
+        book.set_max_nodes(None);
+        add_content(&mut book, None, "D");
+        assert_eq!(book.node_count(), 4);
+    }
+
+    #[test]
+    fn add_node_with_children_checks_max_nodes_up_front() {
+        let mut book = make_book();
+        book.set_max_nodes(Some(2));
+        let req = |title: &str| AddNodeRequest {
+            parent: None,
+            title: title.into(),
+            node_type: NodeType::Content,
+            body: None,
+            placeholder: None,
+            position: Position::Last,
+            properties: HashMap::new(),
+        };
+        let err = book
+            .add_node_with_children(req("Parent"), vec![req("One"), req("Two")])
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            DomainError::MaxNodesExceeded { max: 2, count: 0 }
+        ));
+        assert_eq!(book.node_count(), 0);
+    }
+
     #[test]
     fn add_child_to_section() {
         let mut book = make_book();
@@ -426,7 +1522,7 @@ mod tests {
                 node_type: NodeType::Section,
                 body: None,
                 placeholder: None,
-                position: usize::MAX,
+                position: Position::Last,
                 properties: HashMap::new(),
             })
             .unwrap();
@@ -438,7 +1534,7 @@ mod tests {
                 node_type: NodeType::Content,
                 body: None,
                 placeholder: Some("list test cases here".into()),
-                position: usize::MAX,
+                position: Position::Last,
                 properties: HashMap::new(),
             })
             .unwrap();
@@ -458,7 +1554,7 @@ mod tests {
                 node_type: NodeType::Section,
                 body: None,
                 placeholder: None,
-                position: usize::MAX,
+                position: Position::Last,
                 properties: HashMap::new(),
             })
             .unwrap();
@@ -470,7 +1566,7 @@ mod tests {
                 node_type: NodeType::Content,
                 body: None,
                 placeholder: None,
-                position: usize::MAX,
+                position: Position::Last,
                 properties: HashMap::new(),
             })
             .unwrap();
@@ -481,7 +1577,7 @@ mod tests {
             node_type: NodeType::Content,
             body: None,
             placeholder: None,
-            position: usize::MAX,
+            position: Position::Last,
             properties: HashMap::new(),
         });
 
@@ -501,7 +1597,7 @@ mod tests {
                 node_type: NodeType::Section,
                 body: None,
                 placeholder: None,
-                position: usize::MAX,
+                position: Position::Last,
                 properties: HashMap::new(),
             })
             .unwrap();
@@ -513,7 +1609,7 @@ mod tests {
                 node_type: NodeType::Section,
                 body: None,
                 placeholder: None,
-                position: usize::MAX,
+                position: Position::Last,
                 properties: HashMap::new(),
             })
             .unwrap();
@@ -525,12 +1621,12 @@ mod tests {
                 node_type: NodeType::Content,
                 body: None,
                 placeholder: None,
-                position: usize::MAX,
+                position: Position::Last,
                 properties: HashMap::new(),
             })
             .unwrap();
 
-        book.move_node(child, Some(b), 0).unwrap();
+        book.move_node(child, Some(b), Position::At(0)).unwrap();
 
         assert!(book.get_node(a).unwrap().children().is_empty());
         assert_eq!(book.get_node(b).unwrap().children(), &[child]);
@@ -547,7 +1643,7 @@ mod tests {
                 node_type: NodeType::Section,
                 body: None,
                 placeholder: None,
-                position: usize::MAX,
+                position: Position::Last,
                 properties: HashMap::new(),
             })
             .unwrap();
@@ -559,13 +1655,40 @@ mod tests {
                 node_type: NodeType::Section,
                 body: None,
                 placeholder: None,
-                position: usize::MAX,
+                position: Position::Last,
                 properties: HashMap::new(),
             })
             .unwrap();
 
-        let result = book.move_node(parent, Some(child), 0);
-        assert!(matches!(result, Err(DomainError::CyclicMove(_))));
+        let result = book.move_node(parent, Some(child), Position::At(0));
+        assert!(matches!(
+            result,
+            Err(DomainError::CyclicMove { node_id, new_parent })
+                if node_id == parent && new_parent == child
+        ));
+    }
+
+    #[test]
+    fn move_under_itself_is_rejected_and_book_stays_valid() {
+        let mut book = make_book();
+        let section = book
+            .add_node(AddNodeRequest {
+                parent: None,
+                title: "Section".into(),
+                node_type: NodeType::Section,
+                body: None,
+                placeholder: None,
+                position: Position::Last,
+                properties: HashMap::new(),
+            })
+            .unwrap();
+
+        let result = book.move_node(section, Some(section), Position::At(0));
+        assert!(matches!(result, Err(DomainError::CyclicMove { .. })));
+        let node = book.get_node(section).unwrap();
+        assert_eq!(node.parent(), None);
+        assert!(node.children().is_empty());
+        assert_eq!(book.root_nodes(), &[section]);
     }
 
     #[test]
@@ -578,7 +1701,7 @@ mod tests {
                 node_type: NodeType::Section,
                 body: None,
                 placeholder: None,
-                position: usize::MAX,
+                position: Position::Last,
                 properties: HashMap::new(),
             })
             .unwrap();
@@ -590,7 +1713,7 @@ mod tests {
                 node_type: NodeType::Section,
                 body: None,
                 placeholder: None,
-                position: usize::MAX,
+                position: Position::Last,
                 properties: HashMap::new(),
             })
             .unwrap();
@@ -602,7 +1725,7 @@ mod tests {
                 node_type: NodeType::Content,
                 body: None,
                 placeholder: None,
-                position: usize::MAX,
+                position: Position::Last,
                 properties: HashMap::new(),
             })
             .unwrap();
@@ -623,7 +1746,7 @@ mod tests {
                 node_type: NodeType::Section,
                 body: None,
                 placeholder: None,
-                position: usize::MAX,
+                position: Position::Last,
                 properties: HashMap::new(),
             })
             .unwrap();
@@ -634,9 +1757,7 @@ mod tests {
                 title: Some("New".into()),
                 body: Some(Some("description".into())),
                 node_type: Some(NodeType::Content),
-                placeholder: None,
-                properties: None,
-                status: None,
+                ..Default::default()
             },
         )
         .unwrap();
@@ -657,7 +1778,7 @@ mod tests {
                 node_type: NodeType::Section,
                 body: None,
                 placeholder: None,
-                position: usize::MAX,
+                position: Position::Last,
                 properties: HashMap::new(),
             })
             .unwrap();
@@ -669,7 +1790,7 @@ mod tests {
                 node_type: NodeType::Content,
                 body: None,
                 placeholder: None,
-                position: usize::MAX,
+                position: Position::Last,
                 properties: HashMap::new(),
             })
             .unwrap();
@@ -681,7 +1802,7 @@ mod tests {
                 node_type: NodeType::Content,
                 body: None,
                 placeholder: None,
-                position: usize::MAX,
+                position: Position::Last,
                 properties: HashMap::new(),
             })
             .unwrap();
@@ -693,7 +1814,7 @@ mod tests {
                 node_type: NodeType::Content,
                 body: None,
                 placeholder: None,
-                position: usize::MAX,
+                position: Position::Last,
                 properties: HashMap::new(),
             })
             .unwrap();
@@ -702,4 +1823,446 @@ mod tests {
         let ids: Vec<NodeId> = all.iter().map(|n| n.id()).collect();
         assert_eq!(ids, vec![a, a1, a2, b]);
     }
+
+    fn add_content(book: &mut TemplateBook, parent: Option<NodeId>, title: &str) -> NodeId {
+        book.add_node(AddNodeRequest {
+            parent,
+            title: title.into(),
+            node_type: NodeType::Content,
+            body: None,
+            placeholder: None,
+            position: Position::Last,
+            properties: HashMap::new(),
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn move_is_undone_when_a_corrupt_tree_fools_the_depth_precheck() {
+        let mut book = TemplateBook::new("Corrupt", 3);
+        let x = add_content(&mut book, None, "X");
+        let a = add_content(&mut book, None, "A");
+        let b = add_content(&mut book, Some(a), "B");
+        let c = add_content(&mut book, Some(b), "C");
+
+        // 手で編集したファイルを模す: C は B の子リストにいるが、親リンクは空
+        let mut value = serde_json::to_value(&book).unwrap();
+        value["nodes"][c.to_string()]["parent"] = serde_json::Value::Null;
+        let mut book: TemplateBook = serde_json::from_value(value).unwrap();
+
+        // 親リンクで数えると A の部分木は2段なので事前検証は通るが、実際は3段
+        let err = book.move_node(a, Some(x), Position::At(0)).unwrap_err();
+        assert!(matches!(
+            err,
+            DomainError::DepthInvariantBroken {
+                node_id,
+                depth: 4,
+                max: 3,
+            } if node_id == a
+        ));
+        assert_eq!(book.root_nodes(), [x, a]);
+        assert_eq!(book.get_node(a).unwrap().parent(), None);
+        assert!(book.get_node(x).unwrap().children().is_empty());
+
+        // 正常な深さに収まる移動はそのまま通る
+        book.move_node(b, None, Position::Last).unwrap();
+        book.move_node(a, Some(x), Position::At(0)).unwrap();
+        assert_eq!(book.get_node(x).unwrap().children(), [a]);
+    }
+
+    fn protect(book: &mut TemplateBook, id: NodeId, protected: bool) {
+        book.update_node(
+            id,
+            UpdateNodeRequest {
+                protected: Some(protected),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn protected_node_refuses_direct_and_ancestor_removal() {
+        let mut book = make_book();
+        let ops = add_content(&mut book, None, "Ops");
+        let backups = add_content(&mut book, Some(ops), "Confirm backups exist");
+        let cleanup = add_content(&mut book, Some(ops), "Clean temp files");
+        protect(&mut book, backups, true);
+
+        let err = book.remove_node(backups).unwrap_err();
+        assert!(matches!(
+            err,
+            DomainError::ProtectedNode { node_id, removing }
+                if node_id == backups && removing == backups
+        ));
+        let err = book.remove_node(ops).unwrap_err();
+        assert!(matches!(
+            err,
+            DomainError::ProtectedNode { node_id, removing }
+                if node_id == backups && removing == ops
+        ));
+        assert_eq!(book.node_count(), 3);
+
+        // 守られていない兄弟は削除できる。保護を外せば祖先ごと削除できる
+        book.remove_node(cleanup).unwrap();
+        protect(&mut book, backups, false);
+        book.remove_node(ops).unwrap();
+        assert_eq!(book.node_count(), 0);
+    }
+
+    #[test]
+    fn override_removes_protected_subtree() {
+        let mut book = make_book();
+        let ops = add_content(&mut book, None, "Ops");
+        let backups = add_content(&mut book, Some(ops), "Confirm backups exist");
+        protect(&mut book, backups, true);
+
+        book.remove_node_overriding_protection(ops).unwrap();
+        assert!(book.get_node(backups).is_none());
+        assert!(book.root_nodes().is_empty());
+    }
+
+    #[test]
+    fn ancestors_are_root_first() {
+        let mut book = make_book();
+        let a = add_content(&mut book, None, "A");
+        let b = add_content(&mut book, Some(a), "B");
+        let c = add_content(&mut book, Some(b), "C");
+
+        let titles: Vec<&str> = book.ancestors(c).iter().map(|n| n.title()).collect();
+        assert_eq!(titles, vec!["A", "B"]);
+        assert!(book.ancestors(a).is_empty());
+    }
+
+    #[test]
+    fn recently_created_orders_newest_first_and_filters() {
+        let mut book = make_book();
+        let old = add_content(&mut book, None, "old");
+        let mid = add_content(&mut book, None, "mid");
+        let new = add_content(&mut book, Some(mid), "new");
+        let legacy = add_content(&mut book, None, "legacy");
+        for (id, ts) in [
+            (old, Some(1_000)),
+            (mid, Some(2_000)),
+            (new, Some(3_000)),
+            (legacy, None),
+        ] {
+            book.set_node_created_at(id, ts.map(Timestamp::from_millis));
+        }
+
+        let ids: Vec<NodeId> = book
+            .recently_created(10, None)
+            .iter()
+            .map(|n| n.id())
+            .collect();
+        assert_eq!(ids, vec![new, mid, old]);
+
+        let limited: Vec<NodeId> = book
+            .recently_created(1, None)
+            .iter()
+            .map(|n| n.id())
+            .collect();
+        assert_eq!(limited, vec![new]);
+
+        let since: Vec<NodeId> = book
+            .recently_created(10, Some(Timestamp::from_millis(2_000)))
+            .iter()
+            .map(|n| n.id())
+            .collect();
+        assert_eq!(since, vec![new, mid]);
+    }
+
+    #[test]
+    fn sibling_index_reports_clamped_position() {
+        let mut book = make_book();
+        let a = add_content(&mut book, None, "A");
+        let b = book
+            .add_node(AddNodeRequest {
+                parent: None,
+                title: "B".into(),
+                node_type: NodeType::Content,
+                body: None,
+                placeholder: None,
+                position: Position::At(99),
+                properties: HashMap::new(),
+            })
+            .unwrap();
+        let child = add_content(&mut book, Some(a), "A-1");
+
+        assert_eq!(book.sibling_index(a), Some(0));
+        assert_eq!(book.sibling_index(b), Some(1));
+        assert_eq!(book.sibling_index(child), Some(0));
+        assert_eq!(book.sibling_index(NodeId::new()), None);
+    }
+
+    #[test]
+    fn done_dependencies_no_longer_block() {
+        let mut book = make_book();
+        let a = add_content(&mut book, None, "A");
+        let b = add_content(&mut book, None, "B");
+        let c = add_content(&mut book, None, "C");
+        book.add_dependency(c, a).unwrap();
+        book.add_dependency(c, b).unwrap();
+
+        let check = |done| UpdateNodeRequest {
+            done: Some(done),
+            ..Default::default()
+        };
+        book.update_node(a, check(true)).unwrap();
+        assert!(book.get_node(a).unwrap().is_done());
+        assert_eq!(book.blocked_by(c), vec![b]);
+
+        book.update_node(a, check(false)).unwrap();
+        assert_eq!(book.blocked_by(c), vec![a, b]);
+    }
+
+    #[test]
+    fn dependencies_reject_self_and_cycles() {
+        let mut book = make_book();
+        let a = add_content(&mut book, None, "A");
+        let b = add_content(&mut book, None, "B");
+        let c = add_content(&mut book, None, "C");
+
+        assert!(book.add_dependency(b, a).unwrap());
+        assert!(!book.add_dependency(b, a).unwrap(), "duplicate is a no-op");
+        assert!(book.add_dependency(c, b).unwrap());
+        assert_eq!(book.blocked_by(c), vec![b]);
+
+        assert!(matches!(
+            book.add_dependency(a, a),
+            Err(DomainError::CyclicDependency { .. })
+        ));
+        assert!(matches!(
+            book.add_dependency(a, c),
+            Err(DomainError::CyclicDependency { .. })
+        ));
+        assert!(matches!(
+            book.add_dependency(a, NodeId::new()),
+            Err(DomainError::NodeNotFound(_))
+        ));
+
+        assert!(book.remove_dependency(c, b).unwrap());
+        assert!(book.blocked_by(c).is_empty());
+    }
+
+    #[test]
+    fn remove_node_drops_dangling_dependencies() {
+        let mut book = make_book();
+        let a = add_content(&mut book, None, "A");
+        let a1 = add_content(&mut book, Some(a), "A-1");
+        let b = add_content(&mut book, None, "B");
+        book.add_dependency(b, a).unwrap();
+        book.add_dependency(b, a1).unwrap();
+
+        book.remove_node(a).unwrap();
+        assert!(book.get_node(b).unwrap().depends_on().is_empty());
+    }
+
+    #[test]
+    fn links_require_a_scheme_and_dedupe_by_url() {
+        let mut book = make_book();
+        let a = add_content(&mut book, None, "A");
+
+        assert!(book
+            .add_link(a, Some("Dashboard".into()), "https://grafana.example/d/1")
+            .unwrap());
+        assert!(book.add_link(a, None, "mailto:ops@example.com").unwrap());
+        assert!(
+            !book
+                .add_link(a, Some("Dashboard".into()), "https://grafana.example/d/1")
+                .unwrap(),
+            "same label and url is a no-op"
+        );
+        assert!(book
+            .add_link(a, Some("Grafana".into()), "https://grafana.example/d/1")
+            .unwrap());
+        assert_eq!(
+            book.get_node(a).unwrap().links(),
+            &[
+                (
+                    "Grafana".to_string(),
+                    "https://grafana.example/d/1".to_string()
+                ),
+                (
+                    "mailto:ops@example.com".to_string(),
+                    "mailto:ops@example.com".to_string()
+                ),
+            ]
+        );
+
+        for bad in [
+            "grafana.example/d/1",
+            "https://",
+            "1http://x",
+            "https://a b",
+            "javascript:alert(1)",
+        ] {
+            assert!(
+                matches!(
+                    book.add_link(a, None, bad),
+                    Err(DomainError::InvalidLinkUrl(_))
+                ),
+                "{bad}"
+            );
+        }
+
+        assert!(book.remove_link(a, "Grafana").unwrap());
+        assert!(book.remove_link(a, "mailto:ops@example.com").unwrap());
+        assert!(!book.remove_link(a, "Grafana").unwrap());
+        assert!(book.get_node(a).unwrap().links().is_empty());
+    }
+
+    #[test]
+    fn check_node_count_reports_orphans() {
+        let mut book = make_book();
+        let a = add_content(&mut book, None, "A");
+        add_content(&mut book, Some(a), "A-1");
+        add_content(&mut book, None, "B");
+        assert_eq!(book.check_node_count(), None);
+
+        // 手編集でルート一覧から外れた部分木
+        book.root_nodes.retain(|id| *id != a);
+        let mismatch = book.check_node_count().unwrap();
+        assert_eq!(
+            mismatch,
+            NodeCountMismatch {
+                stored: 3,
+                reachable: 1
+            }
+        );
+        assert_eq!(mismatch.unreachable(), 2);
+        assert!(mismatch.to_string().starts_with("2 of 3 stored nodes"));
+    }
+
+    #[test]
+    fn check_integrity_passes_for_books_built_through_the_api() {
+        let mut book = make_book();
+        let a = add_content(&mut book, None, "A");
+        let a1 = add_content(&mut book, Some(a), "A-1");
+        let b = add_content(&mut book, None, "B");
+        book.move_node(a1, Some(b), Position::First).unwrap();
+        book.move_node(a, None, Position::Last).unwrap();
+        book.remove_node(b).unwrap();
+        assert_eq!(book.check_integrity(), Vec::new());
+    }
+
+    #[test]
+    fn add_child_ignores_a_child_already_listed() {
+        let mut book = make_book();
+        let a = add_content(&mut book, None, "A");
+        let a1 = add_content(&mut book, Some(a), "A-1");
+        let a2 = add_content(&mut book, Some(a), "A-2");
+
+        let parent = book.nodes.get_mut(&a).unwrap();
+        assert_eq!(parent.add_child(a1, 2), 0);
+        assert_eq!(parent.children(), [a1, a2]);
+        assert_eq!(book.all_nodes_dfs().len(), 3);
+        assert_eq!(book.check_integrity(), Vec::new());
+    }
+
+    #[test]
+    fn check_integrity_reports_duplicate_mismatched_and_dangling_references() {
+        let mut book = make_book();
+        let a = add_content(&mut book, None, "A");
+        let a1 = add_content(&mut book, Some(a), "A-1");
+        let b = add_content(&mut book, None, "B");
+
+        // 二重登録（add_child を経由しない古い graft 相当）
+        book.nodes.get_mut(&a).unwrap().children_mut().push(a1);
+        assert_eq!(
+            book.check_integrity(),
+            vec![IntegrityIssue::DuplicateReference { id: a1, count: 2 }]
+        );
+        // DFS は部分木を2回たどり、到達数は格納数より多くなる
+        assert_eq!(book.all_nodes_dfs().len(), 4);
+        assert_eq!(book.check_node_count().unwrap().unreachable(), 0);
+        book.nodes.get_mut(&a).unwrap().remove_child(a1);
+        book.nodes.get_mut(&a).unwrap().add_child(a1, 0);
+
+        // 別の親にも並んでいる子
+        book.nodes.get_mut(&b).unwrap().add_child(a1, 0);
+        let issues = book.check_integrity();
+        assert!(issues.contains(&IntegrityIssue::ParentMismatch {
+            id: a1,
+            listed_under: Some(b),
+            parent: Some(a),
+        }));
+        assert!(issues.contains(&IntegrityIssue::DuplicateReference { id: a1, count: 2 }));
+        book.nodes.get_mut(&b).unwrap().remove_child(a1);
+
+        // 削除済みノードを指す children と、ルートから外れたノード
+        let gone = NodeId::new();
+        book.nodes.get_mut(&b).unwrap().add_child(gone, 0);
+        book.root_nodes.retain(|id| *id != a);
+        let issues = book.check_integrity();
+        assert_eq!(
+            issues,
+            vec![
+                IntegrityIssue::DanglingReference {
+                    id: gone,
+                    listed_under: Some(b),
+                },
+                IntegrityIssue::Unreachable(NodeCountMismatch {
+                    stored: 3,
+                    reachable: 1,
+                }),
+            ]
+        );
+        assert!(issues[0].to_string().ends_with(&format!(
+            "lists node {}, which does not exist",
+            gone.short()
+        )));
+    }
+
+    #[test]
+    fn outline_number_matches_toc_numbering() {
+        let mut book = make_book();
+        let a = add_content(&mut book, None, "A");
+        let b = add_content(&mut book, None, "B");
+        add_content(&mut book, Some(b), "B-1");
+        let b2 = add_content(&mut book, Some(b), "B-2");
+
+        assert_eq!(book.outline_number(a).as_deref(), Some("1"));
+        assert_eq!(book.outline_number(b2).as_deref(), Some("2-2"));
+        assert_eq!(book.outline_number(NodeId::new()), None);
+    }
+
+    fn set_estimate(book: &mut TemplateBook, id: NodeId, minutes: u32) {
+        book.update_node(
+            id,
+            UpdateNodeRequest {
+                estimate_minutes: Some(Some(minutes)),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn estimate_total_skips_non_leaf_sections_and_saturates() {
+        let mut book = make_book();
+        let sec = book
+            .add_node(AddNodeRequest {
+                parent: None,
+                title: "Section".into(),
+                node_type: NodeType::Section,
+                body: None,
+                placeholder: None,
+                position: Position::Last,
+                properties: HashMap::new(),
+            })
+            .unwrap();
+        let a = add_content(&mut book, Some(sec), "A");
+        let b = add_content(&mut book, Some(sec), "B");
+        set_estimate(&mut book, sec, 999);
+        set_estimate(&mut book, a, 20);
+        set_estimate(&mut book, b, 10);
+
+        assert_eq!(book.estimate_total(Some(sec)), 30);
+        assert_eq!(book.estimate_total(Some(a)), 20);
+
+        let c = add_content(&mut book, None, "C");
+        set_estimate(&mut book, c, u32::MAX);
+        assert_eq!(book.estimate_total(None), u32::MAX);
+    }
 }
@@ -42,7 +42,13 @@ impl NodeId {
 
     /// 短縮ID（UUIDの先頭8文字）
     pub fn short(&self) -> String {
-        self.0.to_string()[..8].to_string()
+        self.short_n(8)
+    }
+
+    /// 先頭 `len` 文字の短縮ID。UUID文字列長（36）を超える指定はフルUUIDになる。
+    pub fn short_n(&self, len: usize) -> String {
+        let full = self.0.to_string();
+        full[..len.min(full.len())].to_string()
     }
 }
 
@@ -51,3 +57,31 @@ impl fmt::Display for NodeId {
         write!(f, "{}", self.0)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixed_id() -> NodeId {
+        serde_json::from_str(r#""0123abcd-4567-89ef-0123-456789abcdef""#).unwrap()
+    }
+
+    #[test]
+    fn short_is_first_eight_chars() {
+        assert_eq!(fixed_id().short(), "0123abcd");
+    }
+
+    #[test]
+    fn short_n_respects_length() {
+        let id = fixed_id();
+        assert_eq!(id.short_n(4), "0123");
+        assert_eq!(id.short_n(13), "0123abcd-4567");
+        assert_eq!(id.short_n(0), "");
+    }
+
+    #[test]
+    fn short_n_clamps_to_full_uuid() {
+        let id = fixed_id();
+        assert_eq!(id.short_n(100), id.to_string());
+    }
+}
@@ -35,6 +35,61 @@ pub struct TemplateNode {
     /// 最終更新タイムスタンプ。既存JSONファイルには存在しないため `#[serde(default)]` で None に。
     #[serde(default)]
     updated_at: Option<Timestamp>,
+    /// 作成タイムスタンプ。導入前のJSONファイルには存在しないため `#[serde(default)]` で None に。
+    #[serde(default)]
+    created_at: Option<Timestamp>,
+    /// 非公開メモ（経緯・関連リンク等）。Markdown Eject には既定で出力しない。
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    notes: Option<String>,
+    /// 公開の注意書き（"heads up"）。本文と違ってチェックボックスにはならず、
+    /// Markdown Eject では項目の下の引用として常に出力する。
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    note: Option<String>,
+    /// 項目で実行するシェルコマンド（本文とは別）。Markdown Eject では項目の下の
+    /// ```` ```sh ```` ブロックになり、`extract_commands` でスクリプトとして取り出せる。
+    /// 実行はしない
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    command: Option<String>,
+    /// 着手前に完了しているべきノード（"blocked by"）。
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    depends_on: Vec<NodeId>,
+    /// 外部リンク（ラベル, URL）。Markdown Eject では項目の下に `[label](url)` の
+    /// リストとして出力する。
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    links: Vec<(String, String)>,
+    /// 作業見積もり（分）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    estimate_minutes: Option<u32>,
+    /// 担当者（共同実行する Runbook 用）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    owner: Option<String>,
+    /// チェック済み（繰り返し使う Runbook の実行状態）
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    done: bool,
+    /// Section の子が順序を持つか。`false` なら参照リンク集のような順不同の集まり。
+    /// 導入前のJSONファイルには存在しないため既定は `true`（`true` のときは書き出さない）。
+    #[serde(default = "default_ordered", skip_serializing_if = "is_ordered")]
+    ordered: bool,
+    /// 兄弟内の並び順を決める任意のキー（10, 20, 30 のように疎に振る）。
+    /// キーを持つ兄弟が昇順で先に並び、持たない兄弟は挿入順でその後に続く。
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    order: Option<f64>,
+    /// 削除から守る（消してはならない重要な手順）。守られたノードを含む部分木は
+    /// 明示的に上書きしない限り削除できない
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    protected: bool,
+    /// Markdown Eject での見出しレベルの上書き（1〜6）。子孫の Section はここから
+    /// 1段ずつ下がる。未設定なら深さから決める
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    heading_level: Option<usize>,
+}
+
+fn default_ordered() -> bool {
+    true
+}
+
+fn is_ordered(ordered: &bool) -> bool {
+    *ordered
 }
 
 impl TemplateNode {
@@ -44,6 +99,7 @@ impl TemplateNode {
         title: String,
         node_type: NodeType,
     ) -> Self {
+        let now = Timestamp::now();
         Self {
             id,
             parent,
@@ -54,7 +110,20 @@ impl TemplateNode {
             placeholder: None,
             properties: HashMap::new(),
             status: NodeStatus::Active,
-            updated_at: Some(Timestamp::now()),
+            updated_at: Some(now),
+            created_at: Some(now),
+            notes: None,
+            note: None,
+            command: None,
+            depends_on: Vec::new(),
+            links: Vec::new(),
+            estimate_minutes: None,
+            owner: None,
+            done: false,
+            protected: false,
+            ordered: true,
+            order: None,
+            heading_level: None,
         }
     }
 
@@ -118,6 +187,73 @@ impl TemplateNode {
         self.updated_at
     }
 
+    /// Return the node's creation timestamp, if known (absent for nodes
+    /// created before creation times were recorded).
+    pub fn created_at(&self) -> Option<Timestamp> {
+        self.created_at
+    }
+
+    /// Return the node's private notes, if any.
+    pub fn notes(&self) -> Option<&str> {
+        self.notes.as_deref()
+    }
+
+    /// Return the node's public heads-up note, if any.
+    pub fn note(&self) -> Option<&str> {
+        self.note.as_deref()
+    }
+
+    /// Return the node's runnable shell command, if any.
+    pub fn command(&self) -> Option<&str> {
+        self.command.as_deref()
+    }
+
+    /// Return the IDs of nodes this node is blocked by, in declaration order.
+    pub fn depends_on(&self) -> &[NodeId] {
+        &self.depends_on
+    }
+
+    /// Return the node's external links as `(label, url)` pairs, in insertion order.
+    pub fn links(&self) -> &[(String, String)] {
+        &self.links
+    }
+
+    /// Return the node's effort estimate in minutes, if set.
+    pub fn estimate_minutes(&self) -> Option<u32> {
+        self.estimate_minutes
+    }
+
+    /// Return the node's owner (assignee), if any.
+    pub fn owner(&self) -> Option<&str> {
+        self.owner.as_deref()
+    }
+
+    /// Return `true` if the node has been checked off.
+    pub fn is_done(&self) -> bool {
+        self.done
+    }
+
+    /// Return `true` if the node is protected from removal.
+    pub fn is_protected(&self) -> bool {
+        self.protected
+    }
+
+    /// Return `false` for a Section whose children are an unordered collection.
+    /// Content nodes are always ordered.
+    pub fn is_ordered(&self) -> bool {
+        self.ordered || self.node_type != NodeType::Section
+    }
+
+    /// Return the node's manual sort key among its siblings, if any.
+    pub fn order(&self) -> Option<f64> {
+        self.order
+    }
+
+    /// Return the Markdown heading level override (1-6), if set.
+    pub fn heading_level(&self) -> Option<usize> {
+        self.heading_level
+    }
+
     // --- 内部操作（Book経由でのみ呼ばれる） ---
 
     pub(crate) fn set_title(&mut self, title: String) {
@@ -130,6 +266,103 @@ impl TemplateNode {
         self.updated_at = Some(Timestamp::now());
     }
 
+    pub(crate) fn set_notes(&mut self, notes: Option<String>) {
+        self.notes = notes;
+        self.updated_at = Some(Timestamp::now());
+    }
+
+    pub(crate) fn set_note(&mut self, note: Option<String>) {
+        self.note = note;
+        self.updated_at = Some(Timestamp::now());
+    }
+
+    pub(crate) fn set_command(&mut self, command: Option<String>) {
+        self.command = command;
+        self.updated_at = Some(Timestamp::now());
+    }
+
+    pub(crate) fn set_estimate_minutes(&mut self, minutes: Option<u32>) {
+        self.estimate_minutes = minutes;
+        self.updated_at = Some(Timestamp::now());
+    }
+
+    pub(crate) fn set_owner(&mut self, owner: Option<String>) {
+        self.owner = owner;
+        self.updated_at = Some(Timestamp::now());
+    }
+
+    pub(crate) fn set_done(&mut self, done: bool) {
+        self.done = done;
+        self.updated_at = Some(Timestamp::now());
+    }
+
+    pub(crate) fn set_protected(&mut self, protected: bool) {
+        self.protected = protected;
+        self.updated_at = Some(Timestamp::now());
+    }
+
+    pub(crate) fn set_ordered(&mut self, ordered: bool) {
+        self.ordered = ordered;
+        self.updated_at = Some(Timestamp::now());
+    }
+
+    pub(crate) fn set_order(&mut self, order: Option<f64>) {
+        self.order = order;
+        self.updated_at = Some(Timestamp::now());
+    }
+
+    pub(crate) fn set_heading_level(&mut self, level: Option<usize>) {
+        self.heading_level = level;
+        self.updated_at = Some(Timestamp::now());
+    }
+
+    pub(crate) fn add_dependency(&mut self, on: NodeId) -> bool {
+        if self.depends_on.contains(&on) {
+            return false;
+        }
+        self.depends_on.push(on);
+        self.updated_at = Some(Timestamp::now());
+        true
+    }
+
+    pub(crate) fn remove_dependency(&mut self, on: NodeId) -> bool {
+        let before = self.depends_on.len();
+        self.depends_on.retain(|d| *d != on);
+        let removed = self.depends_on.len() != before;
+        if removed {
+            self.updated_at = Some(Timestamp::now());
+        }
+        removed
+    }
+
+    /// 同じ URL が既にあればラベルだけ差し替える。何も変わらなければ `false`。
+    pub(crate) fn add_link(&mut self, label: String, url: String) -> bool {
+        match self.links.iter_mut().find(|(_, u)| *u == url) {
+            Some((l, _)) if *l == label => return false,
+            Some((l, _)) => *l = label,
+            None => self.links.push((label, url)),
+        }
+        self.updated_at = Some(Timestamp::now());
+        true
+    }
+
+    pub(crate) fn set_links(&mut self, links: Vec<(String, String)>) {
+        self.links = links;
+        self.updated_at = Some(Timestamp::now());
+    }
+
+    /// URL またはラベルが一致するリンクを外す。
+    pub(crate) fn remove_link(&mut self, url_or_label: &str) -> bool {
+        let before = self.links.len();
+        self.links
+            .retain(|(l, u)| u != url_or_label && l != url_or_label);
+        let removed = self.links.len() != before;
+        if removed {
+            self.updated_at = Some(Timestamp::now());
+        }
+        removed
+    }
+
     pub(crate) fn set_node_type(&mut self, node_type: NodeType) {
         self.node_type = node_type;
         self.updated_at = Some(Timestamp::now());
@@ -145,9 +378,19 @@ impl TemplateNode {
         self.updated_at = Some(Timestamp::now());
     }
 
-    pub(crate) fn add_child(&mut self, child_id: NodeId, position: usize) {
+    /// 子を挿入し、実際の挿入位置（末尾へ丸めた後）を返す。
+    /// 既に子として持っていれば何もせず、今の位置を返す（同じ子の二重登録を防ぐ）。
+    pub(crate) fn add_child(&mut self, child_id: NodeId, position: usize) -> usize {
+        if let Some(existing) = self.children.iter().position(|id| *id == child_id) {
+            return existing;
+        }
         let pos = position.min(self.children.len());
         self.children.insert(pos, child_id);
+        pos
+    }
+
+    pub(crate) fn children_mut(&mut self) -> &mut Vec<NodeId> {
+        &mut self.children
     }
 
     pub(crate) fn remove_child(&mut self, child_id: NodeId) {
@@ -159,6 +402,11 @@ impl TemplateNode {
         self.updated_at = Some(Timestamp::now());
     }
 
+    #[cfg(test)]
+    pub(crate) fn set_created_at(&mut self, created_at: Option<Timestamp>) {
+        self.created_at = created_at;
+    }
+
     // Subtask 4 (snapshot_restore) で使用予定
     #[allow(dead_code)]
     pub(crate) fn set_status(&mut self, status: NodeStatus) {
@@ -238,6 +486,84 @@ mod tests {
         let node: TemplateNode = serde_json::from_str(json).expect("deserialize legacy json");
         assert_eq!(node.status(), NodeStatus::Active);
         assert!(node.updated_at().is_none());
+        assert!(node.created_at().is_none());
+    }
+
+    #[test]
+    fn test_serde_ordered_defaults_to_true_and_is_written_only_when_false() {
+        let json = r#"{
+            "id": "00000000-0000-0000-0000-000000000001",
+            "parent": null,
+            "children": [],
+            "title": "Reference links",
+            "body": null,
+            "node_type": "Section",
+            "placeholder": null
+        }"#;
+        let mut node: TemplateNode = serde_json::from_str(json).expect("deserialize legacy json");
+        assert!(node.is_ordered());
+        assert!(!serde_json::to_string(&node).unwrap().contains("ordered"));
+
+        node.set_ordered(false);
+        let written = serde_json::to_string(&node).unwrap();
+        assert!(written.contains("\"ordered\":false"));
+        let back: TemplateNode = serde_json::from_str(&written).unwrap();
+        assert!(!back.is_ordered());
+
+        // Content は常に順序付き
+        let mut content = make_node();
+        content.set_ordered(false);
+        assert!(content.is_ordered());
+    }
+
+    #[test]
+    fn test_serde_command_is_optional_for_older_files() {
+        let json = r#"{
+            "id": "00000000-0000-0000-0000-000000000001",
+            "parent": null,
+            "children": [],
+            "title": "Restart the service",
+            "body": null,
+            "node_type": "Content",
+            "placeholder": null
+        }"#;
+        let mut node: TemplateNode = serde_json::from_str(json).expect("deserialize legacy json");
+        assert_eq!(node.command(), None);
+        assert!(!serde_json::to_string(&node).unwrap().contains("command"));
+
+        node.set_command(Some("systemctl restart app".into()));
+        let back: TemplateNode =
+            serde_json::from_str(&serde_json::to_string(&node).unwrap()).unwrap();
+        assert_eq!(back.command(), Some("systemctl restart app"));
+    }
+
+    #[test]
+    fn test_serde_protected_defaults_to_false_and_is_written_only_when_set() {
+        let json = r#"{
+            "id": "00000000-0000-0000-0000-000000000001",
+            "parent": null,
+            "children": [],
+            "title": "Confirm backups exist",
+            "body": null,
+            "node_type": "Content",
+            "placeholder": null
+        }"#;
+        let mut node: TemplateNode = serde_json::from_str(json).expect("deserialize legacy json");
+        assert!(!node.is_protected());
+        assert!(!serde_json::to_string(&node).unwrap().contains("protected"));
+
+        node.set_protected(true);
+        let written = serde_json::to_string(&node).unwrap();
+        assert!(written.contains("\"protected\":true"));
+        let back: TemplateNode = serde_json::from_str(&written).unwrap();
+        assert!(back.is_protected());
+    }
+
+    #[test]
+    fn test_new_sets_created_at() {
+        let node = make_node();
+        assert!(node.created_at().is_some());
+        assert_eq!(node.created_at(), node.updated_at());
     }
 
     #[test]
@@ -6,7 +6,7 @@ use super::model::id::NodeId;
 
 /// 永続化の抽象。Infra層が実装する。
 #[async_trait]
-pub trait BookRepository {
+pub trait BookRepository: Send + Sync {
     /// Storage-backend-specific error type.
     type Error: std::error::Error + Send + Sync + 'static;
 
@@ -14,6 +14,114 @@ pub trait BookRepository {
     async fn load(&self) -> Result<Option<TemplateBook>, Self::Error>;
     /// Persist the book, overwriting any existing stored state.
     async fn save(&self, book: &TemplateBook) -> Result<(), Self::Error>;
+    /// Return whether a book is currently stored.
+    async fn exists(&self) -> Result<bool, Self::Error>;
+    /// Remove the stored book. Deleting a book that does not exist is not an error.
+    async fn delete(&self) -> Result<(), Self::Error>;
+    /// Persist the book only if none is stored yet. Returns `false`, leaving
+    /// the stored book untouched, when one already exists.
+    ///
+    /// 既定の実装は `exists` と `save` を続けて呼ぶだけで競合に弱い。
+    /// 同時に作られうるバックエンドは原子的に上書きする。
+    async fn create(&self, book: &TemplateBook) -> Result<bool, Self::Error> {
+        if self.exists().await? {
+            return Ok(false);
+        }
+        self.save(book).await?;
+        Ok(true)
+    }
+}
+
+/// バックエンド固有のエラーを型消去したもの。
+///
+/// `Box<dyn Error>` 自体は `std::error::Error` を実装しないため newtype で包む。
+#[derive(Debug)]
+pub struct RepositoryError(Box<dyn std::error::Error + Send + Sync>);
+
+impl RepositoryError {
+    /// Wrap any backend error.
+    pub fn new(err: impl Into<Box<dyn std::error::Error + Send + Sync>>) -> Self {
+        Self(err.into())
+    }
+}
+
+impl std::fmt::Display for RepositoryError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl std::error::Error for RepositoryError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.0.source()
+    }
+}
+
+/// trait object 化した `BookRepository`。バックエンドを実行時に差し替えるために使う。
+pub type DynBookRepository = Box<dyn BookRepository<Error = RepositoryError> + Send + Sync>;
+
+/// 具象リポジトリのエラーを `RepositoryError` に変換するアダプタ。
+struct ErasedBookRepository<R>(R);
+
+#[async_trait]
+impl<R> BookRepository for ErasedBookRepository<R>
+where
+    R: BookRepository + Send + Sync,
+{
+    type Error = RepositoryError;
+
+    async fn load(&self) -> Result<Option<TemplateBook>, Self::Error> {
+        self.0.load().await.map_err(RepositoryError::new)
+    }
+
+    async fn save(&self, book: &TemplateBook) -> Result<(), Self::Error> {
+        self.0.save(book).await.map_err(RepositoryError::new)
+    }
+
+    async fn exists(&self) -> Result<bool, Self::Error> {
+        self.0.exists().await.map_err(RepositoryError::new)
+    }
+
+    async fn delete(&self) -> Result<(), Self::Error> {
+        self.0.delete().await.map_err(RepositoryError::new)
+    }
+
+    async fn create(&self, book: &TemplateBook) -> Result<bool, Self::Error> {
+        self.0.create(book).await.map_err(RepositoryError::new)
+    }
+}
+
+/// 任意の `BookRepository` を `DynBookRepository` に変換する。
+pub fn boxed_repository<R>(repo: R) -> DynBookRepository
+where
+    R: BookRepository + Send + Sync + 'static,
+{
+    Box::new(ErasedBookRepository(repo))
+}
+
+#[async_trait]
+impl<'a> BookRepository for Box<dyn BookRepository<Error = RepositoryError> + Send + Sync + 'a> {
+    type Error = RepositoryError;
+
+    async fn load(&self) -> Result<Option<TemplateBook>, Self::Error> {
+        (**self).load().await
+    }
+
+    async fn save(&self, book: &TemplateBook) -> Result<(), Self::Error> {
+        (**self).save(book).await
+    }
+
+    async fn exists(&self) -> Result<bool, Self::Error> {
+        (**self).exists().await
+    }
+
+    async fn delete(&self) -> Result<(), Self::Error> {
+        (**self).delete().await
+    }
+
+    async fn create(&self, book: &TemplateBook) -> Result<bool, Self::Error> {
+        (**self).create(book).await
+    }
 }
 
 /// ChangeLog の永続化抽象。Infra層が実装する。
@@ -37,3 +145,93 @@ pub trait ChangeLogRepository: Send + Sync {
         node_id: NodeId,
     ) -> Result<Vec<ChangeEntry>, Box<dyn std::error::Error + Send + Sync>>;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::application::service::BookService;
+    use crate::domain::model::book::{AddNodeRequest, Position};
+    use crate::domain::model::node::NodeType;
+    use std::collections::HashMap;
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Clone, Default)]
+    struct InMemoryBookRepo {
+        book: Arc<Mutex<Option<TemplateBook>>>,
+    }
+
+    #[derive(Debug, thiserror::Error)]
+    #[error("in-memory repo error")]
+    struct RepoError;
+
+    #[async_trait]
+    impl BookRepository for InMemoryBookRepo {
+        type Error = RepoError;
+        async fn load(&self) -> Result<Option<TemplateBook>, RepoError> {
+            Ok(self.book.lock().unwrap().clone())
+        }
+        async fn save(&self, book: &TemplateBook) -> Result<(), RepoError> {
+            *self.book.lock().unwrap() = Some(book.clone());
+            Ok(())
+        }
+        async fn exists(&self) -> Result<bool, RepoError> {
+            Ok(self.book.lock().unwrap().is_some())
+        }
+        async fn delete(&self) -> Result<(), RepoError> {
+            *self.book.lock().unwrap() = None;
+            Ok(())
+        }
+    }
+
+    struct ReadOnlyRepo;
+
+    #[async_trait]
+    impl BookRepository for ReadOnlyRepo {
+        type Error = RepoError;
+        async fn load(&self) -> Result<Option<TemplateBook>, RepoError> {
+            Ok(Some(TemplateBook::new("Remote", 4)))
+        }
+        async fn save(&self, _book: &TemplateBook) -> Result<(), RepoError> {
+            Err(RepoError)
+        }
+        async fn exists(&self) -> Result<bool, RepoError> {
+            Ok(true)
+        }
+        async fn delete(&self) -> Result<(), RepoError> {
+            Err(RepoError)
+        }
+    }
+
+    #[tokio::test]
+    async fn boxed_repository_drives_book_service() {
+        let inner = InMemoryBookRepo::default();
+        let svc = BookService::new(boxed_repository(inner.clone()));
+        svc.create_book("Dyn", 4).await.unwrap();
+        svc.add_node(AddNodeRequest {
+            parent: None,
+            title: "A".into(),
+            node_type: NodeType::Content,
+            body: None,
+            placeholder: None,
+            position: Position::Last,
+            properties: HashMap::new(),
+        })
+        .await
+        .unwrap();
+
+        let stored = inner.book.lock().unwrap().clone().unwrap();
+        assert_eq!(stored.title(), "Dyn");
+        assert_eq!(stored.node_count(), 1);
+    }
+
+    #[tokio::test]
+    async fn boxed_repository_preserves_backend_error_message() {
+        let repo = boxed_repository(ReadOnlyRepo);
+        assert_eq!(repo.load().await.unwrap().unwrap().title(), "Remote");
+
+        let err = repo.save(&TemplateBook::new("x", 4)).await.unwrap_err();
+        assert_eq!(err.to_string(), "in-memory repo error");
+        assert!(repo.exists().await.unwrap());
+        assert!(repo.delete().await.is_err());
+    }
+}
@@ -53,12 +53,45 @@ impl BookRepository for JsonBookRepository {
         tokio::fs::rename(&tmp, &self.path).await?;
         Ok(())
     }
+
+    async fn exists(&self) -> Result<bool, Self::Error> {
+        Ok(tokio::fs::try_exists(&self.path).await?)
+    }
+
+    async fn delete(&self) -> Result<(), Self::Error> {
+        match tokio::fs::remove_file(&self.path).await {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// 一意な一時ファイルに書いてからハードリンクで置く。リンクは既存のファイルを
+    /// 上書きしないので、同時に作られても書き込めるのは1つだけで、読み手が書きかけの
+    /// ファイルを見ることもない。
+    async fn create(&self, book: &TemplateBook) -> Result<bool, Self::Error> {
+        if let Some(parent) = self.path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        let content = serde_json::to_string_pretty(book)?;
+        let tmp = self
+            .path
+            .with_extension(format!("{}.tmp", uuid::Uuid::new_v4()));
+        tokio::fs::write(&tmp, &content).await?;
+        let linked = tokio::fs::hard_link(&tmp, &self.path).await;
+        let _ = tokio::fs::remove_file(&tmp).await;
+        match linked {
+            Ok(()) => Ok(true),
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => Ok(false),
+            Err(e) => Err(e.into()),
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::domain::model::book::AddNodeRequest;
+    use crate::domain::model::book::{AddNodeRequest, Position};
     use crate::domain::model::node::NodeType;
 
     #[tokio::test]
@@ -80,7 +113,7 @@ mod tests {
             node_type: NodeType::Content,
             body: Some("description".into()),
             placeholder: Some("notes".into()),
-            position: usize::MAX,
+            position: Position::Last,
             properties: std::collections::HashMap::new(),
         })
         .unwrap();
@@ -100,4 +133,62 @@ mod tests {
         // cleanup
         let _ = std::fs::remove_dir_all(&dir);
     }
+
+    #[test]
+    fn concurrent_creates_store_exactly_one_book() {
+        let dir = std::env::temp_dir().join("outline-mcp-test-create-race");
+        let _ = std::fs::remove_dir_all(&dir);
+        let path = dir.join("race.json");
+        let barrier = std::sync::Barrier::new(8);
+
+        let results: Vec<(String, bool)> = std::thread::scope(|scope| {
+            let handles: Vec<_> = (0..8)
+                .map(|i| {
+                    let (path, barrier) = (&path, &barrier);
+                    scope.spawn(move || {
+                        let runtime = tokio::runtime::Builder::new_current_thread()
+                            .build()
+                            .unwrap();
+                        let title = format!("Writer {i}");
+                        let book = TemplateBook::new(&title, 3);
+                        barrier.wait();
+                        let created = runtime
+                            .block_on(JsonBookRepository::new(path).create(&book))
+                            .unwrap();
+                        (title, created)
+                    })
+                })
+                .collect();
+            handles.into_iter().map(|h| h.join().unwrap()).collect()
+        });
+
+        let winners: Vec<&String> = results.iter().filter(|(_, c)| *c).map(|(t, _)| t).collect();
+        assert_eq!(winners.len(), 1, "{results:?}");
+        let stored = std::fs::read_to_string(&path).unwrap();
+        let stored: TemplateBook = serde_json::from_str(&stored).unwrap();
+        assert_eq!(stored.title(), winners[0]);
+        // 一時ファイルは残らない
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn exists_and_delete() {
+        let dir = std::env::temp_dir().join("outline-mcp-test-exists-delete");
+        let _ = std::fs::remove_dir_all(&dir);
+        let repo = JsonBookRepository::new(dir.join("book.json"));
+
+        assert!(!repo.exists().await.unwrap());
+        repo.save(&TemplateBook::new("Doomed", 3)).await.unwrap();
+        assert!(repo.exists().await.unwrap());
+
+        repo.delete().await.unwrap();
+        assert!(!repo.exists().await.unwrap());
+        assert!(repo.load().await.unwrap().is_none());
+        // 存在しないファイルの削除はエラーにしない
+        repo.delete().await.unwrap();
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
 }
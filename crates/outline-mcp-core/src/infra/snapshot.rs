@@ -347,7 +347,7 @@ impl SnapshotService {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::domain::model::book::{AddNodeRequest, TemplateBook};
+    use crate::domain::model::book::{AddNodeRequest, Position, TemplateBook};
     use crate::domain::model::node::NodeType;
     use ai_store_core::{CacheBackend, EventBackend, StoreConfig};
     use ai_store_mem::{MemCacheBackend, MemEventBackend};
@@ -371,7 +371,7 @@ mod tests {
             node_type: NodeType::Content,
             body: Some("body text".into()),
             placeholder: None,
-            position: usize::MAX,
+            position: Position::Last,
             properties: HashMap::new(),
         })
         .expect("add node");
@@ -621,7 +621,7 @@ mod tests {
                 node_type: NodeType::Content,
                 body: None,
                 placeholder: None,
-                position: usize::MAX,
+                position: Position::Last,
                 properties: HashMap::new(),
             })
             .expect("add second node");
@@ -406,7 +406,7 @@ pub async fn migrate_slug(shelf_dir: &Path, slug: &str) -> Result<MigrationRepor
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::domain::model::book::AddNodeRequest;
+    use crate::domain::model::book::{AddNodeRequest, Position};
     use crate::domain::model::node::NodeType;
     use crate::infra::snapshot::{write_meta, write_snapshot_body, SnapshotMeta, SnapshotService};
     use crate::infra::snapshot_sink::SnapshotDumpSink;
@@ -431,7 +431,7 @@ mod tests {
             node_type: NodeType::Content,
             body: Some("body text".into()),
             placeholder: None,
-            position: usize::MAX,
+            position: Position::Last,
             properties: HashMap::new(),
         })
         .expect("add node");
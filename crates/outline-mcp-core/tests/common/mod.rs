@@ -7,7 +7,7 @@ use std::sync::Mutex;
 
 use async_trait::async_trait;
 use outline_mcp_core::application::service::BookService;
-use outline_mcp_core::domain::model::book::{AddNodeRequest, TemplateBook};
+use outline_mcp_core::domain::model::book::{AddNodeRequest, Position, TemplateBook};
 use outline_mcp_core::domain::model::id::NodeId;
 use outline_mcp_core::domain::model::node::NodeType;
 use outline_mcp_core::domain::repository::BookRepository;
@@ -63,6 +63,15 @@ impl BookRepository for InMemoryRepo {
         self.seed(book);
         Ok(())
     }
+
+    async fn exists(&self) -> Result<bool, Self::Error> {
+        Ok(self.store.lock().unwrap().contains_key("book"))
+    }
+
+    async fn delete(&self) -> Result<(), Self::Error> {
+        self.store.lock().unwrap().remove("book");
+        Ok(())
+    }
 }
 
 // =============================================================================
@@ -96,7 +105,7 @@ impl TestBook {
                 node_type: NodeType::Section,
                 body: None,
                 placeholder: None,
-                position: usize::MAX,
+                position: Position::Last,
                 properties: std::collections::HashMap::new(),
             })
             .unwrap();
@@ -109,7 +118,7 @@ impl TestBook {
                 node_type: NodeType::Content,
                 body: None,
                 placeholder: Some("requirements list".into()),
-                position: usize::MAX,
+                position: Position::Last,
                 properties: std::collections::HashMap::new(),
             })
             .unwrap();
@@ -122,7 +131,7 @@ impl TestBook {
                 node_type: NodeType::Content,
                 body: Some("REST endpoints".into()),
                 placeholder: None,
-                position: usize::MAX,
+                position: Position::Last,
                 properties: std::collections::HashMap::new(),
             })
             .unwrap();
@@ -135,7 +144,7 @@ impl TestBook {
                 node_type: NodeType::Section,
                 body: None,
                 placeholder: None,
-                position: usize::MAX,
+                position: Position::Last,
                 properties: std::collections::HashMap::new(),
             })
             .unwrap();
@@ -148,7 +157,7 @@ impl TestBook {
                 node_type: NodeType::Content,
                 body: None,
                 placeholder: None,
-                position: usize::MAX,
+                position: Position::Last,
                 properties: std::collections::HashMap::new(),
             })
             .unwrap();
@@ -161,7 +170,7 @@ impl TestBook {
                 node_type: NodeType::Content,
                 body: Some("- unit\n- integration".into()),
                 placeholder: None,
-                position: usize::MAX,
+                position: Position::Last,
                 properties: std::collections::HashMap::new(),
             })
             .unwrap();
@@ -4,9 +4,13 @@ mod common;
 
 use common::{assert_error_contains, TestBook};
 
-use outline_mcp_core::application::eject::{EjectConfig, EjectFormat, EjectService};
+use outline_mcp_core::application::eject::{
+    CheckboxLabels, EjectConfig, EjectFormat, EjectService, MarkdownStyle,
+};
 use outline_mcp_core::application::service::BookService;
-use outline_mcp_core::domain::model::book::{AddNodeRequest, TemplateBook, UpdateNodeRequest};
+use outline_mcp_core::domain::model::book::{
+    AddNodeRequest, Position, TemplateBook, UpdateNodeRequest,
+};
 use outline_mcp_core::domain::model::node::NodeType;
 use outline_mcp_core::infra::json_store::JsonBookRepository;
 
@@ -27,18 +31,19 @@ async fn service_add_node() {
     let tb = TestBook::standard();
     let svc = TestBook::service_with_book(&tb.book);
 
-    let (id, _warning) = svc
+    let (placed, _warning) = svc
         .add_node(AddNodeRequest {
             parent: None,
             title: "New Section".into(),
             node_type: NodeType::Section,
             body: None,
             placeholder: None,
-            position: usize::MAX,
+            position: Position::Last,
             properties: std::collections::HashMap::new(),
         })
         .await
         .unwrap();
+    let id = placed.node_id;
 
     let book = svc.read_tree().await.unwrap();
     assert_eq!(book.node_count(), tb.book.node_count() + 1);
@@ -56,10 +61,7 @@ async fn service_update_node() {
         UpdateNodeRequest {
             title: Some("Architecture".into()),
             body: Some(Some("Updated body".into())),
-            node_type: None,
-            placeholder: None,
-            properties: None,
-            status: None,
+            ..Default::default()
         },
     )
     .await
@@ -80,9 +82,9 @@ async fn service_move_node() {
     let design_id = tb.ids["design"];
 
     // Implementation配下のcodeをDesign配下に移動
-    svc.move_node(code_id, Some(design_id), 0)
+    svc.move_node(code_id, Some(design_id), Position::At(0))
         .await
-        .map(|((), _warning)| ())
+        .map(|(_placed, _warning)| ())
         .unwrap();
 
     let book = svc.read_tree().await.unwrap();
@@ -100,7 +102,7 @@ async fn service_remove_node() {
     // Design (+ 2 children) を削除 → 3ノード減
     svc.remove_node(tb.ids["design"])
         .await
-        .map(|((), _warning)| ())
+        .map(|(_placed, _warning)| ())
         .unwrap();
 
     let book = svc.read_tree().await.unwrap();
@@ -130,6 +132,19 @@ fn eject_writes_markdown_file() {
         include_placeholders: true,
         format: EjectFormat::Markdown,
         subtree_root: None,
+        created_after: None,
+        include_notes: false,
+        include_estimates: false,
+        owner: None,
+        include_ancestors: false,
+        style: MarkdownStyle::Checkbox,
+        wrap_width: None,
+        source_slug: None,
+        labels: CheckboxLabels::default(),
+        section_separators: false,
+        mark_incomplete: false,
+        mark_body_lines: false,
+        footer: false,
     };
 
     let path = EjectService::eject(&tb.book, &config).unwrap();
@@ -151,6 +166,19 @@ fn eject_writes_json_file() {
         include_placeholders: true,
         format: EjectFormat::Json,
         subtree_root: None,
+        created_after: None,
+        include_notes: false,
+        include_estimates: false,
+        owner: None,
+        include_ancestors: false,
+        style: MarkdownStyle::Checkbox,
+        wrap_width: None,
+        source_slug: None,
+        labels: CheckboxLabels::default(),
+        section_separators: false,
+        mark_incomplete: false,
+        mark_body_lines: false,
+        footer: false,
     };
 
     let path = EjectService::eject(&tb.book, &config).unwrap();
@@ -172,6 +200,19 @@ fn eject_subtree_only() {
         include_placeholders: true,
         format: EjectFormat::Markdown,
         subtree_root: Some(tb.ids["design"]),
+        created_after: None,
+        include_notes: false,
+        include_estimates: false,
+        owner: None,
+        include_ancestors: false,
+        style: MarkdownStyle::Checkbox,
+        wrap_width: None,
+        source_slug: None,
+        labels: CheckboxLabels::default(),
+        section_separators: false,
+        mark_incomplete: false,
+        mark_body_lines: false,
+        footer: false,
     };
 
     let path = EjectService::eject(&tb.book, &config).unwrap();
@@ -219,8 +260,19 @@ fn import_rejects_deep_nesting() {
         node_type: "content".into(),
         body: None,
         placeholder: None,
+        notes: None,
+        note: None,
+        command: None,
+        links: Vec::new(),
+        estimate_minutes: None,
+        owner: None,
+        ordered: None,
+        order: None,
+        heading_level: None,
         children: vec![],
         properties: std::collections::HashMap::new(),
+        content_hash: None,
+        children_hash: None,
     };
     for i in (0..40).rev() {
         node = EjectTreeNode {
@@ -229,17 +281,40 @@ fn import_rejects_deep_nesting() {
             node_type: "section".into(),
             body: None,
             placeholder: None,
+            notes: None,
+            note: None,
+            command: None,
+            links: Vec::new(),
+            estimate_minutes: None,
+            owner: None,
+            ordered: None,
+            order: None,
+            heading_level: None,
             children: vec![node],
             properties: std::collections::HashMap::new(),
+            content_hash: None,
+            children_hash: None,
         };
     }
 
     let tree = EjectTree {
         title: "Deep".into(),
-        max_depth: 50, // Bookのmax_depthは広くてもimportの再帰制限で弾く
+        // max_depth は上限 (16) まで。それを超えるネストは深さ制限か
+        // importの再帰制限で弾く（max_depth 自体の検証エラーではないこと）
+        max_depth: outline_mcp_core::domain::model::book::TemplateBook::MAX_DEPTH_LIMIT,
         nodes: vec![node],
+        default_placeholder: None,
+        ancestors: Vec::new(),
+        book_id: None,
+        source_slug: None,
+        subtree_uuid: None,
+        subtree_path: None,
     };
 
     let result = EjectService::import_tree(&tree);
     assert!(result.is_err());
+    assert!(!result
+        .unwrap_err()
+        .to_string()
+        .contains("max_depth must be between"));
 }
@@ -5,8 +5,8 @@ mod common;
 use common::TestBook;
 use proptest::prelude::*;
 
-use outline_mcp_core::application::eject::EjectService;
-use outline_mcp_core::domain::model::book::{AddNodeRequest, TemplateBook};
+use outline_mcp_core::application::eject::{EjectService, EjectTree, MarkdownOptions};
+use outline_mcp_core::domain::model::book::{AddNodeRequest, Position, TemplateBook};
 use outline_mcp_core::domain::model::node::NodeType;
 
 // =============================================================================
@@ -79,7 +79,7 @@ proptest! {
             node_type: NodeType::Content,
             body: None,
             placeholder: None,
-            position: usize::MAX,
+            position: Position::Last,
             properties: std::collections::HashMap::new(),
         }).unwrap();
 
@@ -99,7 +99,7 @@ proptest! {
             node_type: NodeType::Section,
             body: None,
             placeholder: None,
-            position: usize::MAX,
+            position: Position::Last,
             properties: std::collections::HashMap::new(),
         }).unwrap();
 
@@ -117,7 +117,7 @@ proptest! {
                 node_type: NodeType::Content,
                 body: None,
                 placeholder: None,
-                position: usize::MAX,
+                position: Position::Last,
                 properties: std::collections::HashMap::new(),
             }).unwrap();
         }
@@ -150,7 +150,7 @@ proptest! {
             node_type: NodeType::Content,
             body: None,
             placeholder: None,
-            position: usize::MAX,
+            position: Position::Last,
             properties: std::collections::HashMap::new(),
         }).unwrap();
 
@@ -159,3 +159,113 @@ proptest! {
         prop_assert!(md.contains(&expected));
     }
 }
+
+// =============================================================================
+// Markdown roundtrip
+// =============================================================================
+
+/// 構造の構文（チェックボックス・placeholder・notes・本文の印）を含む本文の1行。
+fn adversarial_line() -> impl Strategy<Value = String> {
+    prop_oneof![
+        "[A-Za-z][A-Za-z ]{0,11}",
+        "- \\[ \\] [a-z]{1,8}",
+        "- \\[x\\] [a-z]{1,8}",
+        "- [a-z]{1,8}",
+        "  - [a-z]{1,6}",
+        "> [a-z]{1,6}: ___",
+        "_[a-z]{1,6}_",
+        "[a-z]{1,5} <!--body-->",
+    ]
+}
+
+fn adversarial_body() -> impl Strategy<Value = Option<String>> {
+    proptest::option::of(prop::collection::vec(adversarial_line(), 1..5).prop_map(|l| l.join("\n")))
+}
+
+/// タイトル・本文・placeholder。
+type Leaf = (String, Option<String>, Option<String>);
+/// Section のタイトル・本文と、その下の Content（それぞれ子の Content つき）。
+type SectionSpec = (String, Option<String>, Vec<(Leaf, Vec<Leaf>)>);
+/// 比較用の1ノード: (深さ, 種類, タイトル, 本文, placeholder)。
+type NodeShape = (u8, NodeType, String, Option<String>, Option<String>);
+
+/// Section（本文つき）→ Content（本文・placeholder つき）→ 子の Content。
+fn outline() -> impl Strategy<Value = Vec<SectionSpec>> {
+    let leaf = || {
+        (
+            "[A-Z][a-z]{0,8}",
+            adversarial_body(),
+            proptest::option::of("[a-z]{1,8}"),
+        )
+    };
+    prop::collection::vec(
+        (
+            "[A-Z][a-z]{0,8}",
+            adversarial_body(),
+            prop::collection::vec((leaf(), prop::collection::vec(leaf(), 0..3)), 0..3),
+        ),
+        1..4,
+    )
+}
+
+fn add(
+    book: &mut TemplateBook,
+    parent: Option<outline_mcp_core::domain::model::id::NodeId>,
+    node_type: NodeType,
+    leaf: &Leaf,
+) -> outline_mcp_core::domain::model::id::NodeId {
+    book.add_node(AddNodeRequest {
+        parent,
+        title: leaf.0.clone(),
+        node_type,
+        body: leaf.1.clone(),
+        placeholder: leaf.2.clone(),
+        position: Position::Last,
+        properties: std::collections::HashMap::new(),
+    })
+    .unwrap()
+}
+
+/// 比較用の形（DFS 順）。
+fn shape(book: &TemplateBook) -> Vec<NodeShape> {
+    book.all_nodes_dfs()
+        .iter()
+        .map(|n| {
+            (
+                book.depth_of(n.id()),
+                n.node_type().clone(),
+                n.title().to_string(),
+                n.body().map(str::to_string),
+                n.placeholder().map(str::to_string),
+            )
+        })
+        .collect()
+}
+
+proptest! {
+    /// 構造の構文を含む本文でも Markdown の書き出し → 読み込みで木が変わらない。
+    #[test]
+    fn markdown_roundtrip_preserves_structure(sections in outline()) {
+        let mut book = TemplateBook::new("Roundtrip", 4);
+        for (title, body, items) in &sections {
+            let section = add(&mut book, None, NodeType::Section, &(title.clone(), body.clone(), None));
+            for (item, children) in items {
+                let id = add(&mut book, Some(section), NodeType::Content, item);
+                for child in children {
+                    add(&mut book, Some(id), NodeType::Content, child);
+                }
+            }
+        }
+
+        let options = MarkdownOptions {
+            include_placeholders: true,
+            mark_body_lines: true,
+            ..MarkdownOptions::default()
+        };
+        let md = EjectService::render_markdown_with(&book, &options, None);
+        let tree = EjectTree::from_markdown(&md).unwrap();
+        let imported = EjectService::import_tree(&tree).unwrap();
+        prop_assert_eq!(imported.title(), book.title());
+        prop_assert_eq!(shape(&imported), shape(&book), "markdown:\n{}", md);
+    }
+}
@@ -5,7 +5,13 @@ mod common;
 use common::TestBook;
 use insta::{assert_json_snapshot, assert_snapshot};
 
-use outline_mcp_core::application::eject::{EjectService, EjectTree};
+use std::collections::HashMap;
+
+use outline_mcp_core::application::eject::{EjectService, EjectTree, MarkdownOptions};
+use outline_mcp_core::domain::model::book::{
+    AddNodeRequest, Position, TemplateBook, UpdateNodeRequest,
+};
+use outline_mcp_core::domain::model::node::NodeType;
 
 // =============================================================================
 // Markdown snapshots
@@ -32,6 +38,142 @@ fn snapshot_markdown_subtree() {
     assert_snapshot!("markdown_subtree_design", md);
 }
 
+#[test]
+fn snapshot_markdown_nested_body_list() {
+    // Section > Section > Content > Content（深さ4）の本文に2階層のリスト
+    let mut book = TemplateBook::new("Nested", 4);
+    let mut add = |parent, title: &str, node_type, body: Option<&str>| {
+        book.add_node(AddNodeRequest {
+            parent,
+            title: title.into(),
+            node_type,
+            body: body.map(str::to_string),
+            placeholder: None,
+            position: Position::Last,
+            properties: HashMap::new(),
+        })
+        .unwrap()
+    };
+    let ops = add(None, "Operations", NodeType::Section, None);
+    let deploy = add(Some(ops), "Deploy", NodeType::Section, None);
+    let release = add(Some(deploy), "Release", NodeType::Content, None);
+    add(
+        Some(release),
+        "Verify",
+        NodeType::Content,
+        Some("- Check health\n  - API responds\n  - Workers idle\n- Announce\n\tin #ops"),
+    );
+    let md = EjectService::render_markdown(&book, false, None);
+    assert_snapshot!("markdown_nested_body_list", md);
+}
+
+#[test]
+fn snapshot_markdown_unordered_section() {
+    let mut book = TemplateBook::new("Handbook", 4);
+    let mut add = |parent, title: &str, node_type| {
+        book.add_node(AddNodeRequest {
+            parent,
+            title: title.into(),
+            node_type,
+            body: None,
+            placeholder: None,
+            position: Position::Last,
+            properties: HashMap::new(),
+        })
+        .unwrap()
+    };
+    let steps = add(None, "Onboarding", NodeType::Section);
+    add(Some(steps), "Create accounts", NodeType::Content);
+    add(Some(steps), "Pair on first ticket", NodeType::Content);
+    let links = add(None, "Reference links", NodeType::Section);
+    add(Some(links), "Style guide", NodeType::Content);
+    add(Some(links), "Architecture notes", NodeType::Content);
+    book.update_node(
+        links,
+        UpdateNodeRequest {
+            ordered: Some(false),
+            ..Default::default()
+        },
+    )
+    .unwrap();
+    let md = EjectService::render_markdown(&book, false, None);
+    assert_snapshot!("markdown_unordered_section", md);
+}
+
+// =============================================================================
+// HTML snapshots
+// =============================================================================
+
+#[test]
+fn snapshot_html_full() {
+    let tb = TestBook::standard();
+    let options = MarkdownOptions {
+        include_placeholders: true,
+        ..MarkdownOptions::default()
+    };
+    let html = EjectService::render_html(&tb.book, &options, None);
+    assert_well_formed_html(&html);
+    assert_snapshot!("html_full", html);
+}
+
+/// 要素の入れ子が正しく閉じているかを確かめる最小限の HTML パーサ。
+/// 空要素（`<input>` など）は閉じタグを持たず、`<script>` / `<style>` の中身は読み飛ばす。
+fn assert_well_formed_html(html: &str) {
+    const VOID: [&str; 5] = ["meta", "input", "br", "hr", "link"];
+    let mut stack: Vec<String> = Vec::new();
+    let mut rest = html;
+    while let Some(start) = rest.find('<') {
+        rest = &rest[start + 1..];
+        if let Some(doctype) = rest.strip_prefix("!DOCTYPE html>") {
+            rest = doctype;
+            continue;
+        }
+        // 引用符の中の `>` で止まらないよう属性を読み進める
+        let mut quote = None;
+        let end = rest
+            .char_indices()
+            .find(|&(_, c)| match (quote, c) {
+                (None, '"' | '\'') => {
+                    quote = Some(c);
+                    false
+                }
+                (Some(q), c) if c == q => {
+                    quote = None;
+                    false
+                }
+                (None, '>') => true,
+                _ => false,
+            })
+            .map(|(i, _)| i)
+            .unwrap_or_else(|| panic!("unterminated tag near: {rest:.40}"));
+        let tag = &rest[..end];
+        rest = &rest[end + 1..];
+        if let Some(name) = tag.strip_prefix('/') {
+            let open = stack.pop().unwrap_or_else(|| panic!("stray </{name}>"));
+            assert_eq!(open, name, "</{name}> closes <{open}>");
+            continue;
+        }
+        let name: String = tag
+            .chars()
+            .take_while(|c| c.is_ascii_alphanumeric())
+            .collect();
+        assert!(!name.is_empty(), "malformed tag <{tag}>");
+        if VOID.contains(&name.as_str()) {
+            continue;
+        }
+        if name == "script" || name == "style" {
+            let close = format!("</{name}>");
+            let body_end = rest
+                .find(&close)
+                .unwrap_or_else(|| panic!("unclosed <{name}>"));
+            rest = &rest[body_end + close.len()..];
+            continue;
+        }
+        stack.push(name);
+    }
+    assert!(stack.is_empty(), "unclosed elements: {stack:?}");
+}
+
 // =============================================================================
 // JSON snapshots
 // =============================================================================
@@ -61,6 +203,12 @@ fn snapshot_json_subtree() {
 
 /// テスト毎にUUIDが変わるため、連番に置換してスナップショット比較を安定させる。
 fn stabilize_tree(mut tree: EjectTree) -> EjectTree {
+    if tree.book_id.is_some() {
+        tree.book_id = Some("stable-book-id".into());
+    }
+    if tree.subtree_uuid.is_some() {
+        tree.subtree_uuid = Some("stable-subtree-id".into());
+    }
     let mut counter = 0;
     for node in &mut tree.nodes {
         stabilize_node(node, &mut counter);
@@ -0,0 +1,280 @@
+//! Server-wide settings, loaded from an optional JSON file in the shelf
+//! directory (see [`ServerConfig::FILE_NAME`]). Every field has a default,
+//! so a missing file (or a file that only sets some keys) is fine.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use outline_mcp_core::domain::model::book::{EjectDefaults, TemplateBook};
+use serde::{Deserialize, Serialize};
+
+/// Server-wide settings for [`crate::OutlineMcpServer`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ServerConfig {
+    /// Number of UUID characters shown wherever a node's short ID is
+    /// displayed (default 8). Widen it for very large books to reduce
+    /// prefix ambiguity.
+    pub id_display_len: usize,
+    /// Defaults for the `init` tool; each can be overridden per request.
+    pub init: InitDefaults,
+    /// Replaces the built-in MCP `instructions` (e.g. team conventions).
+    /// Blank values or values over [`Self::MAX_INSTRUCTIONS_LEN`] characters
+    /// are ignored.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub instructions: Option<String>,
+    /// Text appended to individual tool descriptions, keyed by tool name.
+    /// Blank values or values over [`Self::MAX_TOOL_SUFFIX_LEN`] characters
+    /// are ignored.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub tool_description_suffixes: BTreeMap<String, String>,
+    /// Which tools are registered (default [`ToolProfile::Full`]).
+    pub tool_profile: ToolProfile,
+    /// Export defaults for `checklist`, used when neither the request nor the
+    /// book's own `eject_defaults` set a parameter.
+    #[serde(skip_serializing_if = "EjectDefaults::is_empty")]
+    pub eject: EjectDefaults,
+    /// Extra directory `checklist` `output_path` may write into, besides the
+    /// shelf directory.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub export_root: Option<PathBuf>,
+    /// Serve the shelf read-only (also set by the `--read-only` startup
+    /// flag): tools that would modify it are refused, and `checklist` files
+    /// go to [`Self::read_only_export_dir`].
+    pub read_only: bool,
+    /// Compatibility switch: treat `null` in a clearable `node_update` field
+    /// (`"body": null`, `"owner": null`, …) as "clear" (the old behaviour)
+    /// instead of "keep". Off by default because some clients send `null` for
+    /// every field they omit; the `clear_*` flags (`clear_body`,
+    /// `clear_owner`, …) are the supported way to clear.
+    pub null_clears_fields: bool,
+    /// Most nodes a single `node_create` (with `children`), `import` or
+    /// `node_move` removal may create plus remove before it asks for
+    /// `confirm: true` (default 100; `null` turns the guard off).
+    pub mutation_budget: Option<usize>,
+    /// Tidy titles given to `node_create`, `node_update` and batch edits:
+    /// trim surrounding whitespace and drop one leading list or number
+    /// marker (`- `, `* `, `1. `) pasted from other documents (default
+    /// true). When off, titles are stored as sent.
+    pub normalize_titles: bool,
+}
+
+/// Tool set exposed to the client.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ToolProfile {
+    /// Every tool.
+    #[default]
+    Full,
+    /// Only [`ToolProfile::COMPACT_TOOLS`], with shortened descriptions, for
+    /// clients that cannot handle a large tool list.
+    Compact,
+}
+
+impl ToolProfile {
+    /// Tools registered under [`ToolProfile::Compact`].
+    pub const COMPACT_TOOLS: [&'static str; 7] = [
+        "shelf",
+        "select_book",
+        "toc",
+        "node_create",
+        "node_update",
+        "node_move",
+        "checklist",
+    ];
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            id_display_len: 8,
+            init: InitDefaults::default(),
+            instructions: None,
+            tool_description_suffixes: BTreeMap::new(),
+            tool_profile: ToolProfile::Full,
+            eject: EjectDefaults::default(),
+            export_root: None,
+            read_only: false,
+            null_clears_fields: false,
+            mutation_budget: Some(Self::DEFAULT_MUTATION_BUDGET),
+            normalize_titles: true,
+        }
+    }
+}
+
+/// Defaults applied by `init` when the request leaves a parameter out.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct InitDefaults {
+    /// Max depth of new books (default 4).
+    pub max_depth: u8,
+    /// Titles of top-level sections every new book starts with (default none).
+    pub skeleton: Vec<String>,
+    /// Whether a newly created book becomes the selected book (default true).
+    pub auto_select: bool,
+    /// Node-count limit of new books (default none, i.e. unlimited).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_nodes: Option<u32>,
+}
+
+impl Default for InitDefaults {
+    fn default() -> Self {
+        Self {
+            max_depth: 4,
+            skeleton: Vec::new(),
+            auto_select: true,
+            max_nodes: None,
+        }
+    }
+}
+
+impl ServerConfig {
+    /// Config file name inside the shelf directory. The `.config` infix keeps
+    /// it out of `shelf` listings (book slugs never contain a dot).
+    pub const FILE_NAME: &'static str = "outline-mcp.config.json";
+
+    /// Longest accepted `instructions` override, in characters.
+    pub const MAX_INSTRUCTIONS_LEN: usize = 4000;
+
+    /// Longest accepted tool description suffix, in characters.
+    pub const MAX_TOOL_SUFFIX_LEN: usize = 500;
+
+    /// Default [`Self::mutation_budget`].
+    pub const DEFAULT_MUTATION_BUDGET: usize = 100;
+
+    /// Tools that still run in read-only mode although they are not annotated
+    /// read-only (`select_book` only changes session state, `checklist` writes
+    /// to the export directory).
+    pub const READ_ONLY_EXTRA_TOOLS: [&'static str; 2] = ["select_book", "checklist"];
+
+    /// Tools annotated read-only that are still refused in read-only mode,
+    /// because they write files to a directory of the caller's choosing.
+    pub const READ_ONLY_DENIED_TOOLS: [&'static str; 2] = ["snapshot_dump", "snapshot_dump_all"];
+
+    /// Where `checklist` writes files in read-only mode: `export_root`, or a
+    /// directory under the system temp dir.
+    pub fn read_only_export_dir(&self) -> PathBuf {
+        self.export_root
+            .clone()
+            .unwrap_or_else(|| std::env::temp_dir().join("outline-mcp-exports"))
+    }
+
+    /// The `instructions` override, if set and within limits.
+    pub fn instructions_override(&self) -> Option<&str> {
+        usable_override(self.instructions.as_deref(), Self::MAX_INSTRUCTIONS_LEN)
+    }
+
+    /// The description suffix for `tool`, if set and within limits.
+    pub fn tool_description_suffix(&self, tool: &str) -> Option<&str> {
+        usable_override(
+            self.tool_description_suffixes.get(tool).map(String::as_str),
+            Self::MAX_TOOL_SUFFIX_LEN,
+        )
+    }
+
+    /// Path of the config file for `shelf_dir`.
+    pub fn default_path(shelf_dir: &Path) -> PathBuf {
+        shelf_dir.join(Self::FILE_NAME)
+    }
+
+    /// Load the config at `path`. A missing file yields the defaults; a file
+    /// that exists but cannot be parsed is an error.
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        match std::fs::read_to_string(path) {
+            Ok(content) => {
+                let config: Self = serde_json::from_str(&content)
+                    .map_err(|e| anyhow::anyhow!("invalid config {}: {e}", path.display()))?;
+                TemplateBook::validate_max_depth(config.init.max_depth)
+                    .map_err(|e| anyhow::anyhow!("invalid config {}: init.{e}", path.display()))?;
+                Ok(config)
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(anyhow::anyhow!(
+                "failed to read config {}: {e}",
+                path.display()
+            )),
+        }
+    }
+}
+
+/// Trimmed `value`, or `None` when it is blank or longer than `max` chars.
+fn usable_override(value: Option<&str>, max: usize) -> Option<&str> {
+    let value = value?.trim();
+    (!value.is_empty() && value.chars().count() <= max).then_some(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn missing_file_yields_defaults() {
+        let path = std::env::temp_dir().join("outline-mcp-config-missing/none.json");
+        assert_eq!(ServerConfig::load(&path).unwrap(), ServerConfig::default());
+    }
+
+    #[test]
+    fn partial_file_keeps_other_defaults() {
+        let config: ServerConfig = serde_json::from_str(r#"{"id_display_len": 12}"#).unwrap();
+        assert_eq!(config.id_display_len, 12);
+        let config: ServerConfig = serde_json::from_str(r#"{"init": {"max_depth": 5}}"#).unwrap();
+        assert_eq!(config.init.max_depth, 5);
+        assert!(config.init.auto_select);
+        assert!(config.init.skeleton.is_empty());
+        assert_eq!(config.init.max_nodes, None);
+        let config: ServerConfig = serde_json::from_str(r#"{"tool_profile": "compact"}"#).unwrap();
+        assert_eq!(config.tool_profile, ToolProfile::Compact);
+        let config: ServerConfig = serde_json::from_str(r#"{"read_only": true}"#).unwrap();
+        assert!(config.read_only);
+        let empty: ServerConfig = serde_json::from_str("{}").unwrap();
+        assert_eq!(empty, ServerConfig::default());
+    }
+
+    #[test]
+    fn invalid_file_is_an_error() {
+        let dir = std::env::temp_dir().join("outline-mcp-config-invalid");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = ServerConfig::default_path(&dir);
+        std::fs::write(&path, "{not json").unwrap();
+        assert!(ServerConfig::load(&path).is_err());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn out_of_range_init_max_depth_is_an_error() {
+        let dir = std::env::temp_dir().join("outline-mcp-config-max-depth");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = ServerConfig::default_path(&dir);
+        std::fs::write(&path, r#"{"init": {"max_depth": 0}}"#).unwrap();
+        let err = ServerConfig::load(&path).unwrap_err().to_string();
+        assert!(err.contains("max_depth must be between 1 and 16"), "{err}");
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn overrides_fall_back_when_blank_or_too_long() {
+        let mut config = ServerConfig {
+            instructions: Some("  Check the incident template first.  ".into()),
+            ..ServerConfig::default()
+        };
+        config
+            .tool_description_suffixes
+            .insert("toc".into(), "   ".into());
+        config.tool_description_suffixes.insert(
+            "init".into(),
+            "x".repeat(ServerConfig::MAX_TOOL_SUFFIX_LEN + 1),
+        );
+        assert_eq!(
+            config.instructions_override(),
+            Some("Check the incident template first.")
+        );
+        assert_eq!(config.tool_description_suffix("toc"), None);
+        assert_eq!(config.tool_description_suffix("init"), None);
+        assert_eq!(config.tool_description_suffix("shelf"), None);
+
+        config.instructions = Some("x".repeat(ServerConfig::MAX_INSTRUCTIONS_LEN + 1));
+        assert_eq!(config.instructions_override(), None);
+    }
+}
@@ -1,6 +1,16 @@
-use outline_mcp_core::domain::model::book::TemplateBook;
+use std::collections::{HashMap, HashSet};
+
+use outline_mcp_core::application::eject::EjectService;
+use outline_mcp_core::application::prompts::EjectPrompts;
+use outline_mcp_core::application::search::TextMatch;
+use outline_mcp_core::application::tree_diff::{ChangeKind, NodeChange};
+use outline_mcp_core::domain::error::DomainError;
+use outline_mcp_core::domain::model::book::{
+    EjectDefaults, Placement, Position, TemplateBook, UpdateNodeRequest,
+};
+use outline_mcp_core::domain::model::changelog::NodeStatus;
 use outline_mcp_core::domain::model::id::NodeId;
-use outline_mcp_core::domain::model::node::TemplateNode;
+use outline_mcp_core::domain::model::node::{NodeType, TemplateNode};
 
 /// Boolean property をタグ表示用に整形する。
 pub(crate) fn format_property_tags(node: &TemplateNode) -> String {
@@ -20,35 +30,268 @@ pub(crate) fn format_property_tags(node: &TemplateNode) -> String {
     format!(" [{}]", tags.join(", "))
 }
 
+/// TOC の番号表記。内部の階層番号は常に `-` 区切りで、表示時のみ変換する。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub(crate) enum Numbering {
+    /// `2-3`（既定）
+    #[default]
+    Dash,
+    /// `2.3`
+    Dot,
+    /// `2.3.`（末尾ピリオド付き）
+    Legal,
+}
+
+impl Numbering {
+    /// 内部表記（`2-3`）を表示用に変換する。
+    pub(crate) fn render(self, internal: &str) -> String {
+        match self {
+            Numbering::Dash => internal.to_string(),
+            Numbering::Dot => internal.replace('-', "."),
+            Numbering::Legal => format!("{}.", internal.replace('-', ".")),
+        }
+    }
+}
+
+/// TOC の表示オプション。
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct TocOptions {
+    pub(crate) numbering: Numbering,
+    /// 担当者を `@name` で末尾に付ける
+    pub(crate) show_owners: bool,
+}
+
+impl From<Numbering> for TocOptions {
+    fn from(numbering: Numbering) -> Self {
+        Self {
+            numbering,
+            ..Self::default()
+        }
+    }
+}
+
 /// Book の全ノードを TOC 形式にフォーマットする。
-pub(crate) fn format_toc(book: &TemplateBook, nodes: &[&TemplateNode]) -> String {
+pub(crate) fn format_toc(
+    book: &TemplateBook,
+    nodes: &[&TemplateNode],
+    options: impl Into<TocOptions>,
+) -> String {
+    let TocOptions {
+        numbering,
+        show_owners,
+    } = options.into();
     let id_map = build_hierarchical_ids(book);
     let mut output = format!("# {} ({} nodes)\n\n", book.title(), book.node_count());
     for node in nodes {
         let depth = book.depth_of(node.id());
         let indent = "  ".repeat(depth.saturating_sub(1) as usize);
-        let hier_id = id_map
-            .iter()
-            .find(|(_, id)| *id == node.id())
-            .map(|(num, _)| num.as_str())
-            .unwrap_or("?");
+        // 順不同の Section の子は番号の代わりに `•`（ID としては階層番号のまま解決できる）
+        let unordered = node
+            .parent()
+            .and_then(|p| book.get_node(p))
+            .is_some_and(|p| !p.is_ordered());
+        let hier_id = if unordered {
+            "•".to_string()
+        } else {
+            id_map
+                .iter()
+                .find(|(_, id)| *id == node.id())
+                .map(|(num, _)| numbering.render(num))
+                .unwrap_or_else(|| "?".to_string())
+        };
         let tags = format_property_tags(node);
+        // dash は従来通り `2-3. Title`。dot は `2.3 Title`、legal は番号自体が `.` で終わる
+        let sep = if numbering == Numbering::Dash && !unordered {
+            "."
+        } else {
+            ""
+        };
+        let estimate = node
+            .estimate_minutes()
+            .map(|m| format!(" (~{m}m)"))
+            .unwrap_or_default();
+        let owner = match node.owner() {
+            Some(o) if show_owners => format!(" @{o}"),
+            _ => String::new(),
+        };
+        let done = if node.is_done() { " ✓" } else { "" };
+        let shield = if node.is_protected() { " 🛡" } else { "" };
         output.push_str(&format!(
-            "{}{}. {}{}\n",
+            "{}{}{} {}{}{}{}{}{}\n",
             indent,
             hier_id,
+            sep,
             node.title(),
+            done,
+            shield,
+            estimate,
+            owner,
             tags
         ));
     }
     output
 }
 
-/// 階層番号かどうか判定（`1`, `2-3`, `1-2-1` 等）
-pub(crate) fn is_hierarchical_id(s: &str) -> bool {
-    !s.is_empty()
-        && s.split('-')
-            .all(|part| !part.is_empty() && part.chars().all(|c| c.is_ascii_digit()))
+/// `placeholders` の出力形式。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub(crate) enum PlaceholderFormat {
+    /// Section ごとの箇条書き（既定）
+    #[default]
+    List,
+    /// ドキュメントに貼り付けられる Markdown 表
+    MarkdownTable,
+}
+
+/// `placeholders` の1項目: (階層番号, タイトル, ヒント)。
+type PlaceholderEntry<'a> = (String, &'a str, &'a str);
+
+/// `placeholders` 用に、記入が必要なノード（Export 時の placeholder を持つもの）を
+/// 直近の Section ごとにまとめて整形する。`root` 指定時はその部分木のみ。
+pub(crate) fn format_placeholders(
+    book: &TemplateBook,
+    root: Option<NodeId>,
+    format: PlaceholderFormat,
+) -> String {
+    let nodes = match root {
+        Some(id) => book.subtree_nodes(id),
+        None => book.all_nodes_dfs(),
+    };
+    let title = match root.and_then(|id| book.get_node(id)) {
+        Some(n) => n.title(),
+        None => book.title(),
+    };
+    let hier: HashMap<NodeId, String> = build_hierarchical_ids(book)
+        .into_iter()
+        .map(|(num, id)| (id, num))
+        .collect();
+    let label = |id: NodeId| hier.get(&id).cloned().unwrap_or_else(|| "?".to_string());
+
+    // (グループ見出し, [(番号, タイトル, ヒント)]) を DFS の初出順で並べる
+    let mut groups: Vec<(String, Vec<PlaceholderEntry>)> = Vec::new();
+    for node in nodes {
+        let Some(hint) = EjectService::effective_placeholder(book, node) else {
+            continue;
+        };
+        let section = book
+            .ancestors(node.id())
+            .into_iter()
+            .rev()
+            .find(|a| *a.node_type() == NodeType::Section);
+        let heading = match section {
+            Some(sec) => format!("{}. {}", label(sec.id()), sec.title()),
+            None => "(top level)".to_string(),
+        };
+        let entry = (label(node.id()), node.title(), hint);
+        match groups.iter_mut().find(|(h, _)| *h == heading) {
+            Some((_, entries)) => entries.push(entry),
+            None => groups.push((heading, vec![entry])),
+        }
+    }
+
+    let total: usize = groups.iter().map(|(_, e)| e.len()).sum();
+    let mut output = format!("# Placeholders: {title} ({total} to fill in)\n\n");
+    if total == 0 {
+        output.push_str("No placeholders: nothing needs filling in.\n");
+        return output;
+    }
+    match format {
+        PlaceholderFormat::List => {
+            for (i, (heading, entries)) in groups.iter().enumerate() {
+                if i > 0 {
+                    output.push('\n');
+                }
+                output.push_str(&format!("## {heading}\n\n"));
+                for (num, title, hint) in entries {
+                    output.push_str(&format!("- {num}. {title}: {}\n", one_line(hint)));
+                }
+            }
+        }
+        PlaceholderFormat::MarkdownTable => {
+            output.push_str("| ID | Section | Item | Placeholder |\n|---|---|---|---|\n");
+            for (heading, entries) in &groups {
+                for (num, title, hint) in entries {
+                    output.push_str(&format!(
+                        "| {num} | {} | {} | {} |\n",
+                        table_cell(heading),
+                        table_cell(title),
+                        table_cell(hint)
+                    ));
+                }
+            }
+        }
+    }
+    output
+}
+
+/// `eject_prompts` の出力: 利用者に一度に尋ねる変数と記入欄の一覧。
+pub(crate) fn format_prompts(book: &TemplateBook, title: &str, prompts: &EjectPrompts) -> String {
+    let hier: HashMap<NodeId, String> = build_hierarchical_ids(book)
+        .into_iter()
+        .map(|(num, id)| (id, num))
+        .collect();
+    let label = |id: NodeId| {
+        let num = hier.get(&id).map_or("?", String::as_str);
+        let title = book.get_node(id).map_or("?", |n| n.title());
+        format!("{num}. {}", one_line(title))
+    };
+
+    let mut output = format!(
+        "# Inputs: {title} ({} variables, {} placeholders)\n\n",
+        prompts.variables.len(),
+        prompts.placeholders.len()
+    );
+    if prompts.is_empty() {
+        output.push_str("Nothing to ask: no `{{variables}}` or placeholders.\n");
+        return output;
+    }
+    if !prompts.variables.is_empty() {
+        output.push_str("## Variables\n\n");
+        for var in &prompts.variables {
+            let users: Vec<String> = var.nodes.iter().map(|&id| label(id)).collect();
+            output.push_str(&format!(
+                "- {{{{{}}}}} — used by {}\n",
+                var.name,
+                users.join(", ")
+            ));
+        }
+    }
+    if !prompts.placeholders.is_empty() {
+        if !prompts.variables.is_empty() {
+            output.push('\n');
+        }
+        output.push_str("## Placeholders\n\n");
+        for ph in &prompts.placeholders {
+            output.push_str(&format!(
+                "- {}: {}\n",
+                label(ph.node_id),
+                one_line(&ph.hint)
+            ));
+        }
+    }
+    output
+}
+
+/// 改行を空白にまとめて1行にする。
+fn one_line(s: &str) -> String {
+    s.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Markdown 表のセル用に `|` をエスケープし1行にする。
+fn table_cell(s: &str) -> String {
+    one_line(s).replace('|', "\\|")
+}
+
+/// 階層番号（`1`, `2-3`, `1-2-1` 等）を内部の `-` 区切りに正規化する。
+/// toc が表示しうる `2.3` / `2.3.` 表記も受け付ける。階層番号でなければ None。
+pub(crate) fn normalize_hierarchical_id(s: &str) -> Option<String> {
+    let body = s.strip_suffix('.').unwrap_or(s);
+    let sep = if body.contains('.') { '.' } else { '-' };
+    let parts: Vec<&str> = body.split(sep).collect();
+    let valid = !body.is_empty()
+        && parts
+            .iter()
+            .all(|part| !part.is_empty() && part.chars().all(|c| c.is_ascii_digit()));
+    valid.then(|| parts.join("-"))
 }
 
 /// Book全体の (階層番号, NodeId) マッピングをDFS順で構築する。
@@ -84,3 +327,1065 @@ pub(crate) fn find_hierarchical_id(book: &TemplateBook, target: NodeId) -> Optio
         .find(|(_, id)| *id == target)
         .map(|(num, _)| num)
 }
+
+/// `book_config` の表示用に Book レベル設定を整形する。
+pub(crate) fn format_book_config(book: &TemplateBook) -> String {
+    let mut output = format!("# Book config: {}\n\n", book.title());
+    output.push_str(&format!("- max_depth: {}\n", book.max_depth()));
+    output.push_str(&format!(
+        "- default_placeholder: {}\n",
+        book.default_placeholder().unwrap_or("(none)")
+    ));
+    match book.max_nodes() {
+        Some(max) => output.push_str(&format!(
+            "- max_nodes: {max} ({} in use)\n",
+            book.node_count()
+        )),
+        None => output.push_str("- max_nodes: (unlimited)\n"),
+    }
+    if book.tags().is_empty() {
+        output.push_str("- tags: (none)\n");
+    } else {
+        output.push_str(&format!("- tags: {}\n", book.tags().join(", ")));
+    }
+    if book.categories().is_empty() {
+        output.push_str("- categories: (none)\n");
+    } else {
+        output.push_str(&format!("- categories: {}\n", book.categories().join(", ")));
+    }
+    output.push_str(&format!(
+        "- eject_defaults: {}\n",
+        format_eject_defaults(book.eject_defaults())
+    ));
+    output
+}
+
+/// Book の書き出し既定値を `output_dir=out, format=json` 形式で1行にする。
+pub(crate) fn format_eject_defaults(defaults: Option<&EjectDefaults>) -> String {
+    let Some(d) = defaults else {
+        return "(none)".to_string();
+    };
+    let mut parts = Vec::new();
+    if let Some(dir) = &d.output_dir {
+        parts.push(format!("output_dir={dir}"));
+    }
+    if let Some(format) = &d.format {
+        parts.push(format!("format={format}"));
+    }
+    if let Some(ph) = d.include_placeholders {
+        parts.push(format!("include_placeholders={ph}"));
+    }
+    parts.join(", ")
+}
+
+/// `book_info` 用に Book の ID と構造の概要を整形する。
+pub(crate) fn format_book_info(slug: &str, book: &TemplateBook) -> String {
+    let mut output = format!("# Book info: {}\n\n", book.title());
+    output.push_str(&format!("- id: {}\n", book.id()));
+    output.push_str(&format!("- slug: {slug}\n"));
+    output.push_str(&format!("- title: {}\n", book.title()));
+    output.push_str(&format!("- max_depth: {}\n", book.max_depth()));
+    output.push_str(&format!("- max_actual_depth: {}\n", book.actual_depth()));
+    output.push_str(&format!("- node_count: {}\n", book.node_count()));
+    output.push_str(&format!("- root_count: {}\n", book.root_nodes().len()));
+    output
+}
+
+/// `node_get` 用に単一ノードの詳細（notes を含む）を整形する。
+pub(crate) fn format_node_detail(
+    book: &TemplateBook,
+    node: &TemplateNode,
+    hier_id: &str,
+    short_id: &str,
+) -> String {
+    let mut output = format!("# {hier_id}. {}\n\n", node.title());
+    output.push_str(&format!("- ID: {short_id}\n"));
+    let type_str = match node.node_type() {
+        NodeType::Section => "section",
+        NodeType::Content => "content",
+    };
+    output.push_str(&format!("- Type: {type_str}\n"));
+    let status_str = match node.status() {
+        NodeStatus::Active => "active",
+        NodeStatus::Draft => "draft",
+    };
+    output.push_str(&format!("- Status: {status_str}\n"));
+    let path = format_parent_path(book, node.id());
+    if !path.is_empty() {
+        output.push_str(&format!("- Path: {path}\n"));
+    }
+    if let Some(ph) = node.placeholder() {
+        output.push_str(&format!("- Placeholder: {ph}\n"));
+    }
+    if let Some(m) = node.estimate_minutes() {
+        output.push_str(&format!("- Estimate: ~{}\n", format_minutes(m)));
+    }
+    if let Some(owner) = node.owner() {
+        output.push_str(&format!("- Owner: @{owner}\n"));
+    }
+    if node.is_done() {
+        output.push_str("- Done: yes\n");
+    }
+    if node.is_protected() {
+        output.push_str("- Protected: yes (removal requires override_protection)\n");
+    }
+    if let Some(order) = node.order() {
+        output.push_str(&format!("- Order key: {order}\n"));
+    }
+    if let Some(level) = node.heading_level() {
+        output.push_str(&format!("- Heading level: {level}\n"));
+    }
+    if !node.is_ordered() {
+        output.push_str("- Ordered: no (children are an unordered collection)\n");
+    }
+    let blocked_by: Vec<String> = book
+        .blocked_by(node.id())
+        .into_iter()
+        .filter_map(|id| book.outline_number(id))
+        .collect();
+    if !blocked_by.is_empty() {
+        output.push_str(&format!("- Blocked by: {}\n", blocked_by.join(", ")));
+    }
+    if !node.properties().is_empty() {
+        let mut props: Vec<String> = node
+            .properties()
+            .iter()
+            .map(|(k, v)| format!("{k}={v}"))
+            .collect();
+        props.sort_unstable();
+        output.push_str(&format!("- Properties: {}\n", props.join(", ")));
+    }
+    if let Some(body) = node.body() {
+        output.push_str(&format!("\n## Body\n\n{body}\n"));
+    }
+    if let Some(note) = node.note() {
+        output.push_str(&format!("\n## Note\n\n{note}\n"));
+    }
+    if let Some(command) = node.command() {
+        output.push_str(&format!("\n## Command\n\n```sh\n{command}\n```\n"));
+    }
+    if !node.links().is_empty() {
+        output.push_str("\n## Links\n\n");
+        for (label, url) in node.links() {
+            output.push_str(&format!("- [{label}]({url})\n"));
+        }
+    }
+    if let Some(notes) = node.notes() {
+        output.push_str(&format!("\n## Notes\n\n{notes}\n"));
+    }
+    output
+}
+
+/// 分を `45m` / `2h` / `2h30m` 形式にする。
+pub(crate) fn format_minutes(minutes: u32) -> String {
+    match (minutes / 60, minutes % 60) {
+        (0, m) => format!("{m}m"),
+        (h, 0) => format!("{h}h"),
+        (h, m) => format!("{h}h{m}m"),
+    }
+}
+
+/// `stats` 用にノード数と見積もり合計（全体 + Section ごと）を整形する。
+pub(crate) fn format_stats(book: &TemplateBook, root: Option<NodeId>) -> String {
+    let nodes = match root {
+        Some(id) => book.subtree_nodes(id),
+        None => book.all_nodes_dfs(),
+    };
+    let title = match root.and_then(|id| book.get_node(id)) {
+        Some(n) => n.title(),
+        None => book.title(),
+    };
+    let sections: Vec<&TemplateNode> = nodes
+        .iter()
+        .copied()
+        .filter(|n| *n.node_type() == NodeType::Section)
+        .collect();
+    let content_count = nodes.len() - sections.len();
+    let unestimated = nodes
+        .iter()
+        .filter(|n| *n.node_type() == NodeType::Content && n.estimate_minutes().is_none())
+        .count();
+    let total = book.estimate_total(root);
+
+    let mut output = format!("# Stats: {title}\n\n");
+    output.push_str(&format!(
+        "- Nodes: {} ({} sections, {} content)\n",
+        nodes.len(),
+        sections.len(),
+        content_count
+    ));
+    output.push_str(&format!(
+        "- Estimated effort: ~{} ({total}m)\n",
+        format_minutes(total)
+    ));
+    output.push_str(&format!("- Unestimated content items: {unestimated}\n"));
+    if root.is_none() {
+        if let Some(deepest) = describe_deepest(book) {
+            output.push_str(&format!("- Deepest: {deepest}\n"));
+        }
+    }
+
+    if !sections.is_empty() {
+        let base_depth = root.map(|id| book.depth_of(id)).unwrap_or(1);
+        output.push_str("\n## By section\n\n");
+        for sec in sections {
+            let indent = "  ".repeat(book.depth_of(sec.id()).saturating_sub(base_depth) as usize);
+            let hier = book
+                .outline_number(sec.id())
+                .unwrap_or_else(|| "?".to_string());
+            let sum = book.estimate_total(Some(sec.id()));
+            output.push_str(&format!(
+                "{indent}- {hier}. {}: ~{}\n",
+                sec.title(),
+                format_minutes(sum)
+            ));
+        }
+    }
+    output
+}
+
+/// 最深の段の使用状況（`level 4 of 4 — 3 paths, e.g. 2-3-1-4 'Verify canary'`）。
+/// 空の Book では `None`。
+pub(crate) fn describe_deepest(book: &TemplateBook) -> Option<String> {
+    let paths = book.deepest_paths();
+    let (path, level) = paths.first()?;
+    let leaf = *path.last()?;
+    let hier = book.outline_number(leaf).unwrap_or_else(|| "?".to_string());
+    let title = book.get_node(leaf).map_or("?", |n| n.title());
+    let count = match paths.len() {
+        1 => "1 path".to_string(),
+        n => format!("{n} paths"),
+    };
+    Some(format!(
+        "level {level} of {} — {count}, e.g. {hier} '{}'",
+        book.max_depth(),
+        one_line(title)
+    ))
+}
+
+/// `status` 用にこのセッションの状態を整形する。
+///
+/// `book` は選択中 Book の読み込み結果（読めなかった場合は `None`）。
+pub(crate) fn format_status(
+    shelf_dir: &std::path::Path,
+    book_count: usize,
+    selected: Option<&str>,
+    book: Option<&TemplateBook>,
+    export_dir: &std::path::Path,
+) -> String {
+    let mut output = "# Status\n\n".to_string();
+    output.push_str(&format!(
+        "- Shelf: {} ({book_count} books)\n",
+        shelf_dir.display()
+    ));
+    output.push_str(&format!(
+        "- Exports go to: {} (`checklist` without `output_dir`)\n",
+        absolute_path(export_dir).display()
+    ));
+    match (selected, book) {
+        (Some(slug), Some(book)) => {
+            output.push_str(&format!(
+                "- Selected book: {slug} — \"{}\" ({} nodes, max_depth {})\n",
+                book.title(),
+                book.node_count(),
+                book.max_depth()
+            ));
+            for issue in book.check_integrity() {
+                output.push_str(&format!("- [WARNING] {issue}\n"));
+            }
+            if book.eject_defaults().is_some() {
+                output.push_str(&format!(
+                    "- Export defaults: {}\n",
+                    format_eject_defaults(book.eject_defaults())
+                ));
+            }
+        }
+        (Some(slug), None) => {
+            output.push_str(&format!("- Selected book: {slug} (failed to load)\n"))
+        }
+        (None, _) => output.push_str("- Selected book: none (use `select_book` or `init`)\n"),
+    }
+    output.push_str(
+        "\nSelection is per session: it lives in this server process only and is never \
+         written to the shelf, so other sessions on the same shelf keep their own.\n",
+    );
+    output
+}
+
+/// 表示用の絶対パス。存在すればシンボリックリンクも解決し、なければカレントディレクトリ基準にする。
+pub(crate) fn absolute_path(path: &std::path::Path) -> std::path::PathBuf {
+    std::fs::canonicalize(path)
+        .or_else(|_| std::path::absolute(path))
+        .unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// `search_shelf` で一致があった1冊分。
+pub(crate) struct ShelfSearchHits {
+    pub(crate) slug: String,
+    pub(crate) book: TemplateBook,
+    pub(crate) matches: Vec<TextMatch>,
+}
+
+/// `search_shelf` 用に一致ノードを Book ごとにまとめて整形する。
+///
+/// `skipped` は読み込めなかった Book の `(slug, 理由)`。`limit` は件数上限で打ち切った場合のみ渡す。
+pub(crate) fn format_shelf_search(
+    query: &str,
+    hits: &[ShelfSearchHits],
+    skipped: &[(String, String)],
+    limit: Option<usize>,
+) -> String {
+    let total: usize = hits.iter().map(|h| h.matches.len()).sum();
+    let mut output = format!(
+        "# Shelf search: \"{query}\" ({total} matches in {} books)\n",
+        hits.len()
+    );
+    if hits.is_empty() {
+        output.push_str("\nNo matching nodes found.\n");
+    }
+    for hit in hits {
+        output.push_str(&format!("\n## {} — {}\n", hit.slug, hit.book.title()));
+        let ids: HashMap<NodeId, String> = build_hierarchical_ids(&hit.book)
+            .into_iter()
+            .map(|(num, id)| (id, num))
+            .collect();
+        for m in &hit.matches {
+            let num = ids.get(&m.node_id).map(String::as_str).unwrap_or("?");
+            let title = hit
+                .book
+                .get_node(m.node_id)
+                .map(|n| n.title())
+                .unwrap_or("?");
+            if m.field == "title" {
+                output.push_str(&format!("- {num}. {title}\n"));
+            } else {
+                output.push_str(&format!("- {num}. {title} [{}] {}\n", m.field, m.snippet));
+            }
+        }
+    }
+    if !skipped.is_empty() || limit.is_some() {
+        output.push('\n');
+    }
+    for (slug, reason) in skipped {
+        output.push_str(&format!(
+            "[NOTE] skipped '{slug}' (could not load: {reason})\n"
+        ));
+    }
+    if let Some(limit) = limit {
+        output.push_str(&format!(
+            "[NOTE] stopped at {limit} matches; narrow the query or raise `limit`\n"
+        ));
+    }
+    output
+}
+
+/// `changes` 用に差分をトップレベルノードごとにまとめて整形する。
+///
+/// `after` は現在の Book（追加・変更・移動ノードの階層番号に使う）。
+pub(crate) fn format_changes(
+    after: &TemplateBook,
+    changes: &[NodeChange],
+    reference: &str,
+) -> String {
+    format_change_report(after, changes, &format!("Changes since {reference}"))
+}
+
+/// [`format_changes`] の見出しを任意にしたもの。
+pub(crate) fn format_change_report(
+    after: &TemplateBook,
+    changes: &[NodeChange],
+    heading: &str,
+) -> String {
+    let mut output = format!("# {heading}\n\n");
+    if changes.is_empty() {
+        output.push_str("No changes.\n");
+        return output;
+    }
+    let count = |kind: ChangeKind| changes.iter().filter(|c| c.kind == kind).count();
+    output.push_str(&format!(
+        "{} changes: {} added, {} removed, {} modified, {} moved\n",
+        changes.len(),
+        count(ChangeKind::Added),
+        count(ChangeKind::Removed),
+        count(ChangeKind::Modified),
+        count(ChangeKind::Moved)
+    ));
+    let mut groups: Vec<&str> = Vec::new();
+    for change in changes {
+        if !groups.contains(&change.group.as_str()) {
+            groups.push(&change.group);
+        }
+    }
+    for group in groups {
+        output.push_str(&format!("\n## {group}\n\n"));
+        for change in changes.iter().filter(|c| c.group == group) {
+            let hier = find_hierarchical_id(after, change.node_id);
+            let line = match (change.kind, hier) {
+                (ChangeKind::Removed, _) | (_, None) => {
+                    format!("- removed: {}", change.title)
+                }
+                (ChangeKind::Added, Some(h)) => format!("- added {h}. {}", change.title),
+                (ChangeKind::Moved, Some(h)) => format!("- moved {h}. {}", change.title),
+                (ChangeKind::Modified, Some(h)) => format!(
+                    "- modified {h}. {} ({})",
+                    change.title,
+                    change.fields.join(", ")
+                ),
+            };
+            output.push_str(&line);
+            output.push('\n');
+        }
+    }
+    output
+}
+
+/// 祖先タイトルを `A > B` 形式で連結する（ルートノードは空文字列）。
+pub(crate) fn format_parent_path(book: &TemplateBook, id: NodeId) -> String {
+    book.ancestors(id)
+        .iter()
+        .map(|a| a.title())
+        .collect::<Vec<_>>()
+        .join(" > ")
+}
+
+/// 経過時間を `42s ago` / `5m ago` / `3h ago` / `2d ago` 形式にする。
+pub(crate) fn format_age(now_millis: i64, then_millis: i64) -> String {
+    let secs = (now_millis - then_millis).max(0) / 1000;
+    match secs {
+        s if s < 60 => format!("{s}s ago"),
+        s if s < 3600 => format!("{}m ago", s / 60),
+        s if s < 86_400 => format!("{}h ago", s / 3600),
+        s => format!("{}d ago", s / 86_400),
+    }
+}
+
+/// 範囲外の position が末尾へ丸められたときの注記（`verb` は "inserted" / "moved" など）。
+/// 丸めが起きていなければ `None`。
+pub(crate) fn clamp_note(verb: &str, placed: &Placement) -> Option<String> {
+    placed.was_clamped().then(|| {
+        format!(
+            "[NOTE] {verb} at position {} (requested {} was clamped)",
+            placed.index, placed.requested
+        )
+    })
+}
+
+/// 変更系ツールの応答本文。`quiet` なら `ack`（階層番号、削除なら "ok"）だけを返し、
+/// そうでなければ `verbose`。保存時の警告はどちらでも `[WARNING]` 行として付ける。
+pub(crate) fn mutation_reply(
+    quiet: bool,
+    ack: &str,
+    verbose: String,
+    warnings: impl IntoIterator<Item = Option<String>>,
+) -> String {
+    let mut msg = if quiet { ack.to_string() } else { verbose };
+    for w in warnings.into_iter().flatten() {
+        msg.push_str(&format!("\n[WARNING] {w}"));
+    }
+    msg
+}
+
+/// `reorder_book` の順序（全ノードの DFS 順）が今の親子関係のまま実現できるかを調べ、
+/// できなければ最初の問題を `label` で表示した文で返す。親は変えないので、各ノードは
+/// 今の親より後ろ、かつ親の部分木が続いている間に置かれていなければならない。
+pub(crate) fn reorder_violation(
+    book: &TemplateBook,
+    order: &[NodeId],
+    label: impl Fn(NodeId) -> String,
+) -> Option<String> {
+    let mut seen = HashSet::new();
+    if let Some(dup) = order.iter().find(|id| !seen.insert(**id)) {
+        return Some(format!("'{}' is listed twice", label(*dup)));
+    }
+    let missing: Vec<String> = book
+        .all_nodes_dfs()
+        .iter()
+        .map(|n| n.id())
+        .filter(|id| !seen.contains(id))
+        .map(&label)
+        .collect();
+    if !missing.is_empty() {
+        return Some(format!(
+            "The order must list every node; missing {}: {}",
+            missing.len(),
+            missing.join(", ")
+        ));
+    }
+    // 開いている祖先の列で、親が先に来て部分木が途切れないことを確かめる
+    let mut open: Vec<NodeId> = Vec::new();
+    for (i, &id) in order.iter().enumerate() {
+        match book.get_node(id).and_then(|n| n.parent()) {
+            Some(parent) if !open.contains(&parent) => {
+                let problem = if order[i..].contains(&parent) {
+                    "comes before its parent"
+                } else {
+                    "is separated from the rest of the subtree of its parent"
+                };
+                return Some(format!(
+                    "'{}' {problem} '{}' (reorder_book keeps every node under its current parent; use `node_move` to change parents)",
+                    label(id),
+                    label(parent)
+                ));
+            }
+            Some(parent) => {
+                while open.last() != Some(&parent) {
+                    open.pop();
+                }
+            }
+            None => open.clear(),
+        }
+        open.push(id);
+    }
+    None
+}
+
+/// [`apply_sibling_order`] の後で、実際の DFS 順が `order` と違えばその理由を返す。
+/// 並び順キー（`order`）を持つ兄弟はキー順が優先され、指定どおりに並ばない。
+pub(crate) fn sort_key_conflict(
+    book: &TemplateBook,
+    order: &[NodeId],
+    label: impl Fn(NodeId) -> String,
+) -> Option<String> {
+    let after = book.all_nodes_dfs();
+    let i = after
+        .iter()
+        .zip(order)
+        .position(|(node, id)| node.id() != *id)?;
+    let under = match book.get_node(order[i]).and_then(|n| n.parent()) {
+        Some(parent) => format!("'{}'", label(parent)),
+        None => "the top level".to_string(),
+    };
+    Some(format!(
+        "Siblings under {under} have `order` sort keys, which decide their order; \
+         clear them with `node_update` first"
+    ))
+}
+
+/// `order` に出てくる順に、各ノードを今の親の子の先頭から詰めて並べ直す。
+/// 位置が変わったノードだけを返す。
+pub(crate) fn apply_sibling_order(
+    book: &mut TemplateBook,
+    order: &[NodeId],
+) -> Result<Vec<NodeId>, DomainError> {
+    let mut next_slot: HashMap<Option<NodeId>, usize> = HashMap::new();
+    let mut moved = Vec::new();
+    for &id in order {
+        let parent = book.get_node(id).and_then(|n| n.parent());
+        let slot = next_slot.entry(parent).or_default();
+        if book.sibling_index(id) != Some(*slot) {
+            book.move_node(id, parent, Position::At(*slot))?;
+            moved.push(id);
+        }
+        *slot += 1;
+    }
+    Ok(moved)
+}
+
+/// `root` の部分木（`None` なら Book 全体）のうち `matches` に当てはまるノードへ
+/// `update` を適用する。対象は変更前の状態で選び、変更したノードを返す。
+pub(crate) fn update_matching_in(
+    book: &mut TemplateBook,
+    root: Option<NodeId>,
+    matches: impl Fn(&TemplateNode) -> bool,
+    update: impl Fn(&TemplateNode) -> UpdateNodeRequest,
+) -> Result<Vec<NodeId>, DomainError> {
+    let candidates = match root {
+        Some(id) => book.subtree_nodes(id),
+        None => book.all_nodes_dfs(),
+    };
+    let updates: Vec<_> = candidates
+        .into_iter()
+        .filter(|n| matches(n))
+        .map(|n| (n.id(), update(n)))
+        .collect();
+    let mut changed = Vec::with_capacity(updates.len());
+    for (id, req) in updates {
+        book.update_node(id, req)?;
+        changed.push(id);
+    }
+    Ok(changed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use outline_mcp_core::application::prompts::collect_prompts;
+    use outline_mcp_core::domain::model::book::{AddNodeRequest, Position, UpdateNodeRequest};
+    use std::collections::HashMap;
+
+    fn toc_book() -> TemplateBook {
+        let mut book = TemplateBook::new("SOP", 4);
+        let add = |book: &mut TemplateBook, parent, title: &str, node_type| {
+            book.add_node(AddNodeRequest {
+                parent,
+                title: title.into(),
+                node_type,
+                body: None,
+                placeholder: None,
+                position: Position::Last,
+                properties: HashMap::new(),
+            })
+            .unwrap()
+        };
+        let prep = add(&mut book, None, "Preparation", NodeType::Section);
+        add(&mut book, Some(prep), "Check access", NodeType::Content);
+        let run = add(&mut book, Some(prep), "Dry run", NodeType::Section);
+        add(&mut book, Some(run), "Review output", NodeType::Content);
+        add(&mut book, None, "Execute", NodeType::Content);
+        book
+    }
+
+    #[test]
+    fn toc_numbering_dash() {
+        let book = toc_book();
+        let nodes = book.all_nodes_dfs();
+        insta::assert_snapshot!(format_toc(&book, &nodes, Numbering::Dash), @r"
+        # SOP (5 nodes)
+
+        1. Preparation
+          1-1. Check access
+          1-2. Dry run
+            1-2-1. Review output
+        2. Execute
+        ");
+    }
+
+    #[test]
+    fn toc_numbering_dot() {
+        let book = toc_book();
+        let nodes = book.all_nodes_dfs();
+        insta::assert_snapshot!(format_toc(&book, &nodes, Numbering::Dot), @r"
+        # SOP (5 nodes)
+
+        1 Preparation
+          1.1 Check access
+          1.2 Dry run
+            1.2.1 Review output
+        2 Execute
+        ");
+    }
+
+    #[test]
+    fn toc_numbering_legal() {
+        let book = toc_book();
+        let nodes = book.all_nodes_dfs();
+        insta::assert_snapshot!(format_toc(&book, &nodes, Numbering::Legal), @r"
+        # SOP (5 nodes)
+
+        1. Preparation
+          1.1. Check access
+          1.2. Dry run
+            1.2.1. Review output
+        2. Execute
+        ");
+    }
+
+    #[test]
+    fn normalize_hierarchical_id_accepts_every_toc_form() {
+        for form in ["2-3", "2.3", "2.3."] {
+            assert_eq!(normalize_hierarchical_id(form).as_deref(), Some("2-3"));
+        }
+        assert_eq!(normalize_hierarchical_id("1").as_deref(), Some("1"));
+        assert_eq!(normalize_hierarchical_id("1.").as_deref(), Some("1"));
+        assert!(normalize_hierarchical_id("").is_none());
+        assert!(normalize_hierarchical_id(".").is_none());
+        assert!(normalize_hierarchical_id("2-3.1").is_none());
+        assert!(normalize_hierarchical_id("2..3").is_none());
+        assert!(normalize_hierarchical_id("v1.2").is_none());
+    }
+
+    #[test]
+    fn clamp_note_only_for_clamped_positions() {
+        let placed = |index, requested| Placement {
+            node_id: NodeId::new(),
+            index,
+            requested,
+        };
+        assert_eq!(
+            clamp_note("inserted", &placed(3, Position::At(7))).as_deref(),
+            Some("[NOTE] inserted at position 3 (requested 7 was clamped)")
+        );
+        assert_eq!(clamp_note("inserted", &placed(2, Position::At(2))), None);
+        assert_eq!(clamp_note("moved", &placed(3, Position::Last)), None);
+    }
+
+    #[test]
+    fn format_age_units() {
+        assert_eq!(format_age(10_000, 0), "10s ago");
+        assert_eq!(format_age(5 * 60_000, 0), "5m ago");
+        assert_eq!(format_age(3 * 3_600_000, 0), "3h ago");
+        assert_eq!(format_age(2 * 86_400_000, 0), "2d ago");
+        // 時計のずれで未来になっても負にしない
+        assert_eq!(format_age(0, 5_000), "0s ago");
+    }
+
+    #[test]
+    fn node_detail_shows_notes() {
+        let mut book = toc_book();
+        let (hier, id) = build_hierarchical_ids(&book)[3].clone();
+        book.update_node(
+            id,
+            UpdateNodeRequest {
+                body: Some(Some("Diff against staging".into())),
+                placeholder: Some(Some("findings".into())),
+                properties: Some(HashMap::from([("scope".into(), "ops".into())])),
+                notes: Some(Some("Added after INC-42".into())),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        let node = book.get_node(id).unwrap();
+        insta::assert_snapshot!(format_node_detail(&book, node, &hier, "abcd1234"), @r"
+        # 1-2-1. Review output
+
+        - ID: abcd1234
+        - Type: content
+        - Status: active
+        - Path: Preparation > Dry run
+        - Placeholder: findings
+        - Properties: scope=ops
+
+        ## Body
+
+        Diff against staging
+
+        ## Notes
+
+        Added after INC-42
+        ");
+    }
+
+    fn estimate(book: &mut TemplateBook, hier: &str, minutes: u32) {
+        let id = build_hierarchical_ids(book)
+            .into_iter()
+            .find(|(n, _)| n == hier)
+            .unwrap()
+            .1;
+        book.update_node(
+            id,
+            UpdateNodeRequest {
+                estimate_minutes: Some(Some(minutes)),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+    }
+
+    fn placeholder(book: &mut TemplateBook, hier: &str, hint: &str) {
+        let id = build_hierarchical_ids(book)
+            .into_iter()
+            .find(|(n, _)| n == hier)
+            .unwrap()
+            .1;
+        book.update_node(
+            id,
+            UpdateNodeRequest {
+                placeholder: Some(Some(hint.into())),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn placeholders_grouped_by_section() {
+        let mut book = toc_book();
+        placeholder(&mut book, "1-1", "who granted access");
+        placeholder(&mut book, "1-2-1", "diff summary");
+        placeholder(&mut book, "2", "change ticket");
+        insta::assert_snapshot!(format_placeholders(&book, None, PlaceholderFormat::List), @r"
+        # Placeholders: SOP (3 to fill in)
+
+        ## 1. Preparation
+
+        - 1-1. Check access: who granted access
+
+        ## 1-2. Dry run
+
+        - 1-2-1. Review output: diff summary
+
+        ## (top level)
+
+        - 2. Execute: change ticket
+        ");
+    }
+
+    #[test]
+    fn placeholders_table_escapes_cells_and_uses_book_default() {
+        let mut book = toc_book();
+        placeholder(&mut book, "1-1", "yes | no");
+        book.set_default_placeholder(Some("notes".into()));
+        insta::assert_snapshot!(
+            format_placeholders(&book, None, PlaceholderFormat::MarkdownTable),
+            @r"
+            # Placeholders: SOP (3 to fill in)
+
+            | ID | Section | Item | Placeholder |
+            |---|---|---|---|
+            | 1-1 | 1. Preparation | Check access | yes \| no |
+            | 1-2-1 | 1-2. Dry run | Review output | notes |
+            | 2 | (top level) | Execute | notes |
+            "
+        );
+    }
+
+    #[test]
+    fn placeholders_subtree_and_empty() {
+        let mut book = toc_book();
+        placeholder(&mut book, "1-2-1", "diff summary");
+        placeholder(&mut book, "2", "change ticket");
+        let dry_run = build_hierarchical_ids(&book)[2].1;
+        insta::assert_snapshot!(
+            format_placeholders(&book, Some(dry_run), PlaceholderFormat::List),
+            @r"
+            # Placeholders: Dry run (1 to fill in)
+
+            ## 1-2. Dry run
+
+            - 1-2-1. Review output: diff summary
+            "
+        );
+        insta::assert_snapshot!(
+            format_placeholders(&toc_book(), None, PlaceholderFormat::List),
+            @r"
+            # Placeholders: SOP (0 to fill in)
+
+            No placeholders: nothing needs filling in.
+            "
+        );
+    }
+
+    #[test]
+    fn prompts_list_each_variable_once_with_its_users() {
+        let mut book = toc_book();
+        placeholder(&mut book, "1-1", "{{ticket}} approver");
+        placeholder(&mut book, "2", "{{ticket}} link");
+        placeholder(&mut book, "1-2-1", "diff summary");
+        let prompts = collect_prompts(&book, None);
+        insta::assert_snapshot!(format_prompts(&book, "SOP", &prompts), @r"
+        # Inputs: SOP (1 variables, 3 placeholders)
+
+        ## Variables
+
+        - {{ticket}} — used by 1-1. Check access, 2. Execute
+
+        ## Placeholders
+
+        - 1-1. Check access: {{ticket}} approver
+        - 1-2-1. Review output: diff summary
+        - 2. Execute: {{ticket}} link
+        ");
+        let none = collect_prompts(&toc_book(), None);
+        assert!(format_prompts(&book, "SOP", &none)
+            .ends_with("Nothing to ask: no `{{variables}}` or placeholders.\n"));
+    }
+
+    #[test]
+    fn shelf_search_groups_by_book() {
+        let book = toc_book();
+        let ids = build_hierarchical_ids(&book);
+        let hits = vec![ShelfSearchHits {
+            slug: "sop".into(),
+            matches: vec![
+                TextMatch {
+                    node_id: ids[1].1,
+                    field: "title",
+                    snippet: "Check access".into(),
+                },
+                TextMatch {
+                    node_id: ids[4].1,
+                    field: "body",
+                    snippet: "…check the access log…".into(),
+                },
+            ],
+            book,
+        }];
+        let skipped = vec![("broken".to_string(), "invalid JSON".to_string())];
+        insta::assert_snapshot!(format_shelf_search("access", &hits, &skipped, Some(2)), @r#"
+        # Shelf search: "access" (2 matches in 1 books)
+
+        ## sop — SOP
+        - 1-1. Check access
+        - 2. Execute [body] …check the access log…
+
+        [NOTE] skipped 'broken' (could not load: invalid JSON)
+        [NOTE] stopped at 2 matches; narrow the query or raise `limit`
+        "#);
+    }
+
+    #[test]
+    fn book_info_lists_identity_and_shape() {
+        let book = toc_book();
+        let info = format_book_info("sop", &book).replace(&book.id().to_string(), "<book-id>");
+        insta::assert_snapshot!(info, @r"
+        # Book info: SOP
+
+        - id: <book-id>
+        - slug: sop
+        - title: SOP
+        - max_depth: 4
+        - max_actual_depth: 3
+        - node_count: 5
+        - root_count: 2
+        ");
+    }
+
+    #[test]
+    fn toc_shows_estimates() {
+        let mut book = toc_book();
+        estimate(&mut book, "1-1", 30);
+        let nodes = book.all_nodes_dfs();
+        insta::assert_snapshot!(format_toc(&book, &nodes, Numbering::Dash), @r"
+        # SOP (5 nodes)
+
+        1. Preparation
+          1-1. Check access (~30m)
+          1-2. Dry run
+            1-2-1. Review output
+        2. Execute
+        ");
+    }
+
+    #[test]
+    fn toc_bullets_children_of_unordered_sections() {
+        let mut book = toc_book();
+        let prep = build_hierarchical_ids(&book)[0].1;
+        book.update_node(
+            prep,
+            UpdateNodeRequest {
+                ordered: Some(false),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        let nodes = book.all_nodes_dfs();
+        insta::assert_snapshot!(format_toc(&book, &nodes, Numbering::Dash), @r"
+        # SOP (5 nodes)
+
+        1. Preparation
+          • Check access
+          • Dry run
+            1-2-1. Review output
+        2. Execute
+        ");
+    }
+
+    #[test]
+    fn stats_sums_estimates_per_section() {
+        let mut book = toc_book();
+        estimate(&mut book, "1-1", 30);
+        estimate(&mut book, "1-2-1", 45);
+        estimate(&mut book, "2", 60);
+        insta::assert_snapshot!(format_stats(&book, None), @r"
+        # Stats: SOP
+
+        - Nodes: 5 (2 sections, 3 content)
+        - Estimated effort: ~2h15m (135m)
+        - Unestimated content items: 0
+        - Deepest: level 3 of 4 — 1 path, e.g. 1-2-1 'Review output'
+
+        ## By section
+
+        - 1. Preparation: ~1h15m
+          - 1-2. Dry run: ~45m
+        ");
+    }
+
+    #[test]
+    fn status_reports_selection_and_shelf() {
+        let book = toc_book();
+        let shelf = std::path::Path::new("/data/shelf");
+        insta::assert_snapshot!(format_status(shelf, 3, Some("sop"), Some(&book), &shelf.join("exports")), @r#"
+        # Status
+
+        - Shelf: /data/shelf (3 books)
+        - Exports go to: /data/shelf/exports (`checklist` without `output_dir`)
+        - Selected book: sop — "SOP" (5 nodes, max_depth 4)
+
+        Selection is per session: it lives in this server process only and is never written to the shelf, so other sessions on the same shelf keep their own.
+        "#);
+        insta::assert_snapshot!(format_status(shelf, 0, None, None, &shelf.join("exports")), @r"
+        # Status
+
+        - Shelf: /data/shelf (0 books)
+        - Exports go to: /data/shelf/exports (`checklist` without `output_dir`)
+        - Selected book: none (use `select_book` or `init`)
+
+        Selection is per session: it lives in this server process only and is never written to the shelf, so other sessions on the same shelf keep their own.
+        ");
+    }
+
+    #[test]
+    fn changes_grouped_by_top_level_section() {
+        use outline_mcp_core::application::tree_diff::diff_books;
+
+        let before = toc_book();
+        let mut after = before.clone();
+        let ids = build_hierarchical_ids(&after);
+        let id_of = |h: &str| ids.iter().find(|(k, _)| k == h).unwrap().1;
+        after.remove_node(id_of("1-2-1")).unwrap();
+        after.move_node(id_of("1-1"), None, Position::Last).unwrap();
+        estimate(&mut after, "2", 15);
+
+        let changes = diff_books(&before, &after);
+        insta::assert_snapshot!(format_changes(&after, &changes, "select_book"), @r"
+        # Changes since select_book
+
+        3 changes: 0 added, 1 removed, 1 modified, 1 moved
+
+        ## Execute
+
+        - modified 2. Execute (estimate)
+
+        ## Check access
+
+        - moved 3. Check access
+
+        ## Preparation
+
+        - removed: Review output
+        ");
+        insta::assert_snapshot!(format_changes(&before, &[], "snapshot 'v1'"), @r"
+        # Changes since snapshot 'v1'
+
+        No changes.
+        ");
+    }
+
+    #[test]
+    fn format_minutes_units() {
+        assert_eq!(format_minutes(45), "45m");
+        assert_eq!(format_minutes(120), "2h");
+        assert_eq!(format_minutes(150), "2h30m");
+    }
+
+    #[test]
+    fn toc_shows_owners_only_when_requested() {
+        let mut book = toc_book();
+        let id = build_hierarchical_ids(&book)[1].1;
+        book.update_node(
+            id,
+            UpdateNodeRequest {
+                owner: Some(Some("alice".into())),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        let nodes = book.all_nodes_dfs();
+        assert!(!format_toc(&book, &nodes, Numbering::Dash).contains("@alice"));
+
+        let options = TocOptions {
+            show_owners: true,
+            ..TocOptions::default()
+        };
+        insta::assert_snapshot!(format_toc(&book, &nodes, options), @r"
+        # SOP (5 nodes)
+
+        1. Preparation
+          1-1. Check access @alice
+          1-2. Dry run
+            1-2-1. Review output
+        2. Execute
+        ");
+    }
+}
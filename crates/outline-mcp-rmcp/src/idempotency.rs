@@ -0,0 +1,78 @@
+//! Recently seen `node_create` idempotency keys, so a retried call returns
+//! the node created the first time instead of inserting a duplicate.
+//!
+//! Kept in memory per book slug; a server restart forgets all keys.
+
+use std::collections::{HashMap, VecDeque};
+
+use outline_mcp_core::domain::model::id::NodeId;
+
+/// Per-book LRU of `idempotency_key → NodeId`.
+#[derive(Debug, Default)]
+pub(crate) struct IdempotencyCache {
+    books: HashMap<String, VecDeque<(String, NodeId)>>,
+}
+
+impl IdempotencyCache {
+    /// Keys remembered per book; the least recently used key is dropped first.
+    pub(crate) const CAPACITY: usize = 256;
+
+    /// Node created earlier under `key` in `slug`, marking the key as used.
+    pub(crate) fn get(&mut self, slug: &str, key: &str) -> Option<NodeId> {
+        let entries = self.books.get_mut(slug)?;
+        let pos = entries.iter().position(|(k, _)| k == key)?;
+        let entry = entries.remove(pos)?;
+        let id = entry.1;
+        entries.push_back(entry);
+        Some(id)
+    }
+
+    /// Remember that `key` created `id` in `slug`.
+    pub(crate) fn insert(&mut self, slug: &str, key: &str, id: NodeId) {
+        let entries = self.books.entry(slug.to_string()).or_default();
+        entries.retain(|(k, _)| k != key);
+        entries.push_back((key.to_string(), id));
+        while entries.len() > Self::CAPACITY {
+            entries.pop_front();
+        }
+    }
+
+    /// Forget `key` in `slug` (e.g. its node has since been removed).
+    pub(crate) fn forget(&mut self, slug: &str, key: &str) {
+        if let Some(entries) = self.books.get_mut(slug) {
+            entries.retain(|(k, _)| k != key);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keys_are_scoped_per_book() {
+        let mut cache = IdempotencyCache::default();
+        let id = NodeId::new();
+        cache.insert("rust", "req-1", id);
+        assert_eq!(cache.get("rust", "req-1"), Some(id));
+        assert_eq!(cache.get("ops", "req-1"), None);
+        cache.forget("rust", "req-1");
+        assert_eq!(cache.get("rust", "req-1"), None);
+    }
+
+    #[test]
+    fn least_recently_used_key_is_evicted() {
+        let mut cache = IdempotencyCache::default();
+        let first = NodeId::new();
+        cache.insert("rust", "first", first);
+        for i in 1..IdempotencyCache::CAPACITY {
+            cache.insert("rust", &format!("k{i}"), NodeId::new());
+        }
+        // 参照した "first" は最新扱いになり、次の追加では "k1" が落ちる
+        assert_eq!(cache.get("rust", "first"), Some(first));
+        cache.insert("rust", "overflow", NodeId::new());
+        assert_eq!(cache.get("rust", "first"), Some(first));
+        assert_eq!(cache.get("rust", "k1"), None);
+        assert!(cache.get("rust", "k2").is_some());
+    }
+}
@@ -13,6 +13,8 @@
 //!
 //! ## Design
 //!
+//! - `config`: [`ServerConfig`] (and its [`InitDefaults`]) — optional
+//!   server-wide settings read from the shelf directory.
 //! - `server`: [`OutlineMcpServer`] — holds the shelf directory (multi-book
 //!   root) and the currently selected book, and implements `ServerHandler`.
 //! - `tools`: the `#[tool]`-annotated MCP tool handlers (node CRUD,
@@ -21,6 +23,8 @@
 //!   their validation helpers.
 //! - `helpers`: hierarchical-ID (`toc` numbering) bookkeeping shared by
 //!   `server` and `tools`.
+//! - `idempotency`: in-memory per-book cache of `node_create` idempotency
+//!   keys (retry deduplication).
 //! - `resources`: bundled Markdown guides exposed via `outline://guides/*`.
 //!
 //! Consumers that only need to run the server as-is should call [`run`].
@@ -28,10 +32,13 @@
 //! larger MCP host) can construct [`OutlineMcpServer`] and drive it with
 //! any `rmcp` transport.
 
+mod config;
 mod helpers;
+mod idempotency;
 mod request;
 mod resources;
 mod server;
 mod tools;
 
-pub use server::{run, OutlineMcpServer};
+pub use config::{InitDefaults, ServerConfig, ToolProfile};
+pub use server::{run, run_with, OutlineMcpServer, RunOptions};
@@ -1,13 +1,19 @@
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::path::{Component, Path, PathBuf};
 
 use rmcp::ErrorData as McpError;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
+use crate::helpers::{Numbering, PlaceholderFormat};
+
+use outline_mcp_core::application::dedupe::DedupeMode;
+use outline_mcp_core::application::eject::{DepthStrategy, EjectFormat, IdConflict, MarkdownStyle};
+use outline_mcp_core::domain::model::book::{EjectDefaults, Position, UpdateNodeRequest};
 use outline_mcp_core::domain::model::changelog::NodeStatus;
 use outline_mcp_core::domain::model::id::NodeId;
 use outline_mcp_core::domain::model::node::NodeType;
+use outline_mcp_core::domain::model::timestamp::Timestamp;
 
 // =============================================================================
 // Validation helpers
@@ -91,35 +97,278 @@ pub(crate) fn validate_filename(filename: &str) -> Result<(), McpError> {
     Ok(())
 }
 
-/// importパスの拡張子を検証する。
-pub(crate) fn validate_import_path(file_path: &str) -> Result<PathBuf, McpError> {
+/// 取り込むファイルの形式。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ImportFormat {
+    Json,
+    Markdown,
+}
+
+/// importパスの拡張子を検証し、形式を返す。
+pub(crate) fn validate_import_path(file_path: &str) -> Result<(PathBuf, ImportFormat), McpError> {
     let path = PathBuf::from(file_path);
     match path.extension().and_then(|e| e.to_str()) {
-        Some("json") => Ok(path),
+        Some("json") => Ok((path, ImportFormat::Json)),
+        Some("md") | Some("markdown") => Ok((path, ImportFormat::Markdown)),
         _ => Err(McpError::invalid_params(
-            "Only .json files can be imported",
+            "Only .json and .md files can be imported",
             None,
         )),
     }
 }
 
+/// `output_path` を検証し、書き込み先のディレクトリとファイル名に分ける。
+///
+/// 絶対パスのみ受け付け、`..` を含むものは拒否する。存在する最も深い祖先を
+/// canonicalize（シンボリックリンクを解決）し、まだない部分を付け直した場所が
+/// `roots` のいずれかの中に収まることを確かめる。ファイル名は [`validate_filename`]
+/// と同じ規則で、既存ファイルがシンボリックリンクならそのリンク先も検証する。
+pub(crate) fn resolve_output_path(
+    output_path: &str,
+    roots: &[&Path],
+) -> Result<(PathBuf, String), McpError> {
+    let path = Path::new(output_path.trim());
+    if !path.is_absolute() {
+        return Err(McpError::invalid_params(
+            format!("output_path must be an absolute path, got '{output_path}'"),
+            None,
+        ));
+    }
+    if path.components().any(|c| c == Component::ParentDir) {
+        return Err(McpError::invalid_params(
+            "output_path must not contain '..'",
+            None,
+        ));
+    }
+    let filename = path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .ok_or_else(|| McpError::invalid_params("output_path must end with a file name", None))?;
+    validate_filename(filename)?;
+
+    let outside = |shown: &Path| {
+        let allowed: Vec<String> = roots.iter().map(|r| r.display().to_string()).collect();
+        McpError::invalid_params(
+            format!(
+                "output_path '{}' is outside the allowed export roots: {}",
+                shown.display(),
+                allowed.join(", ")
+            ),
+            None,
+        )
+    };
+    let canonical_roots: Vec<PathBuf> =
+        roots.iter().filter_map(|r| r.canonicalize().ok()).collect();
+    let inside = |p: &Path| canonical_roots.iter().any(|root| p.starts_with(root));
+
+    // 存在する最も深い祖先だけを canonicalize し、まだないディレクトリを後ろに付け直す
+    let mut existing = path.parent().ok_or_else(|| outside(path))?;
+    let mut missing = Vec::new();
+    let resolved = loop {
+        match existing.canonicalize() {
+            Ok(real) => break real,
+            Err(_) => {
+                missing.push(existing.file_name().ok_or_else(|| outside(path))?);
+                existing = existing.parent().ok_or_else(|| outside(path))?;
+            }
+        }
+    };
+    let dir = missing
+        .iter()
+        .rev()
+        .fold(resolved, |acc, part| acc.join(part));
+    if !inside(&dir) {
+        return Err(outside(path));
+    }
+    if let Ok(target) = dir.join(filename).canonicalize() {
+        if !inside(&target) {
+            return Err(outside(path));
+        }
+    }
+    Ok((dir, filename.to_string()))
+}
+
+/// `node_type` に書ける語（[`parse_node_type`] と `help` が共有する）。
+pub(crate) const NODE_TYPE_NAMES: &[(&str, NodeType)] = &[
+    ("section", NodeType::Section),
+    ("content", NodeType::Content),
+];
+
+/// `status` に書ける語（[`parse_node_status`] と `help` が共有する）。
+pub(crate) const NODE_STATUS_NAMES: &[(&str, NodeStatus)] =
+    &[("active", NodeStatus::Active), ("draft", NodeStatus::Draft)];
+
+fn names<T>(table: &[(&str, T)]) -> String {
+    table
+        .iter()
+        .map(|(name, _)| *name)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
 pub(crate) fn parse_node_type(s: &str) -> Result<NodeType, McpError> {
-    match s {
-        "section" => Ok(NodeType::Section),
-        "content" => Ok(NodeType::Content),
-        other => Err(McpError::invalid_params(
-            format!("Unknown node_type: '{other}'. Use: section, content"),
+    match NODE_TYPE_NAMES.iter().find(|(name, _)| *name == s) {
+        Some((_, node_type)) => Ok(node_type.clone()),
+        None => Err(McpError::invalid_params(
+            format!("Unknown node_type: '{s}'. Use: {}", names(NODE_TYPE_NAMES)),
             None,
         )),
     }
 }
 
 pub(crate) fn parse_node_status(s: &str) -> Result<NodeStatus, McpError> {
+    match NODE_STATUS_NAMES.iter().find(|(name, _)| *name == s) {
+        Some((_, status)) => Ok(*status),
+        None => Err(McpError::invalid_params(
+            format!("Unknown status: '{s}'. Use: {}", names(NODE_STATUS_NAMES)),
+            None,
+        )),
+    }
+}
+
+/// MCP の `position`。0 始まりの番号か `"first"` / `"last"`（文字列の数字も番号として読む）。
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(untagged)]
+pub(crate) enum PositionArg {
+    Index(usize),
+    Keyword(String),
+}
+
+/// `position` を内部の [`Position`] にする。省略は末尾。
+pub(crate) fn parse_position(arg: Option<&PositionArg>) -> Result<Position, McpError> {
+    match arg {
+        None => Ok(Position::Last),
+        Some(PositionArg::Index(index)) => Ok(Position::At(*index)),
+        Some(PositionArg::Keyword(word)) => match word.trim().to_ascii_lowercase().as_str() {
+            "first" => Ok(Position::First),
+            "last" => Ok(Position::Last),
+            other => other.parse().map(Position::At).map_err(|_| {
+                McpError::invalid_params(
+                    format!(
+                        "Unknown position: '{word}'. Use \"first\", \"last\" or a 0-based index"
+                    ),
+                    None,
+                )
+            }),
+        },
+    }
+}
+
+pub(crate) fn parse_numbering(s: Option<&str>) -> Result<Numbering, McpError> {
+    match s {
+        None | Some("dash") => Ok(Numbering::Dash),
+        Some("dot") => Ok(Numbering::Dot),
+        Some("legal") => Ok(Numbering::Legal),
+        Some(other) => Err(McpError::invalid_params(
+            format!("Unknown numbering: '{other}'. Use: dash, dot, legal"),
+            None,
+        )),
+    }
+}
+
+pub(crate) fn parse_placeholder_format(s: Option<&str>) -> Result<PlaceholderFormat, McpError> {
+    match s {
+        None | Some("list") => Ok(PlaceholderFormat::List),
+        Some("markdown-table") => Ok(PlaceholderFormat::MarkdownTable),
+        Some(other) => Err(McpError::invalid_params(
+            format!("Unknown format: '{other}'. Use: list, markdown-table"),
+            None,
+        )),
+    }
+}
+
+pub(crate) fn parse_dedupe_mode(s: Option<&str>) -> Result<DedupeMode, McpError> {
+    match s {
+        None | Some("off") => Ok(DedupeMode::Off),
+        Some("skip") => Ok(DedupeMode::Skip),
+        Some("update") => Ok(DedupeMode::Update),
+        Some(other) => Err(McpError::invalid_params(
+            format!("Unknown dedupe: '{other}'. Use: off, skip, update"),
+            None,
+        )),
+    }
+}
+
+pub(crate) fn parse_id_conflict(s: Option<&str>) -> Result<IdConflict, McpError> {
+    match s {
+        None | Some("off") => Ok(IdConflict::Off),
+        Some("error") => Ok(IdConflict::Error),
+        Some("skip") => Ok(IdConflict::Skip),
+        Some("replace") => Ok(IdConflict::Replace),
+        Some(other) => Err(McpError::invalid_params(
+            format!("Unknown on_id_conflict: '{other}'. Use: off, error, skip, replace"),
+            None,
+        )),
+    }
+}
+
+pub(crate) fn parse_eject_format(s: &str) -> Result<EjectFormat, McpError> {
     match s {
-        "active" => Ok(NodeStatus::Active),
-        "draft" => Ok(NodeStatus::Draft),
+        "markdown" => Ok(EjectFormat::Markdown),
+        "json" => Ok(EjectFormat::Json),
+        "html" => Ok(EjectFormat::Html),
         other => Err(McpError::invalid_params(
-            format!("Unknown status: '{other}'. Use: active, draft"),
+            format!("Unknown format: '{other}'. Use: markdown, json, html"),
+            None,
+        )),
+    }
+}
+
+/// `checklist` の書き出し先・形式・placeholder 表示の決定結果。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct EjectSettings {
+    pub output_dir: PathBuf,
+    pub format: EjectFormat,
+    pub include_placeholders: bool,
+}
+
+/// 項目ごとに リクエスト > Book の `eject_defaults` > 全体設定 > 組み込みの既定値
+/// （`default_dir`・markdown・placeholder あり）の順で最初に指定された値を使う。
+pub(crate) fn resolve_eject_settings(
+    request: &EjectDefaults,
+    book: Option<&EjectDefaults>,
+    config: &EjectDefaults,
+    default_dir: &Path,
+) -> Result<EjectSettings, McpError> {
+    let layers = [Some(request), book, Some(config)];
+    let layers = layers.iter().flatten();
+    let output_dir = layers
+        .clone()
+        .find_map(|l| l.output_dir.as_deref())
+        .map_or_else(|| default_dir.to_path_buf(), PathBuf::from);
+    let format = match layers.clone().find_map(|l| l.format.as_deref()) {
+        Some(f) => parse_eject_format(f)?,
+        None => EjectFormat::Markdown,
+    };
+    let include_placeholders = layers
+        .clone()
+        .find_map(|l| l.include_placeholders)
+        .unwrap_or(true);
+    Ok(EjectSettings {
+        output_dir,
+        format,
+        include_placeholders,
+    })
+}
+
+pub(crate) fn parse_markdown_style(s: Option<&str>) -> Result<MarkdownStyle, McpError> {
+    match s {
+        None | Some("checkbox") => Ok(MarkdownStyle::Checkbox),
+        Some("form") => Ok(MarkdownStyle::Form),
+        Some(other) => Err(McpError::invalid_params(
+            format!("Unknown style: '{other}'. Use: checkbox, form"),
+            None,
+        )),
+    }
+}
+
+pub(crate) fn parse_depth_strategy(s: Option<&str>) -> Result<DepthStrategy, McpError> {
+    match s {
+        None | Some("error") => Ok(DepthStrategy::Error),
+        Some("flatten") => Ok(DepthStrategy::Flatten),
+        Some("raise") => Ok(DepthStrategy::Raise),
+        Some(other) => Err(McpError::invalid_params(
+            format!("Unknown depth_strategy: '{other}'. Use: error, flatten, raise"),
             None,
         )),
     }
@@ -134,11 +383,56 @@ pub(crate) fn normalize_text(s: Option<String>) -> Option<String> {
     s.map(|v| unescape_newlines(&v))
 }
 
+/// 入力されたタイトルの `\n` を改行に戻し、`normalize` なら [`normalize_title`] もかける。
+pub(crate) fn input_title(raw: &str, normalize: bool) -> String {
+    let title = unescape_newlines(raw);
+    if normalize {
+        normalize_title(&title)
+    } else {
+        title
+    }
+}
+
+/// 前後の空白と、他の文書から貼り付けた先頭のリスト記号（`- ` `* ` `+ `）や番号
+/// （`1. ` `2) `）を1つ取り除く。記号を除くと空になるタイトルは空白だけ除く。
+pub(crate) fn normalize_title(title: &str) -> String {
+    let trimmed = title.trim();
+    let digits = trimmed.chars().take_while(char::is_ascii_digit).count();
+    let rest = match trimmed.chars().next() {
+        Some('-' | '*' | '+') => &trimmed[1..],
+        Some(_) if (1..=3).contains(&digits) => match trimmed[digits..].chars().next() {
+            Some('.' | ')') => &trimmed[digits + 1..],
+            _ => return trimmed.to_string(),
+        },
+        _ => return trimmed.to_string(),
+    };
+    // 記号の直後に空白がなければ（"-1" や "3.5 GHz"）タイトルの一部とみなす
+    match rest.strip_prefix(char::is_whitespace).map(str::trim) {
+        Some(stripped) if !stripped.is_empty() => stripped.to_string(),
+        _ => trimmed.to_string(),
+    }
+}
+
 pub(crate) fn parse_node_id(s: &str) -> Result<NodeId, McpError> {
     serde_json::from_value(serde_json::Value::String(s.to_string()))
         .map_err(|_| McpError::invalid_params(format!("Invalid node_id: '{s}'"), None))
 }
 
+/// millis 整数または ISO 8601 文字列を Timestamp に変換する。
+pub(crate) fn parse_timestamp_arg(s: &str, field: &str) -> Result<Timestamp, McpError> {
+    if let Ok(millis) = s.parse::<i64>() {
+        return Ok(Timestamp::from_millis(millis));
+    }
+    Timestamp::parse_iso8601(s).map_err(|_| {
+        McpError::invalid_params(
+            format!(
+                "Invalid {field}: '{s}'. Use millis or ISO 8601 (e.g. 2026-01-31T09:00:00.000Z)."
+            ),
+            None,
+        )
+    })
+}
+
 // =============================================================================
 // Request types
 // =============================================================================
@@ -146,107 +440,824 @@ pub(crate) fn parse_node_id(s: &str) -> Result<NodeId, McpError> {
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub(crate) struct McpNodeCreateRequest {
     #[schemars(
-        description = "Parent ID from `toc` output (e.g. '1', '2-3'). Omit for root-level node. UUID also accepted."
+        description = "Parent ID from `toc` output (e.g. '1', '2-3'). Omit for root-level node. UUID also accepted."
+    )]
+    pub parent: Option<String>,
+    #[schemars(description = "Node title (required)")]
+    pub title: String,
+    #[schemars(
+        description = "Node type: section or content. Default: section when `children` are given, otherwise content (an actionable item)"
+    )]
+    pub node_type: Option<String>,
+    #[schemars(description = "Optional markdown body content")]
+    pub body: Option<String>,
+    #[schemars(
+        description = "Optional placeholder hint for checklist export (e.g. 'write test cases here')"
+    )]
+    pub placeholder: Option<String>,
+    #[schemars(
+        description = "Optional shell command the item runs (kept apart from the body). `checklist` renders it as a ```sh block under the item; `extract_commands: true` collects them into a script. Never executed."
+    )]
+    pub command: Option<String>,
+    #[schemars(
+        description = "Position among siblings: \"first\", \"last\" or a 0-based index (0 is the first; an index past the end appends). Default: last."
+    )]
+    pub position: Option<PositionArg>,
+    #[schemars(
+        description = "Optional key-value properties (e.g. {\"inject\": \"true\", \"scope\": \"rust\"})"
+    )]
+    pub properties: Option<HashMap<String, String>>,
+    #[schemars(
+        description = "Child nodes to create under the new node in the same call, in order (one level only)"
+    )]
+    pub children: Option<Vec<McpNodeChildSpec>>,
+    #[schemars(
+        description = "Optional client-chosen key for safe retries: repeating a key in the same book returns the node created the first time instead of creating a duplicate"
+    )]
+    pub idempotency_key: Option<String>,
+    #[schemars(
+        description = "When a sibling with the same title (case/whitespace-insensitive) exists: 'off' (default) creates anyway, 'skip' reuses the existing node, 'update' reuses it and overwrites its body/placeholder. `children` are matched against the reused node's children the same way."
+    )]
+    pub dedupe: Option<String>,
+    #[schemars(
+        description = "Reply with just the node's hierarchical ID instead of the full confirmation, to save context in bulk edits (default: false). Warnings are still reported."
+    )]
+    #[serde(default)]
+    pub quiet: bool,
+    #[schemars(
+        description = "Proceed even though the call creates or removes more nodes than the server's per-call `mutation_budget` (default 100). Without it such a call changes nothing and replies with a preview."
+    )]
+    #[serde(default)]
+    pub confirm: bool,
+}
+
+/// `node_create` の `children` の1要素（1階層のみ）。
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub(crate) struct McpNodeChildSpec {
+    #[schemars(description = "Child title (required)")]
+    pub title: String,
+    #[schemars(description = "Node type: section or content (default: content)")]
+    pub node_type: Option<String>,
+    #[schemars(description = "Optional markdown body content")]
+    pub body: Option<String>,
+    #[schemars(description = "Optional placeholder hint for checklist export")]
+    pub placeholder: Option<String>,
+    /// Not supported; present only so nested input gets a helpful error.
+    #[schemars(skip)]
+    #[serde(default, skip_serializing)]
+    pub children: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub(crate) struct McpNodeUpdateRequest {
+    #[schemars(description = "Node ID from `toc` output (e.g. '2-3'). UUID also accepted.")]
+    pub node_id: String,
+    #[serde(flatten)]
+    pub fields: McpNodeUpdateFields,
+    #[schemars(
+        description = "Also move the node under this parent ID from `toc` output ('root' for top level), after applying the field updates. Both are saved together or not at all."
+    )]
+    pub new_parent: Option<String>,
+    #[schemars(
+        description = "Position among siblings: \"first\", \"last\" or a 0-based index (0 is the first; an index past the end appends). Default: last. Without `new_parent`, reorders within the current parent."
+    )]
+    pub position: Option<PositionArg>,
+    #[schemars(
+        description = "Reply with just the node's hierarchical ID instead of the full confirmation, to save context in bulk edits (default: false). Warnings are still reported."
+    )]
+    #[serde(default)]
+    pub quiet: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub(crate) struct McpNodeUpdateBulkRequest {
+    #[schemars(
+        description = "Node IDs from `toc` output (e.g. ['2-1', '2-3']). UUIDs also accepted. If any cannot be resolved, nothing is changed."
+    )]
+    pub node_ids: Vec<String>,
+    #[serde(flatten)]
+    pub fields: McpNodeUpdateFields,
+}
+
+/// `node_update` / `node_update_bulk` が共有する更新項目。
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub(crate) struct McpNodeUpdateFields {
+    #[schemars(description = "New title (omit to keep current)")]
+    pub title: Option<String>,
+    #[schemars(
+        description = "New body (omit to keep current). `null` also keeps the body unless the server sets `null_clears_fields`; use `clear_body: true` to clear it."
+    )]
+    #[serde(default, deserialize_with = "explicit_null")]
+    pub body: Option<Option<String>>,
+    #[schemars(description = "New node type: section or content")]
+    pub node_type: Option<String>,
+    #[schemars(
+        description = "New placeholder hint (omit to keep current). `null` also keeps it unless the server sets `null_clears_fields`; use `clear_placeholder: true` to clear it."
+    )]
+    #[serde(default, deserialize_with = "explicit_null")]
+    pub placeholder: Option<Option<String>>,
+    #[schemars(
+        description = "Remove the body (default: false). Cannot be combined with a `body` string."
+    )]
+    #[serde(default)]
+    pub clear_body: bool,
+    #[schemars(
+        description = "Remove the placeholder hint (default: false). Cannot be combined with a `placeholder` string."
+    )]
+    #[serde(default)]
+    pub clear_placeholder: bool,
+    #[schemars(description = "Replace all properties (omit to keep current). Pass {} to clear.")]
+    pub properties: Option<HashMap<String, String>>,
+    #[schemars(
+        description = "Node status: 'active' or 'draft'. Draft nodes are excluded from select_book inject."
+    )]
+    pub status: Option<String>,
+    #[schemars(
+        description = "Private notes (rationale, incident links). Hidden from Markdown exports unless include_notes is set (omit to keep current). `null` also keeps them unless the server sets `null_clears_fields`; use `clear_notes: true` to clear them."
+    )]
+    #[serde(default, deserialize_with = "explicit_null")]
+    pub notes: Option<Option<String>>,
+    #[schemars(
+        description = "Remove the private notes (default: false). Cannot be combined with a `notes` value."
+    )]
+    #[serde(default)]
+    pub clear_notes: bool,
+    #[schemars(
+        description = "Public heads-up note shown under the item in Markdown exports as a blockquote — never turned into checkboxes, unlike `body` (omit to keep current). `null` also keeps it unless the server sets `null_clears_fields`; use `clear_note: true` to clear it."
+    )]
+    #[serde(default, deserialize_with = "explicit_null")]
+    pub note: Option<Option<String>>,
+    #[schemars(
+        description = "Remove the public note (default: false). Cannot be combined with a `note` value."
+    )]
+    #[serde(default)]
+    pub clear_note: bool,
+    #[schemars(
+        description = "Effort estimate in minutes, shown in toc as (~30m) (omit to keep current). `null` also keeps it unless the server sets `null_clears_fields`; use `clear_estimate_minutes: true` to clear it."
+    )]
+    #[serde(default, deserialize_with = "explicit_null")]
+    pub estimate_minutes: Option<Option<u32>>,
+    #[schemars(
+        description = "Remove the effort estimate (default: false). Cannot be combined with a `estimate_minutes` value."
+    )]
+    #[serde(default)]
+    pub clear_estimate_minutes: bool,
+    #[schemars(
+        description = "Owner / assignee name (omit to keep current). `null` also keeps it unless the server sets `null_clears_fields`; use `clear_owner: true` to clear it."
+    )]
+    #[serde(default, deserialize_with = "explicit_null")]
+    pub owner: Option<Option<String>>,
+    #[schemars(
+        description = "Remove the owner (default: false). Cannot be combined with a `owner` value."
+    )]
+    #[serde(default)]
+    pub clear_owner: bool,
+    #[schemars(
+        description = "Check the item off (true) or reopen it (false). Use `reset_progress` to reopen a whole run."
+    )]
+    pub done: Option<bool>,
+    #[schemars(
+        description = "Sections only: false marks the children as an unordered collection (e.g. reference links) — `toc` shows them as `•` and `checklist` drops their checkboxes; true restores numbering"
+    )]
+    pub ordered: Option<bool>,
+    #[schemars(
+        description = "Manual sort key among siblings (e.g. 10, 20, 30; use 15 to slot between). Keyed siblings are listed first in ascending order, overriding insertion order (omit to keep current). `null` also keeps it unless the server sets `null_clears_fields`; use `clear_order: true` to clear it."
+    )]
+    #[serde(default, deserialize_with = "explicit_null")]
+    pub order: Option<Option<f64>>,
+    #[schemars(
+        description = "Remove the manual sort key (default: false). Cannot be combined with a `order` value."
+    )]
+    #[serde(default)]
+    pub clear_order: bool,
+    #[schemars(
+        description = "Protect the node from removal (true) or lift the protection (false). Removing a protected node, or any ancestor of one, fails unless `node_move` passes override_protection: true. Shown as 🛡 in toc."
+    )]
+    pub protected: Option<bool>,
+    #[schemars(
+        description = "Sections only: Markdown heading level (1-6) for this section in `checklist` exports, overriding the depth-based level; its descendant sections follow one level deeper each (omit to keep current). `null` also keeps it unless the server sets `null_clears_fields`; use `clear_heading_level: true` to clear it."
+    )]
+    #[serde(default, deserialize_with = "explicit_null")]
+    pub heading_level: Option<Option<usize>>,
+    #[schemars(
+        description = "Remove the heading level override (default: false). Cannot be combined with a `heading_level` value."
+    )]
+    #[serde(default)]
+    pub clear_heading_level: bool,
+    #[schemars(
+        description = "Shell command the item runs, rendered as a ```sh block by `checklist` and collected by `extract_commands`. Never executed (omit to keep current). `null` also keeps it unless the server sets `null_clears_fields`; use `clear_command: true` to clear it."
+    )]
+    #[serde(default, deserialize_with = "explicit_null")]
+    pub command: Option<Option<String>>,
+    #[schemars(
+        description = "Remove the shell command (default: false). Cannot be combined with a `command` value."
+    )]
+    #[serde(default)]
+    pub clear_command: bool,
+}
+
+impl McpNodeUpdateFields {
+    /// `body` と `clear_body` から実際の更新を決める（`None` = 変えない、
+    /// `Some(None)` = 消す）。`null_clears` は設定 `null_clears_fields`。
+    pub(crate) fn body_update(
+        &self,
+        null_clears: bool,
+    ) -> Result<Option<Option<String>>, McpError> {
+        clearable_update("body", self.body.clone(), self.clear_body, null_clears)
+    }
+
+    /// [`Self::body_update`] の placeholder 版。
+    pub(crate) fn placeholder_update(
+        &self,
+        null_clears: bool,
+    ) -> Result<Option<Option<String>>, McpError> {
+        clearable_update(
+            "placeholder",
+            self.placeholder.clone(),
+            self.clear_placeholder,
+            null_clears,
+        )
+    }
+
+    /// ノードに依らない検証をしたうえで `UpdateNodeRequest` にする
+    /// （改行のエスケープ解除・空文字の正規化、`normalize_titles` ならタイトルの整形を含む）。
+    pub(crate) fn to_update_request(
+        &self,
+        null_clears: bool,
+        normalize_titles: bool,
+    ) -> Result<UpdateNodeRequest, McpError> {
+        let node_type = self.node_type.as_deref().map(parse_node_type).transpose()?;
+        let status = self.status.as_deref().map(parse_node_status).transpose()?;
+        let body = self.body_update(null_clears)?;
+        let placeholder = self.placeholder_update(null_clears)?;
+        let notes = clearable_update("notes", self.notes.clone(), self.clear_notes, null_clears)?;
+        let note = clearable_update("note", self.note.clone(), self.clear_note, null_clears)?;
+        let estimate_minutes = clearable_update(
+            "estimate_minutes",
+            self.estimate_minutes,
+            self.clear_estimate_minutes,
+            null_clears,
+        )?;
+        let owner = clearable_update("owner", self.owner.clone(), self.clear_owner, null_clears)?;
+        let order = clearable_update("order", self.order, self.clear_order, null_clears)?;
+        let heading_level = clearable_update(
+            "heading_level",
+            self.heading_level,
+            self.clear_heading_level,
+            null_clears,
+        )?;
+        let command = clearable_update(
+            "command",
+            self.command.clone(),
+            self.clear_command,
+            null_clears,
+        )?;
+        if self.order.flatten().is_some_and(|o| !o.is_finite()) {
+            return Err(McpError::invalid_params(
+                "`order` must be a finite number",
+                None,
+            ));
+        }
+        if self
+            .heading_level
+            .flatten()
+            .is_some_and(|l| !(1..=6).contains(&l))
+        {
+            return Err(McpError::invalid_params(
+                "`heading_level` must be between 1 and 6",
+                None,
+            ));
+        }
+        Ok(UpdateNodeRequest {
+            title: self
+                .title
+                .as_deref()
+                .map(|t| input_title(t, normalize_titles)),
+            body: body.map(normalize_text),
+            node_type,
+            placeholder: placeholder.map(normalize_text),
+            properties: self.properties.clone(),
+            status,
+            notes: notes.map(normalize_text),
+            note: note.map(normalize_text),
+            estimate_minutes,
+            owner,
+            done: self.done,
+            ordered: self.ordered,
+            order,
+            protected: self.protected,
+            heading_level,
+            command: command.map(normalize_text),
+        })
+    }
+
+    /// 更新後のノードに対する検証: Section 専用の項目を Content に付けていないか。
+    pub(crate) fn check_applies_to(&self, node_type: Option<&NodeType>) -> Result<(), McpError> {
+        let is_section = node_type == Some(&NodeType::Section);
+        if self.ordered.is_some() && !is_section {
+            return Err(McpError::invalid_params(
+                "`ordered` applies to sections only (it controls how their children are numbered)",
+                None,
+            ));
+        }
+        if self.heading_level.flatten().is_some() && !is_section {
+            return Err(McpError::invalid_params(
+                "`heading_level` applies to sections only (content items are checkboxes, not headings)",
+                None,
+            ));
+        }
+        Ok(())
+    }
+}
+
+fn clearable_update<T>(
+    field: &str,
+    value: Option<Option<T>>,
+    clear: bool,
+    null_clears: bool,
+) -> Result<Option<Option<T>>, McpError> {
+    match value {
+        Some(Some(_)) if clear => Err(McpError::invalid_params(
+            format!("Give either `{field}` or `clear_{field}: true`, not both."),
+            None,
+        )),
+        _ if clear => Ok(Some(None)),
+        // 多くのクライアントが省略したフィールドも null で送るため、既定では null を「変えない」とみなす
+        Some(None) if !null_clears => Ok(None),
+        value => Ok(value),
+    }
+}
+
+/// `Option<Option<T>>` 用: キーの省略は `None`、`null` は `Some(None)` にする
+/// （serde の既定ではどちらも `None` になる）。`#[serde(default)]` と併用する。
+fn explicit_null<'de, D, T>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub(crate) struct McpNodeTemplatizeRequest {
+    #[schemars(description = "Node ID from `toc` output (e.g. '2-3'). UUID also accepted.")]
+    pub node_id: String,
+    #[schemars(
+        description = "Placeholder hint describing what to write (e.g. 'list the deploy steps')"
+    )]
+    pub placeholder: String,
+    #[schemars(
+        description = "Templatize every leaf in the subtree instead of the node itself (default: false)"
+    )]
+    #[serde(default)]
+    pub recursive: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, Default)]
+pub(crate) struct McpResetProgressRequest {
+    #[schemars(
+        description = "Only reset items under this node from `toc` output (e.g. '2'). Omit to reset the whole book."
+    )]
+    pub node_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub(crate) struct McpRetypeSubtreeRequest {
+    #[schemars(
+        description = "Root of the subtree to normalize, from `toc` output (e.g. '2'). The node itself is included."
+    )]
+    pub node_id: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub(crate) struct McpSeedPlaceholdersRequest {
+    #[schemars(
+        description = "Placeholder hint to give every bare content leaf (e.g. 'Describe the step')"
+    )]
+    pub hint: String,
+    #[schemars(
+        description = "Only seed under this node, from `toc` output (e.g. '2'). Omit for the whole book."
+    )]
+    pub node_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub(crate) struct McpNodeDependRequest {
+    #[schemars(description = "ID of the blocked node from `toc` output (e.g. '2-3')")]
+    pub node_id: String,
+    #[schemars(description = "ID of the node that must be done first (e.g. '2-1')")]
+    pub depends_on: String,
+    #[schemars(description = "Remove the dependency instead of adding it (default: false)")]
+    #[serde(default)]
+    pub remove: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub(crate) struct McpNodeLinkUrlRequest {
+    #[schemars(description = "ID of the node from `toc` output (e.g. '2-3')")]
+    pub node_id: String,
+    #[schemars(
+        description = "URL with a scheme (e.g. 'https://grafana.example/d/api'). With `remove: true`, the URL or label of the link to drop."
+    )]
+    pub url: String,
+    #[schemars(
+        description = "Link text shown in exports (default: the URL). Re-adding a linked URL with a new label relabels it."
+    )]
+    #[serde(default)]
+    pub label: Option<String>,
+    #[schemars(description = "Remove the link instead of adding it (default: false)")]
+    #[serde(default)]
+    pub remove: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub(crate) struct McpAssignRequest {
+    #[schemars(
+        description = "Node IDs from `toc` output (e.g. ['2-1', '2-3']). UUIDs also accepted."
+    )]
+    pub node_ids: Vec<String>,
+    #[schemars(description = "Owner name to assign. Empty string clears the owner.")]
+    pub owner: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub(crate) struct McpNodeMoveRequest {
+    #[schemars(description = "Node ID from `toc` output (e.g. '2-3'). UUID also accepted.")]
+    pub node_id: String,
+    #[schemars(
+        description = "Action: 'move' to relocate, 'remove' to delete (with descendants), 'to_top' / 'to_bottom' to make it the first / last top-level node (no new_parent or position)"
+    )]
+    pub action: String,
+    #[schemars(
+        description = "New parent ID from `toc` output, or a '/'-separated title path from the top level (e.g. 'Design/API'); null for root. Required for 'move' action."
+    )]
+    pub new_parent: Option<String>,
+    #[schemars(
+        description = "Position among siblings: \"first\", \"last\" or a 0-based index (0 is the first; an index past the end appends). Default: last."
+    )]
+    pub position: Option<PositionArg>,
+    #[schemars(
+        description = "For 'remove': delete even if the node is, or contains, a protected node (default: false)"
+    )]
+    #[serde(default)]
+    pub override_protection: bool,
+    #[schemars(
+        description = "Reply with just the moved node's hierarchical ID (or 'ok' for 'remove') instead of the full confirmation, to save context in bulk edits (default: false). Warnings are still reported."
+    )]
+    #[serde(default)]
+    pub quiet: bool,
+    #[schemars(
+        description = "Proceed even though the call creates or removes more nodes than the server's per-call `mutation_budget` (default 100). Without it such a call changes nothing and replies with a preview."
+    )]
+    #[serde(default)]
+    pub confirm: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub(crate) struct McpNodeGetRequest {
+    #[schemars(description = "Node ID from `toc` output (e.g. '2-3'). UUID also accepted.")]
+    pub node_id: String,
+    #[schemars(
+        description = "Return an editable fill-in stub (title, body, placeholder as `hint: ____`) instead of the full detail (default: false)"
+    )]
+    pub stub: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub(crate) struct McpStatsRequest {
+    #[schemars(description = "Section ID from `toc` output (e.g. '2'). Omit for the whole book.")]
+    pub subtree_root: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, Default)]
+pub(crate) struct McpPlaceholdersRequest {
+    #[schemars(description = "Section ID from `toc` output (e.g. '2'). Omit for the whole book.")]
+    pub subtree_root: Option<String>,
+    #[schemars(
+        description = "Output format: 'list' (default, grouped by section) or 'markdown-table' (for pasting into docs)"
+    )]
+    pub format: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, Default)]
+pub(crate) struct McpEjectPromptsRequest {
+    #[schemars(description = "Section ID from `toc` output (e.g. '2'). Omit for the whole book.")]
+    pub subtree_root: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, Default)]
+pub(crate) struct McpTreeJsonRequest {
+    #[schemars(
+        description = "Section ID from `toc` output (e.g. '2') to return only its children. Omit for the whole book."
+    )]
+    pub subtree_root: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub(crate) struct McpSearchShelfRequest {
+    #[schemars(
+        description = "Text to find (case-insensitive) in node titles, bodies, placeholders, and notes"
+    )]
+    pub query: String,
+    #[schemars(description = "Maximum matches across all books (default 50)")]
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub(crate) struct McpTocRequest {
+    #[schemars(description = "Section ID from `toc` output (e.g. '2'). Omit to show entire book.")]
+    pub subtree_root: Option<String>,
+    #[schemars(
+        description = "Filter by properties (e.g. {\"inject\": \"true\"}). Only matching nodes shown."
+    )]
+    pub filter: Option<HashMap<String, String>>,
+    #[schemars(
+        description = "Numbering style: 'dash' (default, 2-3), 'dot' (2.3), or 'legal' (2.3.). Any style is accepted as a node ID."
+    )]
+    pub numbering: Option<String>,
+    #[schemars(description = "Append each node's owner as @name (default: false)")]
+    #[serde(default)]
+    pub show_owners: bool,
+    #[schemars(
+        description = "List only section nodes, skipping content items, for a structural outline of a large book (default: false). Numbers stay the same as in the full listing."
+    )]
+    pub sections_only: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub(crate) struct McpEjectRequest {
+    #[schemars(
+        description = "Output directory path (default: the `exports/` directory under the shelf, created on first export)"
+    )]
+    pub output_dir: Option<String>,
+    #[schemars(description = "Output filename (default: '<book-title>.md')")]
+    pub filename: Option<String>,
+    #[schemars(
+        description = "Full absolute file path to write, e.g. '/shelf/exports/ops/runbook.md'. Missing directories are created. Must stay inside the shelf's `exports/` directory or the configured `export_root`, and never in the shelf directory itself (book and config files). Replaces `output_dir` + `filename`."
+    )]
+    pub output_path: Option<String>,
+    #[schemars(description = "Include placeholder hints as fill-in fields (default: true)")]
+    pub include_placeholders: Option<bool>,
+    #[schemars(
+        description = "Output format: 'markdown' (default), 'json' (tree-structured), 'html' (self-contained fill-in form: checkboxes, text fields for placeholders, save/print buttons) or 'backup' (the whole book exactly as stored, IDs and metadata included, for restoring with `import`)"
+    )]
+    pub format: Option<String>,
+    #[schemars(
+        description = "Section ID from `toc` output (e.g. '2'). Omit to export entire book."
+    )]
+    pub subtree_root: Option<String>,
+    #[schemars(
+        description = "Return the rendered content in the tool result instead of writing a file (default: false)"
+    )]
+    #[serde(default)]
+    pub inline: bool,
+    #[schemars(
+        description = "Encoding for inline content: 'text' (default) or 'base64' for clients that mishandle multi-line text"
+    )]
+    pub encoding: Option<String>,
+    #[schemars(
+        description = "Only export nodes created at or after this time (millis or ISO 8601), plus their ancestors for context"
+    )]
+    pub created_after: Option<String>,
+    #[schemars(
+        description = "Render private node notes as italic blocks in Markdown output (default: false). JSON always includes notes."
+    )]
+    #[serde(default)]
+    pub include_notes: bool,
+    #[schemars(description = "Append each item's effort estimate, e.g. (~30m) (default: false)")]
+    #[serde(default)]
+    pub include_estimates: bool,
+    #[schemars(
+        description = "Only export items assigned to this owner (plus their parent sections)"
+    )]
+    pub filter_owner: Option<String>,
+    #[schemars(
+        description = "When exporting a nested subtree, start with a 'Context: A → B → C' breadcrumb of its ancestors (JSON: `ancestors` field). Omitted for top-level subtrees (default: false)"
+    )]
+    #[serde(default)]
+    pub include_ancestors: bool,
+    #[schemars(
+        description = "Markdown style: 'checkbox' (default, task list) or 'form' (each item a bold label with a 'placeholder: _______' fill line, no checkboxes — for data-capture runbooks)"
+    )]
+    pub style: Option<String>,
+    #[schemars(
+        description = "Soft-wrap Markdown body lines at this many characters (titles, headings, code fences and long URLs are never broken). Omit to keep lines as written. Also accepted as `wrap`."
+    )]
+    #[serde(alias = "wrap")]
+    pub wrap_width: Option<usize>,
+    #[schemars(
+        description = "Word placed after each open checkbox in the Markdown task list, e.g. 'TODO' or a localized keyword ('- [ ] TODO Deploy'). Omit for a plain '[ ]'."
+    )]
+    pub todo_word: Option<String>,
+    #[schemars(
+        description = "Word placed after each checked-off checkbox, e.g. 'DONE' ('- [x] DONE Deploy'). Omit for a plain '[x]'."
+    )]
+    pub done_word: Option<String>,
+    #[schemars(
+        description = "Instead of a checklist, output only the nodes' `command`s (of `subtree_root`, or the whole book) as a copy-pasteable shell script, each preceded by a `# 2-3 Title` comment. Default filename ends in `.sh`. Nothing is executed (default: false)."
+    )]
+    #[serde(default)]
+    pub extract_commands: bool,
+    #[schemars(
+        description = "Put a `---` horizontal rule between consecutive top-level sections of a full-book Markdown export (default: false)"
+    )]
+    pub section_separators: Option<bool>,
+    #[schemars(
+        description = "Append `⚠ needs detail` to checkbox items that have a placeholder but no body, so a bare fill-in line is not mistaken for a finished step. The same nodes are reported by `lint` (default: true)"
+    )]
+    pub mark_incomplete: Option<bool>,
+    #[schemars(
+        description = "End Markdown body lines that look like checklist syntax (list items, `> quotes`, `_notes_`) with an HTML comment `<!--body-->`, so `import` of the file keeps them in the body instead of turning them into nodes. Turn on for exports you plan to edit and import back (default: false)"
+    )]
+    pub mark_body_lines: Option<bool>,
+    #[schemars(
+        description = "End the export with a footer naming the source book's title and ID, the node count and the generation time: an HTML comment in Markdown/HTML, a top-level `_comment` key beside the tree in JSON (import ignores it) (default: false)"
+    )]
+    pub footer: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub(crate) struct McpRecentRequest {
+    #[schemars(description = "Maximum number of nodes to list (default: 20)")]
+    pub limit: Option<usize>,
+    #[schemars(description = "Only list nodes created within the last N minutes")]
+    pub within_minutes: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub(crate) struct McpDuplicatesRequest {
+    #[schemars(
+        description = "Also group near-identical titles within a small edit distance (default: false)"
+    )]
+    #[serde(default)]
+    pub similarity: bool,
+    #[schemars(description = "Maximum edit distance for `similarity` mode (default: 2)")]
+    pub max_distance: Option<usize>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub(crate) struct McpLintRequest {
+    #[schemars(description = "Report leaves deeper than this many levels (default: 5)")]
+    pub deep_threshold: Option<u8>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub(crate) struct McpImportRequest {
+    #[schemars(
+        description = "Path to a JSON file exported by `checklist` (format: json), a `.md` checklist exported by it (checkbox style), or a `checklist` backup (format: backup), which is restored verbatim with every ID and field. Give either this or `source_book`."
+    )]
+    pub file_path: Option<String>,
+    #[schemars(
+        description = "With a backup `file_path`: restore into this new slug instead of replacing the selected book. The slug must not exist yet."
+    )]
+    pub slug: Option<String>,
+    #[schemars(
+        description = "Copy another shelf book (number from `shelf` or slug) instead of reading a file. Node IDs are regenerated and the source book is left untouched. Must differ from the selected book."
+    )]
+    pub source_book: Option<String>,
+    #[schemars(
+        description = "Preview the imported tree as a toc without replacing the current book (default: false)"
+    )]
+    pub dry_run: Option<bool>,
+    #[schemars(
+        description = "Import only this top-level node of the export, by 1-based position (e.g. '2') or title"
+    )]
+    pub subtree: Option<String>,
+    #[schemars(
+        description = "With `subtree`: merge it under this node of the current book ('root' for top level) instead of replacing the book. ID from `toc` output (e.g. '2')."
+    )]
+    pub parent: Option<String>,
+    #[schemars(
+        description = "When the import is deeper than max_depth: 'error' (default) refuses and names the deepest path, 'flatten' folds deeper nodes into their parent's body as bullets, 'raise' lifts max_depth to fit (up to 16)"
+    )]
+    pub depth_strategy: Option<String>,
+    #[schemars(
+        description = "With a JSON `file_path`: reject keys the export format does not know (e.g. a mistyped \"titel\") instead of silently ignoring them (default: false)"
+    )]
+    pub strict: Option<bool>,
+    #[schemars(
+        description = "With `parent` (merge): 'off' (default) adds everything, 'skip' reuses existing siblings with the same title (case/whitespace-insensitive) and merges their children, 'update' also overwrites the existing body/placeholder"
     )]
-    pub parent: Option<String>,
-    #[schemars(description = "Node title (required)")]
-    pub title: String,
-    #[schemars(description = "Node type: section or content")]
-    pub node_type: String,
-    #[schemars(description = "Optional markdown body content")]
-    pub body: Option<String>,
+    pub dedupe: Option<String>,
     #[schemars(
-        description = "Optional placeholder hint for checklist export (e.g. 'write test cases here')"
+        description = "With `parent` (merge): match incoming nodes to existing ones by their exported `id`. 'off' (default) ignores IDs and creates everything fresh, 'error' refuses and lists the colliding IDs, 'skip' keeps the existing node and merges the incoming children under it, 'replace' overwrites the existing node's fields (it stays where it is) and merges the children under it"
     )]
-    pub placeholder: Option<String>,
-    #[schemars(description = "Position among siblings (0-based). Omit to append at end.")]
-    pub position: Option<usize>,
+    pub on_id_conflict: Option<String>,
     #[schemars(
-        description = "Optional key-value properties (e.g. {\"inject\": \"true\", \"scope\": \"rust\"})"
+        description = "For a subtree export of this same book (its `book_id` matches and the `subtree_uuid` node still exists): replace that subtree's children in place instead of replacing the whole book (default: true when the metadata matches; false forces the old behaviour)"
     )]
-    pub properties: Option<HashMap<String, String>>,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
-pub(crate) struct McpNodeUpdateRequest {
-    #[schemars(description = "Node ID from `toc` output (e.g. '2-3'). UUID also accepted.")]
-    pub node_id: String,
-    #[schemars(description = "New title (omit to keep current)")]
-    pub title: Option<String>,
-    #[schemars(description = "New body (null to clear, omit to keep current)")]
-    pub body: Option<Option<String>>,
-    #[schemars(description = "New node type: section or content")]
-    pub node_type: Option<String>,
-    #[schemars(description = "New placeholder hint (null to clear)")]
-    pub placeholder: Option<Option<String>>,
-    #[schemars(description = "Replace all properties (omit to keep current). Pass {} to clear.")]
-    pub properties: Option<HashMap<String, String>>,
+    pub in_place: Option<bool>,
     #[schemars(
-        description = "Node status: 'active' or 'draft'. Draft nodes are excluded from select_book inject."
+        description = "Proceed even though the call creates or removes more nodes than the server's per-call `mutation_budget` (default 100). Without it such a call changes nothing and replies with a preview."
     )]
-    pub status: Option<String>,
+    #[serde(default)]
+    pub confirm: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
-pub(crate) struct McpNodeMoveRequest {
-    #[schemars(description = "Node ID from `toc` output (e.g. '2-3'). UUID also accepted.")]
-    pub node_id: String,
-    #[schemars(description = "Action: 'move' to relocate, 'remove' to delete (with descendants)")]
-    pub action: String,
+pub(crate) struct McpInitRequest {
+    #[schemars(description = "Book title")]
+    pub title: String,
     #[schemars(
-        description = "New parent ID from `toc` output (null for root). Required for 'move' action."
+        description = "Book slug for filename (e.g. 'rust', 'development'). Alphanumeric, hyphens, underscores only."
     )]
-    pub new_parent: Option<String>,
-    #[schemars(description = "Position among new siblings (0-based). Default: append at end.")]
-    pub position: Option<usize>,
+    pub slug: String,
+    #[schemars(
+        description = "Maximum tree depth, 1-16 (default: server config `init.max_depth`, normally 4; recommended: 3-4)"
+    )]
+    pub max_depth: Option<u8>,
+    #[schemars(
+        description = "Top-level section titles to create in the new book (default: server config `init.skeleton`, normally none)"
+    )]
+    pub skeleton: Option<Vec<String>>,
+    #[schemars(
+        description = "Select the new book after creating it (default: server config `init.auto_select`, normally true)"
+    )]
+    pub auto_select: Option<bool>,
+    #[schemars(
+        description = "Topic tags for filtering the shelf (e.g. [\"ops\", \"onboarding\"])"
+    )]
+    pub tags: Option<Vec<String>>,
+    #[schemars(
+        description = "Categories that group the book in `shelf_categories` (e.g. [\"Operations\"])"
+    )]
+    pub categories: Option<Vec<String>>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
-pub(crate) struct McpTocRequest {
-    #[schemars(description = "Section ID from `toc` output (e.g. '2'). Omit to show entire book.")]
-    pub subtree_root: Option<String>,
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, Default)]
+pub(crate) struct McpBookConfigRequest {
     #[schemars(
-        description = "Filter by properties (e.g. {\"inject\": \"true\"}). Only matching nodes shown."
+        description = "Default fill-in hint for content nodes without their own placeholder (applied at export only). Pass \"\" to clear. Omit to keep current."
     )]
-    pub filter: Option<HashMap<String, String>>,
+    pub default_placeholder: Option<String>,
+    #[schemars(
+        description = "Maximum number of nodes in the book; adding beyond it fails. Pass 0 to remove the limit. Omit to keep current."
+    )]
+    pub max_nodes: Option<u32>,
+    #[schemars(
+        description = "Raise the book's maximum tree depth (up to 16). Lowering is refused; `stats` shows how deep the book already goes. Omit to keep current."
+    )]
+    pub max_depth: Option<u8>,
+    #[schemars(
+        description = "Replace the book's topic tags (used by `shelf` `tag` filtering). Pass [] to clear. Omit to keep current."
+    )]
+    pub tags: Option<Vec<String>>,
+    #[schemars(
+        description = "Replace the book's categories (used by `shelf_categories` and `shelf` `category` filtering). Pass [] to clear. Omit to keep current."
+    )]
+    pub categories: Option<Vec<String>>,
+    #[schemars(
+        description = "Replace the book's export defaults for `checklist` (used for parameters a request leaves out, before the server config). Pass {} to clear. Omit to keep current."
+    )]
+    pub eject_defaults: Option<McpEjectDefaults>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
-pub(crate) struct McpEjectRequest {
-    #[schemars(description = "Output directory path (default: current directory)")]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, Default)]
+pub(crate) struct McpEjectDefaults {
+    #[schemars(description = "Directory exports are written into")]
     pub output_dir: Option<String>,
-    #[schemars(description = "Output filename (default: '<book-title>.md')")]
-    pub filename: Option<String>,
-    #[schemars(description = "Include placeholder hints as fill-in fields (default: true)")]
-    pub include_placeholders: Option<bool>,
-    #[schemars(description = "Output format: 'markdown' (default) or 'json' (tree-structured)")]
+    #[schemars(description = "Export format: 'markdown', 'json' or 'html'")]
     pub format: Option<String>,
+    #[schemars(description = "Render placeholder hints as fill-in fields")]
+    pub include_placeholders: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, Default)]
+pub(crate) struct McpShelfRequest {
     #[schemars(
-        description = "Section ID from `toc` output (e.g. '2'). Omit to export entire book."
+        description = "List the selected book first (default: false). Numbers follow the displayed order, so `select_book` by number stays consistent."
     )]
-    pub subtree_root: Option<String>,
+    pub pin_selected: Option<bool>,
+    #[schemars(
+        description = "Only list books carrying this tag (case-insensitive). Numbers follow the filtered list. See `shelf_tags` for the tags in use."
+    )]
+    pub tag: Option<String>,
+    #[schemars(
+        description = "Only list books in this category (case-insensitive). Numbers follow the filtered list. See `shelf_categories` for the categories in use."
+    )]
+    pub category: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
-pub(crate) struct McpImportRequest {
-    #[schemars(description = "Path to JSON file exported by eject (format: json)")]
-    pub file_path: String,
-}
+pub(crate) struct McpShelfTagsRequest {}
 
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
-pub(crate) struct McpInitRequest {
-    #[schemars(description = "Book title")]
-    pub title: String,
-    #[schemars(
-        description = "Book slug for filename (e.g. 'rust', 'development'). Alphanumeric, hyphens, underscores only."
-    )]
-    pub slug: String,
-    #[schemars(description = "Maximum tree depth (default: 4, recommended: 3-4)")]
-    pub max_depth: Option<u8>,
-}
+pub(crate) struct McpShelfCategoriesRequest {}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub(crate) struct McpStatusRequest {}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub(crate) struct McpVerifyRoundtripRequest {}
 
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
-pub(crate) struct McpShelfRequest {}
+pub(crate) struct McpHelpRequest {}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, Default)]
+pub(crate) struct McpBookInfoRequest {
+    #[schemars(description = "Book number from `shelf` or slug. Omit for the selected book.")]
+    pub book: Option<String>,
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub(crate) struct McpGenRoutingRequest {}
@@ -259,6 +1270,26 @@ pub(crate) struct McpSnapshotCreateRequest {
     pub label: Option<String>,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, Default)]
+pub(crate) struct McpChangesRequest {
+    #[schemars(
+        description = "Reference to compare against: a snapshot label, a snapshot timestamp (millis), or an ISO 8601 time (uses the newest snapshot at or before it). Omit to compare against the book as it was when this session last ran `select_book`."
+    )]
+    pub since: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub(crate) struct McpCompareWithFileRequest {
+    #[schemars(
+        description = "Path to a JSON export (from `checklist` format: json) or a checkbox-style `.md` checklist, usually one edited outside the server"
+    )]
+    pub file_path: String,
+    #[schemars(
+        description = "Reject keys the JSON export format does not know instead of ignoring them (default: false)"
+    )]
+    pub strict: Option<bool>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub(crate) struct McpSnapshotDiffRequest {
     #[schemars(
@@ -298,7 +1329,7 @@ pub(crate) struct McpSnapshotDumpRequest {
     pub timestamp: String,
     #[schemars(description = "Output directory path")]
     pub output_dir: String,
-    #[schemars(description = "Output format: 'markdown' (default) or 'json'")]
+    #[schemars(description = "Output format: 'markdown' (default), 'json' or 'html'")]
     pub format: Option<String>,
     #[schemars(description = "Overwrite existing subdirectory if present (default: false)")]
     pub overwrite: Option<bool>,
@@ -310,7 +1341,7 @@ pub(crate) struct McpSnapshotDumpAllRequest {
         description = "Output directory path (subdirs 'v01_<millis>' .. 'vNN_<millis>' will be created inside; 01 = oldest)"
     )]
     pub output_dir: String,
-    #[schemars(description = "Output format: 'markdown' (default) or 'json'")]
+    #[schemars(description = "Output format: 'markdown' (default), 'json' or 'html'")]
     pub format: Option<String>,
     #[schemars(description = "Overwrite existing subdirectories if present (default: false)")]
     pub overwrite: Option<bool>,
@@ -338,12 +1369,63 @@ pub(crate) struct McpBookHistoryRequest {
 pub(crate) struct McpDumpRequest {
     #[schemars(description = "Output directory path")]
     pub output_dir: String,
-    #[schemars(description = "Output format: 'markdown' (default) or 'json'")]
+    #[schemars(description = "Output format: 'markdown' (default), 'json' or 'html'")]
     pub format: Option<String>,
     #[schemars(description = "Output filename (default: '<book-title>.<ext>')")]
     pub filename: Option<String>,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub(crate) struct McpShelfExportRequest {
+    #[schemars(description = "Output directory path")]
+    pub output_dir: String,
+    #[schemars(
+        description = "Output format: 'markdown' (default; one document, each book as an H1), 'json' (array of trees) or 'html' (one fill-in form)"
+    )]
+    pub format: Option<String>,
+    #[schemars(description = "Output filename (default: 'shelf.<ext>')")]
+    pub filename: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub(crate) struct McpShelfHandbookRequest {
+    #[schemars(description = "Output directory path (default: the shelf directory)")]
+    pub output_dir: Option<String>,
+    #[schemars(description = "Output filename (default: 'handbook.md')")]
+    pub filename: Option<String>,
+    #[schemars(description = "Document title, the only H1 (default: 'Handbook')")]
+    pub title: Option<String>,
+    #[schemars(description = "Include placeholder fill-in lines (default: true)")]
+    pub include_placeholders: Option<bool>,
+    #[schemars(
+        description = "Return the Markdown in the response instead of writing a file (default: false)"
+    )]
+    #[serde(default)]
+    pub inline: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub(crate) struct McpSplitBookRequest {
+    #[schemars(
+        description = "Slug for each new book, with `{n}` (1-based number of the section) and/or `{title}` (the section title as a slug), e.g. 'proj-{n}' or 'ops-{title}'"
+    )]
+    pub slug_template: String,
+    #[schemars(
+        description = "Also remove the split sections from the original book, turning the copy into a move (default: false). Refused if a section contains a protected node."
+    )]
+    #[serde(default)]
+    pub remove_sections: bool,
+    #[schemars(
+        description = "When a generated slug is already taken: 'error' (default) creates nothing and lists the clashes, 'suffix' appends -2, -3, … until the slug is free"
+    )]
+    pub on_collision: Option<String>,
+    #[schemars(
+        description = "Proceed even though the call creates or removes more nodes than the server's per-call `mutation_budget` (default 100). Without it such a call changes nothing and replies with a preview."
+    )]
+    #[serde(default)]
+    pub confirm: bool,
+}
+
 // =============================================================================
 // Batch operation request types
 // =============================================================================
@@ -354,8 +1436,18 @@ pub(crate) struct McpBatchMoveItem {
     pub node_id: String,
     #[schemars(description = "New parent UUID (null for root)")]
     pub new_parent: Option<String>,
-    #[schemars(description = "Position among new siblings (0-based). Default: append at end.")]
-    pub position: Option<usize>,
+    #[schemars(
+        description = "Position among siblings: \"first\", \"last\" or a 0-based index (0 is the first; an index past the end appends). Default: last."
+    )]
+    pub position: Option<PositionArg>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub(crate) struct McpReorderBookRequest {
+    #[schemars(
+        description = "Every node's current ID from `toc` (e.g. '2-3'), in the desired outline order: a parent before its children, each subtree kept together. Parents stay the same; only sibling order changes."
+    )]
+    pub order: Vec<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
@@ -432,6 +1524,140 @@ mod tests {
         assert!(parse_node_type("unknown").is_err());
     }
 
+    #[test]
+    fn parse_placeholder_formats() {
+        assert_eq!(
+            parse_placeholder_format(None).unwrap(),
+            PlaceholderFormat::List
+        );
+        assert_eq!(
+            parse_placeholder_format(Some("markdown-table")).unwrap(),
+            PlaceholderFormat::MarkdownTable
+        );
+        assert!(parse_placeholder_format(Some("csv")).is_err());
+    }
+
+    #[test]
+    fn parse_numbering_styles() {
+        assert_eq!(parse_numbering(None).unwrap(), Numbering::Dash);
+        assert_eq!(parse_numbering(Some("dot")).unwrap(), Numbering::Dot);
+        assert_eq!(parse_numbering(Some("legal")).unwrap(), Numbering::Legal);
+        assert!(parse_numbering(Some("roman")).is_err());
+    }
+
+    /// 各層（リクエスト・Book・全体設定）の有無のすべての組み合わせ。
+    fn layer_combinations() -> impl Iterator<Item = [bool; 3]> {
+        (0..8u8).map(|bits| [bits & 1 != 0, bits & 2 != 0, bits & 4 != 0])
+    }
+
+    fn resolve_layers(
+        set: [bool; 3],
+        with_book: bool,
+        layer: impl Fn(usize) -> EjectDefaults,
+    ) -> EjectSettings {
+        let pick = |i: usize| {
+            if set[i] {
+                layer(i)
+            } else {
+                EjectDefaults::default()
+            }
+        };
+        let book = pick(1);
+        let book = with_book.then_some(&book);
+        resolve_eject_settings(&pick(0), book, &pick(2), Path::new("exports")).unwrap()
+    }
+
+    #[test]
+    fn eject_settings_prefer_request_then_book_then_config_then_builtin() {
+        let dirs = ["req-dir", "book-dir", "config-dir"];
+        let formats = ["json", "markdown", "json"];
+        let placeholders = [false, true, false];
+        for with_book in [true, false] {
+            for set in layer_combinations() {
+                // Book の層は Book 自体に既定値がなければ飛ばされる
+                let winner = (0..3).find(|&i| set[i] && (i != 1 || with_book));
+
+                let got = resolve_layers(set, with_book, |i| EjectDefaults {
+                    output_dir: Some(dirs[i].into()),
+                    ..EjectDefaults::default()
+                });
+                let want = winner.map_or("exports", |i| dirs[i]);
+                assert_eq!(got.output_dir, PathBuf::from(want), "{set:?} {with_book}");
+
+                let got = resolve_layers(set, with_book, |i| EjectDefaults {
+                    format: Some(formats[i].into()),
+                    ..EjectDefaults::default()
+                });
+                let want = winner.map_or("markdown", |i| formats[i]);
+                assert_eq!(got.format, parse_eject_format(want).unwrap());
+
+                let got = resolve_layers(set, with_book, |i| EjectDefaults {
+                    include_placeholders: Some(placeholders[i]),
+                    ..EjectDefaults::default()
+                });
+                let want = winner.is_none_or(|i| placeholders[i]);
+                assert_eq!(got.include_placeholders, want, "{set:?} {with_book}");
+            }
+        }
+    }
+
+    #[test]
+    fn eject_settings_resolve_each_field_independently() {
+        let request = EjectDefaults {
+            format: Some("json".into()),
+            ..EjectDefaults::default()
+        };
+        let book = EjectDefaults {
+            output_dir: Some("exports".into()),
+            format: Some("markdown".into()),
+            ..EjectDefaults::default()
+        };
+        let config = EjectDefaults {
+            output_dir: Some("shared".into()),
+            include_placeholders: Some(false),
+            ..EjectDefaults::default()
+        };
+        assert_eq!(
+            resolve_eject_settings(&request, Some(&book), &config, Path::new("exports")).unwrap(),
+            EjectSettings {
+                output_dir: PathBuf::from("exports"),
+                format: EjectFormat::Json,
+                include_placeholders: false,
+            }
+        );
+    }
+
+    #[test]
+    fn eject_settings_reject_unknown_winning_format_only() {
+        let bad = EjectDefaults {
+            format: Some("pdf".into()),
+            ..EjectDefaults::default()
+        };
+        let empty = EjectDefaults::default();
+        assert!(resolve_eject_settings(&empty, Some(&bad), &empty, Path::new("exports")).is_err());
+        // 上の層が決めていれば下の層の値は読まない
+        let json = EjectDefaults {
+            format: Some("json".into()),
+            ..EjectDefaults::default()
+        };
+        let got = resolve_eject_settings(&json, Some(&bad), &bad, Path::new("exports")).unwrap();
+        assert_eq!(got.format, EjectFormat::Json);
+    }
+
+    #[test]
+    fn node_create_request_with_children() {
+        let req: McpNodeCreateRequest = serde_json::from_str(
+            r#"{"title": "Rollback", "node_type": "section",
+                "children": [{"title": "Stop traffic"}, {"title": "Verify", "children": []}]}"#,
+        )
+        .unwrap();
+        let children = req.children.unwrap();
+        assert_eq!(children[0].title, "Stop traffic");
+        assert!(children[0].node_type.is_none());
+        assert!(children[0].children.is_none());
+        assert!(children[1].children.is_some());
+    }
+
     #[test]
     fn init_request_with_slug() {
         let req: McpInitRequest =
@@ -439,6 +1665,8 @@ mod tests {
         assert_eq!(req.title, "Test");
         assert_eq!(req.slug, "test");
         assert!(req.max_depth.is_none());
+        assert!(req.skeleton.is_none());
+        assert!(req.auto_select.is_none());
     }
 
     #[test]
@@ -458,6 +1686,15 @@ mod tests {
         assert!(validate_slug("日本語").is_err());
     }
 
+    #[test]
+    fn book_config_request_empty_shows_settings() {
+        let req: McpBookConfigRequest = serde_json::from_str("{}").unwrap();
+        assert!(req.default_placeholder.is_none());
+        let req: McpBookConfigRequest =
+            serde_json::from_str(r#"{"default_placeholder": "notes"}"#).unwrap();
+        assert_eq!(req.default_placeholder.as_deref(), Some("notes"));
+    }
+
     #[test]
     fn shelf_request_empty() {
         let _req: McpShelfRequest = serde_json::from_str("{}").unwrap();
@@ -497,6 +1734,24 @@ mod tests {
         assert!(req.new_parent.is_none());
     }
 
+    #[test]
+    fn node_templatize_request_defaults_to_non_recursive() {
+        let req: McpNodeTemplatizeRequest =
+            serde_json::from_str(r#"{"node_id": "2-3", "placeholder": "describe it"}"#).unwrap();
+        assert_eq!(req.node_id, "2-3");
+        assert_eq!(req.placeholder, "describe it");
+        assert!(!req.recursive);
+    }
+
+    #[test]
+    fn node_templatize_request_recursive() {
+        let req: McpNodeTemplatizeRequest = serde_json::from_str(
+            r#"{"node_id": "2", "placeholder": "fill in", "recursive": true}"#,
+        )
+        .unwrap();
+        assert!(req.recursive);
+    }
+
     #[test]
     fn eject_request_defaults() {
         let req: McpEjectRequest = serde_json::from_str("{}").unwrap();
@@ -505,13 +1760,72 @@ mod tests {
         assert!(req.include_placeholders.is_none());
         assert!(req.format.is_none());
         assert!(req.subtree_root.is_none());
+        assert!(!req.inline);
+        assert!(req.encoding.is_none());
+        assert!(req.wrap_width.is_none());
+    }
+
+    #[test]
+    fn eject_request_accepts_wrap_alias() {
+        let req: McpEjectRequest = serde_json::from_str(r#"{"wrap": 72}"#).unwrap();
+        assert_eq!(req.wrap_width, Some(72));
+    }
+
+    #[test]
+    fn eject_request_inline_base64() {
+        let req: McpEjectRequest =
+            serde_json::from_str(r#"{"inline": true, "encoding": "base64"}"#).unwrap();
+        assert!(req.inline);
+        assert_eq!(req.encoding.as_deref(), Some("base64"));
+    }
+
+    #[test]
+    fn recent_request_defaults() {
+        let req: McpRecentRequest = serde_json::from_str("{}").unwrap();
+        assert!(req.limit.is_none());
+        assert!(req.within_minutes.is_none());
+    }
+
+    #[test]
+    fn duplicates_request_defaults_to_exact() {
+        let req: McpDuplicatesRequest = serde_json::from_str("{}").unwrap();
+        assert!(!req.similarity);
+        assert!(req.max_distance.is_none());
+    }
+
+    #[test]
+    fn parse_timestamp_arg_accepts_millis_and_iso() {
+        assert_eq!(
+            parse_timestamp_arg("1700000000000", "created_after")
+                .unwrap()
+                .as_millis(),
+            1_700_000_000_000
+        );
+        let iso = Timestamp::from_millis(1_700_000_000_000).to_iso8601();
+        assert_eq!(
+            parse_timestamp_arg(&iso, "created_after")
+                .unwrap()
+                .as_millis(),
+            1_700_000_000_000
+        );
+        assert!(parse_timestamp_arg("yesterday", "created_after").is_err());
     }
 
     #[test]
     fn import_request_parse() {
         let req: McpImportRequest =
             serde_json::from_str(r#"{"file_path": "/tmp/book.json"}"#).unwrap();
-        assert_eq!(req.file_path, "/tmp/book.json");
+        assert_eq!(req.file_path.as_deref(), Some("/tmp/book.json"));
+        assert!(req.source_book.is_none());
+        assert!(req.dry_run.is_none());
+
+        let req: McpImportRequest = serde_json::from_str(r#"{"source_book": "2"}"#).unwrap();
+        assert!(req.file_path.is_none());
+        assert_eq!(req.source_book.as_deref(), Some("2"));
+
+        let req: McpImportRequest =
+            serde_json::from_str(r#"{"file_path": "/tmp/book.json", "dry_run": true}"#).unwrap();
+        assert_eq!(req.dry_run, Some(true));
     }
 
     // ---- sanitize_for_filename tests ----
@@ -579,6 +1893,75 @@ mod tests {
         assert_eq!(sanitize_for_filename("///"), "untitled");
     }
 
+    #[test]
+    fn normalize_title_trims_and_strips_one_pasted_marker() {
+        assert_eq!(normalize_title("  - Do thing  "), "Do thing");
+        assert_eq!(normalize_title("* Item"), "Item");
+        assert_eq!(normalize_title("1. Intro"), "Intro");
+        assert_eq!(normalize_title("12)  Wrap up "), "Wrap up");
+        assert_eq!(normalize_title("- - nested"), "- nested");
+        // 記号の後に空白がない・記号だけ・番号が長いものはタイトルの一部
+        assert_eq!(normalize_title("-1 offset"), "-1 offset");
+        assert_eq!(normalize_title("3.5 GHz tuning"), "3.5 GHz tuning");
+        assert_eq!(normalize_title(" - "), "-");
+        assert_eq!(normalize_title("2026. Review"), "2026. Review");
+        assert_eq!(normalize_title("日本語 "), "日本語");
+    }
+
+    #[test]
+    fn input_title_keeps_raw_title_when_normalization_is_off() {
+        assert_eq!(input_title("  - Do thing\\n2 ", true), "Do thing\n2");
+        assert_eq!(input_title("  - Do thing  ", false), "  - Do thing  ");
+    }
+
+    #[test]
+    fn output_path_stays_inside_allowed_roots() {
+        let base = std::env::temp_dir().join("outline-mcp-output-path-test");
+        let _ = std::fs::remove_dir_all(&base);
+        let shelf = base.join("shelf");
+        let exports = base.join("exports");
+        std::fs::create_dir_all(&shelf).unwrap();
+        std::fs::create_dir_all(&exports).unwrap();
+        let roots = [shelf.as_path(), exports.as_path()];
+        let real_shelf = shelf.canonicalize().unwrap();
+
+        // まだないディレクトリも許可されたルートの下なら受け付ける
+        let nested = shelf.join("ops/2026/runbook.md");
+        let (dir, name) = resolve_output_path(nested.to_str().unwrap(), &roots).unwrap();
+        assert_eq!(dir, real_shelf.join("ops/2026"));
+        assert_eq!(name, "runbook.md");
+        let second = exports.join("runbook.json");
+        assert!(resolve_output_path(second.to_str().unwrap(), &roots).is_ok());
+
+        let outside = base.join("elsewhere/runbook.md");
+        let err = resolve_output_path(outside.to_str().unwrap(), &roots).unwrap_err();
+        assert!(
+            err.message.contains("outside the allowed export roots"),
+            "{}",
+            err.message
+        );
+        let escape = format!("{}/../elsewhere/x.md", shelf.display());
+        assert!(resolve_output_path(&escape, &roots).is_err());
+        assert!(resolve_output_path("runbook.md", &roots).is_err());
+        assert!(resolve_output_path(&format!("{}/", shelf.display()), &roots).is_err());
+
+        #[cfg(unix)]
+        {
+            // ルート内のシンボリックリンクを経由した外への書き込みも拒否する
+            std::fs::create_dir_all(base.join("elsewhere")).unwrap();
+            std::os::unix::fs::symlink(base.join("elsewhere"), shelf.join("link")).unwrap();
+            let via_link = shelf.join("link/runbook.md");
+            assert!(resolve_output_path(via_link.to_str().unwrap(), &roots).is_err());
+            std::os::unix::fs::symlink(base.join("elsewhere/target.md"), shelf.join("file.md"))
+                .unwrap();
+            std::fs::write(base.join("elsewhere/target.md"), "").unwrap();
+            let file_link = shelf.join("file.md");
+            assert!(resolve_output_path(file_link.to_str().unwrap(), &roots).is_err());
+        }
+
+        let _ = std::fs::remove_dir_all(&base);
+    }
+
     #[test]
     fn gen_routing_request_empty() {
         let _req: McpGenRoutingRequest = serde_json::from_str("{}").unwrap();
@@ -615,6 +1998,15 @@ mod tests {
         assert!(req.filename.is_none());
     }
 
+    #[test]
+    fn shelf_export_request_parse_minimal() {
+        let req: McpShelfExportRequest =
+            serde_json::from_str(r#"{"output_dir": "/tmp/out"}"#).unwrap();
+        assert_eq!(req.output_dir, "/tmp/out");
+        assert!(req.format.is_none());
+        assert!(req.filename.is_none());
+    }
+
     #[test]
     fn dump_request_parse_full() {
         let req: McpDumpRequest = serde_json::from_str(
@@ -651,7 +2043,7 @@ mod tests {
             req.moves[0].new_parent.as_deref(),
             Some("00000000-0000-0000-0000-000000000002")
         );
-        assert_eq!(req.moves[0].position, Some(3));
+        assert_eq!(req.moves[0].position, Some(PositionArg::Index(3)));
     }
 
     #[test]
@@ -693,6 +2085,50 @@ mod tests {
         assert_eq!(req.updates[0].status.as_deref(), Some("draft"));
     }
 
+    #[test]
+    fn node_update_body_null_keeps_unless_null_clears_fields() {
+        let parse = |json: &str| -> McpNodeUpdateRequest { serde_json::from_str(json).unwrap() };
+        let omitted = parse(r#"{"node_id": "1"}"#);
+        let null = parse(r#"{"node_id": "1", "body": null, "placeholder": null}"#);
+        let text = parse(r#"{"node_id": "1", "body": "steps", "placeholder": "hint"}"#);
+        assert_eq!(null.fields.body, Some(None));
+
+        for null_clears in [false, true] {
+            assert_eq!(omitted.fields.body_update(null_clears).unwrap(), None);
+            assert_eq!(
+                omitted.fields.placeholder_update(null_clears).unwrap(),
+                None
+            );
+            assert_eq!(
+                text.fields.body_update(null_clears).unwrap(),
+                Some(Some("steps".into()))
+            );
+            assert_eq!(
+                text.fields.placeholder_update(null_clears).unwrap(),
+                Some(Some("hint".into()))
+            );
+        }
+        assert_eq!(null.fields.body_update(false).unwrap(), None);
+        assert_eq!(null.fields.placeholder_update(false).unwrap(), None);
+        assert_eq!(null.fields.body_update(true).unwrap(), Some(None));
+        assert_eq!(null.fields.placeholder_update(true).unwrap(), Some(None));
+    }
+
+    #[test]
+    fn node_update_clear_flags_clear_and_reject_a_new_value() {
+        let req: McpNodeUpdateRequest = serde_json::from_str(
+            r#"{"node_id": "1", "clear_body": true, "clear_placeholder": true}"#,
+        )
+        .unwrap();
+        assert_eq!(req.fields.body_update(false).unwrap(), Some(None));
+        assert_eq!(req.fields.placeholder_update(false).unwrap(), Some(None));
+
+        let req: McpNodeUpdateRequest =
+            serde_json::from_str(r#"{"node_id": "1", "body": "x", "clear_body": true}"#).unwrap();
+        let err = req.fields.body_update(false).unwrap_err();
+        assert!(err.message.contains("clear_body"), "{}", err.message);
+    }
+
     #[test]
     fn batch_update_request_body_null_deserializes() {
         // Without #[serde(default)] or custom deserializer, serde treats both
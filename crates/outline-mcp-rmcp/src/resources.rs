@@ -4,6 +4,10 @@
 //! so that `outline://guides/<slug>` returns the exact markdown that
 //! ships with the crate. Sources live under `docs/guides/` at this
 //! crate's root.
+//!
+//! Books are exposed read-only as rendered Markdown: `outline://selected`
+//! for this session's selected book and `outline://<slug>` for any book on
+//! the shelf.
 
 use rmcp::model::{
     ListResourcesResult, RawResource, ReadResourceResult, Resource, ResourceContents,
@@ -65,6 +69,64 @@ pub(crate) fn read(uri: &str) -> Option<ReadResourceResult> {
     ]))
 }
 
+/// URI of the session's selected book.
+pub(crate) const SELECTED_URI: &str = "outline://selected";
+
+/// Which book a book URI names.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum BookTarget<'a> {
+    /// `outline://selected`.
+    Selected,
+    /// `outline://<slug>`.
+    Slug(&'a str),
+}
+
+/// Parse a book URI. Guide URIs (`outline://guides/...`) and anything with a
+/// path separator are not book URIs. `selected` always means the selected
+/// book, even if a book with that slug exists.
+pub(crate) fn parse_book_uri(uri: &str) -> Option<BookTarget<'_>> {
+    let rest = uri.strip_prefix("outline://")?;
+    match rest {
+        "selected" => Some(BookTarget::Selected),
+        "" => None,
+        slug if slug.contains('/') => None,
+        slug => Some(BookTarget::Slug(slug)),
+    }
+}
+
+/// URI of the book stored under `slug`.
+pub(crate) fn book_uri(slug: &str) -> String {
+    format!("outline://{slug}")
+}
+
+/// Resource entry for a book rendered as Markdown.
+pub(crate) fn book_resource(uri: String, name: &str, title: &str, description: String) -> Resource {
+    use rmcp::model::Annotated;
+    Annotated {
+        raw: RawResource {
+            uri,
+            name: name.to_string(),
+            title: Some(title.to_string()),
+            description: Some(description),
+            mime_type: Some("text/markdown".to_string()),
+            size: None,
+            icons: None,
+            meta: None,
+        },
+        annotations: None,
+    }
+}
+
+/// Read result for a book rendered as Markdown.
+pub(crate) fn book_contents(uri: &str, markdown: String) -> ReadResourceResult {
+    ReadResourceResult::new(vec![ResourceContents::TextResourceContents {
+        uri: uri.to_string(),
+        mime_type: Some("text/markdown".to_string()),
+        text: markdown,
+        meta: None,
+    }])
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -107,6 +169,18 @@ mod tests {
         );
     }
 
+    #[test]
+    fn book_uris_name_selected_or_a_slug_but_not_guides() {
+        assert_eq!(parse_book_uri(SELECTED_URI), Some(BookTarget::Selected));
+        assert_eq!(
+            parse_book_uri(&book_uri("rust-rules")),
+            Some(BookTarget::Slug("rust-rules"))
+        );
+        assert_eq!(parse_book_uri("outline://guides/snapshot-workflow"), None);
+        assert_eq!(parse_book_uri("outline://"), None);
+        assert_eq!(parse_book_uri("file:///tmp/x"), None);
+    }
+
     #[test]
     fn read_returns_none_for_unknown_uri() {
         assert!(read("outline://guides/does-not-exist").is_none());
@@ -6,7 +6,7 @@
 
 use std::collections::HashMap;
 use std::path::PathBuf;
-use std::sync::{Arc, RwLock};
+use std::sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard};
 
 use ai_store_core::Store;
 use ai_store_sqlite::SqliteStore;
@@ -15,7 +15,7 @@ use rmcp::{
     model::{
         CallToolRequestParams, CallToolResult, Implementation, ListResourcesResult,
         ListToolsResult, PaginatedRequestParams, ProtocolVersion, ReadResourceRequestParams,
-        ReadResourceResult, ServerCapabilities, ServerInfo,
+        ReadResourceResult, Resource, ServerCapabilities, ServerInfo,
     },
     service::{RequestContext, RoleServer},
     transport::stdio,
@@ -23,18 +23,28 @@ use rmcp::{
 };
 use tokio::sync::Mutex as AsyncMutex;
 
+use outline_mcp_core::application::eject::EjectService;
 use outline_mcp_core::application::error::AppError;
+use outline_mcp_core::application::natural_sort::natural_cmp;
 use outline_mcp_core::application::service::BookService;
+use outline_mcp_core::domain::error::DomainError;
+use outline_mcp_core::domain::model::book::TemplateBook;
 use outline_mcp_core::domain::model::id::NodeId;
+use outline_mcp_core::domain::model::timestamp::Timestamp;
+use outline_mcp_core::domain::repository::{boxed_repository, BookRepository, DynBookRepository};
 use outline_mcp_core::infra::changelog_bridge::HistoryPreservingChangeLogRepository;
 use outline_mcp_core::infra::json_store::JsonBookRepository;
 use outline_mcp_core::infra::snapshot::SnapshotService;
 use outline_mcp_core::infra::snapshot_migrator::count_orphan_snapshots;
 use outline_mcp_core::infra::snapshot_sink::SnapshotOnlySink;
 
-use crate::helpers::{build_hierarchical_ids, find_hierarchical_id, is_hierarchical_id};
-use crate::request::parse_node_id;
-use crate::resources;
+use crate::config::{ServerConfig, ToolProfile};
+use crate::helpers::{
+    build_hierarchical_ids, describe_deepest, find_hierarchical_id, normalize_hierarchical_id,
+};
+use crate::idempotency::IdempotencyCache;
+use crate::request::{parse_node_id, validate_slug};
+use crate::resources::{self, BookTarget};
 
 // =============================================================================
 // Public entry point
@@ -42,6 +52,19 @@ use crate::resources;
 
 /// MCP Serverを起動する。shelf_dirは複数Book格納ディレクトリ。
 pub async fn run(shelf_dir: PathBuf) -> anyhow::Result<()> {
+    run_with(shelf_dir, RunOptions::default()).await
+}
+
+/// Startup options that override the shelf's config file (e.g. from
+/// command-line flags).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RunOptions {
+    /// Serve the shelf read-only even if the config does not say so.
+    pub read_only: bool,
+}
+
+/// [`run`] with startup overrides.
+pub async fn run_with(shelf_dir: PathBuf, options: RunOptions) -> anyhow::Result<()> {
     // Best-effort: a minimal stderr-only subscriber so `tracing::warn!`
     // calls (e.g. `OutlineMcpServer::store_for`'s orphan-snapshot warning)
     // are actually visible somewhere. stdout is reserved for the MCP stdio
@@ -53,7 +76,9 @@ pub async fn run(shelf_dir: PathBuf) -> anyhow::Result<()> {
         .with_writer(std::io::stderr)
         .try_init();
 
-    let server = OutlineMcpServer::new(shelf_dir);
+    let mut config = ServerConfig::load(&ServerConfig::default_path(&shelf_dir))?;
+    config.read_only |= options.read_only;
+    let server = OutlineMcpServer::new(shelf_dir).with_config(config);
     let service = server.serve(stdio()).await?;
     service.waiting().await?;
     Ok(())
@@ -63,6 +88,75 @@ pub async fn run(shelf_dir: PathBuf) -> anyhow::Result<()> {
 // MCP Server
 // =============================================================================
 
+/// poison していても読み取りロックを取る。
+///
+/// セッション状態のロック（`selected` など）は値を丸ごと代入するだけなので、
+/// 保持中に panic したスレッドがあっても中身が半端な状態にはならない。poison を
+/// エラーにするとサーバーを再起動するまで選択が使えなくなるため、印を消して続ける。
+pub(crate) fn read_recovering<T>(lock: &RwLock<T>) -> RwLockReadGuard<'_, T> {
+    lock.read().unwrap_or_else(|poisoned| {
+        lock.clear_poison();
+        poisoned.into_inner()
+    })
+}
+
+/// poison していても書き込みロックを取る（[`read_recovering`] を参照）。
+pub(crate) fn write_recovering<T>(lock: &RwLock<T>) -> RwLockWriteGuard<'_, T> {
+    lock.write().unwrap_or_else(|poisoned| {
+        lock.clear_poison();
+        poisoned.into_inner()
+    })
+}
+
+/// `resolve_id` がどこまでの解決方法を許すか。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ResolvePolicy {
+    /// 階層番号 / UUID / 短縮プレフィックス / タイトル部分一致のすべてを許す。
+    Lenient,
+    /// 階層番号と UUID（短縮プレフィックス含む）のみ。削除など破壊的操作用。
+    Strict,
+}
+
+/// ノード参照の書き方（[`OutlineMcpServer::resolve_id_in`] が試す順）と例。`help` が使う。
+pub(crate) const NODE_REF_FORMATS: &[(&str, &str)] = &[
+    (
+        "hierarchical number from `toc`",
+        "'2-3' (also '2.3' / '2.3.')",
+    ),
+    ("full UUID", "'3f2a9c1e-0b4d-4e6f-8a1b-2c3d4e5f6a7b'"),
+    ("UUID prefix (unique within the book)", "'3f2a9c1e'"),
+    (
+        "title substring, case-insensitive (not for destructive calls)",
+        "'deploy'",
+    ),
+];
+
+/// Book 参照の書き方（[`OutlineMcpServer::resolve_book_ref`] が試す順）と例。
+pub(crate) const BOOK_REF_FORMATS: &[(&str, &str)] = &[
+    ("number from the last `shelf` listing", "'2'"),
+    ("slug", "'rust-guide'"),
+];
+
+/// エラー文を補う操作の文脈（[`OutlineMcpServer::describe_app_error`] 用）。
+#[derive(Debug, Default, Clone, Copy)]
+pub(crate) struct ErrorContext<'a> {
+    /// 利用者の入力（`"2-3"` やタイトル）と解決後の ID の対応。
+    pub(crate) refs: &'a [(&'a str, NodeId)],
+    /// 追加・移動先の親。`Some(None)` はトップレベル、`None` は親を伴わない操作。
+    pub(crate) parent: Option<Option<NodeId>>,
+}
+
+/// slug から Book の保存先リポジトリを組み立てるファクトリ。
+pub(crate) type RepositoryFactory = Arc<dyn Fn(&str) -> DynBookRepository + Send + Sync>;
+
+/// 選択時点の Book の写し（`changes` の既定の比較対象）。
+#[derive(Clone)]
+pub(crate) struct SessionBaseline {
+    pub(crate) slug: String,
+    pub(crate) selected_at: Timestamp,
+    pub(crate) book: TemplateBook,
+}
+
 /// The outline-mcp MCP server.
 ///
 /// Holds the shelf directory (the directory containing one JSON file per
@@ -73,7 +167,18 @@ pub async fn run(shelf_dir: PathBuf) -> anyhow::Result<()> {
 pub struct OutlineMcpServer {
     pub(crate) shelf_dir: PathBuf,
     pub(crate) selected: Arc<RwLock<Option<String>>>,
+    /// Slug order of the last `shelf` listing shown with `pin_selected`, so
+    /// `select_book` numbers match what was displayed. `None` means the
+    /// plain sorted order of `Self::list_book_slugs`.
+    pub(crate) shelf_order: Arc<RwLock<Option<Vec<String>>>>,
+    /// Book as it was at this session's last `select_book` / `init`; the
+    /// default reference for `changes`.
+    pub(crate) session_baseline: Arc<RwLock<Option<SessionBaseline>>>,
     tool_router: ToolRouter<Self>,
+    pub(crate) config: Arc<ServerConfig>,
+    /// Builds the `BookRepository` behind `Self::service_for`. Defaults to
+    /// one `JsonBookRepository` per `{shelf_dir}/{slug}.json`.
+    repository_factory: RepositoryFactory,
     /// Lazily constructed, slug-keyed `ai_store_sqlite::SqliteStore` handles
     /// (bundles the `Store`, its SQLite backend driver, and the shared
     /// `AsyncIsle` in one type — see `Self::store_for`) backing both
@@ -82,6 +187,9 @@ pub struct OutlineMcpServer {
     /// thereafter — opening spawns a dedicated backend thread
     /// (`ai-store-sqlite`), so this must not happen on every tool call.
     snapshot_stores: Arc<AsyncMutex<HashMap<String, SqliteStore>>>,
+    /// Recently seen `node_create` idempotency keys per book. Held across the
+    /// create so concurrent retries of one key cannot both insert.
+    pub(crate) idempotency: Arc<AsyncMutex<IdempotencyCache>>,
 }
 
 impl OutlineMcpServer {
@@ -89,12 +197,103 @@ impl OutlineMcpServer {
     /// containing one JSON file per book). No book is selected until
     /// `select_book` (or `init`) is called.
     pub fn new(shelf_dir: PathBuf) -> Self {
+        let json_dir = shelf_dir.clone();
         Self {
             shelf_dir,
             selected: Arc::new(RwLock::new(None)),
-            tool_router: Self::tool_router(),
+            shelf_order: Arc::new(RwLock::new(None)),
+            session_baseline: Arc::new(RwLock::new(None)),
+            tool_router: Self::router_for(ToolProfile::Full),
+            config: Arc::new(ServerConfig::default()),
+            repository_factory: Arc::new(move |slug: &str| {
+                boxed_repository(JsonBookRepository::new(
+                    json_dir.join(format!("{slug}.json")),
+                ))
+            }),
             snapshot_stores: Arc::new(AsyncMutex::new(HashMap::new())),
+            idempotency: Arc::new(AsyncMutex::new(IdempotencyCache::default())),
+        }
+    }
+
+    /// Replace the server-wide settings (defaults otherwise).
+    ///
+    /// Tool description suffixes from the config are merged into the tool
+    /// list here; unusable entries are skipped with a warning.
+    pub fn with_config(mut self, config: ServerConfig) -> Self {
+        if config.instructions.is_some() && config.instructions_override().is_none() {
+            tracing::warn!(
+                "ignoring config `instructions`: blank or longer than {} characters",
+                ServerConfig::MAX_INSTRUCTIONS_LEN
+            );
         }
+        self.tool_router = Self::router_for(config.tool_profile);
+        for name in config.tool_description_suffixes.keys() {
+            let Some(suffix) = config.tool_description_suffix(name) else {
+                tracing::warn!(
+                    "ignoring description suffix for `{name}`: blank or longer than {} characters",
+                    ServerConfig::MAX_TOOL_SUFFIX_LEN
+                );
+                continue;
+            };
+            let Some(route) = self.tool_router.map.get_mut(name.as_str()) else {
+                tracing::warn!("ignoring description suffix for unknown tool `{name}`");
+                continue;
+            };
+            let description = match route.attr.description.as_deref() {
+                Some(base) => format!("{base} {suffix}"),
+                None => suffix.to_string(),
+            };
+            route.attr.description = Some(description.into());
+        }
+        self.config = Arc::new(config);
+        self
+    }
+
+    /// `profile` に応じたツール一覧。Compact は少数のツールに絞り、説明文も短くする。
+    fn router_for(profile: ToolProfile) -> ToolRouter<Self> {
+        let mut router = Self::tool_router();
+        if profile == ToolProfile::Compact {
+            router
+                .map
+                .retain(|name, _| ToolProfile::COMPACT_TOOLS.contains(&name.as_ref()));
+            for (name, route) in router.map.iter_mut() {
+                if let Some(description) = compact_description(name) {
+                    route.attr.description = Some(description.into());
+                }
+            }
+        }
+        router
+    }
+
+    /// Replace the book storage backend (e.g. an HTTP or SQLite repository).
+    ///
+    /// `factory` is called with the book slug on every tool call. Shelf
+    /// listing and the changelog / snapshot subsystems still use `shelf_dir`.
+    pub fn with_repository_factory<F>(mut self, factory: F) -> Self
+    where
+        F: Fn(&str) -> DynBookRepository + Send + Sync + 'static,
+    {
+        self.repository_factory = Arc::new(factory);
+        self
+    }
+
+    /// 設定された長さの短縮IDを返す。
+    pub(crate) fn short_id(&self, id: NodeId) -> String {
+        id.short_n(self.config.id_display_len)
+    }
+
+    /// `output_dir` を指定しない `checklist` の書き出し先。
+    ///
+    /// 読み取り専用なら export 用ディレクトリ、そうでなければ Book の `eject_defaults` >
+    /// `eject` 設定 > shelf 直下の `exports/` の順。起動元のカレントディレクトリには依存しない。
+    pub(crate) fn default_export_dir(&self, book: Option<&TemplateBook>) -> PathBuf {
+        if self.config.read_only {
+            return self.config.read_only_export_dir();
+        }
+        book.and_then(|b| b.eject_defaults())
+            .and_then(|d| d.output_dir.as_deref())
+            .or(self.config.eject.output_dir.as_deref())
+            .map_or_else(|| self.shelf_dir.join("exports"), PathBuf::from)
     }
 
     /// Returns the (lazily constructed, cached) ai-store `Store` for `slug`,
@@ -181,11 +380,6 @@ impl OutlineMcpServer {
         ))
     }
 
-    /// slug からBookファイルパスを返す。
-    pub(crate) fn book_path(&self, slug: &str) -> PathBuf {
-        self.shelf_dir.join(format!("{slug}.json"))
-    }
-
     /// Constructs the (ai-store-backed, JSON-history-preserving) changelog
     /// repository for `slug`, sharing `slug`'s `Store` with the snapshot
     /// subsystem (see `Self::store_for`). Single construction point used by
@@ -207,36 +401,128 @@ impl OutlineMcpServer {
         )
     }
 
+    /// 選択中の Book の slug（未選択なら `None`）。
+    pub(crate) fn selected_slug(&self) -> Option<String> {
+        read_recovering(&self.selected).clone()
+    }
+
+    /// 選択中の Book の slug。未選択ならエラー。
+    pub(crate) fn require_selected(&self) -> Result<String, McpError> {
+        self.selected_slug().ok_or_else(|| {
+            McpError::invalid_params(
+                "No book selected. Use `shelf` to list books and `select_book` to choose one.",
+                None,
+            )
+        })
+    }
+
+    /// 選択中の Book を入れ替える（`None` で未選択に戻す）。
+    pub(crate) fn set_selected(&self, slug: Option<String>) {
+        *write_recovering(&self.selected) = slug;
+    }
+
     /// 選択中BookのServiceを返す。未選択ならエラー。
-    pub(crate) async fn service(&self) -> Result<BookService<JsonBookRepository>, McpError> {
-        let slug = {
-            let guard = self
-                .selected
-                .read()
-                .map_err(|_| McpError::internal_error("Lock poisoned", None))?;
-            guard
-                .as_ref()
-                .ok_or_else(|| {
-                    McpError::invalid_params(
-                        "No book selected. Use `shelf` to list books and `select_book` to choose one.",
-                        None,
-                    )
-                })?
-                .clone()
-        };
+    pub(crate) async fn service(&self) -> Result<BookService<DynBookRepository>, McpError> {
+        let slug = self.require_selected()?;
         self.service_for(&slug).await
     }
 
+    /// 指定slugのBookリポジトリだけを返す（changelog / event store は開かない）。
+    pub(crate) fn repository_for(&self, slug: &str) -> DynBookRepository {
+        (self.repository_factory)(slug)
+    }
+
+    /// 指定slugのBookが保存済みか。event store を開かないので未知の slug でも副作用がない。
+    pub(crate) async fn book_exists(&self, slug: &str) -> Result<bool, McpError> {
+        self.repository_for(slug)
+            .exists()
+            .await
+            .map_err(|e| Self::to_mcp_error(AppError::Storage(Box::new(e))))
+    }
+
+    /// 選択時点の Book を `changes` の既定の比較対象として記録する。
+    pub(crate) fn record_session_baseline(&self, slug: &str, book: &TemplateBook) {
+        *write_recovering(&self.session_baseline) = Some(SessionBaseline {
+            slug: slug.to_string(),
+            selected_at: Timestamp::now(),
+            book: book.clone(),
+        });
+    }
+
     /// 指定slugのServiceを返す（選択状態不要）。
     pub(crate) async fn service_for(
         &self,
         slug: &str,
-    ) -> Result<BookService<JsonBookRepository>, McpError> {
-        let repo = JsonBookRepository::new(self.book_path(slug));
+    ) -> Result<BookService<DynBookRepository>, McpError> {
+        let repo = self.repository_for(slug);
         let changelog = Box::new(self.changelog_for(slug).await?);
         Ok(BookService::new(repo).with_changelog(changelog))
     }
 
+    /// 選択中の Book（`outline://selected`）と Shelf の各 Book（`outline://<slug>`）の
+    /// Resource 一覧。読み込めない Book はタイトルの代わりに slug を出す。
+    pub(crate) async fn book_resources(&self) -> Result<Vec<Resource>, McpError> {
+        let selected = self.selected_slug();
+        let mut list = Vec::new();
+        for slug in self.list_book_slugs()? {
+            let title = match self.service_for(&slug).await {
+                Ok(svc) => svc.read_tree().await.ok().map(|b| b.title().to_string()),
+                Err(_) => None,
+            }
+            .unwrap_or_else(|| slug.clone());
+            if selected.as_deref() == Some(slug.as_str()) {
+                list.insert(
+                    0,
+                    resources::book_resource(
+                        resources::SELECTED_URI.to_string(),
+                        "selected",
+                        &title,
+                        format!("The selected book ({slug}) rendered as Markdown."),
+                    ),
+                );
+            }
+            list.push(resources::book_resource(
+                resources::book_uri(&slug),
+                &slug,
+                &title,
+                format!("Book '{slug}' rendered as Markdown."),
+            ));
+        }
+        Ok(list)
+    }
+
+    /// Book の URI なら Markdown に描画して返す。Book の URI でなければ `None`。
+    pub(crate) async fn read_book_resource(
+        &self,
+        uri: &str,
+    ) -> Result<Option<ReadResourceResult>, McpError> {
+        let slug = match resources::parse_book_uri(uri) {
+            None => return Ok(None),
+            Some(BookTarget::Selected) => self.selected_slug().ok_or_else(|| {
+                McpError::invalid_params(
+                    "No book selected. Use `select_book` first, or read `outline://<slug>`.",
+                    None,
+                )
+            })?,
+            Some(BookTarget::Slug(slug)) => {
+                validate_slug(slug)?;
+                if !self.book_exists(slug).await? {
+                    return Err(McpError::invalid_params(
+                        format!(
+                            "Book '{slug}' not found. Use `resources/list` to see available books."
+                        ),
+                        None,
+                    ));
+                }
+                slug.to_string()
+            }
+        };
+        let svc = self.service_for(&slug).await?;
+        let book = svc.read_tree().await.map_err(Self::to_mcp_error)?;
+        let markdown = EjectService::render_markdown(&book, true, None);
+        Ok(Some(resources::book_contents(uri, markdown)))
+    }
+
     /// Shelf内のslug一覧をソート順で返す。
     pub(crate) fn list_book_slugs(&self) -> Result<Vec<String>, McpError> {
         if !self.shelf_dir.exists() {
@@ -263,14 +549,21 @@ impl OutlineMcpServer {
                     .map(String::from)
             })
             .collect();
-        slugs.sort();
+        // `book2` < `book10`。番号での選択（`resolve_book_ref`）もこの順に従う
+        slugs.sort_by(|a, b| natural_cmp(a, b));
         Ok(slugs)
     }
 
     /// 番号 or slug → slug に解決する。
+    ///
+    /// 番号は直前の `shelf` 表示順（`pin_selected` 使用時）に対応する。
     pub(crate) fn resolve_book_ref(&self, book_ref: &str) -> Result<String, McpError> {
         if let Ok(num) = book_ref.parse::<usize>() {
-            let slugs = self.list_book_slugs()?;
+            let pinned = read_recovering(&self.shelf_order).clone();
+            let slugs = match pinned {
+                Some(order) => order,
+                None => self.list_book_slugs()?,
+            };
             if num == 0 || num > slugs.len() {
                 return Err(McpError::invalid_params(
                     format!(
@@ -290,20 +583,151 @@ impl OutlineMcpServer {
         McpError::internal_error(format!("{e}"), None)
     }
 
+    /// `AppError` の説明文。ノードは `'2-3 Testing'` のように階層番号とタイトルで
+    /// 示し（`book` は操作前の Book）、利用者の入力と違えば `(given as 'test')` を添える。
+    /// Book にないノードは利用者の入力で示す。
+    pub(crate) fn describe_app_error(
+        &self,
+        book: &TemplateBook,
+        e: &AppError,
+        ctx: &ErrorContext,
+    ) -> String {
+        let label = |id: NodeId| {
+            let given = ctx
+                .refs
+                .iter()
+                .find(|(_, resolved)| *resolved == id)
+                .map(|(input, _)| input.trim());
+            match book.get_node(id) {
+                Some(node) => {
+                    let hier = find_hierarchical_id(book, id).unwrap_or_else(|| self.short_id(id));
+                    match given {
+                        Some(input) if input != hier => {
+                            format!("'{hier} {}' (given as '{input}')", node.title())
+                        }
+                        _ => format!("'{hier} {}'", node.title()),
+                    }
+                }
+                None => format!(
+                    "'{}'",
+                    given.map_or_else(|| self.short_id(id), str::to_string)
+                ),
+            }
+        };
+        match e {
+            AppError::Domain(DomainError::CyclicMove {
+                node_id,
+                new_parent,
+            }) => {
+                if node_id == new_parent {
+                    format!("cannot move {} under itself", label(*node_id))
+                } else {
+                    format!(
+                        "cannot move {} under its own descendant {}",
+                        label(*node_id),
+                        label(*new_parent)
+                    )
+                }
+            }
+            AppError::Domain(DomainError::MaxDepthExceeded { node_id, max }) => {
+                let target = match ctx.parent {
+                    Some(Some(parent)) => format!(" under {}", label(parent)),
+                    Some(None) => " at the top level".to_string(),
+                    None => String::new(),
+                };
+                // 追加時の node_id はまだ Book にない新しい ID
+                let mut msg = if book.get_node(*node_id).is_some() {
+                    format!(
+                        "max depth {max} exceeded moving {}{target}",
+                        label(*node_id)
+                    )
+                } else {
+                    format!("max depth {max} exceeded adding{target}")
+                };
+                if let Some(deepest) = describe_deepest(book) {
+                    msg.push_str(&format!(
+                        ". Deepest now: {deepest}; raise the limit with `book_config` `max_depth`"
+                    ));
+                }
+                msg
+            }
+            AppError::Domain(DomainError::ProtectedNode { node_id, removing }) => {
+                if node_id == removing {
+                    format!(
+                        "{} is protected; pass `override_protection: true` to delete it anyway",
+                        label(*node_id)
+                    )
+                } else {
+                    format!(
+                        "cannot remove {}: it contains protected node {}; pass `override_protection: true` to delete it anyway",
+                        label(*removing),
+                        label(*node_id)
+                    )
+                }
+            }
+            AppError::Domain(DomainError::NodeNotFound(id)) => format!(
+                "node {} not found (it may have been removed or renumbered; run `toc` for current IDs)",
+                label(*id)
+            ),
+            other => other.to_string(),
+        }
+    }
+
+    /// [`Self::to_mcp_error`] のうち、利用者の指定ミスによるエラー（移動の循環・深さ超過・
+    /// ノードなし）は `book` と `ctx` を使って分かりやすい `invalid_params` にする。
+    pub(crate) fn to_mcp_error_in(
+        &self,
+        book: &TemplateBook,
+        e: AppError,
+        ctx: &ErrorContext,
+    ) -> McpError {
+        match e {
+            AppError::Domain(
+                DomainError::CyclicMove { .. }
+                | DomainError::MaxDepthExceeded { .. }
+                | DomainError::NodeNotFound(_)
+                | DomainError::ProtectedNode { .. },
+            ) => McpError::invalid_params(self.describe_app_error(book, &e, ctx), None),
+            other => Self::to_mcp_error(other),
+        }
+    }
+
     /// 階層番号 / Full UUID / short prefix / title部分一致 → NodeId。
     ///
     /// 優先順位:
     /// 1. 階層番号 (e.g. "1", "2-3") — `toc` 出力と対応
     /// 2. Full UUID
     /// 3. 短縮UUIDプレフィックス
-    /// 4. タイトル部分一致（フォールバック）
-    pub(crate) async fn resolve_id(&self, s: &str) -> Result<NodeId, McpError> {
-        // 1. 階層番号（"1", "2-3", "1-2-1" 等）
-        if is_hierarchical_id(s) {
-            let svc = self.service().await?;
-            let book = svc.read_tree().await.map_err(Self::to_mcp_error)?;
-            let mapping = build_hierarchical_ids(&book);
-            if let Some((_, id)) = mapping.iter().find(|(num, _)| num == s) {
+    /// 4. タイトル部分一致（フォールバック、`ResolvePolicy::Lenient` のみ）
+    pub(crate) async fn resolve_id(
+        &self,
+        s: &str,
+        policy: ResolvePolicy,
+    ) -> Result<NodeId, McpError> {
+        // Full UUID は Book を読まずに解決できる（階層番号と衝突しない）
+        if normalize_hierarchical_id(s).is_none() {
+            if let Ok(id) = parse_node_id(s) {
+                return Ok(id);
+            }
+        }
+        let svc = self.service().await?;
+        let book = svc.read_tree().await.map_err(Self::to_mcp_error)?;
+        self.resolve_id_in(&book, s, policy)
+    }
+
+    /// [`Self::resolve_id`] を読み込み済みの `book` に対して行う。
+    ///
+    /// `BookService::with_book` の中で使い、解決と変更を同じ Book インスタンスで行う。
+    pub(crate) fn resolve_id_in(
+        &self,
+        book: &TemplateBook,
+        s: &str,
+        policy: ResolvePolicy,
+    ) -> Result<NodeId, McpError> {
+        // 1. 階層番号（"1", "2-3", "1-2-1" 等。toc の dot / legal 表記も受け付ける）
+        if let Some(num) = normalize_hierarchical_id(s) {
+            let mapping = build_hierarchical_ids(book);
+            if let Some((_, id)) = mapping.iter().find(|(n, _)| *n == num) {
                 return Ok(*id);
             }
             return Err(McpError::invalid_params(
@@ -317,9 +741,6 @@ impl OutlineMcpServer {
             return Ok(id);
         }
 
-        let svc = self.service().await?;
-        let book = svc.read_tree().await.map_err(Self::to_mcp_error)?;
-
         // 3. 短縮プレフィックスでBook内を検索
         let id_matches: Vec<NodeId> = book
             .all_node_ids()
@@ -328,14 +749,34 @@ impl OutlineMcpServer {
         match id_matches.len() {
             1 => return Ok(id_matches[0]),
             n if n > 1 => {
+                // 表示長に揃えた候補を出し、より長いプレフィックスを促す
+                let len = self.config.id_display_len.max(s.len() + 1);
+                let candidates = id_matches
+                    .iter()
+                    .map(|id| id.short_n(len))
+                    .collect::<Vec<_>>()
+                    .join(", ");
                 return Err(McpError::invalid_params(
-                    format!("Ambiguous ID prefix: '{s}' matches {n} nodes"),
+                    format!(
+                        "Ambiguous ID prefix: '{s}' matches {n} nodes ({candidates}). Use a longer prefix or the full UUID."
+                    ),
                     None,
-                ))
+                ));
             }
             _ => {}
         }
 
+        // 破壊的操作ではタイトル一致で意図しないノードを掴まないよう打ち切る
+        if policy == ResolvePolicy::Strict {
+            return Err(McpError::invalid_params(
+                format!(
+                    "'{s}' is not a node number or UUID. Destructive operations do not match by title; \
+                     run `toc` and pass the node's number (e.g. '2-3') or UUID."
+                ),
+                None,
+            ));
+        }
+
         // 4. タイトル部分一致（case-insensitive, フォールバック）
         let query = s.to_lowercase();
         let title_matches: Vec<NodeId> = book
@@ -356,8 +797,8 @@ impl OutlineMcpServer {
                     title_matches
                         .iter()
                         .map(|id| {
-                            let hier = find_hierarchical_id(&book, *id)
-                                .unwrap_or_else(|| id.short().to_string());
+                            let hier = find_hierarchical_id(book, *id)
+                                .unwrap_or_else(|| self.short_id(*id));
                             book.get_node(*id)
                                 .map(|node| format!("'{}' ({})", node.title(), hier))
                                 .unwrap_or(hier)
@@ -369,12 +810,188 @@ impl OutlineMcpServer {
             )),
         }
     }
+
+    /// `"Design/API"` のようなスラッシュ区切りのタイトルパスを親から順にたどって解決する。
+    ///
+    /// 各区間はその階層の子タイトルと完全一致（大文字小文字は区別しない）で照合し、
+    /// ノードは作らない。タイトル自体に `/` を含む場合（"CI/CD"）もそのまま一致する。
+    /// 一致が複数ならその候補を、見つからなければ最も深く一致した階層の子を挙げて失敗する。
+    pub(crate) fn resolve_title_path_in(
+        &self,
+        book: &TemplateBook,
+        path: &str,
+    ) -> Result<NodeId, McpError> {
+        let path = path.trim().trim_matches('/');
+        let mut matches = Vec::new();
+        // 最も深く一致した途中のノード（見つからない時の候補表示用）
+        let mut deepest: Option<(usize, NodeId)> = None;
+        let mut stack: Vec<(&[NodeId], &str, usize)> = vec![(book.root_nodes(), path, 0)];
+        while let Some((children, rest, depth)) = stack.pop() {
+            for &child in children {
+                let Some(node) = book.get_node(child) else {
+                    continue;
+                };
+                let title = node.title().trim();
+                let Some(head) = rest.get(..title.len()) else {
+                    continue;
+                };
+                if title.is_empty() || !head.eq_ignore_ascii_case(title) {
+                    continue;
+                }
+                let tail = &rest[title.len()..];
+                if tail.trim().is_empty() {
+                    matches.push(child);
+                } else if let Some(next) = tail.trim_start().strip_prefix('/') {
+                    if deepest.is_none_or(|(d, _)| depth + 1 > d) {
+                        deepest = Some((depth + 1, child));
+                    }
+                    stack.push((node.children(), next.trim_start(), depth + 1));
+                }
+            }
+        }
+
+        let label = |id: NodeId| {
+            let hier = find_hierarchical_id(book, id).unwrap_or_else(|| self.short_id(id));
+            format!(
+                "{hier}. {}",
+                book.get_node(id).map(|n| n.title()).unwrap_or("?")
+            )
+        };
+        match matches.len() {
+            1 => Ok(matches[0]),
+            0 => {
+                let (under, children) = match deepest {
+                    Some((_, id)) => (
+                        format!("under '{}'", label(id)),
+                        book.get_node(id).map(|n| n.children()).unwrap_or(&[]),
+                    ),
+                    None => ("at the top level".to_string(), book.root_nodes()),
+                };
+                let available = if children.is_empty() {
+                    "none".to_string()
+                } else {
+                    children
+                        .iter()
+                        .map(|id| format!("'{}'", label(*id)))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                };
+                Err(McpError::invalid_params(
+                    format!(
+                        "No node at title path '{path}'. Children {under}: {available}. \
+                         Paths are matched from the top level, one exact title per '/'-separated segment."
+                    ),
+                    None,
+                ))
+            }
+            n => Err(McpError::invalid_params(
+                format!(
+                    "Ambiguous title path: '{path}' matches {n} nodes: {}. Pass the node's number from `toc` instead.",
+                    matches
+                        .iter()
+                        .map(|id| format!("'{}'", label(*id)))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ),
+                None,
+            )),
+        }
+    }
 }
 
 // =============================================================================
 // ServerHandler impl
 // =============================================================================
 
+/// `ToolProfile::Compact` 用の短い説明文。
+fn compact_description(tool: &str) -> Option<&'static str> {
+    Some(match tool {
+        "shelf" => "List the books on the shelf, numbered for `select_book`.",
+        "select_book" => "Select the book to work on, by number from `shelf` or slug.",
+        "toc" => "Show the selected book's outline with the IDs (e.g. '2-3') the other tools take.",
+        "node_create" => {
+            "Add a section or content node under `parent` (an ID from `toc`; omit for top level)."
+        }
+        "node_update" => "Edit a node's title, body, type, placeholder, properties or done flag.",
+        "node_move" => {
+            "Move a node (action 'move') or delete it with its descendants (action 'remove')."
+        }
+        "checklist" => "Export a section or the whole book as a Markdown checklist.",
+        _ => return None,
+    })
+}
+
+/// Built-in MCP `instructions`, used unless the config overrides them.
+const DEFAULT_INSTRUCTIONS: &str = "Create and manage tree-structured knowledge notes.\n\
+    \n\
+    Intended flow: organize knowledge as tree nodes (sections and content), \
+    browse with `toc`, and use node properties for metadata.\n\
+    \n\
+    Context Injection: nodes with property `inject=true` have their body \
+    automatically included in `select_book` output — use this to inject \
+    persistent rules/context into every session.\n\
+    \n\
+    Tools: `shelf` → `select_book` → `toc` → `node_create`/`node_update`/`node_move`. \
+    `checklist` for task export. `init` for new book. \
+    `help` for accepted ID formats and examples.\n\
+    History: `snapshot_create`/`snapshot_list`/`snapshot_restore` for versioning. \
+    `node_history` for change tracking. `dump` for full export.\n\
+    Batch: `node_batch_move`/`node_batch_update` for bulk operations (UUID required). \
+    Query: `node_query` for searching nodes by properties/status/type.\n\
+    Resources: read guides via `outline://guides/<name>`, and a book as Markdown via \
+    `outline://selected` or `outline://<slug>` (see `resources/list`).";
+
+impl OutlineMcpServer {
+    /// `get_info` の instructions。読み取り専用なら書き込みを試さないよう注記を足す。
+    fn instructions(&self) -> String {
+        let base = self
+            .config
+            .instructions_override()
+            .unwrap_or(DEFAULT_INSTRUCTIONS);
+        if !self.config.read_only {
+            return base.to_string();
+        }
+        format!(
+            "{base}\n\nREAD-ONLY SERVER: this shelf is served read-only. Tools that modify it \
+             (`init`, `node_create`, `node_update`, `node_move`, `import`, …) always fail — do not \
+             retry them. Browse with `shelf`, `select_book`, `toc` and `node_get`; `checklist` \
+             files are written to {}.",
+            self.config.read_only_export_dir().display()
+        )
+    }
+
+    /// 読み取り専用モードで `tool` を拒否するならそのエラー。
+    ///
+    /// 許可するのは read-only 注釈のツールと [`ServerConfig::READ_ONLY_EXTRA_TOOLS`]
+    /// で、[`ServerConfig::READ_ONLY_DENIED_TOOLS`] は除く。注釈で判定するので、
+    /// 後から足したツールも read-only と宣言しない限り拒否される。未知のツール名は
+    /// ルーターの「見つからない」エラーに任せる。
+    pub(crate) fn read_only_rejection(&self, tool: &str) -> Option<McpError> {
+        if !self.config.read_only {
+            return None;
+        }
+        let route = self.tool_router.map.get(tool)?;
+        let annotated_read_only = route
+            .attr
+            .annotations
+            .as_ref()
+            .and_then(|a| a.read_only_hint)
+            .unwrap_or(false);
+        let allowed = ServerConfig::READ_ONLY_EXTRA_TOOLS.contains(&tool)
+            || (annotated_read_only && !ServerConfig::READ_ONLY_DENIED_TOOLS.contains(&tool));
+        (!allowed).then(|| {
+            McpError::invalid_params(
+                format!(
+                    "`{tool}` is unavailable: this outline-mcp server is read-only. \
+                     Only browsing tools (shelf, select_book, toc, node_get, checklist, …) work; \
+                     do not retry writes."
+                ),
+                None,
+            )
+        })
+    }
+}
+
 impl ServerHandler for OutlineMcpServer {
     fn get_info(&self) -> ServerInfo {
         let server_info = Implementation::new("outline-mcp", env!("CARGO_PKG_VERSION"))
@@ -390,24 +1007,7 @@ impl ServerHandler for OutlineMcpServer {
         ServerInfo::new(capabilities)
             .with_protocol_version(ProtocolVersion::V_2025_03_26)
             .with_server_info(server_info)
-            .with_instructions(
-                "Create and manage tree-structured knowledge notes.\n\
-                 \n\
-                 Intended flow: organize knowledge as tree nodes (sections and content), \
-                 browse with `toc`, and use node properties for metadata.\n\
-                 \n\
-                 Context Injection: nodes with property `inject=true` have their body \
-                 automatically included in `select_book` output — use this to inject \
-                 persistent rules/context into every session.\n\
-                 \n\
-                 Tools: `shelf` → `select_book` → `toc` → `node_create`/`node_update`/`node_move`. \
-                 `checklist` for task export. `init` for new book.\n\
-                 History: `snapshot_create`/`snapshot_list`/`snapshot_restore` for versioning. \
-                 `node_history` for change tracking. `dump` for full export.\n\
-                 Batch: `node_batch_move`/`node_batch_update` for bulk operations (UUID required). \
-                 Query: `node_query` for searching nodes by properties/status/type.\n\
-                 Resources: read guides via `outline://guides/<name>` (see `resources/list`).",
-            )
+            .with_instructions(self.instructions())
     }
 
     async fn list_tools(
@@ -427,6 +1027,9 @@ impl ServerHandler for OutlineMcpServer {
         request: CallToolRequestParams,
         context: RequestContext<RoleServer>,
     ) -> Result<CallToolResult, McpError> {
+        if let Some(err) = self.read_only_rejection(&request.name) {
+            return Err(err);
+        }
         let tool_ctx = ToolCallContext::new(self, request, context);
         self.tool_router.call(tool_ctx).await
     }
@@ -436,7 +1039,9 @@ impl ServerHandler for OutlineMcpServer {
         _request: Option<PaginatedRequestParams>,
         _context: RequestContext<RoleServer>,
     ) -> Result<ListResourcesResult, McpError> {
-        Ok(resources::list_all())
+        let mut result = resources::list_all();
+        result.resources.extend(self.book_resources().await?);
+        Ok(result)
     }
 
     async fn read_resource(
@@ -444,7 +1049,10 @@ impl ServerHandler for OutlineMcpServer {
         request: ReadResourceRequestParams,
         _context: RequestContext<RoleServer>,
     ) -> Result<ReadResourceResult, McpError> {
-        resources::read(&request.uri).ok_or_else(|| {
+        if let Some(guide) = resources::read(&request.uri) {
+            return Ok(guide);
+        }
+        self.read_book_resource(&request.uri).await?.ok_or_else(|| {
             McpError::invalid_params(
                 format!(
                     "Unknown resource: '{}'. Use `resources/list` to see available URIs.",
@@ -472,9 +1080,44 @@ mod tests {
         assert!(!info.server_info.version.is_empty());
     }
 
+    #[tokio::test]
+    async fn poisoned_selection_lock_recovers() {
+        let dir = std::env::temp_dir().join("outline-mcp-server-poison-test");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let server = OutlineMcpServer::new(dir.clone());
+        let svc = server.service_for("alpha").await.unwrap();
+        svc.create_book("Alpha", 4).await.unwrap();
+
+        // 書き込みガードを持ったまま panic させてロックを poison する
+        let selected = Arc::clone(&server.selected);
+        let panicked = std::thread::spawn(move || {
+            let mut guard = selected.write().unwrap();
+            *guard = Some("alpha".to_string());
+            panic!("tool handler crashed mid-update");
+        })
+        .join();
+        assert!(panicked.is_err());
+        assert!(server.selected.is_poisoned());
+
+        // 以降の呼び出しは内部エラーにならず、代入済みの値をそのまま使う
+        assert_eq!(server.selected_slug().as_deref(), Some("alpha"));
+        assert!(!server.selected.is_poisoned());
+        assert!(server.service().await.is_ok());
+        server.set_selected(None);
+        let err = server.service().await.err().unwrap();
+        assert!(
+            err.message.starts_with("No book selected"),
+            "{}",
+            err.message
+        );
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
     #[tokio::test]
     async fn test_service_for_and_changelog_for_share_slug_history() {
-        use outline_mcp_core::domain::model::book::AddNodeRequest;
+        use outline_mcp_core::domain::model::book::{AddNodeRequest, Position};
         use outline_mcp_core::domain::model::node::NodeType;
         use outline_mcp_core::domain::repository::ChangeLogRepository;
         use std::collections::HashMap;
@@ -493,18 +1136,19 @@ mod tests {
         svc.create_book("Wiring Test", 4)
             .await
             .expect("create_book");
-        let (id, warning) = svc
+        let (placed, warning) = svc
             .add_node(AddNodeRequest {
                 parent: None,
                 title: "Node".to_string(),
                 node_type: NodeType::Content,
                 body: None,
                 placeholder: None,
-                position: usize::MAX,
+                position: Position::Last,
                 properties: HashMap::new(),
             })
             .await
             .expect("add_node");
+        let id = placed.node_id;
         assert!(
             warning.is_none(),
             "changelog append should succeed: {warning:?}"
@@ -522,4 +1166,326 @@ mod tests {
 
         let _ = std::fs::remove_dir_all(&dir);
     }
+
+    #[tokio::test]
+    async fn test_service_for_uses_custom_repository_factory() {
+        use outline_mcp_core::domain::model::book::TemplateBook;
+        use outline_mcp_core::domain::repository::{BookRepository, RepositoryError};
+
+        struct FixedRepo(String);
+
+        #[async_trait::async_trait]
+        impl BookRepository for FixedRepo {
+            type Error = RepositoryError;
+            async fn load(&self) -> Result<Option<TemplateBook>, RepositoryError> {
+                Ok(Some(TemplateBook::new(&self.0, 4)))
+            }
+            async fn save(&self, _book: &TemplateBook) -> Result<(), RepositoryError> {
+                Err(RepositoryError::new("read-only remote"))
+            }
+            async fn exists(&self) -> Result<bool, RepositoryError> {
+                Ok(true)
+            }
+            async fn delete(&self) -> Result<(), RepositoryError> {
+                Err(RepositoryError::new("read-only remote"))
+            }
+        }
+
+        let dir = std::env::temp_dir().join("outline-mcp-server-repo-factory-test");
+        let _ = std::fs::remove_dir_all(&dir);
+        let server = OutlineMcpServer::new(dir.clone())
+            .with_repository_factory(|slug| Box::new(FixedRepo(format!("remote:{slug}"))));
+
+        let svc = server.service_for("handbook").await.expect("service_for");
+        let book = svc.read_tree().await.expect("read_tree");
+        assert_eq!(book.title(), "remote:handbook");
+        let err = svc.save_book(&book).await.unwrap_err();
+        assert!(err.to_string().contains("read-only remote"), "{err}");
+        assert!(!dir.join("handbook.json").exists());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn test_book_resources_list_and_render_selected_and_slug() {
+        use rmcp::model::ResourceContents;
+
+        let dir = std::env::temp_dir().join("outline-mcp-server-book-resources-test");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).expect("create temp shelf dir");
+
+        let server = OutlineMcpServer::new(dir.clone());
+        for (slug, title) in [("alpha", "Alpha Rules"), ("beta", "Beta Runbook")] {
+            let svc = server.service_for(slug).await.expect("service_for");
+            svc.create_book(title, 4).await.expect("create_book");
+        }
+
+        // 未選択なら outline://selected は一覧に出ず、読むとエラー
+        let uris =
+            |list: Vec<Resource>| -> Vec<String> { list.into_iter().map(|r| r.raw.uri).collect() };
+        let listed = uris(server.book_resources().await.unwrap());
+        assert_eq!(listed, ["outline://alpha", "outline://beta"]);
+        assert!(server
+            .read_book_resource("outline://selected")
+            .await
+            .is_err());
+
+        *server.selected.write().unwrap() = Some("beta".to_string());
+        let listed = uris(server.book_resources().await.unwrap());
+        assert_eq!(
+            listed,
+            ["outline://selected", "outline://alpha", "outline://beta"]
+        );
+
+        let text_of = |result: ReadResourceResult| match &result.contents[0] {
+            ResourceContents::TextResourceContents { uri, text, .. } => (uri.clone(), text.clone()),
+            _ => panic!("expected text contents"),
+        };
+        let selected = server
+            .read_book_resource("outline://selected")
+            .await
+            .unwrap()
+            .expect("book URI");
+        assert_eq!(
+            text_of(selected),
+            (
+                "outline://selected".to_string(),
+                "# Beta Runbook\n\n".to_string()
+            )
+        );
+        let alpha = server
+            .read_book_resource("outline://alpha")
+            .await
+            .unwrap()
+            .expect("book URI");
+        assert!(text_of(alpha).1.starts_with("# Alpha Rules"));
+
+        assert!(server
+            .read_book_resource("outline://missing")
+            .await
+            .is_err());
+        assert!(server
+            .read_book_resource("outline://guides/snapshot-workflow")
+            .await
+            .unwrap()
+            .is_none());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_resolve_book_ref_follows_pinned_shelf_order() {
+        let dir = std::env::temp_dir().join("outline-mcp-server-shelf-order-test");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).expect("create temp shelf dir");
+        std::fs::write(dir.join("alpha.json"), "{}").unwrap();
+        std::fs::write(dir.join("beta.json"), "{}").unwrap();
+
+        let server = OutlineMcpServer::new(dir.clone());
+        assert_eq!(server.resolve_book_ref("1").unwrap(), "alpha");
+
+        *server.shelf_order.write().unwrap() = Some(vec!["beta".into(), "alpha".into()]);
+        assert_eq!(server.resolve_book_ref("1").unwrap(), "beta");
+        assert_eq!(server.resolve_book_ref("2").unwrap(), "alpha");
+        assert!(server.resolve_book_ref("3").is_err());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_book_numbers_follow_natural_slug_order() {
+        let dir = std::env::temp_dir().join("outline-mcp-server-natural-order-test");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).expect("create temp shelf dir");
+        for slug in ["book10", "book2", "Book1", "book3"] {
+            std::fs::write(dir.join(format!("{slug}.json")), "{}").unwrap();
+        }
+
+        let server = OutlineMcpServer::new(dir.clone());
+        assert_eq!(
+            server.list_book_slugs().unwrap(),
+            ["Book1", "book2", "book3", "book10"]
+        );
+        // バイト順なら "3" は book2 だった
+        assert_eq!(server.resolve_book_ref("3").unwrap(), "book3");
+        assert_eq!(server.resolve_book_ref("4").unwrap(), "book10");
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_config_overrides_instructions_and_tool_descriptions() {
+        let mut config = ServerConfig {
+            instructions: Some("Always check the incident template first.".into()),
+            ..ServerConfig::default()
+        };
+        config
+            .tool_description_suffixes
+            .insert("toc".into(), "Start every session here.".into());
+        config
+            .tool_description_suffixes
+            .insert("no_such_tool".into(), "Ignored.".into());
+        let server = OutlineMcpServer::new(PathBuf::from("/tmp/test-shelf")).with_config(config);
+
+        let info = server.get_info();
+        assert_eq!(
+            info.instructions.as_deref(),
+            Some("Always check the incident template first.")
+        );
+        let tools = server.tool_router.list_all();
+        let toc = tools.iter().find(|t| t.name == "toc").unwrap();
+        assert!(toc
+            .description
+            .as_deref()
+            .unwrap()
+            .ends_with(" Start every session here."));
+        assert!(tools.iter().all(|t| t.name != "no_such_tool"));
+
+        let default = OutlineMcpServer::new(PathBuf::from("/tmp/test-shelf"));
+        assert_eq!(
+            default.get_info().instructions.as_deref(),
+            Some(DEFAULT_INSTRUCTIONS)
+        );
+    }
+
+    #[test]
+    fn test_compact_profile_lists_exactly_the_compact_tools() {
+        let config = ServerConfig {
+            tool_profile: ToolProfile::Compact,
+            ..ServerConfig::default()
+        };
+        let server = OutlineMcpServer::new(PathBuf::from("/tmp/test-shelf")).with_config(config);
+        let tools = server.tool_router.list_all();
+        let mut names: Vec<&str> = tools.iter().map(|t| t.name.as_ref()).collect();
+        names.sort_unstable();
+        let mut expected = ToolProfile::COMPACT_TOOLS.to_vec();
+        expected.sort_unstable();
+        assert_eq!(names, expected);
+        for tool in &tools {
+            let description = tool.description.as_deref().unwrap();
+            assert_eq!(Some(description), compact_description(&tool.name));
+        }
+
+        let full = OutlineMcpServer::new(PathBuf::from("/tmp/test-shelf"));
+        let full_tools = full.tool_router.list_all();
+        assert!(full_tools.len() > tools.len());
+        assert!(full_tools.iter().any(|t| t.name == "import"));
+    }
+
+    #[test]
+    fn test_cyclic_move_error_names_both_nodes() {
+        use outline_mcp_core::domain::model::book::{AddNodeRequest, Position};
+        use outline_mcp_core::domain::model::node::NodeType;
+
+        let mut book = TemplateBook::new("Plan", 4);
+        let add = |book: &mut TemplateBook, parent, title: &str| {
+            book.add_node(AddNodeRequest {
+                parent,
+                title: title.into(),
+                node_type: NodeType::Section,
+                body: None,
+                placeholder: None,
+                position: Position::Last,
+                properties: HashMap::new(),
+            })
+            .unwrap()
+        };
+        add(&mut book, None, "Design");
+        let implementation = add(&mut book, None, "Implementation");
+        add(&mut book, Some(implementation), "Coding");
+        add(&mut book, Some(implementation), "Review");
+        let testing = add(&mut book, Some(implementation), "Testing");
+
+        let server = OutlineMcpServer::new(PathBuf::from("/tmp/test-shelf"));
+        let cyclic = |new_parent| {
+            AppError::Domain(DomainError::CyclicMove {
+                node_id: implementation,
+                new_parent,
+            })
+        };
+        let none = ErrorContext::default();
+        assert_eq!(
+            server.describe_app_error(&book, &cyclic(testing), &none),
+            "cannot move '2 Implementation' under its own descendant '2-3 Testing'"
+        );
+        assert_eq!(
+            server.describe_app_error(&book, &cyclic(implementation), &none),
+            "cannot move '2 Implementation' under itself"
+        );
+        let err = server.to_mcp_error_in(&book, cyclic(testing), &none);
+        assert_eq!(err.code, rmcp::model::ErrorCode::INVALID_PARAMS);
+    }
+
+    #[test]
+    fn test_domain_errors_name_nodes_by_number_title_and_input() {
+        use outline_mcp_core::domain::model::book::{AddNodeRequest, Position};
+        use outline_mcp_core::domain::model::node::NodeType;
+
+        let mut book = TemplateBook::new("Plan", 2);
+        let add = |book: &mut TemplateBook, parent, title: &str| {
+            book.add_node(AddNodeRequest {
+                parent,
+                title: title.into(),
+                node_type: NodeType::Section,
+                body: None,
+                placeholder: None,
+                position: Position::Last,
+                properties: HashMap::new(),
+            })
+            .unwrap()
+        };
+        let design = add(&mut book, None, "Design");
+        add(&mut book, Some(design), "API");
+        let implementation = add(&mut book, None, "Implementation");
+        let testing = add(&mut book, Some(implementation), "Testing");
+        let server = OutlineMcpServer::new(PathBuf::from("/tmp/test-shelf"));
+
+        // 追加: node_id は新しい ID なので親で示す
+        let err = book
+            .add_node(AddNodeRequest {
+                parent: Some(testing),
+                title: "Unit".into(),
+                node_type: NodeType::Content,
+                body: None,
+                placeholder: None,
+                position: Position::Last,
+                properties: HashMap::new(),
+            })
+            .unwrap_err();
+        let ctx = ErrorContext {
+            refs: &[("2-1", testing)],
+            parent: Some(Some(testing)),
+        };
+        assert_eq!(
+            server.describe_app_error(&book, &err.into(), &ctx),
+            "max depth 2 exceeded adding under '2-1 Testing'. Deepest now: level 2 of 2 — 2 paths, e.g. 1-1 'API'; raise the limit with `book_config` `max_depth`"
+        );
+
+        // 移動: タイトルで指定された親は入力も添える
+        let err = book
+            .move_node(design, Some(testing), Position::At(0))
+            .unwrap_err();
+        let ctx = ErrorContext {
+            refs: &[("1", design), ("testing", testing)],
+            parent: Some(Some(testing)),
+        };
+        let mcp = server.to_mcp_error_in(&book, err.into(), &ctx);
+        assert_eq!(mcp.code, rmcp::model::ErrorCode::INVALID_PARAMS);
+        assert_eq!(
+            mcp.message,
+            "max depth 2 exceeded moving '1 Design' under '2-1 Testing' (given as 'testing'). Deepest now: level 2 of 2 — 2 paths, e.g. 1-1 'API'; raise the limit with `book_config` `max_depth`"
+        );
+
+        // 存在しないノードは利用者の入力で示す
+        let gone = NodeId::new();
+        let ctx = ErrorContext {
+            refs: &[("1-4", gone)],
+            parent: None,
+        };
+        let err = AppError::Domain(DomainError::NodeNotFound(gone));
+        assert_eq!(
+            server.describe_app_error(&book, &err, &ctx),
+            "node '1-4' not found (it may have been removed or renumbered; run `toc` for current IDs)"
+        );
+    }
 }
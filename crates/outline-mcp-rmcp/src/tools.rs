@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::path::PathBuf;
 
 use rmcp::{
@@ -6,31 +6,71 @@ use rmcp::{
     ErrorData as McpError,
 };
 
-use outline_mcp_core::application::eject::{EjectConfig, EjectFormat, EjectService, EjectTree};
-
-use crate::helpers::{build_hierarchical_ids, find_hierarchical_id, format_toc};
+use outline_mcp_core::application::backup::{is_backup, parse_backup, render_backup};
+use outline_mcp_core::application::dedupe::{add_deduped, DedupeMode};
+use outline_mcp_core::application::duplicates::find_duplicates;
+use outline_mcp_core::application::eject::{
+    CheckboxLabels, DepthFit, EjectConfig, EjectFormat, EjectService, EjectTree, EjectTreeNode,
+    IdConflict, MarkdownStyle,
+};
+use outline_mcp_core::application::error::AppError;
+use outline_mcp_core::application::lint::{lint, DEFAULT_DEEP_BRANCH_THRESHOLD};
+use outline_mcp_core::application::natural_sort::natural_cmp;
+use outline_mcp_core::application::prompts::collect_prompts;
+use outline_mcp_core::application::search::search_text;
+use outline_mcp_core::application::tree_diff::{diff_books, diff_books_by_path, ChangeKind};
+use outline_mcp_core::application::tree_view::build_tree_view;
+use outline_mcp_core::infra::snapshot::SnapshotInfo;
+
+use crate::helpers::{
+    absolute_path, apply_sibling_order, build_hierarchical_ids, clamp_note, find_hierarchical_id,
+    format_age, format_book_config, format_book_info, format_change_report, format_changes,
+    format_node_detail, format_parent_path, format_placeholders, format_prompts,
+    format_shelf_search, format_stats, format_status, format_toc, mutation_reply,
+    reorder_violation, sort_key_conflict, update_matching_in, Numbering, ShelfSearchHits,
+    TocOptions,
+};
 use crate::request::{
-    normalize_text, parse_node_id, parse_node_status, parse_node_type, sanitize_for_filename,
-    unescape_newlines, validate_filename, validate_import_path, validate_slug, McpBatchMoveRequest,
-    McpBatchUpdateRequest, McpBookHistoryRequest, McpDumpRequest, McpEjectRequest,
-    McpGenRoutingRequest, McpImportRequest, McpInitRequest, McpNodeCreateRequest,
-    McpNodeHistoryRequest, McpNodeMoveRequest, McpNodeQueryRequest, McpNodeUpdateRequest,
-    McpSelectBookRequest, McpShelfRequest, McpSnapshotCreateRequest, McpSnapshotDiffRequest,
-    McpSnapshotDumpAllRequest, McpSnapshotDumpRequest, McpSnapshotListRequest,
-    McpSnapshotRestoreRequest, McpSnapshotTagRequest, McpTocRequest,
+    input_title, normalize_text, parse_dedupe_mode, parse_depth_strategy, parse_eject_format,
+    parse_id_conflict, parse_markdown_style, parse_node_id, parse_node_status, parse_node_type,
+    parse_numbering, parse_placeholder_format, parse_position, parse_timestamp_arg,
+    resolve_eject_settings, resolve_output_path, sanitize_for_filename, unescape_newlines,
+    validate_filename, validate_import_path, validate_slug, EjectSettings, ImportFormat,
+    McpAssignRequest, McpBatchMoveRequest, McpBatchUpdateRequest, McpBookConfigRequest,
+    McpBookHistoryRequest, McpBookInfoRequest, McpChangesRequest, McpCompareWithFileRequest,
+    McpDumpRequest, McpDuplicatesRequest, McpEjectPromptsRequest, McpEjectRequest,
+    McpGenRoutingRequest, McpHelpRequest, McpImportRequest, McpInitRequest, McpLintRequest,
+    McpNodeCreateRequest, McpNodeDependRequest, McpNodeGetRequest, McpNodeHistoryRequest,
+    McpNodeLinkUrlRequest, McpNodeMoveRequest, McpNodeQueryRequest, McpNodeTemplatizeRequest,
+    McpNodeUpdateBulkRequest, McpNodeUpdateRequest, McpPlaceholdersRequest, McpRecentRequest,
+    McpReorderBookRequest, McpResetProgressRequest, McpRetypeSubtreeRequest, McpSearchShelfRequest,
+    McpSeedPlaceholdersRequest, McpSelectBookRequest, McpShelfCategoriesRequest,
+    McpShelfExportRequest, McpShelfHandbookRequest, McpShelfRequest, McpShelfTagsRequest,
+    McpSnapshotCreateRequest, McpSnapshotDiffRequest, McpSnapshotDumpAllRequest,
+    McpSnapshotDumpRequest, McpSnapshotListRequest, McpSnapshotRestoreRequest,
+    McpSnapshotTagRequest, McpSplitBookRequest, McpStatsRequest, McpStatusRequest, McpTocRequest,
+    McpTreeJsonRequest, McpVerifyRoundtripRequest, NODE_STATUS_NAMES, NODE_TYPE_NAMES,
+};
+use crate::server::{
+    read_recovering, write_recovering, ErrorContext, OutlineMcpServer, ResolvePolicy,
+    BOOK_REF_FORMATS, NODE_REF_FORMATS,
 };
-use crate::server::OutlineMcpServer;
 
-use outline_mcp_core::domain::model::book::AddNodeRequest;
+use outline_mcp_core::domain::error::DomainError;
 use outline_mcp_core::domain::model::book::UpdateNodeRequest;
+use outline_mcp_core::domain::model::book::{
+    AddNodeRequest, EjectDefaults, Position, TemplateBook,
+};
 use outline_mcp_core::domain::model::changelog::{ChangeAction, ChangeEntry, NodeStatus};
+use outline_mcp_core::domain::model::id::NodeId;
+use outline_mcp_core::domain::model::node::{NodeType, TemplateNode};
 use outline_mcp_core::domain::model::timestamp::Timestamp;
 
 #[tool_router(vis = "pub(crate)")]
 impl OutlineMcpServer {
     #[tool(
         name = "node_create",
-        description = "Add a new node to the book. Use a parent ID from `toc` output (e.g. '1') to nest under a section, or omit for root-level.",
+        description = "Add a new node to the book. Use a parent ID from `toc` output (e.g. '1') to nest under a section, or omit for root-level. `node_type` defaults to section when `children` are given and to content otherwise. Pass `children` to create its child nodes in the same call (one level only; nothing is created if the children would exceed max_depth). Set `idempotency_key` so a retried call returns the original node instead of a duplicate.",
         annotations(
             read_only_hint = false,
             destructive_hint = false,
@@ -43,36 +83,161 @@ impl OutlineMcpServer {
         Parameters(req): Parameters<McpNodeCreateRequest>,
     ) -> Result<CallToolResult, McpError> {
         let svc = self.service().await?;
-        let node_type = parse_node_type(&req.node_type)?;
-        let parent = match req.parent.as_deref() {
-            Some(s) => Some(self.resolve_id(s).await?),
+        let dedupe = parse_dedupe_mode(req.dedupe.as_deref())?;
+        let position = parse_position(req.position.as_ref())?;
+
+        // 再送された idempotency_key には最初に作ったノードを返す
+        let idempotency_key = req
+            .idempotency_key
+            .as_deref()
+            .map(str::trim)
+            .filter(|k| !k.is_empty())
+            .map(str::to_string);
+        let mut idempotency = match &idempotency_key {
+            Some(key) => {
+                let slug = self.selected_slug().unwrap_or_default();
+                let cache = self.idempotency.lock().await;
+                Some((cache, slug, key.clone()))
+            }
             None => None,
         };
 
-        let add_req = AddNodeRequest {
-            parent,
-            title: unescape_newlines(&req.title),
-            node_type,
-            body: normalize_text(req.body),
-            placeholder: normalize_text(req.placeholder),
-            position: req.position.unwrap_or(usize::MAX),
-            properties: req.properties.unwrap_or_default(),
+        let children = req.children.unwrap_or_default();
+        if children.iter().any(|c| c.children.is_some()) {
+            return Err(McpError::invalid_params(
+                "`children` is one level deep only. Create the child first and call `node_create` with it as parent, or build the outline as JSON and use `import`.",
+                None,
+            ));
+        }
+        // 省略時は、子を一緒に作るなら Section、そうでなければ Content（多くは実行する項目）
+        let node_type = match req.node_type.as_deref() {
+            Some(t) => parse_node_type(t)?,
+            None if !children.is_empty() => NodeType::Section,
+            None => NodeType::Content,
         };
+        let child_reqs = children
+            .into_iter()
+            .map(|c| {
+                Ok(AddNodeRequest {
+                    parent: None,
+                    title: input_title(&c.title, self.config.normalize_titles),
+                    node_type: match c.node_type.as_deref() {
+                        Some(t) => parse_node_type(t)?,
+                        None => NodeType::Content,
+                    },
+                    body: normalize_text(c.body),
+                    placeholder: normalize_text(c.placeholder),
+                    position: Position::Last,
+                    properties: HashMap::new(),
+                })
+            })
+            .collect::<Result<Vec<_>, McpError>>()?;
+        let titles: Vec<&str> = std::iter::once(req.title.as_str())
+            .chain(child_reqs.iter().map(|c| c.title.as_str()))
+            .collect();
+        self.check_mutation_budget(titles.len(), 0, &titles, req.confirm)?;
+
+        // 親の解決・追加・応答の階層番号を、1回読み込んだ同じ Book で行う
+        let ((msg, hier, created), warnings) = svc
+            .with_book(|book| {
+                if let Some((cache, slug, key)) = idempotency.as_mut() {
+                    if let Some(existing) = cache.get(slug, key) {
+                        match book.get_node(existing) {
+                            Some(node) => {
+                                let hier = find_hierarchical_id(book, existing)
+                                    .unwrap_or_else(|| self.short_id(existing));
+                                let msg = format!(
+                                    "Already created: {hier}. {} (idempotency_key '{key}'; nothing new was added)",
+                                    node.title()
+                                );
+                                return Ok(((msg, hier, None), Vec::new()));
+                            }
+                            // 既に削除されたノードのキーは忘れて作り直す
+                            None => cache.forget(slug, key),
+                        }
+                    }
+                }
 
-        let (id, warning) = svc.add_node(add_req).await.map_err(Self::to_mcp_error)?;
+                let parent = req
+                    .parent
+                    .as_deref()
+                    .map(|s| self.resolve_id_in(book, s, ResolvePolicy::Lenient))
+                    .transpose()?;
+                let refs: Vec<(&str, NodeId)> = req.parent.as_deref().zip(parent).into_iter().collect();
+                let ctx = ErrorContext {
+                    refs: &refs,
+                    parent: Some(parent),
+                };
+                let add_req = AddNodeRequest {
+                    parent,
+                    title: input_title(&req.title, self.config.normalize_titles),
+                    node_type,
+                    body: normalize_text(req.body),
+                    placeholder: normalize_text(req.placeholder),
+                    position,
+                    properties: req.properties.unwrap_or_default(),
+                };
+                let added = add_deduped(book, add_req, child_reqs, dedupe)
+                    .map_err(|e| self.to_mcp_error_in(book, e.into(), &ctx))?;
+                let id = added.node_id;
+                if let Some(command) = normalize_text(req.command).filter(|_| added.placed.is_some()) {
+                    book.update_node(
+                        id,
+                        UpdateNodeRequest {
+                            command: Some(Some(command)),
+                            ..Default::default()
+                        },
+                    )
+                    .map_err(|e| self.to_mcp_error_in(book, e.into(), &ctx))?;
+                }
 
-        // 階層番号を逆引き
-        let book = svc.read_tree().await.map_err(Self::to_mcp_error)?;
-        let hier = find_hierarchical_id(&book, id).unwrap_or_else(|| id.short().to_string());
+                let hier = find_hierarchical_id(book, id).unwrap_or_else(|| self.short_id(id));
+                let title = book.get_node(id).map(|n| n.title()).unwrap_or("?");
+                let mut msg = match added.placed {
+                    Some(_) => format!("Created: {hier}. {title}"),
+                    None => format!(
+                        "Already exists: {hier}. {title} (dedupe '{}'; not created)",
+                        req.dedupe.as_deref().unwrap_or("off")
+                    ),
+                };
+                for child in &added.created_children {
+                    msg.push_str(&format!(
+                        "\n  {}. {}",
+                        find_hierarchical_id(book, *child)
+                            .unwrap_or_else(|| self.short_id(*child)),
+                        book.get_node(*child).map(|n| n.title()).unwrap_or("?")
+                    ));
+                }
+                let counts = added.counts;
+                if counts.skipped > 0 || counts.updated > 0 {
+                    msg.push_str(&format!(
+                        "\nDedupe: {} created, {} skipped, {} updated",
+                        counts.created, counts.skipped, counts.updated
+                    ));
+                }
+                // 末尾へ丸められた position をエージェントに知らせる
+                if let Some(note) = added.placed.and_then(|p| clamp_note("inserted", &p)) {
+                    msg.push_str(&format!("\n{note}"));
+                }
+                let changes = added
+                    .placed
+                    .map(|p| p.node_id)
+                    .into_iter()
+                    .chain(added.created_children)
+                    .map(|created| (created, ChangeAction::Create))
+                    .chain(added.updated.into_iter().map(|u| (u, ChangeAction::Update)))
+                    .collect();
+                Ok(((msg, hier, Some(id)), changes))
+            })
+            .await
+            .map_err(Self::to_mcp_error)??;
 
-        let mut msg = format!(
-            "Created: {}. {}",
-            hier,
-            book.get_node(id).map(|n| n.title()).unwrap_or("?")
-        );
-        if let Some(w) = warning {
-            msg.push_str(&format!("\n[WARNING] {w}"));
+        if let (Some((cache, slug, key)), Some(id)) = (idempotency.as_mut(), created) {
+            cache.insert(slug, key, id);
         }
+        drop(idempotency);
+
+        let msg = mutation_reply(req.quiet, &hier, msg, warnings);
         Ok(CallToolResult::success(vec![rmcp::model::Content::text(
             msg,
         )]))
@@ -80,7 +245,7 @@ impl OutlineMcpServer {
 
     #[tool(
         name = "node_update",
-        description = "Edit a node's title, body, type, or placeholder. Specify the node by ID from `toc` output (e.g. '2-3'). Only specified fields are changed.",
+        description = "Edit a node's title, body, type, or placeholder. Specify the node by ID from `toc` output (e.g. '2-3'). Only specified fields are changed. Add `new_parent` / `position` to also relocate the node in the same save.",
         annotations(
             read_only_hint = false,
             destructive_hint = false,
@@ -93,34 +258,169 @@ impl OutlineMcpServer {
         Parameters(req): Parameters<McpNodeUpdateRequest>,
     ) -> Result<CallToolResult, McpError> {
         let svc = self.service().await?;
-        let id = self.resolve_id(&req.node_id).await?;
-        let node_type = req.node_type.as_deref().map(parse_node_type).transpose()?;
-
-        let status = req.status.as_deref().map(parse_node_status).transpose()?;
+        let update_req = req
+            .fields
+            .to_update_request(self.config.null_clears_fields, self.config.normalize_titles)?;
+        let relocating = req.new_parent.is_some() || req.position.is_some();
+        let position = parse_position(req.position.as_ref())?;
+
+        let ((msg, hier), warnings) = svc
+            .with_book(|book| {
+                let id = self.resolve_id_in(book, &req.node_id, ResolvePolicy::Lenient)?;
+                // new_parent / position 指定時は更新と移動を1回の保存で行う
+                let relocate = if relocating {
+                    let new_parent = match req.new_parent.as_deref().map(str::trim) {
+                        Some("root") | Some("") => None,
+                        Some(s) => Some(self.resolve_id_in(book, s, ResolvePolicy::Lenient)?),
+                        None => book.get_node(id).and_then(|n| n.parent()),
+                    };
+                    Some((new_parent, position))
+                } else {
+                    None
+                };
 
-        let update_req = UpdateNodeRequest {
-            title: req.title.map(|t| unescape_newlines(&t)),
-            body: req.body.map(normalize_text),
-            node_type,
-            placeholder: req.placeholder.map(normalize_text),
-            properties: req.properties,
-            status,
-        };
+                let mut refs = vec![(req.node_id.as_str(), id)];
+                if let (Some(input), Some((Some(parent), _))) =
+                    (req.new_parent.as_deref(), relocate)
+                {
+                    refs.push((input, parent));
+                }
+                let ctx = ErrorContext {
+                    refs: &refs,
+                    parent: relocate.map(|(parent, _)| parent),
+                };
+                book.update_node(id, update_req)
+                    .map_err(|e| self.to_mcp_error_in(book, e.into(), &ctx))?;
+                req.fields
+                    .check_applies_to(book.get_node(id).map(|n| n.node_type()))?;
+                let mut changes = vec![(id, ChangeAction::Update)];
+                let placed = match relocate {
+                    Some((new_parent, position)) => {
+                        // 循環の説明には移動前の並びを使う（失敗した移動は Book を変えない）
+                        let placed = book
+                            .move_node(id, new_parent, position)
+                            .map_err(|e| self.to_mcp_error_in(book, e.into(), &ctx))?;
+                        changes.push((id, ChangeAction::Move));
+                        Some(placed)
+                    }
+                    None => None,
+                };
 
-        let ((), warning) = svc
-            .update_node(id, update_req)
+                let hier = find_hierarchical_id(book, id).unwrap_or_else(|| self.short_id(id));
+                let mut msg = format!(
+                    "{}: {}. {}",
+                    if relocating {
+                        "Updated and moved"
+                    } else {
+                        "Updated"
+                    },
+                    hier,
+                    book.get_node(id).map(|n| n.title()).unwrap_or("?")
+                );
+                if let Some(note) = placed.as_ref().and_then(|p| clamp_note("moved", p)) {
+                    msg.push_str(&format!("\n{note}"));
+                }
+                Ok(((msg, hier), changes))
+            })
             .await
-            .map_err(Self::to_mcp_error)?;
+            .map_err(Self::to_mcp_error)??;
 
-        let book = svc.read_tree().await.map_err(Self::to_mcp_error)?;
-        let hier = find_hierarchical_id(&book, id).unwrap_or_else(|| id.short().to_string());
+        let msg = mutation_reply(req.quiet, &hier, msg, warnings);
+        Ok(CallToolResult::success(vec![rmcp::model::Content::text(
+            msg,
+        )]))
+    }
 
-        let mut msg = format!(
-            "Updated: {}. {}",
-            hier,
-            book.get_node(id).map(|n| n.title()).unwrap_or("?")
-        );
-        if let Some(w) = warning {
+    #[tool(
+        name = "node_update_bulk",
+        description = "Apply the same edit to several nodes at once (e.g. node_ids ['2-1', '2-3'] with owner 'alice', node_type 'content' or properties). Takes the same fields as `node_update`, with the same meaning (including the `clear_*` flags). All IDs are resolved before anything changes and everything is saved together: one bad ID or rejected edit leaves the book untouched.",
+        annotations(
+            read_only_hint = false,
+            destructive_hint = false,
+            idempotent_hint = true,
+            open_world_hint = false
+        )
+    )]
+    async fn node_update_bulk(
+        &self,
+        Parameters(req): Parameters<McpNodeUpdateBulkRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        if req.node_ids.is_empty() {
+            return Err(McpError::invalid_params("node_ids must not be empty", None));
+        }
+        let null_clears = self.config.null_clears_fields;
+        let normalize_titles = self.config.normalize_titles;
+        // 項目の検証は解決の前に済ませる（ノードに依らない）
+        req.fields
+            .to_update_request(null_clears, normalize_titles)?;
+        let svc = self.service().await?;
+
+        let (lines, warnings) = svc
+            .with_book(|book| {
+                // すべて同じ Book に対して解決してから変更する（番号のずれを避ける）
+                let mut ids = Vec::with_capacity(req.node_ids.len());
+                let mut unresolved = Vec::new();
+                for node_ref in &req.node_ids {
+                    match self.resolve_id_in(book, node_ref, ResolvePolicy::Lenient) {
+                        Ok(id) if !ids.contains(&id) => ids.push(id),
+                        Ok(_) => {}
+                        Err(e) => unresolved.push(format!("'{node_ref}': {}", e.message)),
+                    }
+                }
+                if !unresolved.is_empty() {
+                    return Err(McpError::invalid_params(
+                        format!(
+                            "Could not resolve {} of {} node(s); nothing was changed.\n- {}",
+                            unresolved.len(),
+                            req.node_ids.len(),
+                            unresolved.join("\n- ")
+                        ),
+                        None,
+                    ));
+                }
+
+                let labels: Vec<String> = ids
+                    .iter()
+                    .map(|&id| find_hierarchical_id(book, id).unwrap_or_else(|| self.short_id(id)))
+                    .collect();
+                for (&id, label) in ids.iter().zip(&labels) {
+                    let refs = [(label.as_str(), id)];
+                    let ctx = ErrorContext {
+                        refs: &refs,
+                        parent: None,
+                    };
+                    let fail = |e: McpError| {
+                        McpError::invalid_params(
+                            format!("{label}: {}; nothing was changed.", e.message),
+                            None,
+                        )
+                    };
+                    let update = req
+                        .fields
+                        .to_update_request(null_clears, normalize_titles)?;
+                    book.update_node(id, update)
+                        .map_err(|e| fail(self.to_mcp_error_in(book, e.into(), &ctx)))?;
+                    req.fields
+                        .check_applies_to(book.get_node(id).map(|n| n.node_type()))
+                        .map_err(fail)?;
+                }
+
+                let lines: Vec<String> = ids
+                    .iter()
+                    .zip(&labels)
+                    .map(|(&id, label)| {
+                        let title = book.get_node(id).map(|n| n.title()).unwrap_or("?");
+                        format!("- {label}. {title}: updated")
+                    })
+                    .collect();
+                let changes = ids.iter().map(|&id| (id, ChangeAction::Update)).collect();
+                Ok((lines, changes))
+            })
+            .await
+            .map_err(Self::to_mcp_error)??;
+
+        let mut msg = format!("Updated {} node(s):\n{}", lines.len(), lines.join("\n"));
+        for w in warnings.into_iter().flatten() {
             msg.push_str(&format!("\n[WARNING] {w}"));
         }
         Ok(CallToolResult::success(vec![rmcp::model::Content::text(
@@ -129,127 +429,157 @@ impl OutlineMcpServer {
     }
 
     #[tool(
-        name = "node_move",
-        description = "Move or delete a node (and its descendants). Specify node by ID from `toc` output (e.g. '2-3'). Action 'move' relocates, 'remove' deletes.",
+        name = "node_templatize",
+        description = "Turn filled content back into a template: clear the body and set a placeholder hint in one call. With `recursive: true`, every leaf under the node is templatized instead.",
         annotations(
             read_only_hint = false,
             destructive_hint = true,
-            idempotent_hint = false,
+            idempotent_hint = true,
             open_world_hint = false
         )
     )]
-    async fn node_move(
+    async fn node_templatize(
         &self,
-        Parameters(req): Parameters<McpNodeMoveRequest>,
+        Parameters(req): Parameters<McpNodeTemplatizeRequest>,
     ) -> Result<CallToolResult, McpError> {
         let svc = self.service().await?;
-        let id = self.resolve_id(&req.node_id).await?;
+        let placeholder = unescape_newlines(&req.placeholder);
+
+        // 解決・変更・応答を、1回読み込んだ同じ Book で行う（単体・再帰とも1回の保存）
+        let (mut msg, warnings) = svc
+            .with_book(|book| {
+                let id = self.resolve_id_in(book, &req.node_id, ResolvePolicy::Strict)?;
+                let targets: Vec<NodeId> = if req.recursive {
+                    book.subtree_nodes(id)
+                        .into_iter()
+                        .filter(|n| n.is_leaf())
+                        .map(|n| n.id())
+                        .collect()
+                } else {
+                    vec![id]
+                };
+                let ctx = ErrorContext {
+                    refs: &[(req.node_id.as_str(), id)],
+                    parent: None,
+                };
+                for &target in &targets {
+                    book.update_node(
+                        target,
+                        UpdateNodeRequest {
+                            body: Some(None),
+                            placeholder: Some(Some(placeholder.clone())),
+                            ..Default::default()
+                        },
+                    )
+                    .map_err(|e| self.to_mcp_error_in(book, e.into(), &ctx))?;
+                }
 
-        match req.action.as_str() {
-            "move" => {
-                let new_parent = match req.new_parent.as_deref() {
-                    Some(s) => Some(self.resolve_id(s).await?),
-                    None => None,
+                let hier = find_hierarchical_id(book, id).unwrap_or_else(|| self.short_id(id));
+                let title = book.get_node(id).map(|n| n.title()).unwrap_or("?");
+                let msg = if req.recursive {
+                    format!(
+                        "Templatized {} leaf node(s) under {hier}. {title}",
+                        targets.len()
+                    )
+                } else {
+                    format!("Templatized: {hier}. {title}")
                 };
-                let position = req.position.unwrap_or(usize::MAX);
-                let ((), warning) = svc
-                    .move_node(id, new_parent, position)
-                    .await
-                    .map_err(Self::to_mcp_error)?;
+                let changes = targets
+                    .into_iter()
+                    .map(|t| (t, ChangeAction::Update))
+                    .collect();
+                Ok((msg, changes))
+            })
+            .await
+            .map_err(Self::to_mcp_error)??;
 
-                let book = svc.read_tree().await.map_err(Self::to_mcp_error)?;
-                let hier =
-                    find_hierarchical_id(&book, id).unwrap_or_else(|| id.short().to_string());
-                let mut msg = format!(
-                    "Moved → {}. {}",
-                    hier,
-                    book.get_node(id).map(|n| n.title()).unwrap_or("?")
-                );
-                if let Some(w) = warning {
-                    msg.push_str(&format!("\n[WARNING] {w}"));
-                }
-                Ok(CallToolResult::success(vec![rmcp::model::Content::text(
-                    msg,
-                )]))
-            }
-            "remove" => {
-                // 削除前に階層番号を取得
-                let book = svc.read_tree().await.map_err(Self::to_mcp_error)?;
-                let hier =
-                    find_hierarchical_id(&book, id).unwrap_or_else(|| id.short().to_string());
-                let title = book
-                    .get_node(id)
-                    .map(|n| n.title().to_string())
-                    .unwrap_or_default();
-
-                let ((), warning) = svc.remove_node(id).await.map_err(Self::to_mcp_error)?;
-                let mut msg = format!("Removed: {}. {} (and descendants)", hier, title);
-                if let Some(w) = warning {
-                    msg.push_str(&format!("\n[WARNING] {w}"));
-                }
-                Ok(CallToolResult::success(vec![rmcp::model::Content::text(
-                    msg,
-                )]))
-            }
-            other => Err(McpError::invalid_params(
-                format!("Unknown action: '{other}'. Use: move, remove"),
-                None,
-            )),
+        for w in warnings.into_iter().flatten() {
+            msg.push_str(&format!("\n[WARNING] {w}"));
         }
+        Ok(CallToolResult::success(vec![rmcp::model::Content::text(
+            msg,
+        )]))
     }
 
     #[tool(
-        name = "toc",
-        description = "Show table of contents with numbered IDs (e.g. 1, 1-1, 2-3). Run this first — use the returned IDs to specify nodes in `checklist`, `node_create`, and other tools.",
+        name = "node_depend",
+        description = "Declare that a node is blocked by another (e.g. '2-2' cannot start until '2-1' is done). Blocked items are marked `(blocked by 2-1)` in `checklist` exports. Pass `remove: true` to drop the dependency.",
         annotations(
-            read_only_hint = true,
+            read_only_hint = false,
             destructive_hint = false,
+            idempotent_hint = true,
             open_world_hint = false
         )
     )]
-    async fn toc(
+    async fn node_depend(
         &self,
-        Parameters(req): Parameters<McpTocRequest>,
+        Parameters(req): Parameters<McpNodeDependRequest>,
     ) -> Result<CallToolResult, McpError> {
         let svc = self.service().await?;
-        let book = svc.read_tree().await.map_err(Self::to_mcp_error)?;
-
-        let subtree_id = match req.subtree_root.as_deref() {
-            Some(s) => Some(self.resolve_id(s).await?),
-            None => None,
-        };
 
-        let mut nodes = match subtree_id {
-            Some(root_id) => book.subtree_nodes(root_id),
-            None => book.all_nodes_dfs(),
-        };
+        // 解決・変更・応答を、1回読み込んだ同じ Book で行う
+        let (mut msg, warnings) = svc
+            .with_book(|book| {
+                let id = self.resolve_id_in(book, &req.node_id, ResolvePolicy::Lenient)?;
+                let on = self.resolve_id_in(book, &req.depends_on, ResolvePolicy::Lenient)?;
+                let ctx = ErrorContext {
+                    refs: &[(req.node_id.as_str(), id), (req.depends_on.as_str(), on)],
+                    parent: None,
+                };
+                let changed = if req.remove {
+                    book.remove_dependency(id, on)
+                } else {
+                    book.add_dependency(id, on)
+                }
+                .map_err(|e| self.to_mcp_error_in(book, e.into(), &ctx))?;
 
-        // プロパティフィルタ
-        if let Some(ref filter) = req.filter {
-            if !filter.is_empty() {
-                nodes.retain(|node| {
-                    filter
-                        .iter()
-                        .all(|(k, v)| node.get_property(k).map(|pv| pv == v).unwrap_or(false))
-                });
-            }
-        }
+                let label = |nid| {
+                    let hier =
+                        find_hierarchical_id(book, nid).unwrap_or_else(|| self.short_id(nid));
+                    let title = book.get_node(nid).map(|n| n.title()).unwrap_or("?");
+                    format!("{hier}. {title}")
+                };
+                let msg = match (req.remove, changed) {
+                    (false, true) => format!(
+                        "Dependency added: {} is blocked by {}",
+                        label(id),
+                        label(on)
+                    ),
+                    (false, false) => format!(
+                        "No change: {} is already blocked by {}",
+                        label(id),
+                        label(on)
+                    ),
+                    (true, true) => format!(
+                        "Dependency removed: {} is no longer blocked by {}",
+                        label(id),
+                        label(on)
+                    ),
+                    (true, false) => {
+                        format!("No change: {} was not blocked by {}", label(id), label(on))
+                    }
+                };
+                let changes = if changed {
+                    vec![(id, ChangeAction::Update)]
+                } else {
+                    Vec::new()
+                };
+                Ok((msg, changes))
+            })
+            .await
+            .map_err(Self::to_mcp_error)??;
 
-        if nodes.is_empty() {
-            return Ok(CallToolResult::success(vec![rmcp::model::Content::text(
-                "No matching nodes. Use `node_create` to add nodes.",
-            )]));
+        for w in warnings.into_iter().flatten() {
+            msg.push_str(&format!("\n[WARNING] {w}"));
         }
-
-        let output = format_toc(&book, &nodes);
         Ok(CallToolResult::success(vec![rmcp::model::Content::text(
-            output,
+            msg,
         )]))
     }
 
     #[tool(
-        name = "checklist",
-        description = "Export a section as a Markdown checklist with checkboxes. First run `toc` to find the section ID, then pass it as subtree_root (e.g. '2'). Omit subtree_root for full book export. Book is NOT modified.",
+        name = "node_link_url",
+        description = "Attach an external link (dashboard, runbook, ticket) to a node, e.g. url 'https://grafana.example/d/api', label 'API dashboard'. `checklist` lists links under the item as `[label](url)`; `node_get` shows them. Pass `remove: true` with the URL or label to drop one.",
         annotations(
             read_only_hint = false,
             destructive_hint = false,
@@ -257,1193 +587,1503 @@ impl OutlineMcpServer {
             open_world_hint = false
         )
     )]
-    async fn checklist(
+    async fn node_link_url(
         &self,
-        Parameters(req): Parameters<McpEjectRequest>,
+        Parameters(req): Parameters<McpNodeLinkUrlRequest>,
     ) -> Result<CallToolResult, McpError> {
         let svc = self.service().await?;
-        let book = svc.read_tree().await.map_err(Self::to_mcp_error)?;
-
-        let include_placeholders = req.include_placeholders.unwrap_or(true);
-        let format = match req.format.as_deref() {
-            Some("json") => EjectFormat::Json,
-            Some("markdown") | None => EjectFormat::Markdown,
-            Some(other) => {
-                return Err(McpError::invalid_params(
-                    format!("Unknown format: '{other}'. Use: markdown, json"),
-                    None,
-                ))
-            }
-        };
-        let subtree_root = match req.subtree_root.as_deref() {
-            Some(s) => Some(self.resolve_id(s).await?),
-            None => None,
-        };
-
-        let output_dir = req
-            .output_dir
-            .map(PathBuf::from)
-            .unwrap_or_else(|| PathBuf::from("."));
 
-        let default_ext = match format {
-            EjectFormat::Markdown => "md",
-            EjectFormat::Json => "json",
-        };
-        let filename = req.filename.unwrap_or_else(|| {
-            match subtree_root {
-                Some(root_id) => {
-                    // subtree指定時: "2_Testing.md", "6-3_DSL_Architecture.md"
-                    let hier =
-                        find_hierarchical_id(&book, root_id).unwrap_or_else(|| "0".to_string());
-                    let title = book
-                        .get_node(root_id)
-                        .map(|n| sanitize_for_filename(n.title()))
-                        .unwrap_or_else(|| "unknown".to_string());
-                    format!("{}_{}.{}", hier, title, default_ext)
-                }
-                None => {
-                    format!("{}.{}", sanitize_for_filename(book.title()), default_ext)
+        // 解決・変更・応答を、1回読み込んだ同じ Book で行う
+        let (mut msg, warnings) = svc
+            .with_book(|book| {
+                let id = self.resolve_id_in(book, &req.node_id, ResolvePolicy::Lenient)?;
+                let ctx = ErrorContext {
+                    refs: &[(req.node_id.as_str(), id)],
+                    parent: None,
+                };
+                let changed = if req.remove {
+                    book.remove_link(id, &req.url)
+                } else {
+                    book.add_link(id, req.label, &req.url)
                 }
-            }
-        });
-        validate_filename(&filename)?;
-
-        let config = EjectConfig {
-            output_dir,
-            filename,
-            include_placeholders,
-            format,
-            subtree_root,
-        };
+                .map_err(|e| match e {
+                    DomainError::InvalidLinkUrl(_) => McpError::invalid_params(e.to_string(), None),
+                    other => self.to_mcp_error_in(book, other.into(), &ctx),
+                })?;
 
-        let path = EjectService::eject(&book, &config).map_err(Self::to_mcp_error)?;
+                let hier = find_hierarchical_id(book, id).unwrap_or_else(|| self.short_id(id));
+                let node = book.get_node(id);
+                let title = node.map(|n| n.title()).unwrap_or("?");
+                let url = req.url.trim();
+                let msg = match (req.remove, changed) {
+                    (false, true) => {
+                        let label = node
+                            .and_then(|n| n.links().iter().find(|(_, u)| u == url))
+                            .map_or(url, |(l, _)| l.as_str());
+                        format!("Link added to {hier}. {title}: [{label}]({url})")
+                    }
+                    (false, false) => format!("No change: {hier}. {title} already links to {url}"),
+                    (true, true) => format!("Link removed from {hier}. {title}: {url}"),
+                    (true, false) => format!("No change: {hier}. {title} has no link '{url}'"),
+                };
+                let changes = if changed {
+                    vec![(id, ChangeAction::Update)]
+                } else {
+                    Vec::new()
+                };
+                Ok((msg, changes))
+            })
+            .await
+            .map_err(Self::to_mcp_error)??;
 
+        for w in warnings.into_iter().flatten() {
+            msg.push_str(&format!("\n[WARNING] {w}"));
+        }
         Ok(CallToolResult::success(vec![rmcp::model::Content::text(
-            format!("Checklist exported to: {}", path.display()),
+            msg,
         )]))
     }
 
     #[tool(
-        name = "import",
-        description = "Import a book from a JSON file (previously exported with `checklist` format: json). Replaces the current book entirely.",
+        name = "assign",
+        description = "Set the owner of several nodes at once (e.g. node_ids ['2-1', '2-3'], owner 'alice'). Pass an empty owner to unassign. Use `toc` with `show_owners` or `checklist` with `filter_owner` to view each person's slice.",
         annotations(
             read_only_hint = false,
-            destructive_hint = true,
-            idempotent_hint = false,
+            destructive_hint = false,
+            idempotent_hint = true,
             open_world_hint = false
         )
     )]
-    async fn import(
+    async fn assign(
         &self,
-        Parameters(req): Parameters<McpImportRequest>,
+        Parameters(req): Parameters<McpAssignRequest>,
     ) -> Result<CallToolResult, McpError> {
+        if req.node_ids.is_empty() {
+            return Err(McpError::invalid_params("node_ids must not be empty", None));
+        }
+        let owner = req.owner.trim();
+        let owner = (!owner.is_empty()).then(|| owner.to_string());
         let svc = self.service().await?;
-        let import_path = validate_import_path(&req.file_path)?;
-        let content = std::fs::read_to_string(&import_path)
-            .map_err(|e| McpError::internal_error(format!("Failed to read file: {e}"), None))?;
-        let tree: EjectTree = serde_json::from_str(&content)
-            .map_err(|e| McpError::invalid_params(format!("Invalid JSON: {e}"), None))?;
-
-        let book = EjectService::import_tree(&tree).map_err(Self::to_mcp_error)?;
-        let node_count = book.node_count();
-        svc.save_book(&book).await.map_err(Self::to_mcp_error)?;
 
+        // すべて同じ Book に対して解決してから変更し、1回で保存する（位置番号のずれを避ける）
+        let (count, warnings) = svc
+            .with_book(|book| {
+                let refs = req
+                    .node_ids
+                    .iter()
+                    .map(|node_ref| {
+                        self.resolve_id_in(book, node_ref, ResolvePolicy::Lenient)
+                            .map(|id| (node_ref.as_str(), id))
+                    })
+                    .collect::<Result<Vec<_>, McpError>>()?;
+                let ctx = ErrorContext {
+                    refs: &refs,
+                    parent: None,
+                };
+                for &(_, id) in &refs {
+                    book.update_node(
+                        id,
+                        UpdateNodeRequest {
+                            owner: Some(owner.clone()),
+                            ..Default::default()
+                        },
+                    )
+                    .map_err(|e| self.to_mcp_error_in(book, e.into(), &ctx))?;
+                }
+                let changes = refs
+                    .iter()
+                    .map(|&(_, id)| (id, ChangeAction::Update))
+                    .collect();
+                Ok((refs.len(), changes))
+            })
+            .await
+            .map_err(Self::to_mcp_error)??;
+
+        let mut msg = match &owner {
+            Some(o) => format!("Assigned {count} node(s) to @{o}"),
+            None => format!("Unassigned {count} node(s)"),
+        };
+        for w in warnings.into_iter().flatten() {
+            msg.push_str(&format!("\n[WARNING] {w}"));
+        }
         Ok(CallToolResult::success(vec![rmcp::model::Content::text(
-            format!("Imported '{}': {} nodes", tree.title, node_count),
+            msg,
         )]))
     }
 
     #[tool(
-        name = "init",
-        description = "Create a new book in the shelf. Requires a slug (filename) and title. Auto-selects the new book.",
+        name = "reset_progress",
+        description = "Reopen every checked-off content item in the selected book (or under `node_id`), turning a finished run of a recurring checklist back into a fresh one. Check items off with `node_update` `done: true`.",
         annotations(
             read_only_hint = false,
             destructive_hint = false,
-            idempotent_hint = false,
+            idempotent_hint = true,
             open_world_hint = false
         )
     )]
-    async fn init(
+    async fn reset_progress(
         &self,
-        Parameters(req): Parameters<McpInitRequest>,
+        Parameters(req): Parameters<McpResetProgressRequest>,
     ) -> Result<CallToolResult, McpError> {
-        validate_slug(&req.slug)?;
+        let svc = self.service().await?;
 
-        let path = self.book_path(&req.slug);
-        if path.exists() {
-            return Err(McpError::invalid_params(
-                format!(
-                    "Book '{}' already exists. Choose a different slug.",
-                    req.slug
-                ),
-                None,
-            ));
+        // 解決・変更・応答を、1回読み込んだ同じ Book で行う
+        let ((count, scope), warnings) = svc
+            .with_book(|book| {
+                let root = req
+                    .node_id
+                    .as_deref()
+                    .map(|s| self.resolve_id_in(book, s, ResolvePolicy::Lenient))
+                    .transpose()?;
+                let reopened = update_matching_in(
+                    book,
+                    root,
+                    |n| *n.node_type() == NodeType::Content && n.is_done(),
+                    |_| UpdateNodeRequest {
+                        done: Some(false),
+                        ..Default::default()
+                    },
+                )
+                .map_err(|e| Self::to_mcp_error(e.into()))?;
+
+                let scope = match root {
+                    Some(id) => {
+                        let hier =
+                            find_hierarchical_id(book, id).unwrap_or_else(|| self.short_id(id));
+                        let title = book.get_node(id).map(|n| n.title()).unwrap_or("?");
+                        format!(" under {hier}. {title}")
+                    }
+                    None => String::new(),
+                };
+                let count = reopened.len();
+                let changes = reopened
+                    .into_iter()
+                    .map(|id| (id, ChangeAction::Update))
+                    .collect();
+                Ok(((count, scope), changes))
+            })
+            .await
+            .map_err(Self::to_mcp_error)??;
+
+        let mut msg = if count == 0 {
+            format!("Nothing to reset{scope}: no items are checked off.")
+        } else {
+            format!("Reset {count} item(s){scope}.")
+        };
+        for w in warnings.into_iter().flatten() {
+            msg.push_str(&format!("\n[WARNING] {w}"));
         }
+        Ok(CallToolResult::success(vec![rmcp::model::Content::text(
+            msg,
+        )]))
+    }
 
-        std::fs::create_dir_all(&self.shelf_dir).map_err(|e| {
-            McpError::internal_error(format!("Failed to create shelf directory: {e}"), None)
-        })?;
+    #[tool(
+        name = "retype_subtree",
+        description = "Normalize node types under a node: every node with children becomes a section and every leaf becomes content. Use after an import that typed everything as content. Reports how many nodes changed type.",
+        annotations(
+            read_only_hint = false,
+            destructive_hint = false,
+            idempotent_hint = true,
+            open_world_hint = false
+        )
+    )]
+    async fn retype_subtree(
+        &self,
+        Parameters(req): Parameters<McpRetypeSubtreeRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        let svc = self.service().await?;
 
-        let svc = self.service_for(&req.slug).await?;
-        let max_depth = req.max_depth.unwrap_or(4);
-        let book = svc
-            .create_book(&req.title, max_depth)
+        // 子を持つ → Section、葉 → Content。すでに規約どおりのノードは触らない
+        let convention = |n: &TemplateNode| {
+            if n.is_leaf() {
+                NodeType::Content
+            } else {
+                NodeType::Section
+            }
+        };
+        // 解決・変更・応答を、1回読み込んだ同じ Book で行う
+        let ((count, label), warnings) = svc
+            .with_book(|book| {
+                let root = self.resolve_id_in(book, &req.node_id, ResolvePolicy::Lenient)?;
+                let retyped = update_matching_in(
+                    book,
+                    Some(root),
+                    |n| *n.node_type() != convention(n),
+                    |n| UpdateNodeRequest {
+                        node_type: Some(convention(n)),
+                        ..Default::default()
+                    },
+                )
+                .map_err(|e| Self::to_mcp_error(e.into()))?;
+
+                let hier = find_hierarchical_id(book, root).unwrap_or_else(|| self.short_id(root));
+                let title = book.get_node(root).map(|n| n.title()).unwrap_or("?");
+                let label = format!("{hier}. {title}");
+                let count = retyped.len();
+                let changes = retyped
+                    .into_iter()
+                    .map(|id| (id, ChangeAction::Update))
+                    .collect();
+                Ok(((count, label), changes))
+            })
             .await
-            .map_err(Self::to_mcp_error)?;
-
-        // Auto-select
-        let mut guard = self
-            .selected
-            .write()
-            .map_err(|_| McpError::internal_error("Lock poisoned", None))?;
-        *guard = Some(req.slug.clone());
+            .map_err(Self::to_mcp_error)??;
 
+        let mut msg = if count == 0 {
+            format!("No change under {label}: node types already follow the section/content convention.")
+        } else {
+            format!("Retyped {count} node(s) under {label}.")
+        };
+        for w in warnings.into_iter().flatten() {
+            msg.push_str(&format!("\n[WARNING] {w}"));
+        }
         Ok(CallToolResult::success(vec![rmcp::model::Content::text(
-            format!(
-                "Created book: '{}' (slug: {}, max_depth: {}). Auto-selected.",
-                book.title(),
-                req.slug,
-                book.max_depth()
-            ),
+            msg,
         )]))
     }
 
     #[tool(
-        name = "shelf",
-        description = "List all books in the shelf. Shows book slugs, titles, and node counts. The currently selected book is marked with ★.",
+        name = "seed_placeholders",
+        description = "Turn a bare outline into a fill-in template: give every content leaf that has neither a body nor a placeholder the placeholder `hint` (e.g. 'Describe the step'). Nodes that already have guidance are left alone. Optionally limited to the subtree under `node_id`; reports how many nodes were seeded.",
         annotations(
-            read_only_hint = true,
+            read_only_hint = false,
             destructive_hint = false,
+            idempotent_hint = true,
             open_world_hint = false
         )
     )]
-    async fn shelf(
+    async fn seed_placeholders(
         &self,
-        #[allow(unused_variables)] Parameters(_req): Parameters<McpShelfRequest>,
+        Parameters(req): Parameters<McpSeedPlaceholdersRequest>,
     ) -> Result<CallToolResult, McpError> {
-        let slugs = self.list_book_slugs()?;
-
-        if slugs.is_empty() {
-            return Ok(CallToolResult::success(vec![rmcp::model::Content::text(
-                "Shelf is empty. Use `init` to create a new book.",
-            )]));
+        let hint = unescape_newlines(req.hint.trim());
+        if hint.is_empty() {
+            return Err(McpError::invalid_params("hint must not be empty", None));
         }
+        let svc = self.service().await?;
 
-        // Guard は clone した値だけ保持して即座に drop する（`.await` を跨いで
-        // `RwLockReadGuard` (非 Send) を持ち越すと `#[tool]` の Send 境界を破る）。
-        let selected: Option<String> = {
-            let guard = self
-                .selected
-                .read()
-                .map_err(|_| McpError::internal_error("Lock poisoned", None))?;
-            guard.clone()
-        };
-
-        let mut entries: Vec<(String, String, usize)> = Vec::new();
-        for slug in &slugs {
-            match self.service_for(slug).await {
-                Ok(svc) => match svc.read_tree().await {
-                    Ok(book) => {
-                        entries.push((slug.clone(), book.title().to_string(), book.node_count()));
-                    }
-                    Err(_) => {
-                        entries.push((slug.clone(), "(failed to load)".to_string(), 0));
+        let is_content_leaf = |n: &TemplateNode| *n.node_type() == NodeType::Content && n.is_leaf();
+        // 解決・変更・応答を、1回読み込んだ同じ Book で行う
+        let ((count, skipped, scope), warnings) = svc
+            .with_book(|book| {
+                let root = req
+                    .node_id
+                    .as_deref()
+                    .map(|s| self.resolve_id_in(book, s, ResolvePolicy::Lenient))
+                    .transpose()?;
+                let seeded = update_matching_in(
+                    book,
+                    root,
+                    |n| is_content_leaf(n) && n.body().is_none() && n.placeholder().is_none(),
+                    |_| UpdateNodeRequest {
+                        placeholder: Some(Some(hint.clone())),
+                        ..Default::default()
+                    },
+                )
+                .map_err(|e| Self::to_mcp_error(e.into()))?;
+
+                let scope = match root {
+                    Some(id) => {
+                        let hier =
+                            find_hierarchical_id(book, id).unwrap_or_else(|| self.short_id(id));
+                        let title = book.get_node(id).map(|n| n.title()).unwrap_or("?");
+                        format!(" under {hier}. {title}")
                     }
-                },
-                Err(_) => {
-                    entries.push((slug.clone(), "(failed to load)".to_string(), 0));
+                    None => String::new(),
+                };
+                let leaves = match root {
+                    Some(id) => book.subtree_nodes(id),
+                    None => book.all_nodes_dfs(),
                 }
-            }
-        }
+                .into_iter()
+                .filter(|n| is_content_leaf(n))
+                .count();
+                let count = seeded.len();
+                let changes = seeded
+                    .into_iter()
+                    .map(|id| (id, ChangeAction::Update))
+                    .collect();
+                Ok(((count, leaves.saturating_sub(count), scope), changes))
+            })
+            .await
+            .map_err(Self::to_mcp_error)??;
 
-        let mut output = format!("# Shelf ({} books)\n\n", entries.len());
-        for (i, (slug, title, count)) in entries.iter().enumerate() {
-            let marker = if selected.as_deref() == Some(slug.as_str()) {
-                " ★"
-            } else {
-                ""
-            };
-            output.push_str(&format!(
-                "{}. {} — \"{}\" ({} nodes){}\n",
-                i + 1,
-                slug,
-                title,
-                count,
-                marker
-            ));
+        let mut msg = if count == 0 {
+            format!("Nothing to seed{scope}: every content leaf already has a body or placeholder.")
+        } else {
+            format!(
+                "Seeded {count} content node(s){scope} with placeholder '{hint}'; {skipped} already had guidance."
+            )
+        };
+        for w in warnings.into_iter().flatten() {
+            msg.push_str(&format!("\n[WARNING] {w}"));
         }
-
         Ok(CallToolResult::success(vec![rmcp::model::Content::text(
-            output,
+            msg,
         )]))
     }
 
     #[tool(
-        name = "select_book",
-        description = "Select a book to work with. Use a number from `shelf` output or a book slug. All subsequent operations (toc, node_create, etc.) will target the selected book. Automatically shows TOC unless quiet=true.",
+        name = "node_move",
+        description = "Move or delete a node (and its descendants). Specify node by ID from `toc` output (e.g. '2-3'). Action 'move' relocates (new_parent may also be a title path like 'Design/API'), 'remove' deletes, 'to_top' / 'to_bottom' make it the first / last top-level node. Removing a protected node (🛡 in toc) or a subtree containing one fails unless `override_protection: true`.",
         annotations(
             read_only_hint = false,
-            destructive_hint = false,
-            idempotent_hint = true,
+            destructive_hint = true,
+            idempotent_hint = false,
             open_world_hint = false
         )
     )]
-    async fn select_book(
+    async fn node_move(
         &self,
-        Parameters(req): Parameters<McpSelectBookRequest>,
+        Parameters(req): Parameters<McpNodeMoveRequest>,
     ) -> Result<CallToolResult, McpError> {
-        let slug = self.resolve_book_ref(&req.book)?;
-
-        let path = self.book_path(&slug);
-        if !path.exists() {
+        let svc = self.service().await?;
+        // remove はタイトル一致を許さない（意図しないノードの削除を防ぐ）
+        let policy = if req.action == "remove" {
+            ResolvePolicy::Strict
+        } else {
+            ResolvePolicy::Lenient
+        };
+        // to_top / to_bottom はトップレベルの先頭・末尾への移動の略記
+        let extreme = match req.action.as_str() {
+            "move" | "remove" => None,
+            "to_top" => Some(Position::First),
+            "to_bottom" => Some(Position::Last),
+            _ => {
+                return Err(McpError::invalid_params(
+                    format!(
+                        "Unknown action: '{}'. Use: move, remove, to_top, to_bottom",
+                        req.action
+                    ),
+                    None,
+                ))
+            }
+        };
+        if extreme.is_some() && (req.new_parent.is_some() || req.position.is_some()) {
             return Err(McpError::invalid_params(
                 format!(
-                    "Book '{}' not found in shelf. Use `shelf` to list available books.",
-                    slug
+                    "'{}' always moves to the top level; omit new_parent and position",
+                    req.action
                 ),
                 None,
             ));
         }
 
-        let svc = self.service_for(&slug).await?;
-        let book = svc.read_tree().await.map_err(Self::to_mcp_error)?;
-
-        let mut guard = self
-            .selected
-            .write()
-            .map_err(|_| McpError::internal_error("Lock poisoned", None))?;
-        *guard = Some(slug.clone());
+        // 解決・変更・応答を、1回読み込んだ同じ Book で行う
+        let ((msg, ack), warnings) = svc
+            .with_book(|book| {
+                let id = self.resolve_id_in(book, &req.node_id, policy)?;
+                if req.action == "remove" {
+                    // 削除前に階層番号を取得
+                    let hier = find_hierarchical_id(book, id).unwrap_or_else(|| self.short_id(id));
+                    let title = book
+                        .get_node(id)
+                        .map(|n| n.title().to_string())
+                        .unwrap_or_default();
+                    self.check_mutation_budget(
+                        0,
+                        1 + book.descendant_count(id),
+                        &[title.as_str()],
+                        req.confirm,
+                    )?;
+                    let ctx = ErrorContext {
+                        refs: &[(req.node_id.as_str(), id)],
+                        parent: None,
+                    };
+                    let removed = if req.override_protection {
+                        book.remove_node_overriding_protection(id)
+                    } else {
+                        book.remove_node(id)
+                    };
+                    removed.map_err(|e| self.to_mcp_error_in(book, e.into(), &ctx))?;
+                    let msg = format!("Removed: {}. {} (and descendants)", hier, title);
+                    return Ok(((msg, "ok".to_string()), vec![(id, ChangeAction::Delete)]));
+                }
 
-        let toc_section = if req.quiet {
-            String::new()
-        } else {
-            let nodes = book.all_nodes_dfs();
-            if nodes.is_empty() {
-                String::from("\n(empty)")
-            } else {
-                format!("\n\n{}", format_toc(&book, &nodes))
-            }
-        };
+                let new_parent = req
+                    .new_parent
+                    .as_deref()
+                    .map(|s| {
+                        // "Design/API" はタイトルパスとして上からたどる（番号・UUID に '/' は含まれない）
+                        if s.contains('/') {
+                            self.resolve_title_path_in(book, s)
+                        } else {
+                            self.resolve_id_in(book, s, ResolvePolicy::Lenient)
+                        }
+                    })
+                    .transpose()?;
+                let position = match extreme {
+                    Some(position) => position,
+                    None => parse_position(req.position.as_ref())?,
+                };
+                let mut refs = vec![(req.node_id.as_str(), id)];
+                refs.extend(req.new_parent.as_deref().zip(new_parent));
+                let ctx = ErrorContext {
+                    refs: &refs,
+                    parent: Some(new_parent),
+                };
 
-        // Context Injection: inject=true ノードの body を自動出力
-        let inject_filter = {
-            let mut m = HashMap::new();
-            m.insert("inject".to_string(), "true".to_string());
-            m
-        };
-        let injected_nodes: Vec<_> = book
-            .nodes_matching(&inject_filter)
-            .into_iter()
-            .filter(|node| node.status() != NodeStatus::Draft)
-            .collect();
-        let inject_section = if injected_nodes.is_empty() {
-            String::new()
-        } else {
-            let mut buf = format!(
-                "\n\n---\n# Injected Context ({} rules)\n",
-                injected_nodes.len()
-            );
-            for node in &injected_nodes {
-                buf.push_str(&format!("\n## {}\n", node.title()));
-                if let Some(body) = node.body() {
-                    buf.push_str(body);
-                    buf.push('\n');
+                // 移動前の親・位置を控えておく（監査・undo 用）
+                let old_slot = self.describe_slot(
+                    book,
+                    book.get_node(id).and_then(|n| n.parent()),
+                    book.sibling_index(id),
+                );
+                let old_parent = book.get_node(id).and_then(|n| n.parent());
+                let old_index = book.sibling_index(id);
+                let placed = book
+                    .move_node(id, new_parent, position)
+                    .map_err(|e| self.to_mcp_error_in(book, e.into(), &ctx))?;
+
+                let hier = find_hierarchical_id(book, id).unwrap_or_else(|| self.short_id(id));
+                // 同じ親の同じ位置への移動は保存せず、移動していないことを伝える
+                if old_parent == new_parent && old_index == Some(placed.index) {
+                    let msg = format!(
+                        "No change: {}. {} is already at {old_slot}",
+                        hier,
+                        book.get_node(id).map(|n| n.title()).unwrap_or("?")
+                    );
+                    return Ok(((msg, hier), Vec::new()));
                 }
-            }
-            buf
-        };
+                let new_slot = self.describe_slot(book, new_parent, book.sibling_index(id));
+                let mut msg = format!(
+                    "Moved → {}. {}\nFrom: {old_slot}\nTo:   {new_slot}",
+                    hier,
+                    book.get_node(id).map(|n| n.title()).unwrap_or("?")
+                );
+                if let Some(note) = clamp_note("moved", &placed) {
+                    msg.push_str(&format!("\n{note}"));
+                }
+                Ok(((msg, hier), vec![(id, ChangeAction::Move)]))
+            })
+            .await
+            .map_err(Self::to_mcp_error)??;
 
+        let msg = mutation_reply(req.quiet, &ack, msg, warnings);
         Ok(CallToolResult::success(vec![rmcp::model::Content::text(
-            format!(
-                "Selected: {} — \"{}\" ({} nodes){}{}",
-                slug,
-                book.title(),
-                book.node_count(),
-                toc_section,
-                inject_section
-            ),
+            msg,
         )]))
     }
 
     #[tool(
-        name = "gen_routing",
-        description = "Generate a Markdown routing table from nodes with `routing` property across all books. Set `routing` property on nodes to define work scenarios (e.g. routing=\"Git操作\"). Use `|` separator for multiple scenarios. Optional `routing_ref` property overrides the default §ID reference (e.g. routing_ref=\"select_book で全体参照\").",
+        name = "toc",
+        description = "Show table of contents with numbered IDs (e.g. 1, 1-1, 2-3; `numbering` switches to 2.3 or 2.3. style — every form is accepted as an ID). Run this first — use the returned IDs to specify nodes in `checklist`, `node_create`, and other tools.",
         annotations(
             read_only_hint = true,
             destructive_hint = false,
             open_world_hint = false
         )
     )]
-    async fn gen_routing(
+    async fn toc(
         &self,
-        #[allow(unused_variables)] Parameters(_req): Parameters<McpGenRoutingRequest>,
+        Parameters(req): Parameters<McpTocRequest>,
     ) -> Result<CallToolResult, McpError> {
-        let slugs = self.list_book_slugs()?;
-
-        // Collect: (scene, book_slug, reference_text)
-        let mut entries: Vec<(String, String, String)> = Vec::new();
-
-        for slug in &slugs {
-            let svc = match self.service_for(slug).await {
-                Ok(svc) => svc,
-                Err(_) => continue,
-            };
-            let book = match svc.read_tree().await {
-                Ok(b) => b,
-                Err(_) => continue,
-            };
+        let svc = self.service().await?;
+        let book = svc.read_tree().await.map_err(Self::to_mcp_error)?;
 
-            let id_map = build_hierarchical_ids(&book);
+        let subtree_id = match req.subtree_root.as_deref() {
+            Some(s) => Some(self.resolve_id(s, ResolvePolicy::Lenient).await?),
+            None => None,
+        };
 
-            for node in book.all_nodes_dfs() {
-                let routing = match node.get_property("routing") {
-                    Some(v) => v.to_string(),
-                    None => continue,
-                };
+        let mut nodes = match subtree_id {
+            Some(root_id) => book.subtree_nodes(root_id),
+            None => book.all_nodes_dfs(),
+        };
 
-                let reference = if let Some(r) = node.get_property("routing_ref") {
-                    r.to_string()
-                } else {
-                    let hier = id_map
+        // プロパティフィルタ
+        if let Some(ref filter) = req.filter {
+            if !filter.is_empty() {
+                nodes.retain(|node| {
+                    filter
                         .iter()
-                        .find(|(_, id)| *id == node.id())
-                        .map(|(num, _)| num.as_str())
-                        .unwrap_or("?");
-                    format!("§{} {}", hier, node.title())
-                };
-
-                for scene in routing.split('|') {
-                    let scene = scene.trim();
-                    if !scene.is_empty() {
-                        entries.push((scene.to_string(), slug.clone(), reference.clone()));
-                    }
-                }
+                        .all(|(k, v)| node.get_property(k).map(|pv| pv == v).unwrap_or(false))
+                });
             }
         }
 
-        if entries.is_empty() {
+        // 見出しだけの概観: Content を落とす（番号は全体の採番のまま）
+        if req.sections_only.unwrap_or(false) {
+            nodes.retain(|node| *node.node_type() == NodeType::Section);
+        }
+
+        if nodes.is_empty() {
             return Ok(CallToolResult::success(vec![rmcp::model::Content::text(
-                "No nodes with `routing` property found. Add `routing` property to nodes to include them in the routing table.",
+                "No matching nodes. Use `node_create` to add nodes.",
             )]));
         }
 
-        // Group same (scene, book) → merge references
-        let mut grouped: Vec<(String, String, Vec<String>)> = Vec::new();
-        for (scene, book, reference) in &entries {
-            if let Some(existing) = grouped.iter_mut().find(|(s, b, _)| s == scene && b == book) {
-                existing.2.push(reference.clone());
-            } else {
-                grouped.push((scene.clone(), book.clone(), vec![reference.clone()]));
-            }
-        }
-
-        // Sort by book, then scene
-        grouped.sort_by(|a, b| a.1.cmp(&b.1).then(a.0.cmp(&b.0)));
-
-        let mut output = String::from("| 場面 | Book | ノード |\n|---|---|---|\n");
-        for (scene, book, refs) in &grouped {
-            output.push_str(&format!("| {} | {} | {} |\n", scene, book, refs.join(", ")));
-        }
-
+        let numbering = parse_numbering(req.numbering.as_deref())?;
+        let options = TocOptions {
+            numbering,
+            show_owners: req.show_owners,
+        };
+        let output = format_toc(&book, &nodes, options);
         Ok(CallToolResult::success(vec![rmcp::model::Content::text(
             output,
         )]))
     }
 
     #[tool(
-        name = "snapshot_create",
-        description = "Create a snapshot of the current book state. Optionally attach a label (sidecar '.meta.json'; snapshot body schema is unchanged). Use `snapshot_list` to view saved snapshots and `snapshot_restore` to revert.",
+        name = "stats",
+        description = "Summarize the book (or a subtree): node counts and summed effort estimates overall and per section. Set estimates with `node_update` (`estimate_minutes`).",
         annotations(
-            read_only_hint = false,
+            read_only_hint = true,
             destructive_hint = false,
-            idempotent_hint = false,
             open_world_hint = false
         )
     )]
-    async fn snapshot_create(
+    async fn stats(
         &self,
-        Parameters(req): Parameters<McpSnapshotCreateRequest>,
+        Parameters(req): Parameters<McpStatsRequest>,
     ) -> Result<CallToolResult, McpError> {
         let svc = self.service().await?;
         let book = svc.read_tree().await.map_err(Self::to_mcp_error)?;
-
-        let slug = {
-            let guard = self
-                .selected
-                .read()
-                .map_err(|_| McpError::internal_error("Lock poisoned", None))?;
-            guard
-                .as_ref()
-                .ok_or_else(|| {
-                    McpError::invalid_params(
-                        "No book selected. Use `shelf` to list books and `select_book` to choose one.",
-                        None,
-                    )
-                })?
-                .clone()
+        let root = match req.subtree_root.as_deref() {
+            Some(s) => Some(self.resolve_id(s, ResolvePolicy::Lenient).await?),
+            None => None,
         };
+        Ok(CallToolResult::success(vec![rmcp::model::Content::text(
+            format_stats(&book, root),
+        )]))
+    }
 
-        let label = match req.label.as_deref() {
-            Some(s) => Some(validate_snapshot_label(s)?),
+    #[tool(
+        name = "placeholders",
+        description = "Pre-flight form for the book (or a subtree): every fill-in the checklist export will ask for, with its ID, title and placeholder hint, grouped by section, plus a total. `format: \"markdown-table\"` gives a table for pasting into docs.",
+        annotations(
+            read_only_hint = true,
+            destructive_hint = false,
+            open_world_hint = false
+        )
+    )]
+    async fn placeholders(
+        &self,
+        Parameters(req): Parameters<McpPlaceholdersRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        let format = parse_placeholder_format(req.format.as_deref())?;
+        let svc = self.service().await?;
+        let book = svc.read_tree().await.map_err(Self::to_mcp_error)?;
+        let root = match req.subtree_root.as_deref() {
+            Some(s) => Some(self.resolve_id(s, ResolvePolicy::Lenient).await?),
             None => None,
         };
-
-        let snap_svc = self.snapshot_service_for(&slug).await?;
-        let path = snap_svc
-            .create(&book, label.as_deref())
-            .await
-            .map_err(|e| {
-                McpError::internal_error(format!("Failed to create snapshot: {e}"), None)
-            })?;
-
-        let size_bytes = std::fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
-
-        // ファイル名からタイムスタンプ(millis)を取得
-        let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("");
-        let millis_str = stem.rsplit('.').next().unwrap_or("");
-
-        let label_note = label
-            .as_deref()
-            .map(|l| format!(" [label: {l}]"))
-            .unwrap_or_default();
-
         Ok(CallToolResult::success(vec![rmcp::model::Content::text(
-            format!(
-                "Snapshot created: {} ({} bytes){}",
-                millis_str, size_bytes, label_note
-            ),
+            format_placeholders(&book, root, format),
         )]))
     }
 
     #[tool(
-        name = "snapshot_list",
-        description = "List all snapshots for the selected book. Use the timestamp value with `snapshot_restore` to revert to a specific state.",
+        name = "eject_prompts",
+        description = "Everything to ask the user before exporting the book (or a subtree), in one list: each `{{variable}}` used in titles, bodies, placeholders, notes or commands (listed once, with the nodes using it) and each placeholder fill-in. Ask the human for all of them in one turn.",
         annotations(
             read_only_hint = true,
             destructive_hint = false,
             open_world_hint = false
         )
     )]
-    async fn snapshot_list(
+    async fn eject_prompts(
         &self,
-        #[allow(unused_variables)] Parameters(_req): Parameters<McpSnapshotListRequest>,
+        Parameters(req): Parameters<McpEjectPromptsRequest>,
     ) -> Result<CallToolResult, McpError> {
         let svc = self.service().await?;
         let book = svc.read_tree().await.map_err(Self::to_mcp_error)?;
-
-        let slug = {
-            let guard = self
-                .selected
-                .read()
-                .map_err(|_| McpError::internal_error("Lock poisoned", None))?;
-            guard
-                .as_ref()
-                .ok_or_else(|| {
-                    McpError::invalid_params(
-                        "No book selected. Use `shelf` to list books and `select_book` to choose one.",
-                        None,
-                    )
-                })?
-                .clone()
+        let root = match req.subtree_root.as_deref() {
+            Some(s) => Some(self.resolve_id_in(&book, s, ResolvePolicy::Lenient)?),
+            None => None,
         };
-
-        let snap_svc = self.snapshot_service_for(&slug).await?;
-        let infos = snap_svc.list().await.map_err(|e| {
-            McpError::internal_error(format!("Failed to list snapshots: {e}"), None)
-        })?;
-
-        if infos.is_empty() {
-            return Ok(CallToolResult::success(vec![rmcp::model::Content::text(
-                format!("No snapshots for \"{}\".", book.title()),
-            )]));
-        }
-
-        let mut output = format!(
-            "# Snapshots for \"{}\" ({} snapshots)\n\n",
-            book.title(),
-            infos.len()
-        );
-        for (i, info) in infos.iter().enumerate() {
-            let size_kb = info.size_bytes as f64 / 1024.0;
-            let label_note = info
-                .label
-                .as_deref()
-                .map(|l| format!(" [{}]", l))
-                .unwrap_or_default();
-            output.push_str(&format!(
-                "{}. {} — {} ({:.1} KB){}\n",
-                i + 1,
-                info.timestamp.to_iso8601(),
-                info.timestamp.as_millis(),
-                size_kb,
-                label_note
-            ));
-        }
-
+        let title = match root.and_then(|id| book.get_node(id)) {
+            Some(n) => n.title(),
+            None => book.title(),
+        };
+        let prompts = collect_prompts(&book, root);
         Ok(CallToolResult::success(vec![rmcp::model::Content::text(
-            output,
+            format_prompts(&book, title, &prompts),
         )]))
     }
 
     #[tool(
-        name = "snapshot_restore",
-        description = "Restore the selected book from a snapshot. This overwrites the current book state. Use `snapshot_list` to find available timestamps.",
+        name = "tree_json",
+        description = "The whole book (or a subtree) as one nested JSON object for rendering in a UI: each node has `uuid`, `hierarchical_id` (as in `toc`), `title`, `node_type`, `body`, `placeholder`, `depth` and `children`. Book is NOT modified.",
         annotations(
-            read_only_hint = false,
-            destructive_hint = true,
-            idempotent_hint = false,
+            read_only_hint = true,
+            destructive_hint = false,
             open_world_hint = false
         )
     )]
-    async fn snapshot_restore(
+    async fn tree_json(
         &self,
-        Parameters(req): Parameters<McpSnapshotRestoreRequest>,
+        Parameters(req): Parameters<McpTreeJsonRequest>,
     ) -> Result<CallToolResult, McpError> {
-        let millis: i64 = req.timestamp.parse().map_err(|_| {
-            McpError::invalid_params(
-                format!(
-                    "Invalid timestamp: '{}'. Must be a millis integer.",
-                    req.timestamp
-                ),
-                None,
-            )
-        })?;
-
-        let slug = {
-            let guard = self
-                .selected
-                .read()
-                .map_err(|_| McpError::internal_error("Lock poisoned", None))?;
-            guard
-                .as_ref()
-                .ok_or_else(|| {
-                    McpError::invalid_params(
-                        "No book selected. Use `shelf` to list books and `select_book` to choose one.",
-                        None,
-                    )
-                })?
-                .clone()
+        let svc = self.service().await?;
+        let book = svc.read_tree().await.map_err(Self::to_mcp_error)?;
+        let root = match req.subtree_root.as_deref() {
+            Some(s) => Some(self.resolve_id_in(&book, s, ResolvePolicy::Lenient)?),
+            None => None,
         };
-
-        let snap_svc = self.snapshot_service_for(&slug).await?;
-        let restored = snap_svc.restore(millis).await.map_err(|e| {
-            McpError::internal_error(format!("Failed to restore snapshot: {e}"), None)
+        let view = build_tree_view(&book, root);
+        let text = serde_json::to_string_pretty(&view).map_err(|e| {
+            McpError::internal_error(format!("Failed to serialize tree: {e}"), None)
         })?;
-
-        let node_count = restored.node_count();
-
-        // changelog に Restore エントリを記録（ベストエフォート）
-        let cl_repo = self.changelog_for(&slug).await?;
-        let ts = Timestamp::now();
-        let mut warning: Option<String> = None;
-        for id in restored.all_node_ids() {
-            let entry = ChangeEntry::new(id, ChangeAction::Restore, None, None, ts);
-            if let Err(e) =
-                outline_mcp_core::domain::repository::ChangeLogRepository::append(&cl_repo, &entry)
-                    .await
-            {
-                warning = Some(format!("changelog write failed: {e}"));
-                break;
-            }
-        }
-
-        let svc = self.service().await?;
-        svc.save_book(&restored).await.map_err(Self::to_mcp_error)?;
-
-        let mut msg = format!(
-            "Restored from snapshot {}. {} nodes.",
-            req.timestamp, node_count
-        );
-        if let Some(w) = warning {
-            msg.push_str(&format!("\n[WARNING] {w}"));
-        }
-
         Ok(CallToolResult::success(vec![rmcp::model::Content::text(
-            msg,
+            text,
         )]))
     }
 
     #[tool(
-        name = "snapshot_tag",
-        description = "Attach or overwrite a label on an existing snapshot. Writes only the sidecar '.meta.json'; the snapshot body is not touched. Use `snapshot_list` to find timestamps.",
+        name = "node_get",
+        description = "Show a single node in full: type, status, path, placeholder, properties, body, and private notes. Notes are only visible here (and in exports with include_notes). Pass stub=true for an editable fill-in block (placeholder rendered as `hint: ____`) to complete and send back via `node_update`.",
         annotations(
-            read_only_hint = false,
+            read_only_hint = true,
             destructive_hint = false,
-            idempotent_hint = true,
             open_world_hint = false
         )
     )]
-    async fn snapshot_tag(
+    async fn node_get(
         &self,
-        Parameters(req): Parameters<McpSnapshotTagRequest>,
+        Parameters(req): Parameters<McpNodeGetRequest>,
     ) -> Result<CallToolResult, McpError> {
-        let millis: i64 = req.timestamp.parse().map_err(|_| {
-            McpError::invalid_params(
-                format!(
-                    "Invalid timestamp: '{}'. Must be a millis integer.",
-                    req.timestamp
-                ),
-                None,
-            )
+        let svc = self.service().await?;
+        let book = svc.read_tree().await.map_err(Self::to_mcp_error)?;
+        let id = self
+            .resolve_id(&req.node_id, ResolvePolicy::Lenient)
+            .await?;
+        let node = book.get_node(id).ok_or_else(|| {
+            McpError::invalid_params(format!("Node not found: '{}'", req.node_id), None)
         })?;
-
-        let label = validate_snapshot_label(&req.label)?;
-
-        let slug = {
-            let guard = self
-                .selected
-                .read()
-                .map_err(|_| McpError::internal_error("Lock poisoned", None))?;
-            guard
-                .as_ref()
-                .ok_or_else(|| {
-                    McpError::invalid_params(
-                        "No book selected. Use `shelf` to list books and `select_book` to choose one.",
-                        None,
-                    )
-                })?
-                .clone()
+        let hier = find_hierarchical_id(&book, id).unwrap_or_else(|| "?".to_string());
+        let output = if req.stub.unwrap_or(false) {
+            format!(
+                "{}\n---\nFill in the blanks and send the result back with `node_update` (node_id: {hier}).",
+                EjectService::render_stub(&book, node)
+            )
+        } else {
+            format_node_detail(&book, node, &hier, &self.short_id(id))
         };
-
-        let snap_svc = self.snapshot_service_for(&slug).await?;
-        let meta_path = snap_svc
-            .tag(millis, &label)
-            .await
-            .map_err(|e| McpError::internal_error(format!("Failed to tag snapshot: {e}"), None))?;
-
         Ok(CallToolResult::success(vec![rmcp::model::Content::text(
-            format!(
-                "Snapshot {} tagged as '{}' (meta: {})",
-                millis,
-                label,
-                meta_path.display()
-            ),
+            output,
         )]))
     }
 
     #[tool(
-        name = "snapshot_diff",
-        description = "Unified diff between two snapshots (from_ts must be strictly less than to_ts). Both snapshots are rendered as Markdown and compared with the `similar` crate. Response is a JSON object with 'from' / 'to' metadata (timestamp / label / iso) and a unified-diff 'diff' string; the diff header uses the label when present, otherwise the timestamp.",
+        name = "checklist",
+        description = "Export a section as a Markdown checklist with checkboxes. First run `toc` to find the section ID, then pass it as subtree_root (e.g. '2'). Omit subtree_root for full book export. Pass `inline: true` to return the content instead of writing a file (`encoding: base64` for binary-safe transport). `include_ancestors: true` prefixes a nested subtree with a `Context: A → B → C` breadcrumb. Book is NOT modified.",
         annotations(
-            read_only_hint = true,
+            read_only_hint = false,
             destructive_hint = false,
             idempotent_hint = true,
             open_world_hint = false
         )
     )]
-    async fn snapshot_diff(
+    async fn checklist(
         &self,
-        Parameters(req): Parameters<McpSnapshotDiffRequest>,
+        Parameters(req): Parameters<McpEjectRequest>,
     ) -> Result<CallToolResult, McpError> {
-        let from_ms: i64 = req.from_ts.parse().map_err(|_| {
-            McpError::invalid_params(
-                format!(
-                    "Invalid from_ts: '{}'. Must be a millis integer.",
-                    req.from_ts
-                ),
-                None,
-            )
-        })?;
-        let to_ms: i64 = req.to_ts.parse().map_err(|_| {
-            McpError::invalid_params(
-                format!("Invalid to_ts: '{}'. Must be a millis integer.", req.to_ts),
-                None,
-            )
-        })?;
-        if from_ms >= to_ms {
+        let svc = self.service().await?;
+        let book = svc.read_tree().await.map_err(Self::to_mcp_error)?;
+
+        // format: backup は書き出し形式ではなく、保存形式そのままの Book 全体
+        let backup = req.format.as_deref() == Some("backup");
+        let requested = EjectDefaults {
+            output_dir: req.output_dir.clone(),
+            format: req.format.clone().filter(|_| !backup),
+            include_placeholders: req.include_placeholders,
+        };
+        let EjectSettings {
+            output_dir,
+            format,
+            include_placeholders,
+        } = resolve_eject_settings(
+            &requested,
+            book.eject_defaults(),
+            &self.config.eject,
+            &self.default_export_dir(None),
+        )?;
+        let style = parse_markdown_style(req.style.as_deref())?;
+        if req.wrap_width == Some(0) {
             return Err(McpError::invalid_params(
-                format!("from_ts ({from_ms}) must be strictly less than to_ts ({to_ms})"),
+                "wrap_width (or `wrap`) must be at least 1",
                 None,
             ));
         }
-        let context_lines = req.context_lines.unwrap_or(3);
-
-        let slug = {
-            let guard = self
-                .selected
-                .read()
-                .map_err(|_| McpError::internal_error("Lock poisoned", None))?;
-            guard
-                .as_ref()
-                .ok_or_else(|| {
-                    McpError::invalid_params(
-                        "No book selected. Use `shelf` to list books and `select_book` to choose one.",
-                        None,
-                    )
-                })?
-                .clone()
+        let wrap_width = req.wrap_width;
+        let labels = CheckboxLabels {
+            todo_word: checkbox_word(req.todo_word.as_deref(), "todo_word")?,
+            done_word: checkbox_word(req.done_word.as_deref(), "done_word")?,
+        };
+        let subtree_root = match req.subtree_root.as_deref() {
+            Some(s) => Some(self.resolve_id(s, ResolvePolicy::Lenient).await?),
+            None => None,
         };
 
-        let snap_svc = self.snapshot_service_for(&slug).await?;
+        let created_after = req
+            .created_after
+            .as_deref()
+            .map(|s| parse_timestamp_arg(s, "created_after"))
+            .transpose()?;
 
-        // 両 snapshot の meta (label) を取りに行くために list を舐める
-        let infos = snap_svc.list().await.map_err(|e| {
-            McpError::internal_error(format!("Failed to list snapshots: {e}"), None)
-        })?;
-        let find_label = |ms: i64| -> Option<String> {
-            infos
-                .iter()
-                .find(|i| i.timestamp.as_millis() == ms)
-                .and_then(|i| i.label.clone())
+        // output_path は output_dir + filename の代わりに、許可されたルート内の完全なパスを受け取る
+        let output_path = match req.output_path.as_deref() {
+            Some(_) if req.output_dir.is_some() || req.filename.is_some() => {
+                return Err(McpError::invalid_params(
+                    "output_path replaces output_dir and filename; pass one or the other",
+                    None,
+                ));
+            }
+            Some(_) if req.inline => {
+                return Err(McpError::invalid_params(
+                    "output_path cannot be combined with inline: true",
+                    None,
+                ));
+            }
+            Some(p) if self.config.read_only => {
+                let export_dir = self.config.read_only_export_dir();
+                // 既定の一時ディレクトリはまだないことがある（ないルートは照合できない）
+                let _ = std::fs::create_dir_all(&export_dir);
+                Some(resolve_output_path(p, &[export_dir.as_path()])?)
+            }
+            Some(p) => {
+                // shelf 直下には Book と設定のファイルがあるので、書けるのは exports/ の下だけ
+                let exports = self.shelf_dir.join("exports");
+                let _ = std::fs::create_dir_all(&exports);
+                let mut roots = vec![exports.as_path()];
+                roots.extend(self.config.export_root.as_deref());
+                let (dir, name) = resolve_output_path(p, &roots)?;
+                // export_root が shelf そのものを指していても Book を上書きさせない
+                if self
+                    .shelf_dir
+                    .canonicalize()
+                    .is_ok_and(|shelf| shelf == dir)
+                {
+                    return Err(McpError::invalid_params(
+                        format!(
+                            "output_path '{p}' is in the shelf directory, next to the book and config files; write under {} instead",
+                            exports.display()
+                        ),
+                        None,
+                    ));
+                }
+                Some((dir, name))
+            }
+            None => None,
+        };
+        let (output_dir, filename) = match output_path {
+            Some((dir, name)) => (dir, Some(name)),
+            // 読み取り専用では shelf を汚さないよう、書き出し先を export 用ディレクトリに固定する
+            None if self.config.read_only => (self.config.read_only_export_dir(), req.filename),
+            None => (output_dir, req.filename),
         };
 
-        let from_book = snap_svc.restore(from_ms).await.map_err(|e| {
-            McpError::internal_error(format!("Failed to load from snapshot: {e}"), None)
-        })?;
-        let to_book = snap_svc.restore(to_ms).await.map_err(|e| {
-            McpError::internal_error(format!("Failed to load to snapshot: {e}"), None)
-        })?;
-
-        let from_md = EjectService::render_markdown(&from_book, true, None);
-        let to_md = EjectService::render_markdown(&to_book, true, None);
+        if backup && (subtree_root.is_some() || req.extract_commands) {
+            return Err(McpError::invalid_params(
+                "format: backup always saves the whole book as stored; leave out subtree_root and extract_commands",
+                None,
+            ));
+        }
+        if req.extract_commands && req.format.as_deref().is_some_and(|f| f != "markdown") {
+            return Err(McpError::invalid_params(
+                "extract_commands writes a shell script; leave `format` out",
+                None,
+            ));
+        }
+        let default_ext = match format {
+            _ if backup => "backup.json",
+            _ if req.extract_commands => "sh",
+            EjectFormat::Markdown => "md",
+            EjectFormat::Json => "json",
+            EjectFormat::Html => "html",
+        };
+        let filename = filename.unwrap_or_else(|| {
+            match subtree_root {
+                Some(root_id) => {
+                    // subtree指定時: "2_Testing.md", "6-3_DSL_Architecture.md"
+                    let hier =
+                        find_hierarchical_id(&book, root_id).unwrap_or_else(|| "0".to_string());
+                    let title = book
+                        .get_node(root_id)
+                        .map(|n| sanitize_for_filename(n.title()))
+                        .unwrap_or_else(|| "unknown".to_string());
+                    format!("{}_{}.{}", hier, title, default_ext)
+                }
+                None => {
+                    format!("{}.{}", sanitize_for_filename(book.title()), default_ext)
+                }
+            }
+        });
+        validate_filename(&filename)?;
 
-        let from_ts = Timestamp::from_millis(from_ms);
-        let to_ts = Timestamp::from_millis(to_ms);
-        let from_label = find_label(from_ms);
-        let to_label = find_label(to_ms);
-        let from_header = diff_header_name(from_label.as_deref(), from_ms);
-        let to_header = diff_header_name(to_label.as_deref(), to_ms);
+        let config = EjectConfig {
+            output_dir,
+            filename,
+            include_placeholders,
+            format,
+            subtree_root,
+            created_after,
+            include_notes: req.include_notes,
+            include_estimates: req.include_estimates,
+            owner: req.filter_owner.clone(),
+            include_ancestors: req.include_ancestors,
+            style,
+            wrap_width,
+            source_slug: self.selected_slug(),
+            labels,
+            section_separators: req.section_separators.unwrap_or(false),
+            mark_incomplete: req.mark_incomplete.unwrap_or(true),
+            mark_body_lines: req.mark_body_lines.unwrap_or(false),
+            footer: req.footer.unwrap_or(false),
+        };
 
-        let diff = similar::TextDiff::from_lines(&from_md, &to_md);
-        let mut diff_out = String::new();
-        diff_out.push_str(&format!(
-            "--- {} ({} / {})\n",
-            from_header,
-            from_ms,
-            from_ts.to_iso8601()
-        ));
-        diff_out.push_str(&format!(
-            "+++ {} ({} / {})\n",
-            to_header,
-            to_ms,
-            to_ts.to_iso8601()
-        ));
-        for hunk in diff
-            .unified_diff()
-            .context_radius(context_lines)
-            .iter_hunks()
-        {
-            diff_out.push_str(&hunk.to_string());
+        if backup {
+            let content = render_backup(&book).map_err(|e| {
+                McpError::internal_error(format!("Failed to serialize backup: {e}"), None)
+            })?;
+            if req.inline {
+                let encoded = encode_inline(content, req.encoding.as_deref())?;
+                return Ok(CallToolResult::success(vec![rmcp::model::Content::text(
+                    encoded,
+                )]));
+            }
+            std::fs::create_dir_all(&config.output_dir).map_err(|e| {
+                McpError::internal_error(format!("Failed to create directory: {e}"), None)
+            })?;
+            let path = config.output_dir.join(&config.filename);
+            std::fs::write(&path, content).map_err(|e| {
+                McpError::internal_error(format!("Failed to write backup: {e}"), None)
+            })?;
+            return Ok(CallToolResult::success(vec![rmcp::model::Content::text(
+                format!(
+                    "Backup written to: {} ({} nodes; restore with `import`)",
+                    absolute_path(&path).display(),
+                    book.node_count()
+                ),
+            )]));
         }
 
-        let response = serde_json::json!({
-            "from": {
-                "timestamp": from_ms,
-                "label": from_label,
-                "iso": from_ts.to_iso8601(),
-            },
-            "to": {
-                "timestamp": to_ms,
-                "label": to_label,
-                "iso": to_ts.to_iso8601(),
-            },
-            "diff": diff_out,
-        });
+        if req.inline {
+            let content = if req.extract_commands {
+                EjectService::render_commands(&book, subtree_root)
+            } else {
+                EjectService::render(&book, &config)
+            }
+            .map_err(Self::to_mcp_error)?;
+            let encoded = encode_inline(content, req.encoding.as_deref())?;
+            return Ok(CallToolResult::success(vec![rmcp::model::Content::text(
+                encoded,
+            )]));
+        }
+        if req.encoding.is_some() {
+            return Err(McpError::invalid_params(
+                "encoding is only valid with inline: true",
+                None,
+            ));
+        }
 
-        let text = serde_json::to_string_pretty(&response).map_err(|e| {
-            McpError::internal_error(format!("Failed to serialize diff response: {e}"), None)
-        })?;
+        if req.extract_commands {
+            let path = EjectService::eject_commands(&book, &config).map_err(Self::to_mcp_error)?;
+            return Ok(CallToolResult::success(vec![rmcp::model::Content::text(
+                format!("Commands extracted to: {}", absolute_path(&path).display()),
+            )]));
+        }
+        let path = EjectService::eject(&book, &config).map_err(Self::to_mcp_error)?;
 
         Ok(CallToolResult::success(vec![rmcp::model::Content::text(
-            text,
+            format!("Checklist exported to: {}", absolute_path(&path).display()),
         )]))
     }
 
     #[tool(
-        name = "snapshot_dump",
-        description = "Dump a single snapshot to a subdirectory as 'book.md' (or 'book.json'). The live book on the shelf is NOT touched. After running, use `Bash(diff -u <dir1>/book.md <dir2>/book.md)` for unified diff between snapshots.",
+        name = "recent",
+        description = "List the most recently created nodes (newest first) with their ID, parent path, and age. Use after an agent session to audit what was added; pair with `checklist` `created_after` to export just the new material.",
         annotations(
             read_only_hint = true,
             destructive_hint = false,
-            idempotent_hint = true,
             open_world_hint = false
         )
     )]
-    async fn snapshot_dump(
+    async fn recent(
         &self,
-        Parameters(req): Parameters<McpSnapshotDumpRequest>,
+        Parameters(req): Parameters<McpRecentRequest>,
     ) -> Result<CallToolResult, McpError> {
-        let millis: i64 = req.timestamp.parse().map_err(|_| {
-            McpError::invalid_params(
-                format!(
-                    "Invalid timestamp: '{}'. Must be a millis integer.",
-                    req.timestamp
-                ),
-                None,
-            )
-        })?;
-
-        let slug = {
-            let guard = self
-                .selected
-                .read()
-                .map_err(|_| McpError::internal_error("Lock poisoned", None))?;
-            guard
-                .as_ref()
-                .ok_or_else(|| {
-                    McpError::invalid_params(
-                        "No book selected. Use `shelf` to list books and `select_book` to choose one.",
-                        None,
-                    )
-                })?
-                .clone()
-        };
+        let svc = self.service().await?;
+        let book = svc.read_tree().await.map_err(Self::to_mcp_error)?;
 
-        let format = parse_dump_format(req.format.as_deref())?;
-        let overwrite = req.overwrite.unwrap_or(false);
+        let now = Timestamp::now().as_millis();
+        let limit = req.limit.unwrap_or(20);
+        let since = req
+            .within_minutes
+            .map(|m| i64::try_from(m).unwrap_or(i64::MAX).saturating_mul(60_000))
+            .map(|window| Timestamp::from_millis(now.saturating_sub(window)));
+        let nodes = book.recently_created(limit, since);
 
-        let snap_svc = self.snapshot_service_for(&slug).await?;
+        if nodes.is_empty() {
+            return Ok(CallToolResult::success(vec![rmcp::model::Content::text(
+                "No recently created nodes. (Nodes created before creation times were recorded are not listed.)",
+            )]));
+        }
 
-        // list を舐めて index を確定 (01 = 最古)
-        let mut infos = snap_svc.list().await.map_err(|e| {
-            McpError::internal_error(format!("Failed to list snapshots: {e}"), None)
-        })?;
-        infos.reverse(); // 昇順 (最古が先頭)
-        let total = infos.len();
-        let (idx, info) = infos
-            .iter()
-            .enumerate()
-            .find(|(_, i)| i.timestamp.as_millis() == millis)
-            .ok_or_else(|| {
-                McpError::invalid_params(format!("Snapshot not found for timestamp {millis}"), None)
-            })?;
+        let id_map = build_hierarchical_ids(&book);
+        let mut output = format!("# Recently created ({} nodes)\n\n", nodes.len());
+        for node in nodes {
+            let hier = id_map
+                .iter()
+                .find(|(_, id)| *id == node.id())
+                .map(|(num, _)| num.as_str())
+                .unwrap_or("?");
+            let path = format_parent_path(&book, node.id());
+            let age = node
+                .created_at()
+                .map(|c| format_age(now, c.as_millis()))
+                .unwrap_or_default();
+            if path.is_empty() {
+                output.push_str(&format!("{hier}. {} ({age})\n", node.title()));
+            } else {
+                output.push_str(&format!("{hier}. {} — in {path} ({age})\n", node.title()));
+            }
+        }
 
-        let book = snap_svc
-            .restore(millis)
-            .await
-            .map_err(|e| McpError::internal_error(format!("Failed to load snapshot: {e}"), None))?;
+        Ok(CallToolResult::success(vec![rmcp::model::Content::text(
+            output,
+        )]))
+    }
 
-        let root = PathBuf::from(&req.output_dir);
-        let subdir = subdir_name(idx + 1, total, info.timestamp.as_millis());
-        let out_dir = root.join(&subdir);
-        prepare_dump_dir(&out_dir, overwrite)?;
+    #[tool(
+        name = "duplicates",
+        description = "Report content nodes whose titles repeat across the book (case/whitespace-insensitive). With `similarity: true`, near-identical titles within a small edit distance are grouped too.",
+        annotations(
+            read_only_hint = true,
+            destructive_hint = false,
+            open_world_hint = false
+        )
+    )]
+    async fn duplicates(
+        &self,
+        Parameters(req): Parameters<McpDuplicatesRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        let svc = self.service().await?;
+        let book = svc.read_tree().await.map_err(Self::to_mcp_error)?;
 
-        let filename = dump_filename(&format);
-        let config = EjectConfig {
-            output_dir: out_dir.clone(),
-            filename: filename.to_string(),
-            include_placeholders: true,
-            format,
-            subtree_root: None,
+        let max_distance = if req.similarity {
+            req.max_distance.unwrap_or(2)
+        } else {
+            0
         };
-        let path = EjectService::eject(&book, &config).map_err(Self::to_mcp_error)?;
+        let groups = find_duplicates(&book, max_distance);
+        if groups.is_empty() {
+            return Ok(CallToolResult::success(vec![rmcp::model::Content::text(
+                "No duplicate content titles found.",
+            )]));
+        }
+
+        let id_map = build_hierarchical_ids(&book);
+        let mut output = format!("# Duplicates ({} groups)\n", groups.len());
+        for group in &groups {
+            output.push_str(&format!(
+                "\n## \"{}\" ({} nodes)\n",
+                group.key,
+                group.members.len()
+            ));
+            for &id in &group.members {
+                let hier = id_map
+                    .iter()
+                    .find(|(_, nid)| *nid == id)
+                    .map(|(num, _)| num.as_str())
+                    .unwrap_or("?");
+                let title = book.get_node(id).map(|n| n.title()).unwrap_or("?");
+                let path = format_parent_path(&book, id);
+                if path.is_empty() {
+                    output.push_str(&format!("- {hier}. {title}\n"));
+                } else {
+                    output.push_str(&format!("- {hier}. {title} — in {path}\n"));
+                }
+            }
+        }
+        output.push_str(
+            "\nTo resolve: keep one node (merge content into it with `node_update`), then delete the others with `node_move` action 'remove'.",
+        );
 
         Ok(CallToolResult::success(vec![rmcp::model::Content::text(
-            format!("Snapshot dumped to: {}", path.display()),
+            output,
         )]))
     }
 
     #[tool(
-        name = "snapshot_dump_all",
-        description = "Dump every snapshot for the selected book into 'vNN_<millis>' subdirectories (01 = oldest). Each subdir contains 'book.md' (or 'book.json'). The live book on the shelf is NOT touched. After running, use `Bash(diff -u <dir>/v03_*/book.md <dir>/v04_*/book.md)` for unified diff between consecutive snapshots.",
+        name = "lint",
+        description = "Report structural smells with a suggested fix each: sections without children, content nodes with children, empty titles, and very deep branches. Read-only.",
         annotations(
             read_only_hint = true,
             destructive_hint = false,
-            idempotent_hint = true,
             open_world_hint = false
         )
     )]
-    async fn snapshot_dump_all(
+    async fn lint(
         &self,
-        Parameters(req): Parameters<McpSnapshotDumpAllRequest>,
+        Parameters(req): Parameters<McpLintRequest>,
     ) -> Result<CallToolResult, McpError> {
-        let slug = {
-            let guard = self
-                .selected
-                .read()
-                .map_err(|_| McpError::internal_error("Lock poisoned", None))?;
-            guard
-                .as_ref()
-                .ok_or_else(|| {
-                    McpError::invalid_params(
-                        "No book selected. Use `shelf` to list books and `select_book` to choose one.",
-                        None,
-                    )
-                })?
-                .clone()
-        };
-
-        let format = parse_dump_format(req.format.as_deref())?;
-        let overwrite = req.overwrite.unwrap_or(false);
+        let svc = self.service().await?;
+        let book = svc.read_tree().await.map_err(Self::to_mcp_error)?;
 
-        let snap_svc = self.snapshot_service_for(&slug).await?;
-        let mut infos = snap_svc.list().await.map_err(|e| {
-            McpError::internal_error(format!("Failed to list snapshots: {e}"), None)
-        })?;
-        if infos.is_empty() {
+        let threshold = req.deep_threshold.unwrap_or(DEFAULT_DEEP_BRANCH_THRESHOLD);
+        let findings = lint(&book, threshold);
+        if findings.is_empty() {
             return Ok(CallToolResult::success(vec![rmcp::model::Content::text(
-                "No snapshots to dump.".to_string(),
+                "No structural issues found.",
             )]));
         }
-        infos.reverse(); // 01 = 最古
 
-        let root = PathBuf::from(&req.output_dir);
-        let total = infos.len();
-        let filename = dump_filename(&format);
-        let mut written: Vec<String> = Vec::with_capacity(total);
+        let id_map = build_hierarchical_ids(&book);
+        let mut output = format!("# Lint ({} findings)\n\n", findings.len());
+        for finding in &findings {
+            let hier = id_map
+                .iter()
+                .find(|(_, nid)| *nid == finding.node_id)
+                .map(|(num, _)| num.as_str())
+                .unwrap_or("?");
+            let title = book
+                .get_node(finding.node_id)
+                .map(|n| n.title())
+                .unwrap_or("?");
+            output.push_str(&format!(
+                "- {hier}. {title}: {} → {}\n",
+                finding.kind.describe(),
+                finding.kind.suggestion()
+            ));
+        }
 
-        for (idx, info) in infos.iter().enumerate() {
-            let millis = info.timestamp.as_millis();
-            let book = snap_svc.restore(millis).await.map_err(|e| {
-                McpError::internal_error(format!("Failed to load snapshot {millis}: {e}"), None)
-            })?;
-
-            let subdir = subdir_name(idx + 1, total, millis);
-            let out_dir = root.join(&subdir);
-            prepare_dump_dir(&out_dir, overwrite)?;
-
-            let config = EjectConfig {
-                output_dir: out_dir.clone(),
-                filename: filename.to_string(),
-                include_placeholders: true,
-                format: format.clone(),
-                subtree_root: None,
-            };
-            let path = EjectService::eject(&book, &config).map_err(Self::to_mcp_error)?;
-            written.push(path.display().to_string());
-        }
-
-        let mut output = format!(
-            "Dumped {} snapshots to: {}\n\n",
-            written.len(),
-            root.display()
-        );
-        for line in &written {
-            output.push_str(&format!("- {line}\n"));
-        }
         Ok(CallToolResult::success(vec![rmcp::model::Content::text(
             output,
         )]))
     }
 
     #[tool(
-        name = "node_history",
-        description = "Show the change history for a specific node. Returns entries in chronological order (oldest first).",
+        name = "import",
+        description = "Import a book from a JSON file (previously exported with `checklist` format: json), a Markdown checklist exported by `checklist`, or, with `source_book`, copy another shelf book. Replaces the current book entirely — pass `dry_run: true` first to preview the imported tree. `subtree` picks one top-level node of the export (position or title); add `parent` to merge it under a node of the current book instead of replacing. A subtree export of the current book goes back in place (its subtree is replaced, not the book) unless `in_place: false`. `depth_strategy` (error / flatten / raise) handles imports deeper than max_depth.",
         annotations(
-            read_only_hint = true,
-            destructive_hint = false,
+            read_only_hint = false,
+            destructive_hint = true,
+            idempotent_hint = false,
             open_world_hint = false
         )
     )]
-    async fn node_history(
+    async fn import(
         &self,
-        Parameters(req): Parameters<McpNodeHistoryRequest>,
+        Parameters(req): Parameters<McpImportRequest>,
     ) -> Result<CallToolResult, McpError> {
-        let id = self.resolve_id(&req.node_id).await?;
-
-        let slug = {
-            let guard = self
-                .selected
-                .read()
-                .map_err(|_| McpError::internal_error("Lock poisoned", None))?;
-            guard
-                .as_ref()
-                .ok_or_else(|| {
-                    McpError::invalid_params(
-                        "No book selected. Use `shelf` to list books and `select_book` to choose one.",
-                        None,
-                    )
-                })?
-                .clone()
+        let file = match (req.file_path.as_deref(), req.source_book.as_deref()) {
+            (Some(file_path), None) => {
+                Some(read_import_file(file_path, req.strict.unwrap_or(false))?)
+            }
+            (Some(_), Some(_)) => {
+                return Err(McpError::invalid_params(
+                    "Give either `file_path` or `source_book`, not both.",
+                    None,
+                ))
+            }
+            (None, _) => None,
         };
-
+        let file = match file {
+            Some(ImportFile::Backup(book)) => return self.restore_backup(*book, &req).await,
+            Some(ImportFile::Tree(tree)) => Some(tree),
+            None => None,
+        };
+        if req.slug.is_some() {
+            return Err(McpError::invalid_params(
+                "`slug` applies to backup files only (checklist format: backup); other imports go into the selected book.",
+                None,
+            ));
+        }
         let svc = self.service().await?;
-        let book = svc.read_tree().await.map_err(Self::to_mcp_error)?;
+        let strategy = parse_depth_strategy(req.depth_strategy.as_deref())?;
+        let tree: EjectTree = match (file, req.source_book.as_deref()) {
+            (Some(tree), _) => tree,
+            (None, Some(book_ref)) => {
+                let slug = self.resolve_book_ref(book_ref)?;
+                validate_slug(&slug)?;
+                let selected = self.selected_slug();
+                if selected.as_deref() == Some(slug.as_str()) {
+                    return Err(McpError::invalid_params(
+                        format!("source_book '{slug}' is the selected book. Select the destination book first, then import from '{slug}'."),
+                        None,
+                    ));
+                }
+                if !self.book_exists(&slug).await? {
+                    return Err(McpError::invalid_params(
+                        format!("Book '{slug}' not found. Use `shelf` to see available books."),
+                        None,
+                    ));
+                }
+                // build_tree → import_tree を通すことで ID は振り直される（コピー元は読むだけ）
+                let source = self
+                    .service_for(&slug)
+                    .await?
+                    .read_tree()
+                    .await
+                    .map_err(Self::to_mcp_error)?;
+                EjectService::build_tree(&source, None)
+            }
+            (None, None) => {
+                return Err(McpError::invalid_params(
+                    "Give `file_path` (a JSON export) or `source_book` (a book on the shelf).",
+                    None,
+                ))
+            }
+        };
 
-        let title = book
-            .get_node(id)
-            .map(|n| n.title().to_string())
-            .unwrap_or_else(|| id.short().to_string());
+        let selected = match req.subtree.as_deref() {
+            Some(selector) => Some(tree.find_top_level(selector).ok_or_else(|| {
+                McpError::invalid_params(
+                    format!(
+                        "No top-level node '{selector}' in '{}'. Use a 1-based position or an exact title: {}",
+                        tree.title,
+                        tree.nodes
+                            .iter()
+                            .enumerate()
+                            .map(|(i, n)| format!("{}. {}", i + 1, n.title))
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    ),
+                    None,
+                )
+            })?),
+            None => None,
+        };
+        if req.parent.is_some() && selected.is_none() {
+            return Err(McpError::invalid_params(
+                "`parent` requires `subtree` (the node to merge).",
+                None,
+            ));
+        }
+        let dedupe = parse_dedupe_mode(req.dedupe.as_deref())?;
+        if dedupe != DedupeMode::Off && req.parent.is_none() {
+            return Err(McpError::invalid_params(
+                "`dedupe` applies to merges only: add `subtree` and `parent`.",
+                None,
+            ));
+        }
+        let on_id_conflict = parse_id_conflict(req.on_id_conflict.as_deref())?;
+        if on_id_conflict != IdConflict::Off && req.parent.is_none() {
+            return Err(McpError::invalid_params(
+                "`on_id_conflict` applies to merges only: add `subtree` and `parent`.",
+                None,
+            ));
+        }
 
-        let cl_repo = self.changelog_for(&slug).await?;
-        let mut entries =
-            outline_mcp_core::domain::repository::ChangeLogRepository::load_by_node(&cl_repo, id)
-                .await
-                .map_err(|e| {
-                    McpError::internal_error(format!("Failed to load history: {e}"), None)
-                })?;
+        let current = svc.read_tree().await.map_err(Self::to_mcp_error)?;
+        // 同じ Book から出した部分木は、既定で元の部分木を置き換える
+        let in_place_root =
+            if req.in_place != Some(false) && selected.is_none() && req.parent.is_none() {
+                tree.in_place_target(&current)
+            } else {
+                None
+            };
+        if req.in_place == Some(true) && in_place_root.is_none() {
+            return Err(McpError::invalid_params(
+                "`in_place` needs a subtree export of the selected book whose subtree still exists (matching `book_id` / `subtree_uuid`), without `subtree` or `parent`.",
+                None,
+            ));
+        }
+        // 変更前に、作成・削除するノード数を1回あたりの予算と照らし合わせる
+        if !req.dry_run.unwrap_or(false) {
+            let incoming: &[EjectTreeNode] = match selected {
+                Some(node) => std::slice::from_ref(node),
+                None => &tree.nodes,
+            };
+            let removed = match (in_place_root, req.parent.is_some()) {
+                (Some(root), _) => current.descendant_count(root),
+                (None, true) => 0,
+                (None, false) => current.node_count(),
+            };
+            let titles: Vec<&str> = incoming.iter().map(|n| n.title.as_str()).collect();
+            self.check_mutation_budget(count_tree_nodes(incoming), removed, &titles, req.confirm)?;
+        }
 
-        // 時系列順（古い順）
-        entries.sort_by_key(|a| a.timestamp);
+        // parent 指定時は現在の Book に部分木をマージ、それ以外は Book を置き換える
+        let (book, node_count, action, note) = match in_place_root {
+            Some(root) => {
+                let mut book = current.clone();
+                let before = book.max_depth();
+                let fit = EjectService::replace_subtree(&mut book, root, &tree, strategy)
+                    .map_err(import_error)?;
+                let count = book.descendant_count(root);
+                let path = find_hierarchical_id(&book, root).unwrap_or_default();
+                (
+                    book,
+                    count,
+                    "replacing a subtree of",
+                    format!(
+                        " Replaced subtree {path} in place.{}",
+                        describe_depth_fit(before, fit)
+                    ),
+                )
+            }
+            None => match (selected, req.parent.as_deref().map(str::trim)) {
+                (Some(node), Some(parent)) => {
+                    let parent = match parent {
+                        "root" | "" => None,
+                        s => Some(self.resolve_id(s, ResolvePolicy::Lenient).await?),
+                    };
+                    let mut book = current.clone();
+                    let before = (book.node_count(), book.max_depth());
+                    let import = EjectService::import_subtree_merged(
+                        &mut book,
+                        parent,
+                        node,
+                        strategy,
+                        dedupe,
+                        on_id_conflict,
+                    )
+                    .map_err(import_error)?;
+                    let added = book.node_count() - before.0;
+                    let mut note = describe_depth_fit(before.1, import.fit);
+                    if dedupe != DedupeMode::Off {
+                        let counts = import.dedupe;
+                        note.push_str(&format!(
+                            " Dedupe: {} created, {} skipped, {} updated.",
+                            counts.created, counts.skipped, counts.updated
+                        ));
+                    }
+                    if on_id_conflict != IdConflict::Off {
+                        let verb = match on_id_conflict {
+                            IdConflict::Replace => "replaced",
+                            _ => "kept",
+                        };
+                        note.push_str(&format!(
+                            " Matched by ID: {} existing node(s) {verb}.",
+                            import.id_matches
+                        ));
+                    }
+                    (book, added, "merging into", note)
+                }
+                (selected, _) => {
+                    let tree = match selected {
+                        Some(node) => EjectTree {
+                            nodes: vec![node.clone()],
+                            ..tree.clone()
+                        },
+                        None => tree.clone(),
+                    };
+                    let (book, fit) =
+                        EjectService::import_tree_fitted(&tree, strategy).map_err(import_error)?;
+                    let count = book.node_count();
+                    (
+                        book,
+                        count,
+                        "replacing",
+                        describe_depth_fit(tree.max_depth, fit),
+                    )
+                }
+            },
+        };
+        let source = match selected {
+            Some(node) => format!("'{}' from '{}'", node.title, tree.title),
+            None => format!("'{}'", tree.title),
+        };
 
-        if entries.is_empty() {
+        if req.dry_run.unwrap_or(false) {
+            let preview = format_toc(&book, &book.all_nodes_dfs(), Numbering::Dash);
             return Ok(CallToolResult::success(vec![rmcp::model::Content::text(
-                format!("No history for \"{}\".", title),
+                format!(
+                    "[DRY RUN] Would import {source}: {node_count} nodes, {action} the current book.{note} No changes saved.\n\n{preview}"
+                ),
             )]));
         }
 
-        let mut output = format!(
-            "# History for \"{}\" ({} entries)\n\n",
-            title,
-            entries.len()
-        );
-        for (i, entry) in entries.iter().enumerate() {
-            let action_str = match entry.action {
-                ChangeAction::Create => "create",
-                ChangeAction::Update => "update",
-                ChangeAction::Delete => "delete",
-                ChangeAction::Move => "move",
-                ChangeAction::Restore => "restore",
-            };
-            output.push_str(&format!(
-                "{}. [{}] {}\n",
-                i + 1,
-                entry.timestamp.to_iso8601(),
-                action_str
+        svc.save_book(&book).await.map_err(Self::to_mcp_error)?;
+
+        Ok(CallToolResult::success(vec![rmcp::model::Content::text(
+            format!("Imported {source}: {node_count} nodes.{note}"),
+        )]))
+    }
+
+    /// バックアップを選択中の Book（`slug` 指定時は新しい Book）としてそのまま戻す。
+    async fn restore_backup(
+        &self,
+        book: TemplateBook,
+        req: &McpImportRequest,
+    ) -> Result<CallToolResult, McpError> {
+        if req.subtree.is_some()
+            || req.parent.is_some()
+            || req.in_place == Some(true)
+            || req.dedupe.is_some()
+            || req.on_id_conflict.is_some()
+            || req.depth_strategy.is_some()
+        {
+            return Err(McpError::invalid_params(
+                "A backup restores the whole book as saved; subtree, parent, in_place, dedupe, on_id_conflict and depth_strategy do not apply.",
+                None,
             ));
-            if let Some(ref before) = entry.before {
-                output.push_str(&format!("   before: {}\n", before));
+        }
+        let issues = book.check_integrity();
+        if !issues.is_empty() {
+            return Err(McpError::invalid_params(
+                format!(
+                    "The backup's tree is damaged, nothing was restored: {}",
+                    issues
+                        .iter()
+                        .map(ToString::to_string)
+                        .collect::<Vec<_>>()
+                        .join("; ")
+                ),
+                None,
+            ));
+        }
+        let (slug, svc, replaced) = match req.slug.as_deref() {
+            Some(slug) => {
+                validate_slug(slug)?;
+                if self.book_exists(slug).await? {
+                    return Err(McpError::invalid_params(
+                        format!("Book '{slug}' already exists. Pick a new slug, or select it and import without `slug` to overwrite it."),
+                        None,
+                    ));
+                }
+                (slug.to_string(), self.service_for(slug).await?, 0)
             }
-            if let Some(ref after) = entry.after {
-                output.push_str(&format!("   after: {}\n", after));
+            None => {
+                let slug = self.selected_slug().unwrap_or_default();
+                let svc = self.service().await?;
+                let current = svc.read_tree().await.map_err(Self::to_mcp_error)?;
+                (slug, svc, current.node_count())
             }
+        };
+        let titles: Vec<&str> = book
+            .root_nodes()
+            .iter()
+            .filter_map(|id| book.get_node(*id))
+            .map(|n| n.title())
+            .collect();
+        if !req.dry_run.unwrap_or(false) {
+            self.check_mutation_budget(book.node_count(), replaced, &titles, req.confirm)?;
         }
 
+        let target = match req.slug {
+            Some(_) => format!("new book '{slug}'"),
+            None => format!("'{slug}', replacing the current book"),
+        };
+        if req.dry_run.unwrap_or(false) {
+            let preview = format_toc(&book, &book.all_nodes_dfs(), Numbering::Dash);
+            return Ok(CallToolResult::success(vec![rmcp::model::Content::text(
+                format!(
+                    "[DRY RUN] Would restore backup '{}' into {target}: {} nodes, IDs preserved. No changes saved.\n\n{preview}",
+                    book.title(),
+                    book.node_count()
+                ),
+            )]));
+        }
+        svc.save_book(&book).await.map_err(Self::to_mcp_error)?;
         Ok(CallToolResult::success(vec![rmcp::model::Content::text(
-            output,
+            format!(
+                "Restored backup '{}' into {target}: {} nodes, IDs preserved.",
+                book.title(),
+                book.node_count()
+            ),
         )]))
     }
 
     #[tool(
-        name = "book_history",
-        description = "List the full edit history of the selected book (all nodes, chronological). Each entry shows action / node hierarchical id / title. Snapshot labels are cross-referenced when the entry's timestamp matches a snapshot. Use `since` / `until` (millis) to bound a range — e.g. pair with two snapshot timestamps from `snapshot_list` to see what changed between them.",
+        name = "init",
+        description = "Create a new book in the shelf. Requires a slug (filename) and title. Optional `tags` label the book for `shelf` filtering; optional `categories` group it in `shelf_categories`. `max_depth`, `skeleton` (top-level sections to pre-create) and `auto_select` fall back to the server config's `init` defaults (4, none, true).",
         annotations(
-            read_only_hint = true,
+            read_only_hint = false,
             destructive_hint = false,
+            idempotent_hint = false,
             open_world_hint = false
         )
     )]
-    async fn book_history(
+    async fn init(
         &self,
-        Parameters(req): Parameters<McpBookHistoryRequest>,
+        Parameters(req): Parameters<McpInitRequest>,
     ) -> Result<CallToolResult, McpError> {
-        let slug = {
-            let guard = self
-                .selected
-                .read()
-                .map_err(|_| McpError::internal_error("Lock poisoned", None))?;
-            guard
-                .as_ref()
-                .ok_or_else(|| {
-                    McpError::invalid_params(
-                        "No book selected. Use `shelf` to list books and `select_book` to choose one.",
-                        None,
-                    )
-                })?
-                .clone()
+        validate_slug(&req.slug)?;
+        let defaults = &self.config.init;
+        let max_depth = req.max_depth.unwrap_or(defaults.max_depth);
+        TemplateBook::validate_max_depth(max_depth)
+            .map_err(|e| McpError::invalid_params(e.to_string(), None))?;
+        let skeleton = req.skeleton.unwrap_or_else(|| defaults.skeleton.clone());
+        let auto_select = req.auto_select.unwrap_or(defaults.auto_select);
+
+        let exists = || {
+            McpError::invalid_params(
+                format!(
+                    "Book '{}' already exists. Choose a different slug.",
+                    req.slug
+                ),
+                None,
+            )
         };
-
-        let since = parse_optional_millis(req.since.as_deref(), "since")?;
-        let until = parse_optional_millis(req.until.as_deref(), "until")?;
-        if let (Some(s), Some(u)) = (since, until) {
-            if s > u {
-                return Err(McpError::invalid_params(
-                    format!("since ({s}) must be <= until ({u})"),
-                    None,
-                ));
-            }
+        if self.book_exists(&req.slug).await? {
+            return Err(exists());
         }
-        let limit = req.limit.unwrap_or(50);
-
-        let svc = self.service().await?;
-        let book = svc.read_tree().await.map_err(Self::to_mcp_error)?;
 
-        let cl_repo = self.changelog_for(&slug).await?;
-        let mut entries =
-            outline_mcp_core::domain::repository::ChangeLogRepository::load_all(&cl_repo)
-                .await
-                .map_err(|e| {
-                    McpError::internal_error(format!("Failed to load history: {e}"), None)
-                })?;
+        std::fs::create_dir_all(&self.shelf_dir).map_err(|e| {
+            McpError::internal_error(format!("Failed to create shelf directory: {e}"), None)
+        })?;
 
-        // 時系列降順 (最新が先頭) — 後で range 絞り + limit
-        entries.sort_by_key(|e| std::cmp::Reverse(e.timestamp));
+        let svc = self.service_for(&req.slug).await?;
+        // 確認の後に別のセッションが同じ slug を作っていても上書きしない
+        let mut book = svc
+            .create_book(&req.title, max_depth)
+            .await
+            .map_err(|e| match e {
+                AppError::BookAlreadyExists => exists(),
+                other => Self::to_mcp_error(other),
+            })?;
+        book.set_max_nodes(defaults.max_nodes);
+        book.set_tags(req.tags.unwrap_or_default());
+        book.set_categories(req.categories.unwrap_or_default());
 
-        if let Some(s) = since {
-            entries.retain(|e| e.timestamp.as_millis() >= s);
+        let skeleton: Vec<String> = skeleton
+            .iter()
+            .map(|t| t.trim())
+            .filter(|t| !t.is_empty())
+            .map(str::to_string)
+            .collect();
+        if !skeleton.is_empty() {
+            for title in &skeleton {
+                book.add_node(AddNodeRequest {
+                    parent: None,
+                    title: title.clone(),
+                    node_type: NodeType::Section,
+                    body: None,
+                    placeholder: None,
+                    position: Position::Last,
+                    properties: HashMap::new(),
+                })
+                .map_err(|e| Self::to_mcp_error(e.into()))?;
+            }
         }
-        if let Some(u) = until {
-            entries.retain(|e| e.timestamp.as_millis() <= u);
+        if !skeleton.is_empty()
+            || book.max_nodes().is_some()
+            || !book.tags().is_empty()
+            || !book.categories().is_empty()
+        {
+            svc.save_book(&book).await.map_err(Self::to_mcp_error)?;
         }
 
-        let total_in_range = entries.len();
-        if limit > 0 && entries.len() > limit {
-            entries.truncate(limit);
+        if auto_select {
+            self.set_selected(Some(req.slug.clone()));
+            self.record_session_baseline(&req.slug, &book);
         }
 
-        // snapshot label cross-ref (best-effort: store construction failure
-        // should not break history display)
-        let snap_infos = match self.snapshot_service_for(&slug).await {
-            Ok(svc) => svc.list().await.unwrap_or_default(),
-            Err(_) => Vec::new(),
-        };
-        let snap_label_by_millis: HashMap<i64, String> = snap_infos
-            .iter()
-            .filter_map(|i| {
-                i.label
-                    .as_ref()
-                    .map(|l| (i.timestamp.as_millis(), l.clone()))
-            })
-            .collect();
-
-        if entries.is_empty() {
-            return Ok(CallToolResult::success(vec![rmcp::model::Content::text(
-                format!(
-                    "No history for \"{}\" in the requested range.",
-                    book.title()
-                ),
-            )]));
+        let mut msg = format!(
+            "Created book: '{}' (slug: {}, max_depth: {}).",
+            book.title(),
+            req.slug,
+            book.max_depth()
+        );
+        if !skeleton.is_empty() {
+            msg.push_str(&format!(" Sections: {}.", skeleton.join(", ")));
         }
-
-        let showing_note = if limit > 0 && total_in_range > limit {
-            format!(
-                " (showing newest {} of {} in range)",
-                entries.len(),
-                total_in_range
-            )
-        } else {
-            format!(" ({} entries)", entries.len())
-        };
-
-        let mut output = format!("# History for \"{}\"{}\n\n", book.title(), showing_note);
-
-        for (i, entry) in entries.iter().enumerate() {
-            let action_str = match entry.action {
-                ChangeAction::Create => "create",
-                ChangeAction::Update => "update",
-                ChangeAction::Delete => "delete",
-                ChangeAction::Move => "move",
-                ChangeAction::Restore => "restore",
-            };
-            let hier = find_hierarchical_id(&book, entry.node_id)
-                .unwrap_or_else(|| entry.node_id.short().to_string());
-            let title = book
-                .get_node(entry.node_id)
-                .map(|n| n.title().to_string())
-                .unwrap_or_else(|| "(deleted)".to_string());
-            let label_note = snap_label_by_millis
-                .get(&entry.timestamp.as_millis())
-                .map(|l| format!(" [snapshot: {l}]"))
-                .unwrap_or_default();
-            output.push_str(&format!(
-                "{}. [{}] {} node={} title=\"{}\"{}\n",
-                i + 1,
-                entry.timestamp.to_iso8601(),
-                action_str,
-                hier,
-                title,
-                label_note
-            ));
+        if auto_select {
+            msg.push_str(" Auto-selected.");
         }
-
         Ok(CallToolResult::success(vec![rmcp::model::Content::text(
-            output,
+            msg,
         )]))
     }
 
     #[tool(
-        name = "dump",
-        description = "Export the entire selected book to a file. Unlike `checklist`, this always exports the full book (no subtree). Supports markdown (default) and json formats.",
+        name = "book_config",
+        description = "Show or change book-level settings of the selected book. Call with no arguments to show the current settings. `default_placeholder` is the fill-in hint exported for content nodes without their own placeholder (pass \"\" to clear). `max_nodes` caps the number of nodes (pass 0 to remove the limit). `tags` replaces the topic tags used by `shelf` filtering and `categories` the groups listed by `shelf_categories` (pass [] to clear).",
         annotations(
             read_only_hint = false,
             destructive_hint = false,
@@ -1451,85 +2091,162 @@ impl OutlineMcpServer {
             open_world_hint = false
         )
     )]
-    async fn dump(
+    async fn book_config(
         &self,
-        Parameters(req): Parameters<McpDumpRequest>,
+        Parameters(req): Parameters<McpBookConfigRequest>,
     ) -> Result<CallToolResult, McpError> {
         let svc = self.service().await?;
-        let book = svc.read_tree().await.map_err(Self::to_mcp_error)?;
+        let mut book = svc.read_tree().await.map_err(Self::to_mcp_error)?;
 
-        let format = match req.format.as_deref() {
-            Some("json") => EjectFormat::Json,
-            Some("markdown") | None => EjectFormat::Markdown,
-            Some(other) => {
+        let mut changed = false;
+        if let Some(ph) = req.default_placeholder {
+            let ph = unescape_newlines(&ph);
+            book.set_default_placeholder((!ph.trim().is_empty()).then_some(ph));
+            changed = true;
+        }
+        if let Some(max) = req.max_nodes {
+            book.set_max_nodes((max > 0).then_some(max));
+            changed = true;
+        }
+        if let Some(depth) = req.max_depth {
+            if depth < book.max_depth() {
                 return Err(McpError::invalid_params(
-                    format!("Unknown format: '{other}'. Use: markdown, json"),
+                    format!(
+                        "max_depth can only be raised (currently {})",
+                        book.max_depth()
+                    ),
                     None,
-                ))
+                ));
             }
-        };
+            book.raise_max_depth(depth)
+                .map_err(|e| McpError::invalid_params(e.to_string(), None))?;
+            changed = true;
+        }
+        if let Some(tags) = req.tags {
+            book.set_tags(tags);
+            changed = true;
+        }
+        if let Some(categories) = req.categories {
+            book.set_categories(categories);
+            changed = true;
+        }
+        if let Some(defaults) = req.eject_defaults {
+            if let Some(format) = defaults.format.as_deref() {
+                parse_eject_format(format)?;
+            }
+            let output_dir = defaults.output_dir.filter(|d| !d.trim().is_empty());
+            book.set_eject_defaults(EjectDefaults {
+                output_dir,
+                format: defaults.format,
+                include_placeholders: defaults.include_placeholders,
+            });
+            changed = true;
+        }
 
-        let default_ext = match format {
-            EjectFormat::Markdown => "md",
-            EjectFormat::Json => "json",
-        };
+        if changed {
+            svc.save_book(&book).await.map_err(Self::to_mcp_error)?;
+        }
 
-        let filename = match req.filename {
-            Some(f) => f,
-            None => format!("{}.{}", sanitize_for_filename(book.title()), default_ext),
+        let mut output = if changed {
+            "Book config updated.\n\n".to_string()
+        } else {
+            String::new()
         };
-        validate_filename(&filename)?;
-
-        let output_dir = PathBuf::from(&req.output_dir);
+        output.push_str(&format_book_config(&book));
+        Ok(CallToolResult::success(vec![rmcp::model::Content::text(
+            output,
+        )]))
+    }
 
-        let config = EjectConfig {
-            output_dir,
-            filename,
-            include_placeholders: true,
-            format,
-            subtree_root: None,
+    #[tool(
+        name = "book_info",
+        description = "Show a book's stable identity and shape: its ID (a UUID that survives slug renames), slug, title, max_depth, deepest actual branch, node count, and number of top-level nodes. Defaults to the selected book; pass `book` (number from `shelf` or slug) for another.",
+        annotations(
+            read_only_hint = true,
+            destructive_hint = false,
+            open_world_hint = false
+        )
+    )]
+    async fn book_info(
+        &self,
+        Parameters(req): Parameters<McpBookInfoRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        let slug = match req.book.as_deref() {
+            Some(book_ref) => {
+                let slug = self.resolve_book_ref(book_ref)?;
+                validate_slug(&slug)?;
+                if !self.book_exists(&slug).await? {
+                    return Err(McpError::invalid_params(
+                        format!("Book '{slug}' not found. Use `shelf` to see available books."),
+                        None,
+                    ));
+                }
+                slug
+            }
+            None => self.selected_slug().ok_or_else(|| {
+                McpError::invalid_params(
+                    "No book selected. Use `select_book` or pass `book`.",
+                    None,
+                )
+            })?,
         };
-
-        let path = EjectService::eject(&book, &config).map_err(Self::to_mcp_error)?;
-
+        let book = self
+            .service_for(&slug)
+            .await?
+            .read_tree()
+            .await
+            .map_err(Self::to_mcp_error)?;
         Ok(CallToolResult::success(vec![rmcp::model::Content::text(
-            format!("Book dumped to: {}", path.display()),
+            format_book_info(&slug, &book),
         )]))
     }
 
-    /// UUID文字列をNodeIdに解決する。フルUUIDまたは短縮プレフィックスを受け付ける。
-    /// 階層番号やタイトル一致は受け付けない（バッチ操作のtoc IDズレ問題回避）。
-    async fn resolve_uuid(
+    #[tool(
+        name = "verify_roundtrip",
+        description = "Self-check before relying on JSON exports for backup: export the selected book to JSON, import it back, and compare the copy with the original (titles, types, bodies, placeholders, notes, estimates, owners, properties, child order). Replies `lossless` or lists the differing nodes by hierarchical ID. Done state, status, protection and dependencies are not part of the export and are not compared. Book is NOT modified.",
+        annotations(
+            read_only_hint = true,
+            destructive_hint = false,
+            idempotent_hint = true,
+            open_world_hint = false
+        )
+    )]
+    async fn verify_roundtrip(
         &self,
-        s: &str,
-    ) -> Result<outline_mcp_core::domain::model::id::NodeId, McpError> {
-        // 1. Full UUID
-        if let Ok(id) = parse_node_id(s) {
-            return Ok(id);
-        }
-        // 2. Short prefix in current book
+        #[allow(unused_variables)] Parameters(_req): Parameters<McpVerifyRoundtripRequest>,
+    ) -> Result<CallToolResult, McpError> {
         let svc = self.service().await?;
         let book = svc.read_tree().await.map_err(Self::to_mcp_error)?;
-        let matches: Vec<outline_mcp_core::domain::model::id::NodeId> = book
-            .all_node_ids()
-            .filter(|id| id.to_string().starts_with(s))
-            .collect();
-        match matches.len() {
-            1 => Ok(matches[0]),
-            0 => Err(McpError::invalid_params(
-                format!("No node with UUID starting with '{s}'"),
-                None,
-            )),
-            n => Err(McpError::invalid_params(
-                format!("Ambiguous UUID prefix '{s}' matches {n} nodes"),
-                None,
-            )),
+        let differences = EjectService::verify_roundtrip(&book).map_err(Self::to_mcp_error)?;
+        if differences.is_empty() {
+            return Ok(CallToolResult::success(vec![rmcp::model::Content::text(
+                format!(
+                    "lossless: '{}' ({} nodes) survives a JSON export and re-import unchanged.",
+                    book.title(),
+                    book.node_count()
+                ),
+            )]));
         }
+        let mut msg = format!(
+            "NOT lossless: {} difference(s) after a JSON export and re-import of '{}':\n",
+            differences.len(),
+            book.title()
+        );
+        for difference in &differences {
+            let location = match difference.node_id {
+                Some(id) => find_hierarchical_id(&book, id).unwrap_or_else(|| id.short()),
+                None => "book".to_string(),
+            };
+            msg.push_str(&format!("- {location}: {}\n", difference.detail));
+        }
+        Ok(CallToolResult::success(vec![rmcp::model::Content::text(
+            msg,
+        )]))
     }
 
     #[tool(
-        name = "node_batch_move",
-        description = "Move multiple nodes in a single atomic operation. All nodes must be specified by UUID (not toc ID). Use `node_query` or `dump` to find UUIDs. All moves succeed or none are saved.",
+        name = "split_book",
+        description = "Split the selected book into one new book per top-level section: the section's title becomes the book title and its children the book's top level. `slug_template` names the new books, e.g. 'proj-{n}' or 'ops-{title}'. The original is left as is unless `remove_sections: true`. Top-level content items are not split. Replies with the created slugs.",
         annotations(
             read_only_hint = false,
             destructive_hint = true,
@@ -1537,55 +2254,158 @@ impl OutlineMcpServer {
             open_world_hint = false
         )
     )]
-    async fn node_batch_move(
+    async fn split_book(
         &self,
-        Parameters(req): Parameters<McpBatchMoveRequest>,
+        Parameters(req): Parameters<McpSplitBookRequest>,
     ) -> Result<CallToolResult, McpError> {
-        let total = req.moves.len();
+        let template = req.slug_template.trim();
+        if !template.contains("{n}") && !template.contains("{title}") {
+            return Err(McpError::invalid_params(
+                "`slug_template` needs `{n}` or `{title}` so every section gets its own slug (e.g. 'proj-{n}')",
+                None,
+            ));
+        }
+        let add_suffix = match req.on_collision.as_deref() {
+            None | Some("error") => false,
+            Some("suffix") => true,
+            Some(other) => {
+                return Err(McpError::invalid_params(
+                    format!("Invalid on_collision '{other}': use 'error' or 'suffix'"),
+                    None,
+                ))
+            }
+        };
+        let svc = self.service().await?;
+        let source_slug = self.selected_slug().unwrap_or_default();
+        let book = svc.read_tree().await.map_err(Self::to_mcp_error)?;
+        let sections: Vec<&TemplateNode> = book
+            .root_nodes()
+            .iter()
+            .filter_map(|id| book.get_node(*id))
+            .filter(|n| *n.node_type() == NodeType::Section)
+            .collect();
+        if sections.is_empty() {
+            return Err(McpError::invalid_params(
+                format!("'{}' has no top-level sections to split", book.title()),
+                None,
+            ));
+        }
 
-        // Resolve all UUIDs first, reporting the first failure with its index
-        let mut resolved: Vec<(
-            outline_mcp_core::domain::model::id::NodeId,
-            Option<outline_mcp_core::domain::model::id::NodeId>,
-            usize,
-        )> = Vec::with_capacity(total);
+        // 守られたノードを含む Section は、何も作る前に削除を断る
+        if req.remove_sections {
+            let mut trial = book.clone();
+            let ctx = ErrorContext {
+                refs: &[],
+                parent: None,
+            };
+            for section in &sections {
+                trial
+                    .remove_node(section.id())
+                    .map_err(|e| self.to_mcp_error_in(&book, e.into(), &ctx))?;
+            }
+        }
 
-        for (i, item) in req.moves.iter().enumerate() {
-            let id = self.resolve_uuid(&item.node_id).await.map_err(|e| {
+        // 生成した slug を検証し、既存の Book や生成済みの slug との衝突を解決する
+        let mut taken: HashSet<String> = self.list_book_slugs()?.into_iter().collect();
+        let mut planned: Vec<(String, &TemplateNode)> = Vec::new();
+        let mut clashes = Vec::new();
+        for (i, section) in sections.iter().enumerate() {
+            let base = template
+                .replace("{n}", &(i + 1).to_string())
+                .replace("{title}", &slug_from_title(section.title()));
+            validate_slug(&base).map_err(|e| {
                 McpError::invalid_params(
                     format!(
-                        "Batch move failed at operation {}/{total} (node {}): {e}. No changes saved.",
-                        i + 1,
-                        item.node_id
+                        "slug '{base}' generated for '{}' is invalid: {}",
+                        section.title(),
+                        e.message
                     ),
                     None,
                 )
             })?;
-            let new_parent = match item.new_parent.as_deref() {
-                Some(s) => Some(self.resolve_uuid(s).await.map_err(|e| {
-                    McpError::invalid_params(
-                        format!(
-                            "Batch move failed at operation {}/{total} (node {}): parent UUID: {e}. No changes saved.",
-                            i + 1,
-                            item.node_id
-                        ),
-                        None,
-                    )
-                })?),
-                None => None,
+            let slug = if !taken.contains(&base) {
+                base
+            } else if add_suffix {
+                (2..)
+                    .map(|k| format!("{base}-{k}"))
+                    .find(|s| !taken.contains(s))
+                    .unwrap_or_default()
+            } else {
+                clashes.push(format!("'{base}' ({})", section.title()));
+                continue;
             };
-            let position = item.position.unwrap_or(usize::MAX);
-            resolved.push((id, new_parent, position));
+            taken.insert(slug.clone());
+            planned.push((slug, section));
+        }
+        if !clashes.is_empty() {
+            return Err(McpError::invalid_params(
+                format!(
+                    "Slug(s) already taken: {}. Nothing was created. Change `slug_template` or pass `on_collision: \"suffix\"`.",
+                    clashes.join(", ")
+                ),
+                None,
+            ));
         }
 
-        let svc = self.service().await?;
-        let (count, warnings) = svc.batch_move(resolved).await.map_err(|e| {
-            McpError::internal_error(format!("Batch move failed: {e}. No changes saved."), None)
-        })?;
+        let created_count: usize = sections.iter().map(|s| book.descendant_count(s.id())).sum();
+        let removed_count = if req.remove_sections {
+            created_count + sections.len()
+        } else {
+            0
+        };
+        let titles: Vec<&str> = sections.iter().map(|s| s.title()).collect();
+        self.check_mutation_budget(created_count, removed_count, &titles, req.confirm)?;
+
+        let mut msg = format!("Split '{}' into {} book(s):\n", book.title(), planned.len());
+        let mut dropped = Vec::new();
+        for (slug, section) in &planned {
+            let tree = EjectService::build_tree(&book, Some(section.id()));
+            let new_book = EjectService::import_tree(&tree).map_err(Self::to_mcp_error)?;
+            self.service_for(slug)
+                .await?
+                .save_book(&new_book)
+                .await
+                .map_err(Self::to_mcp_error)?;
+            msg.push_str(&format!(
+                "- {slug}: '{}' ({} nodes)\n",
+                new_book.title(),
+                new_book.node_count()
+            ));
+            // Section 自身の本文などは新しい Book に入る場所がない
+            if section.body().is_some() || section.note().is_some() || section.notes().is_some() {
+                dropped.push(section.title());
+            }
+        }
+        if !dropped.is_empty() {
+            msg.push_str(&format!(
+                "Not carried over: the body / notes of {} (a book has no body of its own).\n",
+                dropped.join(", ")
+            ));
+        }
 
-        let mut msg = format!("Batch move complete: {count}/{total} operations succeeded.");
-        for w in warnings.into_iter().flatten() {
-            msg.push_str(&format!("\n[WARNING] {w}"));
+        if req.remove_sections {
+            let ids: Vec<NodeId> = planned.iter().map(|(_, s)| s.id()).collect();
+            svc.with_book(|book| {
+                for id in &ids {
+                    book.remove_node(*id)
+                        .map_err(|e| Self::to_mcp_error(e.into()))?;
+                }
+                let changes = ids.iter().map(|id| (*id, ChangeAction::Delete)).collect();
+                Ok::<_, McpError>(((), changes))
+            })
+            .await
+            .map_err(Self::to_mcp_error)??;
+            msg.push_str(&format!("Removed the split sections from '{source_slug}'."));
+        } else {
+            msg.push_str(
+                "The original book is unchanged; pass `remove_sections: true` to move the sections instead of copying them.",
+            );
+        }
+        let kept = book.root_nodes().len() - sections.len();
+        if kept > 0 {
+            msg.push_str(&format!(
+                "\n{kept} top-level content item(s) stay in '{source_slug}' (only sections are split)."
+            ));
         }
         Ok(CallToolResult::success(vec![rmcp::model::Content::text(
             msg,
@@ -1593,416 +2413,5062 @@ impl OutlineMcpServer {
     }
 
     #[tool(
-        name = "node_batch_update",
-        description = "Update multiple nodes' properties, status, title, or body in a single atomic operation. All nodes must be specified by UUID.",
+        name = "help",
+        description = "Quick reference for agents: which node references are accepted and in what order they are tried (toc number, UUID, UUID prefix, title), how books are referenced, the node types and statuses, and one-line examples of the common flows. Takes no parameters; nothing is read or changed.",
         annotations(
-            read_only_hint = false,
+            read_only_hint = true,
             destructive_hint = false,
             idempotent_hint = true,
             open_world_hint = false
         )
     )]
-    async fn node_batch_update(
+    async fn help(
         &self,
-        Parameters(req): Parameters<McpBatchUpdateRequest>,
+        #[allow(unused_variables)] Parameters(_req): Parameters<McpHelpRequest>,
     ) -> Result<CallToolResult, McpError> {
-        let total = req.updates.len();
-
-        // Resolve all UUIDs first
-        let mut resolved: Vec<(
-            outline_mcp_core::domain::model::id::NodeId,
-            outline_mcp_core::domain::model::book::UpdateNodeRequest,
-        )> = Vec::with_capacity(total);
+        Ok(CallToolResult::success(vec![rmcp::model::Content::text(
+            help_text(),
+        )]))
+    }
 
-        for (i, item) in req.updates.iter().enumerate() {
-            let id = self.resolve_uuid(&item.node_id).await.map_err(|e| {
-                McpError::invalid_params(
-                    format!(
-                        "Batch update failed at operation {}/{total} (node {}): {e}. No changes saved.",
-                        i + 1,
-                        item.node_id
-                    ),
-                    None,
-                )
-            })?;
-            let status = item
-                .status
-                .as_deref()
-                .map(parse_node_status)
-                .transpose()
-                .map_err(|e| {
-                    McpError::invalid_params(
-                        format!(
-                            "Batch update failed at operation {}/{total} (node {}): {e}. No changes saved.",
-                            i + 1,
-                            item.node_id
-                        ),
-                        None,
-                    )
-                })?;
-            let update_req = outline_mcp_core::domain::model::book::UpdateNodeRequest {
-                title: item.title.as_deref().map(unescape_newlines),
-                body: item.body.clone().map(|b| b.map(|s| unescape_newlines(&s))),
-                node_type: None,
-                placeholder: None,
-                properties: item.properties.clone(),
-                status,
-            };
-            resolved.push((id, update_req));
-        }
-
-        let svc = self.service().await?;
-        let (count, warnings) = svc.batch_update(resolved).await.map_err(|e| {
-            McpError::internal_error(format!("Batch update failed: {e}. No changes saved."), None)
-        })?;
-
-        let mut msg = format!("Batch update complete: {count}/{total} operations succeeded.");
-        for w in warnings.into_iter().flatten() {
-            msg.push_str(&format!("\n[WARNING] {w}"));
-        }
+    #[tool(
+        name = "status",
+        description = "Show this session's state: the shelf directory and the currently selected book. Selection is per session (kept in server memory, never written to the shelf), so other sessions sharing the shelf are unaffected.",
+        annotations(
+            read_only_hint = true,
+            destructive_hint = false,
+            open_world_hint = false
+        )
+    )]
+    async fn status(
+        &self,
+        #[allow(unused_variables)] Parameters(_req): Parameters<McpStatusRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        let book_count = self.list_book_slugs()?.len();
+        let selected = self.selected_slug();
+        let book = match selected.as_deref() {
+            Some(slug) => match self.service_for(slug).await {
+                Ok(svc) => svc.read_tree().await.ok(),
+                Err(_) => None,
+            },
+            None => None,
+        };
+        let output = format_status(
+            &self.shelf_dir,
+            book_count,
+            selected.as_deref(),
+            book.as_ref(),
+            &self.default_export_dir(book.as_ref()),
+        );
         Ok(CallToolResult::success(vec![rmcp::model::Content::text(
-            msg,
+            output,
         )]))
     }
 
     #[tool(
-        description = "Query nodes by properties, status, type, or subtree. Returns UUIDs needed for batch operations. Use `include_body: true` to include node content.",
+        name = "shelf",
+        description = "List all books in the shelf. Shows book slugs, titles, and node counts. The currently selected book is marked with ★; pass pin_selected=true to list it first.",
         annotations(
             read_only_hint = true,
             destructive_hint = false,
             open_world_hint = false
         )
     )]
-    async fn node_query(
+    async fn shelf(
         &self,
-        Parameters(req): Parameters<McpNodeQueryRequest>,
+        Parameters(req): Parameters<McpShelfRequest>,
     ) -> Result<CallToolResult, McpError> {
-        use outline_mcp_core::domain::model::node::NodeType;
+        let slugs = self.list_book_slugs()?;
 
-        let svc = self.service().await?;
-        let book = svc.read_tree().await.map_err(Self::to_mcp_error)?;
+        if slugs.is_empty() {
+            return Ok(CallToolResult::success(vec![rmcp::model::Content::text(
+                "Shelf is empty. Use `init` to create a new book.",
+            )]));
+        }
 
-        let root_id = match req.subtree_root.as_deref() {
-            Some(s) => Some(self.resolve_id(s).await?),
-            None => None,
-        };
+        // Guard は clone した値だけ保持して即座に drop する（`.await` を跨いで
+        // `RwLockReadGuard` (非 Send) を持ち越すと `#[tool]` の Send 境界を破る）。
+        let selected = self.selected_slug();
 
-        let mut nodes = match root_id {
-            Some(id) => book.subtree_nodes(id),
-            None => book.all_nodes_dfs(),
-        };
+        let mut entries = self.shelf_entries(&slugs).await;
+        let tag = req.tag.as_deref().map(str::trim).filter(|t| !t.is_empty());
+        if let Some(tag) = tag {
+            entries.retain(|e| e.tags.iter().any(|t| t.eq_ignore_ascii_case(tag)));
+        }
+        let category = req
+            .category
+            .as_deref()
+            .map(str::trim)
+            .filter(|c| !c.is_empty());
+        if let Some(category) = category {
+            entries.retain(|e| {
+                e.categories
+                    .iter()
+                    .any(|c| c.eq_ignore_ascii_case(category))
+            });
+        }
+        let filtered = tag.is_some() || category.is_some();
 
-        if let Some(ref filter) = req.filter {
-            if !filter.is_empty() {
-                nodes.retain(|node| {
-                    filter
-                        .iter()
-                        .all(|(k, v)| node.get_property(k).map(|pv| pv == v).unwrap_or(false))
-                });
+        let pin = req.pin_selected.unwrap_or(false);
+        if pin {
+            if let Some(pos) = entries
+                .iter()
+                .position(|e| selected.as_deref() == Some(e.slug.as_str()))
+            {
+                let entry = entries.remove(pos);
+                entries.insert(0, entry);
             }
         }
+        // select_book の番号指定が表示順（絞り込み後）と一致するよう、表示順を記録する
+        *write_recovering(&self.shelf_order) =
+            (pin || filtered).then(|| entries.iter().map(|e| e.slug.clone()).collect());
 
-        if let Some(ref k) = req.kind {
-            let nt = parse_node_type(k)?;
-            nodes.retain(|n| n.node_type() == &nt);
+        let mut filters = Vec::new();
+        if let Some(tag) = tag {
+            filters.push(format!("tagged '{tag}'"));
         }
-
-        if let Some(ref s) = req.status {
-            let st = parse_node_status(s)?;
-            nodes.retain(|n| n.status() == st);
+        if let Some(category) = category {
+            filters.push(format!("in category '{category}'"));
         }
-
-        if nodes.is_empty() {
-            return Ok(CallToolResult::success(vec![rmcp::model::Content::text(
-                "No matching nodes found.",
-            )]));
+        let mut output = if filters.is_empty() {
+            format!("# Shelf ({} books)\n\n", entries.len())
+        } else {
+            format!(
+                "# Shelf ({} books {})\n\n",
+                entries.len(),
+                filters.join(", ")
+            )
+        };
+        if entries.is_empty() && filtered {
+            output.push_str("No books match. Use `shelf_tags` / `shelf_categories` to see the tags and categories in use.\n");
         }
-
-        let mut output = format!("# Query Results ({} matches)\n", nodes.len());
-        for (i, node) in nodes.iter().enumerate() {
-            let short = node.id().short();
-            let full = node.id().to_string();
-            let type_str = match node.node_type() {
-                NodeType::Section => "section",
-                NodeType::Content => "content",
+        for (i, entry) in entries.iter().enumerate() {
+            let marker = if selected.as_deref() == Some(entry.slug.as_str()) {
+                " ★"
+            } else {
+                ""
             };
-            let status_str = match node.status() {
-                outline_mcp_core::domain::model::changelog::NodeStatus::Active => "active",
-                outline_mcp_core::domain::model::changelog::NodeStatus::Draft => "draft",
+            let tags = if entry.tags.is_empty() {
+                String::new()
+            } else {
+                format!(" [{}]", entry.tags.join(", "))
+            };
+            let orphans = match entry.unreachable {
+                0 => String::new(),
+                n => format!(", {n} unreachable"),
             };
             output.push_str(&format!(
-                "\n{}. [{}] {}\n   UUID: {}\n   Type: {}\n   Status: {}\n",
+                "{}. {} — \"{}\" ({} nodes{}){}{}\n",
                 i + 1,
-                short,
-                node.title(),
-                full,
-                type_str,
-                status_str,
+                entry.slug,
+                entry.title,
+                entry.node_count,
+                orphans,
+                tags,
+                marker
             ));
-            let props = node.properties();
-            if !props.is_empty() {
-                let props_str = props
-                    .iter()
-                    .map(|(k, v)| format!("{}={}", k, v))
-                    .collect::<Vec<_>>()
-                    .join(", ");
-                output.push_str(&format!("   Properties: {}\n", props_str));
-            }
-            if req.include_body {
-                if let Some(body) = node.body() {
-                    output.push_str(&format!("   Body: {}\n", body));
-                }
-            }
-            output.push_str("   ---\n");
         }
 
         Ok(CallToolResult::success(vec![rmcp::model::Content::text(
             output,
         )]))
     }
-}
 
-// ---------------------------------------------------------------------------
-// snapshot label / dump helpers
-// ---------------------------------------------------------------------------
+    #[tool(
+        name = "shelf_tags",
+        description = "List every book tag in use on the shelf with how many books carry it. Filter the shelf by one with `shelf` `tag`; set tags with `init` or `book_config`.",
+        annotations(
+            read_only_hint = true,
+            destructive_hint = false,
+            open_world_hint = false
+        )
+    )]
+    async fn shelf_tags(
+        &self,
+        #[allow(unused_variables)] Parameters(_req): Parameters<McpShelfTagsRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        let slugs = self.list_book_slugs()?;
+        let entries = self.shelf_entries(&slugs).await;
+
+        // 大文字小文字違いは最初に見つかった表記にまとめる
+        let mut counts: Vec<(String, usize)> = Vec::new();
+        for tag in entries.iter().flat_map(|e| &e.tags) {
+            match counts.iter_mut().find(|(t, _)| t.eq_ignore_ascii_case(tag)) {
+                Some((_, n)) => *n += 1,
+                None => counts.push((tag.clone(), 1)),
+            }
+        }
+        if counts.is_empty() {
+            return Ok(CallToolResult::success(vec![rmcp::model::Content::text(
+                "No book tags yet. Add them with `book_config` `tags` (or `init` `tags`).",
+            )]));
+        }
+        counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| natural_cmp(&a.0, &b.0)));
 
-/// snapshot label の validation。
-///
-/// - 空 / 前後空白のみは拒否
-/// - 64 文字上限
-/// - 許可文字: 英数字 / space / `-_.:,()`  (path traversal 防止 & filename 化しないので厳格すぎない範囲)
-/// - trim した form を返す
-fn validate_snapshot_label(raw: &str) -> Result<String, McpError> {
-    let trimmed = raw.trim();
-    if trimmed.is_empty() {
-        return Err(McpError::invalid_params(
-            "label must not be empty or whitespace only",
-            None,
-        ));
-    }
-    if trimmed.chars().count() > 64 {
-        return Err(McpError::invalid_params(
-            "label must be 64 characters or fewer",
-            None,
-        ));
+        let mut output = format!("# Shelf tags ({})\n\n", counts.len());
+        for (tag, n) in counts {
+            output.push_str(&format!("- {tag} ({n} books)\n"));
+        }
+        Ok(CallToolResult::success(vec![rmcp::model::Content::text(
+            output,
+        )]))
     }
-    let ok = trimmed
-        .chars()
-        .all(|c| c.is_alphanumeric() || matches!(c, ' ' | '-' | '_' | '.' | ':' | ',' | '(' | ')'));
-    if !ok {
-        return Err(McpError::invalid_params(
-            "label may only contain letters, digits, spaces, and '-_.:,()'",
-            None,
-        ));
+
+    #[tool(
+        name = "shelf_categories",
+        description = "List the books on the shelf grouped by category (a book can sit in several); uncategorized books come last. Filter the shelf by one with `shelf` `category`; set categories with `init` or `book_config`.",
+        annotations(
+            read_only_hint = true,
+            destructive_hint = false,
+            open_world_hint = false
+        )
+    )]
+    async fn shelf_categories(
+        &self,
+        #[allow(unused_variables)] Parameters(_req): Parameters<McpShelfCategoriesRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        let slugs = self.list_book_slugs()?;
+        let entries = self.shelf_entries(&slugs).await;
+
+        // 大文字小文字違いは最初に見つかった表記にまとめる
+        let mut groups: Vec<(String, Vec<&ShelfEntry>)> = Vec::new();
+        let mut uncategorized = Vec::new();
+        for entry in &entries {
+            if entry.categories.is_empty() {
+                uncategorized.push(entry);
+            }
+            for category in &entry.categories {
+                match groups
+                    .iter_mut()
+                    .find(|(c, _)| c.eq_ignore_ascii_case(category))
+                {
+                    Some((_, books)) => books.push(entry),
+                    None => groups.push((category.clone(), vec![entry])),
+                }
+            }
+        }
+        if groups.is_empty() {
+            return Ok(CallToolResult::success(vec![rmcp::model::Content::text(
+                "No book categories yet. Add them with `book_config` `categories` (or `init` `categories`).",
+            )]));
+        }
+        groups.sort_by_key(|(c, _)| c.to_lowercase());
+
+        let mut output = format!("# Shelf categories ({})\n", groups.len());
+        let uncategorized =
+            (!uncategorized.is_empty()).then(|| ("(uncategorized)".to_string(), uncategorized));
+        for (category, books) in groups.iter().chain(uncategorized.iter()) {
+            output.push_str(&format!("\n## {category} ({} books)\n", books.len()));
+            for entry in books {
+                output.push_str(&format!("- {} — \"{}\"\n", entry.slug, entry.title));
+            }
+        }
+        Ok(CallToolResult::success(vec![rmcp::model::Content::text(
+            output,
+        )]))
     }
-    Ok(trimmed.to_string())
-}
 
-/// optional millis 文字列を i64 に parse する。None は None を返す。
-fn parse_optional_millis(s: Option<&str>, field: &str) -> Result<Option<i64>, McpError> {
-    match s {
-        None => Ok(None),
-        Some(v) => v.parse::<i64>().map(Some).map_err(|_| {
-            McpError::invalid_params(
-                format!("Invalid {field}: '{v}'. Must be a millis integer."),
-                None,
-            )
-        }),
+    #[tool(
+        name = "search_shelf",
+        description = "Find text in every book on the shelf (titles, bodies, placeholders, notes; case-insensitive). Matches are grouped by book with `toc` IDs and snippets. Stops after `limit` matches (default 50); books that fail to load are skipped with a note.",
+        annotations(
+            read_only_hint = true,
+            destructive_hint = false,
+            open_world_hint = false
+        )
+    )]
+    async fn search_shelf(
+        &self,
+        Parameters(req): Parameters<McpSearchShelfRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        if req.query.trim().is_empty() {
+            return Err(McpError::invalid_params("`query` must not be empty", None));
+        }
+        let limit = req.limit.unwrap_or(50).max(1);
+
+        let mut hits = Vec::new();
+        let mut skipped = Vec::new();
+        let mut remaining = limit;
+        for slug in self.list_book_slugs()? {
+            if remaining == 0 {
+                break;
+            }
+            // event store は開かず、Book 本体だけを読む
+            let book = match self.repository_for(&slug).load().await {
+                Ok(Some(book)) => book,
+                Ok(None) => continue,
+                Err(e) => {
+                    skipped.push((slug, e.to_string()));
+                    continue;
+                }
+            };
+            let matches = search_text(&book, &req.query, remaining);
+            if matches.is_empty() {
+                continue;
+            }
+            remaining -= matches.len();
+            hits.push(ShelfSearchHits {
+                slug,
+                book,
+                matches,
+            });
+        }
+
+        let truncated = (remaining == 0).then_some(limit);
+        Ok(CallToolResult::success(vec![rmcp::model::Content::text(
+            format_shelf_search(req.query.trim(), &hits, &skipped, truncated),
+        )]))
     }
-}
 
-/// diff header の名前部分を決める。label があれば label、なければ timestamp 文字列。
-fn diff_header_name(label: Option<&str>, millis: i64) -> String {
-    match label {
+    #[tool(
+        name = "select_book",
+        description = "Select a book to work with. Use a number from `shelf` output or a book slug. All subsequent operations (toc, node_create, etc.) will target the selected book. Automatically shows TOC unless quiet=true.",
+        annotations(
+            read_only_hint = false,
+            destructive_hint = false,
+            idempotent_hint = true,
+            open_world_hint = false
+        )
+    )]
+    async fn select_book(
+        &self,
+        Parameters(req): Parameters<McpSelectBookRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        let slug = self.resolve_book_ref(&req.book)?;
+
+        if !self.book_exists(&slug).await? {
+            return Err(McpError::invalid_params(
+                format!(
+                    "Book '{}' not found in shelf. Use `shelf` to list available books.",
+                    slug
+                ),
+                None,
+            ));
+        }
+
+        let svc = self.service_for(&slug).await?;
+        let book = svc.read_tree().await.map_err(Self::to_mcp_error)?;
+
+        self.set_selected(Some(slug.clone()));
+        self.record_session_baseline(&slug, &book);
+
+        let toc_section = if req.quiet {
+            String::new()
+        } else {
+            let nodes = book.all_nodes_dfs();
+            if nodes.is_empty() {
+                String::from("\n(empty)")
+            } else {
+                format!("\n\n{}", format_toc(&book, &nodes, Numbering::Dash))
+            }
+        };
+
+        // Context Injection: inject=true ノードの body を自動出力
+        let inject_filter = {
+            let mut m = HashMap::new();
+            m.insert("inject".to_string(), "true".to_string());
+            m
+        };
+        let injected_nodes: Vec<_> = book
+            .nodes_matching(&inject_filter)
+            .into_iter()
+            .filter(|node| node.status() != NodeStatus::Draft)
+            .collect();
+        let inject_section = if injected_nodes.is_empty() {
+            String::new()
+        } else {
+            let mut buf = format!(
+                "\n\n---\n# Injected Context ({} rules)\n",
+                injected_nodes.len()
+            );
+            for node in &injected_nodes {
+                buf.push_str(&format!("\n## {}\n", node.title()));
+                if let Some(body) = node.body() {
+                    buf.push_str(body);
+                    buf.push('\n');
+                }
+            }
+            buf
+        };
+
+        Ok(CallToolResult::success(vec![rmcp::model::Content::text(
+            format!(
+                "Selected: {} — \"{}\" ({} nodes){}{}",
+                slug,
+                book.title(),
+                book.node_count(),
+                toc_section,
+                inject_section
+            ),
+        )]))
+    }
+
+    #[tool(
+        name = "gen_routing",
+        description = "Generate a Markdown routing table from nodes with `routing` property across all books. Set `routing` property on nodes to define work scenarios (e.g. routing=\"Git操作\"). Use `|` separator for multiple scenarios. Optional `routing_ref` property overrides the default §ID reference (e.g. routing_ref=\"select_book で全体参照\").",
+        annotations(
+            read_only_hint = true,
+            destructive_hint = false,
+            open_world_hint = false
+        )
+    )]
+    async fn gen_routing(
+        &self,
+        #[allow(unused_variables)] Parameters(_req): Parameters<McpGenRoutingRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        let slugs = self.list_book_slugs()?;
+
+        // Collect: (scene, book_slug, reference_text)
+        let mut entries: Vec<(String, String, String)> = Vec::new();
+
+        for slug in &slugs {
+            let svc = match self.service_for(slug).await {
+                Ok(svc) => svc,
+                Err(_) => continue,
+            };
+            let book = match svc.read_tree().await {
+                Ok(b) => b,
+                Err(_) => continue,
+            };
+
+            let id_map = build_hierarchical_ids(&book);
+
+            for node in book.all_nodes_dfs() {
+                let routing = match node.get_property("routing") {
+                    Some(v) => v.to_string(),
+                    None => continue,
+                };
+
+                let reference = if let Some(r) = node.get_property("routing_ref") {
+                    r.to_string()
+                } else {
+                    let hier = id_map
+                        .iter()
+                        .find(|(_, id)| *id == node.id())
+                        .map(|(num, _)| num.as_str())
+                        .unwrap_or("?");
+                    format!("§{} {}", hier, node.title())
+                };
+
+                for scene in routing.split('|') {
+                    let scene = scene.trim();
+                    if !scene.is_empty() {
+                        entries.push((scene.to_string(), slug.clone(), reference.clone()));
+                    }
+                }
+            }
+        }
+
+        if entries.is_empty() {
+            return Ok(CallToolResult::success(vec![rmcp::model::Content::text(
+                "No nodes with `routing` property found. Add `routing` property to nodes to include them in the routing table.",
+            )]));
+        }
+
+        // Group same (scene, book) → merge references
+        let mut grouped: Vec<(String, String, Vec<String>)> = Vec::new();
+        for (scene, book, reference) in &entries {
+            if let Some(existing) = grouped.iter_mut().find(|(s, b, _)| s == scene && b == book) {
+                existing.2.push(reference.clone());
+            } else {
+                grouped.push((scene.clone(), book.clone(), vec![reference.clone()]));
+            }
+        }
+
+        // Sort by book, then scene
+        grouped.sort_by(|a, b| natural_cmp(&a.1, &b.1).then_with(|| natural_cmp(&a.0, &b.0)));
+
+        let mut output = String::from("| 場面 | Book | ノード |\n|---|---|---|\n");
+        for (scene, book, refs) in &grouped {
+            output.push_str(&format!("| {} | {} | {} |\n", scene, book, refs.join(", ")));
+        }
+
+        Ok(CallToolResult::success(vec![rmcp::model::Content::text(
+            output,
+        )]))
+    }
+
+    #[tool(
+        name = "snapshot_create",
+        description = "Create a snapshot of the current book state. Optionally attach a label (sidecar '.meta.json'; snapshot body schema is unchanged). Use `snapshot_list` to view saved snapshots and `snapshot_restore` to revert.",
+        annotations(
+            read_only_hint = false,
+            destructive_hint = false,
+            idempotent_hint = false,
+            open_world_hint = false
+        )
+    )]
+    async fn snapshot_create(
+        &self,
+        Parameters(req): Parameters<McpSnapshotCreateRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        let svc = self.service().await?;
+        let book = svc.read_tree().await.map_err(Self::to_mcp_error)?;
+
+        let slug = self.require_selected()?;
+
+        let label = match req.label.as_deref() {
+            Some(s) => Some(validate_snapshot_label(s)?),
+            None => None,
+        };
+
+        let snap_svc = self.snapshot_service_for(&slug).await?;
+        let path = snap_svc
+            .create(&book, label.as_deref())
+            .await
+            .map_err(|e| {
+                McpError::internal_error(format!("Failed to create snapshot: {e}"), None)
+            })?;
+
+        let size_bytes = std::fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+
+        // ファイル名からタイムスタンプ(millis)を取得
+        let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("");
+        let millis_str = stem.rsplit('.').next().unwrap_or("");
+
+        let label_note = label
+            .as_deref()
+            .map(|l| format!(" [label: {l}]"))
+            .unwrap_or_default();
+
+        Ok(CallToolResult::success(vec![rmcp::model::Content::text(
+            format!(
+                "Snapshot created: {} ({} bytes){}",
+                millis_str, size_bytes, label_note
+            ),
+        )]))
+    }
+
+    #[tool(
+        name = "snapshot_list",
+        description = "List all snapshots for the selected book. Use the timestamp value with `snapshot_restore` to revert to a specific state.",
+        annotations(
+            read_only_hint = true,
+            destructive_hint = false,
+            open_world_hint = false
+        )
+    )]
+    async fn snapshot_list(
+        &self,
+        #[allow(unused_variables)] Parameters(_req): Parameters<McpSnapshotListRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        let svc = self.service().await?;
+        let book = svc.read_tree().await.map_err(Self::to_mcp_error)?;
+
+        let slug = self.require_selected()?;
+
+        let snap_svc = self.snapshot_service_for(&slug).await?;
+        let infos = snap_svc.list().await.map_err(|e| {
+            McpError::internal_error(format!("Failed to list snapshots: {e}"), None)
+        })?;
+
+        if infos.is_empty() {
+            return Ok(CallToolResult::success(vec![rmcp::model::Content::text(
+                format!("No snapshots for \"{}\".", book.title()),
+            )]));
+        }
+
+        let mut output = format!(
+            "# Snapshots for \"{}\" ({} snapshots)\n\n",
+            book.title(),
+            infos.len()
+        );
+        for (i, info) in infos.iter().enumerate() {
+            let size_kb = info.size_bytes as f64 / 1024.0;
+            let label_note = info
+                .label
+                .as_deref()
+                .map(|l| format!(" [{}]", l))
+                .unwrap_or_default();
+            output.push_str(&format!(
+                "{}. {} — {} ({:.1} KB){}\n",
+                i + 1,
+                info.timestamp.to_iso8601(),
+                info.timestamp.as_millis(),
+                size_kb,
+                label_note
+            ));
+        }
+
+        Ok(CallToolResult::success(vec![rmcp::model::Content::text(
+            output,
+        )]))
+    }
+
+    #[tool(
+        name = "snapshot_restore",
+        description = "Restore the selected book from a snapshot. This overwrites the current book state. Use `snapshot_list` to find available timestamps.",
+        annotations(
+            read_only_hint = false,
+            destructive_hint = true,
+            idempotent_hint = false,
+            open_world_hint = false
+        )
+    )]
+    async fn snapshot_restore(
+        &self,
+        Parameters(req): Parameters<McpSnapshotRestoreRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        let millis: i64 = req.timestamp.parse().map_err(|_| {
+            McpError::invalid_params(
+                format!(
+                    "Invalid timestamp: '{}'. Must be a millis integer.",
+                    req.timestamp
+                ),
+                None,
+            )
+        })?;
+
+        let slug = self.require_selected()?;
+
+        let snap_svc = self.snapshot_service_for(&slug).await?;
+        let restored = snap_svc.restore(millis).await.map_err(|e| {
+            McpError::internal_error(format!("Failed to restore snapshot: {e}"), None)
+        })?;
+
+        let node_count = restored.node_count();
+
+        // changelog に Restore エントリを記録（ベストエフォート）
+        let cl_repo = self.changelog_for(&slug).await?;
+        let ts = Timestamp::now();
+        let mut warning: Option<String> = None;
+        for id in restored.all_node_ids() {
+            let entry = ChangeEntry::new(id, ChangeAction::Restore, None, None, ts);
+            if let Err(e) =
+                outline_mcp_core::domain::repository::ChangeLogRepository::append(&cl_repo, &entry)
+                    .await
+            {
+                warning = Some(format!("changelog write failed: {e}"));
+                break;
+            }
+        }
+
+        let svc = self.service().await?;
+        svc.save_book(&restored).await.map_err(Self::to_mcp_error)?;
+
+        let mut msg = format!(
+            "Restored from snapshot {}. {} nodes.",
+            req.timestamp, node_count
+        );
+        if let Some(w) = warning {
+            msg.push_str(&format!("\n[WARNING] {w}"));
+        }
+
+        Ok(CallToolResult::success(vec![rmcp::model::Content::text(
+            msg,
+        )]))
+    }
+
+    #[tool(
+        name = "snapshot_tag",
+        description = "Attach or overwrite a label on an existing snapshot. Writes only the sidecar '.meta.json'; the snapshot body is not touched. Use `snapshot_list` to find timestamps.",
+        annotations(
+            read_only_hint = false,
+            destructive_hint = false,
+            idempotent_hint = true,
+            open_world_hint = false
+        )
+    )]
+    async fn snapshot_tag(
+        &self,
+        Parameters(req): Parameters<McpSnapshotTagRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        let millis: i64 = req.timestamp.parse().map_err(|_| {
+            McpError::invalid_params(
+                format!(
+                    "Invalid timestamp: '{}'. Must be a millis integer.",
+                    req.timestamp
+                ),
+                None,
+            )
+        })?;
+
+        let label = validate_snapshot_label(&req.label)?;
+
+        let slug = self.require_selected()?;
+
+        let snap_svc = self.snapshot_service_for(&slug).await?;
+        let meta_path = snap_svc
+            .tag(millis, &label)
+            .await
+            .map_err(|e| McpError::internal_error(format!("Failed to tag snapshot: {e}"), None))?;
+
+        Ok(CallToolResult::success(vec![rmcp::model::Content::text(
+            format!(
+                "Snapshot {} tagged as '{}' (meta: {})",
+                millis,
+                label,
+                meta_path.display()
+            ),
+        )]))
+    }
+
+    #[tool(
+        name = "changes",
+        description = "Concise report of what changed in the selected book since a reference: added / removed / modified / moved nodes, grouped by top-level section. `since` is a snapshot label, a snapshot timestamp (millis), or an ISO 8601 time (newest snapshot at or before it); omit it to compare against the book as it was at this session's last `select_book`.",
+        annotations(
+            read_only_hint = true,
+            destructive_hint = false,
+            idempotent_hint = true,
+            open_world_hint = false
+        )
+    )]
+    async fn changes(
+        &self,
+        Parameters(req): Parameters<McpChangesRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        let svc = self.service().await?;
+        let current = svc.read_tree().await.map_err(Self::to_mcp_error)?;
+        let slug = self.selected_slug().unwrap_or_default();
+
+        let (reference, description) = match req.since.as_deref().map(str::trim) {
+            Some(since) if !since.is_empty() => {
+                let snap_svc = self.snapshot_service_for(&slug).await?;
+                let infos = snap_svc.list().await.map_err(|e| {
+                    McpError::internal_error(format!("Failed to list snapshots: {e}"), None)
+                })?;
+                let info = resolve_snapshot_ref(&infos, since)?;
+                let book = snap_svc
+                    .restore(info.timestamp.as_millis())
+                    .await
+                    .map_err(|e| {
+                        McpError::internal_error(format!("Failed to load snapshot: {e}"), None)
+                    })?;
+                let description = format!(
+                    "snapshot {} ({})",
+                    diff_header_name(info.label.as_deref(), info.timestamp.as_millis()),
+                    info.timestamp.to_iso8601()
+                );
+                (book, description)
+            }
+            _ => {
+                let baseline = read_recovering(&self.session_baseline)
+                    .clone()
+                    .filter(|b| b.slug == slug)
+                    .ok_or_else(|| {
+                        McpError::invalid_params(
+                            "No session reference for this book. Pass `since` (snapshot label, millis, or ISO 8601) or run `select_book` first.",
+                            None,
+                        )
+                    })?;
+                let description = format!("select_book ({})", baseline.selected_at.to_iso8601());
+                (baseline.book, description)
+            }
+        };
+
+        let changes = diff_books(&reference, &current);
+        Ok(CallToolResult::success(vec![rmcp::model::Content::text(
+            format_changes(&current, &changes, &description),
+        )]))
+    }
+
+    #[tool(
+        name = "compare_with_file",
+        description = "Compare the selected book with an exported file (JSON from `checklist` format: json, or a checkbox-style `.md`), e.g. after editing the export outside the server, before a destructive `import`. Nodes are matched by title path, so a renamed node shows as removed + added. Reports added / removed / modified nodes (what importing the file would change), grouped by top-level section. Book is NOT modified.",
+        annotations(
+            read_only_hint = true,
+            destructive_hint = false,
+            idempotent_hint = true,
+            open_world_hint = false
+        )
+    )]
+    async fn compare_with_file(
+        &self,
+        Parameters(req): Parameters<McpCompareWithFileRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        let svc = self.service().await?;
+        let current = svc.read_tree().await.map_err(Self::to_mcp_error)?;
+        let tree = read_export_file(&req.file_path, req.strict.unwrap_or(false))?;
+        let file_book = EjectService::import_tree(&tree).map_err(import_error)?;
+
+        let mut changes = diff_books_by_path(&current, &file_book);
+        // エクスポートが持たない項目（完了・状態・保護）は比較の対象外
+        for change in &mut changes {
+            change
+                .fields
+                .retain(|f| !matches!(*f, "done" | "status" | "protected"));
+        }
+        changes.retain(|c| c.kind != ChangeKind::Modified || !c.fields.is_empty());
+
+        Ok(CallToolResult::success(vec![rmcp::model::Content::text(
+            format_change_report(
+                &file_book,
+                &changes,
+                &format!("Changes in {} against the selected book", req.file_path),
+            ),
+        )]))
+    }
+
+    #[tool(
+        name = "snapshot_diff",
+        description = "Unified diff between two snapshots (from_ts must be strictly less than to_ts). Both snapshots are rendered as Markdown and compared with the `similar` crate. Response is a JSON object with 'from' / 'to' metadata (timestamp / label / iso) and a unified-diff 'diff' string; the diff header uses the label when present, otherwise the timestamp.",
+        annotations(
+            read_only_hint = true,
+            destructive_hint = false,
+            idempotent_hint = true,
+            open_world_hint = false
+        )
+    )]
+    async fn snapshot_diff(
+        &self,
+        Parameters(req): Parameters<McpSnapshotDiffRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        let from_ms: i64 = req.from_ts.parse().map_err(|_| {
+            McpError::invalid_params(
+                format!(
+                    "Invalid from_ts: '{}'. Must be a millis integer.",
+                    req.from_ts
+                ),
+                None,
+            )
+        })?;
+        let to_ms: i64 = req.to_ts.parse().map_err(|_| {
+            McpError::invalid_params(
+                format!("Invalid to_ts: '{}'. Must be a millis integer.", req.to_ts),
+                None,
+            )
+        })?;
+        if from_ms >= to_ms {
+            return Err(McpError::invalid_params(
+                format!("from_ts ({from_ms}) must be strictly less than to_ts ({to_ms})"),
+                None,
+            ));
+        }
+        let context_lines = req.context_lines.unwrap_or(3);
+
+        let slug = self.require_selected()?;
+
+        let snap_svc = self.snapshot_service_for(&slug).await?;
+
+        // 両 snapshot の meta (label) を取りに行くために list を舐める
+        let infos = snap_svc.list().await.map_err(|e| {
+            McpError::internal_error(format!("Failed to list snapshots: {e}"), None)
+        })?;
+        let find_label = |ms: i64| -> Option<String> {
+            infos
+                .iter()
+                .find(|i| i.timestamp.as_millis() == ms)
+                .and_then(|i| i.label.clone())
+        };
+
+        let from_book = snap_svc.restore(from_ms).await.map_err(|e| {
+            McpError::internal_error(format!("Failed to load from snapshot: {e}"), None)
+        })?;
+        let to_book = snap_svc.restore(to_ms).await.map_err(|e| {
+            McpError::internal_error(format!("Failed to load to snapshot: {e}"), None)
+        })?;
+
+        let from_md = EjectService::render_markdown(&from_book, true, None);
+        let to_md = EjectService::render_markdown(&to_book, true, None);
+
+        let from_ts = Timestamp::from_millis(from_ms);
+        let to_ts = Timestamp::from_millis(to_ms);
+        let from_label = find_label(from_ms);
+        let to_label = find_label(to_ms);
+        let from_header = diff_header_name(from_label.as_deref(), from_ms);
+        let to_header = diff_header_name(to_label.as_deref(), to_ms);
+
+        let diff = similar::TextDiff::from_lines(&from_md, &to_md);
+        let mut diff_out = String::new();
+        diff_out.push_str(&format!(
+            "--- {} ({} / {})\n",
+            from_header,
+            from_ms,
+            from_ts.to_iso8601()
+        ));
+        diff_out.push_str(&format!(
+            "+++ {} ({} / {})\n",
+            to_header,
+            to_ms,
+            to_ts.to_iso8601()
+        ));
+        for hunk in diff
+            .unified_diff()
+            .context_radius(context_lines)
+            .iter_hunks()
+        {
+            diff_out.push_str(&hunk.to_string());
+        }
+
+        let response = serde_json::json!({
+            "from": {
+                "timestamp": from_ms,
+                "label": from_label,
+                "iso": from_ts.to_iso8601(),
+            },
+            "to": {
+                "timestamp": to_ms,
+                "label": to_label,
+                "iso": to_ts.to_iso8601(),
+            },
+            "diff": diff_out,
+        });
+
+        let text = serde_json::to_string_pretty(&response).map_err(|e| {
+            McpError::internal_error(format!("Failed to serialize diff response: {e}"), None)
+        })?;
+
+        Ok(CallToolResult::success(vec![rmcp::model::Content::text(
+            text,
+        )]))
+    }
+
+    #[tool(
+        name = "snapshot_dump",
+        description = "Dump a single snapshot to a subdirectory as 'book.md' (or 'book.json'). The live book on the shelf is NOT touched. After running, use `Bash(diff -u <dir1>/book.md <dir2>/book.md)` for unified diff between snapshots.",
+        annotations(
+            read_only_hint = true,
+            destructive_hint = false,
+            idempotent_hint = true,
+            open_world_hint = false
+        )
+    )]
+    async fn snapshot_dump(
+        &self,
+        Parameters(req): Parameters<McpSnapshotDumpRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        let millis: i64 = req.timestamp.parse().map_err(|_| {
+            McpError::invalid_params(
+                format!(
+                    "Invalid timestamp: '{}'. Must be a millis integer.",
+                    req.timestamp
+                ),
+                None,
+            )
+        })?;
+
+        let slug = self.require_selected()?;
+
+        let format = parse_dump_format(req.format.as_deref())?;
+        let overwrite = req.overwrite.unwrap_or(false);
+
+        let snap_svc = self.snapshot_service_for(&slug).await?;
+
+        // list を舐めて index を確定 (01 = 最古)
+        let mut infos = snap_svc.list().await.map_err(|e| {
+            McpError::internal_error(format!("Failed to list snapshots: {e}"), None)
+        })?;
+        infos.reverse(); // 昇順 (最古が先頭)
+        let total = infos.len();
+        let (idx, info) = infos
+            .iter()
+            .enumerate()
+            .find(|(_, i)| i.timestamp.as_millis() == millis)
+            .ok_or_else(|| {
+                McpError::invalid_params(format!("Snapshot not found for timestamp {millis}"), None)
+            })?;
+
+        let book = snap_svc
+            .restore(millis)
+            .await
+            .map_err(|e| McpError::internal_error(format!("Failed to load snapshot: {e}"), None))?;
+
+        let root = PathBuf::from(&req.output_dir);
+        let subdir = subdir_name(idx + 1, total, info.timestamp.as_millis());
+        let out_dir = root.join(&subdir);
+        prepare_dump_dir(&out_dir, overwrite)?;
+
+        let filename = dump_filename(&format);
+        let config = EjectConfig {
+            output_dir: out_dir.clone(),
+            filename: filename.to_string(),
+            include_placeholders: true,
+            format,
+            subtree_root: None,
+            created_after: None,
+            include_notes: false,
+            include_estimates: false,
+            owner: None,
+            include_ancestors: false,
+            style: MarkdownStyle::Checkbox,
+            wrap_width: None,
+            source_slug: None,
+            labels: CheckboxLabels::default(),
+            section_separators: false,
+            mark_incomplete: false,
+            mark_body_lines: false,
+            footer: false,
+        };
+        let path = EjectService::eject(&book, &config).map_err(Self::to_mcp_error)?;
+
+        Ok(CallToolResult::success(vec![rmcp::model::Content::text(
+            format!("Snapshot dumped to: {}", path.display()),
+        )]))
+    }
+
+    #[tool(
+        name = "snapshot_dump_all",
+        description = "Dump every snapshot for the selected book into 'vNN_<millis>' subdirectories (01 = oldest). Each subdir contains 'book.md' (or 'book.json'). The live book on the shelf is NOT touched. After running, use `Bash(diff -u <dir>/v03_*/book.md <dir>/v04_*/book.md)` for unified diff between consecutive snapshots.",
+        annotations(
+            read_only_hint = true,
+            destructive_hint = false,
+            idempotent_hint = true,
+            open_world_hint = false
+        )
+    )]
+    async fn snapshot_dump_all(
+        &self,
+        Parameters(req): Parameters<McpSnapshotDumpAllRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        let slug = self.require_selected()?;
+
+        let format = parse_dump_format(req.format.as_deref())?;
+        let overwrite = req.overwrite.unwrap_or(false);
+
+        let snap_svc = self.snapshot_service_for(&slug).await?;
+        let mut infos = snap_svc.list().await.map_err(|e| {
+            McpError::internal_error(format!("Failed to list snapshots: {e}"), None)
+        })?;
+        if infos.is_empty() {
+            return Ok(CallToolResult::success(vec![rmcp::model::Content::text(
+                "No snapshots to dump.".to_string(),
+            )]));
+        }
+        infos.reverse(); // 01 = 最古
+
+        let root = PathBuf::from(&req.output_dir);
+        let total = infos.len();
+        let filename = dump_filename(&format);
+        let mut written: Vec<String> = Vec::with_capacity(total);
+
+        for (idx, info) in infos.iter().enumerate() {
+            let millis = info.timestamp.as_millis();
+            let book = snap_svc.restore(millis).await.map_err(|e| {
+                McpError::internal_error(format!("Failed to load snapshot {millis}: {e}"), None)
+            })?;
+
+            let subdir = subdir_name(idx + 1, total, millis);
+            let out_dir = root.join(&subdir);
+            prepare_dump_dir(&out_dir, overwrite)?;
+
+            let config = EjectConfig {
+                output_dir: out_dir.clone(),
+                filename: filename.to_string(),
+                include_placeholders: true,
+                format: format.clone(),
+                subtree_root: None,
+                created_after: None,
+                include_notes: false,
+                include_estimates: false,
+                owner: None,
+                include_ancestors: false,
+                style: MarkdownStyle::Checkbox,
+                wrap_width: None,
+                source_slug: None,
+                labels: CheckboxLabels::default(),
+                section_separators: false,
+                mark_incomplete: false,
+                mark_body_lines: false,
+                footer: false,
+            };
+            let path = EjectService::eject(&book, &config).map_err(Self::to_mcp_error)?;
+            written.push(path.display().to_string());
+        }
+
+        let mut output = format!(
+            "Dumped {} snapshots to: {}\n\n",
+            written.len(),
+            root.display()
+        );
+        for line in &written {
+            output.push_str(&format!("- {line}\n"));
+        }
+        Ok(CallToolResult::success(vec![rmcp::model::Content::text(
+            output,
+        )]))
+    }
+
+    #[tool(
+        name = "node_history",
+        description = "Show the change history for a specific node. Returns entries in chronological order (oldest first).",
+        annotations(
+            read_only_hint = true,
+            destructive_hint = false,
+            open_world_hint = false
+        )
+    )]
+    async fn node_history(
+        &self,
+        Parameters(req): Parameters<McpNodeHistoryRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        let id = self
+            .resolve_id(&req.node_id, ResolvePolicy::Lenient)
+            .await?;
+
+        let slug = self.require_selected()?;
+
+        let svc = self.service().await?;
+        let book = svc.read_tree().await.map_err(Self::to_mcp_error)?;
+
+        let title = book
+            .get_node(id)
+            .map(|n| n.title().to_string())
+            .unwrap_or_else(|| self.short_id(id));
+
+        let cl_repo = self.changelog_for(&slug).await?;
+        let mut entries =
+            outline_mcp_core::domain::repository::ChangeLogRepository::load_by_node(&cl_repo, id)
+                .await
+                .map_err(|e| {
+                    McpError::internal_error(format!("Failed to load history: {e}"), None)
+                })?;
+
+        // 時系列順（古い順）
+        entries.sort_by_key(|a| a.timestamp);
+
+        if entries.is_empty() {
+            return Ok(CallToolResult::success(vec![rmcp::model::Content::text(
+                format!("No history for \"{}\".", title),
+            )]));
+        }
+
+        let mut output = format!(
+            "# History for \"{}\" ({} entries)\n\n",
+            title,
+            entries.len()
+        );
+        for (i, entry) in entries.iter().enumerate() {
+            let action_str = match entry.action {
+                ChangeAction::Create => "create",
+                ChangeAction::Update => "update",
+                ChangeAction::Delete => "delete",
+                ChangeAction::Move => "move",
+                ChangeAction::Restore => "restore",
+            };
+            output.push_str(&format!(
+                "{}. [{}] {}\n",
+                i + 1,
+                entry.timestamp.to_iso8601(),
+                action_str
+            ));
+            if let Some(ref before) = entry.before {
+                output.push_str(&format!("   before: {}\n", before));
+            }
+            if let Some(ref after) = entry.after {
+                output.push_str(&format!("   after: {}\n", after));
+            }
+        }
+
+        Ok(CallToolResult::success(vec![rmcp::model::Content::text(
+            output,
+        )]))
+    }
+
+    #[tool(
+        name = "book_history",
+        description = "List the full edit history of the selected book (all nodes, chronological). Each entry shows action / node hierarchical id / title. Snapshot labels are cross-referenced when the entry's timestamp matches a snapshot. Use `since` / `until` (millis) to bound a range — e.g. pair with two snapshot timestamps from `snapshot_list` to see what changed between them.",
+        annotations(
+            read_only_hint = true,
+            destructive_hint = false,
+            open_world_hint = false
+        )
+    )]
+    async fn book_history(
+        &self,
+        Parameters(req): Parameters<McpBookHistoryRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        let slug = self.require_selected()?;
+
+        let since = parse_optional_millis(req.since.as_deref(), "since")?;
+        let until = parse_optional_millis(req.until.as_deref(), "until")?;
+        if let (Some(s), Some(u)) = (since, until) {
+            if s > u {
+                return Err(McpError::invalid_params(
+                    format!("since ({s}) must be <= until ({u})"),
+                    None,
+                ));
+            }
+        }
+        let limit = req.limit.unwrap_or(50);
+
+        let svc = self.service().await?;
+        let book = svc.read_tree().await.map_err(Self::to_mcp_error)?;
+
+        let cl_repo = self.changelog_for(&slug).await?;
+        let mut entries =
+            outline_mcp_core::domain::repository::ChangeLogRepository::load_all(&cl_repo)
+                .await
+                .map_err(|e| {
+                    McpError::internal_error(format!("Failed to load history: {e}"), None)
+                })?;
+
+        // 時系列降順 (最新が先頭) — 後で range 絞り + limit
+        entries.sort_by_key(|e| std::cmp::Reverse(e.timestamp));
+
+        if let Some(s) = since {
+            entries.retain(|e| e.timestamp.as_millis() >= s);
+        }
+        if let Some(u) = until {
+            entries.retain(|e| e.timestamp.as_millis() <= u);
+        }
+
+        let total_in_range = entries.len();
+        if limit > 0 && entries.len() > limit {
+            entries.truncate(limit);
+        }
+
+        // snapshot label cross-ref (best-effort: store construction failure
+        // should not break history display)
+        let snap_infos = match self.snapshot_service_for(&slug).await {
+            Ok(svc) => svc.list().await.unwrap_or_default(),
+            Err(_) => Vec::new(),
+        };
+        let snap_label_by_millis: HashMap<i64, String> = snap_infos
+            .iter()
+            .filter_map(|i| {
+                i.label
+                    .as_ref()
+                    .map(|l| (i.timestamp.as_millis(), l.clone()))
+            })
+            .collect();
+
+        if entries.is_empty() {
+            return Ok(CallToolResult::success(vec![rmcp::model::Content::text(
+                format!(
+                    "No history for \"{}\" in the requested range.",
+                    book.title()
+                ),
+            )]));
+        }
+
+        let showing_note = if limit > 0 && total_in_range > limit {
+            format!(
+                " (showing newest {} of {} in range)",
+                entries.len(),
+                total_in_range
+            )
+        } else {
+            format!(" ({} entries)", entries.len())
+        };
+
+        let mut output = format!("# History for \"{}\"{}\n\n", book.title(), showing_note);
+
+        for (i, entry) in entries.iter().enumerate() {
+            let action_str = match entry.action {
+                ChangeAction::Create => "create",
+                ChangeAction::Update => "update",
+                ChangeAction::Delete => "delete",
+                ChangeAction::Move => "move",
+                ChangeAction::Restore => "restore",
+            };
+            let hier = find_hierarchical_id(&book, entry.node_id)
+                .unwrap_or_else(|| self.short_id(entry.node_id));
+            let title = book
+                .get_node(entry.node_id)
+                .map(|n| n.title().to_string())
+                .unwrap_or_else(|| "(deleted)".to_string());
+            let label_note = snap_label_by_millis
+                .get(&entry.timestamp.as_millis())
+                .map(|l| format!(" [snapshot: {l}]"))
+                .unwrap_or_default();
+            output.push_str(&format!(
+                "{}. [{}] {} node={} title=\"{}\"{}\n",
+                i + 1,
+                entry.timestamp.to_iso8601(),
+                action_str,
+                hier,
+                title,
+                label_note
+            ));
+        }
+
+        Ok(CallToolResult::success(vec![rmcp::model::Content::text(
+            output,
+        )]))
+    }
+
+    #[tool(
+        name = "dump",
+        description = "Export the entire selected book to a file. Unlike `checklist`, this always exports the full book (no subtree). Supports markdown (default), json and html formats.",
+        annotations(
+            read_only_hint = false,
+            destructive_hint = false,
+            idempotent_hint = true,
+            open_world_hint = false
+        )
+    )]
+    async fn dump(
+        &self,
+        Parameters(req): Parameters<McpDumpRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        let svc = self.service().await?;
+        let book = svc.read_tree().await.map_err(Self::to_mcp_error)?;
+
+        let format = parse_dump_format(req.format.as_deref())?;
+
+        let default_ext = match format {
+            EjectFormat::Markdown => "md",
+            EjectFormat::Json => "json",
+            EjectFormat::Html => "html",
+        };
+
+        let filename = match req.filename {
+            Some(f) => f,
+            None => format!("{}.{}", sanitize_for_filename(book.title()), default_ext),
+        };
+        validate_filename(&filename)?;
+
+        let output_dir = PathBuf::from(&req.output_dir);
+
+        let config = EjectConfig {
+            output_dir,
+            filename,
+            include_placeholders: true,
+            format,
+            subtree_root: None,
+            created_after: None,
+            include_notes: false,
+            include_estimates: false,
+            owner: None,
+            include_ancestors: false,
+            style: MarkdownStyle::Checkbox,
+            wrap_width: None,
+            source_slug: None,
+            labels: CheckboxLabels::default(),
+            section_separators: false,
+            mark_incomplete: false,
+            mark_body_lines: false,
+            footer: false,
+        };
+
+        let path = EjectService::eject(&book, &config).map_err(Self::to_mcp_error)?;
+
+        Ok(CallToolResult::success(vec![rmcp::model::Content::text(
+            format!("Book dumped to: {}", path.display()),
+        )]))
+    }
+
+    #[tool(
+        name = "shelf_export",
+        description = "Export every book in the shelf into ONE combined file (e.g. a printable master runbook). Markdown puts each book under its own H1 separated by rules; json writes an array of trees. Books are NOT modified.",
+        annotations(
+            read_only_hint = false,
+            destructive_hint = false,
+            idempotent_hint = true,
+            open_world_hint = false
+        )
+    )]
+    async fn shelf_export(
+        &self,
+        Parameters(req): Parameters<McpShelfExportRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        let format = parse_dump_format(req.format.as_deref())?;
+        let default_ext = match format {
+            EjectFormat::Markdown => "md",
+            EjectFormat::Json => "json",
+            EjectFormat::Html => "html",
+        };
+        let filename = req
+            .filename
+            .unwrap_or_else(|| format!("shelf.{default_ext}"));
+        validate_filename(&filename)?;
+
+        let (books, skipped) = self.load_shelf_books().await?;
+
+        let content = match format {
+            EjectFormat::Markdown => EjectService::render_combined_markdown(&books, true),
+            EjectFormat::Html => EjectService::render_combined_html(&books, true),
+            EjectFormat::Json => {
+                EjectService::render_combined_json(&books).map_err(Self::to_mcp_error)?
+            }
+        };
+
+        let output_dir = PathBuf::from(&req.output_dir);
+        std::fs::create_dir_all(&output_dir).map_err(|e| {
+            McpError::internal_error(format!("Failed to create output dir: {e}"), None)
+        })?;
+        let path = output_dir.join(&filename);
+        std::fs::write(&path, content)
+            .map_err(|e| McpError::internal_error(format!("Failed to write file: {e}"), None))?;
+
+        let mut msg = format!(
+            "Shelf exported ({} books) to: {}",
+            books.len(),
+            path.display()
+        );
+        if !skipped.is_empty() {
+            msg.push_str(&format!(
+                "\n[WARNING] Skipped unloadable books: {}",
+                skipped.join(", ")
+            ));
+        }
+        Ok(CallToolResult::success(vec![rmcp::model::Content::text(
+            msg,
+        )]))
+    }
+
+    #[tool(
+        name = "shelf_handbook",
+        description = "Render every book in the shelf as ONE Markdown handbook for printing or offline reading: a title, a generated table of contents (books and their top-level sections, as links), then each book with its headings demoted one level so the handbook title is the only H1. Writes `handbook.md` (into the shelf directory unless `output_dir` is given) or returns it with `inline: true`. Books are NOT modified.",
+        annotations(
+            read_only_hint = false,
+            destructive_hint = false,
+            idempotent_hint = true,
+            open_world_hint = false
+        )
+    )]
+    async fn shelf_handbook(
+        &self,
+        Parameters(req): Parameters<McpShelfHandbookRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        let filename = req.filename.unwrap_or_else(|| "handbook.md".to_string());
+        validate_filename(&filename)?;
+        let title = req
+            .title
+            .as_deref()
+            .map(str::trim)
+            .filter(|t| !t.is_empty())
+            .unwrap_or("Handbook");
+
+        let (books, skipped) = self.load_shelf_books().await?;
+        let content =
+            EjectService::render_handbook(&books, title, req.include_placeholders.unwrap_or(true));
+        if req.inline {
+            return Ok(CallToolResult::success(vec![rmcp::model::Content::text(
+                content,
+            )]));
+        }
+
+        let output_dir = req
+            .output_dir
+            .map(PathBuf::from)
+            .unwrap_or_else(|| self.shelf_dir.clone());
+        std::fs::create_dir_all(&output_dir).map_err(|e| {
+            McpError::internal_error(format!("Failed to create output dir: {e}"), None)
+        })?;
+        let path = output_dir.join(&filename);
+        std::fs::write(&path, content)
+            .map_err(|e| McpError::internal_error(format!("Failed to write file: {e}"), None))?;
+
+        let mut msg = format!(
+            "Handbook written ({} books) to: {}",
+            books.len(),
+            path.display()
+        );
+        if !skipped.is_empty() {
+            msg.push_str(&format!(
+                "\n[WARNING] Skipped unloadable books: {}",
+                skipped.join(", ")
+            ));
+        }
+        Ok(CallToolResult::success(vec![rmcp::model::Content::text(
+            msg,
+        )]))
+    }
+
+    /// 1回の呼び出しで作成・削除するノードが `mutation_budget` を超えるなら、変更前に
+    /// 件数と先頭のタイトルを示して `confirm: true` を求める。
+    fn check_mutation_budget(
+        &self,
+        created: usize,
+        removed: usize,
+        titles: &[&str],
+        confirm: bool,
+    ) -> Result<(), McpError> {
+        let Some(budget) = self.config.mutation_budget else {
+            return Ok(());
+        };
+        if confirm || created + removed <= budget {
+            return Ok(());
+        }
+        let mut preview: Vec<String> = titles.iter().take(5).map(|t| format!("'{t}'")).collect();
+        if titles.len() > 5 {
+            preview.push("…".to_string());
+        }
+        Err(McpError::invalid_params(
+            format!(
+                "This call would create {created} and remove {removed} node(s), over the per-call budget of {budget} ({}). Nothing was changed. Re-run with `confirm: true` if this is intended, or split the work into smaller calls.",
+                preview.join(", ")
+            ),
+            None,
+        ))
+    }
+
+    /// `shelf_export` / `shelf_handbook` 用に棚の全 Book を読む。読み込めない Book の
+    /// slug は2つ目に返す。1冊も読めなければエラー。
+    async fn load_shelf_books(&self) -> Result<(Vec<TemplateBook>, Vec<String>), McpError> {
+        let mut books = Vec::new();
+        let mut skipped = Vec::new();
+        for slug in self.list_book_slugs()? {
+            let svc = self.service_for(&slug).await?;
+            match svc.read_tree().await {
+                Ok(book) => books.push(book),
+                Err(_) => skipped.push(slug),
+            }
+        }
+        if books.is_empty() {
+            return Err(McpError::invalid_params(
+                "Shelf has no loadable books. Use `init` to create one.",
+                None,
+            ));
+        }
+        Ok((books, skipped))
+    }
+
+    /// `shelf` / `shelf_tags` / `shelf_categories` 用に各 Book のタイトル・ノード数・
+    /// タグ・分類を読む。読み込めない Book は `(failed to load)` として残す。
+    async fn shelf_entries(&self, slugs: &[String]) -> Vec<ShelfEntry> {
+        let mut entries = Vec::with_capacity(slugs.len());
+        for slug in slugs {
+            let book = match self.service_for(slug).await {
+                Ok(svc) => svc.read_tree_checked().await.ok(),
+                Err(_) => None,
+            };
+            entries.push(match book {
+                Some((book, mismatch)) => ShelfEntry {
+                    slug: slug.clone(),
+                    title: book.title().to_string(),
+                    node_count: book.node_count(),
+                    unreachable: mismatch.map_or(0, |m| m.unreachable()),
+                    tags: book.tags().to_vec(),
+                    categories: book.categories().to_vec(),
+                },
+                None => ShelfEntry {
+                    slug: slug.clone(),
+                    title: "(failed to load)".to_string(),
+                    node_count: 0,
+                    unreachable: 0,
+                    tags: Vec::new(),
+                    categories: Vec::new(),
+                },
+            });
+        }
+        entries
+    }
+
+    /// 親と兄弟内位置を `parent 2 [1a2b3c4d], position 0` 形式で表す（ルートは `root`）。
+    /// 階層番号は移動で変わるため、undo に使えるよう短縮UUIDも併記する。
+    fn describe_slot(
+        &self,
+        book: &outline_mcp_core::domain::model::book::TemplateBook,
+        parent: Option<outline_mcp_core::domain::model::id::NodeId>,
+        index: Option<usize>,
+    ) -> String {
+        let parent = match parent {
+            Some(p) => format!(
+                "parent {} [{}]",
+                find_hierarchical_id(book, p).unwrap_or_else(|| "?".to_string()),
+                self.short_id(p)
+            ),
+            None => "root".to_string(),
+        };
+        match index {
+            Some(i) => format!("{parent}, position {i}"),
+            None => parent,
+        }
+    }
+
+    /// UUID文字列をNodeIdに解決する。フルUUIDまたは短縮プレフィックスを受け付ける。
+    /// 階層番号やタイトル一致は受け付けない（バッチ操作のtoc IDズレ問題回避）。
+    async fn resolve_uuid(
+        &self,
+        s: &str,
+    ) -> Result<outline_mcp_core::domain::model::id::NodeId, McpError> {
+        // 1. Full UUID
+        if let Ok(id) = parse_node_id(s) {
+            return Ok(id);
+        }
+        // 2. Short prefix in current book
+        let svc = self.service().await?;
+        let book = svc.read_tree().await.map_err(Self::to_mcp_error)?;
+        let matches: Vec<outline_mcp_core::domain::model::id::NodeId> = book
+            .all_node_ids()
+            .filter(|id| id.to_string().starts_with(s))
+            .collect();
+        match matches.len() {
+            1 => Ok(matches[0]),
+            0 => Err(McpError::invalid_params(
+                format!("No node with UUID starting with '{s}'"),
+                None,
+            )),
+            n => Err(McpError::invalid_params(
+                format!("Ambiguous UUID prefix '{s}' matches {n} nodes"),
+                None,
+            )),
+        }
+    }
+
+    #[tool(
+        name = "node_batch_move",
+        description = "Move multiple nodes in a single atomic operation. All nodes must be specified by UUID (not toc ID). Use `node_query` or `dump` to find UUIDs. All moves succeed or none are saved.",
+        annotations(
+            read_only_hint = false,
+            destructive_hint = true,
+            idempotent_hint = false,
+            open_world_hint = false
+        )
+    )]
+    async fn node_batch_move(
+        &self,
+        Parameters(req): Parameters<McpBatchMoveRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        let total = req.moves.len();
+
+        // Resolve all UUIDs first, reporting the first failure with its index
+        let mut resolved: Vec<(
+            outline_mcp_core::domain::model::id::NodeId,
+            Option<outline_mcp_core::domain::model::id::NodeId>,
+            Position,
+        )> = Vec::with_capacity(total);
+        let mut refs = Vec::with_capacity(total);
+
+        for (i, item) in req.moves.iter().enumerate() {
+            let id = self.resolve_uuid(&item.node_id).await.map_err(|e| {
+                McpError::invalid_params(
+                    format!(
+                        "Batch move failed at operation {}/{total} (node {}): {e}. No changes saved.",
+                        i + 1,
+                        item.node_id
+                    ),
+                    None,
+                )
+            })?;
+            let new_parent = match item.new_parent.as_deref() {
+                Some(s) => Some(self.resolve_uuid(s).await.map_err(|e| {
+                    McpError::invalid_params(
+                        format!(
+                            "Batch move failed at operation {}/{total} (node {}): parent UUID: {e}. No changes saved.",
+                            i + 1,
+                            item.node_id
+                        ),
+                        None,
+                    )
+                })?),
+                None => None,
+            };
+            let position = parse_position(item.position.as_ref())?;
+            refs.push((item.node_id.as_str(), id));
+            refs.extend(item.new_parent.as_deref().zip(new_parent));
+            resolved.push((id, new_parent, position));
+        }
+
+        let svc = self.service().await?;
+        let before = svc.read_tree().await.map_err(Self::to_mcp_error)?;
+        let (count, warnings) = svc.batch_move(resolved).await.map_err(|e| {
+            McpError::internal_error(
+                format!(
+                    "Batch move failed: {}. No changes saved.",
+                    self.describe_app_error(
+                        &before,
+                        &e,
+                        &ErrorContext {
+                            refs: &refs,
+                            parent: None,
+                        }
+                    )
+                ),
+                None,
+            )
+        })?;
+
+        let mut msg = format!("Batch move complete: {count}/{total} operations succeeded.");
+        for w in warnings.into_iter().flatten() {
+            msg.push_str(&format!("\n[WARNING] {w}"));
+        }
+        Ok(CallToolResult::success(vec![rmcp::model::Content::text(
+            msg,
+        )]))
+    }
+
+    #[tool(
+        name = "reorder_book",
+        description = "Re-sequence siblings across the whole book in one call: pass every node's current ID from `toc` in the new outline (DFS) order. Only sibling order changes — every node stays under its current parent, and the new position does not reparent it (use `node_move` for that). The list must name every node once, put each node after its current parent and keep each subtree together. All moves are saved together or not at all.",
+        annotations(
+            read_only_hint = false,
+            destructive_hint = false,
+            idempotent_hint = true,
+            open_world_hint = false
+        )
+    )]
+    async fn reorder_book(
+        &self,
+        Parameters(req): Parameters<McpReorderBookRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        let svc = self.service().await?;
+        let (moved, warnings) = svc
+            .with_book(|book| {
+                let ids = req
+                    .order
+                    .iter()
+                    .map(|s| self.resolve_id_in(book, s, ResolvePolicy::Strict))
+                    .collect::<Result<Vec<_>, _>>()?;
+                // 表示は並べ替え前の番号で（失敗時は何も変わらない）
+                let labels: HashMap<NodeId, String> = build_hierarchical_ids(book)
+                    .into_iter()
+                    .filter_map(|(hier, id)| {
+                        let title = book.get_node(id)?.title();
+                        Some((id, format!("{hier}. {title}")))
+                    })
+                    .collect();
+                let label = |id: NodeId| {
+                    labels
+                        .get(&id)
+                        .cloned()
+                        .unwrap_or_else(|| self.short_id(id))
+                };
+                let invalid = |msg: String| {
+                    McpError::invalid_params(format!("{msg}; nothing was changed."), None)
+                };
+
+                if let Some(problem) = reorder_violation(book, &ids, label) {
+                    return Err(invalid(problem));
+                }
+                let moved = match apply_sibling_order(book, &ids) {
+                    Ok(moved) => moved,
+                    Err(e) => {
+                        let ctx = ErrorContext {
+                            refs: &[],
+                            parent: None,
+                        };
+                        return Err(self.to_mcp_error_in(book, e.into(), &ctx));
+                    }
+                };
+                if let Some(problem) = sort_key_conflict(book, &ids, label) {
+                    return Err(invalid(problem));
+                }
+                let changes: Vec<_> = moved
+                    .into_iter()
+                    .map(|id| (id, ChangeAction::Move))
+                    .collect();
+                Ok((changes.len(), changes))
+            })
+            .await
+            .map_err(Self::to_mcp_error)??;
+
+        let mut msg = if moved == 0 {
+            "No change: the book is already in this order.".to_string()
+        } else {
+            format!(
+                "Reordered the book: {moved} node(s) moved among their siblings. Run `toc` for the new numbering."
+            )
+        };
+        for w in warnings.into_iter().flatten() {
+            msg.push_str(&format!("\n[WARNING] {w}"));
+        }
+        Ok(CallToolResult::success(vec![rmcp::model::Content::text(
+            msg,
+        )]))
+    }
+
+    #[tool(
+        name = "node_batch_update",
+        description = "Update multiple nodes' properties, status, title, or body in a single atomic operation. All nodes must be specified by UUID.",
+        annotations(
+            read_only_hint = false,
+            destructive_hint = false,
+            idempotent_hint = true,
+            open_world_hint = false
+        )
+    )]
+    async fn node_batch_update(
+        &self,
+        Parameters(req): Parameters<McpBatchUpdateRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        let total = req.updates.len();
+
+        // Resolve all UUIDs first
+        let mut resolved: Vec<(
+            outline_mcp_core::domain::model::id::NodeId,
+            outline_mcp_core::domain::model::book::UpdateNodeRequest,
+        )> = Vec::with_capacity(total);
+
+        for (i, item) in req.updates.iter().enumerate() {
+            let id = self.resolve_uuid(&item.node_id).await.map_err(|e| {
+                McpError::invalid_params(
+                    format!(
+                        "Batch update failed at operation {}/{total} (node {}): {e}. No changes saved.",
+                        i + 1,
+                        item.node_id
+                    ),
+                    None,
+                )
+            })?;
+            let status = item
+                .status
+                .as_deref()
+                .map(parse_node_status)
+                .transpose()
+                .map_err(|e| {
+                    McpError::invalid_params(
+                        format!(
+                            "Batch update failed at operation {}/{total} (node {}): {e}. No changes saved.",
+                            i + 1,
+                            item.node_id
+                        ),
+                        None,
+                    )
+                })?;
+            let update_req = outline_mcp_core::domain::model::book::UpdateNodeRequest {
+                title: item
+                    .title
+                    .as_deref()
+                    .map(|t| input_title(t, self.config.normalize_titles)),
+                body: item.body.clone().map(|b| b.map(|s| unescape_newlines(&s))),
+                properties: item.properties.clone(),
+                status,
+                ..Default::default()
+            };
+            resolved.push((id, update_req));
+        }
+
+        let svc = self.service().await?;
+        let (count, warnings) = svc.batch_update(resolved).await.map_err(|e| {
+            McpError::internal_error(format!("Batch update failed: {e}. No changes saved."), None)
+        })?;
+
+        let mut msg = format!("Batch update complete: {count}/{total} operations succeeded.");
+        for w in warnings.into_iter().flatten() {
+            msg.push_str(&format!("\n[WARNING] {w}"));
+        }
+        Ok(CallToolResult::success(vec![rmcp::model::Content::text(
+            msg,
+        )]))
+    }
+
+    #[tool(
+        description = "Query nodes by properties, status, type, or subtree. Returns UUIDs needed for batch operations. Use `include_body: true` to include node content.",
+        annotations(
+            read_only_hint = true,
+            destructive_hint = false,
+            open_world_hint = false
+        )
+    )]
+    async fn node_query(
+        &self,
+        Parameters(req): Parameters<McpNodeQueryRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        use outline_mcp_core::domain::model::node::NodeType;
+
+        let svc = self.service().await?;
+        let book = svc.read_tree().await.map_err(Self::to_mcp_error)?;
+
+        let root_id = match req.subtree_root.as_deref() {
+            Some(s) => Some(self.resolve_id(s, ResolvePolicy::Lenient).await?),
+            None => None,
+        };
+
+        let mut nodes = match root_id {
+            Some(id) => book.subtree_nodes(id),
+            None => book.all_nodes_dfs(),
+        };
+
+        if let Some(ref filter) = req.filter {
+            if !filter.is_empty() {
+                nodes.retain(|node| {
+                    filter
+                        .iter()
+                        .all(|(k, v)| node.get_property(k).map(|pv| pv == v).unwrap_or(false))
+                });
+            }
+        }
+
+        if let Some(ref k) = req.kind {
+            let nt = parse_node_type(k)?;
+            nodes.retain(|n| n.node_type() == &nt);
+        }
+
+        if let Some(ref s) = req.status {
+            let st = parse_node_status(s)?;
+            nodes.retain(|n| n.status() == st);
+        }
+
+        if nodes.is_empty() {
+            return Ok(CallToolResult::success(vec![rmcp::model::Content::text(
+                "No matching nodes found.",
+            )]));
+        }
+
+        let mut output = format!("# Query Results ({} matches)\n", nodes.len());
+        for (i, node) in nodes.iter().enumerate() {
+            let short = self.short_id(node.id());
+            let full = node.id().to_string();
+            let type_str = match node.node_type() {
+                NodeType::Section => "section",
+                NodeType::Content => "content",
+            };
+            let status_str = match node.status() {
+                outline_mcp_core::domain::model::changelog::NodeStatus::Active => "active",
+                outline_mcp_core::domain::model::changelog::NodeStatus::Draft => "draft",
+            };
+            output.push_str(&format!(
+                "\n{}. [{}] {}\n   UUID: {}\n   Type: {}\n   Status: {}\n",
+                i + 1,
+                short,
+                node.title(),
+                full,
+                type_str,
+                status_str,
+            ));
+            let props = node.properties();
+            if !props.is_empty() {
+                let props_str = props
+                    .iter()
+                    .map(|(k, v)| format!("{}={}", k, v))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                output.push_str(&format!("   Properties: {}\n", props_str));
+            }
+            if req.include_body {
+                if let Some(body) = node.body() {
+                    output.push_str(&format!("   Body: {}\n", body));
+                }
+            }
+            output.push_str("   ---\n");
+        }
+
+        Ok(CallToolResult::success(vec![rmcp::model::Content::text(
+            output,
+        )]))
+    }
+}
+
+// ---------------------------------------------------------------------------
+// snapshot label / dump helpers
+// ---------------------------------------------------------------------------
+
+/// snapshot label の validation。
+///
+/// - 空 / 前後空白のみは拒否
+/// - 64 文字上限
+/// - 許可文字: 英数字 / space / `-_.:,()`  (path traversal 防止 & filename 化しないので厳格すぎない範囲)
+/// - trim した form を返す
+fn validate_snapshot_label(raw: &str) -> Result<String, McpError> {
+    let trimmed = raw.trim();
+    if trimmed.is_empty() {
+        return Err(McpError::invalid_params(
+            "label must not be empty or whitespace only",
+            None,
+        ));
+    }
+    if trimmed.chars().count() > 64 {
+        return Err(McpError::invalid_params(
+            "label must be 64 characters or fewer",
+            None,
+        ));
+    }
+    let ok = trimmed
+        .chars()
+        .all(|c| c.is_alphanumeric() || matches!(c, ' ' | '-' | '_' | '.' | ':' | ',' | '(' | ')'));
+    if !ok {
+        return Err(McpError::invalid_params(
+            "label may only contain letters, digits, spaces, and '-_.:,()'",
+            None,
+        ));
+    }
+    Ok(trimmed.to_string())
+}
+
+/// optional millis 文字列を i64 に parse する。None は None を返す。
+fn parse_optional_millis(s: Option<&str>, field: &str) -> Result<Option<i64>, McpError> {
+    match s {
+        None => Ok(None),
+        Some(v) => v.parse::<i64>().map(Some).map_err(|_| {
+            McpError::invalid_params(
+                format!("Invalid {field}: '{v}'. Must be a millis integer."),
+                None,
+            )
+        }),
+    }
+}
+
+/// `shelf` の1行分。
+struct ShelfEntry {
+    slug: String,
+    title: String,
+    node_count: usize,
+    /// `node_count` のうちルートから辿れない孤立ノードの数。
+    unreachable: usize,
+    tags: Vec<String>,
+    categories: Vec<String>,
+}
+
+/// `changes` の `since` をスナップショットに解決する。
+///
+/// 優先順位: 1. label 完全一致（同名なら最新） 2. millis 完全一致
+/// 3. millis / ISO 8601 の時刻以前で最新のスナップショット。
+fn resolve_snapshot_ref<'a>(
+    snapshots: &'a [SnapshotInfo],
+    since: &str,
+) -> Result<&'a SnapshotInfo, McpError> {
+    let newest = |matches: &dyn Fn(&SnapshotInfo) -> bool| {
+        snapshots
+            .iter()
+            .filter(|i| matches(i))
+            .max_by_key(|i| i.timestamp.as_millis())
+    };
+    if let Some(info) = newest(&|i| i.label.as_deref() == Some(since)) {
+        return Ok(info);
+    }
+    let at = parse_timestamp_arg(since, "since")?.as_millis();
+    if let Some(info) = newest(&|i| i.timestamp.as_millis() == at) {
+        return Ok(info);
+    }
+    newest(&|i| i.timestamp.as_millis() <= at).ok_or_else(|| {
+        McpError::invalid_params(
+            format!(
+                "No snapshot labeled '{since}' or taken at or before it. Use `snapshot_list` to see available snapshots."
+            ),
+            None,
+        )
+    })
+}
+
+/// diff header の名前部分を決める。label があれば label、なければ timestamp 文字列。
+fn diff_header_name(label: Option<&str>, millis: i64) -> String {
+    match label {
         Some(l) if !l.is_empty() => l.to_string(),
         _ => millis.to_string(),
     }
-}
+}
+
+/// 深さ超過はリクエスト側の問題なので invalid_params で返す。
+fn import_error(e: AppError) -> McpError {
+    match e {
+        AppError::ImportTooDeep { .. } | AppError::ImportIdConflict(_) => {
+            McpError::invalid_params(e.to_string(), None)
+        }
+        other => OutlineMcpServer::to_mcp_error(other),
+    }
+}
+
+/// `help` の本文。参照の書き方・種別・状態の語はパーサと同じ定数から組み立てる。
+fn help_text() -> String {
+    let numbered = |table: &[(&str, &str)]| {
+        table
+            .iter()
+            .enumerate()
+            .map(|(i, (format, example))| format!("  {}. {format} — e.g. {example}\n", i + 1))
+            .collect::<String>()
+    };
+    let words = |names: Vec<&str>| names.join(", ");
+    let mut text = String::from("# outline-mcp quick reference\n\n");
+    text.push_str("Node references (tried in this order):\n");
+    text.push_str(&numbered(NODE_REF_FORMATS));
+    text.push_str(
+        "  Removing a node takes only a number or UUID. `node_batch_move` / `node_batch_update` need a UUID or prefix.\n\n",
+    );
+    text.push_str("Book references (`select_book`, `source_book`, ...):\n");
+    text.push_str(&numbered(BOOK_REF_FORMATS));
+    text.push_str(&format!(
+        "\nNode types: {}\n",
+        words(NODE_TYPE_NAMES.iter().map(|(name, _)| *name).collect())
+    ));
+    text.push_str(&format!(
+        "Statuses: {}\n",
+        words(NODE_STATUS_NAMES.iter().map(|(name, _)| *name).collect())
+    ));
+    text.push_str(
+        "\nCommon flows:\n\
+         - Open a book: `shelf` → `select_book` {\"book\": \"2\"} → `toc`\n\
+         - Add: `node_create` {\"parent\": \"2\", \"title\": \"Deploy\", \"body\": \"...\"}\n\
+         - Edit: `node_update` {\"node_id\": \"2-3\", \"title\": \"Deploy v2\"}\n\
+         - Move: `node_move` {\"node_id\": \"2-3\", \"action\": \"move\", \"new_parent\": \"1\"}\n\
+         - Remove: `node_move` {\"node_id\": \"2-3\", \"action\": \"remove\"}\n\
+         - Export: `checklist` {\"subtree_root\": \"2\", \"inline\": true}\n\
+         - Undo: `snapshot_create` before big edits, `snapshot_restore` to go back\n",
+    );
+    text
+}
+
+/// `depth_strategy` が何をしたかの一文（何もしなければ空）。
+/// チェックボックスの状態語を検証する。空なら付けない。1行に収まる語だけを受け付ける。
+fn checkbox_word(word: Option<&str>, field: &str) -> Result<Option<String>, McpError> {
+    match word.map(str::trim) {
+        None | Some("") => Ok(None),
+        Some(w) if w.contains(['\n', '\r', '[', ']']) => Err(McpError::invalid_params(
+            format!("{field} must be a single word or phrase without line breaks or brackets"),
+            None,
+        )),
+        Some(w) => Ok(Some(w.to_string())),
+    }
+}
+
+/// タイトルを slug 用に変換する（英数字は小文字、それ以外は `-` にまとめる）。
+fn slug_from_title(title: &str) -> String {
+    let mut slug = String::new();
+    for c in title.chars() {
+        if c.is_ascii_alphanumeric() {
+            slug.push(c.to_ascii_lowercase());
+        } else if !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    match slug.trim_matches('-') {
+        "" => "section".to_string(),
+        s => s.to_string(),
+    }
+}
+
+/// エクスポートのノード数（子孫を含む）。
+fn count_tree_nodes(nodes: &[EjectTreeNode]) -> usize {
+    nodes
+        .iter()
+        .map(|n| 1 + count_tree_nodes(&n.children))
+        .sum()
+}
+
+/// `import` / `compare_with_file` 用に、JSON か Markdown のエクスポートを読む。
+/// `import` が読むファイル: 書き出したツリーか、保存形式そのままのバックアップ。
+enum ImportFile {
+    Tree(EjectTree),
+    Backup(Box<TemplateBook>),
+}
+
+fn read_import_file(file_path: &str, strict: bool) -> Result<ImportFile, McpError> {
+    let (import_path, format) = validate_import_path(file_path)?;
+    let content = std::fs::read_to_string(&import_path)
+        .map_err(|e| McpError::internal_error(format!("Failed to read file: {e}"), None))?;
+    match format {
+        ImportFormat::Json if is_backup(&content) => parse_backup(&content)
+            .map(|book| ImportFile::Backup(Box::new(book)))
+            .map_err(|e| McpError::invalid_params(e.to_string(), None)),
+        ImportFormat::Json => EjectTree::from_json(&content, strict)
+            .map(ImportFile::Tree)
+            .map_err(|e| McpError::invalid_params(format!("Invalid JSON: {e}"), None)),
+        ImportFormat::Markdown => EjectTree::from_markdown(&content)
+            .map(ImportFile::Tree)
+            .map_err(|e| McpError::invalid_params(e.to_string(), None)),
+    }
+}
+
+fn read_export_file(file_path: &str, strict: bool) -> Result<EjectTree, McpError> {
+    match read_import_file(file_path, strict)? {
+        ImportFile::Tree(tree) => Ok(tree),
+        ImportFile::Backup(_) => Err(McpError::invalid_params(
+            format!("'{file_path}' is a backup (checklist format: backup), not an export; restore it with `import`."),
+            None,
+        )),
+    }
+}
+
+fn describe_depth_fit(original_max: u8, fit: DepthFit) -> String {
+    if fit.folded > 0 {
+        format!(
+            " Folded {} node(s) beyond max_depth {} into their parent's body.",
+            fit.folded, fit.max_depth
+        )
+    } else if fit.max_depth > original_max {
+        format!(" Raised max_depth {original_max} → {}.", fit.max_depth)
+    } else {
+        String::new()
+    }
+}
+
+fn parse_dump_format(s: Option<&str>) -> Result<EjectFormat, McpError> {
+    match s {
+        Some("json") => Ok(EjectFormat::Json),
+        Some("markdown") | None => Ok(EjectFormat::Markdown),
+        Some("html") => Ok(EjectFormat::Html),
+        Some(other) => Err(McpError::invalid_params(
+            format!("Unknown format: '{other}'. Use: markdown, json, html"),
+            None,
+        )),
+    }
+}
+
+fn dump_filename(format: &EjectFormat) -> &'static str {
+    match format {
+        EjectFormat::Markdown => "book.md",
+        EjectFormat::Json => "book.json",
+        EjectFormat::Html => "book.html",
+    }
+}
+
+fn subdir_name(index: usize, total: usize, millis: i64) -> String {
+    let width = if total >= 100 { 3 } else { 2 };
+    format!("v{:0width$}_{}", index, millis, width = width)
+}
+
+fn prepare_dump_dir(dir: &std::path::Path, overwrite: bool) -> Result<(), McpError> {
+    if dir.exists() {
+        if !overwrite {
+            return Err(McpError::invalid_params(
+                format!(
+                    "Output subdir already exists: {}. Pass overwrite=true to allow.",
+                    dir.display()
+                ),
+                None,
+            ));
+        }
+        std::fs::remove_dir_all(dir).map_err(|e| {
+            McpError::internal_error(
+                format!("Failed to remove existing dir {}: {}", dir.display(), e),
+                None,
+            )
+        })?;
+    }
+    std::fs::create_dir_all(dir).map_err(|e| {
+        McpError::internal_error(
+            format!("Failed to create dir {}: {}", dir.display(), e),
+            None,
+        )
+    })?;
+    Ok(())
+}
+
+// ---------------------------------------------------------------------------
+// inline export helpers
+// ---------------------------------------------------------------------------
+
+/// inline 出力を指定エンコーディングに変換する。`None` / `"text"` はそのまま返す。
+fn encode_inline(content: String, encoding: Option<&str>) -> Result<String, McpError> {
+    use base64::Engine as _;
+
+    match encoding {
+        None | Some("text") => Ok(content),
+        Some("base64") => Ok(base64::engine::general_purpose::STANDARD.encode(content)),
+        Some(other) => Err(McpError::invalid_params(
+            format!("Unknown encoding: '{other}'. Use: text, base64"),
+            None,
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tool_tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{Arc, Mutex};
+
+    use super::*;
+    use crate::config::ServerConfig;
+    use crate::request::{McpNodeMoveRequest, PositionArg};
+    use outline_mcp_core::domain::model::book::{AddNodeRequest, Position};
+    use outline_mcp_core::domain::repository::{BookRepository, RepositoryError};
+    use rmcp::ServerHandler;
+
+    #[test]
+    fn subdir_name_pads_two_digits() {
+        assert_eq!(subdir_name(1, 5, 12345), "v01_12345");
+        assert_eq!(subdir_name(9, 12, 999), "v09_999");
+        assert_eq!(subdir_name(12, 12, 100), "v12_100");
+    }
+
+    #[test]
+    fn subdir_name_uses_three_digits_when_needed() {
+        assert_eq!(subdir_name(1, 150, 7), "v001_7");
+        assert_eq!(subdir_name(150, 150, 7), "v150_7");
+    }
+
+    #[test]
+    fn parse_dump_format_default_markdown() {
+        assert!(matches!(
+            parse_dump_format(None).unwrap(),
+            EjectFormat::Markdown
+        ));
+        assert!(matches!(
+            parse_dump_format(Some("markdown")).unwrap(),
+            EjectFormat::Markdown
+        ));
+        assert!(matches!(
+            parse_dump_format(Some("json")).unwrap(),
+            EjectFormat::Json
+        ));
+        assert!(matches!(
+            parse_dump_format(Some("html")).unwrap(),
+            EjectFormat::Html
+        ));
+    }
+
+    #[test]
+    fn parse_dump_format_rejects_unknown() {
+        assert!(parse_dump_format(Some("yaml")).is_err());
+    }
+
+    #[test]
+    fn dump_filename_by_format() {
+        assert_eq!(dump_filename(&EjectFormat::Markdown), "book.md");
+        assert_eq!(dump_filename(&EjectFormat::Json), "book.json");
+        assert_eq!(dump_filename(&EjectFormat::Html), "book.html");
+    }
+
+    #[test]
+    fn prepare_dump_dir_creates_new() {
+        let dir = std::env::temp_dir().join("outline-mcp-dump-helper-new");
+        let _ = std::fs::remove_dir_all(&dir);
+        prepare_dump_dir(&dir, false).expect("create");
+        assert!(dir.exists());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn prepare_dump_dir_errors_when_exists_without_overwrite() {
+        let dir = fresh_dir("outline-mcp-dump-helper-existing");
+        let res = prepare_dump_dir(&dir, false);
+        assert!(res.is_err());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn parse_optional_millis_handles_none_some_bad() {
+        assert!(matches!(parse_optional_millis(None, "since"), Ok(None)));
+        assert!(matches!(
+            parse_optional_millis(Some("1782885148593"), "since"),
+            Ok(Some(1782885148593))
+        ));
+        assert!(parse_optional_millis(Some("abc"), "since").is_err());
+    }
+
+    fn snapshot_info(millis: i64, label: Option<&str>) -> SnapshotInfo {
+        SnapshotInfo {
+            timestamp: Timestamp::from_millis(millis),
+            path: PathBuf::from(format!("book.snap.{millis}.json")),
+            size_bytes: 0,
+            label: label.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn resolve_snapshot_ref_prefers_label_then_exact_then_latest_before() {
+        let snaps = vec![
+            snapshot_info(1_000, Some("draft")),
+            snapshot_info(2_000, None),
+            snapshot_info(3_000, Some("draft")),
+        ];
+        let ts = |s: &str| resolve_snapshot_ref(&snaps, s).map(|i| i.timestamp.as_millis());
+        assert_eq!(ts("draft").unwrap(), 3_000);
+        assert_eq!(ts("2000").unwrap(), 2_000);
+        assert_eq!(ts("2500").unwrap(), 2_000);
+        assert_eq!(ts("1970-01-01T00:00:01.500Z").unwrap(), 1_000);
+        assert!(ts("999").is_err());
+        assert!(ts("no-such-label").is_err());
+    }
+
+    #[test]
+    fn diff_header_prefers_label() {
+        assert_eq!(diff_header_name(Some("v03_rating"), 12345), "v03_rating");
+    }
+
+    #[test]
+    fn diff_header_falls_back_to_millis_when_label_absent() {
+        assert_eq!(diff_header_name(None, 12345), "12345");
+    }
+
+    #[test]
+    fn diff_header_falls_back_to_millis_when_label_empty() {
+        assert_eq!(diff_header_name(Some(""), 999), "999");
+    }
+
+    #[test]
+    fn validate_label_accepts_normal_input() {
+        assert_eq!(
+            validate_snapshot_label(" rating-pass ").unwrap(),
+            "rating-pass"
+        );
+        assert_eq!(
+            validate_snapshot_label("v03 L3 sketch").unwrap(),
+            "v03 L3 sketch"
+        );
+        assert_eq!(
+            validate_snapshot_label("Milestone (1.0): draft").unwrap(),
+            "Milestone (1.0): draft"
+        );
+    }
+
+    #[test]
+    fn validate_label_rejects_empty() {
+        assert!(validate_snapshot_label("").is_err());
+        assert!(validate_snapshot_label("   ").is_err());
+    }
+
+    #[test]
+    fn validate_label_rejects_too_long() {
+        let long: String = "a".repeat(65);
+        assert!(validate_snapshot_label(&long).is_err());
+        let ok: String = "a".repeat(64);
+        assert!(validate_snapshot_label(&ok).is_ok());
+    }
+
+    #[test]
+    fn validate_label_rejects_disallowed_chars() {
+        assert!(validate_snapshot_label("bad/slash").is_err());
+        assert!(validate_snapshot_label("back\\slash").is_err());
+        assert!(validate_snapshot_label("nl\nne").is_err());
+    }
+
+    #[test]
+    fn prepare_dump_dir_overwrites_when_flag_set() {
+        let dir = fresh_dir("outline-mcp-dump-helper-overwrite");
+        std::fs::write(dir.join("stale.txt"), "old").unwrap();
+        prepare_dump_dir(&dir, true).expect("overwrite");
+        assert!(dir.exists());
+        assert!(!dir.join("stale.txt").exists());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn encode_inline_defaults_to_plain_text() {
+        assert_eq!(encode_inline("a\nb".into(), None).unwrap(), "a\nb");
+        assert_eq!(encode_inline("a\nb".into(), Some("text")).unwrap(), "a\nb");
+    }
+
+    #[test]
+    fn encode_inline_base64_roundtrips() {
+        use base64::Engine as _;
+
+        let content = "# Book\n\n- [ ] item `x` <y>\n".to_string();
+        let encoded = encode_inline(content.clone(), Some("base64")).unwrap();
+        assert!(!encoded.contains('\n'));
+        let decoded = base64::engine::general_purpose::STANDARD
+            .decode(encoded)
+            .unwrap();
+        assert_eq!(String::from_utf8(decoded).unwrap(), content);
+    }
+
+    #[test]
+    fn encode_inline_rejects_unknown() {
+        assert!(encode_inline(String::new(), Some("hex")).is_err());
+    }
+
+    fn import_req(source_book: &str) -> McpImportRequest {
+        McpImportRequest {
+            file_path: None,
+            slug: None,
+            source_book: Some(source_book.to_string()),
+            dry_run: None,
+            subtree: None,
+            parent: None,
+            depth_strategy: None,
+            strict: None,
+            dedupe: None,
+            on_id_conflict: None,
+            in_place: None,
+            confirm: false,
+        }
+    }
+
+    /// "Ops"（子 Restart / Backups）と "Dev" を持つ runbook を選んだサーバと、
+    /// Ops の部分木を JSON で書き出した EjectTree。
+    async fn runbook_with_ops_export(dir: &std::path::Path) -> (OutlineMcpServer, EjectTree) {
+        let server = OutlineMcpServer::new(dir.to_path_buf());
+        let svc = server.service_for("runbook").await.unwrap();
+        svc.create_book("Runbook", 4).await.unwrap();
+        let section = |parent, title: &str| AddNodeRequest {
+            parent,
+            title: title.into(),
+            node_type: NodeType::Section,
+            body: None,
+            placeholder: None,
+            position: Position::Last,
+            properties: HashMap::new(),
+        };
+        let (ops, _) = svc.add_node(section(None, "Ops")).await.unwrap();
+        svc.add_node(section(Some(ops.node_id), "Restart"))
+            .await
+            .unwrap();
+        svc.add_node(section(Some(ops.node_id), "Backups"))
+            .await
+            .unwrap();
+        svc.add_node(section(None, "Dev")).await.unwrap();
+        *server.selected.write().unwrap() = Some("runbook".to_string());
+        let book = svc.read_tree().await.unwrap();
+        let json = EjectService::render_json(&book, Some(ops.node_id)).unwrap();
+        (server, EjectTree::from_json(&json, true).unwrap())
+    }
+
+    fn write_tree(dir: &std::path::Path, tree: &EjectTree) -> McpImportRequest {
+        let path = dir.join("ops.json");
+        std::fs::write(&path, serde_json::to_string(tree).unwrap()).unwrap();
+        McpImportRequest {
+            file_path: Some(path.to_string_lossy().into_owned()),
+            source_book: None,
+            ..import_req("unused")
+        }
+    }
+
+    #[tokio::test]
+    async fn compare_with_file_reports_edits_to_an_export() {
+        let dir = fresh_dir("outline-mcp-compare-with-file-test");
+        let (server, _) = runbook_with_ops_export(&dir).await;
+        let book = server.service().await.unwrap().read_tree().await.unwrap();
+        let mut tree = EjectService::build_tree(&book, None);
+
+        let unchanged = server
+            .compare_with_file(Parameters(McpCompareWithFileRequest {
+                file_path: write_tree(&dir, &tree).file_path.unwrap(),
+                strict: None,
+            }))
+            .await
+            .unwrap();
+        let text = |r: &CallToolResult| r.content[0].as_text().unwrap().text.clone();
+        assert!(
+            text(&unchanged).contains("No changes."),
+            "{}",
+            text(&unchanged)
+        );
+
+        tree.nodes[0].children[0].body = Some("drain first".into());
+        tree.nodes[0].children.remove(1);
+        let mut rollback = tree.nodes[1].clone();
+        rollback.title = "Rollback".into();
+        tree.nodes.push(rollback);
+        let result = server
+            .compare_with_file(Parameters(McpCompareWithFileRequest {
+                file_path: write_tree(&dir, &tree).file_path.unwrap(),
+                strict: None,
+            }))
+            .await
+            .unwrap();
+        let report = text(&result);
+        assert!(
+            report.contains("3 changes: 1 added, 1 removed, 1 modified"),
+            "{report}"
+        );
+        assert!(
+            report.contains("- modified 1-1. Restart (body)"),
+            "{report}"
+        );
+        assert!(report.contains("- removed: Backups"), "{report}");
+        assert!(report.contains("- added 3. Rollback"), "{report}");
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn subtree_export_of_same_book_is_replaced_in_place() {
+        let dir = fresh_dir("outline-mcp-import-in-place-test");
+        let (server, mut tree) = runbook_with_ops_export(&dir).await;
+        assert_eq!(tree.subtree_path.as_deref(), Some("1"));
+        tree.nodes.truncate(1);
+        tree.nodes[0].title = "Restart safely".into();
+
+        let result = server
+            .import(Parameters(write_tree(&dir, &tree)))
+            .await
+            .unwrap();
+        let msg = &result.content[0].as_text().unwrap().text;
+        assert!(msg.contains("Replaced subtree 1 in place"), "{msg}");
+
+        let book = server.service().await.unwrap().read_tree().await.unwrap();
+        let titles: Vec<&str> = book.all_nodes_dfs().iter().map(|n| n.title()).collect();
+        assert_eq!(titles, ["Ops", "Restart safely", "Dev"]);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn subtree_export_of_other_book_replaces_the_book() {
+        let dir = fresh_dir("outline-mcp-import-other-book-test");
+        let (server, mut tree) = runbook_with_ops_export(&dir).await;
+        tree.book_id = Some(outline_mcp_core::domain::model::id::BookId::new().to_string());
+
+        server
+            .import(Parameters(write_tree(&dir, &tree)))
+            .await
+            .unwrap();
+        let book = server.service().await.unwrap().read_tree().await.unwrap();
+        assert_eq!(book.title(), "Ops");
+        assert_eq!(book.node_count(), 2);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn in_place_requires_subtree_metadata() {
+        let dir = fresh_dir("outline-mcp-import-no-metadata-test");
+        let (server, mut tree) = runbook_with_ops_export(&dir).await;
+        tree.book_id = None;
+        tree.subtree_uuid = None;
+
+        let req = McpImportRequest {
+            in_place: Some(true),
+            ..write_tree(&dir, &tree)
+        };
+        let err = server.import(Parameters(req)).await.unwrap_err();
+        assert!(err.message.contains("book_id"), "{}", err.message);
+
+        // 既定では従来どおり Book ごと置き換える
+        server
+            .import(Parameters(write_tree(&dir, &tree)))
+            .await
+            .unwrap();
+        let book = server.service().await.unwrap().read_tree().await.unwrap();
+        assert_eq!(book.title(), "Ops");
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn import_over_the_mutation_budget_needs_confirm() {
+        let dir = fresh_dir("outline-mcp-import-budget-test");
+        let (server, tree) = runbook_with_ops_export(&dir).await;
+        let server = server.with_config(crate::config::ServerConfig {
+            mutation_budget: Some(3),
+            ..Default::default()
+        });
+
+        // 部分木の置き換えは子2つを消して2つ作る（計4）
+        let dry_run = McpImportRequest {
+            dry_run: Some(true),
+            ..write_tree(&dir, &tree)
+        };
+        server.import(Parameters(dry_run)).await.expect("dry run");
+        let err = server
+            .import(Parameters(write_tree(&dir, &tree)))
+            .await
+            .unwrap_err();
+        assert!(
+            err.message.contains("create 2 and remove 2 node(s)"),
+            "{}",
+            err.message
+        );
+        let confirmed = McpImportRequest {
+            confirm: true,
+            ..write_tree(&dir, &tree)
+        };
+        server
+            .import(Parameters(confirmed))
+            .await
+            .expect("confirmed");
+        let book = server.service().await.unwrap().read_tree().await.unwrap();
+        assert_eq!(book.node_count(), 4);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn import_reads_markdown_checklist_export() {
+        let dir = fresh_dir("outline-mcp-import-markdown-test");
+        let server = OutlineMcpServer::new(dir.clone());
+
+        let source = server.service_for("runbook").await.unwrap();
+        source.create_book("Runbook", 4).await.unwrap();
+        let (placed, _) = source
+            .add_node(AddNodeRequest {
+                parent: None,
+                title: "Deploy".into(),
+                node_type: NodeType::Content,
+                body: Some("Before you start:\n- [ ] freeze merges\n> owner: ___".into()),
+                placeholder: Some("version".into()),
+                position: Position::Last,
+                properties: HashMap::new(),
+            })
+            .await
+            .unwrap();
+        let book = source.read_tree().await.unwrap();
+        let md_path = dir.join("runbook.md");
+        // 本文の中のチェックリスト風の行は mark_body_lines で印を付けて書き出す
+        *server.selected.write().unwrap() = Some("runbook".to_string());
+        let export: McpEjectRequest = serde_json::from_value(serde_json::json!({
+            "inline": true,
+            "include_placeholders": true,
+            "mark_body_lines": true,
+        }))
+        .unwrap();
+        let md = text(server.checklist(Parameters(export)).await.unwrap());
+        assert!(md.contains("freeze merges <!--body-->"), "{md}");
+        std::fs::write(&md_path, md).unwrap();
+
+        let scratch = server.service_for("scratch").await.unwrap();
+        scratch.create_book("Scratch", 4).await.unwrap();
+        *server.selected.write().unwrap() = Some("scratch".to_string());
+        let req = McpImportRequest {
+            file_path: Some(md_path.to_string_lossy().into_owned()),
+            source_book: None,
+            ..import_req("unused")
+        };
+        let result = server.import(Parameters(req)).await.unwrap();
+        let msg = &result.content[0].as_text().unwrap().text;
+        assert!(msg.starts_with("Imported 'Runbook': 1 nodes."), "{msg}");
+
+        let imported = scratch.read_tree().await.unwrap();
+        let node = imported.get_node(imported.root_nodes()[0]).unwrap();
+        let original = book.get_node(placed.node_id).unwrap();
+        assert_eq!(node.title(), original.title());
+        assert_eq!(node.body(), original.body());
+        assert_eq!(node.placeholder(), Some("version"));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn import_copies_shelf_book_into_selected_with_new_ids() {
+        let dir = fresh_dir("outline-mcp-import-source-book-test");
+        let server = OutlineMcpServer::new(dir.clone());
+
+        let source = server.service_for("runbook").await.unwrap();
+        source.create_book("Runbook", 4).await.unwrap();
+        let (placed, _) = source
+            .add_node(AddNodeRequest {
+                parent: None,
+                title: "Deploy".into(),
+                node_type: NodeType::Section,
+                body: None,
+                placeholder: None,
+                position: Position::Last,
+                properties: HashMap::new(),
+            })
+            .await
+            .unwrap();
+        let source_id = placed.node_id;
+        let scratch = server.service_for("scratch").await.unwrap();
+        scratch.create_book("Scratch", 4).await.unwrap();
+        let source_file = dir.join("runbook.json");
+        let source_before = std::fs::read_to_string(&source_file).unwrap();
+
+        *server.selected.write().unwrap() = Some("scratch".to_string());
+        server
+            .import(Parameters(import_req("runbook")))
+            .await
+            .expect("import from shelf book");
+
+        let copied = scratch.read_tree().await.unwrap();
+        assert_eq!(copied.title(), "Runbook");
+        let root = copied.get_node(copied.root_nodes()[0]).unwrap();
+        assert_eq!(root.title(), "Deploy");
+        assert_ne!(root.id(), source_id, "IDs are regenerated");
+        assert_eq!(
+            std::fs::read_to_string(&source_file).unwrap(),
+            source_before
+        );
+
+        // コピー元 = コピー先は拒否
+        *server.selected.write().unwrap() = Some("runbook".to_string());
+        let err = server
+            .import(Parameters(import_req("runbook")))
+            .await
+            .unwrap_err();
+        assert!(
+            err.message.contains("is the selected book"),
+            "{}",
+            err.message
+        );
+
+        let mut both = import_req("scratch");
+        both.file_path = Some("/tmp/book.json".into());
+        assert!(server.import(Parameters(both)).await.is_err());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn merge_too_deep_errors_untouched_then_raises_on_request() {
+        let dir = fresh_dir("outline-mcp-import-depth-strategy-test");
+        let server = OutlineMcpServer::new(dir.clone());
+
+        let section = |parent, title: &str| AddNodeRequest {
+            parent,
+            title: title.into(),
+            node_type: NodeType::Section,
+            body: None,
+            placeholder: None,
+            position: Position::Last,
+            properties: HashMap::new(),
+        };
+        let source = server.service_for("deep").await.unwrap();
+        source.create_book("Deep", 3).await.unwrap();
+        let mut parent = None;
+        for title in ["Ops", "Deploy", "Rollback"] {
+            let (placed, _) = source.add_node(section(parent, title)).await.unwrap();
+            parent = Some(placed.node_id);
+        }
+        let target = server.service_for("shallow").await.unwrap();
+        target.create_book("Shallow", 2).await.unwrap();
+        target.add_node(section(None, "Host")).await.unwrap();
+        let target_file = dir.join("shallow.json");
+        let before = std::fs::read_to_string(&target_file).unwrap();
+
+        *server.selected.write().unwrap() = Some("shallow".to_string());
+        let mut req = import_req("deep");
+        req.subtree = Some("1".into());
+        req.parent = Some("1".into());
+        let err = server.import(Parameters(req.clone())).await.unwrap_err();
+        assert!(
+            err.message.contains("'Ops > Deploy > Rollback'"),
+            "{}",
+            err.message
+        );
+        assert_eq!(std::fs::read_to_string(&target_file).unwrap(), before);
+
+        req.depth_strategy = Some("raise".into());
+        let result = server.import(Parameters(req)).await.unwrap();
+        let text = result.content[0].as_text().unwrap().text.clone();
+        assert!(text.contains("Raised max_depth 2 → 4"), "{text}");
+        assert_eq!(target.read_tree().await.unwrap().max_depth(), 4);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn retype_subtree_applies_section_content_convention() {
+        let dir = fresh_dir("outline-mcp-retype-subtree-test");
+        let server = OutlineMcpServer::new(dir.clone());
+
+        let node = |parent, title: &str, node_type| AddNodeRequest {
+            parent,
+            title: title.into(),
+            node_type,
+            body: None,
+            placeholder: None,
+            position: Position::Last,
+            properties: HashMap::new(),
+        };
+        let svc = server.service_for("legacy").await.unwrap();
+        svc.create_book("Legacy", 4).await.unwrap();
+        let (ops, _) = svc
+            .add_node(node(None, "Ops", NodeType::Content))
+            .await
+            .unwrap();
+        let ops = ops.node_id;
+        svc.add_node(node(Some(ops), "Deploy", NodeType::Content))
+            .await
+            .unwrap();
+        svc.add_node(node(Some(ops), "Later", NodeType::Section))
+            .await
+            .unwrap();
+        svc.add_node(node(None, "Other", NodeType::Section))
+            .await
+            .unwrap();
+
+        *server.selected.write().unwrap() = Some("legacy".to_string());
+        let req = McpRetypeSubtreeRequest {
+            node_id: "1".into(),
+        };
+        let result = server
+            .retype_subtree(Parameters(req.clone()))
+            .await
+            .unwrap();
+        let text = result.content[0].as_text().unwrap().text.clone();
+        assert_eq!(text, "Retyped 2 node(s) under 1. Ops.");
+
+        let book = svc.read_tree().await.unwrap();
+        let types: Vec<(&str, &NodeType)> = book
+            .all_nodes_dfs()
+            .into_iter()
+            .map(|n| (n.title(), n.node_type()))
+            .collect();
+        assert_eq!(
+            types,
+            vec![
+                ("Ops", &NodeType::Section),
+                ("Deploy", &NodeType::Content),
+                ("Later", &NodeType::Content),
+                ("Other", &NodeType::Section),
+            ]
+        );
+
+        let result = server.retype_subtree(Parameters(req)).await.unwrap();
+        let text = result.content[0].as_text().unwrap().text.clone();
+        assert!(text.starts_with("No change under 1. Ops"), "{text}");
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn backup_round_trips_every_id_and_field() {
+        let dir = fresh_dir("outline-mcp-backup-roundtrip-test");
+        let (server, _) = runbook_with_ops_export(&dir).await;
+        let svc = server.service().await.unwrap();
+        let original = svc.read_tree().await.unwrap();
+
+        let export: McpEjectRequest = serde_json::from_value(serde_json::json!({
+            "format": "backup",
+            "output_dir": dir.to_str().unwrap(),
+        }))
+        .unwrap();
+        let msg = server.checklist(Parameters(export)).await.unwrap();
+        let msg = msg.content[0].as_text().unwrap().text.clone();
+        assert!(msg.contains("Runbook.backup.json (4 nodes"), "{msg}");
+        let path = dir.join("Runbook.backup.json");
+
+        // 新しい slug へ: Book ID もノード ID もそのまま
+        let restore = McpImportRequest {
+            file_path: Some(path.to_string_lossy().into_owned()),
+            source_book: None,
+            slug: Some("runbook-copy".into()),
+            ..import_req("unused")
+        };
+        server.import(Parameters(restore)).await.unwrap();
+        let copy = server
+            .service_for("runbook-copy")
+            .await
+            .unwrap()
+            .read_tree()
+            .await
+            .unwrap();
+        assert_eq!(
+            serde_json::to_value(&copy).unwrap(),
+            serde_json::to_value(&original).unwrap()
+        );
+
+        // 既存の slug には戻さない。部分木の指定も受け付けない
+        let taken = McpImportRequest {
+            file_path: Some(path.to_string_lossy().into_owned()),
+            source_book: None,
+            slug: Some("runbook-copy".into()),
+            ..import_req("unused")
+        };
+        let err = server.import(Parameters(taken)).await.unwrap_err();
+        assert!(err.message.contains("already exists"), "{}", err.message);
+        let partial = McpImportRequest {
+            file_path: Some(path.to_string_lossy().into_owned()),
+            source_book: None,
+            subtree: Some("1".into()),
+            ..import_req("unused")
+        };
+        assert!(server.import(Parameters(partial)).await.is_err());
+
+        // 選択中の Book を置き換える
+        svc.with_book(|book| {
+            let id = book.root_nodes()[0];
+            book.remove_node(id)?;
+            Ok::<_, AppError>(((), vec![(id, ChangeAction::Delete)]))
+        })
+        .await
+        .unwrap()
+        .unwrap();
+        let restore = McpImportRequest {
+            file_path: Some(path.to_string_lossy().into_owned()),
+            source_book: None,
+            ..import_req("unused")
+        };
+        let msg = server.import(Parameters(restore)).await.unwrap();
+        let msg = msg.content[0].as_text().unwrap().text.clone();
+        assert_eq!(
+            msg,
+            "Restored backup 'Runbook' into 'runbook', replacing the current book: 4 nodes, IDs preserved."
+        );
+        assert_eq!(
+            serde_json::to_value(svc.read_tree().await.unwrap()).unwrap(),
+            serde_json::to_value(&original).unwrap()
+        );
+
+        // 新しいスキーマのバックアップは拒否する
+        let mut newer: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        newer["schema_version"] = 99.into();
+        std::fs::write(&path, newer.to_string()).unwrap();
+        let restore = McpImportRequest {
+            file_path: Some(path.to_string_lossy().into_owned()),
+            source_book: None,
+            ..import_req("unused")
+        };
+        let err = server.import(Parameters(restore)).await.unwrap_err();
+        assert!(
+            err.message.contains("schema_version 99 is newer"),
+            "{}",
+            err.message
+        );
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    /// 読み込み・保存の回数を数えるリポジトリ。`shift` なら読み込むたびに先頭へ
+    /// トップレベルノードを1つ差し込む（読み込みの合間に別クライアントが編集したのを模す）。
+    #[derive(Clone)]
+    struct ShiftingRepo {
+        book: Arc<Mutex<TemplateBook>>,
+        loads: Arc<AtomicUsize>,
+        saves: Arc<AtomicUsize>,
+        shift: bool,
+    }
+
+    impl ShiftingRepo {
+        fn new(book: TemplateBook, shift: bool) -> Self {
+            Self {
+                book: Arc::new(Mutex::new(book)),
+                loads: Arc::new(AtomicUsize::new(0)),
+                saves: Arc::new(AtomicUsize::new(0)),
+                shift,
+            }
+        }
+
+        fn server(&self, dir: &std::path::Path) -> OutlineMcpServer {
+            let repo = self.clone();
+            let server = OutlineMcpServer::new(dir.to_path_buf())
+                .with_repository_factory(move |_| Box::new(repo.clone()));
+            *server.selected.write().unwrap() = Some("runbook".to_string());
+            server
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl BookRepository for ShiftingRepo {
+        type Error = RepositoryError;
+        async fn load(&self) -> Result<Option<TemplateBook>, RepositoryError> {
+            let n = self.loads.fetch_add(1, Ordering::SeqCst) + 1;
+            let mut book = self.book.lock().unwrap();
+            if self.shift {
+                book.add_node(add_req(None, &format!("Intruder {n}"), Position::At(0)))
+                    .map_err(RepositoryError::new)?;
+            }
+            Ok(Some(book.clone()))
+        }
+        async fn save(&self, book: &TemplateBook) -> Result<(), RepositoryError> {
+            self.saves.fetch_add(1, Ordering::SeqCst);
+            *self.book.lock().unwrap() = book.clone();
+            Ok(())
+        }
+        async fn exists(&self) -> Result<bool, RepositoryError> {
+            Ok(true)
+        }
+        async fn delete(&self) -> Result<(), RepositoryError> {
+            Ok(())
+        }
+    }
+
+    fn add_req(
+        parent: Option<outline_mcp_core::domain::model::id::NodeId>,
+        title: &str,
+        position: Position,
+    ) -> AddNodeRequest {
+        AddNodeRequest {
+            parent,
+            title: title.into(),
+            node_type: NodeType::Section,
+            body: None,
+            placeholder: None,
+            position,
+            properties: HashMap::new(),
+        }
+    }
+
+    fn text(result: CallToolResult) -> String {
+        result.content[0].as_text().unwrap().text.clone()
+    }
+
+    /// 空の一時ディレクトリ（前回の実行の残りは消す）。
+    fn fresh_dir(name: &str) -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join(name);
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// `slug` に Book を作る。`nodes` は（親の添字, タイトル, 種類, 本文）を順に足す。
+    async fn add_book(
+        server: &OutlineMcpServer,
+        slug: &str,
+        title: &str,
+        nodes: &[(Option<usize>, &str, NodeType, Option<&str>)],
+    ) {
+        let svc = server.service_for(slug).await.unwrap();
+        svc.create_book(title, 6).await.unwrap();
+        let mut ids = Vec::new();
+        for (parent, title, node_type, body) in nodes {
+            let (placed, _) = svc
+                .add_node(AddNodeRequest {
+                    parent: parent.map(|i| ids[i]),
+                    title: title.to_string(),
+                    node_type: node_type.clone(),
+                    body: body.map(str::to_string),
+                    placeholder: None,
+                    position: Position::Last,
+                    properties: HashMap::new(),
+                })
+                .await
+                .unwrap();
+            ids.push(placed.node_id);
+        }
+    }
+
+    #[tokio::test]
+    async fn mutations_resolve_and_report_against_one_load() {
+        let dir = fresh_dir("outline-mcp-single-load-test");
+
+        let mut book = TemplateBook::new("Runbook", 4);
+        let alpha = book
+            .add_node(add_req(None, "Alpha", Position::Last))
+            .unwrap();
+        book.add_node(add_req(None, "Beta", Position::Last))
+            .unwrap();
+        let repo = ShiftingRepo::new(book, true);
+        let server = repo.server(&dir);
+
+        // 読み込み時点で "2" は Alpha（Intruder 1 が先頭に入る）
+        let created = text(
+            server
+                .node_create(Parameters(McpNodeCreateRequest {
+                    parent: Some("2".into()),
+                    title: "Child".into(),
+                    node_type: Some("content".into()),
+                    body: None,
+                    placeholder: None,
+                    command: None,
+                    position: None,
+                    properties: None,
+                    children: None,
+                    idempotency_key: None,
+                    dedupe: None,
+                    quiet: false,
+                    confirm: false,
+                }))
+                .await
+                .expect("node_create"),
+        );
+        assert_eq!(repo.loads.load(Ordering::SeqCst), 1, "one load per call");
+        assert_eq!(created, "Created: 2-1. Child");
+        {
+            let saved = repo.book.lock().unwrap();
+            let child = saved.get_node(alpha).unwrap().children()[0];
+            assert_eq!(find_hierarchical_id(&saved, child).as_deref(), Some("2-1"));
+        }
+
+        // 移動の報告も同じ読み込み（Intruder 2 が入った後）の番号で揃う
+        let moved = text(
+            server
+                .node_move(Parameters(McpNodeMoveRequest {
+                    node_id: "3-1".into(),
+                    action: "move".into(),
+                    new_parent: None,
+                    position: Some(PositionArg::Index(0)),
+                    override_protection: false,
+                    quiet: false,
+                    confirm: false,
+                }))
+                .await
+                .expect("node_move"),
+        );
+        assert_eq!(repo.loads.load(Ordering::SeqCst), 2);
+        assert!(moved.starts_with("Moved → 1. Child"), "{moved}");
+        let saved = repo.book.lock().unwrap();
+        let first = saved.get_node(saved.root_nodes()[0]).unwrap();
+        assert_eq!(first.title(), "Child");
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn batch_edit_tools_resolve_against_one_load() {
+        let dir = fresh_dir("outline-mcp-batch-single-load-test");
+
+        let mut book = TemplateBook::new("Runbook", 4);
+        let mut add = |parent, title: &str, node_type| {
+            book.add_node(AddNodeRequest {
+                node_type,
+                ..add_req(parent, title, Position::Last)
+            })
+            .unwrap()
+        };
+        let alpha = add(None, "Alpha", NodeType::Section);
+        let step = add(Some(alpha), "Step", NodeType::Content);
+        let beta = add(None, "Beta", NodeType::Section);
+        let other = add(Some(beta), "Other", NodeType::Content);
+        let gamma = add(None, "Gamma", NodeType::Section);
+        let loose = add(Some(gamma), "Loose", NodeType::Section);
+        book.update_node(
+            step,
+            UpdateNodeRequest {
+                done: Some(true),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        let repo = ShiftingRepo::new(book, true);
+        let server = repo.server(&dir);
+        fn parse<T: serde::de::DeserializeOwned>(v: serde_json::Value) -> T {
+            serde_json::from_value(v).unwrap()
+        }
+
+        // 呼び出し k 回目の読み込みでは Intruder が k 個先頭に入り、Alpha は k+1 番になる
+        let replies = [
+            text(
+                server
+                    .assign(Parameters(parse(
+                        serde_json::json!({"node_ids": ["2-1"], "owner": "alice"}),
+                    )))
+                    .await
+                    .unwrap(),
+            ),
+            text(
+                server
+                    .node_depend(Parameters(parse(
+                        serde_json::json!({"node_id": "3-1", "depends_on": "4-1"}),
+                    )))
+                    .await
+                    .unwrap(),
+            ),
+            text(
+                server
+                    .node_link_url(Parameters(parse(
+                        serde_json::json!({"node_id": "4-1", "url": "https://grafana.example"}),
+                    )))
+                    .await
+                    .unwrap(),
+            ),
+            text(
+                server
+                    .reset_progress(Parameters(parse(serde_json::json!({"node_id": "5"}))))
+                    .await
+                    .unwrap(),
+            ),
+            text(
+                server
+                    .node_templatize(Parameters(parse(
+                        serde_json::json!({"node_id": "6-1", "placeholder": "fill in"}),
+                    )))
+                    .await
+                    .unwrap(),
+            ),
+            text(
+                server
+                    .seed_placeholders(Parameters(parse(
+                        serde_json::json!({"node_id": "8", "hint": "Describe"}),
+                    )))
+                    .await
+                    .unwrap(),
+            ),
+            text(
+                server
+                    .retype_subtree(Parameters(parse(serde_json::json!({"node_id": "10"}))))
+                    .await
+                    .unwrap(),
+            ),
+        ];
+        assert_eq!(repo.loads.load(Ordering::SeqCst), 7, "one load per call");
+        // 直接足したノードには作成イベントがないので、changelog の警告行は見ない
+        let replies = replies.map(|r| r.lines().next().unwrap_or_default().to_string());
+        assert_eq!(
+            replies[1],
+            "Dependency added: 3-1. Step is blocked by 4-1. Other"
+        );
+        assert!(replies[3].ends_with("under 5. Alpha."), "{}", replies[3]);
+        assert_eq!(replies[4], "Templatized: 6-1. Step");
+        assert!(replies[5].contains("under 8. Beta"), "{}", replies[5]);
+        assert!(replies[6].ends_with("under 10. Gamma."), "{}", replies[6]);
+
+        let saved = repo.book.lock().unwrap();
+        let node = |id| saved.get_node(id).unwrap();
+        assert_eq!(node(step).owner(), Some("alice"));
+        assert_eq!(node(step).depends_on(), &[other]);
+        assert_eq!(node(step).links().len(), 1);
+        assert!(!node(step).is_done());
+        assert_eq!(node(step).placeholder(), Some("fill in"));
+        assert_eq!(node(other).placeholder(), Some("Describe"));
+        assert_eq!(*node(loose).node_type(), NodeType::Content);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn moving_to_the_current_slot_saves_nothing() {
+        let dir = fresh_dir("outline-mcp-noop-move-test");
+
+        let mut book = TemplateBook::new("Runbook", 4);
+        let alpha = book
+            .add_node(add_req(None, "Alpha", Position::Last))
+            .unwrap();
+        book.add_node(add_req(Some(alpha), "First", Position::Last))
+            .unwrap();
+        book.add_node(add_req(Some(alpha), "Second", Position::Last))
+            .unwrap();
+        let repo = ShiftingRepo::new(book, false);
+        let server = repo.server(&dir);
+
+        let move_req = |position| McpNodeMoveRequest {
+            node_id: "1-2".into(),
+            action: "move".into(),
+            new_parent: Some("1".into()),
+            position,
+            override_protection: false,
+            quiet: false,
+            confirm: false,
+        };
+        // 末尾への丸めも含めて、今と同じ位置なら保存しない
+        for position in [Some(PositionArg::Index(1)), None] {
+            let msg = text(
+                server
+                    .node_move(Parameters(move_req(position)))
+                    .await
+                    .unwrap(),
+            );
+            assert!(msg.starts_with("No change: 1-2. Second"), "{msg}");
+        }
+        assert_eq!(repo.saves.load(Ordering::SeqCst), 0);
+
+        let msg = text(
+            server
+                .node_move(Parameters(move_req(Some(PositionArg::Index(0)))))
+                .await
+                .unwrap(),
+        );
+        assert!(msg.starts_with("Moved → 1-1. Second"), "{msg}");
+        assert_eq!(repo.saves.load(Ordering::SeqCst), 1);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn removing_a_protected_subtree_needs_override() {
+        let dir = fresh_dir("outline-mcp-protected-remove-test");
+
+        let mut book = TemplateBook::new("Runbook", 4);
+        let ops = book.add_node(add_req(None, "Ops", Position::Last)).unwrap();
+        let backups = book
+            .add_node(add_req(Some(ops), "Confirm backups", Position::Last))
+            .unwrap();
+        let repo = ShiftingRepo::new(book, false);
+        let server = repo.server(&dir);
+
+        let update: McpNodeUpdateRequest =
+            serde_json::from_str(r#"{"node_id": "1-1", "protected": true}"#).unwrap();
+        server.node_update(Parameters(update)).await.unwrap();
+        assert!(repo
+            .book
+            .lock()
+            .unwrap()
+            .get_node(backups)
+            .unwrap()
+            .is_protected());
+        let toc: McpTocRequest = serde_json::from_str("{}").unwrap();
+        let listing = text(server.toc(Parameters(toc)).await.unwrap());
+        assert!(listing.contains("1-1. Confirm backups 🛡"), "{listing}");
+
+        let remove = |override_protection| McpNodeMoveRequest {
+            node_id: "1".into(),
+            action: "remove".into(),
+            new_parent: None,
+            position: None,
+            override_protection,
+            quiet: false,
+            confirm: false,
+        };
+        let err = server
+            .node_move(Parameters(remove(false)))
+            .await
+            .unwrap_err();
+        assert_eq!(
+            err.message,
+            "cannot remove '1 Ops': it contains protected node '1-1 Confirm backups'; pass `override_protection: true` to delete it anyway"
+        );
+        assert_eq!(repo.book.lock().unwrap().node_count(), 2);
+
+        let msg = text(server.node_move(Parameters(remove(true))).await.unwrap());
+        assert!(msg.starts_with("Removed: 1. Ops"), "{msg}");
+        assert_eq!(repo.book.lock().unwrap().node_count(), 0);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn verify_roundtrip_reports_lossless_book() {
+        let dir = fresh_dir("outline-mcp-verify-roundtrip-test");
+
+        let mut book = TemplateBook::new("Runbook", 4);
+        let ops = book.add_node(add_req(None, "Ops", Position::Last)).unwrap();
+        book.add_node(add_req(Some(ops), "Restart", Position::Last))
+            .unwrap();
+        let server = ShiftingRepo::new(book, false).server(&dir);
+
+        let reply = text(
+            server
+                .verify_roundtrip(Parameters(McpVerifyRoundtripRequest {}))
+                .await
+                .unwrap(),
+        );
+        assert!(
+            reply.starts_with("lossless: 'Runbook' (2 nodes)"),
+            "{reply}"
+        );
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn node_move_to_top_and_bottom_of_the_book() {
+        let dir = fresh_dir("outline-mcp-move-extremes-test");
+
+        let mut book = TemplateBook::new("Runbook", 4);
+        let ops = book.add_node(add_req(None, "Ops", Position::Last)).unwrap();
+        let backups = book
+            .add_node(add_req(Some(ops), "Backups", Position::Last))
+            .unwrap();
+        let dev = book.add_node(add_req(None, "Dev", Position::Last)).unwrap();
+        let repo = ShiftingRepo::new(book, false);
+        let server = repo.server(&dir);
+        let move_req = |node_id: &str, action: &str| -> McpNodeMoveRequest {
+            serde_json::from_value(serde_json::json!({
+                "node_id": node_id,
+                "action": action,
+                "quiet": true,
+            }))
+            .unwrap()
+        };
+
+        let top = text(
+            server
+                .node_move(Parameters(move_req("1-1", "to_top")))
+                .await
+                .unwrap(),
+        );
+        // 変更履歴側の warning が続くことがあるので1行目だけ見る
+        assert_eq!(top.lines().next(), Some("1"));
+        assert_eq!(repo.book.lock().unwrap().root_nodes(), [backups, ops, dev]);
+
+        let bottom = text(
+            server
+                .node_move(Parameters(move_req("1", "to_bottom")))
+                .await
+                .unwrap(),
+        );
+        assert_eq!(bottom.lines().next(), Some("3"));
+        assert_eq!(repo.book.lock().unwrap().root_nodes(), [ops, dev, backups]);
+
+        let mut with_parent = move_req("1", "to_top");
+        with_parent.new_parent = Some("2".into());
+        let err = server.node_move(Parameters(with_parent)).await.unwrap_err();
+        assert!(err.message.contains("omit new_parent"), "{}", err.message);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn node_move_accepts_title_path_as_new_parent() {
+        let dir = fresh_dir("outline-mcp-move-title-path-test");
+
+        let mut book = TemplateBook::new("Spec", 4);
+        let design = book
+            .add_node(add_req(None, "Design", Position::Last))
+            .unwrap();
+        let api = book
+            .add_node(add_req(Some(design), "API", Position::Last))
+            .unwrap();
+        book.add_node(add_req(Some(design), "Storage", Position::Last))
+            .unwrap();
+        let ops = book.add_node(add_req(None, "Ops", Position::Last)).unwrap();
+        let cicd = book
+            .add_node(add_req(Some(ops), "CI/CD", Position::Last))
+            .unwrap();
+        book.add_node(add_req(Some(ops), "API", Position::Last))
+            .unwrap();
+        let task = book
+            .add_node(add_req(None, "Auth", Position::Last))
+            .unwrap();
+        let repo = ShiftingRepo::new(book, false);
+        let server = repo.server(&dir);
+        let move_to = |parent: &str| -> McpNodeMoveRequest {
+            serde_json::from_value(serde_json::json!({
+                "node_id": "3",
+                "action": "move",
+                "new_parent": parent,
+                "quiet": true,
+            }))
+            .unwrap()
+        };
+
+        // 大文字小文字と前後の空白・スラッシュは問わない
+        server
+            .node_move(Parameters(move_to(" design / api/")))
+            .await
+            .unwrap();
+        {
+            let book = repo.book.lock().unwrap();
+            assert_eq!(book.get_node(task).unwrap().parent(), Some(api));
+        }
+
+        // タイトル自体の '/' もそのまま一致する
+        let back: McpNodeMoveRequest = serde_json::from_value(serde_json::json!({
+            "node_id": "1-1-1",
+            "action": "move",
+            "new_parent": "Ops/CI/CD",
+            "quiet": true,
+        }))
+        .unwrap();
+        server.node_move(Parameters(back)).await.unwrap();
+        {
+            let book = repo.book.lock().unwrap();
+            assert_eq!(book.get_node(task).unwrap().parent(), Some(cicd));
+        }
+
+        // 途中までしか一致しなければ、その階層の子を挙げる
+        let missing: McpNodeMoveRequest = serde_json::from_value(serde_json::json!({
+            "node_id": "2-1-1",
+            "action": "move",
+            "new_parent": "Design/Security",
+        }))
+        .unwrap();
+        let err = server.node_move(Parameters(missing)).await.unwrap_err();
+        assert!(
+            err.message
+                .contains("Children under '1. Design': '1-1. API', '1-2. Storage'"),
+            "{}",
+            err.message
+        );
+        let top: McpNodeMoveRequest = serde_json::from_value(serde_json::json!({
+            "node_id": "2-1-1",
+            "action": "move",
+            "new_parent": "Plan/API",
+        }))
+        .unwrap();
+        let err = server.node_move(Parameters(top)).await.unwrap_err();
+        assert!(
+            err.message
+                .contains("Children at the top level: '1. Design', '2. Ops'"),
+            "{}",
+            err.message
+        );
+
+        // 重複するタイトルパスは候補を挙げて止める
+        {
+            let mut book = repo.book.lock().unwrap();
+            book.add_node(add_req(Some(design), "API", Position::Last))
+                .unwrap();
+        }
+        let twin: McpNodeMoveRequest = serde_json::from_value(serde_json::json!({
+            "node_id": "2-1-1",
+            "action": "move",
+            "new_parent": "Design/API",
+        }))
+        .unwrap();
+        let err = server.node_move(Parameters(twin)).await.unwrap_err();
+        assert!(
+            err.message
+                .contains("matches 2 nodes: '1-1. API', '1-3. API'"),
+            "{}",
+            err.message
+        );
+        {
+            let book = repo.book.lock().unwrap();
+            assert_eq!(book.get_node(task).unwrap().parent(), Some(cicd));
+        }
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn toc_sections_only_skips_content_items() {
+        let dir = fresh_dir("outline-mcp-toc-sections-only-test");
+
+        let mut book = TemplateBook::new("Runbook", 4);
+        let ops = book.add_node(add_req(None, "Ops", Position::Last)).unwrap();
+        book.add_node(AddNodeRequest {
+            node_type: NodeType::Content,
+            ..add_req(Some(ops), "Restart", Position::Last)
+        })
+        .unwrap();
+        book.add_node(add_req(Some(ops), "Backups", Position::Last))
+            .unwrap();
+        let repo = ShiftingRepo::new(book, false);
+        let server = repo.server(&dir);
+
+        let toc: McpTocRequest = serde_json::from_str(r#"{"sections_only": true}"#).unwrap();
+        let listing = text(server.toc(Parameters(toc)).await.unwrap());
+        assert_eq!(listing, "# Runbook (3 nodes)\n\n1. Ops\n  1-2. Backups\n");
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn checklist_output_path_writes_nested_file_inside_shelf() {
+        let dir = fresh_dir("outline-mcp-output-path-tool-test");
+
+        let mut book = TemplateBook::new("Runbook", 4);
+        book.add_node(add_req(None, "Ops", Position::Last)).unwrap();
+        let repo = ShiftingRepo::new(book, false);
+        let server = repo.server(&dir);
+
+        let target = dir.join("exports/ops/runbook.md");
+        let req: McpEjectRequest = serde_json::from_value(serde_json::json!({
+            "output_path": target.to_str().unwrap(),
+        }))
+        .unwrap();
+        let msg = text(server.checklist(Parameters(req)).await.unwrap());
+        assert!(msg.ends_with("exports/ops/runbook.md"), "{msg}");
+        assert!(std::fs::read_to_string(&target).unwrap().contains("Ops"));
+
+        let both: McpEjectRequest = serde_json::from_value(serde_json::json!({
+            "output_path": target.to_str().unwrap(),
+            "filename": "other.md",
+        }))
+        .unwrap();
+        assert!(server.checklist(Parameters(both)).await.is_err());
+        let outside: McpEjectRequest = serde_json::from_value(serde_json::json!({
+            "output_path": std::env::temp_dir().join("outline-mcp-stray.md").to_str().unwrap(),
+        }))
+        .unwrap();
+        assert!(server.checklist(Parameters(outside)).await.is_err());
+
+        // shelf 直下の Book ファイルや設定ファイルは上書きできない
+        std::fs::write(dir.join("runbook.json"), "{}").unwrap();
+        for (name, format) in [
+            ("runbook.json", "json"),
+            ("runbook.json", "backup"),
+            ("outline-mcp.config.json", "json"),
+            ("notes.md", "markdown"),
+        ] {
+            let clobber: McpEjectRequest = serde_json::from_value(serde_json::json!({
+                "output_path": dir.join(name).to_str().unwrap(),
+                "format": format,
+            }))
+            .unwrap();
+            let err = server.checklist(Parameters(clobber)).await.unwrap_err();
+            assert!(err.message.contains("exports"), "{}", err.message);
+        }
+        assert_eq!(
+            std::fs::read_to_string(dir.join("runbook.json")).unwrap(),
+            "{}"
+        );
+        assert!(!dir.join("outline-mcp.config.json").exists());
+
+        // export_root が shelf を指していても同じ
+        let server = repo.server(&dir).with_config(ServerConfig {
+            export_root: Some(dir.clone()),
+            ..ServerConfig::default()
+        });
+        let clobber: McpEjectRequest = serde_json::from_value(serde_json::json!({
+            "output_path": dir.join("runbook.json").to_str().unwrap(),
+            "format": "json",
+        }))
+        .unwrap();
+        let err = server.checklist(Parameters(clobber)).await.unwrap_err();
+        assert!(err.message.contains("shelf directory"), "{}", err.message);
+        assert_eq!(
+            std::fs::read_to_string(dir.join("runbook.json")).unwrap(),
+            "{}"
+        );
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn node_get_matches_titles_but_removal_requires_a_number_or_uuid() {
+        let dir = fresh_dir("outline-mcp-resolve-policy-test");
+        let server = OutlineMcpServer::new(dir.clone());
+        add_book(
+            &server,
+            "policy-book",
+            "Policy Test",
+            &[
+                (None, "Integration tests", NodeType::Content, None),
+                (None, "Release", NodeType::Content, None),
+            ],
+        )
+        .await;
+        *server.selected.write().unwrap() = Some("policy-book".to_string());
+        let get = |node_id: &str| {
+            let req: McpNodeGetRequest =
+                serde_json::from_value(serde_json::json!({ "node_id": node_id })).unwrap();
+            server.node_get(Parameters(req))
+        };
+        let remove = |node_id: &str| {
+            let req: McpNodeMoveRequest = serde_json::from_value(
+                serde_json::json!({ "node_id": node_id, "action": "remove" }),
+            )
+            .unwrap();
+            server.node_move(Parameters(req))
+        };
+        let titles = || async {
+            let book = server.service().await.unwrap().read_tree().await.unwrap();
+            book.all_nodes_dfs()
+                .iter()
+                .map(|n| n.title().to_string())
+                .collect::<Vec<_>>()
+        };
+
+        // 閲覧（node_get）はタイトルの部分一致で解決する
+        let shown = text(get("test").await.unwrap());
+        assert!(shown.contains("Integration tests"), "{shown}");
+
+        // 削除は同じ文字列を拒否し、何も消さない
+        let err = remove("test").await.unwrap_err();
+        assert!(
+            err.message.contains("not a node number or UUID"),
+            "{}",
+            err.message
+        );
+        assert_eq!(titles().await, ["Integration tests", "Release"]);
+
+        // 階層番号と UUID なら削除できる
+        remove("1").await.unwrap();
+        assert_eq!(titles().await, ["Release"]);
+        let release = server
+            .service()
+            .await
+            .unwrap()
+            .read_tree()
+            .await
+            .unwrap()
+            .root_nodes()[0];
+        remove(&release.to_string()).await.unwrap();
+        assert!(titles().await.is_empty());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn recent_treats_huge_within_minutes_as_unbounded() {
+        let dir = fresh_dir("outline-mcp-recent-window-test");
+        let server = OutlineMcpServer::new(dir.clone());
+        add_book(
+            &server,
+            "runbook",
+            "Runbook",
+            &[(None, "Deploy", NodeType::Content, None)],
+        )
+        .await;
+        *server.selected.write().unwrap() = Some("runbook".to_string());
+
+        // u64 を i64 にそのまま変換すると負になり、境界が未来に来て何も出なくなる
+        for within in [60, u64::MAX] {
+            let req: McpRecentRequest =
+                serde_json::from_value(serde_json::json!({ "within_minutes": within })).unwrap();
+            let msg = text(server.recent(Parameters(req)).await.unwrap());
+            assert!(msg.contains("Deploy"), "within_minutes {within}: {msg}");
+        }
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn checklist_accepts_wrap_as_alias_for_wrap_width() {
+        let dir = fresh_dir("outline-mcp-wrap-alias-tool-test");
+        let server = OutlineMcpServer::new(dir.clone());
+        add_book(
+            &server,
+            "runbook",
+            "Runbook",
+            &[(
+                None,
+                "Deploy",
+                NodeType::Content,
+                Some("one two three four five six seven eight nine ten"),
+            )],
+        )
+        .await;
+        *server.selected.write().unwrap() = Some("runbook".to_string());
+
+        let export = |args: serde_json::Value| {
+            let req: McpEjectRequest = serde_json::from_value(args).unwrap();
+            server.checklist(Parameters(req))
+        };
+        let by_alias = text(
+            export(serde_json::json!({ "inline": true, "wrap": 20 }))
+                .await
+                .unwrap(),
+        );
+        let by_name = text(
+            export(serde_json::json!({ "inline": true, "wrap_width": 20 }))
+                .await
+                .unwrap(),
+        );
+        let unwrapped = text(export(serde_json::json!({ "inline": true })).await.unwrap());
+        assert_eq!(by_alias, by_name);
+        assert_ne!(by_alias, unwrapped);
+        assert!(by_alias
+            .lines()
+            .all(|l| l.chars().count() <= 20 || !l.contains(' ')));
+
+        // 0 は別名でも拒否し、エラーは両方の名前を出す
+        let err = export(serde_json::json!({ "inline": true, "wrap": 0 }))
+            .await
+            .unwrap_err();
+        assert!(err.message.contains("`wrap`"), "{}", err.message);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn checklist_defaults_to_exports_under_shelf_and_reports_absolute_path() {
+        let dir = fresh_dir("outline-mcp-default-exports-test");
+
+        let mut book = TemplateBook::new("Runbook", 4);
+        book.add_node(add_req(None, "Ops", Position::Last)).unwrap();
+        let repo = ShiftingRepo::new(book, false);
+        let server = repo.server(&dir);
+        let exports = dir.canonicalize().unwrap().join("exports");
+
+        // 書き出すまでディレクトリは作らない
+        let status = text(
+            server
+                .status(Parameters(McpStatusRequest {}))
+                .await
+                .unwrap(),
+        );
+        assert!(
+            status.contains(&format!("- Exports go to: {} (", exports.display())),
+            "{status}"
+        );
+        assert!(!exports.exists());
+
+        let req: McpEjectRequest = serde_json::from_value(serde_json::json!({})).unwrap();
+        let msg = text(server.checklist(Parameters(req)).await.unwrap());
+        let written = exports.join("Runbook.md");
+        assert_eq!(msg, format!("Checklist exported to: {}", written.display()));
+        assert!(written.is_file());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn quiet_mutations_reply_with_id_or_ok() {
+        let dir = fresh_dir("outline-mcp-quiet-test");
+
+        let mut book = TemplateBook::new("Runbook", 4);
+        let ops = book.add_node(add_req(None, "Ops", Position::Last)).unwrap();
+        book.add_node(add_req(Some(ops), "Restart", Position::Last))
+            .unwrap();
+        let repo = ShiftingRepo::new(book, false);
+        let server = repo.server(&dir);
+
+        let create: McpNodeCreateRequest = serde_json::from_str(
+            r#"{"parent": "1", "title": "Verify", "node_type": "content", "quiet": true}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            text(server.node_create(Parameters(create)).await.unwrap()),
+            "1-2"
+        );
+
+        let update: McpNodeUpdateRequest = serde_json::from_str(
+            r#"{"node_id": "1-2", "body": "check logs", "position": 0, "quiet": true}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            text(server.node_update(Parameters(update)).await.unwrap()),
+            "1-1"
+        );
+
+        let remove: McpNodeMoveRequest =
+            serde_json::from_str(r#"{"node_id": "1-2", "action": "remove", "quiet": true}"#)
+                .unwrap();
+        assert_eq!(
+            text(server.node_move(Parameters(remove)).await.unwrap()),
+            "ok"
+        );
+        assert_eq!(repo.book.lock().unwrap().node_count(), 2);
+
+        // quiet を付けなければ従来どおりの応答
+        let create: McpNodeCreateRequest =
+            serde_json::from_str(r#"{"parent": "1", "title": "Report", "node_type": "content"}"#)
+                .unwrap();
+        let msg = text(server.node_create(Parameters(create)).await.unwrap());
+        assert_eq!(msg, "Created: 1-2. Report");
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn dedupe_skip_reuses_matching_sibling_without_saving() {
+        let dir = fresh_dir("outline-mcp-dedupe-create-test");
+
+        let mut book = TemplateBook::new("Runbook", 4);
+        book.add_node(add_req(None, "Deploy", Position::Last))
+            .unwrap();
+        let repo = ShiftingRepo::new(book, false);
+        let server = repo.server(&dir);
+
+        let create = |dedupe: &str| McpNodeCreateRequest {
+            parent: None,
+            title: "DEPLOY".into(),
+            node_type: Some("section".into()),
+            body: None,
+            placeholder: None,
+            command: None,
+            position: None,
+            properties: None,
+            children: None,
+            idempotency_key: None,
+            dedupe: Some(dedupe.into()),
+            quiet: false,
+            confirm: false,
+        };
+        let msg = text(
+            server
+                .node_create(Parameters(create("skip")))
+                .await
+                .unwrap(),
+        );
+        assert!(msg.starts_with("Already exists: 1. Deploy"), "{msg}");
+        assert_eq!(repo.saves.load(Ordering::SeqCst), 0);
+
+        let msg = text(server.node_create(Parameters(create("off"))).await.unwrap());
+        assert_eq!(msg, "Created: 2. DEPLOY");
+        assert_eq!(repo.book.lock().unwrap().root_nodes().len(), 2);
+
+        let err = server
+            .node_create(Parameters(create("merge")))
+            .await
+            .unwrap_err();
+        assert!(err.message.contains("dedupe"), "{}", err.message);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn omitted_node_type_is_inferred_from_children() {
+        let dir = fresh_dir("outline-mcp-infer-node-type-test");
+
+        let repo = ShiftingRepo::new(TemplateBook::new("Runbook", 4), false);
+        let server = repo.server(&dir);
+        let create = |json: &str| -> McpNodeCreateRequest { serde_json::from_str(json).unwrap() };
+        server
+            .node_create(Parameters(create(r#"{"title": "Check disk space"}"#)))
+            .await
+            .unwrap();
+        server
+            .node_create(Parameters(create(
+                r#"{"title": "Deploy", "children": [{"title": "Tag release"}]}"#,
+            )))
+            .await
+            .unwrap();
+
+        let book = repo.book.lock().unwrap();
+        let types: Vec<NodeType> = book
+            .all_nodes_dfs()
+            .iter()
+            .map(|n| n.node_type().clone())
+            .collect();
+        assert_eq!(
+            types,
+            [NodeType::Content, NodeType::Section, NodeType::Content]
+        );
+        drop(book);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn position_takes_first_last_or_an_index_on_create_and_move() {
+        let dir = fresh_dir("outline-mcp-position-forms-test");
+
+        let repo = ShiftingRepo::new(TemplateBook::new("Runbook", 4), false);
+        let server = repo.server(&dir);
+        let titles = || -> Vec<String> {
+            let book = repo.book.lock().unwrap();
+            book.root_nodes()
+                .iter()
+                .map(|id| book.get_node(*id).unwrap().title().to_string())
+                .collect()
+        };
+        for json in [
+            r#"{"title": "B"}"#,
+            r#"{"title": "C", "position": "last"}"#,
+            r#"{"title": "A", "position": "first"}"#,
+            r#"{"title": "X", "position": 1}"#,
+            r#"{"title": "Y", "position": "2"}"#,
+        ] {
+            let req: McpNodeCreateRequest = serde_json::from_str(json).unwrap();
+            server.node_create(Parameters(req)).await.unwrap();
+        }
+        assert_eq!(titles(), ["A", "X", "Y", "B", "C"]);
+
+        for (json, expected) in [
+            (
+                r#"{"node_id": "2", "action": "move", "position": "last"}"#,
+                ["A", "Y", "B", "C", "X"],
+            ),
+            (
+                r#"{"node_id": "5", "action": "move", "position": "first"}"#,
+                ["X", "A", "Y", "B", "C"],
+            ),
+            (
+                r#"{"node_id": "1", "action": "move", "position": 3}"#,
+                ["A", "Y", "B", "X", "C"],
+            ),
+        ] {
+            let req: McpNodeMoveRequest = serde_json::from_str(json).unwrap();
+            server.node_move(Parameters(req)).await.unwrap();
+            assert_eq!(titles(), expected, "{json}");
+        }
+
+        let req: McpNodeCreateRequest =
+            serde_json::from_str(r#"{"title": "Z", "position": "middle"}"#).unwrap();
+        let err = server.node_create(Parameters(req)).await.unwrap_err();
+        assert!(err.message.contains("Unknown position"), "{}", err.message);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn commands_set_on_create_are_extracted_as_a_script() {
+        let dir = fresh_dir("outline-mcp-extract-commands-test");
+
+        let repo = ShiftingRepo::new(TemplateBook::new("Runbook", 4), false);
+        let server = repo.server(&dir);
+        let create: McpNodeCreateRequest = serde_json::from_str(
+            r#"{"title": "Restart", "command": "systemctl restart app", "children": []}"#,
+        )
+        .unwrap();
+        server.node_create(Parameters(create)).await.unwrap();
+
+        let req: McpEjectRequest =
+            serde_json::from_str(r#"{"inline": true, "extract_commands": true}"#).unwrap();
+        let script = text(server.checklist(Parameters(req)).await.unwrap());
+        assert_eq!(
+            script,
+            "#!/bin/sh\n# Runbook\n\n# 1 Restart\nsystemctl restart app\n"
+        );
+
+        let req: McpEjectRequest =
+            serde_json::from_str(r#"{"extract_commands": true, "format": "json"}"#).unwrap();
+        let err = server.checklist(Parameters(req)).await.unwrap_err();
+        assert!(err.message.contains("shell script"), "{}", err.message);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn links_are_added_shown_exported_and_removed() {
+        let dir = fresh_dir("outline-mcp-node-link-url-test");
+
+        let mut book = TemplateBook::new("Runbook", 4);
+        book.add_node(add_req(None, "Check API", Position::Last))
+            .unwrap();
+        let repo = ShiftingRepo::new(book, false);
+        let server = repo.server(&dir);
+        let link = |json: &str| -> McpNodeLinkUrlRequest { serde_json::from_str(json).unwrap() };
+
+        let msg = text(
+            server
+                .node_link_url(Parameters(link(
+                    r#"{"node_id": "1", "url": "https://grafana.example/d/api", "label": "API dashboard"}"#,
+                )))
+                .await
+                .unwrap(),
+        );
+        assert!(
+            msg.contains("[API dashboard](https://grafana.example/d/api)"),
+            "{msg}"
+        );
+        let err = server
+            .node_link_url(Parameters(link(
+                r#"{"node_id": "1", "url": "grafana.example"}"#,
+            )))
+            .await
+            .unwrap_err();
+        assert!(err.message.contains("scheme"), "{}", err.message);
+
+        let get: McpNodeGetRequest = serde_json::from_str(r#"{"node_id": "1"}"#).unwrap();
+        let detail = text(server.node_get(Parameters(get)).await.unwrap());
+        assert!(
+            detail.contains("## Links\n\n- [API dashboard](https://grafana.example/d/api)\n"),
+            "{detail}"
+        );
+
+        let req: McpEjectRequest = serde_json::from_str(r#"{"inline": true}"#).unwrap();
+        let md = text(server.checklist(Parameters(req)).await.unwrap());
+        assert!(
+            md.contains("## Check API\n\n- [API dashboard](https://grafana.example/d/api)\n"),
+            "{md}"
+        );
+
+        let msg = text(
+            server
+                .node_link_url(Parameters(link(
+                    r#"{"node_id": "1", "url": "API dashboard", "remove": true}"#,
+                )))
+                .await
+                .unwrap(),
+        );
+        assert!(msg.starts_with("Link removed"), "{msg}");
+        let get: McpNodeGetRequest = serde_json::from_str(r#"{"node_id": "1"}"#).unwrap();
+        let detail = text(server.node_get(Parameters(get)).await.unwrap());
+        assert!(!detail.contains("## Links"), "{detail}");
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn seed_placeholders_fills_only_bare_content_leaves() {
+        let dir = fresh_dir("outline-mcp-seed-placeholders-test");
+
+        let mut book = TemplateBook::new("Runbook", 4);
+        let prep = book
+            .add_node(add_req(None, "Prep", Position::Last))
+            .unwrap();
+        let content = |title: &str, body: Option<&str>, placeholder: Option<&str>| AddNodeRequest {
+            parent: Some(prep),
+            title: title.into(),
+            node_type: NodeType::Content,
+            body: body.map(Into::into),
+            placeholder: placeholder.map(Into::into),
+            position: Position::Last,
+            properties: HashMap::new(),
+        };
+        let bare = book.add_node(content("Bare", None, None)).unwrap();
+        let written = book
+            .add_node(content("Written", Some("done"), None))
+            .unwrap();
+        let hinted = book.add_node(content("Hinted", None, Some("URL"))).unwrap();
+        book.add_node(add_req(None, "Empty section", Position::Last))
+            .unwrap();
+        let repo = ShiftingRepo::new(book, false);
+        let server = repo.server(&dir);
+        let seed =
+            |json: &str| -> McpSeedPlaceholdersRequest { serde_json::from_str(json).unwrap() };
+
+        let msg = text(
+            server
+                .seed_placeholders(Parameters(seed(r#"{"hint": "Describe the step"}"#)))
+                .await
+                .unwrap(),
+        );
+        // 直接組み立てた Book は changelog に記録がないので、警告が続くことがある
+        assert!(
+            msg.starts_with(
+                "Seeded 1 content node(s) with placeholder 'Describe the step'; 2 already had guidance."
+            ),
+            "{msg}"
+        );
+        {
+            let book = repo.book.lock().unwrap();
+            let placeholder = |id| book.get_node(id).unwrap().placeholder().map(str::to_string);
+            assert_eq!(placeholder(bare).as_deref(), Some("Describe the step"));
+            assert_eq!(placeholder(written), None);
+            assert_eq!(placeholder(hinted).as_deref(), Some("URL"));
+        }
+
+        let msg = text(
+            server
+                .seed_placeholders(Parameters(seed(r#"{"hint": "Other", "node_id": "1"}"#)))
+                .await
+                .unwrap(),
+        );
+        assert!(msg.starts_with("Nothing to seed under 1. Prep"), "{msg}");
+        assert!(server
+            .seed_placeholders(Parameters(seed(r#"{"hint": "  "}"#)))
+            .await
+            .is_err());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn node_update_bulk_applies_one_edit_or_nothing() {
+        let dir = fresh_dir("outline-mcp-update-bulk-test");
+
+        let mut book = TemplateBook::new("Runbook", 4);
+        let ops = book.add_node(add_req(None, "Ops", Position::Last)).unwrap();
+        let item = |title: &str| AddNodeRequest {
+            parent: Some(ops),
+            title: title.into(),
+            node_type: NodeType::Content,
+            body: Some("old".into()),
+            placeholder: None,
+            position: Position::Last,
+            properties: HashMap::new(),
+        };
+        let deploy = book.add_node(item("Deploy")).unwrap();
+        let verify = book.add_node(item("Verify")).unwrap();
+        let repo = ShiftingRepo::new(book, false);
+        let server = repo.server(&dir);
+        let bulk = |json: &str| -> McpNodeUpdateBulkRequest { serde_json::from_str(json).unwrap() };
+        let snapshot = || serde_json::to_string(&*repo.book.lock().unwrap()).unwrap();
+
+        // 解決できない ID が1つでもあれば何も変えない
+        let before = snapshot();
+        let err = server
+            .node_update_bulk(Parameters(bulk(
+                r#"{"node_ids": ["1-1", "9-9"], "owner": "alice"}"#,
+            )))
+            .await
+            .unwrap_err();
+        assert!(err.message.contains("'9-9'"), "{}", err.message);
+        assert!(
+            err.message.contains("nothing was changed"),
+            "{}",
+            err.message
+        );
+        assert_eq!(snapshot(), before);
+
+        // Section 専用の項目を Content に付けると全体が取り消される
+        let err = server
+            .node_update_bulk(Parameters(bulk(
+                r#"{"node_ids": ["1", "1-1"], "ordered": false}"#,
+            )))
+            .await
+            .unwrap_err();
+        assert!(err.message.starts_with("1-1: `ordered`"), "{}", err.message);
+        assert_eq!(snapshot(), before);
+
+        let msg = text(
+            server
+                .node_update_bulk(Parameters(bulk(
+                    r#"{"node_ids": ["1-1", "Verify", "1-1"], "owner": "alice", "clear_body": true}"#,
+                )))
+                .await
+                .unwrap(),
+        );
+        assert!(
+            msg.starts_with("Updated 2 node(s):\n- 1-1. Deploy: updated\n- 1-2. Verify: updated"),
+            "{msg}"
+        );
+        {
+            let book = repo.book.lock().unwrap();
+            for id in [deploy, verify] {
+                let node = book.get_node(id).unwrap();
+                assert_eq!(node.owner(), Some("alice"));
+                assert_eq!(node.body(), None);
+            }
+        }
+
+        let err = server
+            .node_update_bulk(Parameters(bulk(
+                r#"{"node_ids": ["1-1"], "body": "x", "clear_body": true}"#,
+            )))
+            .await
+            .unwrap_err();
+        assert!(err.message.contains("clear_body"), "{}", err.message);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn clearable_fields_keep_on_null_unless_configured_and_clear_with_flags() {
+        use outline_mcp_core::domain::model::node::TemplateNode;
+
+        type Read = fn(&TemplateNode) -> Option<String>;
+        let fields: [(&str, serde_json::Value, Read); 7] = [
+            ("notes", "see INC-42".into(), |n| {
+                n.notes().map(str::to_owned)
+            }),
+            ("note", "Needs VPN".into(), |n| n.note().map(str::to_owned)),
+            ("estimate_minutes", 30.into(), |n| {
+                n.estimate_minutes().map(|v| v.to_string())
+            }),
+            ("owner", "alice".into(), |n| n.owner().map(str::to_owned)),
+            ("order", 15.5.into(), |n| n.order().map(|v| v.to_string())),
+            ("heading_level", 2.into(), |n| {
+                n.heading_level().map(|v| v.to_string())
+            }),
+            ("command", "make deploy".into(), |n| {
+                n.command().map(str::to_owned)
+            }),
+        ];
+
+        for null_clears_fields in [false, true] {
+            let dir = fresh_dir("outline-mcp-clearable-fields-test");
+            let mut book = TemplateBook::new("Runbook", 4);
+            let ops = book.add_node(add_req(None, "Ops", Position::Last)).unwrap();
+            let repo = ShiftingRepo::new(book, false);
+            let server = repo.server(&dir).with_config(ServerConfig {
+                null_clears_fields,
+                ..ServerConfig::default()
+            });
+            let update = |field: &str, value: serde_json::Value| {
+                let req: McpNodeUpdateRequest =
+                    serde_json::from_value(serde_json::json!({ "node_id": "1", (field): value }))
+                        .unwrap();
+                server.node_update(Parameters(req))
+            };
+
+            for (field, value, read) in &fields {
+                let current = || read(repo.book.lock().unwrap().get_node(ops).unwrap());
+                let ctx = format!("{field} (null_clears_fields: {null_clears_fields})");
+
+                update(field, value.clone()).await.unwrap();
+                let set = current();
+                assert!(set.is_some(), "{ctx}");
+                update("title", "Ops!".into()).await.unwrap();
+                assert_eq!(current(), set, "omitted keeps {ctx}");
+
+                update(field, serde_json::Value::Null).await.unwrap();
+                if null_clears_fields {
+                    assert_eq!(current(), None, "null clears {ctx}");
+                    update(field, value.clone()).await.unwrap();
+                } else {
+                    assert_eq!(current(), set, "null keeps {ctx}");
+                }
+
+                update(&format!("clear_{field}"), true.into())
+                    .await
+                    .unwrap();
+                assert_eq!(current(), None, "clear flag clears {ctx}");
+
+                let both: McpNodeUpdateRequest = serde_json::from_value(serde_json::json!({
+                    "node_id": "1",
+                    (*field): value,
+                    (format!("clear_{field}")): true,
+                }))
+                .unwrap();
+                let err = server.node_update(Parameters(both)).await.unwrap_err();
+                assert!(err.message.contains("not both"), "{ctx}: {}", err.message);
+            }
+
+            let _ = std::fs::remove_dir_all(&dir);
+        }
+    }
+
+    #[tokio::test]
+    async fn heading_level_is_validated() {
+        let dir = fresh_dir("outline-mcp-heading-level-test");
+
+        let mut book = TemplateBook::new("Runbook", 4);
+        let ops = book.add_node(add_req(None, "Ops", Position::Last)).unwrap();
+        let repo = ShiftingRepo::new(book, false);
+        let server = repo.server(&dir);
+        let update = |json: &str| -> McpNodeUpdateRequest { serde_json::from_str(json).unwrap() };
+        let level = || {
+            repo.book
+                .lock()
+                .unwrap()
+                .get_node(ops)
+                .unwrap()
+                .heading_level()
+        };
+
+        let err = server
+            .node_update(Parameters(update(
+                r#"{"node_id": "1", "heading_level": 7}"#,
+            )))
+            .await
+            .unwrap_err();
+        assert!(err.message.contains("between 1 and 6"), "{}", err.message);
+        server
+            .node_update(Parameters(update(
+                r#"{"node_id": "1", "heading_level": 1}"#,
+            )))
+            .await
+            .unwrap();
+        assert_eq!(level(), Some(1));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn pasted_titles_are_normalized_unless_turned_off() {
+        let dir = fresh_dir("outline-mcp-normalize-titles-test");
+
+        let repo = ShiftingRepo::new(TemplateBook::new("Runbook", 4), false);
+        let create: McpNodeCreateRequest = serde_json::from_value(serde_json::json!({
+            "title": "  1. Setup  ",
+            "children": [{"title": "  - Do thing  "}],
+        }))
+        .unwrap();
+        let server = repo.server(&dir);
+        server
+            .node_create(Parameters(create.clone()))
+            .await
+            .unwrap();
+        let rename: McpNodeUpdateRequest = serde_json::from_value(serde_json::json!({
+            "node_id": "1-1",
+            "title": "* Do the thing ",
+        }))
+        .unwrap();
+        server.node_update(Parameters(rename)).await.unwrap();
+        {
+            let book = repo.book.lock().unwrap();
+            let titles: Vec<&str> = book.all_nodes_dfs().iter().map(|n| n.title()).collect();
+            assert_eq!(titles, ["Setup", "Do the thing"]);
+        }
+
+        let raw = repo.server(&dir).with_config(ServerConfig {
+            normalize_titles: false,
+            ..ServerConfig::default()
+        });
+        raw.node_create(Parameters(create)).await.unwrap();
+        {
+            let book = repo.book.lock().unwrap();
+            let titles: Vec<&str> = book.all_nodes_dfs().iter().map(|n| n.title()).collect();
+            assert_eq!(
+                titles,
+                ["Setup", "Do the thing", "  1. Setup  ", "  - Do thing  "]
+            );
+        }
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn bulk_changes_over_the_budget_need_confirm() {
+        let dir = fresh_dir("outline-mcp-mutation-budget-test");
+
+        let mut book = TemplateBook::new("Runbook", 4);
+        let alpha = book
+            .add_node(add_req(None, "Alpha", Position::Last))
+            .unwrap();
+        book.add_node(add_req(Some(alpha), "First", Position::Last))
+            .unwrap();
+        book.add_node(add_req(Some(alpha), "Second", Position::Last))
+            .unwrap();
+        let repo = ShiftingRepo::new(book, false);
+        let server = repo.server(&dir).with_config(ServerConfig {
+            mutation_budget: Some(2),
+            ..ServerConfig::default()
+        });
+
+        // 3ノード（親 + 子2つ）の作成は予算 2 を超える
+        let create = |confirm| -> McpNodeCreateRequest {
+            serde_json::from_value(serde_json::json!({
+                "title": "Beta",
+                "node_type": "section",
+                "children": [{"title": "One"}, {"title": "Two"}],
+                "confirm": confirm,
+            }))
+            .unwrap()
+        };
+        let err = server
+            .node_create(Parameters(create(false)))
+            .await
+            .unwrap_err();
+        assert!(
+            err.message
+                .contains("create 3 and remove 0 node(s), over the per-call budget of 2"),
+            "{}",
+            err.message
+        );
+        assert!(
+            err.message.contains("'Beta', 'One', 'Two'"),
+            "{}",
+            err.message
+        );
+        assert_eq!(repo.saves.load(Ordering::SeqCst), 0, "nothing saved");
+        server
+            .node_create(Parameters(create(true)))
+            .await
+            .expect("confirmed create");
+
+        // 子孫ごとの削除も数える。予算内の削除はそのまま通る
+        let remove = |node_id: &str, confirm| McpNodeMoveRequest {
+            node_id: node_id.into(),
+            action: "remove".into(),
+            new_parent: None,
+            position: None,
+            override_protection: false,
+            quiet: false,
+            confirm,
+        };
+        let err = server
+            .node_move(Parameters(remove("1", false)))
+            .await
+            .unwrap_err();
+        assert!(err.message.contains("remove 3 node(s)"), "{}", err.message);
+        server
+            .node_move(Parameters(remove("1-1", false)))
+            .await
+            .expect("small removal");
+        server
+            .node_move(Parameters(remove("1", true)))
+            .await
+            .expect("confirmed removal");
+        assert_eq!(repo.book.lock().unwrap().root_nodes().len(), 1);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn reorder_book_applies_a_full_dfs_order_or_nothing() {
+        let dir = fresh_dir("outline-mcp-reorder-book-test");
+
+        let mut book = TemplateBook::new("Spec", 4);
+        let a = book.add_node(add_req(None, "A", Position::Last)).unwrap();
+        let a1 = book
+            .add_node(add_req(Some(a), "A1", Position::Last))
+            .unwrap();
+        let a2 = book
+            .add_node(add_req(Some(a), "A2", Position::Last))
+            .unwrap();
+        let b = book.add_node(add_req(None, "B", Position::Last)).unwrap();
+        let b1 = book
+            .add_node(add_req(Some(b), "B1", Position::Last))
+            .unwrap();
+        let repo = ShiftingRepo::new(book, false);
+        let server = repo.server(&dir);
+        let reorder = |order: &[&str]| -> McpReorderBookRequest {
+            serde_json::from_value(serde_json::json!({ "order": order })).unwrap()
+        };
+
+        // 不正な列はどれも何も保存しない
+        for (order, expected) in [
+            (vec!["2", "2-1", "1"], "missing 2: 1-1. A1, 1-2. A2"),
+            (
+                vec!["1", "1-1", "1-1", "2", "2-1"],
+                "'1-1. A1' is listed twice",
+            ),
+            (
+                vec!["1-1", "1", "1-2", "2", "2-1"],
+                "'1-1. A1' comes before its parent '1. A'",
+            ),
+            (
+                vec!["1", "1-1", "2", "1-2", "2-1"],
+                "'1-2. A2' is separated from the rest of the subtree of its parent '1. A'",
+            ),
+        ] {
+            let err = server
+                .reorder_book(Parameters(reorder(&order)))
+                .await
+                .unwrap_err();
+            assert!(err.message.contains(expected), "{order:?}: {}", err.message);
+            assert!(
+                err.message.contains("nothing was changed"),
+                "{}",
+                err.message
+            );
+            if expected.contains("parent") {
+                assert!(err.message.contains("use `node_move`"), "{}", err.message);
+            }
+        }
+        assert_eq!(repo.saves.load(Ordering::SeqCst), 0);
+
+        let msg = text(
+            server
+                .reorder_book(Parameters(reorder(&["2", "2-1", "1", "1-2", "1-1"])))
+                .await
+                .unwrap(),
+        );
+        assert!(msg.contains("2 node(s) moved"), "{msg}");
+        assert_eq!(repo.saves.load(Ordering::SeqCst), 1);
+        {
+            let book = repo.book.lock().unwrap();
+            let dfs: Vec<_> = book.all_nodes_dfs().iter().map(|n| n.id()).collect();
+            assert_eq!(dfs, vec![b, b1, a, a2, a1]);
+            assert!(book.check_integrity().is_empty());
+        }
 
-fn parse_dump_format(s: Option<&str>) -> Result<EjectFormat, McpError> {
-    match s {
-        Some("json") => Ok(EjectFormat::Json),
-        Some("markdown") | None => Ok(EjectFormat::Markdown),
-        Some(other) => Err(McpError::invalid_params(
-            format!("Unknown format: '{other}'. Use: markdown, json"),
-            None,
-        )),
+        // 同じ順序をもう一度渡しても保存しない
+        let msg = text(
+            server
+                .reorder_book(Parameters(reorder(&["1", "1-1", "2", "2-1", "2-2"])))
+                .await
+                .unwrap(),
+        );
+        assert!(msg.starts_with("No change"), "{msg}");
+        assert_eq!(repo.saves.load(Ordering::SeqCst), 1);
+
+        let _ = std::fs::remove_dir_all(&dir);
     }
-}
 
-fn dump_filename(format: &EjectFormat) -> &'static str {
-    match format {
-        EjectFormat::Markdown => "book.md",
-        EjectFormat::Json => "book.json",
+    #[tokio::test]
+    async fn concurrent_init_of_one_slug_keeps_the_first_book() {
+        let dir = fresh_dir("outline-mcp-init-race-test");
+        let server = OutlineMcpServer::new(dir.clone());
+        let init = |title: &str| {
+            server.init(Parameters(McpInitRequest {
+                title: title.into(),
+                slug: "ops".into(),
+                max_depth: None,
+                skeleton: Some(vec![format!("{title} section")]),
+                auto_select: Some(false),
+                tags: None,
+                categories: None,
+            }))
+        };
+
+        let (first, second) = tokio::join!(init("First"), init("Second"));
+        let (winner, loser) = match (first, second) {
+            (Ok(_), Err(e)) => ("First", e),
+            (Err(e), Ok(_)) => ("Second", e),
+            other => panic!("exactly one init should succeed: {other:?}"),
+        };
+        assert!(loser.message.contains("already exists"), "{loser:?}");
+        let book = server
+            .service_for("ops")
+            .await
+            .unwrap()
+            .read_tree()
+            .await
+            .unwrap();
+        assert_eq!(book.title(), winner);
+        assert_eq!(book.node_count(), 1);
+
+        let _ = std::fs::remove_dir_all(&dir);
     }
-}
 
-fn subdir_name(index: usize, total: usize, millis: i64) -> String {
-    let width = if total >= 100 { 3 } else { 2 };
-    format!("v{:0width$}_{}", index, millis, width = width)
-}
+    #[tokio::test]
+    async fn tag_filter_renumbers_the_listing_for_selection() {
+        let dir = fresh_dir("outline-mcp-shelf-tags-test");
+        let server = OutlineMcpServer::new(dir.clone());
+        for (slug, tags) in [
+            ("alpha", vec!["ops"]),
+            ("beta", vec![]),
+            ("gamma", vec!["Ops", "onboarding"]),
+        ] {
+            let svc = server.service_for(slug).await.unwrap();
+            let mut book = svc.create_book(slug, 4).await.unwrap();
+            book.set_tags(tags.into_iter().map(String::from).collect());
+            svc.save_book(&book).await.unwrap();
+        }
 
-fn prepare_dump_dir(dir: &std::path::Path, overwrite: bool) -> Result<(), McpError> {
-    if dir.exists() {
-        if !overwrite {
-            return Err(McpError::invalid_params(
-                format!(
-                    "Output subdir already exists: {}. Pass overwrite=true to allow.",
-                    dir.display()
-                ),
-                None,
-            ));
+        let listing = text(
+            server
+                .shelf(Parameters(McpShelfRequest {
+                    pin_selected: None,
+                    tag: Some("OPS".into()),
+                    category: None,
+                }))
+                .await
+                .unwrap(),
+        );
+        assert!(
+            listing.starts_with("# Shelf (2 books tagged 'OPS')"),
+            "{listing}"
+        );
+        assert!(listing.contains("2. gamma — \"gamma\" (0 nodes) [Ops, onboarding]"));
+        assert!(!listing.contains("beta"));
+        // 番号は絞り込み後の表示順に対応する
+        assert_eq!(server.resolve_book_ref("2").unwrap(), "gamma");
+
+        let tags = text(
+            server
+                .shelf_tags(Parameters(McpShelfTagsRequest {}))
+                .await
+                .unwrap(),
+        );
+        assert_eq!(
+            tags,
+            "# Shelf tags (2)\n\n- ops (2 books)\n- onboarding (1 books)\n"
+        );
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn categories_group_books_and_filter_the_shelf() {
+        let dir = fresh_dir("outline-mcp-shelf-categories-test");
+        let server = OutlineMcpServer::new(dir.clone());
+        for (slug, categories) in [
+            ("alpha", vec!["Operations"]),
+            ("beta", vec![]),
+            ("gamma", vec!["operations", "Onboarding"]),
+        ] {
+            let svc = server.service_for(slug).await.unwrap();
+            let mut book = svc.create_book(slug, 4).await.unwrap();
+            book.set_categories(categories.into_iter().map(String::from).collect());
+            svc.save_book(&book).await.unwrap();
         }
-        std::fs::remove_dir_all(dir).map_err(|e| {
-            McpError::internal_error(
-                format!("Failed to remove existing dir {}: {}", dir.display(), e),
-                None,
-            )
-        })?;
+
+        let grouped = text(
+            server
+                .shelf_categories(Parameters(McpShelfCategoriesRequest {}))
+                .await
+                .unwrap(),
+        );
+        assert_eq!(
+            grouped,
+            "# Shelf categories (2)\n\n## Onboarding (1 books)\n- gamma — \"gamma\"\n\n## Operations (2 books)\n- alpha — \"alpha\"\n- gamma — \"gamma\"\n\n## (uncategorized) (1 books)\n- beta — \"beta\"\n"
+        );
+
+        let listing = text(
+            server
+                .shelf(Parameters(McpShelfRequest {
+                    pin_selected: None,
+                    tag: None,
+                    category: Some("onboarding".into()),
+                }))
+                .await
+                .unwrap(),
+        );
+        assert!(
+            listing.starts_with("# Shelf (1 books in category 'onboarding')"),
+            "{listing}"
+        );
+        assert_eq!(server.resolve_book_ref("1").unwrap(), "gamma");
+
+        let _ = std::fs::remove_dir_all(&dir);
     }
-    std::fs::create_dir_all(dir).map_err(|e| {
-        McpError::internal_error(
-            format!("Failed to create dir {}: {}", dir.display(), e),
-            None,
+
+    #[tokio::test]
+    async fn book_eject_defaults_fill_omitted_checklist_parameters() {
+        let dir = fresh_dir("outline-mcp-eject-defaults-test");
+        let server = OutlineMcpServer::new(dir.clone());
+        server
+            .service_for("ops")
+            .await
+            .unwrap()
+            .create_book("Ops", 4)
+            .await
+            .unwrap();
+        *server.selected.write().unwrap() = Some("ops".to_string());
+
+        let config: McpBookConfigRequest = serde_json::from_str(
+            r#"{"eject_defaults": {"format": "json", "include_placeholders": false}}"#,
         )
-    })?;
-    Ok(())
-}
+        .unwrap();
+        let shown = text(server.book_config(Parameters(config)).await.unwrap());
+        assert!(
+            shown.contains("- eject_defaults: format=json, include_placeholders=false\n"),
+            "{shown}"
+        );
+        let status = text(
+            server
+                .status(Parameters(McpStatusRequest {}))
+                .await
+                .unwrap(),
+        );
+        assert!(
+            status.contains("- Export defaults: format=json"),
+            "{status}"
+        );
 
-#[cfg(test)]
-mod dump_helpers_tests {
-    use super::*;
+        // 省略した format は Book の既定値、指定した format はリクエストが勝つ
+        let checklist = |json: &str| {
+            let req: McpEjectRequest = serde_json::from_str(json).unwrap();
+            server.checklist(Parameters(req))
+        };
+        let out = text(checklist(r#"{"inline": true}"#).await.unwrap());
+        assert!(out.starts_with('{'), "{out}");
+        let out = text(
+            checklist(r#"{"inline": true, "format": "markdown"}"#)
+                .await
+                .unwrap(),
+        );
+        assert!(out.starts_with("# Ops"), "{out}");
 
-    #[test]
-    fn subdir_name_pads_two_digits() {
-        assert_eq!(subdir_name(1, 5, 12345), "v01_12345");
-        assert_eq!(subdir_name(9, 12, 999), "v09_999");
-        assert_eq!(subdir_name(12, 12, 100), "v12_100");
-    }
+        let bad: McpBookConfigRequest =
+            serde_json::from_str(r#"{"eject_defaults": {"format": "pdf"}}"#).unwrap();
+        assert!(server.book_config(Parameters(bad)).await.is_err());
 
-    #[test]
-    fn subdir_name_uses_three_digits_when_needed() {
-        assert_eq!(subdir_name(1, 150, 7), "v001_7");
-        assert_eq!(subdir_name(150, 150, 7), "v150_7");
+        let clear: McpBookConfigRequest =
+            serde_json::from_str(r#"{"eject_defaults": {}}"#).unwrap();
+        let shown = text(server.book_config(Parameters(clear)).await.unwrap());
+        assert!(shown.contains("- eject_defaults: (none)"), "{shown}");
+
+        let _ = std::fs::remove_dir_all(&dir);
     }
 
-    #[test]
-    fn parse_dump_format_default_markdown() {
-        assert!(matches!(
-            parse_dump_format(None).unwrap(),
-            EjectFormat::Markdown
-        ));
-        assert!(matches!(
-            parse_dump_format(Some("markdown")).unwrap(),
-            EjectFormat::Markdown
-        ));
-        assert!(matches!(
-            parse_dump_format(Some("json")).unwrap(),
-            EjectFormat::Json
-        ));
+    #[tokio::test]
+    async fn book_config_raises_max_depth_but_never_lowers_it() {
+        let dir = fresh_dir("outline-mcp-config-max-depth-test");
+        let server = OutlineMcpServer::new(dir.clone());
+        server
+            .service_for("ops")
+            .await
+            .unwrap()
+            .create_book("Ops", 4)
+            .await
+            .unwrap();
+        *server.selected.write().unwrap() = Some("ops".to_string());
+        let config = |json: &str| -> McpBookConfigRequest { serde_json::from_str(json).unwrap() };
+
+        let shown = text(
+            server
+                .book_config(Parameters(config(r#"{"max_depth": 6}"#)))
+                .await
+                .unwrap(),
+        );
+        assert!(shown.contains("- max_depth: 6\n"), "{shown}");
+        let err = server
+            .book_config(Parameters(config(r#"{"max_depth": 3}"#)))
+            .await
+            .unwrap_err();
+        assert!(err.message.contains("only be raised"), "{}", err.message);
+        assert!(server
+            .book_config(Parameters(config(r#"{"max_depth": 17}"#)))
+            .await
+            .is_err());
+
+        let _ = std::fs::remove_dir_all(&dir);
     }
 
-    #[test]
-    fn parse_dump_format_rejects_unknown() {
-        assert!(parse_dump_format(Some("yaml")).is_err());
+    async fn shelf_with_book(name: &str) -> std::path::PathBuf {
+        let dir = fresh_dir(name);
+        let server = OutlineMcpServer::new(dir.clone());
+        add_book(
+            &server,
+            "runbook",
+            "Runbook",
+            &[(None, "Ops", NodeType::Content, None)],
+        )
+        .await;
+        dir
     }
 
-    #[test]
-    fn dump_filename_by_format() {
-        assert_eq!(dump_filename(&EjectFormat::Markdown), "book.md");
-        assert_eq!(dump_filename(&EjectFormat::Json), "book.json");
+    fn read_only_server(dir: &std::path::Path) -> OutlineMcpServer {
+        OutlineMcpServer::new(dir.to_path_buf()).with_config(ServerConfig {
+            read_only: true,
+            export_root: Some(dir.join("exports")),
+            ..ServerConfig::default()
+        })
     }
 
-    #[test]
-    fn prepare_dump_dir_creates_new() {
-        let dir = std::env::temp_dir().join("outline-mcp-dump-helper-new");
-        let _ = std::fs::remove_dir_all(&dir);
-        prepare_dump_dir(&dir, false).expect("create");
-        assert!(dir.exists());
+    #[tokio::test]
+    async fn mutating_tools_are_refused_uniformly() {
+        let dir = shelf_with_book("outline-mcp-read-only-refuse-test").await;
+        let server = read_only_server(&dir);
+
+        for tool in [
+            "init",
+            "node_create",
+            "node_update",
+            "node_move",
+            "import",
+            "node_batch_update",
+            "snapshot_restore",
+            "snapshot_dump",
+        ] {
+            let err = server
+                .read_only_rejection(tool)
+                .unwrap_or_else(|| panic!("{tool} should be refused"));
+            assert!(err.message.contains(&format!("`{tool}` is unavailable")));
+            assert!(err.message.contains("read-only"), "{}", err.message);
+        }
+        for tool in ["shelf", "select_book", "toc", "node_get", "checklist"] {
+            assert!(server.read_only_rejection(tool).is_none(), "{tool}");
+        }
+        // フラグなしでは何も拒否しない
+        let writable = OutlineMcpServer::new(dir.clone());
+        assert!(writable.read_only_rejection("node_create").is_none());
+        assert!(writable.read_only_rejection("init").is_none());
+
+        let info = server.get_info().instructions.unwrap();
+        assert!(info.contains("READ-ONLY SERVER"), "{info}");
+        assert!(!writable
+            .get_info()
+            .instructions
+            .unwrap()
+            .contains("READ-ONLY"));
+
         let _ = std::fs::remove_dir_all(&dir);
     }
 
-    #[test]
-    fn prepare_dump_dir_errors_when_exists_without_overwrite() {
-        let dir = std::env::temp_dir().join("outline-mcp-dump-helper-existing");
-        let _ = std::fs::remove_dir_all(&dir);
-        std::fs::create_dir_all(&dir).unwrap();
-        let res = prepare_dump_dir(&dir, false);
-        assert!(res.is_err());
+    #[tokio::test]
+    async fn browsing_tools_keep_working_and_checklist_goes_to_export_dir() {
+        let dir = shelf_with_book("outline-mcp-read-only-browse-test").await;
+        let server = read_only_server(&dir);
+
+        let shelf = text(
+            server
+                .shelf(Parameters(McpShelfRequest {
+                    pin_selected: None,
+                    tag: None,
+                    category: None,
+                }))
+                .await
+                .unwrap(),
+        );
+        assert!(shelf.contains("runbook"), "{shelf}");
+        server
+            .select_book(Parameters(McpSelectBookRequest {
+                book: "runbook".into(),
+                quiet: true,
+            }))
+            .await
+            .unwrap();
+        let toc: McpTocRequest = serde_json::from_str("{}").unwrap();
+        assert!(text(server.toc(Parameters(toc)).await.unwrap()).contains("Ops"));
+
+        // output_dir を指定しても shelf ではなく export 用ディレクトリに書く
+        let req: McpEjectRequest = serde_json::from_value(serde_json::json!({
+            "output_dir": dir.to_str().unwrap(),
+            "filename": "runbook.md",
+        }))
+        .unwrap();
+        let msg = text(server.checklist(Parameters(req)).await.unwrap());
+        assert!(dir.join("exports/runbook.md").exists(), "{msg}");
+        assert!(!dir.join("runbook.md").exists());
+
+        let into_shelf: McpEjectRequest = serde_json::from_value(serde_json::json!({
+            "output_path": dir.join("stray.md").to_str().unwrap(),
+        }))
+        .unwrap();
+        assert!(server.checklist(Parameters(into_shelf)).await.is_err());
+
         let _ = std::fs::remove_dir_all(&dir);
     }
 
-    #[test]
-    fn parse_optional_millis_handles_none_some_bad() {
-        assert!(matches!(parse_optional_millis(None, "since"), Ok(None)));
-        assert!(matches!(
-            parse_optional_millis(Some("1782885148593"), "since"),
-            Ok(Some(1782885148593))
-        ));
-        assert!(parse_optional_millis(Some("abc"), "since").is_err());
+    #[tokio::test]
+    async fn handbook_has_one_h1_a_linked_toc_and_demoted_books() {
+        let dir = fresh_dir("outline-mcp-shelf-handbook-test");
+        let server = OutlineMcpServer::new(dir.clone());
+        add_book(
+            &server,
+            "dev",
+            "Dev Guide",
+            &[
+                (None, "Setup", NodeType::Section, None),
+                (Some(0), "Install tools", NodeType::Content, None),
+            ],
+        )
+        .await;
+        add_book(
+            &server,
+            "ops",
+            "Ops Runbook",
+            &[
+                (None, "Setup", NodeType::Section, None),
+                (Some(0), "Access", NodeType::Section, None),
+                (Some(1), "Keys", NodeType::Section, None),
+                (Some(2), "Rotate", NodeType::Section, None),
+                (
+                    Some(3),
+                    "Request a key",
+                    NodeType::Content,
+                    Some("```\n# not a heading\n```"),
+                ),
+                (None, "Deploy & Rollback", NodeType::Section, None),
+            ],
+        )
+        .await;
+
+        let req: McpShelfHandbookRequest =
+            serde_json::from_value(serde_json::json!({"inline": true})).unwrap();
+        let result = server.shelf_handbook(Parameters(req)).await.unwrap();
+        let md = result.content[0].as_text().unwrap().text.clone();
+        insta::assert_snapshot!(md, @r"
+        # Handbook
+
+        ## Contents
+
+        - [Dev Guide](#dev-guide)
+          - [Setup](#setup)
+        - [Ops Runbook](#ops-runbook)
+          - [Setup](#setup-1)
+          - [Deploy & Rollback](#deploy--rollback)
+
+        ---
+
+        ## Dev Guide
+
+        ### Setup
+
+
+          - [ ] Install tools
+
+        ---
+
+        ## Ops Runbook
+
+        ### Setup
+
+
+        #### Access
+
+
+        ##### Keys
+
+
+        ##### Rotate
+
+
+                - [ ] Request a key
+                  ```
+                  # not a heading
+                  ```
+        ### Deploy & Rollback
+        ");
+
+        let req: McpShelfHandbookRequest = serde_json::from_value(serde_json::json!({})).unwrap();
+        server.shelf_handbook(Parameters(req)).await.unwrap();
+        assert_eq!(
+            std::fs::read_to_string(dir.join("handbook.md")).unwrap(),
+            md
+        );
+
+        let _ = std::fs::remove_dir_all(&dir);
     }
 
-    #[test]
-    fn diff_header_prefers_label() {
-        assert_eq!(diff_header_name(Some("v03_rating"), 12345), "v03_rating");
+    /// "Ops"（子 Restart / Backups）、"Dev"（子 Build）、Content の "Loose" を持つ
+    /// runbook を選んだサーバ。
+    async fn runbook(dir: &std::path::Path) -> OutlineMcpServer {
+        let server = OutlineMcpServer::new(dir.to_path_buf());
+        add_book(
+            &server,
+            "runbook",
+            "Runbook",
+            &[
+                (None, "Ops", NodeType::Section, None),
+                (Some(0), "Restart", NodeType::Content, None),
+                (Some(0), "Backups", NodeType::Content, None),
+                (None, "Dev", NodeType::Section, None),
+                (Some(3), "Build", NodeType::Content, None),
+                (None, "Loose", NodeType::Content, None),
+            ],
+        )
+        .await;
+        *server.selected.write().unwrap() = Some("runbook".to_string());
+        server
     }
 
-    #[test]
-    fn diff_header_falls_back_to_millis_when_label_absent() {
-        assert_eq!(diff_header_name(None, 12345), "12345");
+    fn split(json: &str) -> Parameters<McpSplitBookRequest> {
+        Parameters(serde_json::from_str(json).unwrap())
     }
 
-    #[test]
-    fn diff_header_falls_back_to_millis_when_label_empty() {
-        assert_eq!(diff_header_name(Some(""), 999), "999");
+    async fn titles(server: &OutlineMcpServer, slug: &str) -> (String, Vec<String>) {
+        let book = server
+            .service_for(slug)
+            .await
+            .unwrap()
+            .read_tree()
+            .await
+            .unwrap();
+        let titles = book
+            .all_nodes_dfs()
+            .iter()
+            .map(|n| n.title().to_string())
+            .collect();
+        (book.title().to_string(), titles)
     }
 
-    #[test]
-    fn validate_label_accepts_normal_input() {
-        assert_eq!(
-            validate_snapshot_label(" rating-pass ").unwrap(),
-            "rating-pass"
+    #[tokio::test]
+    async fn each_section_becomes_a_book_and_collisions_are_resolved_or_refused() {
+        let dir = fresh_dir("outline-mcp-split-book-test");
+        let server = runbook(&dir).await;
+        let taken = server.service_for("proj-2").await.unwrap();
+        taken.create_book("Taken", 4).await.unwrap();
+
+        let err = server
+            .split_book(split(r#"{"slug_template": "proj"}"#))
+            .await
+            .unwrap_err();
+        assert!(
+            err.message.contains("`{n}` or `{title}`"),
+            "{}",
+            err.message
         );
-        assert_eq!(
-            validate_snapshot_label("v03 L3 sketch").unwrap(),
-            "v03 L3 sketch"
+        let err = server
+            .split_book(split(r#"{"slug_template": "proj-{n}"}"#))
+            .await
+            .unwrap_err();
+        assert!(err.message.contains("'proj-2' (Dev)"), "{}", err.message);
+        assert!(
+            !server.book_exists("proj-1").await.unwrap(),
+            "nothing created"
+        );
+
+        let msg = text(
+            server
+                .split_book(split(
+                    r#"{"slug_template": "proj-{n}", "on_collision": "suffix"}"#,
+                ))
+                .await
+                .unwrap(),
         );
+        assert!(msg.contains("- proj-1: 'Ops' (2 nodes)"), "{msg}");
+        assert!(msg.contains("- proj-2-2: 'Dev' (1 nodes)"), "{msg}");
+        assert!(msg.contains("stay in 'runbook'"), "{msg}");
         assert_eq!(
-            validate_snapshot_label("Milestone (1.0): draft").unwrap(),
-            "Milestone (1.0): draft"
+            titles(&server, "proj-1").await,
+            ("Ops".to_string(), vec!["Restart".into(), "Backups".into()])
         );
-    }
+        assert_eq!(titles(&server, "runbook").await.1.len(), 6, "copy only");
 
-    #[test]
-    fn validate_label_rejects_empty() {
-        assert!(validate_snapshot_label("").is_err());
-        assert!(validate_snapshot_label("   ").is_err());
+        let _ = std::fs::remove_dir_all(&dir);
     }
 
-    #[test]
-    fn validate_label_rejects_too_long() {
-        let long: String = "a".repeat(65);
-        assert!(validate_snapshot_label(&long).is_err());
-        let ok: String = "a".repeat(64);
-        assert!(validate_snapshot_label(&ok).is_ok());
-    }
+    #[tokio::test]
+    async fn remove_sections_moves_them_out_of_the_original() {
+        let dir = fresh_dir("outline-mcp-split-book-remove-test");
+        let server = runbook(&dir).await;
 
-    #[test]
-    fn validate_label_rejects_disallowed_chars() {
-        assert!(validate_snapshot_label("bad/slash").is_err());
-        assert!(validate_snapshot_label("back\\slash").is_err());
-        assert!(validate_snapshot_label("nl\nne").is_err());
+        let msg = text(
+            server
+                .split_book(split(
+                    r#"{"slug_template": "rb-{title}", "remove_sections": true}"#,
+                ))
+                .await
+                .unwrap(),
+        );
+        assert!(msg.contains("- rb-ops: 'Ops'"), "{msg}");
+        assert!(msg.contains("- rb-dev: 'Dev'"), "{msg}");
+        assert!(msg.contains("Removed the split sections"), "{msg}");
+        assert_eq!(
+            titles(&server, "runbook").await.1,
+            vec!["Loose".to_string()]
+        );
+        assert_eq!(
+            titles(&server, "rb-dev").await,
+            ("Dev".to_string(), vec!["Build".into()])
+        );
+
+        let _ = std::fs::remove_dir_all(&dir);
     }
 
     #[test]
-    fn prepare_dump_dir_overwrites_when_flag_set() {
-        let dir = std::env::temp_dir().join("outline-mcp-dump-helper-overwrite");
-        let _ = std::fs::remove_dir_all(&dir);
-        std::fs::create_dir_all(&dir).unwrap();
-        std::fs::write(dir.join("stale.txt"), "old").unwrap();
-        prepare_dump_dir(&dir, true).expect("overwrite");
-        assert!(dir.exists());
-        assert!(!dir.join("stale.txt").exists());
-        let _ = std::fs::remove_dir_all(&dir);
+    fn help_lists_every_keyword_the_parsers_accept() {
+        let text = help_text();
+        for (name, _) in NODE_TYPE_NAMES {
+            assert!(parse_node_type(name).is_ok());
+            assert!(text.contains(name), "node type '{name}' missing");
+        }
+        for (name, _) in NODE_STATUS_NAMES {
+            assert!(parse_node_status(name).is_ok());
+            assert!(text.contains(name), "status '{name}' missing");
+        }
+        for (format, example) in NODE_REF_FORMATS.iter().chain(BOOK_REF_FORMATS) {
+            assert!(text.contains(format) && text.contains(example), "{format}");
+        }
+        // 一覧にない語はパーサも受け付けない
+        assert!(parse_node_type("checklist").is_err());
+        assert!(parse_node_status("archived").is_err());
+        assert!(text.lines().count() <= 60, "{text}");
     }
 }
@@ -16,6 +16,7 @@
 
 use std::path::{Path, PathBuf};
 
+use outline_mcp_core::application::natural_sort::natural_cmp;
 use outline_mcp_core::infra::snapshot_migrator::{migrate_slug, MigrationReport};
 
 const HELP_TEXT: &str = "\
@@ -147,7 +148,7 @@ fn list_book_slugs(shelf: &Path) -> anyhow::Result<Vec<String>> {
             }
         })
         .collect();
-    slugs.sort();
+    slugs.sort_by(|a, b| natural_cmp(a, b));
     Ok(slugs)
 }
 
@@ -1,28 +1,44 @@
 //! Thin entry point: dispatches the `migrate-snapshots` CLI subcommand (see
-//! `cli`), or else parses the shelf directory from argv/env and hands off
-//! to `outline_mcp_rmcp::run`, which owns the MCP server (rmcp transport,
-//! tool_router, resources) and its `outline-mcp-core` wiring.
+//! `cli`), or else parses the shelf directory (and the `--read-only` flag)
+//! from argv/env and hands off to `outline_mcp_rmcp::run_with`, which owns
+//! the MCP server (rmcp transport, tool_router, resources) and its
+//! `outline-mcp-core` wiring.
 
 use std::path::PathBuf;
 
+use outline_mcp_rmcp::RunOptions;
+
 mod cli;
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
-    let mut argv = std::env::args().skip(1);
-    let first = argv.next();
+    let mut argv = std::env::args().skip(1).peekable();
 
-    if first.as_deref() == Some("migrate-snapshots") {
+    if argv.peek().map(String::as_str) == Some("migrate-snapshots") {
+        argv.next();
         let exit_code = cli::run(argv).await?;
         std::process::exit(exit_code);
     }
 
-    let shelf_dir = first.map(PathBuf::from).unwrap_or_else(|| {
-        std::env::var("HOME")
-            .map(PathBuf::from)
-            .unwrap_or_else(|_| PathBuf::from("."))
-            .join(".config/outline-mcp/books")
-    });
+    let (flags, positional): (Vec<String>, Vec<String>) = argv.partition(|a| a.starts_with("--"));
+    let mut options = RunOptions::default();
+    for flag in &flags {
+        match flag.as_str() {
+            "--read-only" => options.read_only = true,
+            other => anyhow::bail!("unknown option '{other}' (expected --read-only)"),
+        }
+    }
+
+    let shelf_dir = positional
+        .into_iter()
+        .next()
+        .map(PathBuf::from)
+        .unwrap_or_else(|| {
+            std::env::var("HOME")
+                .map(PathBuf::from)
+                .unwrap_or_else(|_| PathBuf::from("."))
+                .join(".config/outline-mcp/books")
+        });
 
-    outline_mcp_rmcp::run(shelf_dir).await
+    outline_mcp_rmcp::run_with(shelf_dir, options).await
 }